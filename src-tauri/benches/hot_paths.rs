@@ -0,0 +1,122 @@
+//! Benchmarks of the real-time hot paths (codec, DSP, screen encoding)
+//! Run with `cargo bench --features bench`. Each audio bench works on one 20ms
+//! frame, so results compare directly against the 20ms real-time budget.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use hydrowland_lib::bench::{
+    downmix_into, rms, AudioDenoiser, AudioMixer, EncoderConfig, OpusDecoder, OpusEncoder,
+    Resampler, VideoEncoder, VideoFrame, SAMPLES_PER_FRAME, SAMPLE_RATE,
+};
+
+/// One frame of a 440Hz tone with a little noise, like a voice frame
+fn voice_frame(len: usize, rate: u32) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / rate as f32;
+            let noise = ((i * 7919) % 113) as f32 / 113.0 - 0.5;
+            0.3 * (2.0 * std::f32::consts::PI * 440.0 * t).sin() + 0.02 * noise
+        })
+        .collect()
+}
+
+fn opus(c: &mut Criterion) {
+    let frame = voice_frame(SAMPLES_PER_FRAME, SAMPLE_RATE);
+    let mut encoder = OpusEncoder::new().unwrap();
+    let packet = encoder.encode(&frame).unwrap();
+
+    c.bench_function("opus_encode_20ms", |b| {
+        b.iter(|| encoder.encode(black_box(&frame)).unwrap())
+    });
+
+    let mut decoder = OpusDecoder::new().unwrap();
+    c.bench_function("opus_decode_20ms", |b| {
+        b.iter(|| decoder.decode(black_box(&packet)).unwrap())
+    });
+}
+
+fn denoiser(c: &mut Criterion) {
+    let mut denoiser = AudioDenoiser::new();
+    denoiser.set_sample_rate(SAMPLE_RATE);
+    let frame = voice_frame(SAMPLES_PER_FRAME, SAMPLE_RATE);
+    c.bench_function("denoise_20ms_48k", |b| {
+        b.iter(|| denoiser.process(black_box(&frame)))
+    });
+
+    // 44.1kHz devices go through the denoiser's own resampling
+    let mut denoiser = AudioDenoiser::new();
+    denoiser.set_sample_rate(44_100);
+    let frame = voice_frame(882, 44_100);
+    c.bench_function("denoise_20ms_44k1", |b| {
+        b.iter(|| denoiser.process(black_box(&frame)))
+    });
+}
+
+fn resampler(c: &mut Criterion) {
+    let frame = voice_frame(882, 44_100);
+    let mut resampler = Resampler::new(SAMPLE_RATE as f64 / 44_100.0);
+    let mut output = Vec::with_capacity(SAMPLES_PER_FRAME * 2);
+    c.bench_function("resample_20ms_44k1_to_48k", |b| {
+        b.iter(|| {
+            output.clear();
+            resampler.process_into(black_box(&frame), &mut output);
+        })
+    });
+}
+
+fn level(c: &mut Criterion) {
+    let frame = voice_frame(SAMPLES_PER_FRAME, SAMPLE_RATE);
+    c.bench_function("rms_20ms", |b| b.iter(|| rms(black_box(&frame))));
+
+    let stereo = voice_frame(SAMPLES_PER_FRAME * 2, SAMPLE_RATE);
+    let mut mono = Vec::with_capacity(SAMPLES_PER_FRAME);
+    c.bench_function("downmix_stereo_20ms", |b| {
+        b.iter(|| {
+            mono.clear();
+            downmix_into(black_box(&stereo), 2, &mut mono);
+        })
+    });
+}
+
+fn mixer(c: &mut Criterion) {
+    let frame = voice_frame(SAMPLES_PER_FRAME, SAMPLE_RATE);
+    let mut output = vec![0.0f32; SAMPLES_PER_FRAME];
+    c.bench_function("mix_8_peers_20ms", |b| {
+        b.iter_batched(
+            || {
+                let mut mixer = AudioMixer::new();
+                for peer in 0..8 {
+                    mixer.add_peer_samples(&format!("peer-{}", peer), frame.clone());
+                }
+                mixer
+            },
+            |mut mixer| mixer.mix_into(black_box(&mut output)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn screen(c: &mut Criterion) {
+    let (width, height) = (1920u32, 1080u32);
+    // Gradient, so the JPEG encoder has real content to compress
+    let rgba: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            [(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8, 255]
+        })
+        .collect();
+
+    let mut encoder = VideoEncoder::new(EncoderConfig::default());
+    let mut group = c.benchmark_group("screen");
+    group.sample_size(20);
+    group.bench_function("rgba_to_jpeg_1080p", |b| {
+        b.iter_batched(
+            || VideoFrame::new(width, height, rgba.clone()),
+            |frame| encoder.encode(black_box(&frame)).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, opus, denoiser, resampler, level, mixer, screen);
+criterion_main!(benches);
@@ -0,0 +1,176 @@
+//! Active speaker
+//! Picks the participant holding the floor from the local capture and the
+//! audio received from each peer. The floor changes hands only when another
+//! participant clearly dominates for a while, so crosstalk and short
+//! interjections don't make the UI flicker.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// RMS above which a frame counts as voice
+const VOICE_THRESHOLD: f32 = 0.02;
+/// A participant is speaking if voice was heard this recently
+const SPEAKING_WINDOW: Duration = Duration::from_millis(300);
+/// Silence after which the speaker loses the floor
+const RELEASE_AFTER: Duration = Duration::from_millis(800);
+/// A challenger must be this much louder than the speaker...
+const DOMINANCE_RATIO: f32 = 1.5;
+/// ...for this long to take the floor
+const DOMINANCE_HOLD: Duration = Duration::from_millis(500);
+/// Weight of a new frame in the smoothed level
+const SMOOTHING: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy)]
+struct Participant {
+    /// Smoothed RMS of the voiced frames
+    level: f32,
+    last_voice: Option<Instant>,
+}
+
+impl Participant {
+    fn is_speaking(&self, now: Instant) -> bool {
+        self.last_voice
+            .is_some_and(|t| now.duration_since(t) < SPEAKING_WINDOW)
+    }
+}
+
+/// Tracks who is speaking, with dominance hysteresis
+#[derive(Debug, Default)]
+pub struct ActiveSpeakerTracker {
+    participants: HashMap<String, Participant>,
+    speaker: Option<String>,
+    /// Louder participant waiting for the floor, since when
+    challenger: Option<(String, Instant)>,
+}
+
+impl ActiveSpeakerTracker {
+    /// Record the RMS of a 20ms frame of a participant
+    pub fn observe(&mut self, id: &str, rms: f32, now: Instant) {
+        let participant = self
+            .participants
+            .entry(id.to_string())
+            .or_insert(Participant {
+                level: 0.0,
+                last_voice: None,
+            });
+        if rms > VOICE_THRESHOLD {
+            participant.level += SMOOTHING * (rms - participant.level);
+            participant.last_voice = Some(now);
+        }
+    }
+
+    /// Forget a participant (left or stopped sending)
+    pub fn remove(&mut self, id: &str) {
+        self.participants.remove(id);
+    }
+
+    /// Forget everyone
+    pub fn clear(&mut self) {
+        self.participants.clear();
+        self.challenger = None;
+    }
+
+    /// Current speaker
+    pub fn speaker(&self) -> Option<&str> {
+        self.speaker.as_deref()
+    }
+
+    /// Everyone heard within the speaking window, sorted
+    pub fn speaking(&self, now: Instant) -> Vec<String> {
+        let mut speaking: Vec<String> = self
+            .participants
+            .iter()
+            .filter(|(_, p)| p.is_speaking(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        speaking.sort();
+        speaking
+    }
+
+    /// Re-evaluate the floor, returning the new speaker when it changed
+    pub fn update(&mut self, now: Instant) -> Option<Option<String>> {
+        let current = self
+            .speaker
+            .as_ref()
+            .and_then(|id| self.participants.get(id).map(|p| (id, *p)))
+            .filter(|(_, p)| {
+                p.last_voice
+                    .is_some_and(|t| now.duration_since(t) < RELEASE_AFTER)
+            });
+
+        let loudest = self
+            .participants
+            .iter()
+            .filter(|(id, p)| p.is_speaking(now) && Some(*id) != self.speaker.as_ref())
+            .max_by(|a, b| a.1.level.total_cmp(&b.1.level))
+            .map(|(id, p)| (id.clone(), p.level));
+
+        let next = match (current, loudest) {
+            // Floor is free: the loudest speaking participant takes it
+            (None, loudest) => {
+                self.challenger = None;
+                loudest.map(|(id, _)| id)
+            }
+            (Some((id, speaker)), Some((challenger, level)))
+                if level > speaker.level * DOMINANCE_RATIO || !speaker.is_speaking(now) =>
+            {
+                let since = match &self.challenger {
+                    Some((pending, since)) if *pending == challenger => *since,
+                    _ => {
+                        self.challenger = Some((challenger.clone(), now));
+                        now
+                    }
+                };
+                if now.duration_since(since) >= DOMINANCE_HOLD {
+                    self.challenger = None;
+                    Some(challenger)
+                } else {
+                    Some(id.clone())
+                }
+            }
+            (Some((id, _)), _) => {
+                self.challenger = None;
+                Some(id.clone())
+            }
+        };
+
+        if next == self.speaker {
+            return None;
+        }
+        self.speaker = next.clone();
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floor_changes_only_after_sustained_dominance() {
+        let mut tracker = ActiveSpeakerTracker::default();
+        let start = Instant::now();
+        let frame = Duration::from_millis(20);
+
+        // Alice speaks alone and takes the floor
+        tracker.observe("alice", 0.05, start);
+        assert_eq!(tracker.update(start), Some(Some("alice".to_string())));
+
+        // Bob talks over her, much louder: not before the hold time
+        let mut now = start;
+        for _ in 0..50 {
+            now += frame;
+            tracker.observe("alice", 0.05, now);
+            tracker.observe("bob", 0.3, now);
+            if tracker.update(now).is_some() {
+                break;
+            }
+        }
+        assert_eq!(tracker.speaker(), Some("bob"));
+        assert!(now.duration_since(start) >= DOMINANCE_HOLD);
+
+        // Everyone goes quiet: the floor is released
+        now += RELEASE_AFTER;
+        assert_eq!(tracker.update(now), Some(None));
+    }
+}
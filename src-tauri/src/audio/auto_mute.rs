@@ -0,0 +1,126 @@
+//! Auto-mute policies
+//! User settings enforced by the capture pipeline: join muted, mute when the mic
+//! is taken away by another app, and mute after a stretch of silence
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoMuteSettings {
+    /// Start every voice session muted
+    pub join_muted: bool,
+    /// Mute when the input device is lost, e.g. grabbed in exclusive mode by another app
+    pub mute_on_device_conflict: bool,
+    /// Mute after this many minutes without voice (None = never)
+    pub silence_timeout_minutes: Option<u32>,
+}
+
+impl Default for AutoMuteSettings {
+    fn default() -> Self {
+        Self {
+            join_muted: true,
+            mute_on_device_conflict: true,
+            silence_timeout_minutes: None,
+        }
+    }
+}
+
+/// Why the pipeline muted the mic on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoMuteReason {
+    DeviceConflict,
+    Silence,
+}
+
+/// Auto-mute state shared with the capture callback
+pub struct AutoMute {
+    settings: Mutex<AutoMuteSettings>,
+    /// Last frame with voice, or while muted (the timeout counts unmuted silence only)
+    last_voice: Mutex<Instant>,
+    tx: mpsc::UnboundedSender<AutoMuteReason>,
+    rx: Mutex<Option<mpsc::UnboundedReceiver<AutoMuteReason>>>,
+}
+
+impl AutoMute {
+    pub fn new(settings: AutoMuteSettings) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            settings: Mutex::new(settings),
+            last_voice: Mutex::new(Instant::now()),
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    }
+
+    pub fn settings(&self) -> AutoMuteSettings {
+        self.settings.lock().clone()
+    }
+
+    pub fn set_settings(&self, settings: AutoMuteSettings) {
+        *self.settings.lock() = settings;
+        *self.last_voice.lock() = Instant::now();
+    }
+
+    /// Take the receiving end of the auto-mute notifications (for the pipeline task)
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<AutoMuteReason>> {
+        self.rx.lock().take()
+    }
+
+    /// Feed one capture frame; true when the silence timeout just elapsed
+    pub(super) fn observe(&self, muted: bool, speaking: bool) -> bool {
+        self.observe_at(muted, speaking, Instant::now())
+    }
+
+    /// `observe` for a frame captured at `now`
+    fn observe_at(&self, muted: bool, speaking: bool, now: Instant) -> bool {
+        let mut last_voice = self.last_voice.lock();
+        if muted || speaking {
+            *last_voice = now;
+            return false;
+        }
+
+        let Some(minutes) = self.settings.lock().silence_timeout_minutes else {
+            return false;
+        };
+        now.saturating_duration_since(*last_voice) >= Duration::from_secs(u64::from(minutes) * 60)
+    }
+
+    /// Whether losing the input device should mute
+    pub(super) fn mutes_on_device_conflict(&self) -> bool {
+        self.settings.lock().mute_on_device_conflict
+    }
+
+    pub(super) fn notify(&self, reason: AutoMuteReason) {
+        let _ = self.tx.send(reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_timeout_counts_unmuted_silence_only() {
+        let auto_mute = AutoMute::new(AutoMuteSettings {
+            silence_timeout_minutes: Some(2),
+            ..AutoMuteSettings::default()
+        });
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(!auto_mute.observe_at(false, true, at(0)));
+        assert!(!auto_mute.observe_at(false, false, at(119)));
+        // Muted for a while: the silence starts over once unmuted
+        assert!(!auto_mute.observe_at(true, false, at(100)));
+        assert!(!auto_mute.observe_at(false, false, at(219)));
+        assert!(auto_mute.observe_at(false, false, at(220)));
+
+        // No timeout set
+        auto_mute.set_settings(AutoMuteSettings::default());
+        assert!(!auto_mute.observe_at(false, false, at(10_000)));
+    }
+}
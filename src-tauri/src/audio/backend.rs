@@ -0,0 +1,777 @@
+//! Audio backends
+//! The capture and playback pipelines talk to devices through `AudioBackend`:
+//! `CpalBackend` drives the real devices, `MockBackend` produces synthetic input
+//! and records output so the pipelines run deterministically without hardware.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{
+    FromSample, Sample, SampleFormat, SizedSample, StreamConfig, SupportedBufferSize,
+    SupportedStreamConfig, SupportedStreamConfigRange,
+};
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::device_config::{self, DeviceConfig, SupportedConfig};
+use super::watchdog::StreamKind;
+use super::{dsp, CHANNELS, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
+
+/// Native format of an opened stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Error reported by a running stream
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum StreamError {
+    /// The device went away or another app took it over
+    #[error("Device not available")]
+    DeviceNotAvailable,
+    #[error("{0}")]
+    Backend(String),
+}
+
+impl StreamError {
+    /// The device itself is gone (unplugged, disabled), not a passing glitch
+    pub fn is_device_lost(&self) -> bool {
+        match self {
+            Self::DeviceNotAvailable => true,
+            // ALSA reports an unplugged card as ENODEV
+            Self::Backend(message) => message.to_lowercase().contains("no such device"),
+        }
+    }
+}
+
+impl From<cpal::StreamError> for StreamError {
+    fn from(e: cpal::StreamError) -> Self {
+        match e {
+            cpal::StreamError::DeviceNotAvailable => Self::DeviceNotAvailable,
+            other => Self::Backend(other.to_string()),
+        }
+    }
+}
+
+/// Receives interleaved f32 input samples
+pub type InputCallback = Box<dyn FnMut(&[f32]) + Send + 'static>;
+/// Fills an interleaved f32 output buffer
+pub type OutputCallback = Box<dyn FnMut(&mut [f32]) + Send + 'static>;
+pub type ErrorCallback = Box<dyn FnMut(StreamError) + Send + 'static>;
+
+/// A running stream, stopped when dropped (not `Send`: the pipelines keep it on
+/// the stream thread through `ActiveStream`)
+pub type StreamHandle = Box<dyn Any>;
+
+/// Source of audio devices and streams
+pub trait AudioBackend: Send + Sync {
+    fn input_devices(&self) -> AppResult<Vec<String>>;
+    fn output_devices(&self) -> AppResult<Vec<String>>;
+
+    /// Resolve an input device (None for default), returning its name and format
+    fn input_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)>;
+    /// Resolve an output device (None for default), returning its name and format
+    fn output_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)>;
+
+    /// Configurations a device (None for default) can be pinned to
+    fn supported_configs(
+        &self,
+        kind: StreamKind,
+        device: Option<&str>,
+    ) -> AppResult<Vec<SupportedConfig>>;
+
+    /// Start capturing in the format reported by `input_format`
+    fn start_input(
+        &self,
+        device: Option<&str>,
+        on_data: InputCallback,
+        on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle>;
+
+    /// Start playing in the format reported by `output_format`
+    fn start_output(
+        &self,
+        device: Option<&str>,
+        on_data: OutputCallback,
+        on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle>;
+}
+
+/// Real devices through cpal's default host
+#[derive(Default)]
+pub struct CpalBackend;
+
+impl CpalBackend {
+    fn input_device(&self, name: Option<&str>) -> AppResult<cpal::Device> {
+        let host = cpal::default_host();
+        match name {
+            Some(device_name) => host
+                .input_devices()?
+                .find(|d| d.name().is_ok_and(|n| n == device_name))
+                .ok_or_else(|| AppError::DeviceNotFound(device_name.to_string())),
+            None => host
+                .default_input_device()
+                .ok_or_else(|| AppError::DeviceNotFound("No default input device".to_string())),
+        }
+    }
+
+    fn output_device(&self, name: Option<&str>) -> AppResult<cpal::Device> {
+        let host = cpal::default_host();
+        match name {
+            Some(device_name) => host
+                .output_devices()?
+                .find(|d| d.name().is_ok_and(|n| n == device_name))
+                .ok_or_else(|| AppError::DeviceNotFound(device_name.to_string())),
+            None => host
+                .default_output_device()
+                .ok_or_else(|| AppError::DeviceNotFound("No default output device".to_string())),
+        }
+    }
+
+    /// Default input config, or the one the user pinned for the device
+    fn input_config(device: &cpal::Device) -> AppResult<(StreamConfig, SampleFormat)> {
+        let default = device.default_input_config()?;
+        let name = device.name().unwrap_or_default();
+        let pin = device_config::pinned(StreamKind::Capture, &name);
+        if pin == DeviceConfig::default() {
+            return Ok((default.config(), default.sample_format()));
+        }
+        let ranges: Vec<_> = device.supported_input_configs()?.collect();
+        Ok(pin_config(&name, default, &ranges, pin))
+    }
+
+    /// Default output config (or the pinned one), falling back to our preferred config
+    fn output_config(device: &cpal::Device) -> (StreamConfig, SampleFormat) {
+        match device.default_output_config() {
+            Ok(supported) => {
+                let name = device.name().unwrap_or_default();
+                let pin = device_config::pinned(StreamKind::Playback, &name);
+                let ranges: Vec<_> = match device.supported_output_configs() {
+                    Ok(ranges) if pin != DeviceConfig::default() => ranges.collect(),
+                    _ => Vec::new(),
+                };
+                // Default buffer size unless pinned (more compatible)
+                pin_config(&name, supported, &ranges, pin)
+            }
+            Err(_) => (
+                StreamConfig {
+                    channels: CHANNELS,
+                    sample_rate: cpal::SampleRate(SAMPLE_RATE),
+                    buffer_size: cpal::BufferSize::Default,
+                },
+                SampleFormat::F32,
+            ),
+        }
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn input_devices(&self) -> AppResult<Vec<String>> {
+        let devices = cpal::default_host().input_devices()?;
+        Ok(devices.filter_map(|d| d.name().ok()).collect())
+    }
+
+    fn output_devices(&self) -> AppResult<Vec<String>> {
+        let devices = cpal::default_host().output_devices()?;
+        Ok(devices.filter_map(|d| d.name().ok()).collect())
+    }
+
+    fn input_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)> {
+        let device = self.input_device(device)?;
+        let (config, _) = Self::input_config(&device)?;
+        Ok((
+            device.name().unwrap_or_default(),
+            StreamFormat {
+                sample_rate: config.sample_rate.0,
+                channels: config.channels,
+            },
+        ))
+    }
+
+    fn output_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)> {
+        let device = self.output_device(device)?;
+        let (config, _) = Self::output_config(&device);
+        Ok((
+            device.name().unwrap_or_default(),
+            StreamFormat {
+                sample_rate: config.sample_rate.0,
+                channels: config.channels,
+            },
+        ))
+    }
+
+    fn supported_configs(
+        &self,
+        kind: StreamKind,
+        device: Option<&str>,
+    ) -> AppResult<Vec<SupportedConfig>> {
+        let ranges: Vec<_> = match kind {
+            StreamKind::Capture => self
+                .input_device(device)?
+                .supported_input_configs()?
+                .collect(),
+            StreamKind::Playback => self
+                .output_device(device)?
+                .supported_output_configs()?
+                .collect(),
+        };
+        Ok(ranges
+            .into_iter()
+            .map(|range| {
+                let (min_buffer_size, max_buffer_size) = match *range.buffer_size() {
+                    SupportedBufferSize::Range { min, max } => (Some(min), Some(max)),
+                    SupportedBufferSize::Unknown => (None, None),
+                };
+                SupportedConfig {
+                    channels: range.channels(),
+                    min_sample_rate: range.min_sample_rate().0,
+                    max_sample_rate: range.max_sample_rate().0,
+                    min_buffer_size,
+                    max_buffer_size,
+                    sample_format: range.sample_format().to_string(),
+                }
+            })
+            .collect())
+    }
+
+    fn start_input(
+        &self,
+        device: Option<&str>,
+        on_data: InputCallback,
+        on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle> {
+        let device = self.input_device(device)?;
+        let (config, sample_format) = Self::input_config(&device)?;
+
+        let stream = match sample_format {
+            SampleFormat::F32 => build_input(&device, &config, copy_f32, on_data, on_error),
+            // The common integer format has a vectorized conversion
+            SampleFormat::I16 => {
+                build_input(&device, &config, dsp::i16_to_f32_into, on_data, on_error)
+            }
+            SampleFormat::I8 => {
+                build_input(&device, &config, convert_to_f32::<i8>, on_data, on_error)
+            }
+            SampleFormat::I32 => {
+                build_input(&device, &config, convert_to_f32::<i32>, on_data, on_error)
+            }
+            SampleFormat::I64 => {
+                build_input(&device, &config, convert_to_f32::<i64>, on_data, on_error)
+            }
+            SampleFormat::U8 => {
+                build_input(&device, &config, convert_to_f32::<u8>, on_data, on_error)
+            }
+            SampleFormat::U16 => {
+                build_input(&device, &config, convert_to_f32::<u16>, on_data, on_error)
+            }
+            SampleFormat::U32 => {
+                build_input(&device, &config, convert_to_f32::<u32>, on_data, on_error)
+            }
+            SampleFormat::U64 => {
+                build_input(&device, &config, convert_to_f32::<u64>, on_data, on_error)
+            }
+            SampleFormat::F64 => {
+                build_input(&device, &config, convert_to_f32::<f64>, on_data, on_error)
+            }
+            // `SampleFormat` is non-exhaustive
+            format => {
+                return Err(AppError::Audio(format!(
+                    "Unsupported sample format: {:?}",
+                    format
+                )));
+            }
+        }?;
+        stream.play()?;
+        Ok(Box::new(stream))
+    }
+
+    fn start_output(
+        &self,
+        device: Option<&str>,
+        on_data: OutputCallback,
+        on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle> {
+        let device = self.output_device(device)?;
+        let (config, sample_format) = Self::output_config(&device);
+
+        let stream = match sample_format {
+            SampleFormat::F32 => build_output::<f32>(&device, &config, on_data, on_error),
+            SampleFormat::F64 => build_output::<f64>(&device, &config, on_data, on_error),
+            SampleFormat::I8 => build_output::<i8>(&device, &config, on_data, on_error),
+            SampleFormat::I16 => build_output::<i16>(&device, &config, on_data, on_error),
+            SampleFormat::I32 => build_output::<i32>(&device, &config, on_data, on_error),
+            SampleFormat::I64 => build_output::<i64>(&device, &config, on_data, on_error),
+            SampleFormat::U8 => build_output::<u8>(&device, &config, on_data, on_error),
+            SampleFormat::U16 => build_output::<u16>(&device, &config, on_data, on_error),
+            SampleFormat::U32 => build_output::<u32>(&device, &config, on_data, on_error),
+            SampleFormat::U64 => build_output::<u64>(&device, &config, on_data, on_error),
+            // `SampleFormat` is non-exhaustive
+            format => {
+                return Err(AppError::Audio(format!(
+                    "Unsupported sample format: {:?}",
+                    format
+                )));
+            }
+        }?;
+        stream.play()?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// `default` changed to the pinned sample rate and buffer size, where the
+/// device supports them (an unsupported pin is logged and left out)
+fn pin_config(
+    device: &str,
+    default: SupportedStreamConfig,
+    ranges: &[SupportedStreamConfigRange],
+    pin: DeviceConfig,
+) -> (StreamConfig, SampleFormat) {
+    let mut chosen = default;
+    if let Some(rate) = pin.sample_rate {
+        let rate = cpal::SampleRate(rate);
+        let supports = |range: &&SupportedStreamConfigRange| {
+            range.min_sample_rate() <= rate && rate <= range.max_sample_rate()
+        };
+        // Closest to the default: same channels and format, then same channels
+        let range = ranges
+            .iter()
+            .filter(supports)
+            .find(|r| {
+                r.channels() == chosen.channels() && r.sample_format() == chosen.sample_format()
+            })
+            .or_else(|| {
+                ranges
+                    .iter()
+                    .filter(supports)
+                    .find(|r| r.channels() == chosen.channels())
+            })
+            .or_else(|| ranges.iter().find(supports));
+        match range {
+            Some(range) => chosen = range.with_sample_rate(rate),
+            None => tracing::warn!(
+                "{} doesn't support {} Hz, using its default",
+                device,
+                rate.0
+            ),
+        }
+    }
+
+    let mut config = chosen.config();
+    config.buffer_size = cpal::BufferSize::Default;
+    if let Some(frames) = pin.buffer_size {
+        match *chosen.buffer_size() {
+            SupportedBufferSize::Range { min, max } if !(min..=max).contains(&frames) => {
+                tracing::warn!(
+                    "{} buffers must be {} to {} frames, ignoring {}",
+                    device,
+                    min,
+                    max,
+                    frames
+                );
+            }
+            // Unknown range: the driver may still accept it
+            _ => config.buffer_size = cpal::BufferSize::Fixed(frames),
+        }
+    }
+    (config, chosen.sample_format())
+}
+
+fn copy_f32(data: &[f32], out: &mut Vec<f32>) {
+    out.extend_from_slice(data);
+}
+
+/// Any other device format: integers are centered and scaled to [-1, 1]
+fn convert_to_f32<T>(data: &[T], out: &mut Vec<f32>)
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    out.extend(data.iter().map(|&s| s.to_sample::<f32>()));
+}
+
+/// Input stream converting the device samples to f32 with `convert`
+fn build_input<T: SizedSample>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    convert: fn(&[T], &mut Vec<f32>),
+    mut on_data: InputCallback,
+    mut on_error: ErrorCallback,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    // Sized for common device periods so the callback doesn't allocate
+    let mut converted: Vec<f32> = Vec::with_capacity(8192);
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            converted.clear();
+            convert(data, &mut converted);
+            on_data(&converted);
+        },
+        move |err| on_error(err.into()),
+        None,
+    )
+}
+
+/// Output stream converting f32 to the device samples
+fn build_output<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mut on_data: OutputCallback,
+    mut on_error: ErrorCallback,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut rendered: Vec<f32> = Vec::new();
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            rendered.clear();
+            rendered.resize(data.len(), 0.0);
+            on_data(&mut rendered);
+            for (out, &sample) in data.iter_mut().zip(&rendered) {
+                *out = T::from_sample(sample);
+            }
+        },
+        move |err| on_error(err.into()),
+        None,
+    )
+}
+
+/// Synthetic input signal of the mock backend
+#[derive(Debug, Clone, Copy)]
+pub enum MockSignal {
+    Silence,
+    Sine {
+        frequency: f32,
+        amplitude: f32,
+    },
+    /// Uniform white noise from a fixed seed, identical on every run
+    Noise {
+        amplitude: f32,
+    },
+}
+
+const MOCK_INPUT: &str = "Mock Input";
+const MOCK_OUTPUT: &str = "Mock Output";
+/// Callback period of mock streams
+const MOCK_PERIOD: Duration = Duration::from_millis(10);
+const MOCK_NOISE_SEED: u64 = 0x4879_6472_6f77;
+
+/// Stream callbacks of a manual clock backend, by stream id
+type ManualTicks = Arc<Mutex<Vec<(u64, Box<dyn FnMut() + Send>)>>>;
+
+/// Hardware-free backend for tests: one input device generating `signal` and
+/// one output device whose first channel is recorded
+pub struct MockBackend {
+    format: StreamFormat,
+    signal: Mutex<MockSignal>,
+    output: Arc<Mutex<Vec<f32>>>,
+    /// Set when the callbacks only run on `advance` (None: real-time threads)
+    manual: Option<ManualTicks>,
+}
+
+impl MockBackend {
+    pub fn new(signal: MockSignal) -> Self {
+        Self::with_format(
+            signal,
+            StreamFormat {
+                sample_rate: SAMPLE_RATE,
+                channels: CHANNELS,
+            },
+        )
+    }
+
+    /// Mock devices running at another rate or channel count
+    pub fn with_format(signal: MockSignal, format: StreamFormat) -> Self {
+        Self {
+            format,
+            signal: Mutex::new(signal),
+            output: Arc::new(Mutex::new(Vec::new())),
+            manual: None,
+        }
+    }
+
+    /// Mock devices whose callbacks only run when the test calls `advance`, so
+    /// what they capture and play doesn't depend on the scheduler
+    pub fn manual(signal: MockSignal) -> Self {
+        Self {
+            manual: Some(Arc::default()),
+            ..Self::new(signal)
+        }
+    }
+
+    /// Run the callback of every open stream `periods` times (10ms each), in
+    /// opening order; does nothing on a real-time backend
+    pub fn advance(&self, periods: usize) {
+        let Some(ticks) = &self.manual else {
+            return;
+        };
+        let mut ticks = ticks.lock();
+        for _ in 0..periods {
+            for (_, tick) in ticks.iter_mut() {
+                tick();
+            }
+        }
+    }
+
+    /// Change the signal of input streams started from now on
+    pub fn set_signal(&self, signal: MockSignal) {
+        *self.signal.lock() = signal;
+    }
+
+    /// Everything played so far (first channel only)
+    pub fn played(&self) -> Vec<f32> {
+        self.output.lock().clone()
+    }
+
+    fn check_device(name: Option<&str>, expected: &str) -> AppResult<()> {
+        match name {
+            Some(name) if name != expected => Err(AppError::DeviceNotFound(name.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Frames per callback period
+    fn period_frames(&self) -> usize {
+        (self.format.sample_rate as usize * MOCK_PERIOD.as_millis() as usize) / 1000
+    }
+
+    /// Stream calling `tick` once per period, on a thread or on `advance`
+    fn stream(&self, tick: impl FnMut() + Send + 'static) -> StreamHandle {
+        match &self.manual {
+            Some(ticks) => Box::new(ManualStream::register(ticks, tick)),
+            None => Box::new(MockStream::spawn(tick)),
+        }
+    }
+}
+
+impl AudioBackend for MockBackend {
+    fn input_devices(&self) -> AppResult<Vec<String>> {
+        Ok(vec![MOCK_INPUT.to_string()])
+    }
+
+    fn output_devices(&self) -> AppResult<Vec<String>> {
+        Ok(vec![MOCK_OUTPUT.to_string()])
+    }
+
+    fn input_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)> {
+        Self::check_device(device, MOCK_INPUT)?;
+        Ok((MOCK_INPUT.to_string(), self.format))
+    }
+
+    fn output_format(&self, device: Option<&str>) -> AppResult<(String, StreamFormat)> {
+        Self::check_device(device, MOCK_OUTPUT)?;
+        Ok((MOCK_OUTPUT.to_string(), self.format))
+    }
+
+    fn supported_configs(
+        &self,
+        kind: StreamKind,
+        device: Option<&str>,
+    ) -> AppResult<Vec<SupportedConfig>> {
+        let expected = match kind {
+            StreamKind::Capture => MOCK_INPUT,
+            StreamKind::Playback => MOCK_OUTPUT,
+        };
+        Self::check_device(device, expected)?;
+        Ok(vec![SupportedConfig {
+            channels: self.format.channels,
+            min_sample_rate: self.format.sample_rate,
+            max_sample_rate: self.format.sample_rate,
+            min_buffer_size: Some(self.period_frames() as u32),
+            max_buffer_size: Some(self.period_frames() as u32),
+            sample_format: SampleFormat::F32.to_string(),
+        }])
+    }
+
+    fn start_input(
+        &self,
+        device: Option<&str>,
+        mut on_data: InputCallback,
+        _on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle> {
+        Self::check_device(device, MOCK_INPUT)?;
+        let signal = *self.signal.lock();
+        let StreamFormat {
+            sample_rate,
+            channels,
+        } = self.format;
+        let channels = channels as usize;
+        let mut buffer = vec![0.0f32; self.period_frames() * channels];
+        let mut rng = StdRng::seed_from_u64(MOCK_NOISE_SEED);
+        let mut phase = 0.0f32;
+
+        Ok(self.stream(move || {
+            for frame in buffer.chunks_mut(channels) {
+                let sample = match signal {
+                    MockSignal::Silence => 0.0,
+                    MockSignal::Sine {
+                        frequency,
+                        amplitude,
+                    } => {
+                        phase = (phase
+                            + 2.0 * std::f32::consts::PI * frequency / sample_rate as f32)
+                            % (2.0 * std::f32::consts::PI);
+                        amplitude * phase.sin()
+                    }
+                    MockSignal::Noise { amplitude } => amplitude * rng.gen_range(-1.0f32..=1.0),
+                };
+                frame.fill(sample);
+            }
+            on_data(&buffer);
+        }))
+    }
+
+    fn start_output(
+        &self,
+        device: Option<&str>,
+        mut on_data: OutputCallback,
+        _on_error: ErrorCallback,
+    ) -> AppResult<StreamHandle> {
+        Self::check_device(device, MOCK_OUTPUT)?;
+        let channels = self.format.channels as usize;
+        let mut buffer = vec![0.0f32; self.period_frames() * channels];
+        let output = self.output.clone();
+
+        Ok(self.stream(move || {
+            buffer.fill(0.0);
+            on_data(&mut buffer);
+            output
+                .lock()
+                .extend(buffer.chunks(channels).map(|frame| frame[0]));
+        }))
+    }
+}
+
+/// Thread calling a stream callback once per period, at real-time pace
+struct MockStream {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockStream {
+    fn spawn(mut tick: impl FnMut() + Send + 'static) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                let mut next = Instant::now();
+                while running.load(Ordering::SeqCst) {
+                    tick();
+                    next += MOCK_PERIOD;
+                    std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                }
+            })
+        };
+        Self {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for MockStream {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Stream of a manual clock backend, ticked by `MockBackend::advance`
+struct ManualStream {
+    id: u64,
+    ticks: ManualTicks,
+}
+
+impl ManualStream {
+    fn register(ticks: &ManualTicks, tick: impl FnMut() + Send + 'static) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        ticks.lock().push((id, Box::new(tick)));
+        Self {
+            id,
+            ticks: ticks.clone(),
+        }
+    }
+}
+
+impl Drop for ManualStream {
+    fn drop(&mut self) {
+        self.ticks.lock().retain(|(id, _)| *id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_every_integer_format_to_unit_range() {
+        let mut out = Vec::new();
+        convert_to_f32::<u8>(&[0, 128, 255], &mut out);
+        convert_to_f32::<i32>(&[i32::MIN, 0], &mut out);
+        convert_to_f32::<u32>(&[1 << 31], &mut out);
+        convert_to_f32::<f64>(&[0.5], &mut out);
+        assert_eq!(out[..2], [-1.0, 0.0]);
+        assert!((out[2] - 1.0).abs() < 0.01);
+        assert_eq!(out[3..], [-1.0, 0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn unplugged_alsa_card_is_a_lost_device() {
+        assert!(StreamError::DeviceNotAvailable.is_device_lost());
+        let enodev = "ALSA function 'snd_pcm_poll_descriptors_revents' failed with error \
+                      'ENODEV: No such device'";
+        assert!(StreamError::Backend(enodev.to_string()).is_device_lost());
+        assert!(!StreamError::Backend("Buffer underrun".to_string()).is_device_lost());
+    }
+
+    #[test]
+    fn pins_supported_rate_and_buffer_only() {
+        let buffers = SupportedBufferSize::Range { min: 64, max: 4096 };
+        let ranges = [
+            SupportedStreamConfigRange::new(
+                2,
+                cpal::SampleRate(44_100),
+                cpal::SampleRate(96_000),
+                buffers,
+                SampleFormat::I16,
+            ),
+            SupportedStreamConfigRange::new(
+                2,
+                cpal::SampleRate(44_100),
+                cpal::SampleRate(48_000),
+                buffers,
+                SampleFormat::F32,
+            ),
+        ];
+        let default = ranges[1].with_sample_rate(cpal::SampleRate(44_100));
+
+        let pin = DeviceConfig {
+            sample_rate: Some(48_000),
+            buffer_size: Some(256),
+        };
+        let (config, format) = pin_config("Interface", default.clone(), &ranges, pin);
+        assert_eq!(config.sample_rate.0, 48_000);
+        assert_eq!(config.buffer_size, cpal::BufferSize::Fixed(256));
+        assert_eq!(format, SampleFormat::F32);
+
+        // Only the I16 range goes that high
+        let pin = DeviceConfig {
+            sample_rate: Some(96_000),
+            buffer_size: Some(8),
+        };
+        let (config, format) = pin_config("Interface", default, &ranges, pin);
+        assert_eq!(config.sample_rate.0, 96_000);
+        assert_eq!(config.buffer_size, cpal::BufferSize::Default);
+        assert_eq!(format, SampleFormat::I16);
+    }
+}
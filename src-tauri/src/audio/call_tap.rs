@@ -0,0 +1,146 @@
+//! Call tap
+//! Copies of the playback mix and of the processed mic, taken from the voice
+//! pipeline while the call is recorded. Each side is bounded: samples the
+//! recorder doesn't collect in time are dropped rather than piling up on the
+//! audio threads. The output callback never waits on the recorder: it fills a
+//! batch of its own and hands it over when the tap is free.
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::SAMPLES_PER_FRAME;
+
+/// Samples kept per side (~200ms)
+const TAP_CAPACITY: usize = SAMPLES_PER_FRAME * 10;
+
+/// Samples left behind by a frame before the oldest are dropped (~40ms): the
+/// recorder and the audio callbacks don't tick together, this keeps the lag low
+const MAX_BACKLOG: usize = SAMPLES_PER_FRAME * 2;
+
+#[derive(Default)]
+pub struct CallTap {
+    enabled: AtomicBool,
+    /// Playback mix (48kHz mono, after the equalizer and dynamics)
+    mix: Mutex<Vec<f32>>,
+    /// Processed capture, only while not muted
+    mic: Mutex<Vec<f32>>,
+}
+
+impl CallTap {
+    /// Start or stop copying, dropping what wasn't collected
+    pub fn set_enabled(&self, enabled: bool) {
+        for side in [&self.mix, &self.mic] {
+            let mut side = side.lock();
+            side.clear();
+            if enabled {
+                side.reserve_exact(TAP_CAPACITY);
+            }
+        }
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Hand the samples of a playback callback over to the mix side
+    /// While the recorder holds the tap they stay in `batch` for the next callback.
+    pub(crate) fn push_mix(&self, batch: &mut MixBatch) {
+        if !self.is_enabled() {
+            batch.samples.clear();
+            return;
+        }
+        if let Some(mut side) = self.mix.try_lock() {
+            push_bounded(&mut side, &batch.samples);
+            batch.samples.clear();
+        }
+    }
+
+    /// Copy processed capture samples
+    pub(crate) fn push_mic(&self, samples: &[f32]) {
+        if self.is_enabled() {
+            push_bounded(&mut self.mic.lock(), samples);
+        }
+    }
+
+    /// Take one frame of each side, padded with silence when a side ran dry
+    pub fn take_frame(&self, mix: &mut [f32], mic: &mut [f32]) {
+        take_into(&mut self.mix.lock(), mix);
+        take_into(&mut self.mic.lock(), mic);
+    }
+}
+
+/// Playback mix samples gathered by one output callback, outside the tap's lock
+pub(crate) struct MixBatch {
+    samples: Vec<f32>,
+}
+
+impl MixBatch {
+    pub(crate) fn new() -> Self {
+        Self {
+            samples: Vec::with_capacity(TAP_CAPACITY),
+        }
+    }
+
+    pub(crate) fn push(&mut self, sample: f32) {
+        push_bounded(&mut self.samples, &[sample]);
+    }
+}
+
+/// Append what fits, without growing past the capacity reserved at start
+fn push_bounded(side: &mut Vec<f32>, samples: &[f32]) {
+    let room = TAP_CAPACITY.saturating_sub(side.len());
+    side.extend_from_slice(&samples[..samples.len().min(room)]);
+}
+
+fn take_into(side: &mut Vec<f32>, frame: &mut [f32]) {
+    let taken = side.len().min(frame.len());
+    frame[..taken].copy_from_slice(&side[..taken]);
+    frame[taken..].fill(0.0);
+    let backlog = side.len() - taken;
+    side.drain(..taken + backlog.saturating_sub(MAX_BACKLOG));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tap_pads_bounds_and_never_waits() {
+        let tap = CallTap::default();
+        let mut batch = MixBatch::new();
+        let (mut mix, mut mic) = ([1.0; 4], [1.0; 4]);
+
+        // Disabled: nothing is copied
+        batch.push(0.5);
+        tap.push_mix(&mut batch);
+        tap.push_mic(&[0.25]);
+        tap.take_frame(&mut mix, &mut mic);
+        assert_eq!((mix, mic), ([0.0; 4], [0.0; 4]));
+
+        // A side that ran dry is padded with silence
+        tap.set_enabled(true);
+        (0..3).for_each(|_| batch.push(0.5));
+        tap.push_mix(&mut batch);
+        tap.push_mic(&[0.25; 2]);
+        tap.take_frame(&mut mix, &mut mic);
+        assert_eq!(mix, [0.5, 0.5, 0.5, 0.0]);
+        assert_eq!(mic, [0.25, 0.25, 0.0, 0.0]);
+
+        // While the recorder holds the mix, the batch waits for the next callback
+        let held = tap.mix.lock();
+        batch.push(0.5);
+        tap.push_mix(&mut batch);
+        assert_eq!(batch.samples.len(), 1);
+        drop(held);
+        tap.push_mix(&mut batch);
+        assert!(batch.samples.is_empty());
+
+        // Bounded on push, and the backlog behind a frame is capped
+        tap.push_mic(&[0.1; TAP_CAPACITY * 2]);
+        assert_eq!(tap.mic.lock().len(), TAP_CAPACITY);
+        let (mut mix, mut mic) = ([0.0; SAMPLES_PER_FRAME], [0.0; SAMPLES_PER_FRAME]);
+        tap.take_frame(&mut mix, &mut mic);
+        assert_eq!(tap.mic.lock().len(), MAX_BACKLOG);
+    }
+}
@@ -7,6 +7,7 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 
 use super::{CHANNELS, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
 
 /// Audio capture from microphone using cpal
 pub struct AudioCapture {
@@ -18,7 +19,7 @@ pub struct AudioCapture {
 }
 
 impl AudioCapture {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> AppResult<Self> {
         let host = cpal::default_host();
 
         Ok(Self {
@@ -30,11 +31,10 @@ impl AudioCapture {
     }
 
     /// List available input devices
-    pub fn list_devices() -> Result<Vec<String>, String> {
+    pub fn list_devices() -> AppResult<Vec<String>> {
         let host = cpal::default_host();
         let devices = host
-            .input_devices()
-            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
+            .input_devices()?;
 
         let names: Vec<String> = devices
             .filter_map(|d| d.name().ok())
@@ -44,16 +44,15 @@ impl AudioCapture {
     }
 
     /// Select input device by name (None for default)
-    pub fn select_device(&mut self, name: Option<&str>) -> Result<(), String> {
+    pub fn select_device(&mut self, name: Option<&str>) -> AppResult<()> {
         self.device = match name {
             Some(device_name) => {
-                let devices = self.host.input_devices()
-                    .map_err(|e| format!("Failed to enumerate devices: {}", e))?;
+                let devices = self.host.input_devices()?;
 
                 devices
                     .filter(|d| d.name().map(|n| n == device_name).unwrap_or(false))
                     .next()
-                    .ok_or_else(|| format!("Device '{}' not found", device_name))?
+                    .ok_or_else(|| AppError::DeviceNotFound(device_name.to_string()))?
                     .into()
             }
             None => self.host.default_input_device(),
@@ -64,13 +63,13 @@ impl AudioCapture {
 
     /// Start capturing audio
     /// Callback receives chunks of f32 samples
-    pub fn start<F>(&mut self, mut callback: F) -> Result<(), String>
+    pub fn start<F>(&mut self, mut callback: F) -> AppResult<()>
     where
         F: FnMut(Vec<f32>) + Send + 'static,
     {
         let device = self.device.take()
             .or_else(|| self.host.default_input_device())
-            .ok_or("No input device available")?;
+            .ok_or_else(|| AppError::DeviceNotFound("No input device available".to_string()))?;
 
         tracing::info!("Using input device: {}", device.name().unwrap_or_default());
 
@@ -101,9 +100,9 @@ impl AudioCapture {
                 tracing::error!("Audio input error: {}", err);
             },
             None,
-        ).map_err(|e| format!("Failed to build input stream: {}", e))?;
+        )?;
 
-        stream.play().map_err(|e| format!("Failed to start stream: {}", e))?;
+        stream.play()?;
 
         self.device = Some(device);
         self.stream = Some(stream);
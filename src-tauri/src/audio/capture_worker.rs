@@ -0,0 +1,132 @@
+//! Capture worker
+//! The device callback must not block: it only downmixes to mono and pushes the
+//! samples into a lock-free SPSC ring buffer. A worker thread pops whole frames
+//! and runs the heavy part (resampling, denoising, level, Opus) where taking a
+//! lock can't stall the audio driver.
+
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{JoinHandle, Thread};
+use std::time::Duration;
+
+use super::rt_priority;
+use super::{dsp, FRAME_DURATION_MS};
+use crate::error::{AppError, AppResult};
+
+/// Frames the ring buffer holds before the callback starts dropping samples
+const RING_FRAMES: usize = 10;
+
+/// Longest the worker sleeps without being woken (covers a missed wake-up)
+const IDLE_WAIT: Duration = Duration::from_millis(20);
+
+/// Callback side: feeds mono samples to the worker, never blocks
+pub struct CaptureFeed {
+    producer: HeapProd<f32>,
+    channels: usize,
+    samples_per_frame: usize,
+    mono: Vec<f32>,
+    worker: Thread,
+    overruns: Arc<AtomicU64>,
+}
+
+impl CaptureFeed {
+    /// Queue interleaved device samples (called from the device callback)
+    pub fn push(&mut self, data: &[f32]) {
+        let samples = if self.channels > 1 {
+            self.mono.clear();
+            dsp::downmix_into(data, self.channels, &mut self.mono);
+            &self.mono[..]
+        } else {
+            data
+        };
+
+        let pushed = self.producer.push_slice(samples);
+        if pushed < samples.len() {
+            self.overruns
+                .fetch_add((samples.len() - pushed) as u64, Ordering::Relaxed);
+        }
+        if self.producer.occupied_len() >= self.samples_per_frame {
+            self.worker.unpark();
+        }
+    }
+}
+
+/// Worker thread processing one frame at a time, stopped when dropped
+pub struct CaptureWorker {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureWorker {
+    /// Start a worker calling `process` with every `samples_per_frame` mono
+    /// samples; `overruns` counts the samples the callback had to drop
+    pub fn spawn(
+        channels: usize,
+        samples_per_frame: usize,
+        overruns: Arc<AtomicU64>,
+        mut process: impl FnMut(&[f32]) + Send + 'static,
+    ) -> AppResult<(Self, CaptureFeed)> {
+        let (producer, consumer) = HeapRb::<f32>::new(samples_per_frame * RING_FRAMES).split();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let running = running.clone();
+            std::thread::Builder::new()
+                .name("audio-capture-worker".to_string())
+                .spawn(move || {
+                    run(consumer, samples_per_frame, &running, &mut process);
+                })
+                .map_err(|e| AppError::Audio(format!("Failed to spawn capture worker: {}", e)))?
+        };
+
+        let feed = CaptureFeed {
+            producer,
+            channels: channels.max(1),
+            samples_per_frame,
+            // Large enough for any common device period, so the callback doesn't allocate
+            mono: Vec::with_capacity(8192),
+            worker: thread.thread().clone(),
+            overruns,
+        };
+        Ok((
+            Self {
+                running,
+                thread: Some(thread),
+            },
+            feed,
+        ))
+    }
+}
+
+fn run(
+    mut consumer: HeapCons<f32>,
+    samples_per_frame: usize,
+    running: &AtomicBool,
+    process: &mut impl FnMut(&[f32]),
+) {
+    // Frames are due every FRAME_DURATION_MS: ask for real-time scheduling so a
+    // busy machine doesn't starve the worker into ring buffer overruns
+    let sample_rate = samples_per_frame as u32 * 1000 / FRAME_DURATION_MS;
+    let _priority = rt_priority::promote_current_thread(samples_per_frame as u32, sample_rate);
+
+    let mut frame = vec![0.0f32; samples_per_frame];
+    while running.load(Ordering::SeqCst) {
+        while consumer.occupied_len() >= samples_per_frame {
+            consumer.pop_slice(&mut frame);
+            process(&frame);
+        }
+        std::thread::park_timeout(IDLE_WAIT);
+    }
+}
+
+impl Drop for CaptureWorker {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Output crossover
+//! Switching the output device used to stop playback, let the device settle and
+//! reopen, dropping a chunk of the remote audio. Now the new stream opens next
+//! to the old one: the old stream (the leader) keeps draining the playback
+//! buffer and mirrors every sample to the new one (the follower) once it plays.
+//! After a short overlap the follower takes over the buffer and the old stream
+//! goes silent until it is closed.
+
+use parking_lot::{Mutex, MutexGuard};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::SAMPLES_PER_FRAME;
+
+/// Longest wait for the new stream's first callback before handing over anyway
+pub const CROSSOVER_START_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time both streams play the same audio before the old one is retired
+pub const CROSSOVER_OVERLAP: Duration = Duration::from_millis(100);
+
+/// Poll interval while waiting for the new stream
+pub const CROSSOVER_POLL: Duration = Duration::from_millis(10);
+
+/// Ticks mirrored at most (~100ms), in case the follower stops calling back
+const MAX_MIRRORED: usize = SAMPLES_PER_FRAME * 5;
+
+/// One 48kHz sample: the voice mix (None when the buffer ran dry) and the sidetone
+pub type Tick = (Option<f32>, f32);
+
+#[derive(Default)]
+struct State {
+    /// Stream draining the playback buffer
+    leader: u64,
+    /// Stream being switched to
+    follower: Option<u64>,
+    follower_started: bool,
+    mirror: VecDeque<Tick>,
+}
+
+/// Which playback stream plays what
+#[derive(Default)]
+pub struct Crossover {
+    state: Mutex<State>,
+    last_id: AtomicU64,
+}
+
+impl Crossover {
+    /// Id for a new playback stream
+    pub fn next_id(&self) -> u64 {
+        self.last_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Make a stream the only one playing (start, restart)
+    pub fn lead(&self, id: u64) {
+        let mut state = self.state.lock();
+        state.leader = id;
+        state.follower = None;
+        state.mirror.clear();
+    }
+
+    /// Mirror the leader to a stream being opened
+    pub fn follow(&self, id: u64) {
+        let mut state = self.state.lock();
+        state.follower = Some(id);
+        state.follower_started = false;
+        state.mirror.clear();
+    }
+
+    /// Forget a follower that failed to open
+    pub fn abandon(&self, id: u64) {
+        let mut state = self.state.lock();
+        if state.follower == Some(id) {
+            state.follower = None;
+            state.mirror.clear();
+        }
+    }
+
+    /// Whether the follower called back yet
+    pub fn follower_started(&self) -> bool {
+        self.state.lock().follower_started
+    }
+
+    /// Give the playback buffer to the follower, once it played what was mirrored
+    pub fn hand_over(&self, id: u64) {
+        let mut state = self.state.lock();
+        if state.follower == Some(id) {
+            state.leader = id;
+            state.follower = None;
+        }
+    }
+
+    /// Crossover held for a whole playback callback of stream `id`
+    pub(crate) fn enter(&self, id: u64) -> CrossoverGuard<'_> {
+        CrossoverGuard {
+            state: self.state.lock(),
+            id,
+        }
+    }
+}
+
+pub struct CrossoverGuard<'a> {
+    state: MutexGuard<'a, State>,
+    id: u64,
+}
+
+impl CrossoverGuard<'_> {
+    /// Next tick of this stream; `pull` takes one from the playback buffer
+    pub fn next(&mut self, pull: impl FnOnce() -> Tick) -> Tick {
+        let state = &mut *self.state;
+        if state.follower == Some(self.id) {
+            if !state.follower_started {
+                // Start in step with the leader rather than behind it
+                state.follower_started = true;
+                state.mirror.clear();
+            }
+            return state.mirror.pop_front().unwrap_or((None, 0.0));
+        }
+        if state.leader != self.id {
+            // Retired, waiting to be closed
+            return (None, 0.0);
+        }
+
+        if state.follower.is_none() {
+            // Just took over: finish what was mirrored first
+            if let Some(tick) = state.mirror.pop_front() {
+                return tick;
+            }
+        }
+        let tick = pull();
+        if state.follower.is_some() && state.follower_started {
+            if state.mirror.len() == MAX_MIRRORED {
+                state.mirror.pop_front();
+            }
+            state.mirror.push_back(tick);
+        }
+        tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follower_mirrors_then_takes_over() {
+        let crossover = Crossover::default();
+        let (old, new) = (crossover.next_id(), crossover.next_id());
+        crossover.lead(old);
+        crossover.follow(new);
+
+        // Nothing mirrored before the new stream starts
+        assert_eq!(
+            crossover.enter(old).next(|| (Some(0.1), 0.0)),
+            (Some(0.1), 0.0)
+        );
+        assert_eq!(crossover.enter(new).next(|| unreachable!()), (None, 0.0));
+        assert!(crossover.follower_started());
+
+        // Both play the same samples
+        crossover.enter(old).next(|| (Some(0.2), 0.0));
+        crossover.enter(old).next(|| (Some(0.3), 0.0));
+        assert_eq!(
+            crossover.enter(new).next(|| unreachable!()),
+            (Some(0.2), 0.0)
+        );
+
+        // The new stream plays what is left, then the buffer
+        crossover.hand_over(new);
+        assert_eq!(crossover.enter(old).next(|| unreachable!()), (None, 0.0));
+        assert_eq!(
+            crossover.enter(new).next(|| unreachable!()),
+            (Some(0.3), 0.0)
+        );
+        assert_eq!(
+            crossover.enter(new).next(|| (Some(0.4), 0.0)),
+            (Some(0.4), 0.0)
+        );
+    }
+}
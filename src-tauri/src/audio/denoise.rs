@@ -1,22 +1,184 @@
 #![allow(dead_code)]
 
-//! Noise reduction using nnnoiseless (RNNoise-based)
-//! Provides real-time noise suppression for voice audio
+//! Noise reduction
+//! Provides real-time noise suppression for voice audio, with a choice of
+//! backend: nnnoiseless (RNNoise-based), the Speex preprocessor or none
 
-use nnnoiseless::DenoiseState;
+use nnnoiseless::{DenoiseState, RnnModel};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use super::dsp::Resampler;
+use crate::error::{AppError, AppResult};
+
 /// Frame size required by nnnoiseless (480 samples at 48kHz = 10ms)
 const DENOISE_FRAME_SIZE: usize = 480;
 
 /// Target sample rate for nnnoiseless
 const DENOISE_SAMPLE_RATE: u32 = 48000;
 
+/// Noise suppression backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DenoiseBackend {
+    /// RNNoise neural network, the strongest suppression
+    #[default]
+    Nnnoiseless,
+    /// Speex spectral preprocessor, gentler on voices RNNoise mangles
+    /// (needs the `speexdsp` cargo feature)
+    Speexdsp,
+    /// No processing
+    Passthrough,
+}
+
+impl DenoiseBackend {
+    pub const ALL: [Self; 3] = [Self::Nnnoiseless, Self::Speexdsp, Self::Passthrough];
+
+    /// Whether the backend was compiled in
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Speexdsp => cfg!(feature = "speexdsp"),
+            Self::Nnnoiseless | Self::Passthrough => true,
+        }
+    }
+}
+
+/// Largest accepted model file (the built-in model weighs about 100 KB)
+const MAX_MODEL_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Custom trained RNNoise model, used by the nnnoiseless backend instead of
+/// the built-in one
+#[derive(Clone)]
+pub struct RnnoiseModel {
+    path: PathBuf,
+    /// Validated model file, parsed again by each denoiser state
+    bytes: Arc<[u8]>,
+}
+
+impl RnnoiseModel {
+    /// Read and validate a model file
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| {
+                AppError::InvalidArgument(format!("Cannot read {}: {}", path.display(), e))
+            })?
+            .len();
+        if size > MAX_MODEL_SIZE {
+            return Err(AppError::InvalidArgument(format!(
+                "{} is too large for an RNNoise model",
+                path.display()
+            )));
+        }
+
+        let bytes = std::fs::read(path).map_err(|e| {
+            AppError::InvalidArgument(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        if RnnModel::from_bytes(&bytes).is_none() {
+            return Err(AppError::InvalidArgument(format!(
+                "{} is not an RNNoise model",
+                path.display()
+            )));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            bytes: bytes.into(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn state(&self) -> Box<DenoiseState<'static>> {
+        match RnnModel::from_bytes(&self.bytes) {
+            Some(model) => DenoiseState::with_model(model),
+            None => DenoiseState::new(),
+        }
+    }
+}
+
+/// Speex preprocessor working on 16-bit frames
+#[cfg(feature = "speexdsp")]
+struct SpeexEngine {
+    state: speexdsp::preprocess::SpeexPreprocess,
+    pcm: [i16; DENOISE_FRAME_SIZE],
+}
+
+/// Running state of the selected backend
+enum Engine {
+    Nnnoiseless(Box<DenoiseState<'static>>),
+    #[cfg(feature = "speexdsp")]
+    Speexdsp(Box<SpeexEngine>),
+    Passthrough,
+}
+
+impl Engine {
+    fn new(backend: DenoiseBackend, model: Option<&RnnoiseModel>) -> AppResult<Self> {
+        match backend {
+            DenoiseBackend::Nnnoiseless => Ok(Self::Nnnoiseless(
+                model.map_or_else(DenoiseState::new, RnnoiseModel::state),
+            )),
+            #[cfg(feature = "speexdsp")]
+            DenoiseBackend::Speexdsp => {
+                let state = speexdsp::preprocess::SpeexPreprocess::new(
+                    DENOISE_FRAME_SIZE,
+                    DENOISE_SAMPLE_RATE as usize,
+                )
+                .map_err(|_| {
+                    AppError::Audio("Failed to create the Speex preprocessor".to_string())
+                })?;
+                Ok(Self::Speexdsp(Box::new(SpeexEngine {
+                    state,
+                    pcm: [0; DENOISE_FRAME_SIZE],
+                })))
+            }
+            #[cfg(not(feature = "speexdsp"))]
+            DenoiseBackend::Speexdsp => Err(AppError::InvalidArgument(
+                "This build has no speexdsp noise suppression".to_string(),
+            )),
+            DenoiseBackend::Passthrough => Ok(Self::Passthrough),
+        }
+    }
+
+    fn backend(&self) -> DenoiseBackend {
+        match self {
+            Self::Nnnoiseless(_) => DenoiseBackend::Nnnoiseless,
+            #[cfg(feature = "speexdsp")]
+            Self::Speexdsp(_) => DenoiseBackend::Speexdsp,
+            Self::Passthrough => DenoiseBackend::Passthrough,
+        }
+    }
+
+    /// Denoise one DENOISE_FRAME_SIZE frame at 48kHz
+    fn process_frame(&mut self, output: &mut [f32; DENOISE_FRAME_SIZE], frame: &[f32]) {
+        match self {
+            Self::Nnnoiseless(state) => {
+                state.process_frame(output, frame);
+            }
+            #[cfg(feature = "speexdsp")]
+            Self::Speexdsp(engine) => {
+                for (pcm, sample) in engine.pcm.iter_mut().zip(frame) {
+                    *pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                }
+                engine.state.preprocess_run(&mut engine.pcm);
+                for (out, pcm) in output.iter_mut().zip(&engine.pcm) {
+                    *out = *pcm as f32 / i16::MAX as f32;
+                }
+            }
+            Self::Passthrough => output.copy_from_slice(frame),
+        }
+    }
+}
+
 /// Audio denoiser with resampling support
 pub struct AudioDenoiser {
-    /// The nnnoiseless denoiser state
-    state: Box<DenoiseState<'static>>,
+    /// State of the selected backend
+    engine: Engine,
+    /// Custom nnnoiseless model (None for the built-in one)
+    model: Option<RnnoiseModel>,
     /// Input buffer for accumulating samples
     input_buffer: Vec<f32>,
     /// Output buffer for processed samples
@@ -25,29 +187,34 @@ pub struct AudioDenoiser {
     enabled: bool,
     /// Source sample rate (for resampling)
     source_sample_rate: u32,
-    /// Resampling buffer
-    resample_buffer: Vec<f32>,
+    /// Source rate to 48kHz, stateful across calls
+    upsampler: Resampler,
+    /// 48kHz back to the source rate
+    downsampler: Resampler,
 }
 
 impl AudioDenoiser {
     /// Create a new denoiser
     pub fn new() -> Self {
         Self {
-            state: DenoiseState::new(),
+            engine: Engine::Nnnoiseless(DenoiseState::new()),
+            model: None,
             input_buffer: Vec::with_capacity(DENOISE_FRAME_SIZE * 4),
             output_buffer: Vec::with_capacity(DENOISE_FRAME_SIZE * 4),
             enabled: true,
             source_sample_rate: DENOISE_SAMPLE_RATE,
-            resample_buffer: Vec::with_capacity(DENOISE_FRAME_SIZE * 4),
+            upsampler: Resampler::new(1.0),
+            downsampler: Resampler::new(1.0),
         }
     }
 
     /// Set the source sample rate for resampling
     pub fn set_sample_rate(&mut self, rate: u32) {
         self.source_sample_rate = rate;
+        self.upsampler = Resampler::new(DENOISE_SAMPLE_RATE as f64 / rate as f64);
+        self.downsampler = Resampler::new(rate as f64 / DENOISE_SAMPLE_RATE as f64);
         self.input_buffer.clear();
         self.output_buffer.clear();
-        self.resample_buffer.clear();
     }
 
     /// Enable or disable noise reduction
@@ -65,119 +232,91 @@ impl AudioDenoiser {
         self.enabled
     }
 
+    /// Switch backend (the new one starts from a clean state)
+    pub fn set_backend(&mut self, backend: DenoiseBackend) -> AppResult<()> {
+        if backend != self.backend() {
+            self.engine = Engine::new(backend, self.model.as_ref())?;
+            self.clear_buffers();
+        }
+        Ok(())
+    }
+
+    /// Use a custom RNNoise model, or the built-in one with None
+    pub fn set_model(&mut self, model: Option<RnnoiseModel>) {
+        self.model = model;
+        if self.backend() == DenoiseBackend::Nnnoiseless {
+            self.reset();
+        }
+    }
+
+    /// Path of the custom RNNoise model in use
+    pub fn model_path(&self) -> Option<PathBuf> {
+        self.model.as_ref().map(|m| m.path.clone())
+    }
+
+    /// Selected backend
+    pub fn backend(&self) -> DenoiseBackend {
+        self.engine.backend()
+    }
+
     /// Process audio samples through the denoiser
     /// Returns denoised samples (may be empty if buffering)
     pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
-        if !self.enabled {
-            return samples.to_vec();
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output);
+        output
+    }
+
+    /// Same as `process`, appending to `out` (no allocation once the buffers
+    /// have grown to the frame size)
+    pub fn process_into(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        // Passthrough skips the framing and resampling latency
+        if !self.enabled || matches!(self.engine, Engine::Passthrough) {
+            out.extend_from_slice(samples);
+            return;
         }
 
         // Resample to 48kHz if needed
-        let samples_48k = if self.source_sample_rate != DENOISE_SAMPLE_RATE {
-            self.resample_to_48k(samples)
+        if self.source_sample_rate != DENOISE_SAMPLE_RATE {
+            self.upsampler.process_into(samples, &mut self.input_buffer);
         } else {
-            samples.to_vec()
-        };
-
-        // Add to input buffer
-        self.input_buffer.extend_from_slice(&samples_48k);
+            self.input_buffer.extend_from_slice(samples);
+        }
 
         // Process complete frames
-        while self.input_buffer.len() >= DENOISE_FRAME_SIZE {
-            let frame: Vec<f32> = self.input_buffer.drain(..DENOISE_FRAME_SIZE).collect();
-
-            // nnnoiseless expects and returns [f32; DENOISE_FRAME_SIZE]
-            let mut input_frame = [0.0f32; DENOISE_FRAME_SIZE];
-            let mut output_frame = [0.0f32; DENOISE_FRAME_SIZE];
-
-            input_frame.copy_from_slice(&frame);
-
-            // Process the frame
-            self.state.process_frame(&mut output_frame, &input_frame);
-
+        // Every backend works on DENOISE_FRAME_SIZE samples at a time
+        let mut output_frame = [0.0f32; DENOISE_FRAME_SIZE];
+        let mut consumed = 0;
+        while self.input_buffer.len() - consumed >= DENOISE_FRAME_SIZE {
+            let frame = &self.input_buffer[consumed..consumed + DENOISE_FRAME_SIZE];
+            self.engine.process_frame(&mut output_frame, frame);
             self.output_buffer.extend_from_slice(&output_frame);
+            consumed += DENOISE_FRAME_SIZE;
         }
+        self.input_buffer.drain(..consumed);
 
         // Resample back to source rate if needed
-        let result = if self.source_sample_rate != DENOISE_SAMPLE_RATE {
-            let resampled = self.resample_from_48k(&self.output_buffer);
-            self.output_buffer.clear();
-            resampled
+        if self.source_sample_rate != DENOISE_SAMPLE_RATE {
+            self.downsampler.process_into(&self.output_buffer, out);
         } else {
-            let result = self.output_buffer.clone();
-            self.output_buffer.clear();
-            result
-        };
-
-        result
-    }
-
-    /// Simple linear resampling to 48kHz
-    fn resample_to_48k(&self, samples: &[f32]) -> Vec<f32> {
-        if self.source_sample_rate == DENOISE_SAMPLE_RATE {
-            return samples.to_vec();
+            out.extend_from_slice(&self.output_buffer);
         }
-
-        let ratio = DENOISE_SAMPLE_RATE as f64 / self.source_sample_rate as f64;
-        let output_len = (samples.len() as f64 * ratio).ceil() as usize;
-        let mut output = Vec::with_capacity(output_len);
-
-        for i in 0..output_len {
-            let src_idx = i as f64 / ratio;
-            let idx_floor = src_idx.floor() as usize;
-            let idx_ceil = (idx_floor + 1).min(samples.len() - 1);
-            let frac = src_idx - idx_floor as f64;
-
-            let sample = if idx_floor < samples.len() {
-                let s1 = samples[idx_floor];
-                let s2 = samples[idx_ceil];
-                s1 + (s2 - s1) * frac as f32
-            } else {
-                0.0
-            };
-
-            output.push(sample);
-        }
-
-        output
+        self.output_buffer.clear();
     }
 
-    /// Simple linear resampling from 48kHz back to source rate
-    fn resample_from_48k(&self, samples: &[f32]) -> Vec<f32> {
-        if self.source_sample_rate == DENOISE_SAMPLE_RATE {
-            return samples.to_vec();
-        }
-
-        let ratio = self.source_sample_rate as f64 / DENOISE_SAMPLE_RATE as f64;
-        let output_len = (samples.len() as f64 * ratio).ceil() as usize;
-        let mut output = Vec::with_capacity(output_len);
-
-        for i in 0..output_len {
-            let src_idx = i as f64 / ratio;
-            let idx_floor = src_idx.floor() as usize;
-            let idx_ceil = (idx_floor + 1).min(samples.len().saturating_sub(1));
-            let frac = src_idx - idx_floor as f64;
-
-            let sample = if idx_floor < samples.len() {
-                let s1 = samples[idx_floor];
-                let s2 = samples.get(idx_ceil).copied().unwrap_or(s1);
-                s1 + (s2 - s1) * frac as f32
-            } else {
-                0.0
-            };
-
-            output.push(sample);
+    /// Reset the denoiser state
+    pub fn reset(&mut self) {
+        if let Ok(engine) = Engine::new(self.backend(), self.model.as_ref()) {
+            self.engine = engine;
         }
-
-        output
+        self.clear_buffers();
     }
 
-    /// Reset the denoiser state
-    pub fn reset(&mut self) {
-        self.state = DenoiseState::new();
+    fn clear_buffers(&mut self) {
         self.input_buffer.clear();
         self.output_buffer.clear();
-        self.resample_buffer.clear();
+        self.upsampler.reset();
+        self.downsampler.reset();
     }
 }
 
@@ -211,10 +350,30 @@ impl SharedDenoiser {
         self.inner.lock().is_enabled()
     }
 
+    pub fn set_backend(&self, backend: DenoiseBackend) -> AppResult<()> {
+        self.inner.lock().set_backend(backend)
+    }
+
+    pub fn backend(&self) -> DenoiseBackend {
+        self.inner.lock().backend()
+    }
+
+    pub fn set_model(&self, model: Option<RnnoiseModel>) {
+        self.inner.lock().set_model(model);
+    }
+
+    pub fn model_path(&self) -> Option<PathBuf> {
+        self.inner.lock().model_path()
+    }
+
     pub fn process(&self, samples: &[f32]) -> Vec<f32> {
         self.inner.lock().process(samples)
     }
 
+    pub fn process_into(&self, samples: &[f32], out: &mut Vec<f32>) {
+        self.inner.lock().process_into(samples, out);
+    }
+
     pub fn reset(&self) {
         self.inner.lock().reset();
     }
@@ -233,3 +392,28 @@ impl Clone for SharedDenoiser {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passthrough_leaves_samples_untouched() {
+        let mut denoiser = AudioDenoiser::new();
+        denoiser.set_sample_rate(44100);
+        denoiser.set_backend(DenoiseBackend::Passthrough).unwrap();
+
+        let samples: Vec<f32> = (0..441).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        assert_eq!(denoiser.process(&samples), samples);
+        assert_eq!(denoiser.backend(), DenoiseBackend::Passthrough);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_models() {
+        let path = std::env::temp_dir().join(format!("not-a-model-{}.rnn", std::process::id()));
+        std::fs::write(&path, b"hello").unwrap();
+        let result = RnnoiseModel::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(AppError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,147 @@
+//! Pinned device configurations
+//! Some interfaces glitch in their default configuration (a USB interface
+//! defaulting to a tiny buffer, a driver that crackles at 44.1 kHz...). The
+//! user can pin the sample rate and buffer size of a device; pins are saved by
+//! device name and used by the cpal backend every time it opens that device.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::watchdog::StreamKind;
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const DEVICE_CONFIGS_FILE: &str = "device_configs.json";
+
+/// Sample rates a pin may ask for
+const SAMPLE_RATE_RANGE: std::ops::RangeInclusive<u32> = 8_000..=384_000;
+
+/// Buffer sizes a pin may ask for, in frames
+const BUFFER_SIZE_RANGE: std::ops::RangeInclusive<u32> = 16..=16_384;
+
+/// What to force when opening a device (None: the device default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceConfig {
+    pub sample_rate: Option<u32>,
+    /// Frames per callback
+    pub buffer_size: Option<u32>,
+}
+
+impl DeviceConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(rate) = self.sample_rate {
+            if !SAMPLE_RATE_RANGE.contains(&rate) {
+                return Err(AppError::InvalidArgument(format!(
+                    "Sample rate must be between {} and {} Hz",
+                    SAMPLE_RATE_RANGE.start(),
+                    SAMPLE_RATE_RANGE.end()
+                )));
+            }
+        }
+        if let Some(frames) = self.buffer_size {
+            if !BUFFER_SIZE_RANGE.contains(&frames) {
+                return Err(AppError::InvalidArgument(format!(
+                    "Buffer size must be between {} and {} frames",
+                    BUFFER_SIZE_RANGE.start(),
+                    BUFFER_SIZE_RANGE.end()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Pinned configurations, by device name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceConfigs {
+    pub capture: BTreeMap<String, DeviceConfig>,
+    pub playback: BTreeMap<String, DeviceConfig>,
+}
+
+impl DeviceConfigs {
+    const EMPTY: Self = Self {
+        capture: BTreeMap::new(),
+        playback: BTreeMap::new(),
+    };
+
+    fn of(&self, kind: StreamKind) -> &BTreeMap<String, DeviceConfig> {
+        match kind {
+            StreamKind::Capture => &self.capture,
+            StreamKind::Playback => &self.playback,
+        }
+    }
+
+    fn of_mut(&mut self, kind: StreamKind) -> &mut BTreeMap<String, DeviceConfig> {
+        match kind {
+            StreamKind::Capture => &mut self.capture,
+            StreamKind::Playback => &mut self.playback,
+        }
+    }
+}
+
+/// A range of configurations a device supports
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    /// Frames per callback (None: the driver doesn't tell)
+    pub min_buffer_size: Option<u32>,
+    pub max_buffer_size: Option<u32>,
+    pub sample_format: String,
+}
+
+static CURRENT: RwLock<DeviceConfigs> = RwLock::new(DeviceConfigs::EMPTY);
+
+/// Pin of a device (default config when none)
+pub fn pinned(kind: StreamKind, device: &str) -> DeviceConfig {
+    CURRENT
+        .read()
+        .of(kind)
+        .get(device)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Every pinned device
+pub fn device_configs() -> DeviceConfigs {
+    CURRENT.read().clone()
+}
+
+/// Apply the saved pins, skipping invalid ones
+pub fn load_device_configs() {
+    let Some(mut configs) = storage::load_json::<DeviceConfigs>(DEVICE_CONFIGS_FILE) else {
+        return;
+    };
+    for pins in [&mut configs.capture, &mut configs.playback] {
+        pins.retain(|device, config| match config.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Ignoring the saved config of {}: {}", device, e);
+                false
+            }
+        });
+    }
+    *CURRENT.write() = configs;
+}
+
+/// Validate, save and apply a pin (the default config removes it)
+pub fn set_device_config(kind: StreamKind, device: String, config: DeviceConfig) -> AppResult<()> {
+    config.validate()?;
+    let mut configs = device_configs();
+    if config.is_default() {
+        configs.of_mut(kind).remove(&device);
+    } else {
+        configs.of_mut(kind).insert(device, config);
+    }
+    storage::save_json(DEVICE_CONFIGS_FILE, &configs).map_err(AppError::Storage)?;
+    *CURRENT.write() = configs;
+    Ok(())
+}
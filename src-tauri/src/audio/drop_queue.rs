@@ -0,0 +1,116 @@
+//! Drop-oldest queue
+//! Bounded single-consumer queue for live media: when the consumer falls behind,
+//! the oldest item is dropped to make room, so latency stays capped and stale
+//! audio is never sent late. Drops are counted for the stats.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+struct Shared<T> {
+    items: Mutex<VecDeque<T>>,
+    capacity: usize,
+    notify: Notify,
+    pushed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Queue holding at most `capacity` items
+pub fn drop_oldest<T>(capacity: usize) -> (DropOldestSender<T>, DropOldestReceiver<T>) {
+    let shared = Arc::new(Shared {
+        items: Mutex::new(VecDeque::with_capacity(capacity)),
+        capacity: capacity.max(1),
+        notify: Notify::new(),
+        pushed: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        DropOldestSender {
+            shared: shared.clone(),
+        },
+        DropOldestReceiver { shared },
+    )
+}
+
+/// Sending side, never blocks
+pub struct DropOldestSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> DropOldestSender<T> {
+    /// Queue `item`, dropping the oldest one when full (returns whether it did)
+    pub fn push(&self, item: T) -> bool {
+        let dropped = {
+            let mut items = self.shared.items.lock();
+            let dropped = items.len() >= self.shared.capacity;
+            if dropped {
+                items.pop_front();
+            }
+            items.push_back(item);
+            dropped
+        };
+        self.shared.pushed.fetch_add(1, Ordering::Relaxed);
+        if dropped {
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        self.shared.notify.notify_one();
+        dropped
+    }
+
+    /// Items queued since creation
+    pub fn pushed(&self) -> u64 {
+        self.shared.pushed.load(Ordering::Relaxed)
+    }
+
+    /// Items dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Items waiting for the consumer
+    pub fn queued(&self) -> usize {
+        self.shared.items.lock().len()
+    }
+}
+
+/// Receiving side (single consumer)
+pub struct DropOldestReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> DropOldestReceiver<T> {
+    /// Wait for the next item
+    pub async fn recv(&mut self) -> T {
+        loop {
+            if let Some(item) = self.try_recv() {
+                return item;
+            }
+            // `notify_one` keeps a permit when nobody waits, so no push is missed
+            self.shared.notify.notified().await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.shared.items.lock().pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let (tx, mut rx) = drop_oldest(3);
+        for i in 0..5 {
+            tx.push(i);
+        }
+        assert_eq!((tx.pushed(), tx.dropped(), tx.queued()), (5, 2, 3));
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), Some(3));
+        assert_eq!(rx.try_recv(), Some(4));
+        assert_eq!(rx.try_recv(), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Per-sample DSP kernels
+//! RMS, downmix and sample conversion run on every capture frame. They work on
+//! fixed 8-wide chunks with independent accumulators so the compiler emits SIMD
+//! (SSE/AVX/NEON) on stable Rust, and write into caller-owned buffers.
+
+/// Lanes processed per chunk (one AVX register of f32)
+const LANES: usize = 8;
+
+/// Root mean square of `samples`
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let chunks = samples.chunks_exact(LANES);
+    let tail: f32 = chunks.remainder().iter().map(|s| s * s).sum();
+    let mut acc = [0.0f32; LANES];
+    for chunk in chunks {
+        for (a, s) in acc.iter_mut().zip(chunk) {
+            *a += s * s;
+        }
+    }
+    ((acc.iter().sum::<f32>() + tail) / samples.len() as f32).sqrt()
+}
+
+/// RMS mapped to a 0..1 meter level over a -60..0 dBFS range
+pub fn rms_to_level(rms: f32) -> f32 {
+    let db = 20.0 * rms.max(1e-10).log10();
+    ((db + 60.0) / 60.0).clamp(0.0, 1.0)
+}
+
+/// Average interleaved frames of `channels` to mono, appending to `out`
+pub fn downmix_into(data: &[f32], channels: usize, out: &mut Vec<f32>) {
+    match channels {
+        0 | 1 => out.extend_from_slice(data),
+        // Stereo is by far the most common multi-channel input
+        2 => out.extend(data.chunks_exact(2).map(|f| (f[0] + f[1]) * 0.5)),
+        _ => {
+            let scale = 1.0 / channels as f32;
+            out.extend(
+                data.chunks_exact(channels)
+                    .map(|f| f.iter().sum::<f32>() * scale),
+            );
+        }
+    }
+}
+
+/// Convert i16 samples to f32 in -1..1, appending to `out`
+pub fn i16_to_f32_into(data: &[i16], out: &mut Vec<f32>) {
+    const SCALE: f32 = 1.0 / 32768.0;
+    out.reserve(data.len());
+    let chunks = data.chunks_exact(LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let mut lanes = [0.0f32; LANES];
+        for (l, &s) in lanes.iter_mut().zip(chunk) {
+            *l = s as f32 * SCALE;
+        }
+        out.extend_from_slice(&lanes);
+    }
+    out.extend(tail.iter().map(|&s| s as f32 * SCALE));
+}
+
+/// Linear resampler for a continuous stream fed in chunks
+/// The fractional read position and the last input sample carry over between
+/// calls, so chunk boundaries don't restart the phase (which clicks at 44.1kHz).
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    /// Input samples consumed per output sample
+    step: f64,
+    /// Read position; 0 is `last`, 1 is the first sample of the next chunk
+    position: f64,
+    last: f32,
+}
+
+impl Resampler {
+    /// Resampler by `ratio` (output rate / input rate)
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            step: 1.0 / ratio,
+            position: 1.0,
+            last: 0.0,
+        }
+    }
+
+    /// Resample the next chunk of the stream, appending to `out`
+    pub fn process_into(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        let Some(&newest) = samples.last() else {
+            return;
+        };
+        let len = samples.len() as f64;
+        out.reserve(((len - self.position) / self.step).ceil().max(0.0) as usize + 1);
+
+        // Position `i` reads `last` for i = 0 and `samples[i - 1]` after that
+        while self.position < len {
+            let idx = self.position as usize;
+            let frac = (self.position - idx as f64) as f32;
+            let s1 = if idx == 0 {
+                self.last
+            } else {
+                samples[idx - 1]
+            };
+            let s2 = samples[idx];
+            out.push(s1 + (s2 - s1) * frac);
+            self.position += self.step;
+        }
+
+        self.position -= len;
+        self.last = newest;
+    }
+
+    /// Forget the stream position (the next chunk starts a new stream)
+    pub fn reset(&mut self) {
+        self.position = 1.0;
+        self.last = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernels_match_scalar_versions() {
+        // 21 samples: two full chunks and a tail
+        let samples: Vec<f32> = (0..21).map(|i| (i as f32 * 0.37).sin()).collect();
+        let scalar = (samples.iter().map(|s| s * s).sum::<f32>() / 21.0).sqrt();
+        assert!((rms(&samples) - scalar).abs() < 1e-6);
+        assert_eq!(rms(&[]), 0.0);
+
+        let mut mono = Vec::new();
+        downmix_into(&[0.25, 0.75, -1.0, 1.0, 0.5, 0.5], 2, &mut mono);
+        assert_eq!(mono, [0.5, 0.0, 0.5]);
+        downmix_into(&[0.5, 1.0, 0.0], 3, &mut mono);
+        assert_eq!(mono, [0.5, 0.0, 0.5, 0.5]);
+
+        let mut converted = Vec::new();
+        i16_to_f32_into(
+            &[i16::MIN, 0, 16384, -16384, 1, 2, 3, 4, i16::MAX],
+            &mut converted,
+        );
+        assert_eq!(converted[..4], [-1.0, 0.0, 0.5, -0.5]);
+        assert_eq!(converted.len(), 9);
+    }
+
+    #[test]
+    fn resampler_is_continuous_across_chunks() {
+        let input: Vec<f32> = (0..3000).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        let mut whole = Vec::new();
+        Resampler::new(48000.0 / 44100.0).process_into(&input, &mut whole);
+
+        let mut chunked = Vec::new();
+        let mut resampler = Resampler::new(48000.0 / 44100.0);
+        for chunk in input.chunks(1000) {
+            resampler.process_into(chunk, &mut chunked);
+        }
+
+        assert_eq!(whole.len(), chunked.len());
+        assert!(whole
+            .iter()
+            .zip(&chunked)
+            .all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+}
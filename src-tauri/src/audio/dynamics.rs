@@ -0,0 +1,168 @@
+//! Output dynamics
+//! Compressor and brick-wall limiter on the master playback bus, after the
+//! master volume: loud peers are tamed, quiet ones brought closer, and the
+//! output never goes past the ceiling
+
+use serde::{Deserialize, Serialize};
+
+use super::SAMPLE_RATE;
+use crate::error::{AppError, AppResult};
+
+/// Limiter ceiling (-1 dBFS)
+const LIMITER_CEILING: f32 = 0.891;
+/// Time for the limiter to release 63% of its gain reduction
+const LIMITER_RELEASE_MS: f32 = 50.0;
+
+/// Compressor settings (persisted)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DynamicsSettings {
+    pub enabled: bool,
+    /// Level above which the compressor acts, in dBFS (-60 to 0)
+    pub threshold_db: f32,
+    /// Input dB above the threshold per output dB (1 to 20)
+    pub ratio: f32,
+    /// Gain added after compression, in dB (0 to 24)
+    pub makeup_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+impl DynamicsSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        let valid = (-60.0..=0.0).contains(&self.threshold_db)
+            && (1.0..=20.0).contains(&self.ratio)
+            && (0.0..=24.0).contains(&self.makeup_db)
+            && (0.1..=200.0).contains(&self.attack_ms)
+            && (1.0..=2000.0).contains(&self.release_ms);
+        if !valid {
+            return Err(AppError::InvalidArgument(
+                "Compressor settings out of range".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for DynamicsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_db: -18.0,
+            ratio: 4.0,
+            makeup_db: 4.0,
+            attack_ms: 5.0,
+            release_ms: 120.0,
+        }
+    }
+}
+
+/// One-pole smoothing coefficient for a time constant at 48kHz
+fn coefficient(ms: f32) -> f32 {
+    (-1.0 / (ms * 0.001 * SAMPLE_RATE as f32)).exp()
+}
+
+/// Compressor followed by a limiter
+#[derive(Debug, Clone)]
+pub struct Dynamics {
+    settings: DynamicsSettings,
+    attack: f32,
+    release: f32,
+    makeup: f32,
+    limiter_release: f32,
+    /// Compressor gain reduction envelope, in dB (<= 0)
+    reduction_db: f32,
+    /// Limiter gain (<= 1)
+    limiter_gain: f32,
+}
+
+impl Dynamics {
+    pub fn new(settings: DynamicsSettings) -> Self {
+        let mut dynamics = Self {
+            settings,
+            attack: 0.0,
+            release: 0.0,
+            makeup: 1.0,
+            limiter_release: coefficient(LIMITER_RELEASE_MS),
+            reduction_db: 0.0,
+            limiter_gain: 1.0,
+        };
+        dynamics.set_settings(settings);
+        dynamics
+    }
+
+    /// Apply new settings (the envelopes carry over, no click)
+    pub fn set_settings(&mut self, settings: DynamicsSettings) {
+        self.settings = settings;
+        self.attack = coefficient(settings.attack_ms);
+        self.release = coefficient(settings.release_ms);
+        self.makeup = 10f32.powf(settings.makeup_db / 20.0);
+    }
+
+    pub fn settings(&self) -> DynamicsSettings {
+        self.settings
+    }
+
+    /// Process one 48kHz sample
+    /// The limiter always runs: even with the compressor off, nothing exceeds
+    /// the ceiling.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let mut out = sample;
+
+        if self.settings.enabled {
+            let level_db = 20.0 * sample.abs().max(1e-6).log10();
+            let over = level_db - self.settings.threshold_db;
+            let target = if over > 0.0 {
+                -over * (1.0 - 1.0 / self.settings.ratio)
+            } else {
+                0.0
+            };
+            // More reduction follows the attack time, less the release time
+            let coeff = if target < self.reduction_db {
+                self.attack
+            } else {
+                self.release
+            };
+            self.reduction_db = target + coeff * (self.reduction_db - target);
+            out *= 10f32.powf(self.reduction_db / 20.0) * self.makeup;
+        }
+
+        // Instant attack: the gain drops as soon as a sample would clip
+        self.limiter_gain = 1.0 - self.limiter_release * (1.0 - self.limiter_gain);
+        let peak = out.abs() * self.limiter_gain;
+        if peak > LIMITER_CEILING {
+            self.limiter_gain = LIMITER_CEILING / out.abs();
+        }
+        out * self.limiter_gain
+    }
+
+    /// Process 48kHz samples in place
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+impl Default for Dynamics {
+    fn default() -> Self {
+        Self::new(DynamicsSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_holds_the_ceiling_on_a_sudden_blast() {
+        let mut dynamics = Dynamics::default();
+        let mut samples: Vec<f32> = (0..4800)
+            .map(|i| ((i as f32) * 0.01).sin() * 0.01)
+            .collect();
+        samples.extend((0..4800).map(|i| if i % 2 == 0 { 4.0 } else { -4.0 }));
+
+        dynamics.process_in_place(&mut samples);
+        assert!(samples.iter().all(|s| s.abs() <= LIMITER_CEILING + 1e-6));
+    }
+}
@@ -1,8 +1,58 @@
 #![allow(dead_code)]
 
 use opus::{Application, Channels, Decoder, Encoder};
+use serde::{Deserialize, Serialize};
 
 use super::{OPUS_BITRATE, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
+
+/// Highest Opus complexity (best quality, most CPU)
+pub const MAX_OPUS_COMPLEXITY: u8 = 10;
+
+/// Opus settings trading quality against CPU and bitrate against resilience
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncoderOptions {
+    /// 0 (cheapest) to 10 (best quality)
+    pub complexity: u8,
+    /// Packet loss the encoder expects, in percent (tunes FEC redundancy)
+    pub packet_loss_perc: u8,
+    /// Embed a low-bitrate copy of each frame in the next one
+    pub inband_fec: bool,
+    /// Follow the loss peers measure instead of `packet_loss_perc` and
+    /// `inband_fec`, which then only apply until the first measurement
+    pub adaptive_fec: bool,
+}
+
+impl EncoderOptions {
+    pub const DEFAULT: Self = Self {
+        complexity: 9, // libopus default
+        packet_loss_perc: 10,
+        inband_fec: true,
+        adaptive_fec: true,
+    };
+
+    pub fn validate(&self) -> AppResult<()> {
+        if self.complexity > MAX_OPUS_COMPLEXITY {
+            return Err(AppError::InvalidArgument(format!(
+                "Complexity must be between 0 and {}",
+                MAX_OPUS_COMPLEXITY
+            )));
+        }
+        if self.packet_loss_perc > 100 {
+            return Err(AppError::InvalidArgument(
+                "Packet loss must be between 0 and 100%".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
 
 /// Opus encoder for voice compression
 pub struct OpusEncoder {
@@ -10,41 +60,59 @@ pub struct OpusEncoder {
 }
 
 impl OpusEncoder {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> AppResult<Self> {
         let mut encoder = Encoder::new(
             SAMPLE_RATE,
             Channels::Mono,
             Application::Voip, // Optimized for voice
         )
-        .map_err(|e| format!("Failed to create Opus encoder: {}", e))?;
+        .map_err(|e| AppError::Codec(format!("Failed to create Opus encoder: {}", e)))?;
 
         // Set bitrate (64kbps is good for voice)
         encoder
             .set_bitrate(opus::Bitrate::Bits(OPUS_BITRATE))
-            .map_err(|e| format!("Failed to set bitrate: {}", e))?;
+            .map_err(|e| AppError::Codec(format!("Failed to set bitrate: {}", e)))?;
 
-        // Enable Forward Error Correction for packet loss resilience
-        encoder
-            .set_inband_fec(true)
-            .map_err(|e| format!("Failed to enable FEC: {}", e))?;
+        let mut encoder = Self { encoder };
+        encoder.set_options(&EncoderOptions::DEFAULT)?;
+        Ok(encoder)
+    }
 
-        // Set expected packet loss percentage for FEC tuning
-        encoder
-            .set_packet_loss_perc(10)
-            .map_err(|e| format!("Failed to set packet loss percentage: {}", e))?;
+    /// Apply complexity, expected loss and FEC (takes effect on the next frame)
+    pub fn set_options(&mut self, options: &EncoderOptions) -> AppResult<()> {
+        options.validate()?;
+
+        self.encoder
+            .set_complexity(options.complexity as i32)
+            .map_err(|e| AppError::Codec(format!("Failed to set complexity: {}", e)))?;
 
-        Ok(Self { encoder })
+        // Forward Error Correction for packet loss resilience
+        self.encoder
+            .set_inband_fec(options.inband_fec)
+            .map_err(|e| AppError::Codec(format!("Failed to set FEC: {}", e)))?;
+
+        // Expected packet loss percentage for FEC tuning
+        self.encoder
+            .set_packet_loss_perc(options.packet_loss_perc as i32)
+            .map_err(|e| AppError::Codec(format!("Failed to set packet loss percentage: {}", e)))
+    }
+
+    /// Change the target bitrate (bits/s)
+    pub fn set_bitrate(&mut self, bitrate: i32) -> AppResult<()> {
+        self.encoder
+            .set_bitrate(opus::Bitrate::Bits(bitrate))
+            .map_err(|e| AppError::Codec(format!("Failed to set bitrate: {}", e)))
     }
 
     /// Encode f32 samples to Opus bytes
     /// Input must be SAMPLES_PER_FRAME samples (960 for 20ms @ 48kHz)
-    pub fn encode(&mut self, samples: &[f32]) -> Result<Vec<u8>, String> {
+    pub fn encode(&mut self, samples: &[f32]) -> AppResult<Vec<u8>> {
         if samples.len() != SAMPLES_PER_FRAME {
-            return Err(format!(
+            return Err(AppError::Codec(format!(
                 "Expected {} samples, got {}",
                 SAMPLES_PER_FRAME,
                 samples.len()
-            ));
+            )));
         }
 
         // Opus needs max output buffer (encoded voice is usually ~64-128 bytes)
@@ -53,7 +121,7 @@ impl OpusEncoder {
         let len = self
             .encoder
             .encode_float(samples, &mut output)
-            .map_err(|e| format!("Encoding failed: {}", e))?;
+            .map_err(|e| AppError::Codec(format!("Encoding failed: {}", e)))?;
 
         output.truncate(len);
         Ok(output)
@@ -66,35 +134,35 @@ pub struct OpusDecoder {
 }
 
 impl OpusDecoder {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> AppResult<Self> {
         let decoder = Decoder::new(SAMPLE_RATE, Channels::Mono)
-            .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+            .map_err(|e| AppError::Codec(format!("Failed to create Opus decoder: {}", e)))?;
 
         Ok(Self { decoder })
     }
 
     /// Decode Opus bytes to f32 samples
     /// Returns SAMPLES_PER_FRAME samples
-    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<f32>, String> {
+    pub fn decode(&mut self, data: &[u8]) -> AppResult<Vec<f32>> {
         let mut output = vec![0.0f32; SAMPLES_PER_FRAME];
 
         let _len = self
             .decoder
             .decode_float(data, &mut output, false)
-            .map_err(|e| format!("Decoding failed: {}", e))?;
+            .map_err(|e| AppError::Codec(format!("Decoding failed: {}", e)))?;
 
         Ok(output)
     }
 
     /// Decode with packet loss concealment (when packet is lost)
-    pub fn decode_lost(&mut self) -> Result<Vec<f32>, String> {
+    pub fn decode_lost(&mut self) -> AppResult<Vec<f32>> {
         let mut output = vec![0.0f32; SAMPLES_PER_FRAME];
 
         // Pass empty data to trigger PLC
         let _len = self
             .decoder
             .decode_float(&[], &mut output, true) // fec=true for PLC
-            .map_err(|e| format!("PLC decoding failed: {}", e))?;
+            .map_err(|e| AppError::Codec(format!("PLC decoding failed: {}", e)))?;
 
         Ok(output)
     }
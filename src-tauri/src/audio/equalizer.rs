@@ -0,0 +1,256 @@
+//! Output equalizer
+//! Five biquad bands (RBJ cookbook) on the master playback bus, at 48kHz
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use super::SAMPLE_RATE;
+use crate::error::{AppError, AppResult};
+
+/// Number of bands: bass, low mids, mids, presence, treble
+pub const EQ_BANDS: usize = 5;
+
+/// Band gain range in dB
+pub const MAX_EQ_GAIN_DB: f32 = 12.0;
+
+/// Q of the peaking bands (about 1.4 octaves)
+const PEAKING_Q: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+enum BandKind {
+    LowShelf,
+    Peaking,
+    HighShelf,
+}
+
+/// Shape and center frequency (Hz) of each band
+const BANDS: [(BandKind, f32); EQ_BANDS] = [
+    (BandKind::LowShelf, 120.0),
+    (BandKind::Peaking, 400.0),
+    (BandKind::Peaking, 1000.0),
+    (BandKind::Peaking, 3000.0),
+    (BandKind::HighShelf, 8000.0),
+];
+
+/// Built-in band settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EqPreset {
+    Flat,
+    /// Less boom and mud, more presence: easier to follow speech
+    VoiceClarity,
+    BassBoost,
+}
+
+impl EqPreset {
+    fn gains_db(self) -> [f32; EQ_BANDS] {
+        match self {
+            Self::Flat => [0.0; EQ_BANDS],
+            Self::VoiceClarity => [-4.0, -2.0, 0.0, 4.0, 2.0],
+            Self::BassBoost => [6.0, 2.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// Equalizer settings (persisted)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EqSettings {
+    pub enabled: bool,
+    /// Gain of each band in dB, from bass to treble
+    pub gains_db: [f32; EQ_BANDS],
+}
+
+impl EqSettings {
+    pub fn preset(preset: EqPreset) -> Self {
+        Self {
+            enabled: preset != EqPreset::Flat,
+            gains_db: preset.gains_db(),
+        }
+    }
+
+    pub fn validate(&self) -> AppResult<()> {
+        if self
+            .gains_db
+            .iter()
+            .any(|g| !g.is_finite() || g.abs() > MAX_EQ_GAIN_DB)
+        {
+            return Err(AppError::InvalidArgument(format!(
+                "Equalizer gains must be between -{0} and {0} dB",
+                MAX_EQ_GAIN_DB
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        Self::preset(EqPreset::Flat)
+    }
+}
+
+/// Second-order filter, transposed direct form II
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    fn new(kind: BandKind, freq: f32, gain_db: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / SAMPLE_RATE as f32;
+        let (sin, cos) = w0.sin_cos();
+
+        let (b0, b1, b2, a0, a1, a2) = match kind {
+            BandKind::Peaking => {
+                let alpha = sin / (2.0 * PEAKING_Q);
+                (
+                    1.0 + alpha * a,
+                    -2.0 * cos,
+                    1.0 - alpha * a,
+                    1.0 + alpha / a,
+                    -2.0 * cos,
+                    1.0 - alpha / a,
+                )
+            }
+            // Shelf slope of 1
+            BandKind::LowShelf => {
+                let k = sin * a.sqrt() * std::f32::consts::SQRT_2;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + k),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - k),
+                    (a + 1.0) + (a - 1.0) * cos + k,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - k,
+                )
+            }
+            BandKind::HighShelf => {
+                let k = sin * a.sqrt() * std::f32::consts::SQRT_2;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + k),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - k),
+                    (a + 1.0) - (a - 1.0) * cos + k,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - k,
+                )
+            }
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Equalizer of the master playback bus
+#[derive(Debug, Clone)]
+pub struct Equalizer {
+    settings: EqSettings,
+    /// Bands with a non-zero gain (empty when bypassed)
+    filters: Vec<Biquad>,
+}
+
+impl Equalizer {
+    pub fn new(settings: EqSettings) -> Self {
+        let mut eq = Self {
+            settings,
+            filters: Vec::with_capacity(EQ_BANDS),
+        };
+        eq.set_settings(settings);
+        eq
+    }
+
+    /// Apply new settings (filter state restarts from silence)
+    pub fn set_settings(&mut self, settings: EqSettings) {
+        self.settings = settings;
+        self.filters.clear();
+        if settings.enabled {
+            self.filters.extend(
+                BANDS
+                    .iter()
+                    .zip(settings.gains_db)
+                    .filter(|(_, gain)| *gain != 0.0)
+                    .map(|(&(kind, freq), gain)| Biquad::new(kind, freq, gain)),
+            );
+        }
+    }
+
+    pub fn settings(&self) -> EqSettings {
+        self.settings
+    }
+
+    /// Filter one 48kHz sample
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.filters.iter_mut().fold(sample, |s, f| f.process(s))
+    }
+
+    /// Filter 48kHz samples in place
+    pub fn process_in_place(&mut self, samples: &mut [f32]) {
+        if self.filters.is_empty() {
+            return;
+        }
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+impl Default for Equalizer {
+    fn default() -> Self {
+        Self::new(EqSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::dsp;
+
+    fn sine(freq: f32) -> Vec<f32> {
+        (0..SAMPLE_RATE)
+            .map(|i| (2.0 * PI * freq * i as f32 / SAMPLE_RATE as f32).sin() * 0.25)
+            .collect()
+    }
+
+    #[test]
+    fn bass_boost_raises_low_frequencies_only() {
+        let mut eq = Equalizer::new(EqSettings {
+            enabled: true,
+            gains_db: [6.0, 0.0, 0.0, 0.0, 0.0],
+        });
+
+        let mut low = sine(50.0);
+        eq.process_in_place(&mut low);
+        let gain_db = 20.0 * (dsp::rms(&low[4800..]) / dsp::rms(&sine(50.0))).log10();
+        assert!((gain_db - 6.0).abs() < 0.5, "50 Hz gain {gain_db} dB");
+
+        eq.set_settings(eq.settings());
+        let mut high = sine(5000.0);
+        eq.process_in_place(&mut high);
+        let gain_db = 20.0 * (dsp::rms(&high[4800..]) / dsp::rms(&sine(5000.0))).log10();
+        assert!(gain_db.abs() < 0.5, "5 kHz gain {gain_db} dB");
+    }
+}
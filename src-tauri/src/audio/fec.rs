@@ -0,0 +1,116 @@
+//! Loss-adaptive FEC
+//! Opus in-band FEC only pays off when packets are actually lost, and its
+//! redundancy is sized by the loss the encoder is told to expect. The
+//! controller follows the loss peers report on our audio, so a flaky Wi-Fi
+//! link gets protection while a clean one keeps the whole bitrate for the voice.
+
+/// Smoothed loss above which in-band FEC is turned on, in percent
+const FEC_ON_LOSS: f64 = 1.0;
+
+/// Smoothed loss below which it is turned off again (hysteresis)
+const FEC_OFF_LOSS: f64 = 0.5;
+
+/// Highest loss the encoder is told to expect (more only starves the voice)
+const MAX_EXPECTED_LOSS: u8 = 25;
+
+/// Weight of a new measurement above the smoothed loss (react to bursts quickly)
+const RISE_WEIGHT: f64 = 0.6;
+
+/// Weight of a new measurement below it (stay protected a while after a burst)
+const DECAY_WEIGHT: f64 = 0.15;
+
+/// Expected loss and FEC given to the encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LossProtection {
+    pub packet_loss_perc: u8,
+    pub inband_fec: bool,
+}
+
+/// Turns measured loss into encoder settings
+#[derive(Debug, Default)]
+pub struct FecController {
+    smoothed: Option<f64>,
+    current: Option<LossProtection>,
+}
+
+impl FecController {
+    /// Feed the loss measured on our audio, in percent; returns the new
+    /// settings when they change
+    pub fn update(&mut self, loss_percent: f64) -> Option<LossProtection> {
+        let loss = loss_percent.clamp(0.0, 100.0);
+        let smoothed = match self.smoothed {
+            Some(previous) => {
+                let weight = if loss > previous {
+                    RISE_WEIGHT
+                } else {
+                    DECAY_WEIGHT
+                };
+                previous + (loss - previous) * weight
+            }
+            None => loss,
+        };
+        self.smoothed = Some(smoothed);
+
+        let was_on = self.current.is_some_and(|c| c.inband_fec);
+        let threshold = if was_on { FEC_OFF_LOSS } else { FEC_ON_LOSS };
+        let inband_fec = smoothed >= threshold;
+        let packet_loss_perc = if inband_fec {
+            (smoothed.ceil() as u8).min(MAX_EXPECTED_LOSS)
+        } else {
+            0
+        };
+
+        let next = LossProtection {
+            packet_loss_perc,
+            inband_fec,
+        };
+        if self.current == Some(next) {
+            return None;
+        }
+        self.current = Some(next);
+        Some(next)
+    }
+
+    /// Settings from the measurements so far (None before the first one)
+    pub fn current(&self) -> Option<LossProtection> {
+        self.current
+    }
+
+    /// Forget the measurements (back to the configured options)
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fec_follows_loss_with_hysteresis() {
+        let mut fec = FecController::default();
+        let off = fec.update(0.0).unwrap();
+        assert!(!off.inband_fec);
+        assert_eq!(off.packet_loss_perc, 0);
+        assert_eq!(fec.update(0.2), None);
+
+        // A burst turns FEC on right away
+        let on = fec.update(8.0).unwrap();
+        assert!(on.inband_fec);
+        assert!(on.packet_loss_perc >= 4);
+
+        // Stays on while the loss decays, off once it is well gone
+        fec.update(0.0);
+        assert!(fec.current().unwrap().inband_fec);
+        for _ in 0..40 {
+            fec.update(0.0);
+        }
+        assert!(!fec.current().unwrap().inband_fec);
+
+        // Capped so a dead link doesn't get all the bitrate
+        for _ in 0..10 {
+            fec.update(100.0);
+        }
+        assert_eq!(fec.current().unwrap().packet_loss_perc, MAX_EXPECTED_LOSS);
+    }
+}
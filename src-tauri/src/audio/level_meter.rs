@@ -0,0 +1,97 @@
+//! Level event throttling
+//! Levels are measured on every 20ms frame, but the meter only redraws at UI
+//! rate: emitting 50 events a second costs IPC and React renders for nothing.
+//! Frames are folded with peak hold (loudest frame, speaking if any frame was)
+//! and `audio-level` goes out at the configured rate.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::events::AudioLevelEvent;
+
+/// Level events per second unless configured otherwise
+pub const DEFAULT_LEVEL_EVENT_RATE: u32 = 15;
+
+/// One event per 20ms frame, the measurement resolution
+pub const MAX_LEVEL_EVENT_RATE: u32 = 50;
+
+static LEVEL_EVENT_RATE: AtomicU32 = AtomicU32::new(DEFAULT_LEVEL_EVENT_RATE);
+
+/// Set the `audio-level` rate of every capture (clamped to 1..=50 Hz)
+pub fn set_level_event_rate(rate: u32) {
+    LEVEL_EVENT_RATE.store(rate.clamp(1, MAX_LEVEL_EVENT_RATE), Ordering::Relaxed);
+}
+
+/// Current `audio-level` rate
+pub fn level_event_rate() -> u32 {
+    LEVEL_EVENT_RATE.load(Ordering::Relaxed)
+}
+
+/// Coalesces per-frame levels into events at the configured rate
+#[derive(Default)]
+pub struct LevelThrottle {
+    peak: Option<AudioLevelEvent>,
+    next_emit: Option<Instant>,
+}
+
+impl LevelThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold in the level of one frame; returns the peak of the window once it
+    /// is time to emit
+    pub fn observe(&mut self, level: AudioLevelEvent, now: Instant) -> Option<AudioLevelEvent> {
+        self.peak = Some(match self.peak.take() {
+            Some(peak) => AudioLevelEvent {
+                level: peak.level.max(level.level),
+                is_speaking: peak.is_speaking || level.is_speaking,
+                rms: peak.rms.max(level.rms),
+            },
+            None => level,
+        });
+
+        let interval = Duration::from_secs(1) / level_event_rate();
+        let next_emit = *self.next_emit.get_or_insert(now);
+        if now < next_emit {
+            return None;
+        }
+        // Steps from the previous deadline so the average rate holds even though
+        // frames only arrive every 20ms; restarts after a gap (stream stopped)
+        let next = next_emit + interval;
+        self.next_emit = Some(if next > now { next } else { now + interval });
+        self.peak.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesces_frames_with_peak_hold() {
+        let frame = |level: f32, is_speaking: bool| AudioLevelEvent {
+            level,
+            is_speaking,
+            rms: level / 10.0,
+        };
+        let mut throttle = LevelThrottle::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // First frame goes out right away, then one event per ~67ms window
+        assert!(throttle.observe(frame(0.1, false), at(0)).is_some());
+        assert!(throttle.observe(frame(0.8, true), at(20)).is_none());
+        assert!(throttle.observe(frame(0.2, false), at(40)).is_none());
+        assert!(throttle.observe(frame(0.3, false), at(60)).is_none());
+        let event = throttle.observe(frame(0.1, false), at(80)).unwrap();
+        assert_eq!(event.level, 0.8);
+        assert!(event.is_speaking);
+
+        // Over a second of 20ms frames, the default rate is kept
+        let emitted = (5..55)
+            .filter(|i| throttle.observe(frame(0.5, false), at(i * 20)).is_some())
+            .count();
+        assert_eq!(emitted, DEFAULT_LEVEL_EVENT_RATE as usize);
+    }
+}
@@ -0,0 +1,71 @@
+//! Microphone check
+//! Metrics of a short recording of the processed capture, played back to the user
+
+use serde::Serialize;
+use std::time::Duration;
+
+use super::{dsp, SAMPLE_RATE};
+
+/// Length of the recording
+pub const MIC_CHECK_DURATION: Duration = Duration::from_secs(5);
+
+/// Peak above which the input is considered clipping
+const CLIP_THRESHOLD: f32 = 0.99;
+/// RMS below which nothing usable was recorded (about -50 dBFS)
+const SILENCE_THRESHOLD: f32 = 0.003;
+
+/// Result of a mic check
+#[derive(Debug, Clone, Serialize)]
+pub struct MicCheckResult {
+    pub duration_ms: u64,
+    /// Linear peak (0.0 to 1.0)
+    pub peak: f32,
+    /// Linear RMS (0.0 to 1.0)
+    pub rms: f32,
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    pub clipped: bool,
+    pub silent: bool,
+}
+
+impl MicCheckResult {
+    /// Compute metrics of 48kHz mono samples
+    pub fn from_samples(samples: &[f32]) -> Self {
+        let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let rms = dsp::rms(samples);
+
+        Self {
+            duration_ms: samples.len() as u64 * 1000 / SAMPLE_RATE as u64,
+            peak,
+            rms,
+            peak_dbfs: to_dbfs(peak),
+            rms_dbfs: to_dbfs(rms),
+            clipped: peak >= CLIP_THRESHOLD,
+            silent: rms < SILENCE_THRESHOLD,
+        }
+    }
+}
+
+fn to_dbfs(value: f32) -> f32 {
+    20.0 * value.max(1e-10).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_of_a_full_scale_square_wave() {
+        let samples: Vec<f32> = (0..SAMPLE_RATE)
+            .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+            .collect();
+        let result = MicCheckResult::from_samples(&samples);
+
+        assert_eq!(result.duration_ms, 1000);
+        assert_eq!(result.peak, 1.0);
+        assert!((result.rms - 1.0).abs() < 1e-4);
+        assert!(result.clipped);
+        assert!(!result.silent);
+        assert!(MicCheckResult::from_samples(&[]).silent);
+    }
+}
@@ -2,12 +2,57 @@
 
 use std::collections::{HashMap, VecDeque};
 
+use super::dynamics::{Dynamics, DynamicsSettings};
+use super::equalizer::{EqSettings, Equalizer};
 use super::SAMPLES_PER_FRAME;
 
 /// Jitter buffer size in frames (50ms = ~2-3 frames at 20ms/frame)
 const JITTER_BUFFER_FRAMES: usize = 3;
 const JITTER_BUFFER_SAMPLES: usize = SAMPLES_PER_FRAME * JITTER_BUFFER_FRAMES;
 
+/// Buffer depth drift compensation aims for (before each mix)
+const TARGET_DEPTH_SAMPLES: f64 = (SAMPLES_PER_FRAME * 2) as f64;
+
+/// Depth error tolerated before adjusting the rate (a quarter frame)
+const DEPTH_DEADBAND_SAMPLES: f64 = (SAMPLES_PER_FRAME / 4) as f64;
+
+/// Largest playback rate change (0.5%, inaudible on voice)
+const MAX_RATE_ADJUST: f64 = 0.005;
+
+/// Weight of each new depth measurement in the running average (~2s window)
+const DEPTH_SMOOTHING: f64 = 0.01;
+
+/// Holds a peer's buffer depth steady when its clock runs slightly faster or
+/// slower than ours: the average depth drives a tiny resampling ratio, so the
+/// buffer neither grows into latency nor starves into gaps.
+struct DriftCompensator {
+    /// Smoothed buffer depth in samples
+    depth: f64,
+    /// Read position between the first two queued samples
+    position: f64,
+}
+
+impl DriftCompensator {
+    fn new() -> Self {
+        Self {
+            depth: TARGET_DEPTH_SAMPLES,
+            position: 0.0,
+        }
+    }
+
+    /// Input samples to consume per output sample, given the current depth
+    fn step(&mut self, depth: usize) -> f64 {
+        self.depth += (depth as f64 - self.depth) * DEPTH_SMOOTHING;
+        let error = self.depth - TARGET_DEPTH_SAMPLES;
+        if error.abs() <= DEPTH_DEADBAND_SAMPLES {
+            return 1.0;
+        }
+        // Full correction once a whole frame off target
+        let adjust = error / SAMPLES_PER_FRAME as f64 * MAX_RATE_ADJUST;
+        1.0 + adjust.clamp(-MAX_RATE_ADJUST, MAX_RATE_ADJUST)
+    }
+}
+
 /// Per-peer audio buffer
 struct PeerBuffer {
     /// Queue holding decoded samples
@@ -18,6 +63,7 @@ struct PeerBuffer {
     muted: bool,
     /// Last activity timestamp (for detecting silence)
     last_activity: std::time::Instant,
+    drift: DriftCompensator,
 }
 
 impl PeerBuffer {
@@ -27,7 +73,25 @@ impl PeerBuffer {
             volume: 1.0,
             muted: false,
             last_activity: std::time::Instant::now(),
+            drift: DriftCompensator::new(),
+        }
+    }
+
+    /// Next output sample, advancing `step` input samples (linear interpolation;
+    /// exactly the queued samples when `step` is 1)
+    fn next_sample(&mut self, step: f64) -> f32 {
+        let Some(&current) = self.samples.front() else {
+            return 0.0;
+        };
+        let next = self.samples.get(1).copied().unwrap_or(current);
+        let sample = current + (next - current) * self.drift.position as f32;
+
+        self.drift.position += step;
+        while self.drift.position >= 1.0 {
+            self.drift.position -= 1.0;
+            self.samples.pop_front();
         }
+        sample
     }
 }
 
@@ -36,6 +100,10 @@ pub struct AudioMixer {
     peers: HashMap<String, PeerBuffer>,
     /// Master volume (0.0 - 1.0)
     master_volume: f32,
+    /// Equalizer of the mixed output
+    equalizer: Equalizer,
+    /// Compressor and limiter, after the master volume
+    dynamics: Dynamics,
 }
 
 impl AudioMixer {
@@ -43,6 +111,8 @@ impl AudioMixer {
         Self {
             peers: HashMap::new(),
             master_volume: 1.0,
+            equalizer: Equalizer::default(),
+            dynamics: Dynamics::default(),
         }
     }
 
@@ -92,24 +162,28 @@ impl AudioMixer {
                 continue;
             }
 
+            // Slightly faster or slower than real time to absorb clock drift
+            let step = buffer.drift.step(buffer.samples.len());
+
             // Check if we have enough samples (jitter buffer)
-            if buffer.samples.len() < SAMPLES_PER_FRAME {
+            if (buffer.samples.len() as f64) < SAMPLES_PER_FRAME as f64 * step {
                 // Not enough samples yet - skip this peer for now
                 // This provides jitter buffering
                 continue;
             }
 
             // Mix this peer's samples
-            for i in 0..output.len().min(SAMPLES_PER_FRAME) {
-                if let Some(sample) = buffer.samples.pop_front() {
-                    output[i] += sample * buffer.volume * norm_factor;
-                }
+            let gain = buffer.volume * norm_factor;
+            for out in output.iter_mut().take(SAMPLES_PER_FRAME) {
+                *out += buffer.next_sample(step) * gain;
             }
         }
 
-        // Apply master volume and clamp
+        self.equalizer.process_in_place(output);
+
+        // Apply master volume, compress and limit
         for sample in output.iter_mut() {
-            *sample = (*sample * self.master_volume).clamp(-1.0, 1.0);
+            *sample = self.dynamics.process(*sample * self.master_volume);
         }
     }
 
@@ -132,6 +206,16 @@ impl AudioMixer {
         self.master_volume = volume.clamp(0.0, 1.0);
     }
 
+    /// Set the equalizer of the mixed output
+    pub fn set_equalizer(&mut self, settings: EqSettings) {
+        self.equalizer.set_settings(settings);
+    }
+
+    /// Set the compressor of the mixed output
+    pub fn set_dynamics(&mut self, settings: DynamicsSettings) {
+        self.dynamics.set_settings(settings);
+    }
+
     /// Get master volume
     pub fn get_master_volume(&self) -> f32 {
         self.master_volume
@@ -185,3 +269,27 @@ impl Default for AudioMixer {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_compensation_holds_buffer_depth() {
+        let mut mixer = AudioMixer::new();
+        let mut output = vec![0.0f32; SAMPLES_PER_FRAME];
+
+        // The remote clock runs 0.3% fast: uncompensated, the buffer would grow
+        // ~3 samples every frame until it is trimmed at its cap
+        let per_frame = SAMPLES_PER_FRAME * 1003 / 1000;
+        for _ in 0..3000 {
+            mixer.add_peer_samples("fast", vec![0.1; per_frame]);
+            mixer.mix_into(&mut output);
+            assert!(output.iter().all(|&s| s > 0.0), "playback starved");
+        }
+
+        let depth = mixer.peers["fast"].drift.depth;
+        let cap = (JITTER_BUFFER_SAMPLES * 2) as f64;
+        assert!(depth > TARGET_DEPTH_SAMPLES && depth < cap - DEPTH_DEADBAND_SAMPLES);
+    }
+}
@@ -1,25 +1,139 @@
+//! Audio
+//! The room policy and shared constants are always built; the capture, codec
+//! and playback pipeline needs the `audio` feature.
+
+#[cfg(feature = "audio")]
+mod active_speaker;
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+mod auto_mute;
+#[cfg(feature = "audio")]
+mod backend;
+#[cfg(feature = "audio")]
+mod call_tap;
+#[cfg(feature = "audio")]
 mod capture;
+#[cfg(feature = "audio")]
+mod capture_worker;
+#[cfg(feature = "audio")]
+mod crossover;
+#[cfg(feature = "audio")]
 mod denoise;
+#[cfg(feature = "audio")]
+mod device_config;
+#[cfg(feature = "audio")]
+mod drop_queue;
+#[cfg(feature = "audio")]
+mod dsp;
+#[cfg(feature = "audio")]
+mod dynamics;
+#[cfg(feature = "audio")]
 mod encoder;
+#[cfg(feature = "audio")]
+mod fec;
+#[cfg(feature = "audio")]
+mod equalizer;
+#[cfg(feature = "audio")]
+mod level_meter;
+#[cfg(feature = "audio")]
+mod mic_check;
+#[cfg(feature = "audio")]
 mod mixer;
+#[cfg(feature = "audio")]
 mod playback;
+#[cfg(feature = "audio")]
+mod permission;
+mod policy;
+#[cfg(feature = "audio")]
 mod realtime;
+#[cfg(feature = "audio")]
+mod rt_priority;
+#[cfg(feature = "audio")]
+mod sidetone;
+#[cfg(feature = "audio")]
+mod sound_scheme;
+#[cfg(feature = "audio")]
+mod stream_thread;
+#[cfg(feature = "audio")]
 mod streaming;
+#[cfg(feature = "transcription")]
+mod transcription;
+#[cfg(feature = "audio")]
+mod tts;
+#[cfg(feature = "audio")]
+mod voice_message;
+#[cfg(feature = "audio")]
+mod watchdog;
 
-pub use encoder::{OpusDecoder, OpusEncoder};
+pub use auto_mute::AutoMuteReason;
+#[cfg(feature = "audio")]
+pub use auto_mute::AutoMuteSettings;
+#[cfg(feature = "audio")]
+#[allow(unused_imports)]
+pub use backend::{AudioBackend, CpalBackend, MockBackend, MockSignal, StreamFormat};
+#[cfg(feature = "audio")]
+pub use call_tap::CallTap;
+#[cfg(feature = "bench")]
+pub(crate) use dsp::{downmix_into, rms};
+#[cfg(feature = "bench")]
+pub use dsp::Resampler;
+#[cfg(feature = "audio")]
+pub use denoise::{DenoiseBackend, RnnoiseModel};
+#[cfg(feature = "audio")]
+pub use device_config::{
+    device_configs, load_device_configs, set_device_config, DeviceConfig, DeviceConfigs,
+    SupportedConfig,
+};
+#[cfg(feature = "audio")]
+pub use dynamics::DynamicsSettings;
+#[cfg(feature = "audio")]
+pub use encoder::{EncoderOptions, OpusDecoder, OpusEncoder};
+#[cfg(feature = "audio")]
+pub use equalizer::{EqPreset, EqSettings, EQ_BANDS, MAX_EQ_GAIN_DB};
+#[cfg(feature = "audio")]
+pub use level_meter::{level_event_rate, set_level_event_rate, MAX_LEVEL_EVENT_RATE};
+#[cfg(feature = "audio")]
+pub use mic_check::{MicCheckResult, MIC_CHECK_DURATION};
+#[cfg(feature = "audio")]
+pub use permission::MicPermission;
+pub use policy::AudioPolicy;
+#[cfg(feature = "audio")]
 pub use realtime::RealtimeCapture;
-pub use streaming::{AudioStreamingService, AudioPacket};
+#[cfg(feature = "audio")]
+pub use tts::{synthesize, MAX_TTS_RATE, MIN_TTS_RATE};
+#[cfg(feature = "transcription")]
+pub use transcription::{Caption, Transcriber, TranscriptionConfig, TranscriptionStatus};
+#[cfg(feature = "audio")]
+pub use sidetone::SidetoneSettings;
+#[cfg(feature = "audio")]
+pub use sound_scheme::{Sound, SoundCues, SoundEvent, SoundScheme};
+#[cfg(feature = "audio")]
+pub use streaming::{
+    AudioPacket, AudioSendStats, AudioStreamingService, DeviceLost, PEER_AUDIO_TIMEOUT,
+    PLAYBACK_BUFFER_FRAMES,
+};
+#[cfg(feature = "audio")]
+pub use voice_message::{
+    VoiceClip, VoiceMessageAssembler, VoiceMessageInfo, MAX_VOICE_MESSAGE_DURATION,
+    MIN_VOICE_MESSAGE_DURATION,
+};
+#[cfg(feature = "audio")]
+pub use watchdog::{StallDetector, StreamKind, STALL_TIMEOUT, WATCHDOG_INTERVAL};
 
+#[cfg(feature = "audio")]
 #[allow(dead_code)]
 pub use capture::AudioCapture;
+#[cfg(feature = "audio")]
 #[allow(dead_code, unused_imports)]
 pub use denoise::{AudioDenoiser, SharedDenoiser};
+#[cfg(feature = "audio")]
 #[allow(dead_code)]
 pub use mixer::AudioMixer;
+#[cfg(feature = "audio")]
 #[allow(dead_code)]
 pub use playback::AudioPlayback;
-#[allow(dead_code, unused_imports)]
-pub use realtime::AudioLevelEvent;
+
+#[cfg(feature = "audio")]
+pub use permission::{request as request_mic_permission, status as mic_permission_status};
 
 /// Sample rate for all audio operations (48kHz is Opus native)
 pub const SAMPLE_RATE: u32 = 48000;
@@ -0,0 +1,92 @@
+//! Microphone permission
+//! macOS gates the mic behind AVCaptureDevice authorization: without it, capture
+//! still starts but only yields silence. Other platforms have no such gate.
+
+use serde::Serialize;
+
+/// Microphone authorization status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicPermission {
+    Granted,
+    Denied,
+    /// Blocked by parental controls or an MDM profile, the user can't change it
+    Restricted,
+    /// The user has not been asked yet
+    NotDetermined,
+}
+
+impl MicPermission {
+    pub fn is_granted(self) -> bool {
+        self == MicPermission::Granted
+    }
+}
+
+/// Current authorization status
+#[cfg(target_os = "macos")]
+pub fn status() -> MicPermission {
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+
+    let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+        return MicPermission::NotDetermined;
+    };
+    match unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) } {
+        AVAuthorizationStatus::Authorized => MicPermission::Granted,
+        AVAuthorizationStatus::Denied => MicPermission::Denied,
+        AVAuthorizationStatus::Restricted => MicPermission::Restricted,
+        _ => MicPermission::NotDetermined,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn status() -> MicPermission {
+    MicPermission::Granted
+}
+
+/// Show the system prompt if the user has not been asked yet, and return the
+/// resulting status (returns immediately once a decision was made)
+#[cfg(target_os = "macos")]
+pub async fn request() -> MicPermission {
+    let current = status();
+    if current != MicPermission::NotDetermined {
+        return current;
+    }
+    let Some(rx) = prompt() else {
+        return current;
+    };
+
+    match rx.await {
+        Ok(true) => MicPermission::Granted,
+        Ok(false) => MicPermission::Denied,
+        Err(_) => status(),
+    }
+}
+
+/// Ask for access; the receiver gets the user's answer
+/// The completion block is not Send, so it is built and dropped here rather
+/// than held across the await in `request`
+#[cfg(target_os = "macos")]
+fn prompt() -> Option<tokio::sync::oneshot::Receiver<bool>> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVCaptureDevice, AVMediaTypeAudio};
+
+    let media_type = (unsafe { AVMediaTypeAudio })?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    let handler = RcBlock::new(move |granted: Bool| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(granted.as_bool());
+        }
+    });
+    // AVFoundation copies the block, our reference can go
+    unsafe {
+        AVCaptureDevice::requestAccessForMediaType_completionHandler(media_type, &handler);
+    }
+    Some(rx)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub async fn request() -> MicPermission {
+    MicPermission::Granted
+}
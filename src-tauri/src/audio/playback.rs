@@ -7,6 +7,7 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 
 use super::{CHANNELS, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
 
 /// Audio playback to speakers using cpal
 pub struct AudioPlayback {
@@ -18,7 +19,7 @@ pub struct AudioPlayback {
 }
 
 impl AudioPlayback {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> AppResult<Self> {
         let host = cpal::default_host();
 
         Ok(Self {
@@ -30,11 +31,10 @@ impl AudioPlayback {
     }
 
     /// List available output devices
-    pub fn list_devices() -> Result<Vec<String>, String> {
+    pub fn list_devices() -> AppResult<Vec<String>> {
         let host = cpal::default_host();
         let devices = host
-            .output_devices()
-            .map_err(|e| format!("Failed to enumerate output devices: {}", e))?;
+            .output_devices()?;
 
         let names: Vec<String> = devices
             .filter_map(|d| d.name().ok())
@@ -44,16 +44,15 @@ impl AudioPlayback {
     }
 
     /// Select output device by name (None for default)
-    pub fn select_device(&mut self, name: Option<&str>) -> Result<(), String> {
+    pub fn select_device(&mut self, name: Option<&str>) -> AppResult<()> {
         self.device = match name {
             Some(device_name) => {
-                let devices = self.host.output_devices()
-                    .map_err(|e| format!("Failed to enumerate devices: {}", e))?;
+                let devices = self.host.output_devices()?;
 
                 devices
                     .filter(|d| d.name().map(|n| n == device_name).unwrap_or(false))
                     .next()
-                    .ok_or_else(|| format!("Device '{}' not found", device_name))?
+                    .ok_or_else(|| AppError::DeviceNotFound(device_name.to_string()))?
                     .into()
             }
             None => self.host.default_output_device(),
@@ -64,13 +63,13 @@ impl AudioPlayback {
 
     /// Start playback
     /// Callback should return samples to play
-    pub fn start<F>(&mut self, get_samples: F) -> Result<(), String>
+    pub fn start<F>(&mut self, get_samples: F) -> AppResult<()>
     where
         F: Fn() -> Vec<f32> + Send + 'static,
     {
         let device = self.device.take()
             .or_else(|| self.host.default_output_device())
-            .ok_or("No output device available")?;
+            .ok_or_else(|| AppError::DeviceNotFound("No output device available".to_string()))?;
 
         tracing::info!("Using output device: {}", device.name().unwrap_or_default());
 
@@ -122,9 +121,9 @@ impl AudioPlayback {
                 tracing::error!("Audio output error: {}", err);
             },
             None,
-        ).map_err(|e| format!("Failed to build output stream: {}", e))?;
+        )?;
 
-        stream.play().map_err(|e| format!("Failed to start stream: {}", e))?;
+        stream.play()?;
 
         self.device = Some(device);
         self.stream = Some(stream);
@@ -0,0 +1,53 @@
+//! Room audio policy
+//! Defined by the host, advertised over signaling and enforced by each peer's capture pipeline
+
+use serde::{Deserialize, Serialize};
+
+/// Lowest bitrate a policy can impose (Opus stays intelligible above ~6kbps)
+const MIN_POLICY_BITRATE: i32 = 6000;
+
+/// Audio constraints imposed by the room host
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AudioPolicy {
+    /// Only transmit while the push-to-talk key is held
+    pub force_push_to_talk: bool,
+    /// Maximum Opus bitrate in bits/s (None = no cap)
+    pub max_bitrate: Option<i32>,
+    /// Noise suppression cannot be disabled
+    pub require_noise_suppression: bool,
+}
+
+impl AudioPolicy {
+    /// Bitrate to use given the locally requested one
+    pub fn effective_bitrate(&self, requested: i32) -> i32 {
+        match self.max_bitrate {
+            Some(max) => requested.min(max.max(MIN_POLICY_BITRATE)),
+            None => requested,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_bitrate() {
+        let policy = AudioPolicy {
+            max_bitrate: Some(24000),
+            ..Default::default()
+        };
+        assert_eq!(policy.effective_bitrate(64000), 24000);
+        assert_eq!(policy.effective_bitrate(16000), 16000);
+        assert_eq!(AudioPolicy::default().effective_bitrate(64000), 64000);
+    }
+
+    #[test]
+    fn test_effective_bitrate_floor() {
+        let policy = AudioPolicy {
+            max_bitrate: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(policy.effective_bitrate(64000), MIN_POLICY_BITRATE);
+    }
+}
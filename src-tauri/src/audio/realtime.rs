@@ -3,26 +3,20 @@
 //! Real-time audio capture with Tauri events
 //! Captures microphone input and emits audio level events to the frontend
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Host, SampleFormat, Stream};
 use parking_lot::Mutex;
-use serde::Serialize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
-
-use super::denoise::SharedDenoiser;
-
-/// Event payload for audio level updates
-#[derive(Clone, Serialize)]
-pub struct AudioLevelEvent {
-    /// Audio level from 0.0 to 1.0
-    pub level: f32,
-    /// Whether the user is speaking (level > threshold)
-    pub is_speaking: bool,
-    /// Raw RMS value
-    pub rms: f32,
-}
+use std::time::Instant;
+use tauri::AppHandle;
+
+use super::backend::{AudioBackend, CpalBackend, InputCallback};
+use super::denoise::{DenoiseBackend, RnnoiseModel, SharedDenoiser};
+use super::dsp;
+use super::level_meter::LevelThrottle;
+use super::stream_thread::{ActiveStream, DEVICE_SETTLE_DELAY};
+use crate::error::AppResult;
+use crate::events::{self, AudioLevelEvent};
+use crate::perf::{self, Component};
 
 /// Threshold for "speaking" detection
 const SPEAKING_THRESHOLD: f32 = 0.02;
@@ -30,28 +24,10 @@ const SPEAKING_THRESHOLD: f32 = 0.02;
 /// Minimum samples before processing (~20ms at 48kHz)
 const MIN_SAMPLES_FOR_PROCESSING: usize = 960;
 
-/// Calculate RMS (Root Mean Square) of audio samples
-fn calculate_rms(samples: &[f32]) -> f32 {
-    if samples.is_empty() {
-        return 0.0;
-    }
-    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
-    (sum_squares / samples.len() as f32).sqrt()
-}
-
-/// Convert RMS to normalized level (0.0 to 1.0)
-fn rms_to_level(rms: f32) -> f32 {
-    // Convert to dB scale and normalize
-    // -60dB to 0dB range
-    let db = 20.0 * rms.max(1e-10).log10();
-    let normalized = (db + 60.0) / 60.0;
-    normalized.clamp(0.0, 1.0)
-}
-
 /// Real-time audio capture manager
 pub struct RealtimeCapture {
-    host: Host,
-    stream: Arc<Mutex<Option<Stream>>>,
+    backend: Arc<dyn AudioBackend>,
+    stream: Arc<Mutex<Option<ActiveStream>>>,
     is_capturing: Arc<AtomicBool>,
     is_muted: Arc<AtomicBool>,
     current_level: Arc<Mutex<f32>>,
@@ -62,8 +38,13 @@ pub struct RealtimeCapture {
 
 impl RealtimeCapture {
     pub fn new() -> Self {
+        Self::with_backend(Arc::new(CpalBackend))
+    }
+
+    /// Capture running on another backend (e.g. `MockBackend` in tests)
+    pub fn with_backend(backend: Arc<dyn AudioBackend>) -> Self {
         Self {
-            host: cpal::default_host(),
+            backend,
             stream: Arc::new(Mutex::new(None)),
             is_capturing: Arc::new(AtomicBool::new(false)),
             is_muted: Arc::new(AtomicBool::new(true)),
@@ -85,9 +66,20 @@ impl RealtimeCapture {
         self.denoiser.is_enabled()
     }
 
+    /// Select the noise suppression backend
+    pub fn set_denoise_backend(&self, backend: DenoiseBackend) -> AppResult<()> {
+        self.denoiser.set_backend(backend)
+    }
+
+    /// Use a custom RNNoise model, or the built-in one with None
+    pub fn set_denoise_model(&self, model: Option<RnnoiseModel>) {
+        self.denoiser.set_model(model);
+    }
+
     /// Set the input device by name. Pass None for default device.
-    /// If currently capturing, restarts with the new device.
-    pub fn set_input_device(&self, device_name: Option<String>) -> Result<(), String> {
+    /// If currently capturing, restarts with the new device once the old one
+    /// had time to settle (awaited, the calling thread is not blocked).
+    pub async fn set_input_device(&self, device_name: Option<String>) -> AppResult<()> {
         tracing::info!("set_input_device called with: {:?}", device_name);
 
         let was_capturing = self.is_capturing.load(Ordering::SeqCst);
@@ -106,8 +98,8 @@ impl RealtimeCapture {
             *self.current_level.lock() = 0.0;
             tracing::info!("Stream stopped");
 
-            // Small delay to let the audio system settle
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            // Let the audio system settle
+            tokio::time::sleep(DEVICE_SETTLE_DELAY).await;
         }
 
         // Get the app handle before starting
@@ -137,30 +129,8 @@ impl RealtimeCapture {
         self.selected_device.lock().clone()
     }
 
-    /// Get a device by name, or the default if None
-    fn get_device(&self, name: Option<&str>) -> Result<cpal::Device, String> {
-        match name {
-            Some(device_name) => {
-                let devices = self.host.input_devices()
-                    .map_err(|e| format!("Failed to enumerate devices: {}", e))?;
-
-                for device in devices {
-                    if let Ok(n) = device.name() {
-                        if n == device_name {
-                            return Ok(device);
-                        }
-                    }
-                }
-                Err(format!("Device '{}' not found", device_name))
-            }
-            None => self.host
-                .default_input_device()
-                .ok_or_else(|| "No default input device available".to_string()),
-        }
-    }
-
     /// Start capturing and emitting audio level events
-    pub fn start(&self, app_handle: AppHandle) -> Result<(), String> {
+    pub fn start(&self, app_handle: AppHandle) -> AppResult<()> {
         if self.is_capturing.load(Ordering::SeqCst) {
             return Ok(()); // Already capturing
         }
@@ -170,26 +140,18 @@ impl RealtimeCapture {
 
         // Get the selected device or default
         let selected = self.selected_device.lock().clone();
-        let device = self.get_device(selected.as_deref())?;
-
-        let device_name = device.name().unwrap_or_default();
+        let (device_name, format) = self.backend.input_format(selected.as_deref())?;
         tracing::info!("Starting audio capture on: {}", device_name);
 
-        // Use the device's default configuration instead of forcing a specific one
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| format!("Failed to get default input config: {}", e))?;
-
+        // The backend opens the device in its default configuration
         tracing::info!(
-            "Using device config: {} Hz, {} channels, {:?}",
-            supported_config.sample_rate().0,
-            supported_config.channels(),
-            supported_config.sample_format()
+            "Using device config: {} Hz, {} channels",
+            format.sample_rate,
+            format.channels
         );
 
-        let config = supported_config.config();
-        let sample_rate = config.sample_rate.0;
-        let channels = config.channels as usize;
+        let sample_rate = format.sample_rate;
+        let channels = format.channels as usize;
 
         // Configure denoiser with the device's sample rate
         self.denoiser.set_sample_rate(sample_rate);
@@ -205,96 +167,32 @@ impl RealtimeCapture {
 
         // Accumulator for samples (mono-converted)
         let sample_buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::with_capacity(samples_per_frame * 2)));
-
-        let err_fn = |err| {
-            tracing::error!("Audio capture error: {}", err);
-        };
-
-        // Build the stream based on the sample format
-        let stream = match supported_config.sample_format() {
-            SampleFormat::F32 => {
-                device.build_input_stream(
-                    &config,
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        process_audio_data(
-                            data,
-                            channels,
-                            samples_per_frame,
-                            &sample_buffer,
-                            &is_muted,
-                            &current_level,
-                            &app,
-                            &denoiser,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )
-            }
-            SampleFormat::I16 => {
-                let sample_buffer = Arc::new(Mutex::new(Vec::with_capacity(samples_per_frame * 2)));
-                let is_muted = self.is_muted.clone();
-                let current_level = self.current_level.clone();
-                let app = app_handle.clone();
-                let denoiser = self.denoiser.clone();
-
-                device.build_input_stream(
-                    &config,
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        // Convert i16 to f32
-                        let float_data: Vec<f32> = data.iter()
-                            .map(|&s| s as f32 / i16::MAX as f32)
-                            .collect();
-                        process_audio_data(
-                            &float_data,
-                            channels,
-                            samples_per_frame,
-                            &sample_buffer,
-                            &is_muted,
-                            &current_level,
-                            &app,
-                            &denoiser,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )
-            }
-            SampleFormat::U16 => {
-                let sample_buffer = Arc::new(Mutex::new(Vec::with_capacity(samples_per_frame * 2)));
-                let is_muted = self.is_muted.clone();
-                let current_level = self.current_level.clone();
-                let app = app_handle.clone();
-                let denoiser = self.denoiser.clone();
-
-                device.build_input_stream(
-                    &config,
-                    move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                        // Convert u16 to f32
-                        let float_data: Vec<f32> = data.iter()
-                            .map(|&s| (s as f32 / u16::MAX as f32) * 2.0 - 1.0)
-                            .collect();
-                        process_audio_data(
-                            &float_data,
-                            channels,
-                            samples_per_frame,
-                            &sample_buffer,
-                            &is_muted,
-                            &current_level,
-                            &app,
-                            &denoiser,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )
-            }
-            format => {
-                return Err(format!("Unsupported sample format: {:?}", format));
-            }
-        }.map_err(|e| format!("Failed to build input stream: {}", e))?;
-
-        stream.play().map_err(|e| format!("Failed to start audio stream: {}", e))?;
+        let mut level_throttle = LevelThrottle::new();
+
+        let on_data: InputCallback = Box::new(move |data: &[f32]| {
+            process_audio_data(
+                data,
+                channels,
+                samples_per_frame,
+                &sample_buffer,
+                &mut level_throttle,
+                &is_muted,
+                &current_level,
+                &app,
+                &denoiser,
+            );
+        });
+
+        let backend = self.backend.clone();
+        let stream = ActiveStream::open(move || {
+            backend.start_input(
+                selected.as_deref(),
+                on_data,
+                Box::new(|err| {
+                    tracing::error!("Audio capture error: {}", err);
+                }),
+            )
+        })?;
 
         *self.stream.lock() = Some(stream);
         self.is_capturing.store(true, Ordering::SeqCst);
@@ -341,11 +239,13 @@ impl RealtimeCapture {
 }
 
 /// Process audio data and emit events
+#[allow(clippy::too_many_arguments)]
 fn process_audio_data(
     data: &[f32],
     channels: usize,
     samples_per_frame: usize,
     sample_buffer: &Arc<Mutex<Vec<f32>>>,
+    level_throttle: &mut LevelThrottle,
     is_muted: &Arc<AtomicBool>,
     current_level: &Arc<Mutex<f32>>,
     app: &AppHandle,
@@ -354,41 +254,39 @@ fn process_audio_data(
     let mut buffer = sample_buffer.lock();
 
     // Convert to mono if needed by averaging channels
-    if channels > 1 {
-        for chunk in data.chunks(channels) {
-            let mono: f32 = chunk.iter().sum::<f32>() / channels as f32;
-            buffer.push(mono);
-        }
-    } else {
-        buffer.extend_from_slice(data);
-    }
+    dsp::downmix_into(data, channels, &mut buffer);
 
     // Process when we have enough samples
     while buffer.len() >= samples_per_frame {
         let samples: Vec<f32> = buffer.drain(..samples_per_frame).collect();
 
         // Apply noise reduction if enabled
-        let processed_samples = denoiser.process(&samples);
+        let processed_samples = {
+            let _span = perf::span(Component::Denoise);
+            denoiser.process(&samples)
+        };
 
         // Calculate audio level from processed (denoised) samples
-        let rms = calculate_rms(&processed_samples);
+        let rms = dsp::rms(&processed_samples);
         let level = if is_muted.load(Ordering::SeqCst) {
             0.0 // Show 0 when muted
         } else {
-            rms_to_level(rms)
+            dsp::rms_to_level(rms)
         };
 
         // Update current level
         *current_level.lock() = level;
 
-        // Emit event to frontend
+        // Emit event to frontend (coalesced to the UI rate)
         let event = AudioLevelEvent {
             level,
             is_speaking: !is_muted.load(Ordering::SeqCst) && rms > SPEAKING_THRESHOLD,
             rms,
         };
 
-        let _ = app.emit("audio-level", event);
+        if let Some(event) = level_throttle.observe(event, Instant::now()) {
+            let _ = events::emit(app, &event);
+        }
     }
 }
 
@@ -397,7 +295,3 @@ impl Default for RealtimeCapture {
         Self::new()
     }
 }
-
-// Safety: Stream is managed through Arc<Mutex> and atomic flags
-unsafe impl Send for RealtimeCapture {}
-unsafe impl Sync for RealtimeCapture {}
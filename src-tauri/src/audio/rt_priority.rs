@@ -0,0 +1,84 @@
+//! Real-time scheduling for the capture worker
+//! Denoise and Opus encoding must finish every 20ms; at normal priority a busy
+//! machine (a build, the screen encoder) starves the worker and the ring buffer
+//! overruns, which is heard as crackles. The worker asks the OS to treat it as
+//! audio work:
+//! - Windows: MMCSS "Pro Audio" task at high priority
+//! - macOS: Mach time-constraint policy sized on the frame period, the
+//!   scheduling class audio workgroups build on (joining the device's own
+//!   workgroup needs its CoreAudio id, which cpal doesn't expose)
+//! - elsewhere: normal priority
+//!
+//! This is best effort: a refusal is logged and the worker runs as before.
+
+/// Real-time scheduling of the current thread, reverted when dropped
+pub struct RealtimeGuard {
+    #[cfg(windows)]
+    task: Option<windows::Win32::Foundation::HANDLE>,
+    #[cfg(target_os = "macos")]
+    handle: Option<audio_thread_priority::RtPriorityHandle>,
+}
+
+/// Promote the current thread for periods of `frame_samples` at `sample_rate`
+#[cfg(windows)]
+pub fn promote_current_thread(_frame_samples: u32, _sample_rate: u32) -> RealtimeGuard {
+    use windows::core::w;
+    use windows::Win32::System::Threading::{
+        AvSetMmThreadCharacteristicsW, AvSetMmThreadPriority, AVRT_PRIORITY_HIGH,
+    };
+
+    let mut task_index = 0u32;
+    // SAFETY: the task name is a static wide string and `task_index` outlives the call
+    let task = match unsafe { AvSetMmThreadCharacteristicsW(w!("Pro Audio"), &mut task_index) } {
+        Ok(task) => task,
+        Err(e) => {
+            tracing::warn!("MMCSS registration refused, keeping normal priority: {}", e);
+            return RealtimeGuard { task: None };
+        }
+    };
+    // SAFETY: `task` was just returned by AvSetMmThreadCharacteristicsW
+    match unsafe { AvSetMmThreadPriority(task, AVRT_PRIORITY_HIGH) } {
+        Ok(()) => tracing::info!("Capture worker registered as MMCSS \"Pro Audio\""),
+        Err(e) => tracing::warn!("Failed to raise the MMCSS priority: {}", e),
+    }
+    RealtimeGuard { task: Some(task) }
+}
+
+/// Promote the current thread for periods of `frame_samples` at `sample_rate`
+#[cfg(target_os = "macos")]
+pub fn promote_current_thread(frame_samples: u32, sample_rate: u32) -> RealtimeGuard {
+    match audio_thread_priority::promote_current_thread_to_real_time(frame_samples, sample_rate) {
+        Ok(handle) => {
+            tracing::info!("Capture worker running with the real-time policy");
+            RealtimeGuard {
+                handle: Some(handle),
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Real-time policy refused, keeping normal priority: {}", e);
+            RealtimeGuard { handle: None }
+        }
+    }
+}
+
+/// Promote the current thread for periods of `frame_samples` at `sample_rate`
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn promote_current_thread(_frame_samples: u32, _sample_rate: u32) -> RealtimeGuard {
+    tracing::debug!("Real-time priority not supported here, capture worker at normal priority");
+    RealtimeGuard {}
+}
+
+impl Drop for RealtimeGuard {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        if let Some(task) = self.task.take() {
+            // SAFETY: `task` came from AvSetMmThreadCharacteristicsW on this thread
+            let _ =
+                unsafe { windows::Win32::System::Threading::AvRevertMmThreadCharacteristics(task) };
+        }
+        #[cfg(target_os = "macos")]
+        if let Some(handle) = self.handle.take() {
+            let _ = audio_thread_priority::demote_current_thread_from_real_time(handle);
+        }
+    }
+}
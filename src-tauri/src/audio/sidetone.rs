@@ -0,0 +1,165 @@
+//! Sidetone
+//! A copy of the processed mic, played back locally at a low level so headset
+//! users hear themselves as on a phone. Frames go straight from the capture
+//! worker to the playback callback through a short buffer: just enough to
+//! bridge the 20ms capture frames, and trimmed so the delay never builds up.
+
+use parking_lot::{Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::SAMPLES_PER_FRAME;
+use crate::error::{AppError, AppResult};
+
+/// Samples buffered before playing (~30ms), so the next frame arrives in time
+const PREFILL: usize = SAMPLES_PER_FRAME * 3 / 2;
+
+/// Samples kept at most (~60ms): older ones are dropped when the capture
+/// clock runs ahead of the playback clock
+const MAX_BUFFERED: usize = SAMPLES_PER_FRAME * 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidetoneSettings {
+    pub enabled: bool,
+    /// Gain applied to the mic, 0.0 to 1.0
+    pub volume: f32,
+}
+
+impl Default for SidetoneSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: 0.3,
+        }
+    }
+}
+
+impl SidetoneSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(AppError::InvalidArgument(
+                "Sidetone volume must be between 0 and 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Processed mic samples waiting to be played
+#[derive(Default)]
+pub struct SidetoneBuffer {
+    samples: VecDeque<f32>,
+    /// Playing; false while refilling after running dry
+    primed: bool,
+    gain: f32,
+}
+
+impl SidetoneBuffer {
+    /// Next sample to mix into the output (silence while refilling)
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.primed {
+            if self.samples.len() < PREFILL {
+                return 0.0;
+            }
+            self.primed = true;
+        }
+        match self.samples.pop_front() {
+            Some(sample) => sample * self.gain,
+            None => {
+                self.primed = false;
+                0.0
+            }
+        }
+    }
+}
+
+pub struct Sidetone {
+    enabled: AtomicBool,
+    /// f32 bits of the volume
+    volume: AtomicU32,
+    buffer: Mutex<SidetoneBuffer>,
+}
+
+impl Default for Sidetone {
+    fn default() -> Self {
+        let sidetone = Self {
+            enabled: AtomicBool::new(false),
+            volume: AtomicU32::new(0),
+            buffer: Mutex::new(SidetoneBuffer::default()),
+        };
+        sidetone.set_settings(SidetoneSettings::default());
+        sidetone
+    }
+}
+
+impl Sidetone {
+    pub fn set_settings(&self, settings: SidetoneSettings) {
+        self.volume
+            .store(settings.volume.to_bits(), Ordering::Relaxed);
+        let mut buffer = self.buffer.lock();
+        buffer.samples.clear();
+        buffer.primed = false;
+        if settings.enabled {
+            buffer.samples.reserve_exact(MAX_BUFFERED);
+        }
+        self.enabled.store(settings.enabled, Ordering::SeqCst);
+    }
+
+    pub fn settings(&self) -> SidetoneSettings {
+        SidetoneSettings {
+            enabled: self.enabled.load(Ordering::Relaxed),
+            volume: f32::from_bits(self.volume.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Copy processed capture samples (48kHz mono)
+    pub(crate) fn push(&self, samples: &[f32]) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut buffer = self.buffer.lock();
+        buffer.samples.extend(samples);
+        let excess = buffer.samples.len().saturating_sub(MAX_BUFFERED);
+        buffer.samples.drain(..excess);
+    }
+
+    /// Buffer held for a whole playback callback (None when disabled)
+    pub(crate) fn reader(&self) -> Option<MutexGuard<'_, SidetoneBuffer>> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut buffer = self.buffer.lock();
+        buffer.gain = f32::from_bits(self.volume.load(Ordering::Relaxed));
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_after_prefill_and_bounds_the_delay() {
+        let sidetone = Sidetone::default();
+        sidetone.set_settings(SidetoneSettings {
+            enabled: true,
+            volume: 0.5,
+        });
+
+        sidetone.push(&[1.0; SAMPLES_PER_FRAME]);
+        assert_eq!(sidetone.reader().unwrap().next_sample(), 0.0);
+
+        sidetone.push(&[1.0; SAMPLES_PER_FRAME]);
+        let mut reader = sidetone.reader().unwrap();
+        assert_eq!(reader.next_sample(), 0.5);
+        drop(reader);
+
+        // A capture running ahead doesn't add delay
+        for _ in 0..10 {
+            sidetone.push(&[1.0; SAMPLES_PER_FRAME]);
+        }
+        assert_eq!(sidetone.buffer.lock().samples.len(), MAX_BUFFERED);
+    }
+}
@@ -0,0 +1,245 @@
+//! Sound scheme
+//! Audible cues for room events, for users who can't keep an eye on the
+//! window: each event plays a short synthesized tone or a custom WAV file on
+//! the playback output. Cues are rendered once, when the scheme changes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::tts::decode_wav;
+use super::SAMPLE_RATE;
+use crate::error::{AppError, AppResult};
+
+/// Longest custom file played for a cue
+const MAX_CUE_DURATION_MS: usize = 5000;
+/// Largest custom file read (about 5s of 48kHz stereo)
+const MAX_CUE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Tone notes range
+const MAX_TONE_NOTES: usize = 4;
+const TONE_FREQUENCIES: std::ops::RangeInclusive<f32> = 100.0..=4000.0;
+const TONE_NOTE_MS: std::ops::RangeInclusive<u32> = 30..=500;
+/// Fade at both ends of each note, so tones don't click
+const TONE_FADE_MS: usize = 5;
+
+/// Event a cue is played for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundEvent {
+    PeerJoined,
+    PeerLeft,
+    Muted,
+    Unmuted,
+    ChatMessage,
+}
+
+impl SoundEvent {
+    pub const ALL: [SoundEvent; 5] = [
+        SoundEvent::PeerJoined,
+        SoundEvent::PeerLeft,
+        SoundEvent::Muted,
+        SoundEvent::Unmuted,
+        SoundEvent::ChatMessage,
+    ];
+
+    /// Built-in cue: rising for arrivals and unmute, falling for departures and
+    /// mute, a single high note for messages
+    fn default_sound(self) -> Sound {
+        let notes = match self {
+            Self::PeerJoined => vec![523.3, 784.0],
+            Self::PeerLeft => vec![784.0, 523.3],
+            Self::Muted => vec![440.0, 330.0],
+            Self::Unmuted => vec![330.0, 440.0],
+            Self::ChatMessage => vec![1046.5],
+        };
+        Sound::Tone { notes, note_ms: 90 }
+    }
+}
+
+/// What an event plays
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Sound {
+    Silent,
+    /// Sine notes played one after the other, frequencies in Hz
+    Tone {
+        notes: Vec<f32>,
+        note_ms: u32,
+    },
+    /// 16-bit PCM WAV file, cut after 5s
+    File {
+        path: PathBuf,
+    },
+}
+
+impl Sound {
+    fn validate(&self) -> AppResult<()> {
+        match self {
+            Sound::Silent => Ok(()),
+            Sound::Tone { notes, note_ms } => {
+                if notes.is_empty() || notes.len() > MAX_TONE_NOTES {
+                    return Err(AppError::InvalidArgument(format!(
+                        "A tone has 1 to {} notes",
+                        MAX_TONE_NOTES
+                    )));
+                }
+                if !notes.iter().all(|f| TONE_FREQUENCIES.contains(f))
+                    || !TONE_NOTE_MS.contains(note_ms)
+                {
+                    return Err(AppError::InvalidArgument(
+                        "Tone notes out of range".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            Sound::File { path } => {
+                let size = std::fs::metadata(path).map(|m| m.len()).map_err(|e| {
+                    AppError::InvalidArgument(format!(
+                        "Sound file {} unreadable: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                if size > MAX_CUE_FILE_BYTES {
+                    return Err(AppError::InvalidArgument(format!(
+                        "Sound file {} is too large",
+                        path.display()
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 48kHz mono samples of the cue
+    fn render(&self) -> AppResult<Vec<f32>> {
+        match self {
+            Sound::Silent => Ok(Vec::new()),
+            Sound::Tone { notes, note_ms } => Ok(render_tone(notes, *note_ms)),
+            Sound::File { path } => {
+                let bytes = std::fs::read(path).map_err(|e| {
+                    AppError::Audio(format!("Failed to read {}: {}", path.display(), e))
+                })?;
+                let mut samples = decode_wav(&bytes)?;
+                samples.truncate(SAMPLE_RATE as usize * MAX_CUE_DURATION_MS / 1000);
+                Ok(samples)
+            }
+        }
+    }
+}
+
+fn render_tone(notes: &[f32], note_ms: u32) -> Vec<f32> {
+    let note_len = SAMPLE_RATE as usize * note_ms as usize / 1000;
+    let fade = SAMPLE_RATE as usize * TONE_FADE_MS / 1000;
+    let mut samples = Vec::with_capacity(note_len * notes.len());
+    for &frequency in notes {
+        samples.extend((0..note_len).map(|i| {
+            let envelope = (i.min(note_len - 1 - i) as f32 / fade as f32).min(1.0);
+            (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin() * 0.5 * envelope
+        }));
+    }
+    samples
+}
+
+/// Sound scheme (persisted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundScheme {
+    pub enabled: bool,
+    /// 0 to 1
+    pub volume: f32,
+    /// Cue of each event, the built-in one when missing
+    pub sounds: HashMap<SoundEvent, Sound>,
+}
+
+impl Default for SoundScheme {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: 0.6,
+            sounds: SoundEvent::ALL
+                .iter()
+                .map(|&event| (event, event.default_sound()))
+                .collect(),
+        }
+    }
+}
+
+impl SoundScheme {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(AppError::InvalidArgument(
+                "Cue volume must be between 0 and 1".to_string(),
+            ));
+        }
+        self.sounds.values().try_for_each(Sound::validate)
+    }
+
+    pub fn sound(&self, event: SoundEvent) -> Sound {
+        self.sounds
+            .get(&event)
+            .cloned()
+            .unwrap_or_else(|| event.default_sound())
+    }
+}
+
+/// Rendered cues of a scheme
+#[derive(Debug, Default)]
+pub struct SoundCues {
+    scheme: SoundScheme,
+    cues: HashMap<SoundEvent, Arc<[f32]>>,
+}
+
+impl SoundCues {
+    /// Render every cue of `scheme`
+    /// An unreadable file falls back to the built-in cue, so one bad file
+    /// doesn't leave an event silent.
+    pub fn new(scheme: SoundScheme) -> Self {
+        let cues = SoundEvent::ALL
+            .iter()
+            .map(|&event| {
+                let samples = scheme.sound(event).render().unwrap_or_else(|e| {
+                    tracing::warn!("Sound cue for {:?} unusable: {}", event, e);
+                    event.default_sound().render().unwrap_or_default()
+                });
+                let samples: Vec<f32> = samples.iter().map(|s| s * scheme.volume).collect();
+                (event, samples.into())
+            })
+            .collect();
+        Self { scheme, cues }
+    }
+
+    pub fn scheme(&self) -> &SoundScheme {
+        &self.scheme
+    }
+
+    /// Samples to play for `event`, None when its cue is silent
+    pub fn cue(&self, event: SoundEvent) -> Option<Arc<[f32]>> {
+        self.cues.get(&event).filter(|c| !c.is_empty()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_cues_are_distinct_and_click_free() {
+        let cues = SoundCues::new(SoundScheme::default());
+
+        let joined = cues.cue(SoundEvent::PeerJoined).unwrap();
+        let left = cues.cue(SoundEvent::PeerLeft).unwrap();
+        assert_eq!(joined.len(), 2 * SAMPLE_RATE as usize * 90 / 1000);
+        assert_ne!(joined, left);
+        assert!(joined[0].abs() < 1e-3 && joined[joined.len() - 1].abs() < 1e-2);
+
+        let mut scheme = cues.scheme().clone();
+        scheme.sounds.insert(SoundEvent::ChatMessage, Sound::Silent);
+        assert!(SoundCues::new(scheme)
+            .cue(SoundEvent::ChatMessage)
+            .is_none());
+    }
+}
@@ -0,0 +1,113 @@
+//! Stream thread
+//! cpal streams are not `Send` on every platform, so they never leave the thread
+//! that opened them: every stream is opened and dropped on one dedicated thread,
+//! driven through a channel. The rest of the pipeline only holds `ActiveStream`
+//! tokens, which are `Send + Sync` and close their stream when dropped.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, OnceLock};
+use std::time::Duration;
+
+use super::backend::StreamHandle;
+use crate::error::{AppError, AppResult};
+
+/// Time a device needs after its stream is closed before it reopens reliably
+/// (awaited by device switches, never slept on)
+pub const DEVICE_SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+type OpenFn = Box<dyn FnOnce() -> AppResult<StreamHandle> + Send>;
+
+enum Command {
+    Open {
+        id: u64,
+        open: OpenFn,
+        reply: mpsc::Sender<AppResult<()>>,
+    },
+    Close {
+        id: u64,
+        reply: mpsc::Sender<()>,
+    },
+}
+
+/// Command channel of the stream thread, started on first use
+fn control() -> &'static mpsc::Sender<Command> {
+    static CONTROL: OnceLock<mpsc::Sender<Command>> = OnceLock::new();
+    CONTROL.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("audio-streams".to_string())
+            .spawn(move || run(rx))
+            .expect("Failed to spawn the audio stream thread");
+        tx
+    })
+}
+
+fn run(commands: mpsc::Receiver<Command>) {
+    let mut streams: HashMap<u64, StreamHandle> = HashMap::new();
+    for command in commands {
+        match command {
+            Command::Open { id, open, reply } => {
+                // A panicking driver must not take the other streams down with the thread
+                let result = panic::catch_unwind(AssertUnwindSafe(open)).unwrap_or_else(|_| {
+                    Err(AppError::Audio(
+                        "Audio driver panicked while opening a stream".to_string(),
+                    ))
+                });
+                let _ = reply.send(result.map(|stream| {
+                    streams.insert(id, stream);
+                }));
+            }
+            Command::Close { id, reply } => {
+                streams.remove(&id);
+                let _ = reply.send(());
+            }
+        }
+    }
+}
+
+fn thread_gone() -> AppError {
+    AppError::Internal("Audio stream thread stopped".to_string())
+}
+
+/// A stream living on the stream thread, closed when dropped
+#[derive(Debug)]
+pub struct ActiveStream {
+    id: u64,
+}
+
+impl ActiveStream {
+    /// Run `open` (an `AudioBackend::start_*` call) on the stream thread and keep
+    /// the stream there
+    pub fn open(
+        open: impl FnOnce() -> AppResult<StreamHandle> + Send + 'static,
+    ) -> AppResult<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+        let (reply, result) = mpsc::channel();
+        control()
+            .send(Command::Open {
+                id,
+                open: Box::new(open),
+                reply,
+            })
+            .map_err(|_| thread_gone())?;
+        result.recv().map_err(|_| thread_gone())??;
+        Ok(Self { id })
+    }
+}
+
+impl Drop for ActiveStream {
+    /// Returns once the stream is stopped, so a restart never overlaps the old one
+    fn drop(&mut self) {
+        let (reply, done) = mpsc::channel();
+        if control()
+            .send(Command::Close { id: self.id, reply })
+            .is_ok()
+        {
+            let _ = done.recv();
+        }
+    }
+}
@@ -3,19 +3,41 @@
 //! Audio streaming service
 //! Manages the complete audio pipeline: capture -> encode -> transmit -> receive -> decode -> playback
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Host, SampleFormat, Stream, StreamConfig};
 use parking_lot::Mutex;
 use serde::Serialize;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
 use tokio::sync::mpsc;
 
-use super::denoise::SharedDenoiser;
-use super::encoder::{OpusDecoder, OpusEncoder};
-use super::{CHANNELS, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use super::backend::{AudioBackend, CpalBackend, InputCallback, OutputCallback, StreamError};
+use super::call_tap::{CallTap, MixBatch};
+use super::capture_worker::CaptureWorker;
+use super::crossover::{Crossover, CROSSOVER_OVERLAP, CROSSOVER_POLL, CROSSOVER_START_TIMEOUT};
+use super::denoise::{DenoiseBackend, RnnoiseModel, SharedDenoiser};
+use super::device_config::SupportedConfig;
+use super::drop_queue::{drop_oldest, DropOldestReceiver, DropOldestSender};
+use super::dsp;
+use super::dynamics::{Dynamics, DynamicsSettings};
+use super::encoder::{EncoderOptions, OpusDecoder, OpusEncoder};
+use super::equalizer::{EqSettings, Equalizer};
+use super::fec::FecController;
+use super::level_meter::LevelThrottle;
+use super::active_speaker::ActiveSpeakerTracker;
+use super::auto_mute::{AutoMute, AutoMuteReason, AutoMuteSettings};
+use super::policy::AudioPolicy;
+use super::sidetone::{Sidetone, SidetoneSettings};
+use super::stream_thread::{ActiveStream, DEVICE_SETTLE_DELAY};
+use super::watchdog::{Heartbeat, StreamKind};
+use super::{FRAME_DURATION_MS, OPUS_BITRATE, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
+use crate::events::{self, AudioDeviceRetryEvent, AudioLevelEvent};
+use crate::i18n::{tf, Text};
+use crate::perf::{self, Component};
+use crate::server::LOCAL_PEER_ID;
 
 /// Audio packet ready for network transmission
 #[derive(Clone, Debug, Serialize)]
@@ -26,17 +48,38 @@ pub struct AudioPacket {
     pub timestamp: u64,
 }
 
-/// Event payload for audio level updates
-#[derive(Clone, Serialize)]
-pub struct AudioLevelEvent {
-    pub level: f32,
-    pub is_speaking: bool,
-    pub rms: f32,
+/// Outgoing packet statistics
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct AudioSendStats {
+    /// Packets encoded and queued for sending
+    pub packets_queued: u64,
+    /// Packets dropped because the sender fell behind
+    pub packets_dropped: u64,
+    /// Packets waiting to be sent
+    pub queue_len: usize,
+    /// Captured samples dropped because the capture worker fell behind
+    pub capture_overruns: u64,
 }
 
 /// Threshold for "speaking" detection
 const SPEAKING_THRESHOLD: f32 = 0.02;
 
+/// Outgoing queue length: ~200ms of audio, older packets are dropped beyond that
+const OUTGOING_QUEUE_PACKETS: usize = (200 / FRAME_DURATION_MS) as usize;
+
+/// Most mixed audio queued for playback, in frames (~100ms); past it the
+/// oldest half is dropped to keep latency down
+pub const PLAYBACK_BUFFER_FRAMES: usize = 5;
+
+/// Attempts at opening a device before giving up
+const OPEN_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry, doubled after each failed attempt
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(150);
+
+/// Peers silent for longer than this lose their decoder (recreated if they speak again)
+pub const PEER_AUDIO_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Per-peer playback state
 struct PeerPlayback {
     decoder: OpusDecoder,
@@ -44,40 +87,88 @@ struct PeerPlayback {
     last_activity: std::time::Instant,
 }
 
+/// Push-to-talk gate, closed unless the key is held when PTT is required
+/// by the room policy or enabled by the user
+#[derive(Default)]
+struct PushToTalk {
+    required: AtomicBool,
+    enabled: AtomicBool,
+    pressed: AtomicBool,
+}
+
+impl PushToTalk {
+    fn blocks(&self) -> bool {
+        (self.required.load(Ordering::SeqCst) || self.enabled.load(Ordering::SeqCst))
+            && !self.pressed.load(Ordering::SeqCst)
+    }
+}
+
+/// Sounds played locally (voice messages, read-aloud chat, sound cues) at
+/// once, and whether they opened the playback themselves
+#[derive(Default)]
+struct LocalPlayback {
+    count: usize,
+    opened: bool,
+}
+
 /// Resampling state for playback
 struct ResampleState {
     fractional_index: f64,
     last_sample: f32,
+    last_sidetone: f32,
+}
+
+/// A running stream lost its device (unplugged mid-call)
+#[derive(Debug, Clone)]
+pub struct DeviceLost {
+    pub stream: StreamKind,
+    /// Selected device (None: the system default)
+    pub device: Option<String>,
+    pub error: String,
 }
 
 /// Complete audio streaming manager
 pub struct AudioStreamingService {
-    host: Host,
+    backend: Arc<dyn AudioBackend>,
 
     // Capture state
-    capture_stream: Arc<Mutex<Option<Stream>>>,
+    capture_stream: Arc<Mutex<Option<ActiveStream>>>,
+    capture_worker: Arc<Mutex<Option<CaptureWorker>>>,
+    capture_overruns: Arc<AtomicU64>,
     is_capturing: Arc<AtomicBool>,
     is_muted: Arc<AtomicBool>,
     selected_input_device: Arc<Mutex<Option<String>>>,
+    capture_heartbeat: Heartbeat,
 
     // Playback state
-    playback_stream: Arc<Mutex<Option<Stream>>>,
+    playback_stream: Arc<Mutex<Option<ActiveStream>>>,
     is_playing: Arc<AtomicBool>,
+    local_playback: Arc<tokio::sync::Mutex<LocalPlayback>>,
+    is_deafened: Arc<AtomicBool>,
     selected_output_device: Arc<Mutex<Option<String>>>,
+    playback_heartbeat: Heartbeat,
+    // Which stream plays, and what, while switching devices
+    crossover: Arc<Crossover>,
+    equalizer: Arc<Mutex<Equalizer>>,
+    dynamics: Arc<Mutex<Dynamics>>,
 
     // Audio processing
     denoiser: SharedDenoiser,
     encoder: Arc<Mutex<Option<OpusEncoder>>>,
+    encoder_options: Arc<Mutex<EncoderOptions>>,
+    // Expected loss and FEC following the measured loss (adaptive_fec)
+    fec: Arc<Mutex<FecController>>,
 
     // Per-peer audio reception
     peer_playback: Arc<Mutex<HashMap<String, PeerPlayback>>>,
 
     // Mixed output samples ready for playback
     playback_buffer: Arc<Mutex<Vec<f32>>>,
+    playback_buffer_frames: Arc<AtomicUsize>,
 
-    // Channel for encoded audio packets to send
-    outgoing_audio_tx: Arc<Mutex<Option<mpsc::UnboundedSender<AudioPacket>>>>,
-    outgoing_audio_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<AudioPacket>>>>,
+    // Queue of encoded audio packets to send
+    outgoing_audio_tx: Arc<DropOldestSender<AudioPacket>>,
+    outgoing_audio_rx: Arc<Mutex<Option<DropOldestReceiver<AudioPacket>>>>,
 
     // Current audio level
     current_level: Arc<Mutex<f32>>,
@@ -87,30 +178,87 @@ pub struct AudioStreamingService {
 
     // Timestamp counter
     timestamp: Arc<Mutex<u64>>,
+
+    // Room audio policy
+    policy: Arc<Mutex<AudioPolicy>>,
+    // Opus bitrate allowed by the bandwidth budget, in bits/s
+    bitrate_cap: Arc<Mutex<Option<i32>>>,
+    push_to_talk: Arc<PushToTalk>,
+
+    // Processed capture recorded for a mic check (None when not recording)
+    mic_check: Arc<Mutex<Option<Vec<f32>>>>,
+
+    // Copies of the playback mix and the mic for the call recording
+    call_tap: Arc<CallTap>,
+
+    // Same copies for the WHIP broadcast, read at its own pace
+    broadcast_tap: Arc<CallTap>,
+
+    // Processed mic played back locally (input monitoring)
+    sidetone: Arc<Sidetone>,
+
+    // Join-muted, device conflict and silence auto-mute
+    auto_mute: Arc<AutoMute>,
+
+    // Who holds the floor, from the local capture and each peer's audio
+    active_speaker: Arc<Mutex<ActiveSpeakerTracker>>,
+
+    // Streams whose device went away, for the fallback task
+    device_lost_tx: mpsc::UnboundedSender<DeviceLost>,
+    device_lost_rx: Mutex<Option<mpsc::UnboundedReceiver<DeviceLost>>>,
 }
 
 impl AudioStreamingService {
     pub fn new() -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
+        Self::with_backend(Arc::new(CpalBackend))
+    }
+
+    /// Service running on another backend (e.g. `MockBackend` in tests)
+    pub fn with_backend(backend: Arc<dyn AudioBackend>) -> Self {
+        let (tx, rx) = drop_oldest(OUTGOING_QUEUE_PACKETS);
+        let (device_lost_tx, device_lost_rx) = mpsc::unbounded_channel();
 
         Self {
-            host: cpal::default_host(),
+            backend,
             capture_stream: Arc::new(Mutex::new(None)),
+            capture_worker: Arc::new(Mutex::new(None)),
+            capture_overruns: Arc::new(AtomicU64::new(0)),
             is_capturing: Arc::new(AtomicBool::new(false)),
             is_muted: Arc::new(AtomicBool::new(true)),
             selected_input_device: Arc::new(Mutex::new(None)),
+            capture_heartbeat: Heartbeat::default(),
             playback_stream: Arc::new(Mutex::new(None)),
             is_playing: Arc::new(AtomicBool::new(false)),
+            local_playback: Arc::new(tokio::sync::Mutex::new(LocalPlayback::default())),
+            is_deafened: Arc::new(AtomicBool::new(false)),
             selected_output_device: Arc::new(Mutex::new(None)),
+            equalizer: Arc::new(Mutex::new(Equalizer::default())),
+            dynamics: Arc::new(Mutex::new(Dynamics::default())),
+            playback_heartbeat: Heartbeat::default(),
+            crossover: Arc::new(Crossover::default()),
             denoiser: SharedDenoiser::new(),
             encoder: Arc::new(Mutex::new(None)),
+            encoder_options: Arc::new(Mutex::new(EncoderOptions::default())),
+            fec: Arc::new(Mutex::new(FecController::default())),
             peer_playback: Arc::new(Mutex::new(HashMap::new())),
             playback_buffer: Arc::new(Mutex::new(Vec::with_capacity(SAMPLES_PER_FRAME * 10))),
-            outgoing_audio_tx: Arc::new(Mutex::new(Some(tx))),
+            playback_buffer_frames: Arc::new(AtomicUsize::new(PLAYBACK_BUFFER_FRAMES)),
+            outgoing_audio_tx: Arc::new(tx),
             outgoing_audio_rx: Arc::new(Mutex::new(Some(rx))),
             current_level: Arc::new(Mutex::new(0.0)),
             app_handle: Arc::new(Mutex::new(None)),
             timestamp: Arc::new(Mutex::new(0)),
+            policy: Arc::new(Mutex::new(AudioPolicy::default())),
+            bitrate_cap: Arc::new(Mutex::new(None)),
+            push_to_talk: Arc::new(PushToTalk::default()),
+            mic_check: Arc::new(Mutex::new(None)),
+            call_tap: Arc::new(CallTap::default()),
+            broadcast_tap: Arc::new(CallTap::default()),
+            sidetone: Arc::new(Sidetone::default()),
+            auto_mute: Arc::new(AutoMute::new(AutoMuteSettings::default())),
+            active_speaker: Arc::new(Mutex::new(ActiveSpeakerTracker::default())),
+            device_lost_tx,
+            device_lost_rx: Mutex::new(Some(device_lost_rx)),
         }
     }
 
@@ -121,6 +269,10 @@ impl AudioStreamingService {
 
     /// Enable or disable noise suppression
     pub fn set_noise_suppression(&self, enabled: bool) {
+        if !enabled && self.policy.lock().require_noise_suppression {
+            tracing::warn!("Noise suppression is required by the room policy");
+            return;
+        }
         self.denoiser.set_enabled(enabled);
         tracing::info!("Noise suppression: {}", if enabled { "enabled" } else { "disabled" });
     }
@@ -130,219 +282,413 @@ impl AudioStreamingService {
         self.denoiser.is_enabled()
     }
 
-    /// Set input device by name (None for default)
-    pub fn set_input_device(&self, device_name: Option<String>) -> Result<(), String> {
-        let was_capturing = self.is_capturing.load(Ordering::SeqCst);
+    /// Select the noise suppression backend
+    pub fn set_denoise_backend(&self, backend: DenoiseBackend) -> AppResult<()> {
+        if backend == DenoiseBackend::Passthrough && self.policy.lock().require_noise_suppression
+        {
+            return Err(AppError::InvalidArgument(
+                "Noise suppression is required by the room policy".to_string(),
+            ));
+        }
+        self.denoiser.set_backend(backend)?;
+        tracing::info!("Noise suppression backend: {:?}", backend);
+        Ok(())
+    }
 
-        *self.selected_input_device.lock() = device_name;
+    /// Selected noise suppression backend
+    pub fn denoise_backend(&self) -> DenoiseBackend {
+        self.denoiser.backend()
+    }
 
-        if was_capturing {
-            self.stop_capture();
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            self.start_capture()?;
-        }
+    /// Set the equalizer of the playback output
+    pub fn set_equalizer(&self, settings: EqSettings) {
+        self.equalizer.lock().set_settings(settings);
+    }
 
-        Ok(())
+    /// Equalizer of the playback output
+    pub fn equalizer(&self) -> EqSettings {
+        self.equalizer.lock().settings()
     }
 
-    /// Get selected input device
-    pub fn get_input_device(&self) -> Option<String> {
-        self.selected_input_device.lock().clone()
+    /// Set the compressor of the playback output
+    pub fn set_dynamics(&self, settings: DynamicsSettings) {
+        self.dynamics.lock().set_settings(settings);
     }
 
-    /// Set output device by name (None for default)
-    pub fn set_output_device(&self, device_name: Option<String>) -> Result<(), String> {
-        let was_playing = self.is_playing.load(Ordering::SeqCst);
+    /// Compressor of the playback output
+    pub fn dynamics(&self) -> DynamicsSettings {
+        self.dynamics.lock().settings()
+    }
 
-        *self.selected_output_device.lock() = device_name;
+    /// Use a custom RNNoise model, or the built-in one with None
+    pub fn set_denoise_model(&self, model: Option<RnnoiseModel>) {
+        self.denoiser.set_model(model);
+    }
 
-        if was_playing {
-            self.stop_playback();
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            self.start_playback()?;
+    /// Path of the custom RNNoise model in use
+    pub fn denoise_model_path(&self) -> Option<PathBuf> {
+        self.denoiser.model_path()
+    }
+
+    /// Apply a room audio policy to the capture pipeline
+    pub fn apply_policy(&self, policy: AudioPolicy) {
+        if policy.require_noise_suppression {
+            self.denoiser.set_enabled(true);
+            if self.denoiser.backend() == DenoiseBackend::Passthrough {
+                let _ = self.denoiser.set_backend(DenoiseBackend::default());
+            }
         }
 
+        self.push_to_talk
+            .required
+            .store(policy.force_push_to_talk, Ordering::SeqCst);
+
+        let bitrate = self.capped_bitrate(policy.effective_bitrate(OPUS_BITRATE));
+        if let Some(enc) = self.encoder.lock().as_mut() {
+            if let Err(e) = enc.set_bitrate(bitrate) {
+                tracing::warn!("Failed to apply policy bitrate: {}", e);
+            }
+        }
+
+        tracing::info!("Room audio policy applied: {:?}", policy);
+        *self.policy.lock() = policy;
+    }
+
+    /// Cap the Opus bitrate (bits/s) to fit the bandwidth budget, None to lift it
+    pub fn set_bitrate_cap(&self, cap: Option<i32>) {
+        *self.bitrate_cap.lock() = cap;
+        let bitrate = self.capped_bitrate(self.policy.lock().effective_bitrate(OPUS_BITRATE));
+        if let Some(enc) = self.encoder.lock().as_mut() {
+            if let Err(e) = enc.set_bitrate(bitrate) {
+                tracing::warn!("Failed to apply the bandwidth bitrate: {}", e);
+            }
+        }
+    }
+
+    /// `bitrate` lowered to the bandwidth cap, if any
+    fn capped_bitrate(&self, bitrate: i32) -> i32 {
+        match *self.bitrate_cap.lock() {
+            Some(cap) => bitrate.min(cap),
+            None => bitrate,
+        }
+    }
+
+    /// Set Opus complexity, expected loss and FEC (applied to the live encoder)
+    pub fn set_encoder_options(&self, options: EncoderOptions) -> AppResult<()> {
+        options.validate()?;
+        tracing::info!("Encoder options set: {:?}", options);
+        *self.encoder_options.lock() = options;
+        let effective = self.effective_encoder_options();
+        if let Some(enc) = self.encoder.lock().as_mut() {
+            enc.set_options(&effective)?;
+        }
         Ok(())
     }
 
-    /// Get input device by name or default
-    fn get_input_device_by_name(&self, name: Option<&str>) -> Result<cpal::Device, String> {
-        match name {
-            Some(device_name) => {
-                let devices = self.host.input_devices()
-                    .map_err(|e| format!("Failed to enumerate devices: {}", e))?;
+    /// Encoder options with the adaptive expected loss and FEC, once measured
+    fn effective_encoder_options(&self) -> EncoderOptions {
+        let mut options = *self.encoder_options.lock();
+        if options.adaptive_fec {
+            if let Some(protection) = self.fec.lock().current() {
+                options.packet_loss_perc = protection.packet_loss_perc;
+                options.inband_fec = protection.inband_fec;
+            }
+        }
+        options
+    }
 
-                for device in devices {
-                    if let Ok(n) = device.name() {
-                        if n == device_name {
-                            return Ok(device);
-                        }
-                    }
-                }
-                Err(format!("Device '{}' not found", device_name))
+    /// Feed the worst loss peers measure on our audio, in percent (None when
+    /// nobody is connected, going back to the configured options)
+    pub fn report_packet_loss(&self, loss_percent: Option<f64>) {
+        let changed = match loss_percent {
+            Some(loss) => self.fec.lock().update(loss).is_some(),
+            None => {
+                let mut fec = self.fec.lock();
+                let measured = fec.current().is_some();
+                fec.reset();
+                measured
+            }
+        };
+        if !changed || !self.encoder_options.lock().adaptive_fec {
+            return;
+        }
+
+        let options = self.effective_encoder_options();
+        tracing::debug!(
+            "Adaptive FEC: {}% expected loss, FEC {}",
+            options.packet_loss_perc,
+            if options.inband_fec { "on" } else { "off" }
+        );
+        if let Some(enc) = self.encoder.lock().as_mut() {
+            if let Err(e) = enc.set_options(&options) {
+                tracing::warn!("Failed to apply the adaptive FEC: {}", e);
             }
-            None => self.host
-                .default_input_device()
-                .ok_or_else(|| "No default input device".to_string()),
         }
     }
 
-    /// Get output device by name or default
-    fn get_output_device_by_name(&self, name: Option<&str>) -> Result<cpal::Device, String> {
-        match name {
-            Some(device_name) => {
-                let devices = self.host.output_devices()
-                    .map_err(|e| format!("Failed to enumerate devices: {}", e))?;
+    pub fn encoder_options(&self) -> EncoderOptions {
+        *self.encoder_options.lock()
+    }
 
-                for device in devices {
-                    if let Ok(n) = device.name() {
-                        if n == device_name {
-                            return Ok(device);
-                        }
-                    }
-                }
-                Err(format!("Device '{}' not found", device_name))
+    /// Get the active room audio policy
+    pub fn policy(&self) -> AudioPolicy {
+        self.policy.lock().clone()
+    }
+
+    /// Update push-to-talk key state
+    pub fn set_push_to_talk_pressed(&self, pressed: bool) {
+        self.push_to_talk.pressed.store(pressed, Ordering::SeqCst);
+    }
+
+    /// Check if the push-to-talk key is held
+    pub fn is_push_to_talk_pressed(&self) -> bool {
+        self.push_to_talk.pressed.load(Ordering::SeqCst)
+    }
+
+    /// Require the push-to-talk key even when the room policy does not
+    pub fn set_push_to_talk_mode(&self, enabled: bool) {
+        self.push_to_talk.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Set input device by name (None for default)
+    /// A running capture is stopped, given time to settle without blocking the
+    /// thread, and restarted on the new device. Callers hold the voice command
+    /// guard so nothing restarts or stops the stream in between.
+    pub async fn set_input_device(&self, device_name: Option<String>) -> AppResult<()> {
+        *self.selected_input_device.lock() = device_name;
+
+        if self.is_capturing.load(Ordering::SeqCst) {
+            self.stop_capture();
+            tokio::time::sleep(DEVICE_SETTLE_DELAY).await;
+            self.start_capture().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get selected input device
+    pub fn get_input_device(&self) -> Option<String> {
+        self.selected_input_device.lock().clone()
+    }
+
+    /// Set output device by name (None for default)
+    /// A running playback switches without a gap: the new stream opens next to
+    /// the old one and plays the same audio for a moment, then takes over the
+    /// playback buffer and the old stream is closed. If the new device can't be
+    /// opened, playback stays on the old one. Callers hold the voice command
+    /// guard so nothing restarts or stops the stream in between.
+    pub async fn set_output_device(&self, device_name: Option<String>) -> AppResult<()> {
+        let previous = std::mem::replace(&mut *self.selected_output_device.lock(), device_name);
+
+        if !self.is_playing.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let id = self.crossover.next_id();
+        self.crossover.follow(id);
+        let open = || self.open_playback_stream(id);
+        let stream = match self.open_with_retries(StreamKind::Playback, open).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.crossover.abandon(id);
+                *self.selected_output_device.lock() = previous;
+                return Err(e);
             }
-            None => self.host
-                .default_output_device()
-                .ok_or_else(|| "No default output device".to_string()),
+        };
+
+        // Both streams play the same audio until the new one is settled
+        let waiting = Instant::now();
+        while !self.crossover.follower_started() && waiting.elapsed() < CROSSOVER_START_TIMEOUT {
+            tokio::time::sleep(CROSSOVER_POLL).await;
         }
+        tokio::time::sleep(CROSSOVER_OVERLAP).await;
+        self.crossover.hand_over(id);
+        let retired = self.playback_stream.lock().replace(stream);
+        drop(retired);
+
+        tracing::info!("Audio playback switched without interruption");
+        Ok(())
     }
 
     /// Start audio capture
-    pub fn start_capture(&self) -> Result<(), String> {
+    pub async fn start_capture(&self) -> AppResult<()> {
         if self.is_capturing.load(Ordering::SeqCst) {
             return Ok(());
         }
+        self.open_with_retries(StreamKind::Capture, || self.open_capture())
+            .await
+    }
 
-        // Initialize encoder
-        let encoder = OpusEncoder::new()?;
+    fn open_capture(&self) -> AppResult<()> {
+        // Initialize encoder (bitrate capped by the room policy and the bandwidth budget)
+        let mut encoder = OpusEncoder::new()?;
+        let bitrate = self.policy.lock().effective_bitrate(OPUS_BITRATE);
+        encoder.set_bitrate(self.capped_bitrate(bitrate))?;
+        encoder.set_options(&self.effective_encoder_options())?;
         *self.encoder.lock() = Some(encoder);
 
         let selected = self.selected_input_device.lock().clone();
-        let device = self.get_input_device_by_name(selected.as_deref())?;
-
-        let device_name = device.name().unwrap_or_default();
+        let (device_name, format) = self.backend.input_format(selected.as_deref())?;
         tracing::info!("Starting audio capture on: {}", device_name);
 
         // Use native sample rate
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| format!("Failed to get input config: {}", e))?;
-
-        let config = supported_config.config();
-        let sample_rate = config.sample_rate.0;
-        let channels = config.channels as usize;
+        let sample_rate = format.sample_rate;
+        let channels = format.channels as usize;
 
-        // Configure denoiser
-        self.denoiser.set_sample_rate(sample_rate);
+        // The worker resamples to 48kHz before denoising
+        self.denoiser.set_sample_rate(SAMPLE_RATE);
         self.denoiser.reset();
 
         // Calculate samples per frame for this device
         let samples_per_frame = (sample_rate as usize * 20) / 1000; // 20ms
 
-        // Clone all the shared state we need
-        let is_muted = self.is_muted.clone();
-        let current_level = self.current_level.clone();
-        let app_handle = self.app_handle.clone();
-        let denoiser = self.denoiser.clone();
-        let encoder = self.encoder.clone();
-        let outgoing_tx = self.outgoing_audio_tx.clone();
-        let timestamp = self.timestamp.clone();
-
-        // Buffer for accumulating samples
-        let sample_buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::with_capacity(samples_per_frame * 2)));
-
-        // Resampling state if needed
-        let needs_resampling = sample_rate != SAMPLE_RATE;
-        let resample_ratio = SAMPLE_RATE as f64 / sample_rate as f64;
-
-        let err_fn = |err| {
-            tracing::error!("Audio capture error: {}", err);
+        // Everything heavy runs on the capture worker, off the device callback
+        let mut pipeline = CapturePipeline {
+            resampler: (sample_rate != SAMPLE_RATE)
+                .then(|| dsp::Resampler::new(SAMPLE_RATE as f64 / sample_rate as f64)),
+            resampled: Vec::with_capacity(SAMPLES_PER_FRAME * 3),
+            denoised: Vec::with_capacity(SAMPLES_PER_FRAME * 2),
+            level_throttle: LevelThrottle::new(),
+            is_muted: self.is_muted.clone(),
+            push_to_talk: self.push_to_talk.clone(),
+            current_level: self.current_level.clone(),
+            app_handle: self.app_handle.clone(),
+            denoiser: self.denoiser.clone(),
+            encoder: self.encoder.clone(),
+            outgoing_tx: self.outgoing_audio_tx.clone(),
+            timestamp: self.timestamp.clone(),
+            mic_check: self.mic_check.clone(),
+            call_tap: self.call_tap.clone(),
+            broadcast_tap: self.broadcast_tap.clone(),
+            sidetone: self.sidetone.clone(),
+            auto_mute: self.auto_mute.clone(),
+            active_speaker: self.active_speaker.clone(),
         };
-
-        let stream = match supported_config.sample_format() {
-            SampleFormat::F32 => {
-                device.build_input_stream(
-                    &config,
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        process_capture(
-                            data,
-                            channels,
-                            samples_per_frame,
-                            needs_resampling,
-                            resample_ratio,
-                            &sample_buffer,
-                            &is_muted,
-                            &current_level,
-                            &app_handle,
-                            &denoiser,
-                            &encoder,
-                            &outgoing_tx,
-                            &timestamp,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )
-            }
-            SampleFormat::I16 => {
-                let sample_buffer = Arc::new(Mutex::new(Vec::with_capacity(samples_per_frame * 2)));
-                let is_muted = self.is_muted.clone();
-                let current_level = self.current_level.clone();
-                let app_handle = self.app_handle.clone();
-                let denoiser = self.denoiser.clone();
-                let encoder = self.encoder.clone();
-                let outgoing_tx = self.outgoing_audio_tx.clone();
-                let timestamp = self.timestamp.clone();
-
-                device.build_input_stream(
-                    &config,
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        let float_data: Vec<f32> = data.iter()
-                            .map(|&s| s as f32 / i16::MAX as f32)
-                            .collect();
-                        process_capture(
-                            &float_data,
-                            channels,
-                            samples_per_frame,
-                            needs_resampling,
-                            resample_ratio,
-                            &sample_buffer,
-                            &is_muted,
-                            &current_level,
-                            &app_handle,
-                            &denoiser,
-                            &encoder,
-                            &outgoing_tx,
-                            &timestamp,
-                        );
-                    },
-                    err_fn,
-                    None,
-                )
-            }
-            format => {
-                return Err(format!("Unsupported sample format: {:?}", format));
+        let (worker, mut feed) = CaptureWorker::spawn(
+            channels,
+            samples_per_frame,
+            self.capture_overruns.clone(),
+            move |frame| process_frame(frame, &mut pipeline),
+        )?;
+        let heartbeat = self.capture_heartbeat.clone();
+
+        let err_fn = {
+            let is_muted = self.is_muted.clone();
+            let auto_mute = self.auto_mute.clone();
+            let mut report_loss = self.device_loss_reporter(StreamKind::Capture, selected.clone());
+            move |err: StreamError| {
+                tracing::error!("Audio capture error: {}", err);
+                report_loss(&err);
+                // The device went away or another app took it over
+                if err.is_device_lost()
+                    && auto_mute.mutes_on_device_conflict()
+                    && !is_muted.swap(true, Ordering::SeqCst)
+                {
+                    auto_mute.notify(AutoMuteReason::DeviceConflict);
+                }
             }
-        }.map_err(|e| format!("Failed to build input stream: {}", e))?;
+        };
+
+        let on_data: InputCallback = Box::new(move |data: &[f32]| {
+            heartbeat.beat();
+            feed.push(data);
+        });
 
-        stream.play().map_err(|e| format!("Failed to start capture: {}", e))?;
+        let backend = self.backend.clone();
+        let stream = ActiveStream::open(move || {
+            backend.start_input(selected.as_deref(), on_data, Box::new(err_fn))
+        })?;
 
         *self.capture_stream.lock() = Some(stream);
+        *self.capture_worker.lock() = Some(worker);
         self.is_capturing.store(true, Ordering::SeqCst);
 
         tracing::info!("Audio capture started");
         Ok(())
     }
 
+    /// Error callback part reporting a lost device, once per opened stream
+    /// (cpal keeps calling back with errors until the stream is dropped)
+    fn device_loss_reporter(
+        &self,
+        stream: StreamKind,
+        device: Option<String>,
+    ) -> impl FnMut(&StreamError) + Send + 'static {
+        let tx = self.device_lost_tx.clone();
+        let mut reported = false;
+        move |err| {
+            if reported || !err.is_device_lost() {
+                return;
+            }
+            reported = true;
+            let _ = tx.send(DeviceLost {
+                stream,
+                device: device.clone(),
+                error: err.to_string(),
+            });
+        }
+    }
+
+    /// Take the receiving end of the device losses (for the fallback task)
+    pub fn take_device_lost_receiver(&self) -> Option<mpsc::UnboundedReceiver<DeviceLost>> {
+        self.device_lost_rx.lock().take()
+    }
+
+    /// Run `open` until it succeeds, backing off between attempts: right after a
+    /// hotplug, or while another app releases an exclusive-mode device, opening
+    /// fails for a moment before it works again
+    async fn open_with_retries<T>(
+        &self,
+        stream: StreamKind,
+        mut open: impl FnMut() -> AppResult<T>,
+    ) -> AppResult<T> {
+        let mut delay = OPEN_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            match open() {
+                Err(e) if attempt < OPEN_ATTEMPTS && is_transient(&e) => {
+                    tracing::warn!(
+                        "Opening the {:?} device failed (attempt {}/{}): {}, retrying in {:?}",
+                        stream,
+                        attempt,
+                        OPEN_ATTEMPTS,
+                        e,
+                        delay
+                    );
+                    if let Some(app) = self.app_handle.lock().as_ref() {
+                        let text = match stream {
+                            StreamKind::Capture => Text::MicRetry,
+                            StreamKind::Playback => Text::OutputRetry,
+                        };
+                        let (next, max) = ((attempt + 1).to_string(), OPEN_ATTEMPTS.to_string());
+                        let event = AudioDeviceRetryEvent {
+                            stream,
+                            attempt,
+                            max_attempts: OPEN_ATTEMPTS,
+                            retry_in_ms: delay.as_millis() as u64,
+                            error: e.to_string(),
+                            message: tf(text, &[("attempt", &next), ("max", &max)]),
+                        };
+                        let _ = events::emit(app, &event);
+                    }
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Stop audio capture
     pub fn stop_capture(&self) {
         if !self.is_capturing.load(Ordering::SeqCst) {
             return;
         }
 
+        // Stream first, so the callback no longer feeds the worker
         *self.capture_stream.lock() = None;
+        *self.capture_worker.lock() = None;
         *self.encoder.lock() = None;
         self.is_capturing.store(false, Ordering::SeqCst);
         *self.current_level.lock() = 0.0;
@@ -351,41 +697,57 @@ impl AudioStreamingService {
     }
 
     /// Start audio playback
-    pub fn start_playback(&self) -> Result<(), String> {
+    pub async fn start_playback(&self) -> AppResult<()> {
+        // Wanted beyond the local sounds now: they no longer close it
+        self.local_playback.lock().await.opened = false;
         if self.is_playing.load(Ordering::SeqCst) {
             return Ok(());
         }
+        self.open_with_retries(StreamKind::Playback, || self.open_playback())
+            .await
+    }
 
-        let selected = self.selected_output_device.lock().clone();
-        let device = self.get_output_device_by_name(selected.as_deref())?;
+    /// Start a local sound, opening the playback if it isn't running
+    pub async fn begin_local_playback(&self) -> AppResult<()> {
+        let mut local = self.local_playback.lock().await;
+        if !self.is_playing.load(Ordering::SeqCst) {
+            self.open_with_retries(StreamKind::Playback, || self.open_playback())
+                .await?;
+            local.opened = true;
+        }
+        local.count += 1;
+        Ok(())
+    }
 
-        let device_name = device.name().unwrap_or_default();
-        tracing::info!("Starting audio playback on: {}", device_name);
+    /// End a local sound; the last one closes the playback if it opened it
+    pub async fn end_local_playback(&self) {
+        let mut local = self.local_playback.lock().await;
+        local.count = local.count.saturating_sub(1);
+        if local.count == 0 && std::mem::take(&mut local.opened) {
+            self.stop_playback();
+        }
+    }
 
-        // Use default config first, fall back to our preferred config
-        let config = match device.default_output_config() {
-            Ok(supported) => {
-                let mut config = supported.config();
-                // Try to use mono if possible, otherwise keep device channels
-                if config.channels > 1 {
-                    tracing::info!("Output device uses {} channels", config.channels);
-                }
-                // Use default buffer size (more compatible)
-                config.buffer_size = cpal::BufferSize::Default;
-                config
-            }
-            Err(_) => {
-                // Fallback to our preferred config
-                StreamConfig {
-                    channels: CHANNELS,
-                    sample_rate: cpal::SampleRate(SAMPLE_RATE),
-                    buffer_size: cpal::BufferSize::Default,
-                }
-            }
-        };
+    fn open_playback(&self) -> AppResult<()> {
+        let id = self.crossover.next_id();
+        self.crossover.lead(id);
+        let stream = self.open_playback_stream(id)?;
+
+        *self.playback_stream.lock() = Some(stream);
+        self.is_playing.store(true, Ordering::SeqCst);
+
+        tracing::info!("Audio playback started");
+        Ok(())
+    }
 
-        let output_channels = config.channels as usize;
-        let output_sample_rate = config.sample_rate.0;
+    /// Open an output stream on the selected device, playing as `id` in the crossover
+    fn open_playback_stream(&self, id: u64) -> AppResult<ActiveStream> {
+        let selected = self.selected_output_device.lock().clone();
+        let (device_name, format) = self.backend.output_format(selected.as_deref())?;
+        tracing::info!("Starting audio playback on: {}", device_name);
+
+        let output_channels = format.channels as usize;
+        let output_sample_rate = format.sample_rate;
         let needs_resampling = output_sample_rate != SAMPLE_RATE;
         let resample_ratio = output_sample_rate as f64 / SAMPLE_RATE as f64;
 
@@ -397,60 +759,89 @@ impl AudioStreamingService {
         );
 
         let playback_buffer = self.playback_buffer.clone();
+        let heartbeat = self.playback_heartbeat.clone();
+        let equalizer = self.equalizer.clone();
+        let dynamics = self.dynamics.clone();
+        let call_tap = self.call_tap.clone();
+        let broadcast_tap = self.broadcast_tap.clone();
+        let mut call_batch = MixBatch::new();
+        let mut broadcast_batch = MixBatch::new();
+        let sidetone = self.sidetone.clone();
+        let crossover = self.crossover.clone();
 
         // Resampling state - kept between callbacks
         let resample_state: Arc<Mutex<ResampleState>> = Arc::new(Mutex::new(ResampleState {
             fractional_index: 0.0,
             last_sample: 0.0,
+            last_sidetone: 0.0,
         }));
 
-        let stream = device.build_output_stream(
-            &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let mut buffer = playback_buffer.lock();
-                let mut rs = resample_state.lock();
-
-                for frame in 0..(data.len() / output_channels) {
-                    let sample = if needs_resampling {
-                        // Resample from 48kHz to output rate
-                        rs.fractional_index += 1.0 / resample_ratio;
-
-                        while rs.fractional_index >= 1.0 {
-                            rs.fractional_index -= 1.0;
-                            // Use remove(0) for FIFO instead of pop() which is LIFO
-                            if !buffer.is_empty() {
-                                rs.last_sample = buffer.remove(0);
-                            }
-                        }
-                        rs.last_sample
-                    } else {
-                        // No resampling needed - use FIFO order
-                        if !buffer.is_empty() {
-                            buffer.remove(0)
-                        } else {
-                            0.0
-                        }
-                    };
+        let on_data: OutputCallback = Box::new(move |data: &mut [f32]| {
+            heartbeat.beat();
+            let mut buffer = playback_buffer.lock();
+            let mut rs = resample_state.lock();
+            let mut eq = equalizer.lock();
+            let mut dynamics = dynamics.lock();
+            // Mixed after the recording tap: the recording has the mic already
+            let mut monitor = sidetone.reader();
+            // Buffer or mirrored samples, while switching devices
+            let mut source = crossover.enter(id);
+
+            // One 48kHz sample from the buffer (None when it ran dry) and the sidetone
+            let mut pull = || {
+                // Use remove(0) for FIFO instead of pop() which is LIFO
+                let voice = (!buffer.is_empty()).then(|| {
+                    let sample = dynamics.process(eq.process(buffer.remove(0)));
+                    call_batch.push(sample);
+                    broadcast_batch.push(sample);
+                    sample
+                });
+                let sidetone = monitor.as_mut().map_or(0.0, |monitor| monitor.next_sample());
+                (voice, sidetone)
+            };
 
-                    // Duplicate to all output channels
-                    for ch in 0..output_channels {
-                        data[frame * output_channels + ch] = sample;
+            for frame in 0..(data.len() / output_channels) {
+                let sample = if needs_resampling {
+                    // Resample from 48kHz to output rate
+                    rs.fractional_index += 1.0 / resample_ratio;
+
+                    while rs.fractional_index >= 1.0 {
+                        rs.fractional_index -= 1.0;
+                        let (voice, sidetone) = source.next(&mut pull);
+                        if let Some(voice) = voice {
+                            rs.last_sample = voice;
+                        }
+                        rs.last_sidetone = sidetone;
                     }
+                    rs.last_sample + rs.last_sidetone
+                } else {
+                    // No resampling needed - use FIFO order
+                    let (voice, sidetone) = source.next(&mut pull);
+                    voice.unwrap_or(0.0) + sidetone
+                };
+
+                // Duplicate to all output channels
+                for ch in 0..output_channels {
+                    data[frame * output_channels + ch] = sample;
                 }
-            },
-            |err| {
-                tracing::error!("Audio playback error: {}", err);
-            },
-            None,
-        ).map_err(|e| format!("Failed to build output stream: {}", e))?;
-
-        stream.play().map_err(|e| format!("Failed to start playback: {}", e))?;
+            }
 
-        *self.playback_stream.lock() = Some(stream);
-        self.is_playing.store(true, Ordering::SeqCst);
+            call_tap.push_mix(&mut call_batch);
+            broadcast_tap.push_mix(&mut broadcast_batch);
+        });
 
-        tracing::info!("Audio playback started");
-        Ok(())
+        let mut report_loss = self.device_loss_reporter(StreamKind::Playback, selected.clone());
+        let backend = self.backend.clone();
+        ActiveStream::open(move || {
+            backend.start_output(
+                selected.as_deref(),
+                on_data,
+                Box::new(move |err| {
+                    tracing::error!("Audio playback error: {}", err);
+                    report_loss(&err);
+                }),
+            )
+        })
     }
 
     /// Stop audio playback
@@ -466,6 +857,18 @@ impl AudioStreamingService {
         tracing::info!("Audio playback stopped");
     }
 
+    /// Enable sidetone or change its volume (heard while playing)
+    pub fn set_sidetone(&self, settings: SidetoneSettings) -> AppResult<()> {
+        settings.validate()?;
+        self.sidetone.set_settings(settings);
+        tracing::info!("Sidetone set to: {:?}", settings);
+        Ok(())
+    }
+
+    pub fn sidetone(&self) -> SidetoneSettings {
+        self.sidetone.settings()
+    }
+
     /// Set mute state
     pub fn set_muted(&self, muted: bool) {
         self.is_muted.store(muted, Ordering::SeqCst);
@@ -480,6 +883,20 @@ impl AudioStreamingService {
         self.is_muted.load(Ordering::SeqCst)
     }
 
+    /// Set deafen state (incoming audio is dropped while deafened)
+    pub fn set_deafened(&self, deafened: bool) {
+        self.is_deafened.store(deafened, Ordering::SeqCst);
+        if deafened {
+            self.playback_buffer.lock().clear();
+        }
+        tracing::info!("Deafen set to: {}", deafened);
+    }
+
+    /// Get deafen state
+    pub fn is_deafened(&self) -> bool {
+        self.is_deafened.load(Ordering::SeqCst)
+    }
+
     /// Check if capturing
     pub fn is_capturing(&self) -> bool {
         self.is_capturing.load(Ordering::SeqCst)
@@ -490,22 +907,88 @@ impl AudioStreamingService {
         self.is_playing.load(Ordering::SeqCst)
     }
 
+    /// Callback counter of a stream, watched for stalls
+    pub fn heartbeat(&self, kind: StreamKind) -> u64 {
+        match kind {
+            StreamKind::Capture => self.capture_heartbeat.count(),
+            StreamKind::Playback => self.playback_heartbeat.count(),
+        }
+    }
+
+    /// Whether a stream is supposed to be running
+    pub fn is_running(&self, kind: StreamKind) -> bool {
+        match kind {
+            StreamKind::Capture => self.is_capturing(),
+            StreamKind::Playback => self.is_playing(),
+        }
+    }
+
+    /// Selected device of a stream (None: the system default)
+    pub fn selected_device(&self, kind: StreamKind) -> Option<String> {
+        match kind {
+            StreamKind::Capture => self.selected_input_device.lock().clone(),
+            StreamKind::Playback => self.selected_output_device.lock().clone(),
+        }
+    }
+
+    /// Select the device of a stream, restarting it if running
+    pub async fn set_device(&self, kind: StreamKind, device_name: Option<String>) -> AppResult<()> {
+        match kind {
+            StreamKind::Capture => self.set_input_device(device_name).await,
+            StreamKind::Playback => self.set_output_device(device_name).await,
+        }
+    }
+
+    /// Name of the device a stream opens (resolves the system default)
+    pub fn device_name(&self, kind: StreamKind) -> Option<String> {
+        let selected = self.selected_device(kind);
+        let resolved = match kind {
+            StreamKind::Capture => self.backend.input_format(selected.as_deref()),
+            StreamKind::Playback => self.backend.output_format(selected.as_deref()),
+        };
+        resolved.ok().map(|(name, _)| name)
+    }
+
+    /// Tear down and rebuild a stream on the same device
+    pub async fn restart(&self, kind: StreamKind) -> AppResult<()> {
+        match kind {
+            StreamKind::Capture => {
+                self.stop_capture();
+                self.start_capture().await
+            }
+            StreamKind::Playback => {
+                self.stop_playback();
+                self.start_playback().await
+            }
+        }
+    }
+
     /// Get current audio level
     pub fn current_level(&self) -> f32 {
         *self.current_level.lock()
     }
 
-    /// Get the next encoded audio packet (non-blocking)
-    pub fn get_outgoing_packet(&self) -> Option<AudioPacket> {
-        if let Some(rx) = self.outgoing_audio_rx.lock().as_mut() {
-            rx.try_recv().ok()
-        } else {
-            None
+    /// Take the receiving end of the outgoing packet queue (for the forwarding task)
+    pub fn take_outgoing_receiver(&self) -> Option<DropOldestReceiver<AudioPacket>> {
+        self.outgoing_audio_rx.lock().take()
+    }
+
+    /// Outgoing queue counters
+    pub fn send_stats(&self) -> AudioSendStats {
+        AudioSendStats {
+            packets_queued: self.outgoing_audio_tx.pushed(),
+            packets_dropped: self.outgoing_audio_tx.dropped(),
+            queue_len: self.outgoing_audio_tx.queued(),
+            capture_overruns: self.capture_overruns.load(Ordering::Relaxed),
         }
     }
 
     /// Receive audio from a peer
-    pub fn receive_peer_audio(&self, peer_id: &str, opus_data: &[u8]) -> Result<(), String> {
+    pub fn receive_peer_audio(&self, peer_id: &str, opus_data: &[u8]) -> AppResult<()> {
+        if self.is_deafened() {
+            return Ok(());
+        }
+
         let mut peers = self.peer_playback.lock();
 
         // Create decoder for new peer
@@ -521,12 +1004,16 @@ impl AudioStreamingService {
 
         // Decode the audio
         let samples = playback.decoder.decode(opus_data)?;
+        self.active_speaker
+            .lock()
+            .observe(peer_id, dsp::rms(&samples), playback.last_activity);
 
         // Mix into playback buffer
         let mut output = self.playback_buffer.lock();
 
-        // If buffer is getting too large (>100ms), drop old samples to reduce latency
-        let max_buffer_samples = SAMPLES_PER_FRAME * 5; // ~100ms max latency
+        // If buffer is getting too large (>100ms by default), drop old samples to reduce latency
+        let max_buffer_samples =
+            SAMPLES_PER_FRAME * self.playback_buffer_frames.load(Ordering::Relaxed);
         if output.len() > max_buffer_samples {
             let to_remove = output.len() - max_buffer_samples / 2;
             output.drain(0..to_remove);
@@ -537,31 +1024,116 @@ impl AudioStreamingService {
         Ok(())
     }
 
+    /// Allow more (or less) audio queued for playback, in frames: a deeper
+    /// buffer rides out scheduling hiccups at the cost of latency
+    pub fn set_playback_buffer_frames(&self, frames: usize) {
+        self.playback_buffer_frames.store(frames.max(2), Ordering::Relaxed);
+    }
+
+    /// Re-evaluate who holds the floor, returning the new active speaker
+    /// (a peer id or `LOCAL_PEER_ID`, None when nobody speaks) when it changed
+    pub fn update_active_speaker(&self) -> Option<Option<String>> {
+        self.active_speaker.lock().update(Instant::now())
+    }
+
+    /// Participant holding the floor
+    pub fn active_speaker(&self) -> Option<String> {
+        self.active_speaker.lock().speaker().map(str::to_string)
+    }
+
+    /// Participants speaking right now (peer ids and `LOCAL_PEER_ID`)
+    pub fn speaking_participants(&self) -> Vec<String> {
+        self.active_speaker.lock().speaking(Instant::now())
+    }
+
+    /// Auto-mute settings and notifications
+    pub fn auto_mute(&self) -> &AutoMute {
+        &self.auto_mute
+    }
+
+    /// Start recording the processed capture (mic check, voice message)
+    /// The recording includes audio captured while muted, nothing is transmitted.
+    /// Capture past `max_duration` is dropped so the buffer never grows on the
+    /// capture worker.
+    pub fn start_mic_recording(&self, max_duration: Duration) -> AppResult<()> {
+        let mut recording = self.mic_check.lock();
+        if recording.is_some() {
+            return Err(AppError::AlreadyActive("Mic recording already running".to_string()));
+        }
+        *recording = Some(Vec::with_capacity(
+            (SAMPLE_RATE as u128 * max_duration.as_millis() / 1000) as usize,
+        ));
+        Ok(())
+    }
+
+    /// Stop recording and return the 48kHz mono samples
+    pub fn take_mic_recording(&self) -> Vec<f32> {
+        self.mic_check.lock().take().unwrap_or_default()
+    }
+
+    /// Playback mix and mic copies, for the call recording
+    pub fn call_tap(&self) -> Arc<CallTap> {
+        self.call_tap.clone()
+    }
+
+    /// Playback mix and mic copies, for the WHIP broadcast
+    pub fn broadcast_tap(&self) -> Arc<CallTap> {
+        self.broadcast_tap.clone()
+    }
+
+    /// Queue 48kHz mono samples on the playback buffer (e.g. a mic check recording)
+    pub fn queue_playback(&self, samples: &[f32]) {
+        self.playback_buffer.lock().extend_from_slice(samples);
+    }
+
     /// Remove a peer
     pub fn remove_peer(&self, peer_id: &str) {
         self.peer_playback.lock().remove(peer_id);
+        self.active_speaker.lock().remove(peer_id);
+    }
+
+    /// Drop the decoders of peers that sent nothing for `timeout` as of `now`,
+    /// returning each removed peer with how long it had been silent
+    pub fn reap_idle_peers(&self, timeout: Duration, now: Instant) -> Vec<(String, Duration)> {
+        let mut reaped = Vec::new();
+        self.peer_playback.lock().retain(|peer_id, playback| {
+            let idle = now.saturating_duration_since(playback.last_activity);
+            if idle > timeout {
+                reaped.push((peer_id.clone(), idle));
+            }
+            idle <= timeout
+        });
+        let mut active_speaker = self.active_speaker.lock();
+        for (peer_id, _) in &reaped {
+            active_speaker.remove(peer_id);
+        }
+        reaped
     }
 
     /// Clear all peers
     pub fn clear_peers(&self) {
         self.peer_playback.lock().clear();
+        self.active_speaker.lock().clear();
         self.playback_buffer.lock().clear();
     }
 
     /// List input devices
-    pub fn list_input_devices(&self) -> Result<Vec<String>, String> {
-        let devices = self.host.input_devices()
-            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
-
-        Ok(devices.filter_map(|d| d.name().ok()).collect())
+    pub fn list_input_devices(&self) -> AppResult<Vec<String>> {
+        self.backend.input_devices()
     }
 
     /// List output devices
-    pub fn list_output_devices(&self) -> Result<Vec<String>, String> {
-        let devices = self.host.output_devices()
-            .map_err(|e| format!("Failed to enumerate output devices: {}", e))?;
+    pub fn list_output_devices(&self) -> AppResult<Vec<String>> {
+        self.backend.output_devices()
+    }
 
-        Ok(devices.filter_map(|d| d.name().ok()).collect())
+    /// Configurations a device (None for default) can be pinned to
+    pub fn supported_configs(
+        &self,
+        kind: StreamKind,
+        device: Option<&str>,
+    ) -> AppResult<Vec<SupportedConfig>> {
+        self.backend.supported_configs(kind, device)
     }
 }
 
@@ -571,146 +1143,235 @@ impl Default for AudioStreamingService {
     }
 }
 
-// Safety: All state is protected by Arc<Mutex> or atomic operations
-unsafe impl Send for AudioStreamingService {}
-unsafe impl Sync for AudioStreamingService {}
-
-/// Process captured audio data
-fn process_capture(
-    data: &[f32],
-    channels: usize,
-    samples_per_frame: usize,
-    needs_resampling: bool,
-    resample_ratio: f64,
-    sample_buffer: &Arc<Mutex<Vec<f32>>>,
-    is_muted: &Arc<AtomicBool>,
-    current_level: &Arc<Mutex<f32>>,
-    app_handle: &Arc<Mutex<Option<AppHandle>>>,
-    denoiser: &SharedDenoiser,
-    encoder: &Arc<Mutex<Option<OpusEncoder>>>,
-    outgoing_tx: &Arc<Mutex<Option<mpsc::UnboundedSender<AudioPacket>>>>,
-    timestamp: &Arc<Mutex<u64>>,
-) {
-    let mut buffer = sample_buffer.lock();
-
-    // Convert to mono
-    if channels > 1 {
-        for chunk in data.chunks(channels) {
-            let mono: f32 = chunk.iter().sum::<f32>() / channels as f32;
-            buffer.push(mono);
-        }
-    } else {
-        buffer.extend_from_slice(data);
+/// Failures worth retrying: the device is busy or not ready yet (a missing
+/// device or a denied permission won't fix itself in a few hundred ms)
+fn is_transient(error: &AppError) -> bool {
+    matches!(error, AppError::DeviceUnavailable(_) | AppError::Audio(_))
+}
+
+/// Per-stream state of the capture worker, reused for every frame
+struct CapturePipeline {
+    /// Device rate to 48kHz, `None` when the device already runs at 48kHz
+    resampler: Option<dsp::Resampler>,
+    /// 48kHz samples not yet processed: the resampler doesn't give exactly one
+    /// frame per device frame, so whole frames are taken from here
+    resampled: Vec<f32>,
+    /// Scratch buffer sized at start, so steady-state frames don't allocate
+    denoised: Vec<f32>,
+    level_throttle: LevelThrottle,
+    is_muted: Arc<AtomicBool>,
+    push_to_talk: Arc<PushToTalk>,
+    current_level: Arc<Mutex<f32>>,
+    app_handle: Arc<Mutex<Option<AppHandle>>>,
+    denoiser: SharedDenoiser,
+    encoder: Arc<Mutex<Option<OpusEncoder>>>,
+    outgoing_tx: Arc<DropOldestSender<AudioPacket>>,
+    timestamp: Arc<Mutex<u64>>,
+    mic_check: Arc<Mutex<Option<Vec<f32>>>>,
+    call_tap: Arc<CallTap>,
+    broadcast_tap: Arc<CallTap>,
+    sidetone: Arc<Sidetone>,
+    auto_mute: Arc<AutoMute>,
+    active_speaker: Arc<Mutex<ActiveSpeakerTracker>>,
+}
+
+/// Process one 20ms mono frame at the device rate (on the capture worker)
+fn process_frame(samples: &[f32], pipeline: &mut CapturePipeline) {
+    let _span = perf::span(Component::Capture);
+    // Taken out for the loop, so the frames can borrow it while the pipeline is
+    // borrowed mutably (the allocation is kept)
+    let mut pending = std::mem::take(&mut pipeline.resampled);
+    match pipeline.resampler.as_mut() {
+        Some(resampler) => resampler.process_into(samples, &mut pending),
+        None => pending.extend_from_slice(samples),
     }
 
-    // Process complete frames
-    while buffer.len() >= samples_per_frame {
-        let samples: Vec<f32> = buffer.drain(..samples_per_frame).collect();
+    let mut consumed = 0;
+    while pending.len() - consumed >= SAMPLES_PER_FRAME {
+        process_frame_48k(&pending[consumed..consumed + SAMPLES_PER_FRAME], pipeline);
+        consumed += SAMPLES_PER_FRAME;
+    }
+    pending.drain(..consumed);
+    pipeline.resampled = pending;
+}
 
-        // Resample to 48kHz if needed
-        let samples_48k = if needs_resampling {
-            resample(&samples, resample_ratio)
-        } else {
-            samples.clone()
-        };
+/// Denoise, meter and encode one 20ms frame at 48kHz
+fn process_frame_48k(samples_48k: &[f32], pipeline: &mut CapturePipeline) {
+    let CapturePipeline {
+        denoised,
+        level_throttle,
+        is_muted,
+        push_to_talk,
+        current_level,
+        app_handle,
+        denoiser,
+        encoder,
+        outgoing_tx,
+        timestamp,
+        mic_check,
+        call_tap,
+        broadcast_tap,
+        sidetone,
+        auto_mute,
+        active_speaker,
+        ..
+    } = pipeline;
+
+    // Apply noise reduction
+    denoised.clear();
+    {
+        let _span = perf::span(Component::Denoise);
+        denoiser.process_into(samples_48k, denoised);
+    }
 
-        // Apply noise reduction
-        let processed = denoiser.process(&samples_48k);
+    if let Some(recording) = mic_check.lock().as_mut() {
+        let room = recording.capacity() - recording.len();
+        recording.extend_from_slice(&denoised[..denoised.len().min(room)]);
+    }
 
-        // Calculate level
-        let rms = calculate_rms(&processed);
-        let level = if is_muted.load(Ordering::SeqCst) {
-            0.0
-        } else {
-            rms_to_level(rms)
-        };
+    // Muted, or push-to-talk required and not held
+    let mut muted = is_muted.load(Ordering::SeqCst) || push_to_talk.blocks();
 
-        *current_level.lock() = level;
+    // Calculate level
+    let rms = dsp::rms(denoised);
 
-        // Emit level event
+    // Silence timeout: mute here so it holds even if the UI is gone
+    if auto_mute.observe(muted, rms > SPEAKING_THRESHOLD)
+        && !is_muted.swap(true, Ordering::SeqCst)
+    {
+        auto_mute.notify(AutoMuteReason::Silence);
+        muted = true;
+    }
+    let level = if muted {
+        0.0
+    } else {
+        dsp::rms_to_level(rms)
+    };
+
+    *current_level.lock() = level;
+    // The recording, the broadcast and the sidetone hear what peers hear
+    if !muted {
+        call_tap.push_mic(denoised);
+        broadcast_tap.push_mic(denoised);
+        sidetone.push(denoised);
+    }
+    active_speaker
+        .lock()
+        .observe(LOCAL_PEER_ID, if muted { 0.0 } else { rms }, Instant::now());
+
+    // Emit level event (coalesced to the UI rate)
+    let event = AudioLevelEvent {
+        level,
+        is_speaking: !muted && rms > SPEAKING_THRESHOLD,
+        rms,
+    };
+    if let Some(event) = level_throttle.observe(event, Instant::now()) {
         if let Some(app) = app_handle.lock().as_ref() {
-            let event = AudioLevelEvent {
-                level,
-                is_speaking: !is_muted.load(Ordering::SeqCst) && rms > SPEAKING_THRESHOLD,
-                rms,
-            };
-            let _ = app.emit("audio-level", event);
-        }
-
-        // Encode and queue for transmission if not muted
-        if !is_muted.load(Ordering::SeqCst) {
-            // Ensure we have exactly SAMPLES_PER_FRAME samples
-            let to_encode = if processed.len() == SAMPLES_PER_FRAME {
-                processed
-            } else if processed.len() > SAMPLES_PER_FRAME {
-                processed[..SAMPLES_PER_FRAME].to_vec()
-            } else {
-                // Pad with zeros
-                let mut padded = processed;
-                padded.resize(SAMPLES_PER_FRAME, 0.0);
-                padded
-            };
+            let _ = events::emit(app, &event);
+        }
+    }
 
-            if let Some(enc) = encoder.lock().as_mut() {
-                match enc.encode(&to_encode) {
-                    Ok(encoded) => {
-                        let mut ts = timestamp.lock();
-                        let packet = AudioPacket {
-                            data: encoded,
-                            timestamp: *ts,
-                        };
-                        *ts += SAMPLES_PER_FRAME as u64;
+    // Encode and queue for transmission if not muted
+    if !muted {
+        // Ensure we have exactly SAMPLES_PER_FRAME samples (truncate or pad with zeros)
+        denoised.resize(SAMPLES_PER_FRAME, 0.0);
 
-                        if let Some(tx) = outgoing_tx.lock().as_ref() {
-                            let _ = tx.send(packet);
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to encode audio: {}", e);
+        if let Some(enc) = encoder.lock().as_mut() {
+            let encoded = {
+                let _span = perf::span(Component::Encode);
+                enc.encode(denoised)
+            };
+            match encoded {
+                Ok(encoded) => {
+                    let mut ts = timestamp.lock();
+                    let packet = AudioPacket {
+                        data: encoded,
+                        timestamp: *ts,
+                    };
+                    *ts += SAMPLES_PER_FRAME as u64;
+
+                    if outgoing_tx.push(packet) {
+                        tracing::trace!("Outgoing audio queue full, dropped the oldest packet");
                     }
                 }
+                Err(e) => {
+                    tracing::warn!("Failed to encode audio: {}", e);
+                }
             }
         }
     }
 }
 
-/// Calculate RMS of samples
-fn calculate_rms(samples: &[f32]) -> f32 {
-    if samples.is_empty() {
-        return 0.0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{MockBackend, MockSignal};
+    use std::time::Duration;
+
+    #[test]
+    fn mock_backend_drives_capture_and_playback() {
+        let backend = Arc::new(MockBackend::manual(MockSignal::Sine {
+            frequency: 440.0,
+            amplitude: 0.5,
+        }));
+        let service = AudioStreamingService::with_backend(backend.clone());
+        let mut outgoing = service.take_outgoing_receiver().unwrap();
+
+        service.set_noise_suppression(false);
+        service.set_muted(false);
+        tauri::async_runtime::block_on(async {
+            service.start_capture().await.unwrap();
+            service.queue_playback(&[0.25; SAMPLES_PER_FRAME]);
+            service.start_playback().await.unwrap();
+        });
+        // 10 periods of 10ms: 5 frames of 20ms through denoise, level and Opus
+        backend.advance(10);
+        let timestamps: Vec<u64> = tauri::async_runtime::block_on(async {
+            let mut timestamps = Vec::new();
+            for _ in 0..5 {
+                // Only bounds a stalled capture worker, the count doesn't depend on it
+                let packet = tokio::time::timeout(Duration::from_secs(5), outgoing.recv())
+                    .await
+                    .expect("capture worker stalled");
+                timestamps.push(packet.timestamp);
+            }
+            timestamps
+        });
+        service.stop_capture();
+        service.stop_playback();
+
+        let expected: Vec<u64> = (0..5).map(|i| i * SAMPLES_PER_FRAME as u64).collect();
+        assert_eq!(timestamps, expected);
+        assert!(outgoing.try_recv().is_none());
+
+        // The queued frame reached the output device, then silence
+        let played = backend.played();
+        assert_eq!(played.len(), 5 * SAMPLES_PER_FRAME);
+        assert!(played[..SAMPLES_PER_FRAME].iter().all(|&s| s == 0.25));
+        assert!(played[SAMPLES_PER_FRAME..].iter().all(|&s| s == 0.0));
     }
-    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
-    (sum_squares / samples.len() as f32).sqrt()
-}
-
-/// Convert RMS to normalized level
-fn rms_to_level(rms: f32) -> f32 {
-    let db = 20.0 * rms.max(1e-10).log10();
-    let normalized = (db + 60.0) / 60.0;
-    normalized.clamp(0.0, 1.0)
-}
-
-/// Simple linear resampling
-fn resample(samples: &[f32], ratio: f64) -> Vec<f32> {
-    let output_len = (samples.len() as f64 * ratio).ceil() as usize;
-    let mut output = Vec::with_capacity(output_len);
-
-    for i in 0..output_len {
-        let src_idx = i as f64 / ratio;
-        let idx_floor = src_idx.floor() as usize;
-        let idx_ceil = (idx_floor + 1).min(samples.len().saturating_sub(1));
-        let frac = src_idx - idx_floor as f64;
-
-        let sample = if idx_floor < samples.len() {
-            let s1 = samples[idx_floor];
-            let s2 = samples.get(idx_ceil).copied().unwrap_or(s1);
-            s1 + (s2 - s1) * frac as f32
-        } else {
-            0.0
-        };
 
-        output.push(sample);
+    #[test]
+    fn idle_peers_are_reaped() {
+        let service = AudioStreamingService::with_backend(Arc::new(MockBackend::new(
+            MockSignal::Silence,
+        )));
+        let packet = OpusEncoder::new()
+            .unwrap()
+            .encode(&[0.0; SAMPLES_PER_FRAME])
+            .unwrap();
+        // The packet is stamped somewhere between the two
+        let before = Instant::now();
+        service.receive_peer_audio("alice", &packet).unwrap();
+        let after = Instant::now();
+
+        let timeout = PEER_AUDIO_TIMEOUT;
+        assert!(service.reap_idle_peers(timeout, before + timeout).is_empty());
+        let later = after + timeout + Duration::from_millis(1);
+        let reaped = service.reap_idle_peers(timeout, later);
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].0, "alice");
+        assert!(reaped[0].1 > timeout);
+        assert!(service.reap_idle_peers(Duration::ZERO, later).is_empty());
     }
-
-    output
 }
@@ -0,0 +1,427 @@
+//! Live transcription
+//! On-device speech-to-text of the call with whisper.cpp. Opus packets
+//! received from peers are decoded again on a worker thread (decoding is cheap
+//! next to inference), gathered every 20 ms tick (and mixed, unless captions
+//! are per peer), downsampled to the 16kHz whisper expects and cut into
+//! windows ending on a pause or after a few seconds. Each window is
+//! transcribed and its segments reported as captions timed from the start of
+//! the transcription. Audio arriving while the model is busy is dropped.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+use super::encoder::OpusDecoder;
+use super::{FRAME_DURATION_MS, SAMPLES_PER_FRAME};
+use crate::error::{AppError, AppResult};
+
+/// Whisper input rate
+const WHISPER_SAMPLE_RATE: usize = 16000;
+/// 48kHz samples averaged into one 16kHz sample
+const DECIMATION: usize = 3;
+
+/// A window is transcribed once this long...
+const MAX_WINDOW: Duration = Duration::from_secs(6);
+/// ...or after a pause, if it holds at least this much audio
+const MIN_WINDOW: Duration = Duration::from_millis(1500);
+/// Silence that ends a sentence
+const PAUSE: Duration = Duration::from_millis(600);
+/// RMS below which a frame is silence
+const SILENCE_RMS: f32 = 0.01;
+
+/// Opus packets waiting for the worker (~2s of one speaker)
+const QUEUE_PACKETS: usize = 100;
+
+/// Mixed source, when captions are not per peer
+const MIXED: &str = "";
+
+/// Model and transcription options
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionConfig {
+    /// ggml whisper model file (e.g. ggml-base.bin)
+    pub model_path: PathBuf,
+    /// ISO 639-1 code ("fr", "en"...), None to detect the language
+    pub language: Option<String>,
+    /// One caption stream per peer instead of the mixed call audio
+    pub per_peer: bool,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            model_path: PathBuf::new(),
+            language: Some("fr".to_string()),
+            per_peer: false,
+        }
+    }
+}
+
+impl TranscriptionConfig {
+    fn validate(&self) -> AppResult<()> {
+        if !self.model_path.is_file() {
+            return Err(AppError::InvalidArgument(format!(
+                "Whisper model not found: {}",
+                self.model_path.display()
+            )));
+        }
+        if let Some(language) = &self.language {
+            if whisper_rs::get_lang_id(language).is_none() {
+                return Err(AppError::InvalidArgument(format!(
+                    "Unsupported transcription language: {}",
+                    language
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Transcribed segment
+#[derive(Debug, Clone, Serialize)]
+pub struct Caption {
+    /// Speaking peer, None for the mixed call audio
+    pub peer_id: Option<String>,
+    pub text: String,
+    /// From the start of the transcription
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Transcription state
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionStatus {
+    pub active: bool,
+    pub language: Option<String>,
+    pub per_peer: bool,
+    pub captions: u64,
+}
+
+/// Receives the captions, on the worker thread
+pub type CaptionSink = Box<dyn Fn(Caption) + Send>;
+
+struct Session {
+    config: TranscriptionConfig,
+    tx: SyncSender<(String, Vec<u8>)>,
+    worker: JoinHandle<u64>,
+}
+
+/// Live transcription of the audio received from peers
+#[derive(Clone, Default)]
+pub struct Transcriber {
+    session: Arc<Mutex<Option<Session>>>,
+    /// Captions of the last session, once stopped
+    last_captions: Arc<Mutex<u64>>,
+}
+
+impl Transcriber {
+    /// Load the model and start transcribing (replaces the running session)
+    pub fn start(&self, config: TranscriptionConfig, on_caption: CaptionSink) -> AppResult<()> {
+        config.validate()?;
+        self.stop();
+
+        let path = config.model_path.to_string_lossy().to_string();
+        let context =
+            WhisperContext::new_with_params(&path, WhisperContextParameters::default())
+                .map_err(|e| AppError::Audio(format!("Failed to load the whisper model: {}", e)))?;
+
+        let (tx, rx) = mpsc::sync_channel(QUEUE_PACKETS);
+        let worker_config = config.clone();
+        let worker = std::thread::Builder::new()
+            .name("transcription".to_string())
+            .spawn(move || run(context, worker_config, rx, on_caption))
+            .map_err(|e| AppError::Internal(format!("Failed to spawn transcription: {}", e)))?;
+
+        tracing::info!(
+            "Transcription started ({}, language {})",
+            path,
+            config.language.as_deref().unwrap_or("auto")
+        );
+        *self.session.lock() = Some(Session { config, tx, worker });
+        Ok(())
+    }
+
+    /// Stop transcribing (the window in progress is dropped)
+    pub fn stop(&self) {
+        let Some(session) = self.session.lock().take() else {
+            return;
+        };
+        drop(session.tx);
+        // The worker exits after the inference in progress, if any
+        let captions = session.worker.join().unwrap_or(0);
+        *self.last_captions.lock() = captions;
+        tracing::info!("Transcription stopped ({} captions)", captions);
+    }
+
+    /// Queue an Opus packet received from a peer
+    pub fn push(&self, peer_id: &str, opus: &[u8]) {
+        if let Some(session) = self.session.lock().as_ref() {
+            let _ = session.tx.try_send((peer_id.to_string(), opus.to_vec()));
+        }
+    }
+
+    pub fn status(&self) -> TranscriptionStatus {
+        let session = self.session.lock();
+        TranscriptionStatus {
+            active: session.is_some(),
+            language: session.as_ref().and_then(|s| s.config.language.clone()),
+            per_peer: session.as_ref().is_some_and(|s| s.config.per_peer),
+            captions: *self.last_captions.lock(),
+        }
+    }
+}
+
+/// Audio of one source (a peer, or the whole call)
+#[derive(Default)]
+struct Window {
+    /// 16kHz samples not transcribed yet
+    samples: Vec<f32>,
+    /// Position of `samples[0]` from the start, in 16kHz samples
+    offset: usize,
+    /// Trailing silence, in 16kHz samples
+    silence: usize,
+    /// When audio of this source was last received
+    last_heard: Option<Instant>,
+}
+
+impl Window {
+    fn duration(samples: usize) -> Duration {
+        Duration::from_millis((samples * 1000 / WHISPER_SAMPLE_RATE) as u64)
+    }
+
+    /// Append 48kHz samples, returning whether the window should be transcribed
+    fn push(&mut self, samples_48k: &[f32]) -> bool {
+        let start = self.samples.len();
+        self.samples.extend(
+            samples_48k
+                .chunks_exact(DECIMATION)
+                .map(|c| c.iter().sum::<f32>() / DECIMATION as f32),
+        );
+
+        let added = &self.samples[start..];
+        let rms = (added.iter().map(|s| s * s).sum::<f32>() / added.len().max(1) as f32).sqrt();
+        if rms < SILENCE_RMS {
+            self.silence += added.len();
+        } else {
+            self.silence = 0;
+        }
+
+        let len = Self::duration(self.samples.len());
+        len >= MAX_WINDOW || (len >= MIN_WINDOW && Self::duration(self.silence) >= PAUSE)
+    }
+
+    /// Hand the samples over for transcription
+    fn take(&mut self) -> (Vec<f32>, usize) {
+        let offset = self.offset;
+        self.offset += self.samples.len();
+        self.silence = 0;
+        (std::mem::take(&mut self.samples), offset)
+    }
+
+    /// Nothing but silence: skip it rather than have whisper hallucinate
+    fn is_silent(&self) -> bool {
+        self.silence >= self.samples.len()
+    }
+}
+
+/// Sum the audio of every peer over one tick into a single buffer
+/// (None when nobody sent anything)
+fn mix<'a>(buffers: impl IntoIterator<Item = &'a Vec<f32>>) -> Option<Vec<f32>> {
+    let mut mixed: Option<Vec<f32>> = None;
+    for buffer in buffers {
+        let mixed = mixed.get_or_insert_with(Vec::new);
+        if mixed.len() < buffer.len() {
+            mixed.resize(buffer.len(), 0.0);
+        }
+        for (out, sample) in mixed.iter_mut().zip(buffer) {
+            *out += sample;
+        }
+    }
+    mixed.map(|mut mixed| {
+        mixed.iter_mut().for_each(|s| *s = s.clamp(-1.0, 1.0));
+        mixed
+    })
+}
+
+/// Worker: decode, window and transcribe until the session is dropped
+fn run(
+    context: WhisperContext,
+    config: TranscriptionConfig,
+    rx: Receiver<(String, Vec<u8>)>,
+    on_caption: CaptionSink,
+) -> u64 {
+    let mut state = match context.create_state() {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::error!("Failed to create the whisper state: {}", e);
+            return 0;
+        }
+    };
+    let mut decoders: HashMap<String, OpusDecoder> = HashMap::new();
+    let mut windows: HashMap<String, Window> = HashMap::new();
+    let mut captions = 0;
+    let tick = Duration::from_millis(FRAME_DURATION_MS as u64);
+    let idle = tick * 10;
+    let idle_silence = [0.0f32; SAMPLES_PER_FRAME * 10];
+    let mut next_tick = Instant::now() + tick;
+
+    loop {
+        // Audio received from each peer during this tick
+        let mut frames: HashMap<String, Vec<f32>> = HashMap::new();
+        loop {
+            let (peer_id, opus) =
+                match rx.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+                    Ok(packet) => packet,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return captions,
+                };
+            let decoder = match decoders.entry(peer_id.clone()) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => match OpusDecoder::new() {
+                    Ok(decoder) => e.insert(decoder),
+                    Err(e) => {
+                        tracing::warn!("Transcription decoder: {}", e);
+                        continue;
+                    }
+                },
+            };
+            if let Ok(samples) = decoder.decode(&opus) {
+                frames.entry(peer_id).or_default().extend(samples);
+            }
+        }
+        // Behind after a transcription: start over from now
+        let now = Instant::now();
+        next_tick = (next_tick + tick).max(now);
+
+        let heard: Vec<(String, Vec<f32>)> = if config.per_peer {
+            frames.into_iter().collect()
+        } else {
+            mix(frames.values())
+                .map(|mixed| (MIXED.to_string(), mixed))
+                .into_iter()
+                .collect()
+        };
+        let mut ready = Vec::new();
+        for (source, samples) in heard {
+            let window = windows.entry(source.clone()).or_default();
+            window.last_heard = Some(now);
+            if window.push(&samples) {
+                ready.push(source);
+            }
+        }
+        // Sources nothing was received from for a while (muted, or someone
+        // else talking): keep their timeline moving so the last sentence
+        // ends on the pause
+        for (source, window) in windows.iter_mut() {
+            let last_heard = *window.last_heard.get_or_insert(now);
+            if now.duration_since(last_heard) >= idle {
+                window.last_heard = Some(now);
+                if window.push(&idle_silence) && !ready.contains(source) {
+                    ready.push(source.clone());
+                }
+            }
+        }
+
+        for source in ready {
+            let Some(window) = windows.get_mut(&source) else {
+                continue;
+            };
+            let silent = window.is_silent();
+            let (audio, offset) = window.take();
+            if !silent {
+                let peer_id = config.per_peer.then_some(source);
+                captions += transcribe(&mut state, &config, peer_id, &audio, offset, &on_caption);
+            }
+        }
+    }
+}
+
+/// Transcribe a window, returning the number of captions reported
+fn transcribe(
+    state: &mut WhisperState,
+    config: &TranscriptionConfig,
+    peer_id: Option<String>,
+    audio: &[f32],
+    offset: usize,
+    on_caption: &CaptionSink,
+) -> u64 {
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(config.language.as_deref().unwrap_or("auto")));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    params.set_suppress_blank(true);
+
+    if let Err(e) = state.full(params, audio) {
+        tracing::warn!("Transcription failed: {}", e);
+        return 0;
+    }
+
+    let offset_ms = (offset * 1000 / WHISPER_SAMPLE_RATE) as u64;
+    let mut captions = 0;
+    for i in 0..state.full_n_segments().unwrap_or(0) {
+        let Ok(text) = state.full_get_segment_text(i) else {
+            continue;
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        // Segment times are in centiseconds
+        let t0 = state.full_get_segment_t0(i).unwrap_or(0).max(0) as u64 * 10;
+        let t1 = state.full_get_segment_t1(i).unwrap_or(0).max(0) as u64 * 10;
+        on_caption(Caption {
+            peer_id: peer_id.clone(),
+            text: text.to_string(),
+            start_ms: offset_ms + t0,
+            end_ms: offset_ms + t1,
+        });
+        captions += 1;
+    }
+    captions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_closes_on_a_pause_after_speech() {
+        let mut window = Window::default();
+        let voice = vec![0.2f32; SAMPLES_PER_FRAME];
+        let silence = vec![0.0f32; SAMPLES_PER_FRAME];
+
+        // 1.6s of voice, then silence until the pause is long enough
+        for _ in 0..80 {
+            assert!(!window.push(&voice));
+        }
+        let mut frames = 0;
+        while !window.push(&silence) {
+            frames += 1;
+        }
+        assert_eq!(frames + 1, 30);
+
+        let (audio, offset) = window.take();
+        assert_eq!(offset, 0);
+        assert_eq!(audio.len(), 110 * SAMPLES_PER_FRAME / DECIMATION);
+        assert_eq!(window.offset, audio.len());
+    }
+
+    #[test]
+    fn mix_sums_the_peers_of_a_tick() {
+        assert_eq!(mix(&Vec::<Vec<f32>>::new()), None);
+        let a = vec![0.25f32, 0.5, 0.75];
+        let b = vec![0.25f32, 0.75];
+        assert_eq!(mix([&a, &b]), Some(vec![0.5, 1.0, 0.75]));
+    }
+}
@@ -0,0 +1,220 @@
+//! Text-to-speech
+//! Speaks short texts with the platform engine: espeak-ng on Linux, `say` on
+//! macOS and SAPI (through PowerShell) on Windows. The engine renders a WAV
+//! file that is read back and resampled to 48kHz, so speech goes through the
+//! playback path (output device, equalizer, volume) like a peer's voice.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::dsp::{self, Resampler};
+use super::SAMPLE_RATE;
+use crate::error::{AppError, AppResult};
+
+/// Speech rate range, 1 being the engine's normal pace
+pub const MIN_TTS_RATE: f32 = 0.5;
+pub const MAX_TTS_RATE: f32 = 2.0;
+
+/// Longer texts are cut rather than read for minutes
+pub const MAX_TTS_CHARS: usize = 300;
+
+/// Pace of espeak-ng and `say` at rate 1, in words per minute
+#[cfg(not(target_os = "windows"))]
+const BASE_WPM: f32 = 175.0;
+
+/// Render `text` in `language` (ISO 639-1) as 48kHz mono samples
+pub fn synthesize(text: &str, rate: f32, language: &str) -> AppResult<Vec<f32>> {
+    let text: String = text.chars().take(MAX_TTS_CHARS).collect();
+    let path = std::env::temp_dir().join(format!("hydrowland-tts-{}.wav", uuid::Uuid::new_v4()));
+
+    let result = run_engine(&text, rate, language, &path).and_then(|()| {
+        let bytes = std::fs::read(&path)
+            .map_err(|e| AppError::Audio(format!("Failed to read synthesized speech: {}", e)))?;
+        decode_wav(&bytes)
+    });
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn engine_command(rate: f32, language: &str, path: &Path) -> Command {
+    let mut command = Command::new("espeak-ng");
+    command
+        .arg("-v")
+        .arg(language)
+        .arg("-s")
+        .arg(((BASE_WPM * rate) as u32).to_string())
+        .arg("-w")
+        .arg(path)
+        .arg("--stdin");
+    command
+}
+
+/// `say` keeps the system voice, which follows the OS language
+#[cfg(target_os = "macos")]
+fn engine_command(rate: f32, _language: &str, path: &Path) -> Command {
+    let mut command = Command::new("say");
+    command
+        .arg("-r")
+        .arg(((BASE_WPM * rate) as u32).to_string())
+        .arg("-o")
+        .arg(path)
+        .arg("--file-format=WAVE")
+        .arg("--data-format=LEI16@22050")
+        .arg("-f")
+        .arg("-");
+    command
+}
+
+/// SAPI's default voice follows the OS language
+#[cfg(target_os = "windows")]
+fn engine_command(rate: f32, _language: &str, path: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // SAPI rates go from -10 to 10, about doubling the pace every 10 steps
+    let sapi_rate = (rate.log2() * 10.0).round().clamp(-10.0, 10.0) as i32;
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         [Console]::InputEncoding = [Text.Encoding]::UTF8; \
+         $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         $s.Rate = {}; \
+         $s.SetOutputToWaveFile('{}'); \
+         $s.Speak([Console]::In.ReadToEnd()); \
+         $s.Dispose()",
+        sapi_rate,
+        path.display().to_string().replace('\'', "''")
+    );
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+/// Run the engine, the text going through stdin (never parsed as options)
+fn run_engine(text: &str, rate: f32, language: &str, path: &Path) -> AppResult<()> {
+    let mut child = engine_command(rate, language, path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::Audio(format!("Text-to-speech engine unavailable: {}", e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| AppError::Audio(format!("Failed to send text to speak: {}", e)))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| AppError::Audio(format!("Text-to-speech engine failed: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::Audio(format!(
+            "Text-to-speech engine failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// 16-bit PCM WAV to 48kHz mono (also used for custom sound cues)
+pub(super) fn decode_wav(bytes: &[u8]) -> AppResult<Vec<f32>> {
+    let invalid = || AppError::Audio("Invalid WAV from the text-to-speech engine".to_string());
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid());
+    }
+
+    // (channels, sample rate)
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes([
+            bytes[pos + 4],
+            bytes[pos + 5],
+            bytes[pos + 6],
+            bytes[pos + 7],
+        ]) as usize;
+        // Engines writing to a pipe leave the size unset: stop at the end of the file
+        let body = &bytes[pos + 8..(pos + 8).saturating_add(size).min(bytes.len())];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let tag = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]) as usize;
+                let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                // PCM, or WAVE_FORMAT_EXTENSIBLE holding PCM
+                if !(tag == 1 || tag == 0xFFFE) || bits != 16 || channels == 0 || rate == 0 {
+                    return Err(AppError::Audio(format!(
+                        "Unsupported WAV from the text-to-speech engine (format {}, {} bits)",
+                        tag, bits
+                    )));
+                }
+                format = Some((channels, rate));
+            }
+            b"data" => {
+                let (channels, rate) = format.ok_or_else(invalid)?;
+                let pcm: Vec<i16> = body
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .collect();
+                let mut interleaved = Vec::with_capacity(pcm.len());
+                dsp::i16_to_f32_into(&pcm, &mut interleaved);
+                let mut mono = Vec::with_capacity(interleaved.len() / channels);
+                dsp::downmix_into(&interleaved, channels, &mut mono);
+
+                if rate == SAMPLE_RATE {
+                    return Ok(mono);
+                }
+                let mut samples = Vec::new();
+                Resampler::new(SAMPLE_RATE as f64 / rate as f64).process_into(&mono, &mut samples);
+                return Ok(samples);
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        pos = pos.saturating_add(8 + size + (size & 1));
+    }
+    Err(invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_is_decoded_and_resampled_to_48k() {
+        let pcm: Vec<i16> = (0..2205)
+            .map(|i| if i % 2 == 0 { 16384 } else { -16384 })
+            .collect();
+        let data: Vec<u8> = pcm.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&22050u32.to_le_bytes());
+        wav.extend_from_slice(&44100u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+
+        // 100ms at 22.05kHz
+        let samples = decode_wav(&wav).unwrap();
+        assert!(
+            (samples.len() as i64 - 4800).abs() <= 2,
+            "{} samples",
+            samples.len()
+        );
+        assert!(samples.iter().all(|s| s.abs() <= 0.5 + 1e-6));
+        assert!(decode_wav(b"RIFF\0\0\0\0WAVE").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Voice messages
+//! Short clips recorded from the processed capture (the mic check recording),
+//! Opus-encoded in 20ms packets like live voice and sent to the room as
+//! binary data channel messages. A clip is split into chunks small enough for
+//! any SCTP stack; receivers put them back together and keep the clip until
+//! it is played.
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::encoder::{OpusDecoder, OpusEncoder};
+use super::{FRAME_DURATION_MS, SAMPLES_PER_FRAME};
+use crate::error::{AppError, AppResult};
+
+/// Longest clip, the recording stops there
+pub const MAX_VOICE_MESSAGE_DURATION: Duration = Duration::from_secs(60);
+
+/// Shorter than this, the recording is discarded (a click on the button)
+pub const MIN_VOICE_MESSAGE_DURATION: Duration = Duration::from_millis(300);
+
+/// Voice messages are speech only: a lower bitrate than live voice keeps a
+/// full-length clip around 200 KB
+const VOICE_MESSAGE_BITRATE: i32 = 24000;
+
+/// Tag of a voice message chunk (other binary messages are ignored)
+const MAGIC: &[u8; 4] = b"HVM1";
+
+/// Magic, clip id (UUID), chunk index, chunk count
+const CHUNK_HEADER_LEN: usize = 4 + 16 + 2 + 2;
+
+/// Clip bytes per chunk, well below the usual 64 KB SCTP message limit
+const CHUNK_PAYLOAD: usize = 16 * 1024;
+
+/// Chunks of the longest clip, with room for the encoder overshooting its
+/// bitrate (a full-length clip is about 190 KB, 12 chunks)
+const MAX_CHUNKS: usize = 13;
+
+/// Clips whose chunks stop arriving are dropped after this long
+const INCOMPLETE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Clips of one peer being received at once, the oldest is dropped beyond
+const MAX_PARTIAL_PER_PEER: usize = 2;
+
+/// What a chat shows of a voice message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceMessageInfo {
+    pub id: String,
+    pub sender: String,
+    pub duration_ms: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// Encoded voice message
+#[derive(Debug, Clone)]
+pub struct VoiceClip {
+    pub info: VoiceMessageInfo,
+    packets: Vec<Vec<u8>>,
+}
+
+impl VoiceClip {
+    /// Encode 48kHz mono samples (cut at `MAX_VOICE_MESSAGE_DURATION`)
+    pub fn encode(sender: &str, samples: &[f32]) -> AppResult<Self> {
+        let max_frames =
+            (MAX_VOICE_MESSAGE_DURATION.as_millis() / FRAME_DURATION_MS as u128) as usize;
+        let mut encoder = OpusEncoder::new()?;
+        encoder.set_bitrate(VOICE_MESSAGE_BITRATE)?;
+
+        let mut frame = Vec::with_capacity(SAMPLES_PER_FRAME);
+        let mut packets = Vec::new();
+        for chunk in samples.chunks(SAMPLES_PER_FRAME).take(max_frames) {
+            frame.clear();
+            frame.extend_from_slice(chunk);
+            frame.resize(SAMPLES_PER_FRAME, 0.0);
+            packets.push(encoder.encode(&frame)?);
+        }
+
+        Ok(Self {
+            info: VoiceMessageInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                sender: sender.to_string(),
+                duration_ms: packets.len() as u64 * FRAME_DURATION_MS as u64,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default(),
+            },
+            packets,
+        })
+    }
+
+    /// Decode to 48kHz mono samples
+    pub fn decode(&self) -> AppResult<Vec<f32>> {
+        let mut decoder = OpusDecoder::new()?;
+        let mut samples = Vec::with_capacity(self.packets.len() * SAMPLES_PER_FRAME);
+        for packet in &self.packets {
+            samples.extend(decoder.decode(packet)?);
+        }
+        Ok(samples)
+    }
+
+    /// Binary messages carrying the clip
+    pub fn to_chunks(&self) -> AppResult<Vec<Bytes>> {
+        let id = uuid::Uuid::parse_str(&self.info.id)
+            .map_err(|e| AppError::Internal(format!("Invalid voice message id: {}", e)))?;
+        let info = serde_json::to_vec(&self.info)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize voice message: {}", e)))?;
+
+        // Payload: info length and JSON, then length-prefixed Opus packets
+        let mut payload = BytesMut::new();
+        payload.put_u32(info.len() as u32);
+        payload.put_slice(&info);
+        for packet in &self.packets {
+            payload.put_u16(packet.len() as u16);
+            payload.put_slice(packet);
+        }
+
+        // Receivers drop longer clips
+        let count = payload.len().div_ceil(CHUNK_PAYLOAD);
+        if count > MAX_CHUNKS {
+            return Err(AppError::InvalidArgument(
+                "Voice message too long".to_string(),
+            ));
+        }
+        Ok(payload
+            .chunks(CHUNK_PAYLOAD)
+            .enumerate()
+            .map(|(index, data)| {
+                let mut chunk = BytesMut::with_capacity(CHUNK_HEADER_LEN + data.len());
+                chunk.put_slice(MAGIC);
+                chunk.put_slice(id.as_bytes());
+                chunk.put_u16(index as u16);
+                chunk.put_u16(count as u16);
+                chunk.put_slice(data);
+                chunk.freeze()
+            })
+            .collect())
+    }
+
+    fn from_payload(payload: &[u8]) -> Option<Self> {
+        let (len, rest) = split_u32(payload)?;
+        let (info, mut rest) = split_at(rest, len as usize)?;
+        let info: VoiceMessageInfo = serde_json::from_slice(info).ok()?;
+
+        let mut packets = Vec::new();
+        while !rest.is_empty() {
+            let (len, tail) = split_u16(rest)?;
+            let (packet, tail) = split_at(tail, len as usize)?;
+            packets.push(packet.to_vec());
+            rest = tail;
+        }
+        Some(Self { info, packets })
+    }
+}
+
+fn split_at(data: &[u8], mid: usize) -> Option<(&[u8], &[u8])> {
+    (data.len() >= mid).then(|| data.split_at(mid))
+}
+
+fn split_u16(data: &[u8]) -> Option<(u16, &[u8])> {
+    let (head, rest) = split_at(data, 2)?;
+    Some((u16::from_be_bytes([head[0], head[1]]), rest))
+}
+
+fn split_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    let (head, rest) = split_at(data, 4)?;
+    Some((
+        u32::from_be_bytes([head[0], head[1], head[2], head[3]]),
+        rest,
+    ))
+}
+
+/// Chunks received so far of a clip
+struct PartialClip {
+    chunks: Vec<Option<Bytes>>,
+    received: usize,
+    started: Instant,
+}
+
+/// Puts received clips back together, per sending peer
+#[derive(Default)]
+pub struct VoiceMessageAssembler {
+    partial: HashMap<(String, [u8; 16]), PartialClip>,
+}
+
+impl VoiceMessageAssembler {
+    /// Add a binary message from `peer_id`, returning the clip it completes
+    pub fn push(&mut self, peer_id: &str, data: &[u8]) -> Option<VoiceClip> {
+        let now = Instant::now();
+        self.partial
+            .retain(|_, clip| now.duration_since(clip.started) < INCOMPLETE_TIMEOUT);
+
+        if data.len() < CHUNK_HEADER_LEN || &data[..4] != MAGIC {
+            return None;
+        }
+        let id: [u8; 16] = data[4..20].try_into().ok()?;
+        let index = u16::from_be_bytes([data[20], data[21]]) as usize;
+        let count = u16::from_be_bytes([data[22], data[23]]) as usize;
+        if index >= count || count > MAX_CHUNKS {
+            return None;
+        }
+
+        let key = (peer_id.to_string(), id);
+        if !self.partial.contains_key(&key) {
+            self.make_room(peer_id);
+        }
+        let clip = self
+            .partial
+            .entry(key.clone())
+            .or_insert_with(|| PartialClip {
+                chunks: vec![None; count],
+                received: 0,
+                started: now,
+            });
+        if clip.chunks.len() != count {
+            return None;
+        }
+        if clip.chunks[index].is_none() {
+            clip.chunks[index] = Some(Bytes::copy_from_slice(&data[CHUNK_HEADER_LEN..]));
+            clip.received += 1;
+        }
+        if clip.received < count {
+            return None;
+        }
+
+        let clip = self.partial.remove(&key)?;
+        let payload: Vec<u8> = clip.chunks.into_iter().flatten().flatten().collect();
+        VoiceClip::from_payload(&payload)
+    }
+
+    /// Drop the oldest clips `peer_id` is sending so a new one fits
+    fn make_room(&mut self, peer_id: &str) {
+        let from_peer = |key: &(String, [u8; 16])| key.0 == peer_id;
+        while self.partial.keys().filter(|key| from_peer(key)).count() >= MAX_PARTIAL_PER_PEER {
+            let oldest = self
+                .partial
+                .iter()
+                .filter(|(key, _)| from_peer(key))
+                .min_by_key(|(_, clip)| clip.started)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                tracing::debug!("Dropping an incomplete voice message from {}", peer_id);
+                self.partial.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_reassemble_in_any_order() {
+        let clip = VoiceClip {
+            info: VoiceMessageInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                sender: "Alice".to_string(),
+                duration_ms: 20_000,
+                timestamp: 1_700_000_000,
+            },
+            packets: (0..1000).map(|i| vec![i as u8; 60]).collect(),
+        };
+        let mut chunks = clip.to_chunks().unwrap();
+        assert!(chunks.len() > 1);
+        chunks.reverse();
+
+        let mut assembler = VoiceMessageAssembler::default();
+        let last = chunks.pop().unwrap();
+        for chunk in &chunks {
+            assert!(assembler.push("peer", chunk).is_none());
+        }
+        let received = assembler.push("peer", &last).unwrap();
+        assert_eq!(received.info.id, clip.info.id);
+        assert_eq!(received.info.duration_ms, 20_000);
+        assert_eq!(received.packets, clip.packets);
+    }
+
+    #[test]
+    fn partial_clips_are_capped() {
+        let chunk = |id: u8, count: u16| {
+            let mut chunk = MAGIC.to_vec();
+            chunk.extend_from_slice(&[id; 16]);
+            chunk.extend_from_slice(&0u16.to_be_bytes());
+            chunk.extend_from_slice(&count.to_be_bytes());
+            chunk.extend_from_slice(b"data");
+            chunk
+        };
+        let mut assembler = VoiceMessageAssembler::default();
+        // A chunk count no clip can reach is not waited for
+        assert!(assembler.push("peer", &chunk(1, u16::MAX)).is_none());
+        assert!(assembler.partial.is_empty());
+
+        for id in 1..=4 {
+            assembler.push("peer", &chunk(id, 2));
+        }
+        assembler.push("other", &chunk(1, 2));
+        assert_eq!(assembler.partial.len(), MAX_PARTIAL_PER_PEER + 1);
+        let newest = ("peer".to_string(), [4; 16]);
+        assert!(assembler.partial.contains_key(&newest));
+    }
+}
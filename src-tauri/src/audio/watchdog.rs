@@ -0,0 +1,95 @@
+//! Stream watchdog
+//! cpal callbacks sometimes just stop (device sleep, driver hiccup) without any
+//! stream error: the level meter freezes and nothing is sent. The streams bump a
+//! callback counter; the watchdog restarts a stream whose counter stops moving.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a running stream may go without a callback (they normally fire every ~10ms)
+pub const STALL_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// How often the watchdog looks at the counters
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Which stream stalled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamKind {
+    Capture,
+    Playback,
+}
+
+/// Callback counter, bumped from the audio thread
+#[derive(Clone, Default)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Detects a counter that stopped moving for longer than the timeout
+pub struct StallDetector {
+    timeout: Duration,
+    last_count: u64,
+    last_progress: Instant,
+}
+
+impl StallDetector {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_count: 0,
+            last_progress: Instant::now(),
+        }
+    }
+
+    /// Feed the current counter; returns how long it has been stuck once that
+    /// exceeds the timeout (then starts over, so a failed restart is retried
+    /// one timeout later rather than on every check)
+    pub fn check(&mut self, running: bool, count: u64, now: Instant) -> Option<Duration> {
+        if !running || count != self.last_count {
+            self.last_count = count;
+            self.last_progress = now;
+            return None;
+        }
+
+        let stalled = now.duration_since(self.last_progress);
+        if stalled < self.timeout {
+            return None;
+        }
+        self.last_progress = now;
+        Some(stalled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_per_timeout_while_stuck() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(Duration::from_millis(100));
+
+        assert_eq!(detector.check(true, 1, start), None);
+        assert_eq!(detector.check(true, 2, start + Duration::from_millis(90)), None);
+        // Stopped moving at 90ms
+        assert_eq!(detector.check(true, 2, start + Duration::from_millis(150)), None);
+        assert_eq!(
+            detector.check(true, 2, start + Duration::from_millis(200)),
+            Some(Duration::from_millis(110))
+        );
+        assert_eq!(detector.check(true, 2, start + Duration::from_millis(250)), None);
+        // Not running: never a stall
+        assert_eq!(detector.check(false, 2, start + Duration::from_secs(5)), None);
+    }
+}
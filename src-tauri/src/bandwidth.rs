@@ -0,0 +1,136 @@
+//! Bandwidth caps
+//! Upload and download limits set by the user, shared between what the call
+//! sends: voice first (small, and it can't wait), then the screen share, then
+//! voice message uploads (the only paced transfer). Every stream goes to each
+//! peer of the mesh, so the upload is split between the peers before being
+//! shared out.
+//!
+//! The download cap is advertised to the peers, who keep their upload under
+//! it: the peer with the smallest cap receives from every other participant,
+//! so its cap split between them bounds each sender.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+
+/// Lowest cap accepted (voice alone needs a few dozen kbps)
+pub const MIN_CAP_KBPS: u32 = 32;
+
+/// Voice gets up to half of each peer's share, within the Opus range
+const AUDIO_SHARE: f64 = 0.5;
+const MIN_AUDIO_KBPS: f64 = 6.0;
+const MAX_AUDIO_KBPS: f64 = 64.0;
+
+/// Part of what voice leaves that goes to the screen share while it runs,
+/// transfers get the rest
+const SCREEN_SHARE: f64 = 0.8;
+/// Lowest screen share bitrate handed out (0 would read as "uncapped" to the encoder)
+pub const MIN_SCREEN_KBPS: u32 = 50;
+
+/// Transfers never stall completely
+const MIN_TRANSFER_KBPS: f64 = 8.0;
+
+/// Limits set by the user (persisted)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthLimits {
+    /// None = no cap
+    pub upload_kbps: Option<u32>,
+    pub download_kbps: Option<u32>,
+}
+
+impl BandwidthLimits {
+    pub fn validate(&self) -> AppResult<()> {
+        let too_low = |cap: Option<u32>| cap.is_some_and(|kbps| kbps < MIN_CAP_KBPS);
+        if too_low(self.upload_kbps) || too_low(self.download_kbps) {
+            return Err(AppError::InvalidArgument(format!(
+                "Bandwidth caps must be at least {} kbps",
+                MIN_CAP_KBPS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// How the upload budget is shared out, in kbps (None = not limited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Allocation {
+    /// Budget shared out: our cap or the peers' bound, whichever is lower
+    pub upload_kbps: Option<u32>,
+    /// Opus bitrate of the voice stream (each peer gets a copy)
+    pub audio_kbps: Option<u32>,
+    /// Screen share bitrate (each peer gets a copy)
+    pub screen_kbps: Option<u32>,
+    /// Voice message upload rate, all peers together
+    pub transfer_kbps: Option<u32>,
+}
+
+/// Upload bound set by the peers' download caps: the smallest, split between
+/// the `senders` it receives from
+pub fn peer_bound(download_caps: impl IntoIterator<Item = u32>, senders: usize) -> Option<u32> {
+    download_caps
+        .into_iter()
+        .min()
+        .map(|cap| cap / senders.max(1) as u32)
+}
+
+/// Share `upload_kbps` out for streams sent to `peers` peers
+pub fn allocate(upload_kbps: Option<u32>, peers: usize, screen_active: bool) -> Allocation {
+    let Some(total) = upload_kbps else {
+        return Allocation::default();
+    };
+    let peers = peers.max(1) as f64;
+    let per_peer = total as f64 / peers;
+
+    let audio = (per_peer * AUDIO_SHARE).clamp(MIN_AUDIO_KBPS, MAX_AUDIO_KBPS);
+    let rest = (per_peer - audio).max(0.0);
+    let (screen, transfer) = if screen_active {
+        (rest * SCREEN_SHARE, rest * (1.0 - SCREEN_SHARE))
+    } else {
+        (rest, rest)
+    };
+
+    Allocation {
+        upload_kbps: Some(total),
+        audio_kbps: Some(audio as u32),
+        screen_kbps: Some((screen as u32).max(MIN_SCREEN_KBPS)),
+        transfer_kbps: Some((transfer * peers).max(MIN_TRANSFER_KBPS) as u32),
+    }
+}
+
+/// Time sending `bytes` takes at `kbps`
+pub fn pacing_delay(bytes: usize, kbps: u32) -> Duration {
+    Duration::from_secs_f64(bytes as f64 * 8.0 / (kbps.max(1) as f64 * 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_is_served_first_and_peers_bound_the_budget() {
+        assert_eq!(allocate(None, 3, true), Allocation::default());
+
+        // 1000 kbps to 2 peers: 500 each, voice at its full bitrate
+        let allocation = allocate(Some(1000), 2, true);
+        assert_eq!(allocation.audio_kbps, Some(64));
+        assert_eq!(allocation.screen_kbps, Some(348));
+        assert_eq!(allocation.transfer_kbps, Some(174));
+
+        // Tight budget: voice takes half, no screen share running
+        let allocation = allocate(Some(64), 1, false);
+        assert_eq!(allocation.audio_kbps, Some(32));
+        assert_eq!(allocation.transfer_kbps, Some(32));
+        // Never down to 0, which means "uncapped" to the screen encoder
+        assert_eq!(
+            allocate(Some(32), 4, true).screen_kbps,
+            Some(MIN_SCREEN_KBPS)
+        );
+
+        // A peer capped at 600 kbps hears 3 senders
+        assert_eq!(peer_bound([2000, 600], 3), Some(200));
+        assert_eq!(peer_bound([], 3), None);
+        assert_eq!(pacing_delay(1000, 8), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,191 @@
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const BANS_FILE: &str = "bans.json";
+
+#[derive(Error, Debug)]
+pub enum BanError {
+    #[error("Peer is not banned")]
+    NotFound,
+    #[error("Peer is already banned")]
+    AlreadyBanned,
+    #[error("Unknown peer identity")]
+    UnknownIdentity,
+    #[error("Config error: {0}")]
+    ConfigError(String),
+}
+
+/// Une identité bannie (empreinte DTLS du certificat du peer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub fingerprint: String,
+    pub username: String,
+    pub reason: Option<String>,
+    pub banned_at: u64,
+}
+
+/// Liste des bans persistée dans le dossier de config
+#[derive(Clone, Default)]
+pub struct BanList {
+    entries: Arc<RwLock<Vec<BanEntry>>>,
+}
+
+impl BanList {
+    /// Charger la liste depuis le fichier
+    pub fn load() -> Self {
+        let entries: Vec<BanEntry> = storage::load_json(BANS_FILE).unwrap_or_default();
+        if !entries.is_empty() {
+            tracing::info!("Loaded {} banned identities", entries.len());
+        }
+        Self {
+            entries: Arc::new(RwLock::new(entries)),
+        }
+    }
+
+    /// Vérifier si une empreinte est bannie
+    pub fn is_banned(&self, fingerprint: &str) -> bool {
+        self.entries.read().iter().any(|e| e.fingerprint == fingerprint)
+    }
+
+    /// Refuser un peer dont l'identité est bannie (sans empreinte, rien à vérifier)
+    pub fn check_not_banned(&self, peer_id: &str, fingerprint: Option<&str>) -> AppResult<()> {
+        if let Some(fp) = fingerprint {
+            if self.is_banned(fp) {
+                tracing::warn!("Rejected banned peer {} ({})", peer_id, fp);
+                return Err(AppError::PermissionDenied(format!(
+                    "Peer {} is banned",
+                    peer_id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bannir l'identité (empreinte, pseudo) d'un peer, si on la connaît
+    pub fn ban_identity(
+        &self,
+        identity: Option<(String, String)>,
+        reason: Option<String>,
+    ) -> Result<BanEntry, BanError> {
+        let (fingerprint, username) = identity.ok_or(BanError::UnknownIdentity)?;
+        self.ban(fingerprint, username, reason)
+    }
+
+    /// Bannir une identité
+    pub fn ban(
+        &self,
+        fingerprint: String,
+        username: String,
+        reason: Option<String>,
+    ) -> Result<BanEntry, BanError> {
+        let mut entries = self.entries.write();
+        if entries.iter().any(|e| e.fingerprint == fingerprint) {
+            return Err(BanError::AlreadyBanned);
+        }
+
+        let entry = BanEntry {
+            fingerprint,
+            username,
+            reason,
+            banned_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        entries.push(entry.clone());
+        save_bans(&entries)?;
+
+        tracing::info!("Banned {} ({})", entry.username, entry.fingerprint);
+        Ok(entry)
+    }
+
+    /// Retirer un ban
+    pub fn unban(&self, fingerprint: &str) -> Result<(), BanError> {
+        let mut entries = self.entries.write();
+        let before = entries.len();
+        entries.retain(|e| e.fingerprint != fingerprint);
+        if entries.len() == before {
+            return Err(BanError::NotFound);
+        }
+        save_bans(&entries)?;
+
+        tracing::info!("Unbanned {}", fingerprint);
+        Ok(())
+    }
+
+    /// Obtenir la liste des bans
+    pub fn list(&self) -> Vec<BanEntry> {
+        self.entries.read().clone()
+    }
+}
+
+/// Sauvegarder la liste dans le fichier
+fn save_bans(entries: &[BanEntry]) -> Result<(), BanError> {
+    storage::save_json(BANS_FILE, &entries).map_err(BanError::ConfigError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banned(fingerprint: &str) -> BanList {
+        let list = BanList::default();
+        list.entries.write().push(BanEntry {
+            fingerprint: fingerprint.to_string(),
+            username: "mallory".to_string(),
+            reason: None,
+            banned_at: 0,
+        });
+        list
+    }
+
+    #[test]
+    fn test_check_not_banned() {
+        let list = banned("AA:BB");
+        assert!(matches!(
+            list.check_not_banned("p1", Some("AA:BB")),
+            Err(AppError::PermissionDenied(_))
+        ));
+        assert!(list.check_not_banned("p2", Some("CC:DD")).is_ok());
+        // Pas d'empreinte dans le SDP : rien à comparer
+        assert!(list.check_not_banned("p3", None).is_ok());
+        assert!(matches!(
+            list.ban_identity(None, None),
+            Err(BanError::UnknownIdentity)
+        ));
+    }
+
+    #[test]
+    fn test_ban_unban_and_reload() {
+        // Fichiers gardés en mémoire : le test ne touche pas à la config réelle
+        storage::enable_guest_mode();
+        let list = BanList::default();
+        list.ban(
+            "AA:BB".to_string(),
+            "mallory".to_string(),
+            Some("spam".to_string()),
+        )
+        .unwrap();
+        list.ban_identity(Some(("CC:DD".to_string(), "eve".to_string())), None)
+            .unwrap();
+        assert!(matches!(
+            list.ban("AA:BB".to_string(), "mallory".to_string(), None),
+            Err(BanError::AlreadyBanned)
+        ));
+
+        let reloaded = BanList::load();
+        assert!(reloaded.is_banned("AA:BB") && reloaded.is_banned("CC:DD"));
+        assert_eq!(reloaded.list()[0].reason.as_deref(), Some("spam"));
+
+        reloaded.unban("AA:BB").unwrap();
+        assert!(matches!(reloaded.unban("AA:BB"), Err(BanError::NotFound)));
+        let reloaded = BanList::load();
+        assert!(!reloaded.is_banned("AA:BB"));
+        assert_eq!(reloaded.list().len(), 1);
+    }
+}
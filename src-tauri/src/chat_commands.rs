@@ -0,0 +1,190 @@
+//! Chat slash commands
+//! Messages starting with `/` are run by the backend instead of being sent as
+//! text: `/mute @user`, `/kick @user` and `/topic ...` moderate the room (host
+//! only), `/me ...` describes an action. Their outcome reaches every peer as a
+//! structured system message. `//` sends a text starting with a single `/`.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::i18n::{tf, Text};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatCommand {
+    /// Mute the microphone of a peer
+    Mute { target: String },
+    /// Remove a peer from the room
+    Kick { target: String },
+    /// Set the room topic (empty clears it)
+    Topic { topic: String },
+    /// Action in the third person
+    Me { action: String },
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ChatCommandError {
+    #[error("Unknown command /{0}")]
+    Unknown(String),
+    #[error("Usage: {0}")]
+    Usage(&'static str),
+}
+
+impl ChatCommand {
+    /// The command in `message`, None when it is plain text
+    pub fn parse(message: &str) -> Option<Result<Self, ChatCommandError>> {
+        let body = message.trim_start().strip_prefix('/')?;
+        if body.starts_with('/') {
+            return None;
+        }
+        let (name, args) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+        let args = args.trim();
+
+        Some(match name.to_lowercase().as_str() {
+            "mute" => target(args, "/mute @user").map(|target| Self::Mute { target }),
+            "kick" => target(args, "/kick @user").map(|target| Self::Kick { target }),
+            "topic" => Ok(Self::Topic {
+                topic: args.to_string(),
+            }),
+            "me" if !args.is_empty() => Ok(Self::Me {
+                action: args.to_string(),
+            }),
+            "me" => Err(ChatCommandError::Usage("/me action")),
+            _ => Err(ChatCommandError::Unknown(name.to_string())),
+        })
+    }
+
+    /// Moderation is reserved to the host
+    pub fn requires_host(&self) -> bool {
+        !matches!(self, Self::Me { .. })
+    }
+}
+
+/// The username in `@user` (the `@` is optional)
+fn target(args: &str, usage: &'static str) -> Result<String, ChatCommandError> {
+    let name = args.strip_prefix('@').unwrap_or(args).trim();
+    if name.is_empty() {
+        return Err(ChatCommandError::Usage(usage));
+    }
+    Ok(name.to_string())
+}
+
+/// Text of a plain message: `//` stands for a leading `/`
+pub fn unescape(message: &str) -> &str {
+    let trimmed = message.trim_start();
+    if trimmed.starts_with("//") {
+        &trimmed[1..]
+    } else {
+        message
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemMessageKind {
+    Muted,
+    Kicked,
+    Topic,
+    Me,
+}
+
+/// Outcome of a command, shown to everyone in the room
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemMessage {
+    pub kind: SystemMessageKind,
+    /// Who ran the command
+    pub actor: String,
+    /// Peer muted or kicked
+    pub target: Option<String>,
+    /// Topic (None when cleared) or action
+    pub text: Option<String>,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl SystemMessage {
+    pub fn new(command: &ChatCommand, actor: String, timestamp: u64) -> Self {
+        let (kind, target, text) = match command {
+            ChatCommand::Mute { target } => (SystemMessageKind::Muted, Some(target.clone()), None),
+            ChatCommand::Kick { target } => (SystemMessageKind::Kicked, Some(target.clone()), None),
+            ChatCommand::Topic { topic } => (
+                SystemMessageKind::Topic,
+                None,
+                (!topic.is_empty()).then(|| topic.clone()),
+            ),
+            ChatCommand::Me { action } => (SystemMessageKind::Me, None, Some(action.clone())),
+        };
+        Self {
+            kind,
+            actor,
+            target,
+            text,
+            timestamp,
+        }
+    }
+
+    /// Only the host may send moderation messages
+    pub fn requires_host(&self) -> bool {
+        self.kind != SystemMessageKind::Me
+    }
+
+    /// Line shown in the chat and kept in the transcript
+    pub fn describe(&self) -> String {
+        let actor = self.actor.as_str();
+        let target = self.target.as_deref().unwrap_or_default();
+        match (self.kind, self.text.as_deref()) {
+            (SystemMessageKind::Muted, _) => {
+                tf(Text::ChatMuted, &[("actor", actor), ("target", target)])
+            }
+            (SystemMessageKind::Kicked, _) => {
+                tf(Text::ChatKicked, &[("actor", actor), ("target", target)])
+            }
+            (SystemMessageKind::Topic, Some(topic)) => {
+                tf(Text::ChatTopic, &[("actor", actor), ("topic", topic)])
+            }
+            (SystemMessageKind::Topic, None) => tf(Text::ChatTopicCleared, &[("actor", actor)]),
+            (SystemMessageKind::Me, action) => {
+                format!("* {} {}", actor, action.unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slash_commands_are_parsed_and_escaped() {
+        assert_eq!(ChatCommand::parse("salut"), None);
+        assert_eq!(ChatCommand::parse("//etc/hosts"), None);
+        assert_eq!(unescape("//etc/hosts"), "/etc/hosts");
+        assert_eq!(unescape("salut"), "salut");
+
+        assert_eq!(
+            ChatCommand::parse("/mute @Alice B"),
+            Some(Ok(ChatCommand::Mute {
+                target: "Alice B".to_string()
+            }))
+        );
+        assert_eq!(
+            ChatCommand::parse("/KICK"),
+            Some(Err(ChatCommandError::Usage("/kick @user")))
+        );
+        assert_eq!(
+            ChatCommand::parse("/topic"),
+            Some(Ok(ChatCommand::Topic {
+                topic: String::new()
+            }))
+        );
+        assert_eq!(
+            ChatCommand::parse("/dance"),
+            Some(Err(ChatCommandError::Unknown("dance".to_string())))
+        );
+
+        let me = ChatCommand::parse("/me prend un café").unwrap().unwrap();
+        assert!(!me.requires_host());
+        let message = SystemMessage::new(&me, "Bob".to_string(), 0);
+        assert_eq!(message.describe(), "* Bob prend un café");
+        assert!(!message.requires_host());
+    }
+}
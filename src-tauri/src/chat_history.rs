@@ -0,0 +1,349 @@
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use crate::chat_transcript::ChatEntry;
+use crate::storage;
+
+const HISTORY_FILE: &str = "chat_history.sqlite3";
+
+/// Messages de la room renvoyés avant et après chaque résultat
+const CONTEXT_MESSAGES: usize = 2;
+
+/// Nombre max de messages gardés, toutes rooms confondues (les plus anciens
+/// sont supprimés)
+const MAX_HISTORY_MESSAGES: i64 = 100_000;
+
+/// Messages ajoutés entre deux purges
+const PRUNE_EVERY: usize = 500;
+
+/// Nombre de résultats sans limite demandée, et au maximum
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Messages, plus un index FTS5 tenu à jour par des triggers
+/// (accents ignorés : "cafe" trouve "café")
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY,
+    room_code TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    content TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    outgoing INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_room ON messages (room_code, id);
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content, sender,
+    content = 'messages', content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, content, sender) VALUES (new.id, new.content, new.sender);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content, sender)
+    VALUES ('delete', old.id, old.content, old.sender);
+END;
+";
+
+/// Message trouvé, avec les messages qui l'entourent dans sa room
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatSearchHit {
+    pub id: i64,
+    pub room_code: String,
+    #[serde(flatten)]
+    pub message: ChatEntry,
+    /// Extrait du message, les termes trouvés entre `\u{2}` et `\u{3}`
+    pub snippet: String,
+    /// Messages précédents (du plus ancien au plus récent)
+    pub before: Vec<ChatEntry>,
+    /// Messages suivants
+    pub after: Vec<ChatEntry>,
+}
+
+/// Écriture confiée au thread de l'historique
+enum Write {
+    Append(String, ChatEntry),
+    /// Effacer une room (toutes sans code)
+    Clear(Option<String>),
+}
+
+/// Historique du chat persisté dans SQLite, avec recherche plein texte
+/// En mode invité la base reste en mémoire. Les écritures passent par un
+/// thread dédié, dans l'ordre : ajouter un message ne bloque jamais l'appelant
+/// (commande, callback du data channel) sur le disque.
+pub struct ChatHistory {
+    conn: Arc<Mutex<Connection>>,
+    writer: Mutex<Sender<Write>>,
+}
+
+impl ChatHistory {
+    /// Ouvrir (ou créer) l'historique
+    pub fn open() -> Result<Self, String> {
+        let conn = if storage::is_guest_mode() {
+            Connection::open_in_memory()
+        } else {
+            Connection::open(storage::config_file(HISTORY_FILE))
+        }
+        .map_err(|e| e.to_string())?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        let conn = Arc::new(Mutex::new(conn));
+        let (tx, rx) = mpsc::channel();
+        let writer_conn = conn.clone();
+        std::thread::Builder::new()
+            .name("chat-history".to_string())
+            .spawn(move || write_loop(&writer_conn, rx))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn,
+            writer: Mutex::new(tx),
+        })
+    }
+
+    /// Ajouter un message de la room (écrit en arrière-plan)
+    pub fn append(&self, room_code: &str, entry: &ChatEntry) -> Result<(), String> {
+        self.write(Write::Append(room_code.to_string(), entry.clone()))
+    }
+
+    /// Effacer l'historique d'une room, ou de toutes sans code
+    pub fn clear(&self, room_code: Option<&str>) -> Result<(), String> {
+        self.write(Write::Clear(room_code.map(str::to_string)))
+    }
+
+    fn write(&self, write: Write) -> Result<(), String> {
+        self.writer
+            .lock()
+            .send(write)
+            .map_err(|_| "Chat history writer stopped".to_string())
+    }
+
+    /// Chercher dans l'historique (d'une room ou de toutes), les plus
+    /// pertinents d'abord
+    pub fn search(
+        &self,
+        query: &str,
+        room_code: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatSearchHit>, String> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        self.search_fts(&query, room_code, limit)
+            .map_err(|e| e.to_string())
+    }
+
+    fn search_fts(
+        &self,
+        query: &str,
+        room_code: Option<&str>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<ChatSearchHit>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT m.id, m.room_code, m.sender, m.content, m.timestamp, m.outgoing,
+                    snippet(messages_fts, 0, char(2), char(3), '…', 16)
+             FROM messages_fts JOIN messages m ON m.id = messages_fts.rowid
+             WHERE messages_fts MATCH ?1 AND (?2 IS NULL OR m.room_code = ?2)
+             ORDER BY rank
+             LIMIT ?3",
+        )?;
+        let matches = stmt
+            .query_map(params![query, room_code, limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    entry_from_row(row, 2)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        matches
+            .into_iter()
+            .map(|(id, room_code, message, snippet)| {
+                Ok(ChatSearchHit {
+                    before: context(&conn, &room_code, id, true)?,
+                    after: context(&conn, &room_code, id, false)?,
+                    id,
+                    room_code,
+                    message,
+                    snippet,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Thread d'écriture : jusqu'à ce que l'historique soit fermé
+fn write_loop(conn: &Mutex<Connection>, rx: Receiver<Write>) {
+    let mut appended = 0;
+    for write in rx {
+        let conn = conn.lock();
+        let result = match write {
+            Write::Append(room_code, entry) => {
+                appended += 1;
+                insert(&conn, &room_code, &entry).and_then(|()| {
+                    if appended % PRUNE_EVERY == 0 {
+                        prune(&conn, MAX_HISTORY_MESSAGES)?;
+                    }
+                    Ok(())
+                })
+            }
+            Write::Clear(room_code) => clear(&conn, room_code.as_deref()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to write the chat history: {}", e);
+        }
+    }
+}
+
+fn insert(conn: &Connection, room_code: &str, entry: &ChatEntry) -> rusqlite::Result<()> {
+    conn.prepare_cached(
+        "INSERT INTO messages (room_code, sender, content, timestamp, outgoing)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?
+    .execute(params![
+        room_code,
+        entry.sender,
+        entry.content,
+        entry.timestamp as i64,
+        entry.outgoing
+    ])?;
+    Ok(())
+}
+
+/// Ne garder que les `max` messages les plus récents (l'index suit par trigger)
+fn prune(conn: &Connection, max: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM messages WHERE id <= (SELECT MAX(id) FROM messages) - ?1",
+        params![max],
+    )?;
+    Ok(())
+}
+
+fn clear(conn: &Connection, room_code: Option<&str>) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM messages WHERE ?1 IS NULL OR room_code = ?1",
+        params![room_code],
+    )?;
+    Ok(())
+}
+
+/// Message lu à partir de la colonne `first` (sender, content, timestamp, outgoing)
+fn entry_from_row(row: &Row, first: usize) -> rusqlite::Result<ChatEntry> {
+    Ok(ChatEntry {
+        sender: row.get(first)?,
+        content: row.get(first + 1)?,
+        timestamp: row.get::<_, i64>(first + 2)? as u64,
+        outgoing: row.get(first + 3)?,
+    })
+}
+
+/// Messages de la room juste avant (ou après) le message `id`
+fn context(
+    conn: &Connection,
+    room_code: &str,
+    id: i64,
+    before: bool,
+) -> rusqlite::Result<Vec<ChatEntry>> {
+    let sql = if before {
+        "SELECT sender, content, timestamp, outgoing FROM messages
+         WHERE room_code = ?1 AND id < ?2 ORDER BY id DESC LIMIT ?3"
+    } else {
+        "SELECT sender, content, timestamp, outgoing FROM messages
+         WHERE room_code = ?1 AND id > ?2 ORDER BY id ASC LIMIT ?3"
+    };
+    let mut entries = conn
+        .prepare_cached(sql)?
+        .query_map(params![room_code, id, CONTEXT_MESSAGES as i64], |row| {
+            entry_from_row(row, 0)
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if before {
+        entries.reverse();
+    }
+    Ok(entries)
+}
+
+/// Requête FTS5 pour le texte saisi : chaque mot est cherché tel quel (la
+/// syntaxe FTS5 est neutralisée) et en préfixe, pour trouver pendant la frappe
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sender: &str, content: &str) -> ChatEntry {
+        ChatEntry {
+            sender: sender.to_string(),
+            content: content.to_string(),
+            timestamp: 1_700_000_000,
+            outgoing: false,
+        }
+    }
+
+    /// Historique en mémoire, rempli sans passer par le thread d'écriture
+    fn history(messages: &[(&str, &str, &str)]) -> ChatHistory {
+        let history = ChatHistory::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        for (room_code, sender, content) in messages {
+            insert(&history.conn.lock(), room_code, &entry(sender, content)).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn test_search_ranks_matches_with_context() {
+        let history = history(&[
+            ("ABCD", "Alice", "salut"),
+            ("ABCD", "Alice", "on se fait un café ?"),
+            ("ABCD", "Alice", "oui, café à 16h"),
+            ("ABCD", "Alice", "ok"),
+            ("WXYZ", "Bob", "le cafe est froid"),
+        ]);
+
+        // Accents ignorés, recherche en préfixe, syntaxe FTS5 neutralisée
+        assert_eq!(history.search("caf", None, None).unwrap().len(), 3);
+        assert!(history.search("\"café OR", None, None).unwrap().is_empty());
+        assert!(history.search("   ", None, None).unwrap().is_empty());
+
+        let hits = history.search("café 16h", Some("ABCD"), None).unwrap();
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.message.content, "oui, café à 16h");
+        assert!(hit.snippet.contains("\u{2}café\u{3}"));
+        let before: Vec<_> = hit.before.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(before, ["salut", "on se fait un café ?"]);
+        assert_eq!(hit.after.len(), 1);
+    }
+
+    #[test]
+    fn test_clear_and_prune_keep_the_index_in_sync() {
+        let history = history(&[
+            ("ABCD", "Alice", "premier café"),
+            ("ABCD", "Alice", "deuxième café"),
+            ("WXYZ", "Bob", "café aussi"),
+        ]);
+        prune(&history.conn.lock(), 2).unwrap();
+        assert_eq!(history.search("café", None, None).unwrap().len(), 2);
+        clear(&history.conn.lock(), Some("ABCD")).unwrap();
+        let hits = history.search("café", None, None).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].room_code, "WXYZ");
+    }
+}
@@ -0,0 +1,256 @@
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::chat_history::{ChatHistory, ChatSearchHit};
+
+/// Nombre max de messages gardés par room (les plus anciens sont supprimés)
+const MAX_MESSAGES_PER_ROOM: usize = 5000;
+
+/// Message du chat d'une room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatEntry {
+    pub sender: String,
+    pub content: String,
+    /// Secondes depuis l'epoch Unix
+    pub timestamp: u64,
+    /// Envoyé par nous
+    pub outgoing: bool,
+}
+
+/// Format d'export du chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatExportFormat {
+    Json,
+    Html,
+}
+
+impl ChatExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Export JSON d'une room
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    room_code: &'a str,
+    exported_at: u64,
+    messages: &'a [ChatEntry],
+}
+
+/// Transcription en mémoire des chats de la session, par room
+/// Chaque message est aussi gardé dans l'historique persisté, où l'on peut
+/// chercher.
+#[derive(Clone, Default)]
+pub struct ChatTranscript {
+    rooms: Arc<RwLock<HashMap<String, Vec<ChatEntry>>>>,
+    history: Option<Arc<ChatHistory>>,
+}
+
+impl ChatTranscript {
+    /// Transcription reliée à l'historique persisté (sans historique s'il
+    /// ne s'ouvre pas)
+    pub fn load() -> Self {
+        let history = match ChatHistory::open() {
+            Ok(history) => Some(Arc::new(history)),
+            Err(e) => {
+                tracing::warn!("Chat history unavailable: {}", e);
+                None
+            }
+        };
+        Self {
+            rooms: Arc::default(),
+            history,
+        }
+    }
+
+    /// Ajouter un message à la room
+    pub fn record(
+        &self,
+        room_code: &str,
+        sender: &str,
+        content: &str,
+        timestamp: u64,
+        outgoing: bool,
+    ) {
+        let entry = ChatEntry {
+            sender: sender.to_string(),
+            content: content.to_string(),
+            timestamp,
+            outgoing,
+        };
+        if let Some(history) = &self.history {
+            if let Err(e) = history.append(room_code, &entry) {
+                tracing::warn!("Failed to save chat message: {}", e);
+            }
+        }
+
+        let mut rooms = self.rooms.write();
+        let messages = rooms.entry(room_code.to_string()).or_default();
+        messages.push(entry);
+        if messages.len() > MAX_MESSAGES_PER_ROOM {
+            let excess = messages.len() - MAX_MESSAGES_PER_ROOM;
+            messages.drain(..excess);
+        }
+    }
+
+    /// Messages de la room (du plus ancien au plus récent)
+    pub fn messages(&self, room_code: &str) -> Vec<ChatEntry> {
+        self.rooms
+            .read()
+            .get(room_code)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Chercher dans l'historique persisté (vide sans historique)
+    pub fn search(
+        &self,
+        query: &str,
+        room_code: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatSearchHit>, String> {
+        match &self.history {
+            Some(history) => history.search(query, room_code, limit),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Effacer le chat d'une room (de toutes sans code), de la session comme
+    /// de l'historique persisté
+    pub fn clear(&self, room_code: Option<&str>) -> Result<(), String> {
+        match room_code {
+            Some(room_code) => {
+                self.rooms.write().remove(room_code);
+            }
+            None => self.rooms.write().clear(),
+        }
+        match &self.history {
+            Some(history) => history.clear(room_code),
+            None => Ok(()),
+        }
+    }
+
+    /// Rendre le chat de la room dans le format demandé
+    pub fn render(&self, room_code: &str, format: ChatExportFormat) -> Result<String, String> {
+        let messages = self.messages(room_code);
+        match format {
+            ChatExportFormat::Json => serde_json::to_string_pretty(&JsonExport {
+                room_code,
+                exported_at: now(),
+                messages: &messages,
+            })
+            .map_err(|e| e.to_string()),
+            ChatExportFormat::Html => Ok(render_html(room_code, &messages)),
+        }
+    }
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Page HTML autonome (styles inline, heures converties en heure locale par le navigateur)
+fn render_html(room_code: &str, messages: &[ChatEntry]) -> String {
+    let room = escape_html(room_code);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>HydrowLand - Chat de la room {room}</title>\n<style>\n\
+         body{{font-family:system-ui,sans-serif;background:#18181b;color:#e4e4e7;max-width:48rem;margin:2rem auto;padding:0 1rem}}\n\
+         h1{{font-size:1.25rem}}\n.meta{{color:#a1a1aa;font-size:.875rem}}\n\
+         .msg{{margin:.75rem 0}}\n.sender{{font-weight:600}}\n.outgoing .sender{{color:#4ade80}}\n\
+         .content{{white-space:pre-wrap;word-wrap:break-word}}\n\
+         </style>\n</head>\n<body>\n<h1>Chat de la room {room}</h1>\n\
+         <p class=\"meta\">Exporté le <time datetime=\"{exported}\">{exported}</time> - {count} message(s)</p>\n",
+        room = room,
+        exported = iso_utc(now()),
+        count = messages.len(),
+    );
+    for message in messages {
+        let time = iso_utc(message.timestamp);
+        html.push_str(&format!(
+            "<div class=\"msg{class}\"><span class=\"sender\">{sender}</span> \
+             <time class=\"meta\" datetime=\"{time}\">{time}</time>\
+             <div class=\"content\">{content}</div></div>\n",
+            class = if message.outgoing { " outgoing" } else { "" },
+            sender = escape_html(&message.sender),
+            time = time,
+            content = escape_html(&message.content),
+        ));
+    }
+    html.push_str(
+        "<script>document.querySelectorAll(\"time\").forEach(function(t){\
+         t.textContent=new Date(t.dateTime).toLocaleString();});</script>\n</body>\n</html>\n",
+    );
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Date ISO 8601 en UTC (algorithme "civil from days" de Howard Hinnant)
+fn iso_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_export_escapes_messages() {
+        let transcript = ChatTranscript::default();
+        transcript.record(
+            "ABCD",
+            "Eve",
+            "<script>alert('x')</script>",
+            1_700_000_000,
+            false,
+        );
+
+        let html = transcript.render("ABCD", ChatExportFormat::Html).unwrap();
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(html.contains("2023-11-14T22:13:20Z"));
+        assert!(!html.contains("<script>alert"));
+    }
+}
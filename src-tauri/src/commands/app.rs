@@ -0,0 +1,129 @@
+//! App commands
+//! Build and platform capabilities, so the UI can hide what this build or OS
+//! cannot do instead of probing by trial and error, and the locale of the
+//! strings produced by the backend
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::error::{AppError, AppResult};
+use crate::i18n::{self, Locale};
+use crate::tray;
+
+/// What this build can do on this platform
+#[derive(Debug, Clone, Serialize)]
+pub struct AppCapabilities {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    /// Enabled cargo features ("audio", "screen", "video")
+    pub features: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    pub video_codecs: Vec<String>,
+    /// Audio hosts available on this machine (e.g. "CoreAudio", "Wasapi", "Alsa")
+    pub audio_backends: Vec<String>,
+    pub screen_capture_backends: Vec<String>,
+    pub permissions: Vec<PermissionRequirement>,
+}
+
+/// An OS permission a subsystem may need
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionRequirement {
+    /// "microphone" or "screen_recording"
+    pub name: String,
+    /// Whether the OS asks the user before access is granted
+    pub required: bool,
+}
+
+/// Get the version, enabled features, codecs, capture backends and permission
+/// requirements of this build
+#[tauri::command]
+pub fn app_get_capabilities(app: AppHandle) -> AppCapabilities {
+    let mut features = Vec::new();
+    if cfg!(feature = "audio") {
+        features.push("audio".to_string());
+    }
+    if cfg!(feature = "screen") {
+        features.push("screen".to_string());
+    }
+    if cfg!(feature = "video") {
+        features.push("video".to_string());
+    }
+    if cfg!(feature = "whip") {
+        features.push("whip".to_string());
+    }
+
+    let mut permissions = Vec::new();
+    if cfg!(feature = "audio") {
+        permissions.push(PermissionRequirement {
+            name: "microphone".to_string(),
+            required: cfg!(target_os = "macos"),
+        });
+    }
+    if cfg!(feature = "screen") {
+        permissions.push(PermissionRequirement {
+            name: "screen_recording".to_string(),
+            required: cfg!(target_os = "macos"),
+        });
+    }
+
+    AppCapabilities {
+        version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        features,
+        audio_codecs: if cfg!(feature = "audio") {
+            vec!["opus".to_string()]
+        } else {
+            Vec::new()
+        },
+        // Screen frames are sent as JPEG
+        video_codecs: if cfg!(feature = "video") {
+            vec!["jpeg".to_string()]
+        } else {
+            Vec::new()
+        },
+        audio_backends: audio_backends(),
+        screen_capture_backends: if cfg!(feature = "screen") {
+            vec!["xcap".to_string()]
+        } else {
+            Vec::new()
+        },
+        permissions,
+    }
+}
+
+/// Set the locale of backend strings (errors, menus, tray, notifications)
+/// from a BCP 47 tag such as "fr-FR"; unsupported languages fall back to English
+#[tauri::command]
+pub fn app_set_locale(app: AppHandle, locale: String) -> AppResult<Locale> {
+    let locale = Locale::from_tag(&locale);
+    if locale != i18n::locale() {
+        i18n::set_locale(locale);
+        crate::build_menu(&app)
+            .and_then(|menu| app.set_menu(menu))
+            .map_err(|e| AppError::Internal(format!("Failed to rebuild the menu: {}", e)))?;
+        tray::relabel(&app);
+        tracing::info!("Locale set to {:?}", locale);
+    }
+    Ok(locale)
+}
+
+/// Get the locale of backend strings
+#[tauri::command]
+pub fn app_get_locale() -> Locale {
+    i18n::locale()
+}
+
+#[cfg(feature = "audio")]
+fn audio_backends() -> Vec<String> {
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| id.name().to_string())
+        .collect()
+}
+
+#[cfg(not(feature = "audio"))]
+fn audio_backends() -> Vec<String> {
+    Vec::new()
+}
@@ -2,9 +2,25 @@
 
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, State};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
 
-use crate::audio::{AudioCapture, AudioMixer, AudioPlayback, OpusDecoder, OpusEncoder, RealtimeCapture};
+use crate::audio::{
+    self, AudioCapture, AudioMixer, AudioPlayback, AudioStreamingService, DenoiseBackend,
+    DynamicsSettings, EncoderOptions, EqPreset, EqSettings, MicPermission, OpusDecoder,
+    OpusEncoder, RealtimeCapture, RnnoiseModel,
+};
+use crate::commands::guard::{CommandGuards, Subsystem};
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, MicPermissionEvent};
+use crate::storage;
+
+const ENCODER_OPTIONS_FILE: &str = "encoder_options.json";
+const DENOISER_FILE: &str = "denoiser.json";
+const DENOISE_MODEL_FILE: &str = "denoise_model.json";
+const EQUALIZER_FILE: &str = "equalizer.json";
+const DYNAMICS_FILE: &str = "dynamics.json";
 
 /// Thread-safe audio state wrapper
 pub struct AudioState {
@@ -12,23 +28,77 @@ pub struct AudioState {
     realtime: RealtimeCapture,
     is_voice_active: Mutex<bool>,
     master_volume: Mutex<f32>,
+    /// Last microphone permission seen, to emit changes only
+    mic_permission: Mutex<Option<MicPermission>>,
 }
 
-// Safety: AudioState only contains Mutex-protected data and thread-safe RealtimeCapture
-unsafe impl Send for AudioState {}
-unsafe impl Sync for AudioState {}
-
 impl AudioState {
     pub fn new() -> Self {
+        let realtime = RealtimeCapture::new();
+        if let Some(backend) = saved_denoiser() {
+            let _ = realtime.set_denoise_backend(backend);
+        }
+        // A broken model is reported once, by the streaming service
+        if let Ok(Some(model)) = saved_denoise_model() {
+            realtime.set_denoise_model(Some(model));
+        }
+
+        let mut mixer = AudioMixer::new();
+        if let Some(settings) = saved_equalizer() {
+            mixer.set_equalizer(settings);
+        }
+        if let Some(settings) = saved_dynamics() {
+            mixer.set_dynamics(settings);
+        }
+
         Self {
-            mixer: Mutex::new(AudioMixer::new()),
-            realtime: RealtimeCapture::new(),
+            mixer: Mutex::new(mixer),
+            realtime,
             is_voice_active: Mutex::new(false),
             master_volume: Mutex::new(1.0),
+            mic_permission: Mutex::new(None),
         }
     }
 }
 
+impl AudioState {
+    /// Mute the level-meter capture (kept in sync with the streaming mute)
+    pub fn set_muted(&self, muted: bool) {
+        self.realtime.set_muted(muted);
+    }
+
+    pub fn is_voice_active(&self) -> bool {
+        *self.is_voice_active.lock()
+    }
+
+    /// Stop the level-meter capture
+    pub fn stop_voice(&self) {
+        self.realtime.stop();
+        *self.is_voice_active.lock() = false;
+    }
+}
+
+/// Record the microphone permission, notifying the frontend when it changed
+fn update_mic_permission(app: &AppHandle, status: MicPermission) -> MicPermission {
+    let previous = app.state::<AudioState>().mic_permission.lock().replace(status);
+    if previous.is_some_and(|previous| previous != status) {
+        tracing::info!("Microphone permission changed: {:?}", status);
+        let _ = events::emit(app, &MicPermissionEvent { status });
+    }
+    status
+}
+
+/// Fail when the OS blocks the microphone, instead of capturing silence
+/// (not determined yet is fine: opening the mic shows the prompt)
+pub fn ensure_mic_permission(app: &AppHandle) -> AppResult<()> {
+    match update_mic_permission(app, audio::mic_permission_status()) {
+        MicPermission::Denied | MicPermission::Restricted => Err(AppError::PermissionDenied(
+            "Microphone access is blocked in the system settings".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
 impl Default for AudioState {
     fn default() -> Self {
         Self::new()
@@ -43,18 +113,35 @@ pub struct AudioDevice {
 
 /// Initialize audio system (no-op for now, but kept for API consistency)
 #[tauri::command]
-pub fn audio_init(_audio: State<'_, AudioState>) -> Result<(), String> {
+pub fn audio_init(_audio: State<'_, AudioState>) -> AppResult<()> {
     tracing::info!("Audio system initialized");
     Ok(())
 }
 
+/// Check the microphone permission (macOS, always granted elsewhere)
+#[tauri::command]
+pub fn audio_check_permission(app: AppHandle) -> AppResult<MicPermission> {
+    Ok(update_mic_permission(&app, audio::mic_permission_status()))
+}
+
+/// Request the microphone permission (macOS)
+/// Shows the system prompt the first time, then returns the user's decision
+#[tauri::command]
+pub async fn audio_request_permission(app: AppHandle) -> AppResult<MicPermission> {
+    let status = audio::request_mic_permission().await;
+    Ok(update_mic_permission(&app, status))
+}
+
 /// Start voice capture with real-time level monitoring
 /// This starts capturing from the microphone and emits "audio-level" events
 #[tauri::command]
-pub fn audio_start_voice(
+pub async fn audio_start_voice(
     audio: State<'_, AudioState>,
+    guards: State<'_, CommandGuards>,
     app_handle: AppHandle,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    ensure_mic_permission(&app_handle)?;
     let mut active = audio.is_voice_active.lock();
     if *active {
         return Ok(()); // Already active
@@ -70,9 +157,12 @@ pub fn audio_start_voice(
 
 /// Stop voice capture
 #[tauri::command]
-pub fn audio_stop_voice(audio: State<'_, AudioState>) -> Result<(), String> {
-    audio.realtime.stop();
-    *audio.is_voice_active.lock() = false;
+pub async fn audio_stop_voice(
+    audio: State<'_, AudioState>,
+    guards: State<'_, CommandGuards>,
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    audio.stop_voice();
     tracing::info!("Voice capture stopped");
     Ok(())
 }
@@ -104,20 +194,20 @@ pub fn audio_get_level(audio: State<'_, AudioState>) -> f32 {
 
 /// List available input devices (microphones)
 #[tauri::command]
-pub fn audio_list_input_devices() -> Result<Vec<String>, String> {
+pub fn audio_list_input_devices() -> AppResult<Vec<String>> {
     AudioCapture::list_devices()
 }
 
 /// List available output devices (speakers)
 #[tauri::command]
-pub fn audio_list_output_devices() -> Result<Vec<String>, String> {
+pub fn audio_list_output_devices() -> AppResult<Vec<String>> {
     AudioPlayback::list_devices()
 }
 
 /// Encode audio samples to Opus (for sending over network)
 /// Creates encoder on-demand (stateless encoding)
 #[tauri::command]
-pub fn audio_encode(samples: Vec<f32>) -> Result<Vec<u8>, String> {
+pub fn audio_encode(samples: Vec<f32>) -> AppResult<Vec<u8>> {
     let mut encoder = OpusEncoder::new()?;
     encoder.encode(&samples)
 }
@@ -125,7 +215,7 @@ pub fn audio_encode(samples: Vec<f32>) -> Result<Vec<u8>, String> {
 /// Decode Opus audio (from network)
 /// Creates decoder on-demand (stateless decoding)
 #[tauri::command]
-pub fn audio_decode(data: Vec<u8>) -> Result<Vec<f32>, String> {
+pub fn audio_decode(data: Vec<u8>) -> AppResult<Vec<f32>> {
     let mut decoder = OpusDecoder::new()?;
     decoder.decode(&data)
 }
@@ -173,12 +263,14 @@ pub fn audio_cleanup(audio: State<'_, AudioState>) {
 
 /// Set input device by name. Pass null/None for default device.
 #[tauri::command]
-pub fn audio_set_input_device(
+pub async fn audio_set_input_device(
     audio: State<'_, AudioState>,
+    guards: State<'_, CommandGuards>,
     device_name: Option<String>,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
     tracing::info!("Setting input device to: {:?}", device_name);
-    audio.realtime.set_input_device(device_name)
+    audio.realtime.set_input_device(device_name).await
 }
 
 /// Get currently selected input device name (None if using default)
@@ -198,3 +290,193 @@ pub fn audio_set_noise_suppression(audio: State<'_, AudioState>, enabled: bool)
 pub fn audio_is_noise_suppression_enabled(audio: State<'_, AudioState>) -> bool {
     audio.realtime.is_noise_suppression_enabled()
 }
+
+/// Saved noise suppression backend, if still available in this build
+fn saved_denoiser() -> Option<DenoiseBackend> {
+    storage::load_json::<DenoiseBackend>(DENOISER_FILE).filter(|b| b.is_available())
+}
+
+/// Saved custom RNNoise model, loaded and validated
+fn saved_denoise_model() -> AppResult<Option<RnnoiseModel>> {
+    storage::load_json::<Option<PathBuf>>(DENOISE_MODEL_FILE)
+        .flatten()
+        .map(|path| RnnoiseModel::load(&path))
+        .transpose()
+}
+
+/// Apply the saved noise suppression backend and model
+/// A custom model that no longer loads falls back to the built-in one.
+pub fn load_denoiser(service: &AudioStreamingService) {
+    if let Some(backend) = saved_denoiser() {
+        if let Err(e) = service.set_denoise_backend(backend) {
+            tracing::warn!("Ignoring saved noise suppression backend: {}", e);
+        }
+    }
+    match saved_denoise_model() {
+        Ok(Some(model)) => {
+            tracing::info!("Using RNNoise model {}", model.path().display());
+            service.set_denoise_model(Some(model));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(
+            "Custom RNNoise model unusable, using the built-in one: {}",
+            e
+        ),
+    }
+}
+
+/// Select the noise suppression backend, for the voice and the level meter
+/// (RNNoise mangles some voices; Speex is gentler, passthrough disables it)
+#[tauri::command]
+pub fn audio_set_denoiser(
+    audio: State<'_, AudioState>,
+    state: State<'_, StreamingState>,
+    backend: DenoiseBackend,
+) -> AppResult<()> {
+    state.service.set_denoise_backend(backend)?;
+    audio.realtime.set_denoise_backend(backend)?;
+    storage::save_json(DENOISER_FILE, &backend).map_err(AppError::Storage)
+}
+
+/// Get the noise suppression backend
+#[tauri::command]
+pub fn audio_get_denoiser(state: State<'_, StreamingState>) -> DenoiseBackend {
+    state.service.denoise_backend()
+}
+
+/// Use a custom trained RNNoise model for the nnnoiseless backend, or the
+/// built-in one with None. The file is validated before anything changes
+/// and is loaded again at startup.
+#[tauri::command]
+pub fn audio_set_denoise_model(
+    audio: State<'_, AudioState>,
+    state: State<'_, StreamingState>,
+    path: Option<PathBuf>,
+) -> AppResult<()> {
+    let model = path.as_deref().map(RnnoiseModel::load).transpose()?;
+    storage::save_json(DENOISE_MODEL_FILE, &path).map_err(AppError::Storage)?;
+
+    state.service.set_denoise_model(model.clone());
+    audio.realtime.set_denoise_model(model);
+    Ok(())
+}
+
+/// Path of the custom RNNoise model in use (None for the built-in one)
+#[tauri::command]
+pub fn audio_get_denoise_model(state: State<'_, StreamingState>) -> Option<PathBuf> {
+    state.service.denoise_model_path()
+}
+
+/// Backends compiled into this build
+#[tauri::command]
+pub fn audio_list_denoisers() -> Vec<DenoiseBackend> {
+    DenoiseBackend::ALL
+        .into_iter()
+        .filter(|b| b.is_available())
+        .collect()
+}
+
+/// Saved equalizer settings, if valid
+fn saved_equalizer() -> Option<EqSettings> {
+    storage::load_json::<EqSettings>(EQUALIZER_FILE).filter(|s| s.validate().is_ok())
+}
+
+/// Apply the saved equalizer to the playback output
+pub fn load_equalizer(service: &AudioStreamingService) {
+    if let Some(settings) = saved_equalizer() {
+        service.set_equalizer(settings);
+    }
+}
+
+/// Set the output equalizer (gain of each band in dB, bass to treble)
+#[tauri::command]
+pub fn audio_set_equalizer(
+    audio: State<'_, AudioState>,
+    state: State<'_, StreamingState>,
+    settings: EqSettings,
+) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(EQUALIZER_FILE, &settings).map_err(AppError::Storage)?;
+    state.service.set_equalizer(settings);
+    audio.mixer.lock().set_equalizer(settings);
+    Ok(())
+}
+
+/// Apply an equalizer preset, returning its settings
+#[tauri::command]
+pub fn audio_set_equalizer_preset(
+    audio: State<'_, AudioState>,
+    state: State<'_, StreamingState>,
+    preset: EqPreset,
+) -> AppResult<EqSettings> {
+    let settings = EqSettings::preset(preset);
+    audio_set_equalizer(audio, state, settings)?;
+    Ok(settings)
+}
+
+/// Get the output equalizer
+#[tauri::command]
+pub fn audio_get_equalizer(state: State<'_, StreamingState>) -> EqSettings {
+    state.service.equalizer()
+}
+
+/// Saved compressor settings, if valid
+fn saved_dynamics() -> Option<DynamicsSettings> {
+    storage::load_json::<DynamicsSettings>(DYNAMICS_FILE).filter(|s| s.validate().is_ok())
+}
+
+/// Apply the saved compressor to the playback output
+pub fn load_dynamics(service: &AudioStreamingService) {
+    if let Some(settings) = saved_dynamics() {
+        service.set_dynamics(settings);
+    }
+}
+
+/// Set the output compressor (the limiter after it is always on)
+#[tauri::command]
+pub fn audio_set_dynamics(
+    audio: State<'_, AudioState>,
+    state: State<'_, StreamingState>,
+    settings: DynamicsSettings,
+) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(DYNAMICS_FILE, &settings).map_err(AppError::Storage)?;
+    state.service.set_dynamics(settings);
+    audio.mixer.lock().set_dynamics(settings);
+    Ok(())
+}
+
+/// Get the output compressor
+#[tauri::command]
+pub fn audio_get_dynamics(state: State<'_, StreamingState>) -> DynamicsSettings {
+    state.service.dynamics()
+}
+
+/// Apply the saved encoder options (defaults when unset or invalid)
+pub fn load_encoder_options(service: &AudioStreamingService) {
+    if let Some(options) = storage::load_json::<EncoderOptions>(ENCODER_OPTIONS_FILE) {
+        if let Err(e) = service.set_encoder_options(options) {
+            tracing::warn!("Ignoring saved encoder options: {}", e);
+        }
+    }
+}
+
+/// Set Opus complexity (0-10), expected packet loss (%) and in-band FEC
+/// Lower complexity saves CPU on slow machines; more expected loss and FEC
+/// spend bitrate on resilience. With `adaptive_fec`, the expected loss and FEC
+/// follow the loss measured during calls. Applies to the running capture right away.
+#[tauri::command]
+pub fn audio_set_encoder_options(
+    state: State<'_, StreamingState>,
+    options: EncoderOptions,
+) -> AppResult<()> {
+    options.validate()?;
+    storage::save_json(ENCODER_OPTIONS_FILE, &options).map_err(AppError::Storage)?;
+    state.service.set_encoder_options(options)
+}
+
+/// Get the Opus encoder options
+#[tauri::command]
+pub fn audio_get_encoder_options(state: State<'_, StreamingState>) -> EncoderOptions {
+    state.service.encoder_options()
+}
@@ -3,10 +3,43 @@
 //! Audio mesh commands for WebRTC audio streaming
 //! Provides Tauri commands for audio-enabled mesh networking
 
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
 
-use crate::webrtc::{AudioMeshManager, ConnectionOffer, calculate_audio_level};
+use crate::audio::AudioPolicy;
+#[cfg(feature = "audio")]
+use crate::audio::SoundEvent;
+use crate::bans::BanList;
+use crate::chat_commands::{self, ChatCommand};
+use crate::chat_transcript::ChatTranscript;
+use crate::commands::bandwidth::BandwidthState;
+use crate::commands::chat;
+use crate::commands::event_log::record_if_hosting;
+use crate::commands::notifications::{self, NotificationKind};
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+#[cfg(feature = "audio")]
+use crate::commands::{sound_scheme, tts, voice_message};
+use crate::error::{AppError, AppResult};
+use crate::event_log::{EventLog, RoomEventKind};
+use crate::events::{
+    self, PeerChannelEvent, PeerMediaLostEvent, PeerPresenceEvent, PresenceEvent,
+    RoomPolicyEvent, RosterUpdatedEvent,
+};
+use crate::i18n::{t, tf, Text};
+use crate::plugins::{self, PluginRegistry};
+use crate::server::{Peer, RosterUpdate, ServerState, LOCAL_PEER_ID};
+use crate::session::{self, SessionPhase, SessionState};
+use crate::webrtc::gathering::GatheringSender;
+use crate::webrtc::{
+    AudioMeshManager, AudioPacketSender, BinarySender, ConnectionOffer, MediaLostSender,
+    MessageSender, PeerStateSender, SignalingMessage, SignalingSender, calculate_audio_level, DEFAULT_CHANNEL,
+};
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
 
 /// Audio level info for a peer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,20 +49,272 @@ pub struct AudioLevelInfo {
     pub is_speaking: bool,
 }
 
+/// Room key of the mesh used before any room is explicitly joined
+pub const DEFAULT_ROOM: &str = "default";
+
+/// Status of a joined room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomStatus {
+    pub code: String,
+    pub peer_count: usize,
+    /// Mic audio is sent to this room
+    pub is_talk_room: bool,
+    /// Audio of this room is played
+    pub is_listen_room: bool,
+}
+
+/// Joined meshes and the rooms we talk and listen in
+/// Kept under one lock so the talk room always has a mesh
+struct Rooms {
+    meshes: HashMap<String, Arc<AudioMeshManager>>,
+    /// Room receiving our mic audio
+    talk: String,
+    /// Room whose audio is played
+    listen: String,
+}
+
 /// State for audio mesh operations
+/// One mesh instance per joined room, keyed by room code
 pub struct AudioMeshState {
-    manager: AudioMeshManager,
+    rooms: RwLock<Rooms>,
+    ban_list: BanList,
+    signaling_tx: RwLock<Option<SignalingSender>>,
+    audio_rx_tx: RwLock<Option<AudioPacketSender>>,
+    message_tx: RwLock<Option<MessageSender>>,
+    binary_tx: RwLock<Option<BinarySender>>,
+    peer_state_tx: RwLock<Option<PeerStateSender>>,
+    media_lost_tx: RwLock<Option<MediaLostSender>>,
+    gathering_tx: RwLock<Option<GatheringSender>>,
 }
 
 impl AudioMeshState {
     pub fn new() -> Self {
+        Self::with_ban_list(BanList::default())
+    }
+
+    pub fn with_ban_list(ban_list: BanList) -> Self {
+        let mut meshes = HashMap::new();
+        meshes.insert(
+            DEFAULT_ROOM.to_string(),
+            Arc::new(AudioMeshManager::with_ban_list(ban_list.clone())),
+        );
+
         Self {
-            manager: AudioMeshManager::new(),
+            rooms: RwLock::new(Rooms {
+                meshes,
+                talk: DEFAULT_ROOM.to_string(),
+                listen: DEFAULT_ROOM.to_string(),
+            }),
+            ban_list,
+            signaling_tx: RwLock::new(None),
+            audio_rx_tx: RwLock::new(None),
+            message_tx: RwLock::new(None),
+            binary_tx: RwLock::new(None),
+            peer_state_tx: RwLock::new(None),
+            media_lost_tx: RwLock::new(None),
+            gathering_tx: RwLock::new(None),
+        }
+    }
+
+    /// Mesh of the active (talk) room
+    pub fn manager(&self) -> AppResult<Arc<AudioMeshManager>> {
+        let rooms = self.rooms.read();
+        rooms
+            .meshes
+            .get(&rooms.talk)
+            .cloned()
+            .ok_or_else(|| AppError::NotConnected(format!("no mesh for room {}", rooms.talk)))
+    }
+
+    /// Code of the room receiving our mic audio
+    pub fn talk_room(&self) -> String {
+        self.rooms.read().talk.clone()
+    }
+
+    /// Mesh of a joined room
+    pub fn room(&self, code: &str) -> Option<Arc<AudioMeshManager>> {
+        self.rooms.read().meshes.get(code).cloned()
+    }
+
+    /// Mesh of the given room, or of the talk room when None
+    fn room_or_active(&self, code: Option<&str>) -> AppResult<Arc<AudioMeshManager>> {
+        match code {
+            Some(code) => self.room(code).ok_or_else(|| not_in_room(code)),
+            None => self.manager(),
+        }
+    }
+
+    /// All joined meshes
+    pub fn managers(&self) -> Vec<Arc<AudioMeshManager>> {
+        self.rooms.read().meshes.values().cloned().collect()
+    }
+
+    /// Join a room, creating its mesh (no-op if already joined)
+    pub fn join_room(&self, code: &str, username: String) -> Arc<AudioMeshManager> {
+        let mut rooms = self.rooms.write();
+        let listening = rooms.listen == code;
+        let manager = rooms.meshes.entry(code.to_string()).or_insert_with(|| {
+            let manager = AudioMeshManager::with_ban_list(self.ban_list.clone());
+            if let Some(tx) = self.signaling_tx.read().clone() {
+                manager.set_signaling_sender(tx);
+            }
+            if let Some(tx) = self.audio_rx_tx.read().clone() {
+                manager.set_audio_receiver(tx);
+            }
+            if let Some(tx) = self.message_tx.read().clone() {
+                manager.set_message_sender(tx);
+            }
+            if let Some(tx) = self.binary_tx.read().clone() {
+                manager.set_binary_sender(tx);
+            }
+            if let Some(tx) = self.peer_state_tx.read().clone() {
+                manager.set_peer_state_sender(tx);
+            }
+            if let Some(tx) = self.media_lost_tx.read().clone() {
+                manager.set_media_lost_sender(tx);
+            }
+            if let Some(tx) = self.gathering_tx.read().clone() {
+                manager.set_gathering_sender(tx);
+            }
+            manager.set_listening(listening);
+            tracing::info!("Joined room {}", code);
+            Arc::new(manager)
+        });
+        manager.set_username(username);
+        manager.clone()
+    }
+
+    /// Leave a room and close its mesh
+    /// If it was the talk or listen room, fall back to the default room
+    pub fn leave_room(&self, code: &str) -> AppResult<()> {
+        if code == DEFAULT_ROOM {
+            return Err(AppError::InvalidArgument(
+                "Cannot leave the default room".to_string(),
+            ));
+        }
+
+        // Switch away and remove under one lock so the talk room always has a mesh
+        let manager = {
+            let mut rooms = self.rooms.write();
+            let manager = rooms.meshes.remove(code).ok_or_else(|| not_in_room(code))?;
+            if rooms.talk == code {
+                rooms.talk = DEFAULT_ROOM.to_string();
+            }
+            if rooms.listen == code {
+                rooms.listen = DEFAULT_ROOM.to_string();
+                if let Some(default) = rooms.meshes.get(DEFAULT_ROOM) {
+                    default.set_listening(true);
+                }
+            }
+            manager
+        };
+        manager.close_all();
+
+        tracing::info!("Left room {}", code);
+        Ok(())
+    }
+
+    /// Choose which room receives mic audio
+    pub fn set_talk_room(&self, code: &str) -> AppResult<Arc<AudioMeshManager>> {
+        let mut rooms = self.rooms.write();
+        let manager = rooms.meshes.get(code).cloned().ok_or_else(|| not_in_room(code))?;
+        rooms.talk = code.to_string();
+        Ok(manager)
+    }
+
+    /// Choose which room's audio is played
+    pub fn set_listen_room(&self, code: &str) -> AppResult<()> {
+        let mut rooms = self.rooms.write();
+        if !rooms.meshes.contains_key(code) {
+            return Err(not_in_room(code));
+        }
+        for (room_code, manager) in rooms.meshes.iter() {
+            manager.set_listening(room_code == code);
+        }
+        rooms.listen = code.to_string();
+        Ok(())
+    }
+
+    /// Status of every joined room
+    pub fn list_rooms(&self) -> Vec<RoomStatus> {
+        let rooms = self.rooms.read();
+        rooms
+            .meshes
+            .iter()
+            .map(|(code, manager)| RoomStatus {
+                code: code.clone(),
+                peer_count: manager.peer_count(),
+                is_talk_room: *code == rooms.talk,
+                is_listen_room: *code == rooms.listen,
+            })
+            .collect()
+    }
+
+    /// Room a peer belongs to
+    pub fn room_of_peer(&self, peer_id: &str) -> Option<(String, Arc<AudioMeshManager>)> {
+        self.rooms
+            .read()
+            .meshes
+            .iter()
+            .find(|(_, manager)| manager.has_peer(peer_id))
+            .map(|(code, manager)| (code.clone(), manager.clone()))
+    }
+
+    /// Route incoming audio of every room (current and future) to `tx`
+    pub fn set_audio_receiver(&self, tx: AudioPacketSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_audio_receiver(tx.clone());
+        }
+        *self.audio_rx_tx.write() = Some(tx);
+    }
+
+    fn set_signaling_sender(&self, tx: SignalingSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_signaling_sender(tx.clone());
+        }
+        *self.signaling_tx.write() = Some(tx);
+    }
+
+    fn set_message_sender(&self, tx: MessageSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_message_sender(tx.clone());
+        }
+        *self.message_tx.write() = Some(tx);
+    }
+
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    fn set_binary_sender(&self, tx: BinarySender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_binary_sender(tx.clone());
         }
+        *self.binary_tx.write() = Some(tx);
     }
 
-    pub fn manager(&self) -> &AudioMeshManager {
-        &self.manager
+    fn set_peer_state_sender(&self, tx: PeerStateSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_peer_state_sender(tx.clone());
+        }
+        *self.peer_state_tx.write() = Some(tx);
+    }
+
+    fn set_media_lost_sender(&self, tx: MediaLostSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_media_lost_sender(tx.clone());
+        }
+        *self.media_lost_tx.write() = Some(tx);
+    }
+
+    /// Report the ICE gathering of every room (current and future) to `tx`
+    pub fn set_gathering_sender(&self, tx: GatheringSender) {
+        for manager in self.rooms.read().meshes.values() {
+            manager.set_gathering_sender(tx.clone());
+        }
+        *self.gathering_tx.write() = Some(tx);
+    }
+
+    /// Peers with a live connection, across every joined room
+    pub fn connected_peer_count(&self) -> usize {
+        self.rooms.read().meshes.values().map(|m| m.connected_peer_count()).sum()
     }
 }
 
@@ -39,55 +324,100 @@ impl Default for AudioMeshState {
     }
 }
 
+fn not_in_room(code: &str) -> AppError {
+    AppError::InvalidArgument(format!("Not in room {}", code))
+}
+
 // ============ AUDIO MESH COMMANDS ============
 
 /// Initialize audio mesh with username
 #[tauri::command]
-pub fn audio_mesh_init(state: State<'_, AudioMeshState>, username: String) {
-    state.manager().set_username(username);
+pub fn audio_mesh_init(state: State<'_, AudioMeshState>, username: String) -> AppResult<()> {
+    state.manager()?.set_username(username);
+    Ok(())
 }
 
 /// Enable/disable local audio
 #[tauri::command]
-pub fn audio_mesh_enable_audio(state: State<'_, AudioMeshState>, enabled: bool) {
-    state.manager().enable_local_audio(enabled);
+pub fn audio_mesh_enable_audio(
+    state: State<'_, AudioMeshState>,
+    enabled: bool,
+) -> AppResult<()> {
+    state.manager()?.enable_local_audio(enabled);
+    Ok(())
 }
 
 /// Check if audio is enabled
 #[tauri::command]
 pub fn audio_mesh_is_audio_enabled(state: State<'_, AudioMeshState>) -> bool {
-    state.manager().is_audio_enabled()
+    state.manager().is_ok_and(|m| m.is_audio_enabled())
 }
 
 /// Create offer for a peer with audio support
+/// When joining, `join_code` is the code the offer is sent with: that peer is
+/// the host of the joined server, the only one trusted with the roster
 #[tauri::command]
 pub async fn audio_mesh_create_offer(
+    app: AppHandle,
     state: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
     peer_id: String,
     peer_username: String,
-) -> Result<ConnectionOffer, String> {
-    state.manager().create_offer_for_peer(&peer_id, &peer_username).await
+    join_code: Option<String>,
+    room_code: Option<String>,
+) -> AppResult<ConnectionOffer> {
+    let manager = state.room_or_active(room_code.as_deref())?;
+    if let Some(code) = &join_code {
+        server.pin_host(&peer_id, code)?;
+    }
+    let offer = manager.create_offer_for_peer(&peer_id, &peer_username).await?;
+    negotiation_started(&app);
+    Ok(offer)
 }
 
 /// Accept offer from a peer with audio support
+/// When hosting, `join_code` is the code the peer joined with (server code or invite)
 #[tauri::command]
 pub async fn audio_mesh_accept_offer(
+    app: AppHandle,
     state: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
+    log: State<'_, EventLog>,
     peer_id: String,
     peer_username: String,
     offer_base64: String,
-) -> Result<ConnectionOffer, String> {
-    state.manager().accept_offer_from_peer(&peer_id, &peer_username, &offer_base64).await
+    join_code: Option<String>,
+    room_code: Option<String>,
+) -> AppResult<ConnectionOffer> {
+    let manager = state.room_or_active(room_code.as_deref())?;
+    server.validate_join_code(join_code.as_deref())?;
+    let answer = manager.accept_offer_from_peer(&peer_id, &peer_username, &offer_base64).await?;
+    // Consumed only now: a failed handshake doesn't use up the invite
+    if let Err(e) = server.consume_join_code(join_code.as_deref()) {
+        manager.remove_peer(&peer_id);
+        return Err(e.into());
+    }
+    negotiation_started(&app);
+    record_if_hosting(&log, &server, RoomEventKind::Join, &peer_id, &peer_username, join_code);
+    roster_peer_joined(&app, &manager, &peer_id, &peer_username).await;
+    Ok(answer)
 }
 
 /// Accept answer from a peer
 #[tauri::command]
 pub async fn audio_mesh_accept_answer(
+    app: AppHandle,
     state: State<'_, AudioMeshState>,
     peer_id: String,
     answer_base64: String,
-) -> Result<(), String> {
-    state.manager().accept_answer_from_peer(&peer_id, &answer_base64).await
+    room_code: Option<String>,
+) -> AppResult<()> {
+    let manager = state.room_or_active(room_code.as_deref())?;
+    manager.accept_answer_from_peer(&peer_id, &answer_base64).await?;
+    if let Some(username) = manager.peer_username(&peer_id) {
+        roster_peer_joined(&app, &manager, &peer_id, &username).await;
+    }
+    Ok(())
 }
 
 /// Send audio to all peers (broadcast)
@@ -96,8 +426,8 @@ pub async fn audio_mesh_accept_answer(
 pub async fn audio_mesh_broadcast_audio(
     state: State<'_, AudioMeshState>,
     opus_data: Vec<u8>,
-) -> Result<(), String> {
-    state.manager().broadcast_audio(&opus_data).await
+) -> AppResult<()> {
+    state.manager()?.broadcast_audio(&opus_data).await
 }
 
 /// Send audio to specific peer
@@ -106,47 +436,588 @@ pub async fn audio_mesh_send_audio_to_peer(
     state: State<'_, AudioMeshState>,
     peer_id: String,
     opus_data: Vec<u8>,
-) -> Result<(), String> {
-    state.manager().send_audio_to_peer(&peer_id, &opus_data).await
+) -> AppResult<()> {
+    state.manager()?.send_audio_to_peer(&peer_id, &opus_data).await
 }
 
-/// Send chat message to all peers
+/// Send chat message to all peers of the talk room (kept in its transcript)
+/// Messages starting with `/` are chat commands, run instead of being sent
 #[tauri::command]
 pub async fn audio_mesh_send_chat(
+    app: AppHandle,
     state: State<'_, AudioMeshState>,
+    transcript: State<'_, ChatTranscript>,
     message: String,
-) -> Result<(), String> {
-    state.manager().send_chat_message(&message).await
+) -> AppResult<()> {
+    if let Some(command) = ChatCommand::parse(&message) {
+        let command = command.map_err(|e| AppError::InvalidArgument(e.to_string()))?;
+        chat::run_command(&app, command).await?;
+        return Ok(());
+    }
+    let message = chat_commands::unescape(&message);
+
+    let room_code = state.talk_room();
+    let manager = state.manager()?;
+    manager.send_chat_message(message).await?;
+
+    let sender = manager
+        .get_local_username()
+        .unwrap_or_else(|| "Anonymous".to_string());
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    transcript.record(&room_code, &sender, message, timestamp, true);
+    Ok(())
 }
 
 /// Get list of connected peers
 #[tauri::command]
-pub fn audio_mesh_get_peers(state: State<'_, AudioMeshState>) -> Vec<String> {
-    state.manager().get_connected_peers()
+/// When in a server, this is the host-synced roster (minus ourselves)
+#[tauri::command]
+pub fn audio_mesh_get_peers(
+    state: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
+) -> Vec<String> {
+    if server.is_connected() {
+        return server
+            .roster()
+            .into_iter()
+            .filter(|p| p.id != LOCAL_PEER_ID)
+            .map(|p| p.username)
+            .collect();
+    }
+    state
+        .manager()
+        .map(|m| m.get_connected_peers())
+        .unwrap_or_default()
 }
 
 /// Get peer count
 #[tauri::command]
 pub fn audio_mesh_peer_count(state: State<'_, AudioMeshState>) -> usize {
-    state.manager().peer_count()
+    state.manager().map_or(0, |m| m.peer_count())
 }
 
 /// Check if connected to any peer
 #[tauri::command]
 pub fn audio_mesh_is_connected(state: State<'_, AudioMeshState>) -> bool {
-    state.manager().is_connected()
+    state.manager().is_ok_and(|m| m.is_connected())
 }
 
-/// Remove a peer
+/// Remove a peer (from whichever room it belongs to)
 #[tauri::command]
-pub fn audio_mesh_remove_peer(state: State<'_, AudioMeshState>, peer_id: String) {
-    state.manager().remove_peer(&peer_id);
+pub fn audio_mesh_remove_peer(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
+    log: State<'_, EventLog>,
+    peer_id: String,
+) {
+    if let Some((_, manager)) = state.room_of_peer(&peer_id) {
+        if let Some(username) = manager.peer_username(&peer_id) {
+            record_if_hosting(&log, &server, RoomEventKind::Leave, &peer_id, &username, None);
+        }
+        manager.remove_peer(&peer_id);
+        roster_peer_left(&app, manager, &peer_id);
+    }
 }
 
 /// Close all connections
 #[tauri::command]
-pub fn audio_mesh_close_all(state: State<'_, AudioMeshState>) {
-    state.manager().close_all();
+pub fn audio_mesh_close_all(app: AppHandle, state: State<'_, AudioMeshState>) -> AppResult<()> {
+    state.manager()?.close_all();
+    apply_capture_policy(&app, AudioPolicy::default());
+    Ok(())
+}
+
+// ============ ROOM SWITCHING ============
+
+/// Join a room (separate mesh, kept alongside the others)
+#[tauri::command]
+pub fn audio_mesh_join_room(state: State<'_, AudioMeshState>, room_code: String, username: String) {
+    state.join_room(&room_code, username);
+}
+
+/// Leave a room and close its connections
+#[tauri::command]
+pub fn audio_mesh_leave_room(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    room_code: String,
+) -> AppResult<()> {
+    state.leave_room(&room_code)?;
+    apply_capture_policy(&app, state.manager()?.effective_policy());
+    Ok(())
+}
+
+/// List joined rooms
+#[tauri::command]
+pub fn audio_mesh_list_rooms(state: State<'_, AudioMeshState>) -> Vec<RoomStatus> {
+    state.list_rooms()
+}
+
+/// Send mic audio to another joined room
+/// The policy of that room is enforced from now on
+#[tauri::command]
+pub fn audio_mesh_set_talk_room(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    room_code: String,
+) -> AppResult<()> {
+    let manager = state.set_talk_room(&room_code)?;
+    apply_capture_policy(&app, manager.effective_policy());
+    tracing::info!("Talking in room {}", room_code);
+    Ok(())
+}
+
+/// Play the audio of another joined room
+#[tauri::command]
+pub fn audio_mesh_set_listen_room(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    room_code: String,
+) -> AppResult<()> {
+    state.set_listen_room(&room_code)?;
+    // Drop decoders/buffers of the previous room
+    clear_playback_peers(&app);
+    tracing::info!("Listening to room {}", room_code);
+    Ok(())
+}
+
+// ============ VOICE CHANNELS ============
+
+/// Move to a voice channel of a room (audio stays within the channel, chat is room-wide)
+#[tauri::command]
+pub async fn audio_mesh_join_channel(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    channel: String,
+    room_code: Option<String>,
+) -> AppResult<()> {
+    let manager = state.room_or_active(room_code.as_deref())?;
+    manager.join_channel(&channel).await?;
+    // Peers of the previous channel are no longer heard
+    clear_playback_peers(&app);
+    tracing::info!("Moved to voice channel {}", channel);
+    Ok(())
+}
+
+/// List the voice channels of a room and who is in them
+#[tauri::command]
+pub fn audio_mesh_get_channels(
+    state: State<'_, AudioMeshState>,
+    room_code: Option<String>,
+) -> AppResult<Vec<VoiceChannelInfo>> {
+    let manager = state.room_or_active(room_code.as_deref())?;
+
+    let mut members: Vec<(String, String)> = manager
+        .peer_channels()
+        .into_iter()
+        .map(|(_, username, channel)| (channel, username))
+        .collect();
+    if let Some(username) = manager.get_local_username() {
+        members.push((manager.channel(), username));
+    }
+
+    let mut channels: Vec<VoiceChannelInfo> = Vec::new();
+    if !members.iter().any(|(c, _)| c == DEFAULT_CHANNEL) {
+        channels.push(VoiceChannelInfo {
+            name: DEFAULT_CHANNEL.to_string(),
+            members: Vec::new(),
+        });
+    }
+    for (channel, username) in members {
+        match channels.iter_mut().find(|c| c.name == channel) {
+            Some(info) => info.members.push(username),
+            None => channels.push(VoiceChannelInfo {
+                name: channel,
+                members: vec![username],
+            }),
+        }
+    }
+
+    Ok(channels)
+}
+
+// ============ ROOM POLICY ============
+
+/// Set the audio policy of the hosted room (host only)
+/// The policy is applied locally and advertised to every peer
+#[tauri::command]
+pub async fn audio_mesh_set_room_policy(
+    app: AppHandle,
+    state: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
+    policy: AudioPolicy,
+) -> AppResult<()> {
+    if !server.is_hosting() {
+        return Err(AppError::PermissionDenied(
+            "Only the host can set the room policy".to_string(),
+        ));
+    }
+
+    let manager = state.manager()?;
+    apply_capture_policy(&app, policy.clone());
+    manager.set_room_policy(Some(policy));
+    manager.broadcast_room_policy().await
+}
+
+/// Get the audio policy advertised by this host
+#[tauri::command]
+pub fn audio_mesh_get_room_policy(state: State<'_, AudioMeshState>) -> Option<AudioPolicy> {
+    state.manager().ok().and_then(|m| m.room_policy())
+}
+
+/// A voice channel and the users in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceChannelInfo {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+/// Enforce a room policy in the capture pipeline
+fn apply_capture_policy(app: &AppHandle, policy: AudioPolicy) {
+    #[cfg(feature = "audio")]
+    app.state::<StreamingState>().service.apply_policy(policy);
+    #[cfg(not(feature = "audio"))]
+    let _ = (app, policy);
+}
+
+/// Drop the decoders and buffered audio of the peers we no longer hear
+fn clear_playback_peers(app: &AppHandle) {
+    #[cfg(feature = "audio")]
+    app.state::<StreamingState>().service.clear_peers();
+    #[cfg(not(feature = "audio"))]
+    let _ = app;
+}
+
+/// Listen for control messages from peers and apply them
+/// A room policy received from the host is enforced in the capture pipeline
+pub fn spawn_signaling_task(app: AppHandle) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    app.state::<AudioMeshState>().set_signaling_sender(tx);
+
+    let signaling_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some((peer_id, msg)) = rx.recv().await {
+            handle_signaling_message(&signaling_app, &peer_id, msg).await;
+        }
+    });
+
+    // Peer connections drive the session between Connecting, InCall and Reconnecting
+    let (peer_state_tx, mut peer_state_rx) = mpsc::unbounded_channel();
+    app.state::<AudioMeshState>().set_peer_state_sender(peer_state_tx);
+    let peer_state_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some((peer_id, state)) = peer_state_rx.recv().await {
+            handle_peer_state(&peer_state_app, &peer_id, state);
+        }
+    });
+
+    // Remote tracks lost on a live connection (the mesh tries to recover them)
+    let (media_lost_tx, mut media_lost_rx) = mpsc::unbounded_channel();
+    app.state::<AudioMeshState>().set_media_lost_sender(media_lost_tx);
+    let media_lost_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(loss) = media_lost_rx.recv().await {
+            let _ = events::emit(
+                &media_lost_app,
+                &PeerMediaLostEvent {
+                    peer_id: loss.peer_id,
+                    kind: loss.kind,
+                    reason: loss.reason,
+                },
+            );
+        }
+    });
+
+    // Binary messages are voice message chunks
+    #[cfg(feature = "audio")]
+    {
+        let (binary_tx, mut binary_rx) = mpsc::unbounded_channel();
+        app.state::<AudioMeshState>().set_binary_sender(binary_tx);
+        let binary_app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some((peer_id, data)) = binary_rx.recv().await {
+                voice_message::receive(&binary_app, &peer_id, &data);
+            }
+        });
+    }
+
+    // Chat received over the mesh: kept in the room transcript, notified while
+    // the window is unfocused and read aloud when that option is on
+    let (message_tx, mut message_rx) = mpsc::unbounded_channel();
+    app.state::<AudioMeshState>().set_message_sender(message_tx);
+    tauri::async_runtime::spawn(async move {
+        while let Some((peer_id, text)) = message_rx.recv().await {
+            let Ok(SignalingMessage::Chat { sender, content, timestamp }) =
+                serde_json::from_str(&text)
+            else {
+                continue;
+            };
+            if let Some((room_code, _)) = app.state::<AudioMeshState>().room_of_peer(&peer_id) {
+                app.state::<ChatTranscript>()
+                    .record(&room_code, &sender, &content, timestamp, false);
+            }
+            notifications::notify(&app, NotificationKind::ChatMessage, &sender, &content);
+            #[cfg(feature = "audio")]
+            {
+                sound_scheme::play(&app, SoundEvent::ChatMessage);
+                tts::announce(&app, &sender, &content);
+            }
+        }
+    });
+}
+
+fn handle_peer_state(app: &AppHandle, peer_id: &str, state: RTCPeerConnectionState) {
+    // Closing connections after a disconnect must not start a new session
+    if !app.state::<SessionState>().phase().is_active() {
+        return;
+    }
+
+    let connected = app.state::<AudioMeshState>().connected_peer_count();
+    match state {
+        RTCPeerConnectionState::Connected => session::follow(app, SessionPhase::InCall),
+        RTCPeerConnectionState::Disconnected | RTCPeerConnectionState::Failed
+            if connected == 0 =>
+        {
+            tracing::info!("Lost peer {}, no connection left", peer_id);
+            session::follow(app, SessionPhase::Reconnecting);
+        }
+        // Peers left on purpose: back to waiting for peers
+        RTCPeerConnectionState::Closed if connected == 0 => {
+            session::follow(app, SessionPhase::Signaling)
+        }
+        _ => {}
+    }
+}
+
+/// Offer/answer exchange started: the session is now connecting
+fn negotiation_started(app: &AppHandle) {
+    if app.state::<SessionState>().phase() == SessionPhase::Signaling {
+        session::follow(app, SessionPhase::Connecting);
+    }
+}
+
+async fn handle_signaling_message(app: &AppHandle, peer_id: &str, msg: SignalingMessage) {
+    let server = app.state::<ServerState>();
+    let mesh = app.state::<AudioMeshState>();
+    let Some((room_code, manager)) = mesh.room_of_peer(peer_id) else {
+        return;
+    };
+
+    match msg {
+        SignalingMessage::RoomPolicy { policy } => {
+            if server.is_hosting() {
+                tracing::warn!("Ignoring room policy from peer {} while hosting", peer_id);
+                return;
+            }
+            // Only the host sets the policy of its room
+            if !server.is_host_peer(peer_id) {
+                tracing::warn!("Ignoring room policy from non-host peer {}", peer_id);
+                return;
+            }
+
+            manager.set_remote_policy(Some(policy.clone()));
+
+            // Only the talk room's policy constrains our capture
+            if mesh.manager().is_ok_and(|m| Arc::ptr_eq(&manager, &m)) {
+                apply_capture_policy(app, policy.clone());
+            }
+            let _ = events::emit(app, &RoomPolicyEvent { room_code, policy });
+        }
+
+        SignalingMessage::ChannelMove { channel } => {
+            manager.set_peer_channel(peer_id, &channel);
+            let _ = events::emit(
+                app,
+                &PeerChannelEvent {
+                    room_code,
+                    peer_id: peer_id.to_string(),
+                    channel,
+                },
+            );
+        }
+
+        SignalingMessage::Presence { away } => {
+            let _ = events::emit(
+                app,
+                &PeerPresenceEvent(PresenceEvent {
+                    peer_id: Some(peer_id.to_string()),
+                    away,
+                }),
+            );
+        }
+
+        // Host: answer with the authoritative roster, then the room policy
+        SignalingMessage::RosterRequest => {
+            if server.is_hosting() {
+                let msg = SignalingMessage::RosterSnapshot {
+                    snapshot: server.roster_snapshot(),
+                    recipient_id: peer_id.to_string(),
+                };
+                if let Err(e) = manager.send_signaling(peer_id, &msg).await {
+                    tracing::warn!("Failed to send roster to {}: {}", peer_id, e);
+                }
+                if let Some(policy) = manager.room_policy() {
+                    let msg = SignalingMessage::RoomPolicy { policy };
+                    if let Err(e) = manager.send_signaling(peer_id, &msg).await {
+                        tracing::warn!("Failed to send room policy to {}: {}", peer_id, e);
+                    }
+                }
+            }
+        }
+
+        SignalingMessage::RosterSnapshot { snapshot, recipient_id } => {
+            if server.is_hosting() {
+                return;
+            }
+            if server.apply_roster_snapshot(peer_id, &recipient_id, snapshot) {
+                emit_roster(app);
+            } else {
+                tracing::warn!("Ignoring roster snapshot from {} (not the host)", peer_id);
+            }
+        }
+
+        SignalingMessage::RosterJoined { version, peer } => {
+            apply_roster_update(app, &manager, peer_id, version, RosterUpdate::Joined(peer)).await;
+        }
+
+        SignalingMessage::RosterLeft { version, peer_id: left } => {
+            apply_roster_update(app, &manager, peer_id, version, RosterUpdate::Left(left)).await;
+        }
+
+        // The peer lost one of our tracks and renegotiates to get it back
+        SignalingMessage::RenegotiateOffer { sdp_base64 } => {
+            if let Err(e) = manager.accept_renegotiation(peer_id, &sdp_base64).await {
+                tracing::warn!("Failed to answer renegotiation from {}: {}", peer_id, e);
+            }
+        }
+
+        SignalingMessage::RenegotiateAnswer { sdp_base64 } => {
+            if let Err(e) = manager.complete_renegotiation(peer_id, &sdp_base64).await {
+                tracing::warn!("Failed to complete renegotiation with {}: {}", peer_id, e);
+            }
+        }
+
+        // Outcome of a chat command run by a peer
+        SignalingMessage::SystemMessage { message } => {
+            chat::receive_system_message(app, &manager, &room_code, peer_id, message);
+        }
+
+        // Message of a data-channel plugin (whiteboard, game state...)
+        SignalingMessage::Plugin { namespace, payload } => {
+            let registry = app.state::<PluginRegistry>();
+            plugins::dispatch(app, &registry, &manager, &room_code, peer_id, &namespace, payload);
+        }
+
+        SignalingMessage::BandwidthLimit { download_kbps } => {
+            // Only the peers we send to (the talk room) bound our upload
+            if !mesh.manager().is_ok_and(|m| Arc::ptr_eq(&manager, &m)) {
+                return;
+            }
+            tracing::info!("Peer {} download cap: {:?} kbps", peer_id, download_kbps);
+            app.state::<BandwidthState>()
+                .set_peer_download_cap(peer_id, download_kbps);
+        }
+
+        _ => {}
+    }
+}
+
+/// Apply an incremental roster update from the host, resyncing on a gap
+async fn apply_roster_update(
+    app: &AppHandle,
+    manager: &AudioMeshManager,
+    host_peer_id: &str,
+    version: u64,
+    update: RosterUpdate,
+) {
+    let server = app.state::<ServerState>();
+    if server.is_hosting() {
+        return;
+    }
+    // Only the host maintains the roster
+    if !server.is_host_peer(host_peer_id) {
+        tracing::warn!("Ignoring roster update from non-host peer {}", host_peer_id);
+        return;
+    }
+
+    #[cfg(feature = "audio")]
+    let cue = match &update {
+        RosterUpdate::Joined(_) => SoundEvent::PeerJoined,
+        RosterUpdate::Left(_) => SoundEvent::PeerLeft,
+    };
+    if server.apply_roster_update(version, update) {
+        emit_roster(app);
+        #[cfg(feature = "audio")]
+        sound_scheme::play(app, cue);
+    } else {
+        tracing::info!("Roster out of sync, requesting snapshot");
+        let _ = manager
+            .send_signaling(host_peer_id, &SignalingMessage::RosterRequest)
+            .await;
+    }
+}
+
+/// Host: add a peer to the roster and broadcast the update
+pub(crate) async fn roster_peer_joined(
+    app: &AppHandle,
+    manager: &AudioMeshManager,
+    peer_id: &str,
+    username: &str,
+) {
+    let server = app.state::<ServerState>();
+    if !server.is_hosting() {
+        return;
+    }
+
+    let peer = Peer {
+        id: peer_id.to_string(),
+        username: username.to_string(),
+        is_host: false,
+    };
+    if let Some(version) = server.add_peer(peer.clone()) {
+        let _ = manager
+            .broadcast_signaling(&SignalingMessage::RosterJoined { version, peer })
+            .await;
+        emit_roster(app);
+        #[cfg(feature = "audio")]
+        sound_scheme::play(app, SoundEvent::PeerJoined);
+        notifications::notify(
+            app,
+            NotificationKind::JoinRequest,
+            t(Text::PeerJoinedTitle),
+            &tf(Text::PeerJoinedBody, &[("username", username)]),
+        );
+    }
+}
+
+/// Host: remove a peer from the roster and broadcast the update
+pub(crate) fn roster_peer_left(app: &AppHandle, manager: Arc<AudioMeshManager>, peer_id: &str) {
+    let server = app.state::<ServerState>();
+    if !server.is_hosting() {
+        return;
+    }
+
+    if let Some(version) = server.remove_peer(peer_id) {
+        let msg = SignalingMessage::RosterLeft {
+            version,
+            peer_id: peer_id.to_string(),
+        };
+        tauri::async_runtime::spawn(async move {
+            let _ = manager.broadcast_signaling(&msg).await;
+        });
+        emit_roster(app);
+        #[cfg(feature = "audio")]
+        sound_scheme::play(app, SoundEvent::PeerLeft);
+    }
+}
+
+fn emit_roster(app: &AppHandle) {
+    let peers = app.state::<ServerState>().roster();
+    let _ = events::emit(app, &RosterUpdatedEvent { peers });
 }
 
 /// Calculate audio level from samples (utility for frontend)
@@ -0,0 +1,159 @@
+//! Bandwidth cap commands
+//! Upload/download limits and the budget shared out between voice, the screen
+//! share and voice message uploads, re-evaluated as peers come and go
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::bandwidth::{self, Allocation, BandwidthLimits, MIN_CAP_KBPS};
+use crate::commands::audio_mesh::AudioMeshState;
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const BANDWIDTH_FILE: &str = "bandwidth.json";
+
+/// How often the budget is re-evaluated (peers joining, screen share starting)
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// State for the bandwidth caps
+pub struct BandwidthState {
+    limits: RwLock<BandwidthLimits>,
+    /// Download caps advertised by the peers
+    peer_caps: RwLock<HashMap<String, u32>>,
+    /// Budget currently applied
+    allocation: RwLock<Allocation>,
+}
+
+impl BandwidthState {
+    pub fn new() -> Self {
+        let limits = storage::load_json::<BandwidthLimits>(BANDWIDTH_FILE)
+            .filter(|l| l.validate().is_ok())
+            .unwrap_or_default();
+        Self {
+            limits: RwLock::new(limits),
+            peer_caps: RwLock::new(HashMap::new()),
+            allocation: RwLock::new(Allocation::default()),
+        }
+    }
+
+    pub fn limits(&self) -> BandwidthLimits {
+        *self.limits.read()
+    }
+
+    /// Record the download cap a peer advertised
+    /// Caps below what voice needs are raised to `MIN_CAP_KBPS`, so a peer
+    /// can't starve the room's upload.
+    pub fn set_peer_download_cap(&self, peer_id: &str, download_kbps: Option<u32>) {
+        let mut peer_caps = self.peer_caps.write();
+        match download_kbps {
+            Some(kbps) => peer_caps.insert(peer_id.to_string(), kbps.max(MIN_CAP_KBPS)),
+            None => peer_caps.remove(peer_id),
+        };
+    }
+
+    /// Voice message upload rate allowed right now in kbps (None = not limited)
+    pub fn transfer_kbps(&self) -> Option<u32> {
+        self.allocation.read().transfer_kbps
+    }
+}
+
+impl Default for BandwidthState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Share the budget out again and apply it to the subsystems if it changed
+async fn refresh(app: &AppHandle) {
+    let state = app.state::<BandwidthState>();
+    let mesh = app.state::<AudioMeshState>();
+
+    // Meshes joined since the last pass learn our download cap (no-op otherwise)
+    let download_kbps = state.limits().download_kbps;
+    for manager in mesh.managers() {
+        if let Err(e) = manager.set_download_cap(download_kbps).await {
+            tracing::warn!("Failed to advertise the download cap: {}", e);
+        }
+    }
+
+    let Ok(manager) = mesh.manager() else {
+        return;
+    };
+    let peers = manager.connected_peer_count();
+
+    // Peers that left keep no say
+    let peer_bound = {
+        let mut peer_caps = state.peer_caps.write();
+        peer_caps.retain(|peer_id, _| manager.has_peer(peer_id));
+        bandwidth::peer_bound(peer_caps.values().copied(), peers)
+    };
+    let upload = match (state.limits().upload_kbps, peer_bound) {
+        (Some(ours), Some(theirs)) => Some(ours.min(theirs)),
+        (ours, theirs) => ours.or(theirs),
+    };
+
+    #[cfg(feature = "screen")]
+    let screen_active = app.state::<ScreenStreamState>().is_streaming();
+    #[cfg(not(feature = "screen"))]
+    let screen_active = false;
+
+    let allocation = bandwidth::allocate(upload, peers, screen_active);
+    if *state.allocation.read() == allocation {
+        return;
+    }
+    tracing::info!("Bandwidth budget: {:?}", allocation);
+
+    #[cfg(feature = "audio")]
+    app.state::<StreamingState>()
+        .service
+        .set_bitrate_cap(allocation.audio_kbps.map(|kbps| kbps as i32 * 1000));
+    #[cfg(feature = "screen")]
+    app.state::<ScreenStreamState>()
+        .set_bitrate_cap(allocation.screen_kbps);
+    *state.allocation.write() = allocation;
+}
+
+/// Follow the peers and the screen share for the lifetime of the app
+pub fn spawn_bandwidth_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app).await;
+        }
+    });
+}
+
+// ============ BANDWIDTH COMMANDS ============
+
+/// Get the upload and download caps
+#[tauri::command]
+pub fn bandwidth_get_limits(state: State<'_, BandwidthState>) -> BandwidthLimits {
+    state.limits()
+}
+
+/// Set the upload and download caps (the download cap is advertised to the peers)
+#[tauri::command]
+pub async fn bandwidth_set_limits(
+    app: AppHandle,
+    state: State<'_, BandwidthState>,
+    limits: BandwidthLimits,
+) -> AppResult<Allocation> {
+    limits.validate()?;
+    storage::save_json(BANDWIDTH_FILE, &limits).map_err(AppError::Storage)?;
+    *state.limits.write() = limits;
+    refresh(&app).await;
+    Ok(*state.allocation.read())
+}
+
+/// Get how the upload budget is shared out right now
+#[tauri::command]
+pub fn bandwidth_get_allocation(state: State<'_, BandwidthState>) -> Allocation {
+    *state.allocation.read()
+}
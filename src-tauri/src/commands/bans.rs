@@ -0,0 +1,95 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::bans::{BanEntry, BanList};
+use crate::commands::audio_mesh::{roster_peer_left, AudioMeshState};
+use crate::commands::event_log::record_if_hosting;
+use crate::error::{AppError, AppResult};
+use crate::event_log::{EventLog, RoomEventKind};
+use crate::server::ServerState;
+use crate::webrtc::MeshManager;
+
+/// Bannir un peer connecté (par son identité)
+#[tauri::command]
+pub fn ban_peer(
+    app: AppHandle,
+    bans: State<'_, BanList>,
+    mesh: State<'_, MeshManager>,
+    audio_mesh: State<'_, AudioMeshState>,
+    server: State<'_, ServerState>,
+    log: State<'_, EventLog>,
+    peer_id: String,
+    reason: Option<String>,
+) -> AppResult<BanEntry> {
+    let audio_room = audio_mesh.room_of_peer(&peer_id).map(|(_, manager)| manager);
+    let identity = audio_room
+        .as_ref()
+        .and_then(|manager| manager.peer_fingerprint(&peer_id))
+        .or_else(|| mesh.peer_fingerprint(&peer_id));
+
+    let entry = bans.ban_identity(identity, reason)?;
+
+    mesh.remove_peer(&peer_id);
+    if let Some(manager) = audio_room {
+        manager.remove_peer(&peer_id);
+        roster_peer_left(&app, manager, &peer_id);
+    }
+
+    record_if_hosting(
+        &log,
+        &server,
+        RoomEventKind::Ban,
+        &peer_id,
+        &entry.username,
+        entry.reason.clone(),
+    );
+
+    Ok(entry)
+}
+
+/// Expulser un peer connecté (sans le bannir)
+#[tauri::command]
+pub fn kick_peer(app: AppHandle, peer_id: String, reason: Option<String>) -> AppResult<()> {
+    kick(&app, &peer_id, reason)
+}
+
+/// Expulser un peer (commande `kick_peer` ou `/kick` dans le chat)
+pub fn kick(app: &AppHandle, peer_id: &str, reason: Option<String>) -> AppResult<()> {
+    let mesh = app.state::<MeshManager>();
+    let audio_room = app
+        .state::<AudioMeshState>()
+        .room_of_peer(peer_id)
+        .map(|(_, manager)| manager);
+    let username = audio_room
+        .as_ref()
+        .and_then(|manager| manager.peer_username(peer_id))
+        .or_else(|| mesh.peer_username(peer_id))
+        .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))?;
+
+    mesh.remove_peer(peer_id);
+    if let Some(manager) = audio_room {
+        manager.remove_peer(peer_id);
+        roster_peer_left(app, manager, peer_id);
+    }
+
+    record_if_hosting(
+        &app.state::<EventLog>(),
+        &app.state::<ServerState>(),
+        RoomEventKind::Kick,
+        peer_id,
+        &username,
+        reason,
+    );
+    Ok(())
+}
+
+/// Lister les identités bannies
+#[tauri::command]
+pub fn ban_list(bans: State<'_, BanList>) -> Vec<BanEntry> {
+    bans.list()
+}
+
+/// Retirer un ban
+#[tauri::command]
+pub fn ban_remove(bans: State<'_, BanList>, fingerprint: String) -> AppResult<()> {
+    bans.unban(&fingerprint).map_err(Into::into)
+}
@@ -0,0 +1,270 @@
+//! Call timer
+//! Counts how long the call has lasted, warns when configured thresholds are
+//! crossed and, with a time limit, ends the screen share or the whole call once
+//! it is reached (metered connections, or just keeping calls short). The clock
+//! starts when the first peer connection is attempted and stops when the
+//! session ends.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{AppError, AppResult};
+use crate::events::{self, CallTimerExpiredEvent, CallTimerTickEvent, CallTimerWarningEvent};
+use crate::session::{SessionPhase, SessionState};
+use crate::storage;
+
+const CALL_TIMER_FILE: &str = "call_timer.json";
+
+/// Elapsed-time events are sent this often
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// At most this many warning thresholds
+const MAX_WARNINGS: usize = 8;
+
+/// Longest limit or threshold accepted (24h)
+const MAX_MINUTES: u32 = 24 * 60;
+
+/// What happens when the time limit is reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    /// Stop sharing the screen, stay in the call
+    StopStreaming,
+    /// Leave the call
+    #[default]
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CallTimerSettings {
+    pub enabled: bool,
+    /// Warn when the call reaches these durations, in minutes
+    pub warn_at_minutes: Vec<u32>,
+    /// Time limit in minutes (None: no limit)
+    pub limit_minutes: Option<u32>,
+    pub limit_action: LimitAction,
+}
+
+impl CallTimerSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.warn_at_minutes.len() > MAX_WARNINGS {
+            return Err(AppError::InvalidArgument(format!(
+                "At most {} warnings can be set",
+                MAX_WARNINGS
+            )));
+        }
+        let in_range = |minutes: &u32| (1..=MAX_MINUTES).contains(minutes);
+        if !self.warn_at_minutes.iter().all(in_range) || !self.limit_minutes.iter().all(in_range) {
+            return Err(AppError::InvalidArgument(format!(
+                "Durations must be between 1 and {} minutes",
+                MAX_MINUTES
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTimerStatus {
+    /// The clock is running (enabled and in a call)
+    pub running: bool,
+    pub elapsed_secs: u64,
+    /// Time left before the limit, when one is set
+    pub remaining_secs: Option<u64>,
+}
+
+/// The clock of the current call
+struct Run {
+    started: Instant,
+    /// Elapsed time at the previous tick, warnings fire for thresholds past it
+    checked_secs: u64,
+    expired: bool,
+}
+
+/// State for the call timer
+pub struct CallTimerState {
+    settings: RwLock<CallTimerSettings>,
+    run: Mutex<Option<Run>>,
+}
+
+impl CallTimerState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(
+                storage::load_json::<CallTimerSettings>(CALL_TIMER_FILE)
+                    .filter(|s| s.validate().is_ok())
+                    .unwrap_or_default(),
+            ),
+            run: Mutex::new(None),
+        }
+    }
+
+    fn status(&self) -> CallTimerStatus {
+        let limit = self.settings.read().limit_minutes;
+        let run = self.run.lock();
+        let elapsed_secs = run
+            .as_ref()
+            .map_or(0, |run| run.started.elapsed().as_secs());
+        CallTimerStatus {
+            running: run.is_some(),
+            elapsed_secs,
+            remaining_secs: limit.map(|m| (m as u64 * 60).saturating_sub(elapsed_secs)),
+        }
+    }
+}
+
+impl Default for CallTimerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thresholds (in minutes) reached between `from_secs` (excluded) and `to_secs`
+fn crossed(thresholds: &[u32], from_secs: u64, to_secs: u64) -> Vec<u32> {
+    let mut crossed: Vec<u32> = thresholds
+        .iter()
+        .copied()
+        .filter(|&m| (from_secs + 1..=to_secs).contains(&(m as u64 * 60)))
+        .collect();
+    crossed.sort_unstable();
+    crossed.dedup();
+    crossed
+}
+
+/// Advance the clock by one tick
+fn tick(app: &AppHandle) {
+    let state = app.state::<CallTimerState>();
+    let settings = state.settings.read().clone();
+    let phase = app.state::<SessionState>().phase();
+    let in_call = matches!(
+        phase,
+        SessionPhase::Connecting | SessionPhase::InCall | SessionPhase::Reconnecting
+    );
+
+    let mut run = state.run.lock();
+    // Running from the first connection attempt until the session ends
+    if !settings.enabled || !phase.is_active() {
+        if run.take().is_some() {
+            tracing::debug!("Call timer stopped");
+        }
+        return;
+    }
+    if run.is_none() && in_call {
+        *run = Some(Run {
+            started: Instant::now(),
+            checked_secs: 0,
+            expired: false,
+        });
+    }
+    let Some(run) = run.as_mut() else {
+        return;
+    };
+
+    let elapsed_secs = run.started.elapsed().as_secs();
+    let limit_secs = settings.limit_minutes.map(|m| m as u64 * 60);
+    let remaining_secs = limit_secs.map(|limit| limit.saturating_sub(elapsed_secs));
+    let _ = events::emit(
+        app,
+        &CallTimerTickEvent {
+            elapsed_secs,
+            remaining_secs,
+        },
+    );
+
+    for minutes in crossed(&settings.warn_at_minutes, run.checked_secs, elapsed_secs) {
+        tracing::info!("Call timer: {} min reached", minutes);
+        let _ = events::emit(
+            app,
+            &CallTimerWarningEvent {
+                minutes,
+                remaining_secs,
+            },
+        );
+    }
+    run.checked_secs = elapsed_secs;
+
+    if !run.expired && limit_secs.is_some_and(|limit| elapsed_secs >= limit) {
+        run.expired = true;
+        tracing::info!(
+            "Call time limit reached ({:?} min), {:?}",
+            settings.limit_minutes,
+            settings.limit_action
+        );
+        // The frontend owns the peer connections and the share, as for the tray
+        let _ = events::emit(
+            app,
+            &CallTimerExpiredEvent {
+                action: settings.limit_action,
+            },
+        );
+    }
+}
+
+/// Follow the session for the lifetime of the app
+pub fn spawn_call_timer_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            tick(&app);
+        }
+    });
+}
+
+// ============ CALL TIMER COMMANDS ============
+
+/// Get the call timer settings
+#[tauri::command]
+pub fn call_timer_get_settings(state: State<'_, CallTimerState>) -> CallTimerSettings {
+    state.settings.read().clone()
+}
+
+/// Update the call timer settings (a new limit applies to the current call)
+#[tauri::command]
+pub fn call_timer_set_settings(
+    state: State<'_, CallTimerState>,
+    settings: CallTimerSettings,
+) -> AppResult<CallTimerStatus> {
+    settings.validate()?;
+    storage::save_json(CALL_TIMER_FILE, &settings).map_err(AppError::Storage)?;
+    let limit_changed = state.settings.read().limit_minutes != settings.limit_minutes;
+    *state.settings.write() = settings;
+    // A raised or removed limit can be reached again
+    if limit_changed {
+        if let Some(run) = state.run.lock().as_mut() {
+            run.expired = false;
+        }
+    }
+    Ok(state.status())
+}
+
+/// Get the elapsed and remaining time of the current call
+#[tauri::command]
+pub fn call_timer_get_status(state: State<'_, CallTimerState>) -> CallTimerStatus {
+    state.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_once_per_threshold() {
+        let thresholds = [30, 5, 30];
+        assert_eq!(crossed(&thresholds, 0, 299), Vec::<u32>::new());
+        assert_eq!(crossed(&thresholds, 299, 300), vec![5]);
+        assert_eq!(crossed(&thresholds, 300, 301), Vec::<u32>::new());
+        // A missed tick still fires every threshold passed meanwhile
+        assert_eq!(crossed(&thresholds, 0, 3600), vec![5, 30]);
+
+        let settings = CallTimerSettings {
+            enabled: true,
+            warn_at_minutes: vec![0],
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+}
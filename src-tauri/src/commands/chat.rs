@@ -0,0 +1,255 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::oneshot;
+
+use crate::chat_commands::{ChatCommand, SystemMessage, SystemMessageKind};
+use crate::chat_history::ChatSearchHit;
+use crate::chat_transcript::{ChatExportFormat, ChatTranscript};
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::commands::bans;
+#[cfg(feature = "audio")]
+use crate::commands::streaming;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, ChatSystemMessageEvent};
+use crate::server::{ServerError, ServerState};
+use crate::session::SessionState;
+use crate::webrtc::{AudioMeshManager, SignalingMessage};
+
+/// Ajouter à la transcription un message du chat de l'interface (envoyé ou reçu)
+/// Les messages reçus déclenchent le son associé et sont lus à voix haute si
+/// ces options sont activées.
+#[tauri::command]
+pub fn chat_record_message(
+    app: AppHandle,
+    transcript: State<'_, ChatTranscript>,
+    room_code: String,
+    sender: String,
+    content: String,
+    timestamp_ms: u64,
+    outgoing: bool,
+) {
+    transcript.record(&room_code, &sender, &content, timestamp_ms / 1000, outgoing);
+    #[cfg(feature = "audio")]
+    if !outgoing {
+        crate::commands::sound_scheme::play(&app, crate::audio::SoundEvent::ChatMessage);
+        crate::commands::tts::announce(&app, &sender, &content);
+    }
+}
+
+/// Exécuter une commande du chat (message commençant par `/`)
+/// Retourne le message système envoyé à la room.
+#[tauri::command]
+pub async fn chat_run_command(app: AppHandle, message: String) -> AppResult<SystemMessage> {
+    let command = ChatCommand::parse(&message)
+        .ok_or_else(|| AppError::InvalidArgument("Not a chat command".to_string()))?
+        .map_err(|e| AppError::InvalidArgument(e.to_string()))?;
+    run_command(&app, command).await
+}
+
+/// Sujet de la room (None si aucun)
+#[tauri::command]
+pub fn chat_get_topic(server: State<'_, ServerState>) -> Option<String> {
+    server.topic()
+}
+
+/// Exécuter une commande dans la room où l'on parle
+/// La modération (`/mute`, `/kick`, `/topic`) est réservée à l'hôte ; le
+/// résultat part vers les pairs comme message système.
+pub async fn run_command(app: &AppHandle, command: ChatCommand) -> AppResult<SystemMessage> {
+    if !app.state::<SessionState>().phase().is_active() {
+        return Err(ServerError::NotRunning.into());
+    }
+    let server = app.state::<ServerState>();
+    if command.requires_host() && !server.is_hosting() {
+        return Err(AppError::PermissionDenied(
+            "Only the host can moderate the room".to_string(),
+        ));
+    }
+
+    let mesh = app.state::<AudioMeshState>();
+    let room_code = mesh.talk_room();
+    let manager = mesh.manager()?;
+    let actor = manager
+        .get_local_username()
+        .unwrap_or_else(|| "Anonymous".to_string());
+
+    // Pair visé, retrouvé par son pseudo
+    let target = match &command {
+        ChatCommand::Mute { target } | ChatCommand::Kick { target } => {
+            Some(manager.find_peer(target)?)
+        }
+        _ => None,
+    };
+
+    let mut message = SystemMessage::new(&command, actor, now_secs());
+    if let Some((_, username)) = &target {
+        message.target = Some(username.clone());
+    }
+    if message.kind == SystemMessageKind::Topic {
+        server.set_topic(message.text.clone());
+    }
+
+    manager
+        .broadcast_signaling(&SignalingMessage::SystemMessage {
+            message: message.clone(),
+        })
+        .await?;
+    // Expulsé après l'envoi, pour qu'il sache pourquoi
+    if let (SystemMessageKind::Kicked, Some((peer_id, _))) = (message.kind, &target) {
+        bans::kick(app, peer_id, Some(message.describe()))?;
+    }
+
+    show_system_message(app, &room_code, &message, true);
+    Ok(message)
+}
+
+/// Appliquer un message système reçu d'un pair
+/// La modération n'est acceptée que de l'hôte, `/me` qu'au nom de l'expéditeur.
+pub fn receive_system_message(
+    app: &AppHandle,
+    manager: &AudioMeshManager,
+    room_code: &str,
+    peer_id: &str,
+    message: SystemMessage,
+) {
+    let server = app.state::<ServerState>();
+    if message.requires_host() && !server.is_host_peer(peer_id) {
+        tracing::warn!(
+            "Ignoring {:?} from peer {} (not the host)",
+            message.kind,
+            peer_id
+        );
+        return;
+    }
+    if !message.requires_host()
+        && manager.peer_username(peer_id).as_deref() != Some(message.actor.as_str())
+    {
+        tracing::warn!(
+            "Ignoring system message from {} on behalf of another",
+            peer_id
+        );
+        return;
+    }
+
+    match message.kind {
+        SystemMessageKind::Topic => server.set_topic(message.text.clone()),
+        SystemMessageKind::Muted if message.target == manager.get_local_username() => {
+            #[cfg(feature = "audio")]
+            streaming::set_muted(app, true);
+        }
+        _ => {}
+    }
+    show_system_message(app, room_code, &message, false);
+}
+
+/// Garder le message dans la transcription et l'afficher
+fn show_system_message(app: &AppHandle, room_code: &str, message: &SystemMessage, outgoing: bool) {
+    let line = message.describe();
+    app.state::<ChatTranscript>().record(
+        room_code,
+        &message.actor,
+        &line,
+        message.timestamp,
+        outgoing,
+    );
+    let _ = events::emit(
+        app,
+        &ChatSystemMessageEvent {
+            room_code: room_code.to_string(),
+            message: message.clone(),
+            line,
+        },
+    );
+}
+
+/// Horloge système avant 1970 : on date à 0 plutôt que de paniquer
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Chercher dans l'historique du chat (d'une room, ou de toutes)
+/// Les résultats les plus pertinents d'abord, avec les messages qui les entourent.
+#[tauri::command]
+pub async fn chat_search(
+    transcript: State<'_, ChatTranscript>,
+    query: String,
+    room_code: Option<String>,
+    limit: Option<usize>,
+) -> AppResult<Vec<ChatSearchHit>> {
+    let transcript = transcript.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        transcript.search(&query, room_code.as_deref(), limit)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(AppError::Storage)
+}
+
+/// Effacer l'historique du chat d'une room, ou de toutes sans `room_code`
+#[tauri::command]
+pub fn chat_clear_history(
+    transcript: State<'_, ChatTranscript>,
+    room_code: Option<String>,
+) -> AppResult<()> {
+    transcript
+        .clear(room_code.as_deref())
+        .map_err(AppError::Storage)
+}
+
+/// Exporter le chat d'une room en JSON ou en page HTML autonome
+/// Sans `path`, le fichier est choisi avec la boîte de dialogue d'enregistrement.
+/// Retourne le chemin écrit, ou None si l'utilisateur a annulé.
+#[tauri::command]
+pub async fn chat_export(
+    app: AppHandle,
+    transcript: State<'_, ChatTranscript>,
+    room_code: String,
+    format: ChatExportFormat,
+    path: Option<String>,
+) -> AppResult<Option<String>> {
+    let contents = transcript
+        .render(&room_code, format)
+        .map_err(AppError::Internal)?;
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => match pick_destination(&app, &room_code, format).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+
+    std::fs::write(&path, contents)
+        .map_err(|e| AppError::Storage(format!("Failed to write {}: {}", path.display(), e)))?;
+    tracing::info!("Chat of room {} exported to {}", room_code, path.display());
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// Demander où enregistrer l'export (None si annulé)
+async fn pick_destination(
+    app: &AppHandle,
+    room_code: &str,
+    format: ChatExportFormat,
+) -> AppResult<Option<PathBuf>> {
+    let extension = format.extension();
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(format!("hydrowland-chat-{}.{}", room_code, extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    match rx.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| AppError::InvalidArgument(format!("Invalid export path: {}", e))),
+        None => Ok(None),
+    }
+}
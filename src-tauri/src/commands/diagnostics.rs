@@ -0,0 +1,425 @@
+//! Diagnostics commands
+//! Log level control, log export, a one-shot report for bug reports and a
+//! self-test for the first-run health screen
+
+#[cfg(feature = "audio")]
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::audio_mesh::{AudioMeshState, RoomStatus};
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::{screen_stream_get_stats, ScreenStreamState, StreamStatsResponse};
+#[cfg(feature = "audio")]
+use crate::audio::{AudioBackend, AudioSendStats, CpalBackend};
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::logging;
+#[cfg(debug_assertions)]
+use crate::netsim::{self, NetSimConfig};
+use crate::perf::{self, PerfUsage};
+#[cfg(feature = "screen")]
+use crate::screen::ScreenCapture;
+use crate::server::{ServerInfo, ServerState};
+use crate::storage;
+use crate::webrtc::ice_check::{self, IceServerCheck};
+
+/// Max time spent gathering candidates per STUN server
+const ICE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Support report returned by `diagnostics_run`
+#[derive(Debug, Serialize)]
+pub struct DiagnosticsReport {
+    pub generated_at: u64,
+    pub system: SystemInfo,
+    pub audio: AudioReport,
+    pub screen_permission: bool,
+    pub ice: Vec<IceServerCheck>,
+    pub session: SessionReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub arch: String,
+    pub app_version: String,
+    pub tauri_version: String,
+    pub guest_mode: bool,
+    pub log_level: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioReport {
+    pub host: String,
+    pub inputs: Vec<AudioDeviceReport>,
+    pub outputs: Vec<AudioDeviceReport>,
+    /// Set when devices could not be enumerated
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioDeviceReport {
+    pub name: String,
+    pub is_default: bool,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub sample_format: Option<String>,
+    /// Set when the default config could not be read
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionReport {
+    pub server: Option<ServerInfo>,
+    pub rooms: Vec<RoomStatus>,
+    pub is_capturing: bool,
+    pub is_playing: bool,
+    pub is_muted: bool,
+    pub input_device: Option<String>,
+    /// Absent in builds without the `audio` feature
+    #[cfg(feature = "audio")]
+    pub audio_send: AudioSendStats,
+    /// Absent in builds without the `screen` feature
+    #[cfg(feature = "screen")]
+    pub screen_stream: StreamStatsResponse,
+}
+
+/// How long the self-test keeps each audio device open
+#[cfg(feature = "audio")]
+const DEVICE_PROBE_DURATION: Duration = Duration::from_millis(300);
+
+/// Self-test results, one entry per check
+#[derive(Debug, Serialize)]
+pub struct SelfTestReport {
+    /// No check failed (skipped ones don't count)
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelfTestCheck {
+    /// "audio_input", "audio_output", "webrtc" or "config_dir"
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Outcome of one check: Ok(detail) passes, Err fails, None is skipped
+type CheckOutcome = Option<Result<Option<String>, String>>;
+
+fn check(name: &str, started: Instant, outcome: CheckOutcome) -> SelfTestCheck {
+    let (status, detail) = match outcome {
+        Some(Ok(detail)) => (CheckStatus::Passed, detail),
+        Some(Err(e)) => (CheckStatus::Failed, Some(e)),
+        None => (CheckStatus::Skipped, None),
+    };
+    if status == CheckStatus::Failed {
+        tracing::warn!("Self-test {} failed: {:?}", name, detail);
+    }
+    SelfTestCheck {
+        name: name.to_string(),
+        status,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Get the current log filter
+#[tauri::command]
+pub fn diagnostics_get_log_level() -> String {
+    logging::level()
+}
+
+/// Change the log filter at runtime ("debug", "info", "hydrowland_lib=trace", ...)
+#[tauri::command]
+pub fn diagnostics_set_log_level(level: String) -> AppResult<()> {
+    logging::set_level(&level)
+}
+
+/// Zip recent log files for a bug report
+/// Writes to `destination`, or to the downloads folder when None. Returns the archive path.
+#[tauri::command]
+pub fn diagnostics_export_logs(destination: Option<String>) -> AppResult<String> {
+    let path = match destination {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            dirs::download_dir()
+                .unwrap_or_else(logging::log_dir)
+                .join(format!("hydrowland-logs-{}.zip", timestamp))
+        }
+    };
+
+    logging::export_logs(&path)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Gather system, audio, screen, network and session info into a single report
+#[tauri::command]
+pub async fn diagnostics_run(
+    app: AppHandle,
+    server: State<'_, ServerState>,
+    audio_mesh: State<'_, AudioMeshState>,
+) -> AppResult<DiagnosticsReport> {
+    tracing::info!("Running diagnostics");
+
+    #[cfg_attr(not(feature = "audio"), allow(unused_mut))]
+    let mut session = SessionReport {
+        server: server.get_server_info(),
+        rooms: audio_mesh.list_rooms(),
+        is_capturing: false,
+        is_playing: false,
+        is_muted: true,
+        input_device: None,
+        #[cfg(feature = "audio")]
+        audio_send: app.state::<StreamingState>().service.send_stats(),
+        #[cfg(feature = "screen")]
+        screen_stream: screen_stream_get_stats(app.state::<ScreenStreamState>()),
+    };
+    #[cfg(feature = "audio")]
+    {
+        let streaming = app.state::<StreamingState>();
+        session.is_capturing = streaming.service.is_capturing();
+        session.is_playing = streaming.service.is_playing();
+        session.is_muted = streaming.service.is_muted();
+        session.input_device = streaming.service.get_input_device();
+    }
+
+    Ok(DiagnosticsReport {
+        generated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        system: SystemInfo {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            app_version: app.package_info().version.to_string(),
+            tauri_version: tauri::VERSION.to_string(),
+            guest_mode: storage::is_guest_mode(),
+            log_level: logging::level(),
+        },
+        audio: audio_report(),
+        #[cfg(feature = "screen")]
+        screen_permission: ScreenCapture::check_permission(),
+        #[cfg(not(feature = "screen"))]
+        screen_permission: false,
+        ice: ice_check::check_stun_servers(ICE_CHECK_TIMEOUT).await,
+        session,
+    })
+}
+
+/// CPU time of the capture, denoise, encode, screen encode and WebRTC paths
+/// since the previous call, and the process memory
+#[tauri::command]
+pub fn perf_get_usage() -> PerfUsage {
+    perf::usage()
+}
+
+/// Get the simulated network conditions (debug builds only)
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub fn netsim_get_config() -> NetSimConfig {
+    netsim::config()
+}
+
+/// Drop and delay outgoing audio/video packets (debug builds only)
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub fn netsim_set_config(config: NetSimConfig) -> AppResult<()> {
+    netsim::configure(config)
+}
+
+/// Open and close the default audio devices, build a throwaway peer connection
+/// and check that the config dir is writable
+#[tauri::command]
+pub async fn self_test(app: AppHandle) -> AppResult<SelfTestReport> {
+    tracing::info!("Running self-test");
+    let mut checks = Vec::new();
+
+    #[cfg(feature = "audio")]
+    {
+        let streaming = app.state::<StreamingState>();
+        let (capturing, playing) = (streaming.service.is_capturing(), streaming.service.is_playing());
+        let (input, output) = tauri::async_runtime::spawn_blocking(move || {
+            (probe_input(capturing), probe_output(playing))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Audio self-test panicked: {}", e)))?;
+        checks.push(input);
+        checks.push(output);
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        let _ = &app;
+        checks.push(check("audio_input", Instant::now(), None));
+        checks.push(check("audio_output", Instant::now(), None));
+    }
+
+    let started = Instant::now();
+    let webrtc = ice_check::check_local_stack().await;
+    checks.push(check("webrtc", started, Some(webrtc.map(|()| None).map_err(|e| e.to_string()))));
+
+    let started = Instant::now();
+    checks.push(check("config_dir", started, check_config_dir()));
+
+    Ok(SelfTestReport {
+        passed: checks.iter().all(|c| c.status != CheckStatus::Failed),
+        checks,
+    })
+}
+
+/// Open the default input until it delivers audio
+#[cfg(feature = "audio")]
+fn probe_input(in_use: bool) -> SelfTestCheck {
+    let started = Instant::now();
+    if in_use {
+        return check("audio_input", started, Some(Ok(Some("In use by the voice pipeline".to_string()))));
+    }
+    let outcome = (|| {
+        let backend = CpalBackend;
+        let (name, _) = backend.input_format(None).map_err(|e| e.to_string())?;
+        let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = frames.clone();
+        let stream = backend
+            .start_input(
+                None,
+                Box::new(move |data: &[f32]| {
+                    counter.fetch_add(data.len(), std::sync::atomic::Ordering::Relaxed);
+                }),
+                Box::new(|e| tracing::debug!("Self-test input error: {}", e)),
+            )
+            .map_err(|e| e.to_string())?;
+        std::thread::sleep(DEVICE_PROBE_DURATION);
+        drop(stream);
+        if frames.load(std::sync::atomic::Ordering::Relaxed) == 0 {
+            return Err(format!("{}: no audio received", name));
+        }
+        Ok(Some(name))
+    })();
+    check("audio_input", started, Some(outcome))
+}
+
+/// Open the default output, playing silence
+#[cfg(feature = "audio")]
+fn probe_output(in_use: bool) -> SelfTestCheck {
+    let started = Instant::now();
+    if in_use {
+        return check("audio_output", started, Some(Ok(Some("In use by the voice pipeline".to_string()))));
+    }
+    let outcome = (|| {
+        let backend = CpalBackend;
+        let (name, _) = backend.output_format(None).map_err(|e| e.to_string())?;
+        let stream = backend
+            .start_output(
+                None,
+                Box::new(|data: &mut [f32]| data.fill(0.0)),
+                Box::new(|e| tracing::debug!("Self-test output error: {}", e)),
+            )
+            .map_err(|e| e.to_string())?;
+        std::thread::sleep(DEVICE_PROBE_DURATION);
+        drop(stream);
+        Ok(Some(name))
+    })();
+    check("audio_output", started, Some(outcome))
+}
+
+/// Write and remove a probe file in the config dir (skipped in guest mode)
+fn check_config_dir() -> CheckOutcome {
+    if storage::is_guest_mode() {
+        return None;
+    }
+    let dir = storage::config_dir();
+    let probe = dir.join(".self-test");
+    let result = std::fs::write(&probe, b"ok")
+        .and_then(|()| std::fs::remove_file(&probe))
+        .map(|()| Some(dir.to_string_lossy().into_owned()))
+        .map_err(|e| format!("{}: {}", dir.display(), e));
+    Some(result)
+}
+
+#[cfg(not(feature = "audio"))]
+fn audio_report() -> AudioReport {
+    AudioReport {
+        host: "none".to_string(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        error: Some("Built without the audio feature".to_string()),
+    }
+}
+
+#[cfg(feature = "audio")]
+fn audio_report() -> AudioReport {
+    let host = cpal::default_host();
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+    let mut errors = Vec::new();
+
+    let inputs = match host.input_devices() {
+        Ok(devices) => devices
+            .map(|d| device_report(&d, default_input.as_deref(), d.default_input_config()))
+            .collect(),
+        Err(e) => {
+            errors.push(format!("input: {}", e));
+            Vec::new()
+        }
+    };
+    let outputs = match host.output_devices() {
+        Ok(devices) => devices
+            .map(|d| device_report(&d, default_output.as_deref(), d.default_output_config()))
+            .collect(),
+        Err(e) => {
+            errors.push(format!("output: {}", e));
+            Vec::new()
+        }
+    };
+
+    AudioReport {
+        host: format!("{:?}", host.id()),
+        inputs,
+        outputs,
+        error: (!errors.is_empty()).then(|| errors.join("; ")),
+    }
+}
+
+#[cfg(feature = "audio")]
+fn device_report(
+    device: &cpal::Device,
+    default_name: Option<&str>,
+    config: Result<cpal::SupportedStreamConfig, cpal::DefaultStreamConfigError>,
+) -> AudioDeviceReport {
+    let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+    let is_default = default_name == Some(name.as_str());
+    match config {
+        Ok(config) => AudioDeviceReport {
+            name,
+            is_default,
+            sample_rate: Some(config.sample_rate().0),
+            channels: Some(config.channels()),
+            sample_format: Some(format!("{:?}", config.sample_format())),
+            error: None,
+        },
+        Err(e) => AudioDeviceReport {
+            name,
+            is_default,
+            sample_rate: None,
+            channels: None,
+            sample_format: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
@@ -0,0 +1,49 @@
+use tauri::State;
+
+use crate::event_log::{EventLog, RoomEvent, RoomEventKind};
+use crate::error::{AppError, AppResult};
+use crate::server::ServerState;
+
+/// Enregistrer un événement dans le journal (uniquement si on héberge)
+pub(crate) fn record_if_hosting(
+    log: &EventLog,
+    server: &ServerState,
+    kind: RoomEventKind,
+    peer_id: &str,
+    username: &str,
+    detail: Option<String>,
+) {
+    if let Some(code) = server.hosted_code() {
+        log.record(kind, &code, peer_id, username, detail);
+    }
+}
+
+/// Obtenir le journal des événements de room (les `limit` derniers)
+#[tauri::command]
+pub fn room_get_event_log(log: State<'_, EventLog>, limit: Option<usize>) -> Vec<RoomEvent> {
+    log.list(limit)
+}
+
+/// Vider le journal des événements
+#[tauri::command]
+pub fn room_clear_event_log(log: State<'_, EventLog>) -> AppResult<()> {
+    log.clear().map_err(AppError::Storage)
+}
+
+/// Enregistrer une migration d'hôte (appelé par le nouvel hôte)
+#[tauri::command]
+pub fn room_record_host_migration(
+    log: State<'_, EventLog>,
+    server: State<'_, ServerState>,
+    previous_host_id: String,
+    previous_host_username: String,
+) {
+    record_if_hosting(
+        &log,
+        &server,
+        RoomEventKind::HostMigration,
+        &previous_host_id,
+        &previous_host_username,
+        None,
+    );
+}
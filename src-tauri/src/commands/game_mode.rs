@@ -0,0 +1,158 @@
+//! Game mode
+//! While a fullscreen app (usually a game) has the focus, the screen share
+//! drops to a lower frame rate and bitrate and the playback buffer gets deeper,
+//! so the call doesn't fight the game for CPU and stutter. Everything is
+//! restored once the app leaves the foreground.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+#[cfg(feature = "audio")]
+use crate::audio::PLAYBACK_BUFFER_FRAMES;
+use crate::commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, GameModeChangedEvent};
+use crate::screen::ScreenCapture;
+use crate::storage;
+
+const GAME_MODE_FILE: &str = "game_mode.json";
+
+/// How often the foreground app is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Playback buffer while a game runs (~200ms)
+#[cfg(feature = "audio")]
+const GAME_PLAYBACK_BUFFER_FRAMES: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameModeSettings {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameModeStatus {
+    pub enabled: bool,
+    /// Reductions are applied
+    pub active: bool,
+    /// Fullscreen app they are applied for
+    pub app_name: Option<String>,
+}
+
+/// State for the game mode
+pub struct GameModeState {
+    settings: RwLock<GameModeSettings>,
+    /// Fullscreen app the reductions are applied for
+    active: Mutex<Option<String>>,
+}
+
+impl GameModeState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(GAME_MODE_FILE).unwrap_or_default()),
+            active: Mutex::new(None),
+        }
+    }
+
+    fn status(&self) -> GameModeStatus {
+        let app_name = self.active.lock().clone();
+        GameModeStatus {
+            enabled: self.settings.read().enabled,
+            active: app_name.is_some(),
+            app_name,
+        }
+    }
+}
+
+impl Default for GameModeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lower the call's load, or restore it
+fn apply(app: &AppHandle, reduced: bool) {
+    app.state::<ScreenStreamState>().set_reduced(reduced);
+    #[cfg(feature = "audio")]
+    app.state::<StreamingState>()
+        .service
+        .set_playback_buffer_frames(if reduced {
+            GAME_PLAYBACK_BUFFER_FRAMES
+        } else {
+            PLAYBACK_BUFFER_FRAMES
+        });
+}
+
+/// Follow `fullscreen_app`, telling the UI when the mode switches
+fn update(app: &AppHandle, fullscreen_app: Option<String>) {
+    let state = app.state::<GameModeState>();
+    let wanted = fullscreen_app.filter(|_| state.settings.read().enabled);
+    {
+        let mut active = state.active.lock();
+        let switched = active.is_some() != wanted.is_some();
+        *active = wanted.clone();
+        if !switched {
+            return;
+        }
+    }
+
+    apply(app, wanted.is_some());
+    match &wanted {
+        Some(name) => tracing::info!("Game mode on ({} is fullscreen)", name),
+        None => tracing::info!("Game mode off"),
+    }
+    let _ = events::emit(
+        app,
+        &GameModeChangedEvent {
+            active: wanted.is_some(),
+            app_name: wanted,
+        },
+    );
+}
+
+/// Watch the foreground app for the lifetime of the app
+pub fn spawn_game_mode_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if !app.state::<GameModeState>().settings.read().enabled {
+                continue;
+            }
+            // Enumerating windows can take a few ms
+            let fullscreen_app = tokio::task::spawn_blocking(ScreenCapture::fullscreen_app)
+                .await
+                .ok()
+                .flatten();
+            update(&app, fullscreen_app);
+        }
+    });
+}
+
+// ============ GAME MODE COMMANDS ============
+
+/// Get the game mode setting and whether it is active
+#[tauri::command]
+pub fn game_mode_get_status(state: State<'_, GameModeState>) -> GameModeStatus {
+    state.status()
+}
+
+/// Turn the game mode on or off (turning it off restores the settings now)
+#[tauri::command]
+pub fn game_mode_set_enabled(
+    app: AppHandle,
+    state: State<'_, GameModeState>,
+    enabled: bool,
+) -> AppResult<GameModeStatus> {
+    let settings = GameModeSettings { enabled };
+    storage::save_json(GAME_MODE_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    if !enabled {
+        update(&app, None);
+    }
+    Ok(state.status())
+}
@@ -0,0 +1,41 @@
+//! Command guards
+//! State-mutating commands of a subsystem run one at a time, so a double-click
+//! can't interleave two starts. The second call then finds the state the first
+//! one left and returns it instead of failing or spawning a duplicate task.
+
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Group of commands sharing one guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subsystem {
+    /// Hosting, joining and disconnecting
+    Session,
+    /// Mic capture and playback
+    Voice,
+    /// Screen streaming
+    Screen,
+}
+
+/// One lock per subsystem, held for the whole command
+#[derive(Default)]
+pub struct CommandGuards {
+    session: Mutex<()>,
+    voice: Mutex<()>,
+    screen: Mutex<()>,
+}
+
+impl CommandGuards {
+    /// Wait for the running command of `subsystem` (if any) to finish
+    pub async fn lock(&self, subsystem: Subsystem) -> MutexGuard<'_, ()> {
+        let mutex = match subsystem {
+            Subsystem::Session => &self.session,
+            Subsystem::Voice => &self.voice,
+            Subsystem::Screen => &self.screen,
+        };
+        if let Ok(guard) = mutex.try_lock() {
+            return guard;
+        }
+        tracing::debug!("{:?} command waiting for the previous one", subsystem);
+        mutex.lock().await
+    }
+}
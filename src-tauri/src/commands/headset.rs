@@ -0,0 +1,105 @@
+//! Headset button commands
+//! The mute and call buttons of telephony headsets toggle the mute state, the
+//! same way the UI button and the mute hotkey do.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::streaming;
+use crate::error::{AppError, AppResult};
+use crate::headset;
+use crate::storage;
+
+const HEADSET_FILE: &str = "headset.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeadsetSettings {
+    /// Toggle mute from the headset buttons
+    pub enabled: bool,
+}
+
+impl Default for HeadsetSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// State for the headset buttons
+pub struct HeadsetState {
+    settings: RwLock<HeadsetSettings>,
+    /// Run flag of the current watcher (None: stopped)
+    watcher: Mutex<Option<Arc<AtomicBool>>>,
+    /// Names of the headsets being followed
+    headsets: Arc<Mutex<Vec<String>>>,
+}
+
+impl HeadsetState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(HEADSET_FILE).unwrap_or_default()),
+            watcher: Mutex::new(None),
+            headsets: Arc::default(),
+        }
+    }
+}
+
+impl Default for HeadsetState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Start or stop following the headsets, per the settings
+pub fn apply(app: &AppHandle) {
+    let state = app.state::<HeadsetState>();
+    let enabled = state.settings.read().enabled;
+    let mut watcher = state.watcher.lock();
+    match (enabled, watcher.is_some()) {
+        (true, false) => {
+            let running = Arc::new(AtomicBool::new(true));
+            let app = app.clone();
+            headset::spawn_watcher(running.clone(), state.headsets.clone(), move |button| {
+                tracing::debug!("Headset {:?} button pressed", button);
+                streaming::toggle_mute(&app);
+            });
+            *watcher = Some(running);
+        }
+        (false, true) => {
+            if let Some(running) = watcher.take() {
+                running.store(false, Ordering::SeqCst);
+            }
+        }
+        _ => {}
+    }
+}
+
+// ============ HEADSET COMMANDS ============
+
+/// Get headset button settings
+#[tauri::command]
+pub fn headset_get_settings(state: State<'_, HeadsetState>) -> HeadsetSettings {
+    state.settings.read().clone()
+}
+
+/// Update headset button settings
+#[tauri::command]
+pub fn headset_set_settings(
+    app: AppHandle,
+    state: State<'_, HeadsetState>,
+    settings: HeadsetSettings,
+) -> AppResult<()> {
+    storage::save_json(HEADSET_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    apply(&app);
+    Ok(())
+}
+
+/// List the headsets whose buttons are followed
+#[tauri::command]
+pub fn headset_list_devices(state: State<'_, HeadsetState>) -> Vec<String> {
+    state.headsets.lock().clone()
+}
@@ -0,0 +1,131 @@
+//! Global hotkeys
+//! System-wide shortcuts for mute, deafen and push-to-talk, active even when
+//! the window is unfocused
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::commands::streaming::{self, StreamingState};
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const HOTKEYS_FILE: &str = "hotkeys.json";
+
+/// Shortcuts use the accelerator syntax, e.g. "CmdOrCtrl+Shift+M" (None = disabled)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeySettings {
+    pub toggle_mute: Option<String>,
+    pub toggle_deafen: Option<String>,
+    pub push_to_talk: Option<String>,
+    /// Only transmit while the push-to-talk shortcut is held
+    pub push_to_talk_enabled: bool,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            toggle_mute: Some("CmdOrCtrl+Shift+M".to_string()),
+            toggle_deafen: Some("CmdOrCtrl+Shift+D".to_string()),
+            push_to_talk: None,
+            push_to_talk_enabled: false,
+        }
+    }
+}
+
+impl HotkeySettings {
+    fn parse(shortcut: &Option<String>) -> AppResult<Option<Shortcut>> {
+        shortcut
+            .as_deref()
+            .map(|s| {
+                s.parse::<Shortcut>()
+                    .map_err(|e| AppError::InvalidArgument(format!("Invalid shortcut '{}': {}", s, e)))
+            })
+            .transpose()
+    }
+}
+
+/// State for global hotkeys
+pub struct HotkeyState {
+    settings: RwLock<HotkeySettings>,
+}
+
+impl HotkeyState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(HOTKEYS_FILE).unwrap_or_default()),
+        }
+    }
+}
+
+impl Default for HotkeyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============ HOTKEY COMMANDS ============
+
+/// Get hotkey settings
+#[tauri::command]
+pub fn hotkeys_get_settings(state: State<'_, HotkeyState>) -> HotkeySettings {
+    state.settings.read().clone()
+}
+
+/// Update hotkey settings and re-register the shortcuts
+#[tauri::command]
+pub fn hotkeys_set_settings(
+    app: AppHandle,
+    state: State<'_, HotkeyState>,
+    settings: HotkeySettings,
+) -> AppResult<()> {
+    // Validate everything before touching the registered shortcuts
+    HotkeySettings::parse(&settings.toggle_mute)?;
+    HotkeySettings::parse(&settings.toggle_deafen)?;
+    HotkeySettings::parse(&settings.push_to_talk)?;
+
+    storage::save_json(HOTKEYS_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    register_all(&app)
+}
+
+/// Register the configured shortcuts (replaces any previous registration)
+pub fn register_all(app: &AppHandle) -> AppResult<()> {
+    let settings = app.state::<HotkeyState>().settings.read().clone();
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .unregister_all()
+        .map_err(|e| AppError::Internal(format!("Failed to unregister shortcuts: {}", e)))?;
+
+    let register = |shortcut: Option<Shortcut>, action: fn(&AppHandle, ShortcutState)| {
+        let Some(shortcut) = shortcut else {
+            return Ok(());
+        };
+        shortcuts
+            .on_shortcut(shortcut, move |app, _, event| action(app, event.state()))
+            .map_err(|e| AppError::Internal(format!("Failed to register shortcut: {}", e)))
+    };
+
+    register(HotkeySettings::parse(&settings.toggle_mute)?, |app, state| {
+        if state == ShortcutState::Pressed {
+            streaming::toggle_mute(app);
+        }
+    })?;
+    register(HotkeySettings::parse(&settings.toggle_deafen)?, |app, state| {
+        if state == ShortcutState::Pressed {
+            streaming::toggle_deafen(app);
+        }
+    })?;
+    register(HotkeySettings::parse(&settings.push_to_talk)?, |app, state| {
+        streaming::set_push_to_talk_pressed(app, state == ShortcutState::Pressed);
+    })?;
+
+    // Without a shortcut the mic would stay closed forever
+    app.state::<StreamingState>()
+        .service
+        .set_push_to_talk_mode(settings.push_to_talk_enabled && settings.push_to_talk.is_some());
+
+    tracing::info!("Global hotkeys registered: {:?}", settings);
+    Ok(())
+}
@@ -1,8 +1,54 @@
+pub mod app;
+#[cfg(feature = "audio")]
 pub mod audio;
 pub mod audio_mesh;
+pub mod bandwidth;
+pub mod bans;
+pub mod call_timer;
+pub mod chat;
+pub mod diagnostics;
+pub mod event_log;
+#[cfg(feature = "screen")]
+pub mod game_mode;
+pub mod guard;
+#[cfg(feature = "audio")]
+pub mod headset;
+#[cfg(feature = "audio")]
+pub mod hotkeys;
+pub mod notifications;
+#[cfg(feature = "audio")]
+pub mod pip;
+pub mod plugins;
+pub mod port_mapping;
+pub mod presence;
+#[cfg(feature = "screen")]
+pub mod preview_server;
+pub mod proxy;
+pub mod quality;
+#[cfg(feature = "audio")]
+pub mod recording;
 pub mod room;
+#[cfg(feature = "screen")]
 pub mod screen;
+#[cfg(feature = "screen")]
+pub mod screen_popout;
+#[cfg(feature = "screen")]
 pub mod screen_stream;
 pub mod server;
+#[cfg(feature = "audio")]
+pub mod sound_scheme;
+pub mod stats_overlay;
+#[cfg(feature = "audio")]
 pub mod streaming;
+#[cfg(feature = "transcription")]
+pub mod transcription;
+#[cfg(feature = "audio")]
+pub mod tts;
+pub mod updater;
+#[cfg(feature = "video")]
+pub mod virtual_camera;
+#[cfg(feature = "audio")]
+pub mod voice_message;
 pub mod webrtc;
+#[cfg(feature = "whip")]
+pub mod whip;
@@ -0,0 +1,140 @@
+//! Native notifications
+//! OS notifications for chat messages, join requests and file offers, shown only
+//! while the main window is unfocused and governed by do-not-disturb and per-event settings
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const NOTIFICATIONS_FILE: &str = "notifications.json";
+
+/// Kind of event a notification is shown for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ChatMessage,
+    JoinRequest,
+    FileOffer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Do not disturb: silence everything without losing the per-event choices
+    pub do_not_disturb: bool,
+    pub chat_messages: bool,
+    pub join_requests: bool,
+    pub file_offers: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            do_not_disturb: false,
+            chat_messages: true,
+            join_requests: true,
+            file_offers: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether this kind of event may raise a notification
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        if !self.enabled || self.do_not_disturb {
+            return false;
+        }
+        match kind {
+            NotificationKind::ChatMessage => self.chat_messages,
+            NotificationKind::JoinRequest => self.join_requests,
+            NotificationKind::FileOffer => self.file_offers,
+        }
+    }
+}
+
+/// State for notifications
+pub struct NotificationState {
+    settings: RwLock<NotificationSettings>,
+}
+
+impl NotificationState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(NOTIFICATIONS_FILE).unwrap_or_default()),
+        }
+    }
+}
+
+impl Default for NotificationState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Show an OS notification unless settings forbid it or the window has focus
+pub fn notify(app: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    if !app.state::<NotificationState>().settings.read().allows(kind) {
+        return;
+    }
+
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return;
+    }
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
+
+// ============ NOTIFICATION COMMANDS ============
+
+/// Get notification settings
+#[tauri::command]
+pub fn notifications_get_settings(state: State<'_, NotificationState>) -> NotificationSettings {
+    state.settings.read().clone()
+}
+
+/// Update notification settings
+#[tauri::command]
+pub fn notifications_set_settings(
+    state: State<'_, NotificationState>,
+    settings: NotificationSettings,
+) -> AppResult<()> {
+    storage::save_json(NOTIFICATIONS_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    Ok(())
+}
+
+/// Notify for an event received by the frontend (e.g. chat over its own peer connections)
+#[tauri::command]
+pub fn notifications_notify(app: AppHandle, kind: NotificationKind, title: String, body: String) {
+    notify(&app, kind, &title, &body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn do_not_disturb_silences_every_kind() {
+        let mut settings = NotificationSettings::default();
+        assert!(settings.allows(NotificationKind::ChatMessage));
+
+        settings.join_requests = false;
+        assert!(!settings.allows(NotificationKind::JoinRequest));
+        assert!(settings.allows(NotificationKind::FileOffer));
+
+        settings.do_not_disturb = true;
+        assert!(!settings.allows(NotificationKind::ChatMessage));
+        assert!(!settings.allows(NotificationKind::FileOffer));
+    }
+}
@@ -0,0 +1,178 @@
+//! Picture-in-picture
+//! Tiny always-on-top window showing the active speaker's screen share, e.g.
+//! while the main window is minimized. Received frames are pushed here and
+//! only the active speaker's are sent to that window.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, EncodedFrameData, PipFrameEvent, PipStateEvent};
+use crate::i18n::{t, Text};
+
+/// Window label, also the route of its page
+const PIP_LABEL: &str = "pip";
+
+/// State of the picture-in-picture window
+#[derive(Default)]
+pub struct PipState {
+    /// Open the window when the main window is minimized, close it on restore
+    auto: AtomicBool,
+    /// The open window was opened by auto mode (one opened by hand stays open)
+    auto_opened: AtomicBool,
+    /// Latest frame of each presenter while the window is open, shown as soon
+    /// as one of them takes the floor
+    frames: Mutex<HashMap<String, EncodedFrameData>>,
+}
+
+fn is_open(app: &AppHandle) -> bool {
+    app.get_webview_window(PIP_LABEL).is_some()
+}
+
+fn open(app: &AppHandle) -> AppResult<()> {
+    if is_open(app) {
+        return Ok(());
+    }
+
+    let url = WebviewUrl::App(format!("index.html#{}", PIP_LABEL).into());
+    let window = WebviewWindowBuilder::new(app, PIP_LABEL, url)
+        .title(t(Text::PipTitle))
+        .inner_size(320.0, 180.0)
+        .min_inner_size(160.0, 90.0)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to open the PiP window: {}", e)))?;
+
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let state = handle.state::<PipState>();
+            state.auto_opened.store(false, Ordering::SeqCst);
+            state.frames.lock().clear();
+            let _ = events::emit(&handle, &PipStateEvent { open: false });
+        }
+    });
+    let _ = events::emit(app, &PipStateEvent { open: true });
+    Ok(())
+}
+
+fn close(app: &AppHandle) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window(PIP_LABEL) {
+        window
+            .close()
+            .map_err(|e| AppError::Internal(format!("Failed to close the PiP window: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Send `frame` from `presenter_id` to the window if they hold the floor
+pub fn route_frame(app: &AppHandle, presenter_id: &str, frame: &EncodedFrameData) {
+    if !is_open(app) {
+        return;
+    }
+    app.state::<PipState>()
+        .frames
+        .lock()
+        .insert(presenter_id.to_string(), frame.clone());
+
+    let speaker = app.state::<StreamingState>().service.active_speaker();
+    if speaker.as_deref() == Some(presenter_id) {
+        let _ = events::emit_to(
+            app,
+            PIP_LABEL,
+            &PipFrameEvent {
+                peer_id: presenter_id.to_string(),
+                frame: frame.clone(),
+            },
+        );
+    }
+}
+
+/// The floor changed: show the new speaker's latest frame right away
+pub fn speaker_changed(app: &AppHandle, peer_id: Option<&str>) {
+    if !is_open(app) {
+        return;
+    }
+    let frame = peer_id.and_then(|id| app.state::<PipState>().frames.lock().get(id).cloned());
+    if let (Some(peer_id), Some(frame)) = (peer_id, frame) {
+        let _ = events::emit_to(
+            app,
+            PIP_LABEL,
+            &PipFrameEvent {
+                peer_id: peer_id.to_string(),
+                frame,
+            },
+        );
+    }
+}
+
+/// Main window hook: follow its minimized state in auto mode
+pub fn on_main_window_event(window: &Window, event: &WindowEvent) {
+    if !matches!(event, WindowEvent::Resized(_) | WindowEvent::Focused(_)) {
+        return;
+    }
+    let app = window.app_handle();
+    let state = app.state::<PipState>();
+    if !state.auto.load(Ordering::SeqCst) {
+        return;
+    }
+    let result = if window.is_minimized().unwrap_or(false) {
+        if is_open(app) {
+            return;
+        }
+        state.auto_opened.store(true, Ordering::SeqCst);
+        open(app)
+    } else if state.auto_opened.swap(false, Ordering::SeqCst) {
+        close(app)
+    } else {
+        return;
+    };
+    if let Err(e) = result {
+        tracing::warn!("Picture-in-picture: {}", e);
+    }
+}
+
+// ============ PIP COMMANDS ============
+
+/// Open the picture-in-picture window (kept open when the main window is restored)
+#[tauri::command]
+pub fn pip_open(app: AppHandle, state: State<'_, PipState>) -> AppResult<()> {
+    state.auto_opened.store(false, Ordering::SeqCst);
+    open(&app)
+}
+
+/// Close the picture-in-picture window
+#[tauri::command]
+pub fn pip_close(app: AppHandle) -> AppResult<()> {
+    close(&app)
+}
+
+/// Whether the picture-in-picture window is open
+#[tauri::command]
+pub fn pip_is_open(app: AppHandle) -> bool {
+    is_open(&app)
+}
+
+/// Open the window when the main window is minimized (and close it on restore)
+#[tauri::command]
+pub fn pip_set_auto(state: State<'_, PipState>, enabled: bool) {
+    state.auto.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether the window follows the main window's minimized state
+#[tauri::command]
+pub fn pip_get_auto(state: State<'_, PipState>) -> bool {
+    state.auto.load(Ordering::SeqCst)
+}
+
+/// Push a received screen frame (only sent on if its presenter has the floor)
+#[tauri::command]
+pub fn pip_push_frame(app: AppHandle, presenter_id: String, frame: EncodedFrameData) {
+    route_frame(&app, &presenter_id, &frame);
+}
@@ -0,0 +1,52 @@
+//! Data-channel plugin commands
+//! The webview claims a namespace to receive its messages as
+//! `plugin:<namespace>:message` events, and sends under any valid namespace.
+
+use serde_json::Value;
+use tauri::State;
+
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::error::{AppError, AppResult};
+use crate::plugins::{self, PluginRegistry};
+
+/// Forward the messages of `namespace` to the webview
+#[tauri::command]
+pub fn plugin_claim_namespace(
+    registry: State<'_, PluginRegistry>,
+    namespace: String,
+) -> AppResult<()> {
+    Ok(registry.claim_for_webview(&namespace)?)
+}
+
+/// Stop forwarding the messages of a namespace claimed by the webview
+#[tauri::command]
+pub fn plugin_release_namespace(
+    registry: State<'_, PluginRegistry>,
+    namespace: String,
+) -> AppResult<()> {
+    Ok(registry.release_for_webview(&namespace)?)
+}
+
+/// Namespaces claimed by the webview or by plugins built into the app
+#[tauri::command]
+pub fn plugin_list_namespaces(registry: State<'_, PluginRegistry>) -> Vec<String> {
+    registry.namespaces()
+}
+
+/// Send `payload` under `namespace` to a peer, or to every peer of the talk room
+#[tauri::command]
+pub async fn plugin_send(
+    state: State<'_, AudioMeshState>,
+    namespace: String,
+    payload: Value,
+    peer_id: Option<String>,
+) -> AppResult<()> {
+    let manager = match &peer_id {
+        Some(peer_id) => state
+            .room_of_peer(peer_id)
+            .map(|(_, manager)| manager)
+            .ok_or_else(|| AppError::PeerNotFound(peer_id.clone()))?,
+        None => state.manager()?,
+    };
+    plugins::send(&manager, &namespace, payload, peer_id.as_deref()).await
+}
@@ -0,0 +1,210 @@
+//! Port mapping commands
+//! While hosting, ICE traffic goes through one fixed UDP port that the router
+//! is asked to forward (UPnP, then NAT-PMP), so guests behind strict NATs can
+//! still reach the host without a TURN server. The public address ends up in
+//! `ServerInfo` and in the ICE candidates. Best effort: hosting works the same
+//! when the router refuses. Off by default: it opens a port on the router.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{AppError, AppResult};
+use crate::events::{self, PortMappingChangedEvent};
+use crate::port_mapping::{self, Mapping, Method, LEASE};
+use crate::server::ServerState;
+use crate::storage;
+use crate::webrtc::ice_port;
+
+const PORT_MAPPING_FILE: &str = "port_mapping.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortMappingSettings {
+    pub enabled: bool,
+    /// UDP port of the ICE traffic while hosting (0: any free port)
+    pub port: u16,
+}
+
+impl Default for PortMappingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PortMappingStatus {
+    /// Port the ICE traffic shares while hosting
+    pub local_port: Option<u16>,
+    pub mapping: Option<Mapping>,
+    /// Why the router didn't forward the port
+    pub error: Option<String>,
+}
+
+/// State for the port mapping of the hosted server
+pub struct PortMappingState {
+    settings: RwLock<PortMappingSettings>,
+    status: Mutex<PortMappingStatus>,
+    /// Maps the port and renews the lease while hosting
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl PortMappingState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(PORT_MAPPING_FILE).unwrap_or_default()),
+            status: Mutex::new(PortMappingStatus::default()),
+            task: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for PortMappingState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Record the new status, advertise the public address and tell the UI
+fn update(app: &AppHandle, status: PortMappingStatus) {
+    // Candidates keep the local port: only a mapping on the same port can be advertised
+    ice_port::set_external_ip(
+        status
+            .mapping
+            .as_ref()
+            .filter(|mapping| mapping.external.port() == mapping.local_port)
+            .map(|mapping| mapping.external.ip()),
+    );
+    app.state::<ServerState>().set_external_address(
+        status
+            .mapping
+            .as_ref()
+            .map(|mapping| mapping.external.to_string()),
+    );
+    *app.state::<PortMappingState>().status.lock() = status.clone();
+    let _ = events::emit(app, &PortMappingChangedEvent { status });
+}
+
+/// Fix the ICE port and have the router forward it (on hosting)
+/// The port is bound before returning so the first guest already uses it;
+/// the router is asked in the background, discovery takes a few seconds.
+pub async fn start(app: &AppHandle) {
+    let settings = app.state::<PortMappingState>().settings.read().clone();
+    if !settings.enabled {
+        return;
+    }
+    let local_port = match ice_port::open(settings.port).await {
+        Ok(port) => port,
+        Err(e) => {
+            tracing::warn!("Hosting without a fixed ICE port: {}", e);
+            update(
+                app,
+                PortMappingStatus {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            );
+            return;
+        }
+    };
+
+    let task_app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        // Renewed at half the lease, retried as often when the router refused
+        let mut interval = tokio::time::interval(LEASE / 2);
+        let mut held: Option<Mapping> = None;
+        loop {
+            interval.tick().await;
+            let result = port_mapping::map_udp(local_port, held.as_ref()).await;
+            // Only the latest mapping is removed on stop: drop the one it replaces
+            // (NAT-PMP keys mappings by local port, a new one replaces the old)
+            if let Some(previous) = held.take() {
+                let renewed = result.as_ref().is_ok_and(|mapping| {
+                    mapping.method == previous.method
+                        && (mapping.method == Method::NatPmp
+                            || mapping.external == previous.external)
+                });
+                if !renewed {
+                    port_mapping::unmap(&previous).await;
+                }
+            }
+            let status = match result {
+                Ok(mapping) => {
+                    held = Some(mapping.clone());
+                    tracing::info!(
+                        "Router forwards {} to UDP port {} ({:?})",
+                        mapping.external,
+                        local_port,
+                        mapping.method
+                    );
+                    PortMappingStatus {
+                        local_port: Some(local_port),
+                        mapping: Some(mapping),
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    tracing::info!("No port mapping on the router: {}", e);
+                    PortMappingStatus {
+                        local_port: Some(local_port),
+                        mapping: None,
+                        error: Some(e),
+                    }
+                }
+            };
+            update(&task_app, status);
+        }
+    });
+    if let Some(previous) = app.state::<PortMappingState>().task.lock().replace(task) {
+        previous.abort();
+    }
+}
+
+/// Remove the mapping and free the ICE port (on disconnect and on exit)
+pub async fn stop(app: &AppHandle) {
+    let state = app.state::<PortMappingState>();
+    let task = state.task.lock().take();
+    if let Some(task) = task {
+        task.abort();
+    }
+    let mapping = state.status.lock().mapping.take();
+    if let Some(mapping) = mapping {
+        port_mapping::unmap(&mapping).await;
+    }
+    ice_port::close().await;
+    update(app, PortMappingStatus::default());
+}
+
+// ============ PORT MAPPING COMMANDS ============
+
+/// Get the port mapping settings
+#[tauri::command]
+pub fn port_mapping_get_settings(state: State<'_, PortMappingState>) -> PortMappingSettings {
+    state.settings.read().clone()
+}
+
+/// Update the port mapping settings (applied the next time we host)
+#[tauri::command]
+pub fn port_mapping_set_settings(
+    state: State<'_, PortMappingState>,
+    settings: PortMappingSettings,
+) -> AppResult<()> {
+    if settings.port != 0 && settings.port < 1024 {
+        return Err(AppError::InvalidArgument(
+            "The port must be 0 (any) or at least 1024".to_string(),
+        ));
+    }
+    storage::save_json(PORT_MAPPING_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    Ok(())
+}
+
+/// Get the ICE port and what the router forwards to it
+#[tauri::command]
+pub fn port_mapping_get_status(state: State<'_, PortMappingState>) -> PortMappingStatus {
+    state.status.lock().clone()
+}
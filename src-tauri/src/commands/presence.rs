@@ -0,0 +1,161 @@
+//! Presence commands
+//! Detects user inactivity (AFK) and broadcasts an "away" state to the mesh
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::audio_mesh::AudioMeshState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::{self, StreamingState};
+use crate::error::{AppError, AppResult};
+use crate::events::{self, PresenceEvent};
+use crate::storage;
+use crate::webrtc::SignalingMessage;
+
+const AFK_SETTINGS_FILE: &str = "afk.json";
+
+/// How often the system idle time is polled
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// AFK detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AfkSettings {
+    pub enabled: bool,
+    /// Minutes without input before going away
+    pub idle_minutes: u64,
+    /// Mute the microphone while away
+    pub auto_mute: bool,
+}
+
+impl Default for AfkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_minutes: 5,
+            auto_mute: false,
+        }
+    }
+}
+
+/// State for presence tracking
+pub struct PresenceState {
+    settings: RwLock<AfkSettings>,
+    away: AtomicBool,
+    /// Set when we muted the mic on going away (restored on return)
+    muted_by_afk: AtomicBool,
+}
+
+impl PresenceState {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(storage::load_json(AFK_SETTINGS_FILE).unwrap_or_default()),
+            away: AtomicBool::new(false),
+            muted_by_afk: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_away(&self) -> bool {
+        self.away.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for PresenceState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============ PRESENCE COMMANDS ============
+
+/// Get AFK detection settings
+#[tauri::command]
+pub fn presence_get_afk_settings(state: State<'_, PresenceState>) -> AfkSettings {
+    state.settings.read().clone()
+}
+
+/// Update AFK detection settings
+/// Disabling detection while away brings us back online (and unmutes the mic
+/// if going away muted it).
+#[tauri::command]
+pub async fn presence_set_afk_settings(app: AppHandle, settings: AfkSettings) -> AppResult<()> {
+    storage::save_json(AFK_SETTINGS_FILE, &settings).map_err(AppError::Storage)?;
+    let presence = app.state::<PresenceState>();
+    let enabled = settings.enabled;
+    *presence.settings.write() = settings;
+    if !enabled && presence.is_away() {
+        set_away(&app, false, false).await;
+    }
+    Ok(())
+}
+
+/// Check if the local user is away
+#[tauri::command]
+pub fn presence_is_away(state: State<'_, PresenceState>) -> bool {
+    state.is_away()
+}
+
+/// Poll the system idle time and switch between away/online
+pub fn spawn_afk_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let settings = app.state::<PresenceState>().settings.read().clone();
+            if !settings.enabled {
+                // Disabled while away: don't leave us stuck away and muted
+                if app.state::<PresenceState>().is_away() {
+                    set_away(&app, false, false).await;
+                }
+                continue;
+            }
+
+            let idle_secs = match user_idle::UserIdle::get_time() {
+                Ok(idle) => idle.as_seconds(),
+                Err(e) => {
+                    tracing::debug!("Idle time unavailable: {}", e);
+                    continue;
+                }
+            };
+
+            let away = idle_secs >= settings.idle_minutes * 60;
+            if away != app.state::<PresenceState>().is_away() {
+                set_away(&app, away, settings.auto_mute).await;
+            }
+        }
+    });
+}
+
+async fn set_away(app: &AppHandle, away: bool, auto_mute: bool) {
+    let presence = app.state::<PresenceState>();
+    presence.away.store(away, Ordering::SeqCst);
+
+    #[cfg(feature = "audio")]
+    {
+        let streaming = app.state::<StreamingState>();
+        if away {
+            if auto_mute && !streaming.service.is_muted() {
+                streaming::set_muted(app, true);
+                presence.muted_by_afk.store(true, Ordering::SeqCst);
+            }
+        } else if presence.muted_by_afk.swap(false, Ordering::SeqCst) {
+            streaming::set_muted(app, false);
+        }
+    }
+    #[cfg(not(feature = "audio"))]
+    let _ = auto_mute;
+
+    tracing::info!("Presence: {}", if away { "away" } else { "online" });
+
+    let msg = SignalingMessage::Presence { away };
+    for manager in app.state::<AudioMeshState>().managers() {
+        if let Err(e) = manager.broadcast_signaling(&msg).await {
+            tracing::warn!("Failed to broadcast presence: {}", e);
+        }
+    }
+
+    let _ = events::emit(app, &PresenceEvent { peer_id: None, away });
+}
@@ -0,0 +1,29 @@
+//! LAN preview server commands
+//! Let browsers on the network watch our screen stream without HydrowLand
+
+use tauri::State;
+
+use crate::error::AppResult;
+use crate::video::{PreviewServer, PreviewServerConfig, PreviewServerStatus};
+
+/// Start serving our screen stream over HTTP (localhost only unless
+/// `allow_lan`); the status lists the addresses to open
+#[tauri::command]
+pub async fn preview_server_start(
+    server: State<'_, PreviewServer>,
+    config: Option<PreviewServerConfig>,
+) -> AppResult<PreviewServerStatus> {
+    server.start(config.unwrap_or_default()).await
+}
+
+/// Stop the server and disconnect its viewers
+#[tauri::command]
+pub fn preview_server_stop(server: State<'_, PreviewServer>) {
+    server.stop();
+}
+
+/// Get the server addresses and viewer count
+#[tauri::command]
+pub fn preview_server_status(server: State<'_, PreviewServer>) -> PreviewServerStatus {
+    server.status()
+}
@@ -0,0 +1,222 @@
+//! Proxy commands
+//! Behind a corporate proxy, the signaling WebSocket and TURN can only go out
+//! through it. Proxies are saved as named profiles (office, home...) and one
+//! of them is active. The main webview is created with the active proxy, so it
+//! applies to everything the page opens: the signaling connection and TURN
+//! over TCP/TLS. UDP can't cross an HTTP or SOCKS5 proxy, so a profile can
+//! also force calls through TURN relays, in the webview and in the native
+//! meshes alike (see `webrtc::ice_servers`). Changes apply at the next launch.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Url, WebviewWindowBuilder};
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+use crate::webrtc::ice_servers;
+
+const PROXY_FILE: &str = "proxy.json";
+
+/// Label of the main window, as in tauri.conf.json
+const MAIN_LABEL: &str = "main";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyKind {
+    Http,
+    Socks5,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyProfile {
+    pub name: String,
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    /// Only connect to peers through TURN over TCP/TLS (no direct UDP)
+    #[serde(default = "default_relay_only")]
+    pub relay_only: bool,
+}
+
+fn default_relay_only() -> bool {
+    true
+}
+
+impl ProxyProfile {
+    pub fn url(&self) -> AppResult<Url> {
+        let scheme = match self.kind {
+            ProxyKind::Http => "http",
+            ProxyKind::Socks5 => "socks5",
+        };
+        Url::parse(&format!("{}://{}:{}", scheme, self.host.trim(), self.port)).map_err(|e| {
+            AppError::InvalidArgument(format!("Invalid proxy \"{}\": {}", self.name, e))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    /// Name of the profile in use (None: direct connection)
+    pub active: Option<String>,
+    pub profiles: Vec<ProxyProfile>,
+}
+
+impl ProxySettings {
+    pub fn validate(&self) -> AppResult<()> {
+        for (i, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                return Err(AppError::InvalidArgument(
+                    "A proxy profile needs a name".to_string(),
+                ));
+            }
+            if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(AppError::InvalidArgument(format!(
+                    "Two proxy profiles are named \"{}\"",
+                    profile.name
+                )));
+            }
+            if profile.host.trim().is_empty() || profile.port == 0 {
+                return Err(AppError::InvalidArgument(format!(
+                    "The proxy \"{}\" needs a host and a port",
+                    profile.name
+                )));
+            }
+            profile.url()?;
+        }
+        if let Some(active) = &self.active {
+            if !self.profiles.iter().any(|p| p.name == *active) {
+                return Err(AppError::InvalidArgument(format!(
+                    "No proxy profile named \"{}\"",
+                    active
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn active_profile(&self) -> Option<&ProxyProfile> {
+        let active = self.active.as_ref()?;
+        self.profiles.iter().find(|p| p.name == *active)
+    }
+}
+
+/// State for the proxy profiles
+pub struct ProxyState {
+    settings: RwLock<ProxySettings>,
+    /// Profile the main window was created with, until the next launch
+    applied: RwLock<Option<ProxyProfile>>,
+}
+
+impl ProxyState {
+    pub fn new() -> Self {
+        let settings = storage::load_json::<ProxySettings>(PROXY_FILE)
+            .filter(|settings| match settings.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Ignoring saved proxy settings: {}", e);
+                    false
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            settings: RwLock::new(settings),
+            applied: RwLock::new(None),
+        }
+    }
+}
+
+impl Default for ProxyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create the main window, behind the active proxy if any (at startup)
+/// The window is declared with `create: false` in tauri.conf.json because a
+/// webview's proxy can only be set when it is created.
+/// macOS: only applied when built with tauri's `macos-proxy` feature (macOS 14+).
+pub fn create_main_window(app: &AppHandle) -> tauri::Result<()> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == MAIN_LABEL)
+        .cloned()
+        .unwrap_or_default();
+    let mut builder = WebviewWindowBuilder::from_config(app, &config)?;
+
+    let state = app.state::<ProxyState>();
+    let profile = state.settings.read().active_profile().cloned();
+    if let Some(profile) = profile {
+        match profile.url() {
+            Ok(url) => {
+                tracing::info!("Main window goes through the {} proxy", profile.name);
+                builder = builder.proxy_url(url);
+                ice_servers::set_relay_only(profile.relay_only);
+                *state.applied.write() = Some(profile);
+            }
+            Err(e) => tracing::warn!("Not using the proxy: {}", e),
+        }
+    }
+    builder.build()?;
+    Ok(())
+}
+
+// ============ PROXY COMMANDS ============
+
+/// Get the proxy profiles and the active one
+#[tauri::command]
+pub fn proxy_get_settings(state: State<'_, ProxyState>) -> ProxySettings {
+    state.settings.read().clone()
+}
+
+/// Save the proxy profiles (applied at the next launch)
+#[tauri::command]
+pub fn proxy_set_settings(state: State<'_, ProxyState>, settings: ProxySettings) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(PROXY_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    Ok(())
+}
+
+/// Get the proxy this launch goes through (None: direct connection)
+#[tauri::command]
+pub fn proxy_get_active(state: State<'_, ProxyState>) -> Option<ProxyProfile> {
+    state.applied.read().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> ProxyProfile {
+        ProxyProfile {
+            name: name.to_string(),
+            kind: ProxyKind::Socks5,
+            host: "proxy.corp.example".to_string(),
+            port: 1080,
+            relay_only: true,
+        }
+    }
+
+    #[test]
+    fn validate_profiles() {
+        let mut settings = ProxySettings {
+            active: Some("Bureau".to_string()),
+            profiles: vec![profile("Bureau"), profile("Maison")],
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.active_profile().unwrap().url().unwrap().as_str(),
+            "socks5://proxy.corp.example:1080"
+        );
+
+        settings.active = Some("Ailleurs".to_string());
+        assert!(settings.validate().is_err());
+        settings.active = None;
+        settings.profiles.push(profile("Maison"));
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Connection quality commands
+//! Every few seconds, the link with each peer is scored good, fair or poor from
+//! its round-trip time, loss and jitter; `peer-quality-changed` is sent when a
+//! peer's level changes, so the UI only has to draw the bars. The worst loss
+//! on our audio also drives the encoder's adaptive FEC.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::audio_mesh::AudioMeshState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::events::{self, PeerQualityChangedEvent};
+use crate::webrtc::{self, QualityReport, QualityTracker};
+
+/// Time between two scores (loss is measured over this window)
+const QUALITY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Scoring state of a peer
+#[derive(Default)]
+struct PeerQuality {
+    tracker: QualityTracker,
+    /// Expected and received packet counters at the previous score
+    packets: (u64, u64),
+    /// Last report sent for the peer
+    report: Option<QualityReport>,
+}
+
+/// State for the connection quality of every peer
+#[derive(Default)]
+pub struct QualityState {
+    peers: Mutex<HashMap<String, PeerQuality>>,
+}
+
+/// Score every peer, telling the UI about level changes
+async fn refresh(app: &AppHandle) {
+    let mut transport = Vec::new();
+    for manager in app.state::<AudioMeshState>().managers() {
+        transport.extend(manager.transport_stats().await);
+    }
+
+    let mut changed = Vec::new();
+    // Worst loss on the audio we send, for the adaptive FEC
+    let mut outgoing_loss: Option<f64> = None;
+    {
+        let state = app.state::<QualityState>();
+        let mut peers = state.peers.lock();
+        // Peers that left start over if they come back
+        peers.retain(|id, _| transport.iter().any(|t| t.peer_id == *id));
+
+        for t in transport {
+            let peer = peers.entry(t.peer_id.clone()).or_default();
+            let packets = (t.packets_expected, t.packets_received);
+            let received_loss = webrtc::loss_percent(peer.packets, packets);
+            peer.packets = packets;
+            let loss_percent = match (received_loss, t.remote_loss_percent) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            // What the peer reports on our audio, else the loss we see from them
+            if let Some(loss) = t.remote_loss_percent.or(received_loss) {
+                outgoing_loss = Some(outgoing_loss.map_or(loss, |worst| worst.max(loss)));
+            }
+
+            let Some(quality) = webrtc::score(t.rtt_ms, loss_percent, t.jitter_ms) else {
+                continue;
+            };
+            let report = QualityReport {
+                peer_id: t.peer_id,
+                quality,
+                rtt_ms: t.rtt_ms,
+                loss_percent,
+                jitter_ms: t.jitter_ms,
+            };
+            if let Some(level) = peer.tracker.update(quality) {
+                tracing::debug!(
+                    "Link with {} is now {:?} (rtt {:?} ms, loss {:?}%, jitter {:?} ms)",
+                    report.peer_id,
+                    level,
+                    report.rtt_ms,
+                    report.loss_percent,
+                    report.jitter_ms
+                );
+                changed.push(QualityReport {
+                    quality: level,
+                    ..report.clone()
+                });
+            }
+            // Kept at the confirmed level, with the latest numbers
+            if let Some(level) = peer.tracker.current() {
+                peer.report = Some(QualityReport {
+                    quality: level,
+                    ..report
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    app.state::<StreamingState>()
+        .service
+        .report_packet_loss(outgoing_loss);
+    #[cfg(not(feature = "audio"))]
+    let _ = outgoing_loss;
+
+    for report in changed {
+        let _ = events::emit(app, &PeerQualityChangedEvent { report });
+    }
+}
+
+/// Score the peers for the lifetime of the app
+pub fn spawn_quality_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(QUALITY_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app).await;
+        }
+    });
+}
+
+// ============ CONNECTION QUALITY COMMANDS ============
+
+/// Get the latest quality of every scored peer (for a UI opened mid-call)
+#[tauri::command]
+pub fn quality_get_peers(state: State<'_, QualityState>) -> Vec<QualityReport> {
+    state
+        .peers
+        .lock()
+        .values()
+        .filter_map(|peer| peer.report.clone())
+        .collect()
+}
@@ -0,0 +1,96 @@
+//! Call recording commands
+//! Record the call (what we hear, our voice and the screen share) to one
+//! Matroska file, with pause/resume and a summary once it is finished
+
+use base64::Engine;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, EncodedFrameData, RecordingFailedEvent, RecordingFinishedEvent};
+use crate::recording::{CallRecorder, RecordingStatus, RecordingSummary};
+
+/// Start recording to `destination`, or to the videos folder when None
+/// With `screen`, the screen share on screen (ours or a peer's) is recorded too.
+#[tauri::command]
+pub fn recording_start(
+    app: AppHandle,
+    recorder: State<'_, CallRecorder>,
+    streaming: State<'_, StreamingState>,
+    destination: Option<String>,
+    screen: bool,
+) -> AppResult<RecordingStatus> {
+    let path = match destination {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            dirs::video_dir()
+                .or_else(dirs::download_dir)
+                .unwrap_or_else(std::env::temp_dir)
+                .join(format!("hydrowland-call-{}.mkv", timestamp))
+        }
+    };
+
+    recorder.start(path, streaming.service.call_tap(), screen, move |error| {
+        let _ = events::emit(&app, &RecordingFailedEvent { error });
+    })?;
+    Ok(recorder.status())
+}
+
+/// Pause the recording (nothing is recorded until it is resumed)
+#[tauri::command]
+pub fn recording_pause(recorder: State<'_, CallRecorder>) -> AppResult<RecordingStatus> {
+    recorder.set_paused(true)?;
+    Ok(recorder.status())
+}
+
+#[tauri::command]
+pub fn recording_resume(recorder: State<'_, CallRecorder>) -> AppResult<RecordingStatus> {
+    recorder.set_paused(false)?;
+    Ok(recorder.status())
+}
+
+/// Finish the file and tell every window what was recorded
+#[tauri::command]
+pub async fn recording_stop(
+    app: AppHandle,
+    recorder: State<'_, CallRecorder>,
+) -> AppResult<Option<RecordingSummary>> {
+    let recorder = recorder.inner().clone();
+    // Waits for the writer thread to flush the file
+    let summary = tokio::task::spawn_blocking(move || recorder.stop())
+        .await
+        .map_err(|e| AppError::Internal(format!("Recorder task panicked: {}", e)))??;
+    if let Some(summary) = &summary {
+        let _ = events::emit(
+            &app,
+            &RecordingFinishedEvent {
+                summary: summary.clone(),
+            },
+        );
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn recording_status(recorder: State<'_, CallRecorder>) -> RecordingStatus {
+    recorder.status()
+}
+
+/// Push a received screen frame (recorded if its presenter has the screen track)
+#[tauri::command]
+pub fn recording_push_frame(
+    recorder: State<'_, CallRecorder>,
+    presenter_id: String,
+    frame: EncodedFrameData,
+) -> AppResult<()> {
+    let jpeg = base64::engine::general_purpose::STANDARD
+        .decode(&frame.data)
+        .map_err(|e| AppError::InvalidArgument(format!("Invalid frame data: {}", e)))?;
+    recorder.push_screen_frame(&presenter_id, &jpeg);
+    Ok(())
+}
@@ -1,13 +1,14 @@
 use tauri::State;
 use crate::room::{Room, RoomState};
+use crate::error::AppResult;
 
 /// Créer une nouvelle room
 #[tauri::command]
 pub fn create_room(
     state: State<RoomState>,
     username: String,
-) -> Result<Room, String> {
-    state.create_room(username).map_err(|e| e.to_string())
+) -> AppResult<Room> {
+    state.create_room(username).map_err(Into::into)
 }
 
 /// Rejoindre une room existante
@@ -16,18 +17,18 @@ pub fn join_room(
     state: State<RoomState>,
     code: String,
     username: String,
-) -> Result<Room, String> {
-    state.join_room(&code, username).map_err(|e| e.to_string())
+) -> AppResult<Room> {
+    state.join_room(&code, username).map_err(Into::into)
 }
 
 /// Quitter la room actuelle
 #[tauri::command]
-pub fn leave_room(state: State<RoomState>) -> Result<(), String> {
-    state.leave_room().map_err(|e| e.to_string())
+pub fn leave_room(state: State<RoomState>) -> AppResult<()> {
+    state.leave_room().map_err(Into::into)
 }
 
 /// Obtenir les infos de la room actuelle
 #[tauri::command]
-pub fn get_room_info(state: State<RoomState>) -> Result<Option<Room>, String> {
+pub fn get_room_info(state: State<RoomState>) -> AppResult<Option<Room>> {
     Ok(state.get_current_room())
 }
@@ -1,4 +1,6 @@
-use crate::screen::{CaptureSource, CaptureSourceInfo, MonitorInfo, ScreenCapture, WindowInfo};
+use crate::screen::{
+    CaptureSource, CaptureSourceInfo, MonitorInfo, ScreenCapture, ScreenCaptureError, WindowInfo,
+};
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::RwLock;
@@ -29,20 +31,20 @@ impl ScreenState {
 
 /// List all available monitors
 #[tauri::command]
-pub async fn screen_list_monitors() -> Result<Vec<MonitorInfo>, String> {
-    ScreenCapture::list_monitors().map_err(|e| e.to_string())
+pub async fn screen_list_monitors() -> AppResult<Vec<MonitorInfo>> {
+    ScreenCapture::list_monitors().map_err(Into::into)
 }
 
 /// List all available windows
 #[tauri::command]
-pub async fn screen_list_windows(include_minimized: Option<bool>) -> Result<Vec<WindowInfo>, String> {
-    ScreenCapture::list_windows(include_minimized.unwrap_or(false)).map_err(|e| e.to_string())
+pub async fn screen_list_windows(include_minimized: Option<bool>) -> AppResult<Vec<WindowInfo>> {
+    ScreenCapture::list_windows(include_minimized.unwrap_or(false)).map_err(Into::into)
 }
 
 /// List all available capture sources (monitors + windows)
 #[tauri::command]
-pub async fn screen_list_sources(include_minimized: Option<bool>) -> Result<Vec<CaptureSourceInfo>, String> {
-    ScreenCapture::list_sources(include_minimized.unwrap_or(false)).map_err(|e| e.to_string())
+pub async fn screen_list_sources(include_minimized: Option<bool>) -> AppResult<Vec<CaptureSourceInfo>> {
+    ScreenCapture::list_sources(include_minimized.unwrap_or(false)).map_err(Into::into)
 }
 
 /// Select a monitor for capture
@@ -50,7 +52,7 @@ pub async fn screen_list_sources(include_minimized: Option<bool>) -> Result<Vec<
 pub async fn screen_select_monitor(
     state: State<'_, ScreenState>,
     monitor_id: u32,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let capture = state.capture.read().await;
     capture
         .select_source(CaptureSource::Monitor { id: monitor_id })
@@ -63,7 +65,7 @@ pub async fn screen_select_monitor(
 pub async fn screen_select_window(
     state: State<'_, ScreenState>,
     window_id: u32,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let capture = state.capture.read().await;
     capture
         .select_source(CaptureSource::Window { id: window_id })
@@ -73,7 +75,7 @@ pub async fn screen_select_window(
 
 /// Clear the selected source
 #[tauri::command]
-pub async fn screen_clear_selection(state: State<'_, ScreenState>) -> Result<(), String> {
+pub async fn screen_clear_selection(state: State<'_, ScreenState>) -> AppResult<()> {
     let capture = state.capture.read().await;
     capture.clear_source().await;
     Ok(())
@@ -83,21 +85,21 @@ pub async fn screen_clear_selection(state: State<'_, ScreenState>) -> Result<(),
 #[tauri::command]
 pub async fn screen_get_selection(
     state: State<'_, ScreenState>,
-) -> Result<Option<CaptureSource>, String> {
+) -> AppResult<Option<CaptureSource>> {
     let capture = state.capture.read().await;
     Ok(capture.get_selected_source().await)
 }
 
 /// Check if screen recording permission is granted (macOS)
 #[tauri::command]
-pub async fn screen_check_permission() -> Result<bool, String> {
+pub async fn screen_check_permission() -> AppResult<bool> {
     Ok(ScreenCapture::check_permission())
 }
 
 /// Request screen recording permission (macOS)
 /// Returns true if already granted, false if dialog was shown
 #[tauri::command]
-pub async fn screen_request_permission() -> Result<bool, String> {
+pub async fn screen_request_permission() -> AppResult<bool> {
     Ok(ScreenCapture::request_permission())
 }
 
@@ -106,22 +108,22 @@ pub async fn screen_request_permission() -> Result<bool, String> {
 pub async fn screen_capture_preview(
     state: State<'_, ScreenState>,
     max_width: Option<u32>,
-) -> Result<String, String> {
+) -> AppResult<String> {
     let capture = state.capture.read().await;
     capture
         .capture_preview(max_width.unwrap_or(400))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(Into::into)
 }
 
 /// Start screen sharing (sets internal state)
 #[tauri::command]
-pub async fn screen_start_sharing(state: State<'_, ScreenState>) -> Result<(), String> {
+pub async fn screen_start_sharing(state: State<'_, ScreenState>) -> AppResult<()> {
     let capture = state.capture.read().await;
 
     // Check if a source is selected
     if capture.get_selected_source().await.is_none() {
-        return Err("No source selected".to_string());
+        return Err(ScreenCaptureError::NoSourceSelected.into());
     }
 
     capture.set_capturing(true).await;
@@ -130,7 +132,7 @@ pub async fn screen_start_sharing(state: State<'_, ScreenState>) -> Result<(), S
 
 /// Stop screen sharing
 #[tauri::command]
-pub async fn screen_stop_sharing(state: State<'_, ScreenState>) -> Result<(), String> {
+pub async fn screen_stop_sharing(state: State<'_, ScreenState>) -> AppResult<()> {
     let capture = state.capture.read().await;
     capture.set_capturing(false).await;
     Ok(())
@@ -138,7 +140,7 @@ pub async fn screen_stop_sharing(state: State<'_, ScreenState>) -> Result<(), St
 
 /// Check if currently sharing screen
 #[tauri::command]
-pub async fn screen_is_sharing(state: State<'_, ScreenState>) -> Result<bool, String> {
+pub async fn screen_is_sharing(state: State<'_, ScreenState>) -> AppResult<bool> {
     let capture = state.capture.read().await;
     Ok(capture.is_capturing().await)
 }
@@ -147,10 +149,10 @@ pub async fn screen_is_sharing(state: State<'_, ScreenState>) -> Result<bool, St
 #[tauri::command]
 pub async fn screen_capture_frame(
     state: State<'_, ScreenState>,
-) -> Result<String, String> {
+) -> AppResult<String> {
     let capture = state.capture.read().await;
     capture
         .capture_preview(1920) // Full HD max width
         .await
-        .map_err(|e| e.to_string())
+        .map_err(Into::into)
 }
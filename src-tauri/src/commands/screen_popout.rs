@@ -0,0 +1,104 @@
+//! Screen share pop-out
+//! Opens a presenter's screen share in a window of its own, so it can go on
+//! a second monitor. The viewer pushes that presenter's frames, which are sent
+//! to their window only.
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::error::{AppError, AppResult};
+use crate::events::{self, EncodedFrameData, PresenterFrameEvent};
+use crate::i18n::{tf, Text};
+
+/// Prefix of the pop-out window labels, also the route of their page
+const POPOUT_PREFIX: &str = "screen-popout";
+
+/// Label of the pop-out window of `presenter_id`
+/// Labels only allow a few characters: the id is hex encoded.
+fn popout_label(presenter_id: &str) -> String {
+    let hex: String = presenter_id.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}", POPOUT_PREFIX, hex)
+}
+
+// ============ SCREEN POP-OUT COMMANDS ============
+
+/// Open the pop-out window of a presenter (brought to the front if already open)
+#[tauri::command]
+pub async fn screen_popout_open(
+    app: AppHandle,
+    presenter_id: String,
+    presenter_name: String,
+) -> AppResult<()> {
+    if presenter_id.is_empty() {
+        return Err(AppError::InvalidArgument(
+            "Presenter id is empty".to_string(),
+        ));
+    }
+    let label = popout_label(&presenter_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let url = WebviewUrl::App(format!("index.html#{}", POPOUT_PREFIX).into());
+    WebviewWindowBuilder::new(&app, &label, url)
+        .title(tf(
+            Text::ScreenPopoutTitle,
+            &[("username", &presenter_name)],
+        ))
+        .inner_size(1280.0, 720.0)
+        .min_inner_size(480.0, 270.0)
+        .resizable(true)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to open the screen pop-out: {}", e)))?;
+    Ok(())
+}
+
+/// Send a received frame to the presenter's pop-out window
+/// Returns false when that window is not open (closed by the user), so the
+/// viewer can stop pushing.
+#[tauri::command]
+pub fn screen_popout_push_frame(
+    app: AppHandle,
+    presenter_id: String,
+    frame: EncodedFrameData,
+) -> AppResult<bool> {
+    let label = popout_label(&presenter_id);
+    if app.get_webview_window(&label).is_none() {
+        return Ok(false);
+    }
+    events::emit_to(
+        &app,
+        &label,
+        &PresenterFrameEvent {
+            presenter_id,
+            frame,
+        },
+    )
+    .map_err(|e| AppError::Internal(format!("Failed to send the frame: {}", e)))?;
+    Ok(true)
+}
+
+/// Close the pop-out window of a presenter (e.g. they stopped sharing)
+#[tauri::command]
+pub fn screen_popout_close(app: AppHandle, presenter_id: String) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window(&popout_label(&presenter_id)) {
+        window.close().map_err(|e| {
+            AppError::Internal(format!("Failed to close the screen pop-out: {}", e))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_distinct_and_valid() {
+        let a = popout_label("peer/1 é");
+        let b = popout_label("peer_1_é");
+        assert_ne!(a, b);
+        assert!(a.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+        assert!(a.starts_with("screen-popout-"));
+    }
+}
@@ -1,12 +1,34 @@
 //! Screen streaming commands
 //! Handles continuous screen capture, encoding, and WebRTC transmission
 
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use parking_lot::RwLock;
-use tauri::{AppHandle, Emitter, State};
-use tokio::sync::mpsc;
-
-use crate::video::{VideoEncoder, VideoFrame, EncoderConfig};
+use parking_lot::{Mutex, RwLock};
+use tauri::{AppHandle, Manager, State, Window};
+use tokio::sync::oneshot;
+
+use crate::bandwidth::MIN_SCREEN_KBPS;
+use crate::commands::guard::{CommandGuards, Subsystem};
+#[cfg(feature = "audio")]
+use crate::commands::pip;
+use crate::error::AppResult;
+use crate::events::{self, EncodedFrameData};
+#[cfg(feature = "audio")]
+use crate::recording::CallRecorder;
+use crate::screen::{self, FrameAcks, HintsTracker, ScreenCaptureError};
+#[cfg(feature = "audio")]
+use crate::server::LOCAL_PEER_ID;
+use crate::tray;
+use crate::video::{VideoEncoder, VideoFrame, EncoderConfig, PreviewServer, VirtualCamera};
+#[cfg(feature = "whip")]
+use crate::webrtc::WhipPublisher;
+
+/// Encoder bitrate of the stream
+const STREAM_BITRATE_KBPS: u32 = 4000;
+
+/// Frame rate cap and bitrate while reduced (game mode)
+const REDUCED_FPS: u32 = 8;
+const REDUCED_BITRATE_KBPS: u32 = 1500;
 
 /// State for screen streaming
 pub struct ScreenStreamState {
@@ -14,59 +36,172 @@ pub struct ScreenStreamState {
 }
 
 struct ScreenStreamInner {
-    /// Whether streaming is active
-    is_streaming: RwLock<bool>,
-    /// Stop signal sender
-    stop_tx: RwLock<Option<mpsc::Sender<()>>>,
+    /// Generation of the last stream started
+    generation: AtomicU64,
     /// Current FPS
     fps: RwLock<u32>,
+    /// Lower frame rate and bitrate, applied by the running loop (game mode)
+    reduced: AtomicBool,
+    /// Bitrate allowed by the bandwidth budget in kbps, 0 when not capped
+    bitrate_cap: AtomicU32,
+    /// Frames held by the presenter (viewers keep the last one)
+    paused: AtomicBool,
+    /// Frames emitted to the webview and not acknowledged yet
+    acks: Mutex<FrameAcks>,
+    /// Running stream and what it publishes, only written by its generation
+    slot: Mutex<StreamSlot>,
+}
+
+#[derive(Default)]
+struct StreamSlot {
+    /// Stream being captured, if any
+    active: Option<ActiveStream>,
     /// Current encoded frame (for viewers)
-    current_frame: RwLock<Option<EncodedFrameData>>,
+    current_frame: Option<EncodedFrameData>,
     /// Statistics
-    stats: RwLock<StreamStats>,
+    stats: StreamStats,
+}
+
+/// Handle on a running capture loop
+/// Dropping it cancels the loop; the loop only writes to the slot while its
+/// generation is the active one, so a loop still winding down after a quick
+/// stop→start can't clobber the new stream's state.
+struct ActiveStream {
+    generation: u64,
+    _stop_tx: oneshot::Sender<()>,
 }
 
 #[derive(Debug, Clone, Default)]
 struct StreamStats {
     frames_sent: u64,
+    frames_skipped: u64,
+    frames_dropped: u64,
     total_bytes: u64,
     avg_frame_size: u64,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct EncodedFrameData {
-    /// Base64 encoded JPEG data
-    pub data: String,
-    /// Frame width
-    pub width: u32,
-    /// Frame height
-    pub height: u32,
-    /// Whether this is a keyframe
-    pub is_keyframe: bool,
-    /// Frame number
-    pub frame_number: u64,
-    /// Timestamp in milliseconds
-    pub timestamp: u64,
-}
-
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct StreamStatsResponse {
     pub is_streaming: bool,
     pub fps: u32,
     pub frames_sent: u64,
+    /// Frames not captured because the loop was running behind
+    pub frames_skipped: u64,
+    /// Frames not emitted because the webview had not handled the previous ones
+    pub frames_dropped: u64,
     pub total_bytes: u64,
     pub avg_frame_size: u64,
 }
 
+impl ScreenStreamState {
+    /// Whether streaming is active
+    pub fn is_streaming(&self) -> bool {
+        self.inner.slot.lock().active.is_some()
+    }
+
+    /// Cancel the capture loop without waiting for it (used on exit)
+    pub fn stop(&self) {
+        self.inner.stop();
+    }
+
+    /// Lower the frame rate and bitrate (or restore them), also for the
+    /// running stream
+    pub fn set_reduced(&self, reduced: bool) {
+        self.inner.reduced.store(reduced, Ordering::SeqCst);
+    }
+
+    /// Cap the bitrate (kbps) to fit the bandwidth budget, None to lift it
+    /// (stored as 0, so a cap is at least `MIN_SCREEN_KBPS`)
+    pub fn set_bitrate_cap(&self, cap_kbps: Option<u32>) {
+        let cap = cap_kbps.map_or(0, |kbps| kbps.max(MIN_SCREEN_KBPS));
+        self.inner.bitrate_cap.store(cap, Ordering::SeqCst);
+    }
+}
+
+impl ScreenStreamInner {
+    /// Frame rate and bitrate for a stream started at `target_fps`
+    fn rate(&self, target_fps: u32) -> (u32, u32) {
+        let (fps, bitrate) = if self.reduced.load(Ordering::SeqCst) {
+            (target_fps.min(REDUCED_FPS), REDUCED_BITRATE_KBPS)
+        } else {
+            (target_fps, STREAM_BITRATE_KBPS)
+        };
+        match self.bitrate_cap.load(Ordering::SeqCst) {
+            0 => (fps, bitrate),
+            cap => (fps, bitrate.min(cap)),
+        }
+    }
+
+    /// Cancel the running stream (if any) and clear what it published
+    fn stop(&self) {
+        let mut slot = self.slot.lock();
+        slot.active = None;
+        slot.current_frame = None;
+    }
+
+    /// Record a frame of stream `generation`; false once it was stopped
+    fn publish(&self, generation: u64, frame: EncodedFrameData, size: usize) -> bool {
+        let mut slot = self.slot.lock();
+        if slot.active.as_ref().map(|a| a.generation) != Some(generation) {
+            return false;
+        }
+        let stats = &mut slot.stats;
+        stats.frames_sent += 1;
+        stats.total_bytes += size as u64;
+        stats.avg_frame_size = stats.total_bytes / stats.frames_sent;
+        // Stored for late joiners
+        slot.current_frame = Some(frame);
+        true
+    }
+
+    /// Count frames stream `generation` skipped to catch up
+    fn skip(&self, generation: u64, frames: u64) {
+        let mut slot = self.slot.lock();
+        if slot.active.as_ref().map(|a| a.generation) == Some(generation) {
+            slot.stats.frames_skipped += frames;
+        }
+    }
+
+    /// Whether the webview keeps up with the frames; counts a drop when not
+    fn may_emit(&self, generation: u64, frame_number: u64) -> bool {
+        let now = std::time::Instant::now();
+        let mut acks = self.acks.lock();
+        if acks.can_emit(now) {
+            acks.emitted(frame_number, now);
+            return true;
+        }
+        drop(acks);
+
+        let mut slot = self.slot.lock();
+        if slot.active.as_ref().map(|a| a.generation) == Some(generation) {
+            slot.stats.frames_dropped += 1;
+        }
+        false
+    }
+
+    /// Clear the state of stream `generation` when its loop ends on its own
+    fn finish(&self, generation: u64) -> bool {
+        let mut slot = self.slot.lock();
+        if slot.active.as_ref().map(|a| a.generation) != Some(generation) {
+            return false;
+        }
+        slot.active = None;
+        slot.current_frame = None;
+        true
+    }
+}
+
 impl Default for ScreenStreamState {
     fn default() -> Self {
         Self {
             inner: Arc::new(ScreenStreamInner {
-                is_streaming: RwLock::new(false),
-                stop_tx: RwLock::new(None),
+                generation: AtomicU64::new(0),
                 fps: RwLock::new(15),
-                current_frame: RwLock::new(None),
-                stats: RwLock::new(StreamStats::default()),
+                reduced: AtomicBool::new(false),
+                bitrate_cap: AtomicU32::new(0),
+                paused: AtomicBool::new(false),
+                acks: Mutex::new(FrameAcks::default()),
+                slot: Mutex::new(StreamSlot::default()),
             }),
         }
     }
@@ -79,13 +214,15 @@ pub async fn screen_stream_start(
     app: AppHandle,
     screen_state: State<'_, crate::commands::screen::ScreenState>,
     stream_state: State<'_, ScreenStreamState>,
+    guards: State<'_, CommandGuards>,
     fps: Option<u32>,
-) -> Result<(), String> {
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Screen).await;
     let inner = stream_state.inner.clone();
 
-    // Check if already streaming
-    if *inner.is_streaming.read() {
-        return Err("Already streaming".to_string());
+    // Already streaming (e.g. double click): nothing to do
+    if inner.slot.lock().active.is_some() {
+        return Ok(());
     }
 
     // Get the screen capture instance
@@ -95,61 +232,128 @@ pub async fn screen_stream_start(
     {
         let cap = capture.read().await;
         if cap.get_selected_source().await.is_none() {
-            return Err("No screen source selected".to_string());
+            return Err(ScreenCaptureError::NoSourceSelected.into());
         }
     }
 
     // Set FPS
     let target_fps = fps.unwrap_or(15).clamp(5, 30);
     *inner.fps.write() = target_fps;
+    inner.paused.store(false, Ordering::SeqCst);
 
-    // Create stop channel
-    let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
-    *inner.stop_tx.write() = Some(stop_tx);
-
-    // Mark as streaming
-    *inner.is_streaming.write() = true;
-
-    // Reset stats
-    *inner.stats.write() = StreamStats::default();
+    // New generation: the loop owns it and the receiving end of its stop channel
+    let generation = inner.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    inner.acks.lock().reset(generation);
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+    {
+        let mut slot = inner.slot.lock();
+        slot.active = Some(ActiveStream {
+            generation,
+            _stop_tx: stop_tx,
+        });
+        slot.current_frame = None;
+        slot.stats = StreamStats::default();
+    }
+    tray::refresh(&app);
 
     // Clone for the async task
     let inner_clone = inner.clone();
     let app_clone = app.clone();
+    let camera = app.state::<VirtualCamera>().inner().clone();
+    let preview = app.state::<PreviewServer>().inner().clone();
+    #[cfg(feature = "whip")]
+    let whip = app.state::<WhipPublisher>().inner().clone();
+    #[cfg(feature = "audio")]
+    let recorder = app.state::<CallRecorder>().inner().clone();
 
     // Spawn streaming task
     tokio::spawn(async move {
         let mut encoder = VideoEncoder::new(EncoderConfig {
             fps: target_fps,
-            bitrate_kbps: 4000,
+            bitrate_kbps: STREAM_BITRATE_KBPS,
             max_width: 1920,
             max_height: 1080,
             quality: 85,
         });
 
-        let frame_interval = std::time::Duration::from_millis(1000 / target_fps as u64);
+        let mut frame_interval = std::time::Duration::from_millis(1000 / target_fps as u64);
+        let mut rate = (target_fps, STREAM_BITRATE_KBPS);
         let start_time = std::time::Instant::now();
+        // When the next frame is due: frames keep to this grid even when one runs late
+        let mut deadline = start_time;
+        // Presenter hints, and the size of the last captured frame for them
+        let mut hints = HintsTracker::default();
+        let mut resolution = None;
 
         loop {
-            // Check for stop signal
-            if stop_rx.try_recv().is_ok() || !*inner_clone.is_streaming.read() {
+            // Stopped: the sender was dropped
+            if !matches!(stop_rx.try_recv(), Err(oneshot::error::TryRecvError::Empty)) {
                 tracing::info!("Screen streaming stopped");
                 break;
             }
 
-            let frame_start = std::time::Instant::now();
+            // Game mode or bandwidth budget changed: switch rate on the fly
+            let wanted = inner_clone.rate(target_fps);
+            if wanted != rate {
+                rate = wanted;
+                let (fps, bitrate) = rate;
+                encoder.set_rate(fps, bitrate);
+                frame_interval = std::time::Duration::from_millis(1000 / fps as u64);
+                tracing::info!("Screen stream at {} fps, {} kbps", fps, bitrate);
+            }
+
+            // A frame or more behind: skip the frames already late rather than
+            // capturing them late, so the cadence stays on the grid
+            let now = std::time::Instant::now();
+            if now >= deadline + frame_interval {
+                let behind = ((now - deadline).as_nanos() / frame_interval.as_nanos()) as u32;
+                deadline += frame_interval * behind;
+                inner_clone.skip(generation, behind as u64);
+                tracing::debug!("Screen stream behind, skipped {} frame(s)", behind);
+            }
+
+            // Presenter hints go out on change, next to the frames
+            let paused = inner_clone.paused.load(Ordering::SeqCst);
+            if hints.needs_geometry(now) {
+                let geometry = capture.read().await.selected_geometry().await;
+                hints.set_geometry(geometry, now);
+            }
+            let cursor = screen::cursor_position(&app_clone);
+            if let Some(update) = hints.update(resolution, paused, cursor, now) {
+                if let Err(e) = events::emit(&app_clone, &update) {
+                    tracing::warn!("Failed to emit presenter hints: {}", e);
+                }
+            }
+
+            // Paused: no frames, viewers keep the last one
+            if paused {
+                deadline += frame_interval;
+                if deadline > std::time::Instant::now() {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(deadline.into()) => {}
+                        _ = &mut stop_rx => {}
+                    }
+                }
+                continue;
+            }
 
             // Capture frame
             let cap = capture.read().await;
             match cap.capture_frame().await {
                 Ok(captured) => {
                     drop(cap); // Release the lock early
+                    resolution = Some((captured.width, captured.height));
 
                     let video_frame = VideoFrame::new(
                         captured.width,
                         captured.height,
                         captured.data,
                     );
+                    // Raw frame for the virtual camera (when it shows our screen)
+                    camera.push_local(&video_frame);
+                    // And for the WHIP broadcast (H.264, encoded on its own thread)
+                    #[cfg(feature = "whip")]
+                    whip.push_video(&video_frame);
 
                     // Encode frame
                     match encoder.encode(&video_frame) {
@@ -157,6 +361,12 @@ pub async fn screen_stream_start(
                             // Adapt quality based on frame size
                             encoder.adapt_quality(encoded.size());
 
+                            // Same JPEG for the LAN preview viewers
+                            preview.publish(&encoded.data);
+                            // And for the call recording
+                            #[cfg(feature = "audio")]
+                            recorder.push_screen_frame(LOCAL_PEER_ID, &encoded.data);
+
                             // Create encoded frame data
                             use base64::Engine;
                             let frame_data = EncodedFrameData {
@@ -166,23 +376,23 @@ pub async fn screen_stream_start(
                                 is_keyframe: encoded.is_keyframe,
                                 frame_number: encoded.frame_number,
                                 timestamp: start_time.elapsed().as_millis() as u64,
+                                stream_id: generation,
                             };
 
-                            // Update stats
-                            {
-                                let mut stats = inner_clone.stats.write();
-                                stats.frames_sent += 1;
-                                stats.total_bytes += encoded.size() as u64;
-                                stats.avg_frame_size = stats.total_bytes / stats.frames_sent;
+                            if !inner_clone.publish(generation, frame_data.clone(), encoded.size()) {
+                                tracing::info!("Screen streaming stopped");
+                                break;
                             }
 
-                            // Store current frame for late joiners
-                            *inner_clone.current_frame.write() = Some(frame_data.clone());
-
-                            // Emit to frontend
-                            if let Err(e) = app_clone.emit("screen-frame", frame_data) {
-                                tracing::warn!("Failed to emit screen frame: {}", e);
+                            // Emit to frontend, unless it is behind on the previous frames
+                            if inner_clone.may_emit(generation, frame_data.frame_number) {
+                                if let Err(e) = events::emit(&app_clone, &frame_data) {
+                                    tracing::warn!("Failed to emit screen frame: {}", e);
+                                }
                             }
+                            // Picture-in-picture, while we hold the floor
+                            #[cfg(feature = "audio")]
+                            pip::route_frame(&app_clone, LOCAL_PEER_ID, &frame_data);
                         }
                         Err(e) => {
                             tracing::warn!("Failed to encode frame: {}", e);
@@ -195,17 +405,21 @@ pub async fn screen_stream_start(
                 }
             }
 
-            // Sleep to maintain frame rate
-            let elapsed = frame_start.elapsed();
-            if elapsed < frame_interval {
-                tokio::time::sleep(frame_interval - elapsed).await;
+            // Sleep until the next frame is due, waking up early when stopped
+            deadline += frame_interval;
+            if deadline > std::time::Instant::now() {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline.into()) => {}
+                    _ = &mut stop_rx => {}
+                }
             }
         }
 
-        // Cleanup
-        *inner_clone.is_streaming.write() = false;
-        *inner_clone.stop_tx.write() = None;
-        *inner_clone.current_frame.write() = None;
+        // Only clears the state if no newer stream replaced this one
+        if inner_clone.finish(generation) {
+            preview.clear();
+            tray::refresh(&app_clone);
+        }
     });
 
     Ok(())
@@ -214,21 +428,14 @@ pub async fn screen_stream_start(
 /// Stop screen streaming
 #[tauri::command]
 pub async fn screen_stream_stop(
+    app: AppHandle,
     stream_state: State<'_, ScreenStreamState>,
-) -> Result<(), String> {
-    let inner = &stream_state.inner;
-
-    // Get the sender without holding the lock across await
-    let tx = inner.stop_tx.read().clone();
-
-    // Send stop signal
-    if let Some(tx) = tx {
-        let _ = tx.send(()).await;
-    }
-
-    // Mark as not streaming
-    *inner.is_streaming.write() = false;
-    *inner.stop_tx.write() = None;
+    guards: State<'_, CommandGuards>,
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Screen).await;
+    // Cancels the loop: it stops publishing right away and exits at its next check
+    stream_state.inner.stop();
+    tray::refresh(&app);
 
     Ok(())
 }
@@ -238,7 +445,7 @@ pub async fn screen_stream_stop(
 pub fn screen_stream_is_active(
     stream_state: State<'_, ScreenStreamState>,
 ) -> bool {
-    *stream_state.inner.is_streaming.read()
+    stream_state.is_streaming()
 }
 
 /// Get streaming statistics
@@ -247,12 +454,15 @@ pub fn screen_stream_get_stats(
     stream_state: State<'_, ScreenStreamState>,
 ) -> StreamStatsResponse {
     let inner = &stream_state.inner;
-    let stats = inner.stats.read();
+    let slot = inner.slot.lock();
+    let stats = &slot.stats;
 
     StreamStatsResponse {
-        is_streaming: *inner.is_streaming.read(),
+        is_streaming: slot.active.is_some(),
         fps: *inner.fps.read(),
         frames_sent: stats.frames_sent,
+        frames_skipped: stats.frames_skipped,
+        frames_dropped: stats.frames_dropped,
         total_bytes: stats.total_bytes,
         avg_frame_size: stats.avg_frame_size,
     }
@@ -263,7 +473,29 @@ pub fn screen_stream_get_stats(
 pub fn screen_stream_get_current_frame(
     stream_state: State<'_, ScreenStreamState>,
 ) -> Option<EncodedFrameData> {
-    stream_state.inner.current_frame.read().clone()
+    stream_state.inner.slot.lock().current_frame.clone()
+}
+
+/// A window handled a "screen-frame" event (and the ones before it)
+#[tauri::command]
+pub fn screen_stream_ack_frame(
+    window: Window,
+    stream_state: State<'_, ScreenStreamState>,
+    stream_id: u64,
+    frame_number: u64,
+) {
+    stream_state.inner.acks.lock().ack(window.label(), stream_id, frame_number);
+}
+
+/// Hold the frames of the running stream (viewers keep the last one) or resume
+#[tauri::command]
+pub fn screen_stream_set_paused(
+    stream_state: State<'_, ScreenStreamState>,
+    paused: bool,
+) -> AppResult<()> {
+    stream_state.inner.paused.store(paused, Ordering::SeqCst);
+    tracing::info!("Screen stream {}", if paused { "paused" } else { "resumed" });
+    Ok(())
 }
 
 /// Set streaming FPS (will take effect on next stream start)
@@ -271,7 +503,7 @@ pub fn screen_stream_get_current_frame(
 pub fn screen_stream_set_fps(
     stream_state: State<'_, ScreenStreamState>,
     fps: u32,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let target_fps = fps.clamp(5, 30);
     *stream_state.inner.fps.write() = target_fps;
     Ok(())
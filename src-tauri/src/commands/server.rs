@@ -1,5 +1,17 @@
-use tauri::State;
-use crate::server::{ServerConfig, ServerInfo, ServerState};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use crate::commands::guard::{CommandGuards, Subsystem};
+use crate::commands::port_mapping;
+use crate::deep_link::DeepLinkState;
+use crate::error::AppResult;
+use crate::events::{self, InviteExpiredEvent};
+use crate::server::{InviteCode, ServerConfig, ServerInfo, ServerState};
+use crate::session::{self, SessionPhase, SessionState};
+use crate::storage;
+use crate::tray;
+
+/// Intervalle de purge des invitations expirées
+const INVITE_PURGE_INTERVAL: Duration = Duration::from_secs(15);
 
 /// Obtenir ou créer la config serveur
 #[tauri::command]
@@ -9,30 +21,90 @@ pub fn get_server_config(state: State<ServerState>, username: String) -> ServerC
 
 /// Mettre à jour le username
 #[tauri::command]
-pub fn set_username(state: State<ServerState>, username: String) -> Result<(), String> {
-    state.set_username(username).map_err(|e| e.to_string())
+pub fn set_username(state: State<ServerState>, username: String) -> AppResult<()> {
+    state.set_username(username).map_err(Into::into)
 }
 
 /// Démarrer l'hébergement
 #[tauri::command]
-pub fn start_hosting(state: State<ServerState>, username: String) -> Result<ServerInfo, String> {
-    state.start_hosting(username).map_err(|e| e.to_string())
+pub async fn start_hosting(
+    app: AppHandle,
+    state: State<'_, ServerState>,
+    guards: State<'_, CommandGuards>,
+    username: String,
+) -> AppResult<ServerInfo> {
+    let _guard = guards.lock(Subsystem::Session).await;
+    // Double clic : on renvoie le serveur déjà lancé
+    if let Some(info) = state.get_server_info().filter(|info| info.is_hosting) {
+        return Ok(info);
+    }
+    app.state::<SessionState>().check(SessionPhase::Signaling)?;
+    let info = state.start_hosting(username)?;
+    session::transition(&app, SessionPhase::Signaling)?;
+    // Port fixe pour l'ICE, redirigé par le routeur si possible
+    port_mapping::start(&app).await;
+    tray::refresh(&app);
+    Ok(info)
 }
 
 /// Rejoindre un serveur
 #[tauri::command]
-pub fn join_server(
-    state: State<ServerState>,
-    code: String,
-    username: String,
-) -> Result<ServerInfo, String> {
-    state.join_server(code, username).map_err(|e| e.to_string())
+pub async fn join_server(app: AppHandle, code: String, username: String) -> AppResult<ServerInfo> {
+    join(&app, code, username).await
+}
+
+/// Rejoindre un serveur (commande `join_server` ou lien d'invitation)
+pub async fn join(app: &AppHandle, code: String, username: String) -> AppResult<ServerInfo> {
+    let _guard = app.state::<CommandGuards>().lock(Subsystem::Session).await;
+    let state = app.state::<ServerState>();
+    // Déjà connecté à ce serveur : rien à refaire
+    if let Some(info) = state
+        .get_server_info()
+        .filter(|info| !info.is_hosting && info.code.eq_ignore_ascii_case(&code))
+    {
+        return Ok(info);
+    }
+    app.state::<SessionState>().check(SessionPhase::Signaling)?;
+    let info = state.join_server(code, username)?;
+    session::transition(app, SessionPhase::Signaling)?;
+    tray::refresh(app);
+    Ok(info)
+}
+
+/// Vérifier si l'app tourne en mode invité (rien n'est persisté)
+#[tauri::command]
+pub fn is_guest_mode() -> bool {
+    storage::is_guest_mode()
 }
 
 /// Se déconnecter
 #[tauri::command]
-pub fn disconnect(state: State<ServerState>) -> Result<(), String> {
-    state.disconnect().map_err(|e| e.to_string())
+pub async fn disconnect(
+    app: AppHandle,
+    state: State<'_, ServerState>,
+    guards: State<'_, CommandGuards>,
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Session).await;
+    state.disconnect()?;
+    port_mapping::stop(&app).await;
+    // Rien à terminer si aucune session n'a démarré
+    if app.state::<SessionState>().phase().is_active() {
+        session::transition(&app, SessionPhase::Ended)?;
+    }
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Récupérer le code reçu par lien d'invitation avant que le pseudo soit connu
+#[tauri::command]
+pub fn take_pending_join_code(deep_link: State<DeepLinkState>) -> Option<String> {
+    deep_link.take_pending()
+}
+
+/// Obtenir la phase de la session (idle, signaling, connecting, in_call, reconnecting, ended)
+#[tauri::command]
+pub fn session_get_state(session: State<SessionState>) -> SessionPhase {
+    session.phase()
 }
 
 /// Obtenir les infos du serveur actuel
@@ -46,3 +118,40 @@ pub fn get_server_info(state: State<ServerState>) -> Option<ServerInfo> {
 pub fn is_connected(state: State<ServerState>) -> bool {
     state.is_connected()
 }
+
+/// Créer un code d'invitation temporaire et/ou à usage limité
+#[tauri::command]
+pub fn server_create_invite(
+    state: State<ServerState>,
+    ttl_secs: Option<u64>,
+    max_uses: Option<u32>,
+) -> AppResult<InviteCode> {
+    state.create_invite(ttl_secs, max_uses).map_err(Into::into)
+}
+
+/// Lister les codes d'invitation actifs
+#[tauri::command]
+pub fn server_list_invites(state: State<ServerState>) -> Vec<InviteCode> {
+    state.list_invites()
+}
+
+/// Révoquer un code d'invitation
+#[tauri::command]
+pub fn server_revoke_invite(state: State<ServerState>, code: String) -> AppResult<()> {
+    state.revoke_invite(&code).map_err(Into::into)
+}
+
+/// Lancer la tâche de fond qui expire les invitations
+pub fn spawn_invite_expiry_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(INVITE_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let expired = app.state::<ServerState>().purge_expired_invites();
+            for code in expired {
+                tracing::info!("Invite code expired: {}", code);
+                let _ = events::emit(&app, &InviteExpiredEvent { code });
+            }
+        }
+    });
+}
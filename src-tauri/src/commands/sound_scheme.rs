@@ -0,0 +1,102 @@
+//! Sound scheme commands
+//! Cues for joins, departures, mute changes and chat messages, played locally
+//! on the playback output
+
+use parking_lot::RwLock;
+use tauri::{AppHandle, Manager, State};
+
+use crate::audio::{Sound, SoundCues, SoundEvent, SoundScheme};
+use crate::commands::streaming::{play_locally, StreamingState};
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const SOUND_SCHEME_FILE: &str = "sound_scheme.json";
+
+/// State for the sound scheme
+pub struct SoundSchemeState {
+    cues: RwLock<SoundCues>,
+}
+
+impl SoundSchemeState {
+    pub fn new() -> Self {
+        let scheme = storage::load_json::<SoundScheme>(SOUND_SCHEME_FILE)
+            .filter(|s| s.validate().is_ok())
+            .unwrap_or_default();
+        Self {
+            cues: RwLock::new(SoundCues::new(scheme)),
+        }
+    }
+
+    fn apply(&self, scheme: SoundScheme) -> AppResult<()> {
+        scheme.validate()?;
+        storage::save_json(SOUND_SCHEME_FILE, &scheme).map_err(AppError::Storage)?;
+        *self.cues.write() = SoundCues::new(scheme);
+        Ok(())
+    }
+}
+
+impl Default for SoundSchemeState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Play the cue of `event`, if the scheme is on and we aren't deafened
+pub fn play(app: &AppHandle, event: SoundEvent) {
+    let state = app.state::<SoundSchemeState>();
+    let cues = state.cues.read();
+    if !cues.scheme().enabled || app.state::<StreamingState>().service.is_deafened() {
+        return;
+    }
+    let Some(samples) = cues.cue(event) else {
+        return;
+    };
+    drop(cues);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let streaming = app.state::<StreamingState>();
+        if let Err(e) = play_locally(&streaming.service, &samples).await {
+            tracing::debug!("Failed to play the {:?} cue: {}", event, e);
+        }
+    });
+}
+
+// ============ SOUND SCHEME COMMANDS ============
+
+/// Get the sound scheme
+#[tauri::command]
+pub fn sound_scheme_get(state: State<'_, SoundSchemeState>) -> SoundScheme {
+    state.cues.read().scheme().clone()
+}
+
+/// Replace the sound scheme
+#[tauri::command]
+pub fn sound_scheme_set(state: State<'_, SoundSchemeState>, scheme: SoundScheme) -> AppResult<()> {
+    state.apply(scheme)
+}
+
+/// Change the cue of one event
+#[tauri::command]
+pub fn sound_scheme_set_sound(
+    state: State<'_, SoundSchemeState>,
+    event: SoundEvent,
+    sound: Sound,
+) -> AppResult<()> {
+    let mut scheme = state.cues.read().scheme().clone();
+    scheme.sounds.insert(event, sound);
+    state.apply(scheme)
+}
+
+/// Play the cue of an event now, even with the scheme off (settings preview)
+#[tauri::command]
+pub async fn sound_scheme_preview(
+    state: State<'_, SoundSchemeState>,
+    streaming: State<'_, StreamingState>,
+    event: SoundEvent,
+) -> AppResult<()> {
+    let Some(samples) = state.cues.read().cue(event) else {
+        return Ok(());
+    };
+    play_locally(&streaming.service, &samples).await
+}
@@ -0,0 +1,183 @@
+//! Stats overlay
+//! Small frameless always-on-top window showing live call stats. While it is
+//! open, a task samples the peer connections, the screen share and the speaking
+//! participants a few times a second and sends the result to that window only.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::commands::audio_mesh::AudioMeshState;
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::{screen_stream_get_stats, ScreenStreamState};
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::error::{AppError, AppResult};
+use crate::events::{self, CallStatsEvent, PeerCallStats};
+use crate::i18n::{t, Text};
+
+/// Window label, also the route of its page
+const OVERLAY_LABEL: &str = "stats-overlay";
+
+/// Time between two samples
+const STATS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The sampling task is running
+static SAMPLING: AtomicBool = AtomicBool::new(false);
+
+/// Counters of the previous sample, turned into rates
+#[derive(Default)]
+struct Previous {
+    at: Option<Instant>,
+    /// Bytes sent and received per peer
+    peers: HashMap<String, (u64, u64)>,
+    /// Frames and bytes sent by the screen share
+    screen: Option<(u64, u64)>,
+}
+
+fn kbps(bytes: u64, elapsed: f64) -> f64 {
+    bytes as f64 * 8.0 / 1000.0 / elapsed
+}
+
+async fn sample(app: &AppHandle, previous: &mut Previous) -> CallStatsEvent {
+    let now = Instant::now();
+    let elapsed = previous
+        .at
+        .map(|at| now.duration_since(at).as_secs_f64())
+        .filter(|e| *e > 0.0);
+    previous.at = Some(now);
+    let rate = |current: u64, before: u64| {
+        elapsed.map_or(0.0, |e| kbps(current.saturating_sub(before), e))
+    };
+
+    let mut transport = Vec::new();
+    for manager in app.state::<AudioMeshState>().managers() {
+        transport.extend(manager.transport_stats().await);
+    }
+    let mut counters = HashMap::with_capacity(transport.len());
+    let peers: Vec<PeerCallStats> = transport
+        .into_iter()
+        .map(|t| {
+            let (sent, received) = previous
+                .peers
+                .get(&t.peer_id)
+                .copied()
+                .unwrap_or((t.bytes_sent, t.bytes_received));
+            counters.insert(t.peer_id.clone(), (t.bytes_sent, t.bytes_received));
+            PeerCallStats {
+                rtt_ms: t.rtt_ms,
+                send_kbps: rate(t.bytes_sent, sent),
+                recv_kbps: rate(t.bytes_received, received),
+                peer_id: t.peer_id,
+            }
+        })
+        .collect();
+    previous.peers = counters;
+
+    #[cfg(feature = "screen")]
+    let (screen_fps, screen_kbps) = {
+        let stats = screen_stream_get_stats(app.state::<ScreenStreamState>());
+        let current = stats
+            .is_streaming
+            .then_some((stats.frames_sent, stats.total_bytes));
+        let rates = match (current, previous.screen, elapsed) {
+            (Some((frames, bytes)), Some((prev_frames, prev_bytes)), Some(e)) => (
+                Some(frames.saturating_sub(prev_frames) as f64 / e),
+                Some(kbps(bytes.saturating_sub(prev_bytes), e)),
+            ),
+            (Some(_), _, _) => (Some(0.0), Some(0.0)),
+            (None, _, _) => (None, None),
+        };
+        previous.screen = current;
+        rates
+    };
+    #[cfg(not(feature = "screen"))]
+    let (screen_fps, screen_kbps) = (None, None);
+
+    #[cfg(feature = "audio")]
+    let speaking = app
+        .state::<StreamingState>()
+        .service
+        .speaking_participants();
+    #[cfg(not(feature = "audio"))]
+    let speaking = Vec::new();
+
+    CallStatsEvent {
+        send_kbps: peers.iter().map(|p| p.send_kbps).sum(),
+        recv_kbps: peers.iter().map(|p| p.recv_kbps).sum(),
+        max_rtt_ms: peers.iter().filter_map(|p| p.rtt_ms).reduce(f64::max),
+        screen_fps,
+        screen_kbps,
+        speaking,
+        peers,
+    }
+}
+
+/// Sample until the overlay is closed (no-op if already sampling)
+fn spawn_sampling(app: AppHandle) {
+    if SAMPLING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let mut previous = Previous::default();
+            let mut interval = tokio::time::interval(STATS_INTERVAL);
+            while app.get_webview_window(OVERLAY_LABEL).is_some() {
+                interval.tick().await;
+                let stats = sample(&app, &mut previous).await;
+                let _ = events::emit_to(&app, OVERLAY_LABEL, &stats);
+            }
+            SAMPLING.store(false, Ordering::SeqCst);
+            // Reopened while we were stopping: keep going rather than leave it blank
+            if app.get_webview_window(OVERLAY_LABEL).is_none()
+                || SAMPLING.swap(true, Ordering::SeqCst)
+            {
+                return;
+            }
+        }
+    });
+}
+
+// ============ STATS OVERLAY COMMANDS ============
+
+/// Open the stats overlay (brought to the front if already open)
+#[tauri::command]
+pub async fn stats_overlay_open(app: AppHandle) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let url = WebviewUrl::App(format!("index.html#{}", OVERLAY_LABEL).into());
+    WebviewWindowBuilder::new(&app, OVERLAY_LABEL, url)
+        .title(t(Text::StatsOverlayTitle))
+        .inner_size(280.0, 200.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to open the stats overlay: {}", e)))?;
+
+    spawn_sampling(app);
+    Ok(())
+}
+
+/// Close the stats overlay
+#[tauri::command]
+pub fn stats_overlay_close(app: AppHandle) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        window
+            .close()
+            .map_err(|e| AppError::Internal(format!("Failed to close the stats overlay: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Whether the stats overlay is open
+#[tauri::command]
+pub fn stats_overlay_is_open(app: AppHandle) -> bool {
+    app.get_webview_window(OVERLAY_LABEL).is_some()
+}
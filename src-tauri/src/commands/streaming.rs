@@ -1,21 +1,85 @@
 //! Audio streaming commands
 //! Provides Tauri commands for the complete audio pipeline
 
-use tauri::{AppHandle, State};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
 
-use crate::audio::{AudioStreamingService, AudioPacket};
+use crate::audio::{
+    self, AudioPacket, AudioPolicy, AudioSendStats, AudioStreamingService, AutoMuteSettings,
+    DeviceConfig, DeviceConfigs, DeviceLost, MicCheckResult, SidetoneSettings, SoundEvent,
+    StallDetector, StreamKind, SupportedConfig, FRAME_DURATION_MS,
+    MAX_LEVEL_EVENT_RATE, MIC_CHECK_DURATION, PEER_AUDIO_TIMEOUT, SAMPLES_PER_FRAME,
+    STALL_TIMEOUT, WATCHDOG_INTERVAL,
+};
+#[cfg(feature = "transcription")]
+use crate::audio::Transcriber;
+use crate::commands::audio::{
+    ensure_mic_permission, load_denoiser, load_dynamics, load_encoder_options, load_equalizer,
+    AudioState,
+};
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::commands::guard::{CommandGuards, Subsystem};
+use crate::commands::{pip, sound_scheme};
+use crate::error::{AppError, AppResult};
+use crate::events::{
+    self, ActiveSpeakerChangedEvent, AudioDeviceFallbackEvent, AudioStreamStalledEvent,
+    AutoMutedEvent, PeerAudioTimeoutEvent, VoiceStateEvent,
+};
+use crate::storage;
+use crate::tray;
+
+const AUTO_MUTE_FILE: &str = "auto_mute.json";
+const SIDETONE_FILE: &str = "sidetone.json";
+
+/// How often decoders of silent peers are looked for
+const PEER_REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the active speaker is re-evaluated
+const ACTIVE_SPEAKER_INTERVAL: Duration = Duration::from_millis(100);
 
 /// State wrapper for the streaming service
 pub struct StreamingState {
     pub service: AudioStreamingService,
+    /// Frontend subscriber receiving encoded packets as they are produced
+    packet_channel: Mutex<Option<Channel<AudioPacket>>>,
+    /// Set when deafening muted the mic (restored on undeafen)
+    muted_by_deafen: AtomicBool,
 }
 
 impl StreamingState {
     pub fn new() -> Self {
+        let service = AudioStreamingService::new();
+        if let Some(settings) = storage::load_json(AUTO_MUTE_FILE) {
+            service.auto_mute().set_settings(settings);
+        }
+        if let Some(settings) = storage::load_json::<SidetoneSettings>(SIDETONE_FILE) {
+            if let Err(e) = service.set_sidetone(settings) {
+                tracing::warn!("Ignoring saved sidetone settings: {}", e);
+            }
+        }
+        load_encoder_options(&service);
+        audio::load_device_configs();
+        load_denoiser(&service);
+        load_equalizer(&service);
+        load_dynamics(&service);
+
         Self {
-            service: AudioStreamingService::new(),
+            service,
+            packet_channel: Mutex::new(None),
+            muted_by_deafen: AtomicBool::new(false),
         }
     }
+
+    /// Stop capture and playback and drop every peer stream
+    pub fn stop_voice(&self) {
+        self.service.stop_capture();
+        self.service.stop_playback();
+        self.service.clear_peers();
+    }
 }
 
 impl Default for StreamingState {
@@ -29,7 +93,7 @@ impl Default for StreamingState {
 pub fn streaming_init(
     state: State<'_, StreamingState>,
     app_handle: AppHandle,
-) -> Result<(), String> {
+) -> AppResult<()> {
     state.service.set_app_handle(app_handle);
     tracing::info!("Streaming service initialized");
     Ok(())
@@ -37,8 +101,12 @@ pub fn streaming_init(
 
 /// Start audio capture (microphone)
 #[tauri::command]
-pub fn streaming_start_capture(state: State<'_, StreamingState>) -> Result<(), String> {
-    state.service.start_capture()
+pub async fn streaming_start_capture(
+    app: AppHandle,
+    state: State<'_, StreamingState>,
+) -> AppResult<()> {
+    ensure_mic_permission(&app)?;
+    state.service.start_capture().await
 }
 
 /// Stop audio capture
@@ -49,8 +117,8 @@ pub fn streaming_stop_capture(state: State<'_, StreamingState>) {
 
 /// Start audio playback (speakers)
 #[tauri::command]
-pub fn streaming_start_playback(state: State<'_, StreamingState>) -> Result<(), String> {
-    state.service.start_playback()
+pub async fn streaming_start_playback(state: State<'_, StreamingState>) -> AppResult<()> {
+    state.service.start_playback().await
 }
 
 /// Stop audio playback
@@ -61,8 +129,8 @@ pub fn streaming_stop_playback(state: State<'_, StreamingState>) {
 
 /// Set mute state
 #[tauri::command]
-pub fn streaming_set_muted(state: State<'_, StreamingState>, muted: bool) {
-    state.service.set_muted(muted);
+pub fn streaming_set_muted(app: AppHandle, muted: bool) {
+    set_muted(&app, muted);
 }
 
 /// Get mute state
@@ -71,6 +139,18 @@ pub fn streaming_is_muted(state: State<'_, StreamingState>) -> bool {
     state.service.is_muted()
 }
 
+/// Set deafen state (deafening also mutes the mic)
+#[tauri::command]
+pub fn streaming_set_deafened(app: AppHandle, deafened: bool) {
+    set_deafened(&app, deafened);
+}
+
+/// Get deafen state
+#[tauri::command]
+pub fn streaming_is_deafened(state: State<'_, StreamingState>) -> bool {
+    state.service.is_deafened()
+}
+
 /// Check if capturing
 #[tauri::command]
 pub fn streaming_is_capturing(state: State<'_, StreamingState>) -> bool {
@@ -89,13 +169,35 @@ pub fn streaming_get_level(state: State<'_, StreamingState>) -> f32 {
     state.service.current_level()
 }
 
+/// Set how many `audio-level` events are emitted per second (levels are still
+/// measured every frame, events carry the peak since the previous one)
+#[tauri::command]
+pub fn streaming_set_level_event_rate(rate: u32) -> AppResult<()> {
+    if !(1..=MAX_LEVEL_EVENT_RATE).contains(&rate) {
+        return Err(AppError::InvalidArgument(format!(
+            "Level event rate must be between 1 and {} Hz",
+            MAX_LEVEL_EVENT_RATE
+        )));
+    }
+    audio::set_level_event_rate(rate);
+    Ok(())
+}
+
+/// Get the `audio-level` event rate
+#[tauri::command]
+pub fn streaming_get_level_event_rate() -> u32 {
+    audio::level_event_rate()
+}
+
 /// Set input device
 #[tauri::command]
-pub fn streaming_set_input_device(
+pub async fn streaming_set_input_device(
     state: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
     device_name: Option<String>,
-) -> Result<(), String> {
-    state.service.set_input_device(device_name)
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    state.service.set_input_device(device_name).await
 }
 
 /// Get selected input device
@@ -106,25 +208,62 @@ pub fn streaming_get_input_device(state: State<'_, StreamingState>) -> Option<St
 
 /// Set output device
 #[tauri::command]
-pub fn streaming_set_output_device(
+pub async fn streaming_set_output_device(
     state: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
     device_name: Option<String>,
-) -> Result<(), String> {
-    state.service.set_output_device(device_name)
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    state.service.set_output_device(device_name).await
 }
 
 /// List input devices
 #[tauri::command]
-pub fn streaming_list_input_devices(state: State<'_, StreamingState>) -> Result<Vec<String>, String> {
+pub fn streaming_list_input_devices(state: State<'_, StreamingState>) -> AppResult<Vec<String>> {
     state.service.list_input_devices()
 }
 
 /// List output devices
 #[tauri::command]
-pub fn streaming_list_output_devices(state: State<'_, StreamingState>) -> Result<Vec<String>, String> {
+pub fn streaming_list_output_devices(state: State<'_, StreamingState>) -> AppResult<Vec<String>> {
     state.service.list_output_devices()
 }
 
+/// List the configurations a device (None for default) supports
+#[tauri::command]
+pub fn streaming_get_supported_configs(
+    state: State<'_, StreamingState>,
+    kind: StreamKind,
+    device_name: Option<String>,
+) -> AppResult<Vec<SupportedConfig>> {
+    state.service.supported_configs(kind, device_name.as_deref())
+}
+
+/// Get the sample rates and buffer sizes pinned per device
+#[tauri::command]
+pub fn streaming_get_device_configs() -> DeviceConfigs {
+    audio::device_configs()
+}
+
+/// Pin the sample rate and buffer size of a device (all None to unpin)
+/// A running stream of that kind is reopened so the change is heard right away.
+#[tauri::command]
+pub async fn streaming_set_device_config(
+    state: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
+    kind: StreamKind,
+    device_name: String,
+    config: DeviceConfig,
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    tracing::info!("Device config of {} set to {:?}", device_name, config);
+    audio::set_device_config(kind, device_name, config)?;
+    if state.service.is_running(kind) {
+        state.service.restart(kind).await?;
+    }
+    Ok(())
+}
+
 /// Enable/disable noise suppression
 #[tauri::command]
 pub fn streaming_set_noise_suppression(state: State<'_, StreamingState>, enabled: bool) {
@@ -137,11 +276,35 @@ pub fn streaming_is_noise_suppression_enabled(state: State<'_, StreamingState>)
     state.service.is_noise_suppression_enabled()
 }
 
-/// Get the next outgoing audio packet (for sending to peers)
-/// Returns None if no packet is available
+/// Update push-to-talk key state (only gates audio when PTT is forced or enabled)
+#[tauri::command]
+pub fn streaming_set_push_to_talk(app: AppHandle, pressed: bool) {
+    set_push_to_talk_pressed(&app, pressed);
+}
+
+/// Get the audio policy currently enforced on capture
+#[tauri::command]
+pub fn streaming_get_policy(state: State<'_, StreamingState>) -> AudioPolicy {
+    state.service.policy()
+}
+
+/// Subscribe to outgoing audio packets (pushed as they are encoded)
+/// Replaces any previous subscriber
+#[tauri::command]
+pub fn streaming_subscribe_outgoing(state: State<'_, StreamingState>, on_packet: Channel<AudioPacket>) {
+    *state.packet_channel.lock() = Some(on_packet);
+}
+
+/// Stop receiving outgoing audio packets
+#[tauri::command]
+pub fn streaming_unsubscribe_outgoing(state: State<'_, StreamingState>) {
+    *state.packet_channel.lock() = None;
+}
+
+/// Get outgoing packet counters (queued, dropped when the sender fell behind)
 #[tauri::command]
-pub fn streaming_get_outgoing_packet(state: State<'_, StreamingState>) -> Option<AudioPacket> {
-    state.service.get_outgoing_packet()
+pub fn streaming_get_send_stats(state: State<'_, StreamingState>) -> AudioSendStats {
+    state.service.send_stats()
 }
 
 /// Receive audio from a peer
@@ -150,7 +313,7 @@ pub fn streaming_receive_audio(
     state: State<'_, StreamingState>,
     peer_id: String,
     opus_data: Vec<u8>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     state.service.receive_peer_audio(&peer_id, &opus_data)
 }
 
@@ -168,23 +331,403 @@ pub fn streaming_clear_peers(state: State<'_, StreamingState>) {
 
 /// Start both capture and playback for voice chat
 #[tauri::command]
-pub fn streaming_start_voice(
+pub async fn streaming_start_voice(
     state: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
     app_handle: AppHandle,
-) -> Result<(), String> {
-    state.service.set_app_handle(app_handle);
-    state.service.start_capture()?;
-    state.service.start_playback()?;
-    state.service.set_muted(true); // Start muted
-    tracing::info!("Voice streaming started (muted)");
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    // Already running: don't apply join-muted again
+    if state.service.is_capturing() && state.service.is_playing() {
+        return Ok(());
+    }
+    ensure_mic_permission(&app_handle)?;
+    state.service.set_app_handle(app_handle.clone());
+    state.service.start_capture().await?;
+    state.service.start_playback().await?;
+    let join_muted = state.service.auto_mute().settings().join_muted;
+    set_muted(&app_handle, join_muted);
+    tracing::info!("Voice streaming started (muted: {})", join_muted);
     Ok(())
 }
 
+/// Get auto-mute settings
+#[tauri::command]
+pub fn streaming_get_auto_mute_settings(state: State<'_, StreamingState>) -> AutoMuteSettings {
+    state.service.auto_mute().settings()
+}
+
+/// Update auto-mute settings
+#[tauri::command]
+pub fn streaming_set_auto_mute_settings(
+    state: State<'_, StreamingState>,
+    settings: AutoMuteSettings,
+) -> AppResult<()> {
+    if settings.silence_timeout_minutes == Some(0) {
+        return Err(AppError::InvalidArgument(
+            "Silence timeout must be at least one minute".to_string(),
+        ));
+    }
+    storage::save_json(AUTO_MUTE_FILE, &settings).map_err(AppError::Storage)?;
+    state.service.auto_mute().set_settings(settings);
+    Ok(())
+}
+
+/// Get the sidetone settings
+#[tauri::command]
+pub fn streaming_get_sidetone(state: State<'_, StreamingState>) -> SidetoneSettings {
+    state.service.sidetone()
+}
+
+/// Play the processed mic back locally at `volume` (0-1), for headset users
+#[tauri::command]
+pub fn streaming_set_sidetone(
+    state: State<'_, StreamingState>,
+    settings: SidetoneSettings,
+) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(SIDETONE_FILE, &settings).map_err(AppError::Storage)?;
+    state.service.set_sidetone(settings)
+}
+
 /// Stop both capture and playback
 #[tauri::command]
-pub fn streaming_stop_voice(state: State<'_, StreamingState>) {
-    state.service.stop_capture();
-    state.service.stop_playback();
-    state.service.clear_peers();
+pub async fn streaming_stop_voice(
+    state: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
+) -> AppResult<()> {
+    let _guard = guards.lock(Subsystem::Voice).await;
+    state.stop_voice();
     tracing::info!("Voice streaming stopped");
+    Ok(())
+}
+
+/// Get the participant holding the floor ("local" for us)
+#[tauri::command]
+pub fn streaming_get_active_speaker(state: State<'_, StreamingState>) -> Option<String> {
+    state.service.active_speaker()
+}
+
+/// Mic check: record the processed capture, play it back on the selected output,
+/// and return its peak/RMS levels once playback is over
+#[tauri::command]
+pub async fn streaming_mic_check(state: State<'_, StreamingState>) -> AppResult<MicCheckResult> {
+    let service = &state.service;
+
+    // Leave the pipeline as we found it
+    let started_capture = !service.is_capturing();
+    service.start_capture().await?;
+    if let Err(e) = service.start_mic_recording(MIC_CHECK_DURATION) {
+        if started_capture {
+            service.stop_capture();
+        }
+        return Err(e);
+    }
+
+    tracing::info!("Mic check: recording for {:?}", MIC_CHECK_DURATION);
+    tokio::time::sleep(MIC_CHECK_DURATION).await;
+    let samples = service.take_mic_recording();
+    if started_capture {
+        service.stop_capture();
+    }
+    let result = MicCheckResult::from_samples(&samples);
+
+    play_locally(service, &samples).await?;
+
+    tracing::info!(
+        "Mic check done: peak {:.1} dBFS, RMS {:.1} dBFS",
+        result.peak_dbfs,
+        result.rms_dbfs
+    );
+    Ok(result)
+}
+
+/// Play 48kHz mono samples on the selected output, returning once they are over
+/// They are fed at real-time pace, as if they came from a peer; playback is
+/// started for the occasion when it isn't running, and stopped after the last
+/// sound playing this way unless a call needs it meanwhile.
+pub async fn play_locally(service: &AudioStreamingService, samples: &[f32]) -> AppResult<()> {
+    service.begin_local_playback().await?;
+
+    let mut interval = tokio::time::interval(Duration::from_millis(FRAME_DURATION_MS as u64));
+    for frame in samples.chunks(SAMPLES_PER_FRAME) {
+        interval.tick().await;
+        service.queue_playback(frame);
+    }
+    // Let the last frames drain
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    service.end_local_playback().await;
+    Ok(())
+}
+
+// ============ VOICE STATE ============
+// Shared by commands, hotkeys, the tray and presence so every change reaches the UI
+
+/// Mute or unmute the microphone
+pub fn set_muted(app: &AppHandle, muted: bool) {
+    let changed = app.state::<StreamingState>().service.is_muted() != muted;
+    apply_muted(app, muted);
+    emit_voice_state(app);
+    if changed {
+        let cue = if muted { SoundEvent::Muted } else { SoundEvent::Unmuted };
+        sound_scheme::play(app, cue);
+    }
+}
+
+/// Toggle mute, returns the new state
+pub fn toggle_mute(app: &AppHandle) -> bool {
+    let muted = !app.state::<StreamingState>().service.is_muted();
+    set_muted(app, muted);
+    muted
+}
+
+/// Deafen or undeafen; undeafening only unmutes if deafening muted
+pub fn set_deafened(app: &AppHandle, deafened: bool) {
+    let streaming = app.state::<StreamingState>();
+    if streaming.service.is_deafened() == deafened {
+        return;
+    }
+
+    streaming.service.set_deafened(deafened);
+    if deafened {
+        if !streaming.service.is_muted() {
+            apply_muted(app, true);
+            streaming.muted_by_deafen.store(true, Ordering::SeqCst);
+        }
+    } else if streaming.muted_by_deafen.swap(false, Ordering::SeqCst) {
+        apply_muted(app, false);
+    }
+
+    emit_voice_state(app);
+}
+
+/// Toggle deafen, returns the new state
+pub fn toggle_deafen(app: &AppHandle) -> bool {
+    let deafened = !app.state::<StreamingState>().service.is_deafened();
+    set_deafened(app, deafened);
+    deafened
+}
+
+/// Update push-to-talk key state
+pub fn set_push_to_talk_pressed(app: &AppHandle, pressed: bool) {
+    let streaming = app.state::<StreamingState>();
+    if streaming.service.is_push_to_talk_pressed() == pressed {
+        return;
+    }
+    streaming.service.set_push_to_talk_pressed(pressed);
+    emit_voice_state(app);
+}
+
+fn apply_muted(app: &AppHandle, muted: bool) {
+    app.state::<AudioState>().set_muted(muted);
+    app.state::<StreamingState>().service.set_muted(muted);
+}
+
+fn emit_voice_state(app: &AppHandle) {
+    let streaming = app.state::<StreamingState>();
+    let _ = events::emit(
+        app,
+        &VoiceStateEvent {
+            muted: streaming.service.is_muted(),
+            deafened: streaming.service.is_deafened(),
+            push_to_talk_pressed: streaming.service.is_push_to_talk_pressed(),
+        },
+    );
+    tray::refresh(app);
+}
+
+/// Wire the streaming service to the audio mesh:
+/// encoded mic packets are broadcast to the talk room, and audio received
+/// from peers is decoded for playback, with no frontend involvement
+pub fn spawn_audio_pipeline(app: AppHandle) {
+    let Some(mut outgoing_rx) = app.state::<StreamingState>().service.take_outgoing_receiver()
+    else {
+        tracing::warn!("Audio pipeline already running");
+        return;
+    };
+
+    // Mic -> mesh (and to the frontend subscriber, if any)
+    let out_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let packet = outgoing_rx.recv().await;
+            let manager = out_app.state::<AudioMeshState>().manager();
+            if let Some(manager) = manager.ok().filter(|m| m.peer_count() > 0) {
+                if let Err(e) = manager.broadcast_audio(&packet.data).await {
+                    tracing::debug!("Failed to broadcast audio: {}", e);
+                }
+            }
+
+            let streaming = out_app.state::<StreamingState>();
+            let mut channel = streaming.packet_channel.lock();
+            if let Some(ch) = channel.as_ref() {
+                if ch.send(packet).is_err() {
+                    // Webview went away
+                    *channel = None;
+                }
+            }
+        }
+    });
+
+    // Pipeline auto-mutes -> keep AudioState, the UI and the tray in sync
+    let streaming = app.state::<StreamingState>();
+    if let Some(mut auto_mute_rx) = streaming.service.auto_mute().take_receiver() {
+        let mute_app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(reason) = auto_mute_rx.recv().await {
+                tracing::info!("Auto-muted: {:?}", reason);
+                set_muted(&mute_app, true);
+                let _ = events::emit(&mute_app, &AutoMutedEvent { reason });
+            }
+        });
+    }
+
+    // Device unplugged mid-call -> carry on with the system default
+    if let Some(mut lost_rx) = streaming.service.take_device_lost_receiver() {
+        let lost_app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(lost) = lost_rx.recv().await {
+                fall_back_to_default(&lost_app, lost).await;
+            }
+        });
+    }
+
+    // Watchdog: rebuild streams whose callbacks stopped firing
+    let watchdog_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut detectors = [StreamKind::Capture, StreamKind::Playback]
+            .map(|kind| (kind, StallDetector::new(STALL_TIMEOUT)));
+        let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+        loop {
+            interval.tick().await;
+            for (kind, detector) in detectors.iter_mut() {
+                let stalled = {
+                    let service = &watchdog_app.state::<StreamingState>().service;
+                    detector.check(service.is_running(*kind), service.heartbeat(*kind), Instant::now())
+                };
+                if let Some(stalled) = stalled {
+                    restart_stalled_stream(&watchdog_app, *kind, stalled).await;
+                }
+            }
+        }
+    });
+
+    // Reaper: drop the decoders of peers that went silent (left without a goodbye)
+    let reaper_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PEER_REAP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let reaped = reaper_app
+                .state::<StreamingState>()
+                .service
+                .reap_idle_peers(PEER_AUDIO_TIMEOUT, Instant::now());
+            for (peer_id, idle) in reaped {
+                tracing::info!("No audio from peer {} for {:?}, dropped its decoder", peer_id, idle);
+                let _ = events::emit(
+                    &reaper_app,
+                    &PeerAudioTimeoutEvent {
+                        peer_id,
+                        idle_ms: idle.as_millis() as u64,
+                    },
+                );
+            }
+        }
+    });
+
+    // Active speaker: tell the UI who holds the floor
+    let speaker_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(ACTIVE_SPEAKER_INTERVAL);
+        loop {
+            interval.tick().await;
+            let changed = speaker_app
+                .state::<StreamingState>()
+                .service
+                .update_active_speaker();
+            if let Some(peer_id) = changed {
+                pip::speaker_changed(&speaker_app, peer_id.as_deref());
+                let _ = events::emit(&speaker_app, &ActiveSpeakerChangedEvent { peer_id });
+            }
+        }
+    });
+
+    // Mesh -> speakers
+    let (incoming_tx, mut incoming_rx) = mpsc::unbounded_channel();
+    app.state::<AudioMeshState>().set_audio_receiver(incoming_tx);
+    tauri::async_runtime::spawn(async move {
+        while let Some((peer_id, opus_data)) = incoming_rx.recv().await {
+            let streaming = app.state::<StreamingState>();
+            if let Err(e) = streaming.service.receive_peer_audio(&peer_id, &opus_data) {
+                tracing::debug!("Failed to decode audio from {}: {}", peer_id, e);
+            }
+            #[cfg(feature = "transcription")]
+            app.state::<Transcriber>().push(&peer_id, &opus_data);
+        }
+    });
+
+    tracing::info!("Audio pipeline started");
+}
+
+/// Move a stream whose device went away to the system default, unless a
+/// voice command stopped it or picked another device meanwhile
+async fn fall_back_to_default(app: &AppHandle, lost: DeviceLost) {
+    let guards = app.state::<CommandGuards>();
+    let _guard = guards.lock(Subsystem::Voice).await;
+    let streaming = app.state::<StreamingState>();
+    if !streaming.service.is_running(lost.stream)
+        || streaming.service.selected_device(lost.stream) != lost.device
+    {
+        return;
+    }
+
+    tracing::warn!(
+        "Audio {:?} device {:?} went away ({}), falling back to the default",
+        lost.stream,
+        lost.device,
+        lost.error
+    );
+    let fallback_device = match streaming.service.set_device(lost.stream, None).await {
+        Ok(()) => streaming.service.device_name(lost.stream),
+        Err(e) => {
+            tracing::error!("Failed to open the default {:?} device: {}", lost.stream, e);
+            None
+        }
+    };
+    let _ = events::emit(
+        app,
+        &AudioDeviceFallbackEvent {
+            stream: lost.stream,
+            lost_device: lost.device,
+            fallback_device,
+            error: lost.error,
+        },
+    );
+}
+
+/// Rebuild a stalled stream, unless a voice command stopped it meanwhile
+async fn restart_stalled_stream(app: &AppHandle, stream: StreamKind, stalled: Duration) {
+    let guards = app.state::<CommandGuards>();
+    let _guard = guards.lock(Subsystem::Voice).await;
+    let streaming = app.state::<StreamingState>();
+    if !streaming.service.is_running(stream) {
+        return;
+    }
+
+    tracing::warn!("Audio {:?} stream stalled for {:?}, restarting", stream, stalled);
+    let restarted = match streaming.service.restart(stream).await {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::error!("Failed to restart the {:?} stream: {}", stream, e);
+            false
+        }
+    };
+    let _ = events::emit(
+        app,
+        &AudioStreamStalledEvent {
+            stream,
+            stalled_ms: stalled.as_millis() as u64,
+            restarted,
+        },
+    );
 }
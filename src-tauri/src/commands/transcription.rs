@@ -0,0 +1,53 @@
+//! Live transcription commands
+//! Start and stop the on-device captioning of the call; captions are emitted
+//! as `caption` events
+
+use tauri::{AppHandle, State};
+
+use crate::audio::{Caption, Transcriber, TranscriptionConfig, TranscriptionStatus};
+use crate::error::{AppError, AppResult};
+use crate::events::{self, CaptionEvent};
+
+/// Load the model and start captioning (replaces the running transcription)
+#[tauri::command]
+pub async fn transcription_start(
+    app: AppHandle,
+    transcriber: State<'_, Transcriber>,
+    config: TranscriptionConfig,
+) -> AppResult<TranscriptionStatus> {
+    let transcriber = transcriber.inner().clone();
+    let on_caption = Box::new(move |caption: Caption| {
+        let _ = events::emit(
+            &app,
+            &CaptionEvent {
+                peer_id: caption.peer_id,
+                text: caption.text,
+                start_ms: caption.start_ms,
+                end_ms: caption.end_ms,
+            },
+        );
+    });
+    // Loading a model takes a while
+    tokio::task::spawn_blocking(move || {
+        transcriber.start(config, on_caption)?;
+        Ok(transcriber.status())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Transcription start panicked: {}", e)))?
+}
+
+/// Stop captioning
+#[tauri::command]
+pub async fn transcription_stop(transcriber: State<'_, Transcriber>) -> AppResult<()> {
+    let transcriber = transcriber.inner().clone();
+    // Waits for the inference in progress
+    tokio::task::spawn_blocking(move || transcriber.stop())
+        .await
+        .map_err(|e| AppError::Internal(format!("Transcription stop panicked: {}", e)))
+}
+
+/// Get the transcription state
+#[tauri::command]
+pub fn transcription_status(transcriber: State<'_, Transcriber>) -> TranscriptionStatus {
+    transcriber.status()
+}
@@ -0,0 +1,195 @@
+//! Chat read aloud
+//! Accessibility option speaking incoming chat messages on the playback
+//! output, one at a time, with a per-sender choice and rate/volume settings
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audio::{self, MAX_TTS_RATE, MIN_TTS_RATE};
+use crate::commands::streaming::{play_locally, StreamingState};
+use crate::error::{AppError, AppResult};
+use crate::i18n::{self, Locale, Text};
+use crate::storage;
+
+const TTS_FILE: &str = "tts.json";
+
+/// Messages waiting to be read; past this the oldest are skipped
+const MAX_PENDING: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TtsSettings {
+    pub enabled: bool,
+    /// Speech rate, 0.5 to 2 (1 is the engine's normal pace)
+    pub rate: f32,
+    /// 0 to 1
+    pub volume: f32,
+    /// Per-sender choice, senders not listed are read
+    pub senders: HashMap<String, bool>,
+}
+
+impl Default for TtsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: 1.0,
+            volume: 1.0,
+            senders: HashMap::new(),
+        }
+    }
+}
+
+impl TtsSettings {
+    fn validate(&self) -> AppResult<()> {
+        if !(MIN_TTS_RATE..=MAX_TTS_RATE).contains(&self.rate) {
+            return Err(AppError::InvalidArgument(format!(
+                "Speech rate must be between {} and {}",
+                MIN_TTS_RATE, MAX_TTS_RATE
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(AppError::InvalidArgument(
+                "Speech volume must be between 0 and 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether messages from `sender` are read
+    pub fn allows(&self, sender: &str) -> bool {
+        self.enabled && self.senders.get(sender).copied().unwrap_or(true)
+    }
+}
+
+/// State for reading the chat aloud
+pub struct TtsState {
+    settings: RwLock<TtsSettings>,
+    pending: Mutex<VecDeque<String>>,
+    /// A task is reading the pending messages
+    speaking: AtomicBool,
+}
+
+impl TtsState {
+    pub fn new() -> Self {
+        let settings = storage::load_json::<TtsSettings>(TTS_FILE)
+            .filter(|s| s.validate().is_ok())
+            .unwrap_or_default();
+        Self {
+            settings: RwLock::new(settings),
+            pending: Mutex::new(VecDeque::new()),
+            speaking: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Default for TtsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Queue an incoming chat message to be read, if the settings allow it
+pub fn announce(app: &AppHandle, sender: &str, message: &str) {
+    let state = app.state::<TtsState>();
+    if !state.settings.read().allows(sender) || message.trim().is_empty() {
+        return;
+    }
+
+    let text = i18n::tf(
+        Text::ChatMessageSpoken,
+        &[("username", sender), ("message", message)],
+    );
+    {
+        let mut pending = state.pending.lock();
+        if pending.len() >= MAX_PENDING {
+            pending.pop_front();
+        }
+        pending.push_back(text);
+    }
+
+    if !state.speaking.swap(true, Ordering::SeqCst) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { speak_pending(&app).await });
+    }
+}
+
+/// Read the pending messages until there are none left
+async fn speak_pending(app: &AppHandle) {
+    let state = app.state::<TtsState>();
+    loop {
+        let next = state.pending.lock().pop_front();
+        let Some(text) = next else {
+            state.speaking.store(false, Ordering::SeqCst);
+            // A message queued right before the flag was cleared is read now,
+            // unless another task picked it up
+            if state.pending.lock().is_empty() || state.speaking.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            continue;
+        };
+        if let Err(e) = speak(app, &state, text).await {
+            tracing::warn!("Failed to read a chat message aloud: {}", e);
+        }
+    }
+}
+
+async fn speak(app: &AppHandle, state: &TtsState, text: String) -> AppResult<()> {
+    let settings = state.settings.read().clone();
+    let streaming = app.state::<StreamingState>();
+    let service = &streaming.service;
+    // Disabled or deafened since the message was queued
+    if !settings.enabled || service.is_deafened() {
+        return Ok(());
+    }
+
+    let language = match i18n::locale() {
+        Locale::Fr => "fr",
+        Locale::En => "en",
+    };
+    let rate = settings.rate;
+    let mut samples = tokio::task::spawn_blocking(move || audio::synthesize(&text, rate, language))
+        .await
+        .map_err(|e| AppError::Internal(format!("Text-to-speech panicked: {}", e)))??;
+    for sample in &mut samples {
+        *sample *= settings.volume;
+    }
+
+    play_locally(service, &samples).await
+}
+
+// ============ TTS COMMANDS ============
+
+/// Get the chat reading settings
+#[tauri::command]
+pub fn tts_get_settings(state: State<'_, TtsState>) -> TtsSettings {
+    state.settings.read().clone()
+}
+
+/// Update the chat reading settings (turning it off skips the pending messages)
+#[tauri::command]
+pub fn tts_set_settings(state: State<'_, TtsState>, settings: TtsSettings) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(TTS_FILE, &settings).map_err(AppError::Storage)?;
+    if !settings.enabled {
+        state.pending.lock().clear();
+    }
+    *state.settings.write() = settings;
+    Ok(())
+}
+
+/// Read or skip the messages of one sender
+#[tauri::command]
+pub fn tts_set_sender_enabled(
+    state: State<'_, TtsState>,
+    sender: String,
+    enabled: bool,
+) -> AppResult<()> {
+    let mut settings = state.settings.read().clone();
+    settings.senders.insert(sender, enabled);
+    storage::save_json(TTS_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    Ok(())
+}
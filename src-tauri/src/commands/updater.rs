@@ -0,0 +1,251 @@
+//! Updater commands
+//! Release channel selection and update checks done from the backend, so the
+//! channel picks the manifest the updater reads. A manifest can carry a
+//! `rollout` percentage: each install draws a stable bucket (0-99) once and only
+//! sees the update when its bucket falls under that percentage.
+
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::error::{AppError, AppResult};
+use crate::events::{self, UpdateProgressEvent};
+use crate::storage;
+
+const UPDATER_FILE: &str = "updater.json";
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/HydrowZer/HydrowLand/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/HydrowZer/HydrowLand/releases/download/beta/latest.json";
+
+/// Release channel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+/// Persisted updater settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdaterSettings {
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// Staged rollout bucket of this install (0-99), drawn once
+    #[serde(default = "draw_rollout_bucket")]
+    pub rollout_bucket: u8,
+}
+
+impl Default for UpdaterSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::default(),
+            rollout_bucket: draw_rollout_bucket(),
+        }
+    }
+}
+
+fn draw_rollout_bucket() -> u8 {
+    rand::thread_rng().gen_range(0..100)
+}
+
+/// Result of an update check
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateAvailability {
+    /// An update can be installed with `updater_install`
+    pub available: bool,
+    pub current_version: String,
+    /// Version found in the channel manifest (None when up to date)
+    pub version: Option<String>,
+    /// Publication date (RFC 3339)
+    pub date: Option<String>,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+    /// A newer version exists but this install is not in its rollout yet
+    pub deferred_by_rollout: bool,
+}
+
+/// Updater settings and the update found by the last check
+pub struct UpdaterState {
+    settings: RwLock<UpdaterSettings>,
+    pending: Mutex<Option<Update>>,
+}
+
+impl UpdaterState {
+    pub fn new() -> Self {
+        let settings = match storage::load_json::<UpdaterSettings>(UPDATER_FILE) {
+            Some(settings) => settings,
+            None => {
+                // Persist the bucket right away so it stays the same across launches
+                let settings = UpdaterSettings::default();
+                if let Err(e) = storage::save_json(UPDATER_FILE, &settings) {
+                    tracing::warn!("Failed to save updater settings: {}", e);
+                }
+                settings
+            }
+        };
+        Self {
+            settings: RwLock::new(settings),
+            pending: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for UpdaterState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a bucket is part of a rollout (no percentage means everyone)
+fn in_rollout(rollout: Option<u64>, bucket: u8) -> bool {
+    rollout.is_none_or(|percent| u64::from(bucket) < percent)
+}
+
+// ============ UPDATER COMMANDS ============
+
+/// Get the release channel
+#[tauri::command]
+pub fn updater_get_channel(state: State<'_, UpdaterState>) -> UpdateChannel {
+    state.settings.read().channel
+}
+
+/// Set the release channel ("stable" or "beta"), used by the next check
+#[tauri::command]
+pub fn updater_set_channel(
+    state: State<'_, UpdaterState>,
+    channel: UpdateChannel,
+) -> AppResult<()> {
+    let mut settings = state.settings.read().clone();
+    if settings.channel == channel {
+        return Ok(());
+    }
+    settings.channel = channel;
+    storage::save_json(UPDATER_FILE, &settings).map_err(AppError::Storage)?;
+    *state.settings.write() = settings;
+    // An update found on the other channel must not be installed
+    state.pending.lock().take();
+    tracing::info!("Update channel set to {:?}", channel);
+    Ok(())
+}
+
+/// Check the channel manifest for an update
+#[tauri::command]
+pub async fn updater_check_now(
+    app: AppHandle,
+    state: State<'_, UpdaterState>,
+) -> AppResult<UpdateAvailability> {
+    let UpdaterSettings {
+        channel,
+        rollout_bucket,
+    } = state.settings.read().clone();
+
+    let endpoint = Url::parse(channel.endpoint())
+        .map_err(|e| AppError::Internal(format!("Invalid update endpoint: {}", e)))?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| AppError::Update(e.to_string()))?
+        .check()
+        .await
+        .map_err(|e| AppError::Update(e.to_string()))?;
+
+    let current_version = app.package_info().version.to_string();
+    let Some(update) = update else {
+        state.pending.lock().take();
+        return Ok(UpdateAvailability {
+            available: false,
+            current_version,
+            version: None,
+            date: None,
+            notes: None,
+            channel,
+            deferred_by_rollout: false,
+        });
+    };
+
+    let rollout = update.raw_json.get("rollout").and_then(|v| v.as_u64());
+    let available = in_rollout(rollout, rollout_bucket);
+    if !available {
+        tracing::info!(
+            "Update {} deferred (rollout {:?}%, bucket {})",
+            update.version,
+            rollout,
+            rollout_bucket
+        );
+    }
+
+    let availability = UpdateAvailability {
+        available,
+        current_version,
+        version: Some(update.version.clone()),
+        date: update.date.map(|date| date.to_string()),
+        notes: update.body.clone(),
+        channel,
+        deferred_by_rollout: !available,
+    };
+    *state.pending.lock() = available.then_some(update);
+    Ok(availability)
+}
+
+/// Download and install the update found by the last check, emitting
+/// `update-download-progress` along the way (relaunch to apply it)
+#[tauri::command]
+pub async fn updater_install(app: AppHandle, state: State<'_, UpdaterState>) -> AppResult<()> {
+    let update = state
+        .pending
+        .lock()
+        .take()
+        .ok_or_else(|| AppError::InvalidArgument("No update to install".to_string()))?;
+
+    let channel = state.settings.read().channel;
+
+    tracing::info!("Installing update {}", update.version);
+    let mut downloaded = 0u64;
+    let result = update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = events::emit(&app, &UpdateProgressEvent { downloaded, total });
+            },
+            || tracing::info!("Update downloaded"),
+        )
+        .await;
+    if let Err(e) = result {
+        // Kept for a retry, unless a check or a channel switch replaced it meanwhile
+        let mut pending = state.pending.lock();
+        if pending.is_none() && state.settings.read().channel == channel {
+            *pending = Some(update);
+        }
+        return Err(AppError::Update(e.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollout_buckets() {
+        assert!(in_rollout(None, 99));
+        assert!(in_rollout(Some(25), 24));
+        assert!(!in_rollout(Some(25), 25));
+        assert!(!in_rollout(Some(0), 0));
+        assert!(in_rollout(Some(100), 99));
+    }
+}
@@ -0,0 +1,46 @@
+//! Virtual camera commands
+//! Route a screen share into a virtual camera device for other apps
+
+use tauri::State;
+
+use crate::error::{AppError, AppResult};
+use crate::video::{VirtualCamera, VirtualCameraConfig, VirtualCameraSource, VirtualCameraStatus};
+
+/// Start the virtual camera with our screen stream (`local`) or the frames the
+/// viewer pushes with `virtual_camera_push_frame` (`remote`)
+#[tauri::command]
+pub fn virtual_camera_start(
+    camera: State<'_, VirtualCamera>,
+    source: VirtualCameraSource,
+    config: Option<VirtualCameraConfig>,
+) -> AppResult<VirtualCameraStatus> {
+    camera.start(source, config.unwrap_or_default())?;
+    Ok(camera.status())
+}
+
+/// Stop the virtual camera
+#[tauri::command]
+pub fn virtual_camera_stop(camera: State<'_, VirtualCamera>) {
+    camera.stop();
+}
+
+/// Get the virtual camera state and counters
+#[tauri::command]
+pub fn virtual_camera_status(camera: State<'_, VirtualCamera>) -> VirtualCameraStatus {
+    camera.status()
+}
+
+/// Push a received screen frame (base64 JPEG, as in `screen-frame`)
+/// Ignored unless the camera runs on the remote source.
+#[tauri::command]
+pub fn virtual_camera_push_frame(camera: State<'_, VirtualCamera>, data: String) -> AppResult<()> {
+    if !camera.wants(VirtualCameraSource::Remote) {
+        return Ok(());
+    }
+    use base64::Engine;
+    let jpeg = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| AppError::InvalidArgument(format!("Invalid frame data: {}", e)))?;
+    camera.push_remote(jpeg);
+    Ok(())
+}
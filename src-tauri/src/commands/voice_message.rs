@@ -0,0 +1,204 @@
+//! Voice message commands
+//! Record a short clip from the processed capture, send it to the talk room
+//! over the data channel and play clips back on the selected output
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audio::{
+    VoiceClip, VoiceMessageAssembler, VoiceMessageInfo, MAX_VOICE_MESSAGE_DURATION,
+    MIN_VOICE_MESSAGE_DURATION,
+};
+use crate::bandwidth;
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::commands::bandwidth::BandwidthState;
+use crate::commands::guard::{CommandGuards, Subsystem};
+use crate::commands::streaming::{play_locally, StreamingState};
+use crate::error::{AppError, AppResult};
+use crate::events::{self, VoiceMessageEvent};
+
+/// Clips kept for playback (sent and received), oldest dropped first
+const MAX_STORED_CLIPS: usize = 50;
+
+/// Recording in progress
+struct Recording {
+    /// Capture was started for the recording and is stopped with it
+    started_capture: bool,
+    started_at: Instant,
+}
+
+/// State for voice messages
+#[derive(Default)]
+pub struct VoiceMessageState {
+    recording: Mutex<Option<Recording>>,
+    clips: Mutex<VecDeque<VoiceClip>>,
+    assembler: Mutex<VoiceMessageAssembler>,
+}
+
+impl VoiceMessageState {
+    fn store(&self, clip: VoiceClip) {
+        let mut clips = self.clips.lock();
+        if clips.len() >= MAX_STORED_CLIPS {
+            clips.pop_front();
+        }
+        clips.push_back(clip);
+    }
+
+    fn clip(&self, id: &str) -> Option<VoiceClip> {
+        self.clips.lock().iter().find(|c| c.info.id == id).cloned()
+    }
+
+    /// Stop the recording, returning its samples
+    fn finish(&self, streaming: &StreamingState) -> AppResult<(Vec<f32>, Duration)> {
+        let recording =
+            self.recording.lock().take().ok_or_else(|| {
+                AppError::InvalidArgument("No voice message recording".to_string())
+            })?;
+
+        let samples = streaming.service.take_mic_recording();
+        if recording.started_capture {
+            streaming.service.stop_capture();
+        }
+        Ok((samples, recording.started_at.elapsed()))
+    }
+}
+
+/// Handle a binary message received from a peer (the mesh routes every
+/// binary data channel message here)
+pub fn receive(app: &AppHandle, peer_id: &str, data: &[u8]) {
+    let state = app.state::<VoiceMessageState>();
+    let Some(clip) = state.assembler.lock().push(peer_id, data) else {
+        return;
+    };
+    let Some((room_code, _)) = app.state::<AudioMeshState>().room_of_peer(peer_id) else {
+        return;
+    };
+
+    let VoiceMessageInfo {
+        id,
+        sender,
+        duration_ms,
+        timestamp,
+    } = clip.info.clone();
+    tracing::info!("Voice message {} from {} ({} ms)", id, peer_id, duration_ms);
+    state.store(clip);
+    let _ = events::emit(
+        app,
+        &VoiceMessageEvent {
+            room_code,
+            peer_id: peer_id.to_string(),
+            id,
+            sender,
+            duration_ms,
+            timestamp,
+        },
+    );
+}
+
+/// Start recording a voice message, sent with `voice_message_send`
+/// (capture past `MAX_VOICE_MESSAGE_DURATION` is not recorded)
+#[tauri::command]
+pub async fn voice_message_start(
+    state: State<'_, VoiceMessageState>,
+    streaming: State<'_, StreamingState>,
+    guards: State<'_, CommandGuards>,
+) -> AppResult<()> {
+    // Starts the capture: no other voice command runs meanwhile
+    let _guard = guards.lock(Subsystem::Voice).await;
+    if state.recording.lock().is_some() {
+        return Err(AppError::AlreadyActive(
+            "Voice message already recording".to_string(),
+        ));
+    }
+
+    let service = &streaming.service;
+    let started_capture = !service.is_capturing();
+    service.start_capture().await?;
+    if let Err(e) = service.start_mic_recording(MAX_VOICE_MESSAGE_DURATION) {
+        if started_capture {
+            service.stop_capture();
+        }
+        return Err(e);
+    }
+
+    *state.recording.lock() = Some(Recording {
+        started_capture,
+        started_at: Instant::now(),
+    });
+    tracing::info!("Voice message: recording");
+    Ok(())
+}
+
+/// Discard the recording
+#[tauri::command]
+pub fn voice_message_cancel(
+    state: State<'_, VoiceMessageState>,
+    streaming: State<'_, StreamingState>,
+) -> AppResult<()> {
+    state.finish(&streaming)?;
+    tracing::info!("Voice message: cancelled");
+    Ok(())
+}
+
+/// Stop recording, encode the clip and send it to the talk room
+#[tauri::command]
+pub async fn voice_message_send(
+    state: State<'_, VoiceMessageState>,
+    streaming: State<'_, StreamingState>,
+    mesh: State<'_, AudioMeshState>,
+    bandwidth_state: State<'_, BandwidthState>,
+) -> AppResult<VoiceMessageInfo> {
+    let (samples, elapsed) = state.finish(&streaming)?;
+    if elapsed < MIN_VOICE_MESSAGE_DURATION {
+        return Err(AppError::InvalidArgument(
+            "Voice message too short".to_string(),
+        ));
+    }
+
+    let manager = mesh.manager()?;
+    let sender = manager
+        .get_local_username()
+        .unwrap_or_else(|| "Anonymous".to_string());
+    let clip = tokio::task::spawn_blocking(move || VoiceClip::encode(&sender, &samples))
+        .await
+        .map_err(|e| AppError::Internal(format!("Voice message encoder panicked: {}", e)))??;
+
+    let chunks = clip.to_chunks()?;
+    match bandwidth_state.transfer_kbps() {
+        // Paced so the clip stays within the transfer share of the upload cap
+        Some(kbps) => {
+            for chunk in &chunks {
+                manager
+                    .broadcast_binary(std::slice::from_ref(chunk))
+                    .await?;
+                let bytes = chunk.len() * manager.connected_peer_count().max(1);
+                tokio::time::sleep(bandwidth::pacing_delay(bytes, kbps)).await;
+            }
+        }
+        None => manager.broadcast_binary(&chunks).await?,
+    }
+
+    let info = clip.info.clone();
+    tracing::info!("Voice message {} sent ({} ms)", info.id, info.duration_ms);
+    state.store(clip);
+    Ok(info)
+}
+
+/// Play a sent or received voice message, returning once it is over
+#[tauri::command]
+pub async fn voice_message_play(
+    state: State<'_, VoiceMessageState>,
+    streaming: State<'_, StreamingState>,
+    id: String,
+) -> AppResult<()> {
+    let clip = state
+        .clip(&id)
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unknown voice message: {}", id)))?;
+    let samples = tokio::task::spawn_blocking(move || clip.decode())
+        .await
+        .map_err(|e| AppError::Internal(format!("Voice message decoder panicked: {}", e)))??;
+
+    play_locally(&streaming.service, &samples).await
+}
@@ -1,15 +1,91 @@
 #![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::commands::event_log::record_if_hosting;
+use crate::error::AppResult;
+use crate::event_log::{EventLog, RoomEventKind};
+use crate::events;
+use crate::server::ServerState;
+use crate::webrtc::ice_servers::{self, IceConfiguration, IceServerSettings};
+use crate::webrtc::network::{self, NetworkInterface, NetworkSettings};
+use crate::webrtc::timeouts::{self, ConnectionTimeouts};
 use crate::webrtc::{ConnectionOffer, MeshManager, WebRTCManager};
+#[cfg(feature = "whip")]
+use crate::webrtc::WhipPublisher;
+
+/// Get the ICE gathering, connection and keepalive timeouts
+#[tauri::command]
+pub fn webrtc_get_connection_timeouts() -> ConnectionTimeouts {
+    timeouts::current()
+}
+
+/// Save the timeouts used by connections created from now on
+#[tauri::command]
+pub fn webrtc_set_connection_timeouts(timeouts: ConnectionTimeouts) -> AppResult<()> {
+    timeouts::save(timeouts)
+}
+
+/// Get the IP family and interface ICE candidates are gathered on
+#[tauri::command]
+pub fn webrtc_get_network_settings() -> NetworkSettings {
+    network::current()
+}
+
+/// Save the network settings used by connections created from now on
+#[tauri::command]
+pub fn webrtc_set_network_settings(settings: NetworkSettings) -> AppResult<()> {
+    network::save(settings)
+}
+
+/// List the interfaces candidates can be limited to
+#[tauri::command]
+pub fn webrtc_list_network_interfaces() -> Vec<NetworkInterface> {
+    network::list_interfaces()
+}
+
+/// Get the STUN and TURN servers configured by the user
+#[tauri::command]
+pub fn webrtc_get_ice_servers() -> IceServerSettings {
+    ice_servers::current()
+}
+
+/// Save the STUN and TURN servers used by connections created from now on
+#[tauri::command]
+pub fn webrtc_set_ice_servers(settings: IceServerSettings) -> AppResult<()> {
+    ice_servers::save(settings)
+}
+
+/// Servers and relay policy for the webview's peer connections
+#[tauri::command]
+pub fn webrtc_get_ice_configuration() -> IceConfiguration {
+    ice_servers::configuration()
+}
+
+/// Forward the progress of ICE gathering to the frontend ("gathering candidates…")
+/// Every manager reports to the same channel.
+pub fn spawn_gathering_task(app: AppHandle) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    app.state::<WebRTCManager>().set_gathering_sender(tx.clone());
+    app.state::<MeshManager>().set_gathering_sender(tx.clone());
+    #[cfg(feature = "whip")]
+    app.state::<WhipPublisher>().set_gathering_sender(tx.clone());
+    app.state::<AudioMeshState>().set_gathering_sender(tx);
+    tauri::async_runtime::spawn(async move {
+        while let Some(progress) = rx.recv().await {
+            let _ = events::emit(&app, &progress);
+        }
+    });
+}
 
 /// Create a WebRTC offer (host creates this first)
 #[tauri::command]
 pub async fn create_webrtc_offer(
     webrtc: State<'_, WebRTCManager>,
     username: String,
-) -> Result<ConnectionOffer, String> {
+) -> AppResult<ConnectionOffer> {
     webrtc.set_username(username);
     webrtc.create_offer().await
 }
@@ -20,7 +96,7 @@ pub async fn accept_webrtc_offer(
     webrtc: State<'_, WebRTCManager>,
     offer_base64: String,
     username: String,
-) -> Result<ConnectionOffer, String> {
+) -> AppResult<ConnectionOffer> {
     webrtc.set_username(username);
     webrtc.accept_offer(&offer_base64).await
 }
@@ -30,7 +106,7 @@ pub async fn accept_webrtc_offer(
 pub async fn accept_webrtc_answer(
     webrtc: State<'_, WebRTCManager>,
     answer_base64: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     webrtc.accept_answer(&answer_base64).await
 }
 
@@ -39,7 +115,7 @@ pub async fn accept_webrtc_answer(
 pub async fn send_chat_message(
     webrtc: State<'_, WebRTCManager>,
     message: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     webrtc.send_message(&message).await
 }
 
@@ -51,7 +127,7 @@ pub fn is_webrtc_connected(webrtc: State<'_, WebRTCManager>) -> bool {
 
 /// Close WebRTC connection
 #[tauri::command]
-pub fn close_webrtc(webrtc: State<'_, WebRTCManager>) -> Result<(), String> {
+pub fn close_webrtc(webrtc: State<'_, WebRTCManager>) -> AppResult<()> {
     webrtc.close();
     Ok(())
 }
@@ -76,19 +152,30 @@ pub async fn mesh_create_offer(
     mesh: State<'_, MeshManager>,
     peer_id: String,
     peer_username: String,
-) -> Result<ConnectionOffer, String> {
+) -> AppResult<ConnectionOffer> {
     mesh.create_offer_for_peer(&peer_id, &peer_username).await
 }
 
 /// Accept offer from a peer (mesh)
+/// When hosting, `join_code` is the code the peer joined with (server code or invite)
 #[tauri::command]
 pub async fn mesh_accept_offer(
     mesh: State<'_, MeshManager>,
+    server: State<'_, ServerState>,
+    log: State<'_, EventLog>,
     peer_id: String,
     peer_username: String,
     offer_base64: String,
-) -> Result<ConnectionOffer, String> {
-    mesh.accept_offer_from_peer(&peer_id, &peer_username, &offer_base64).await
+    join_code: Option<String>,
+) -> AppResult<ConnectionOffer> {
+    server.validate_join_code(join_code.as_deref())?;
+    let answer = mesh.accept_offer_from_peer(&peer_id, &peer_username, &offer_base64).await?;
+    if let Err(e) = server.consume_join_code(join_code.as_deref()) {
+        mesh.remove_peer(&peer_id);
+        return Err(e.into());
+    }
+    record_if_hosting(&log, &server, RoomEventKind::Join, &peer_id, &peer_username, join_code);
+    Ok(answer)
 }
 
 /// Accept answer from a peer (mesh)
@@ -97,7 +184,7 @@ pub async fn mesh_accept_answer(
     mesh: State<'_, MeshManager>,
     peer_id: String,
     answer_base64: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     mesh.accept_answer_from_peer(&peer_id, &answer_base64).await
 }
 
@@ -106,7 +193,7 @@ pub async fn mesh_accept_answer(
 pub async fn mesh_send_chat(
     mesh: State<'_, MeshManager>,
     message: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     mesh.send_chat_message(&message).await
 }
 
@@ -130,7 +217,15 @@ pub fn mesh_is_connected(mesh: State<'_, MeshManager>) -> bool {
 
 /// Remove a specific peer
 #[tauri::command]
-pub fn mesh_remove_peer(mesh: State<'_, MeshManager>, peer_id: String) {
+pub fn mesh_remove_peer(
+    mesh: State<'_, MeshManager>,
+    server: State<'_, ServerState>,
+    log: State<'_, EventLog>,
+    peer_id: String,
+) {
+    if let Some(username) = mesh.peer_username(&peer_id) {
+        record_if_hosting(&log, &server, RoomEventKind::Leave, &peer_id, &username, None);
+    }
     mesh.remove_peer(&peer_id);
 }
 
@@ -145,6 +240,6 @@ pub fn mesh_close_all(mesh: State<'_, MeshManager>) {
 pub async fn mesh_announce_peer(
     mesh: State<'_, MeshManager>,
     peer_username: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     mesh.announce_new_peer(&peer_username).await
 }
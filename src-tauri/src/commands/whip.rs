@@ -0,0 +1,33 @@
+//! WHIP egress commands
+//! Broadcast our voice and screen share through a WHIP server
+
+use tauri::State;
+
+use crate::commands::streaming::StreamingState;
+use crate::error::AppResult;
+use crate::webrtc::{WhipConfig, WhipPublisher, WhipStatus};
+
+/// Start publishing to a WHIP endpoint (replaces the current broadcast)
+#[tauri::command]
+pub async fn whip_start(
+    publisher: State<'_, WhipPublisher>,
+    streaming: State<'_, StreamingState>,
+    config: WhipConfig,
+) -> AppResult<WhipStatus> {
+    publisher
+        .start(config, streaming.service.broadcast_tap())
+        .await
+}
+
+/// Stop publishing and end the session on the server
+#[tauri::command]
+pub async fn whip_stop(publisher: State<'_, WhipPublisher>) -> AppResult<()> {
+    publisher.stop().await;
+    Ok(())
+}
+
+/// Get the broadcast state and counters
+#[tauri::command]
+pub fn whip_status(publisher: State<'_, WhipPublisher>) -> WhipStatus {
+    publisher.status()
+}
@@ -0,0 +1,115 @@
+//! Crash reporting
+//! A panic hook writes a crash file with a backtrace and notifies the frontend,
+//! so a panic in an audio callback or a background task does not go unnoticed
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use tauri::AppHandle;
+
+use crate::events::{self, BackendCrashEvent};
+use crate::{logging, storage};
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Directory holding the crash files
+pub fn crash_dir() -> PathBuf {
+    logging::log_dir().join("crashes")
+}
+
+/// Install the panic hook (call once, after logging is initialized)
+/// The default hook still runs, so panics are printed to stderr as before.
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        report(info);
+        default_hook(info);
+    }));
+}
+
+/// Enable `backend-crash` events (panics before this are only written to disk)
+pub fn set_app_handle(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+fn report(info: &PanicHookInfo<'_>) {
+    let thread = std::thread::current()
+        .name()
+        .unwrap_or("<unnamed>")
+        .to_string();
+    let message = panic_message(info);
+    let location = info.location().map(|l| l.to_string());
+    let backtrace = Backtrace::force_capture();
+
+    tracing::error!(
+        "Panic in thread '{}' at {}: {}",
+        thread,
+        location.as_deref().unwrap_or("<unknown>"),
+        message
+    );
+
+    let crash_file = if storage::is_guest_mode() {
+        None
+    } else {
+        write_crash_file(&thread, &message, location.as_deref(), &backtrace)
+    };
+
+    if let Some(app) = APP_HANDLE.get() {
+        let _ = events::emit(
+            app,
+            &BackendCrashEvent {
+                thread,
+                message,
+                location,
+                crash_file: crash_file.map(|p| p.to_string_lossy().into_owned()),
+            },
+        );
+    }
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
+fn write_crash_file(
+    thread: &str,
+    message: &str,
+    location: Option<&str>,
+    backtrace: &Backtrace,
+) -> Option<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = crash_dir().join(format!("crash-{}.txt", timestamp));
+
+    let content = format!(
+        "HydrowLand {}\nOS: {} ({})\nTimestamp: {}\nThread: {}\nLocation: {}\nMessage: {}\n\nBacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        timestamp,
+        thread,
+        location.unwrap_or("<unknown>"),
+        message,
+        backtrace
+    );
+
+    fs::create_dir_all(crash_dir()).ok()?;
+    match fs::write(&path, content) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            tracing::error!("Failed to write crash file: {}", e);
+            None
+        }
+    }
+}
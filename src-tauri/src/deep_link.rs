@@ -0,0 +1,168 @@
+//! Join from outside the app
+//! A room code can come from the command line (`--join CODE`) or an invite link
+//! (`hydrowland://join/CODE`). A second launch hands its arguments over to the
+//! running instance, so clicking a link while the app is open joins right there.
+//! The join goes through the same path as the `join_server` command.
+
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager, Url};
+
+use crate::commands;
+use crate::events::{self, AutoJoinEvent};
+use crate::i18n;
+use crate::server::ServerState;
+use crate::tray;
+
+/// URL scheme of invite links
+pub const SCHEME: &str = "hydrowland";
+
+/// Code that arrived before a username was known, for the lobby to pick up
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Option<String>>,
+}
+
+impl DeepLinkState {
+    pub fn take_pending(&self) -> Option<String> {
+        self.pending.lock().take()
+    }
+}
+
+/// Room code in command-line arguments or invite URLs, if any
+pub fn parse_join_code<I>(args: I) -> Option<String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        let code = if arg == "--join" {
+            args.next().map(|next| next.as_ref().to_string())
+        } else if let Some(code) = arg.strip_prefix("--join=") {
+            Some(code.to_string())
+        } else if let Ok(url) = Url::parse(arg) {
+            code_from_url(&url)
+        } else {
+            None
+        };
+        if let Some(code) = code.filter(|code| is_valid_code(code)) {
+            return Some(code.to_uppercase());
+        }
+    }
+    None
+}
+
+fn code_from_url(url: &Url) -> Option<String> {
+    if url.scheme() != SCHEME || url.host_str() != Some("join") {
+        return None;
+    }
+    url.path_segments()?
+        .find(|segment| !segment.is_empty())
+        .map(str::to_string)
+}
+
+/// Server and invite codes are short and alphanumeric
+fn is_valid_code(code: &str) -> bool {
+    (4..=16).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Join the room named in `args`, if any (launch arguments, a second launch or
+/// an opened URL)
+pub fn handle_args<I>(app: &AppHandle, args: I)
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let Some(code) = parse_join_code(args) else {
+        return;
+    };
+    tray::show_main_window(app);
+
+    // The lobby has not sent a username yet (first launch): it joins itself
+    let Some(username) = app
+        .state::<ServerState>()
+        .get_config()
+        .map(|cfg| cfg.username)
+    else {
+        tracing::info!("Join code {} kept until the lobby is ready", code);
+        *app.state::<DeepLinkState>().pending.lock() = Some(code);
+        return;
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("Auto-joining {}", code);
+        let event = match commands::server::join(&app, code.clone(), username).await {
+            Ok(info) => AutoJoinEvent {
+                code,
+                info: Some(info),
+                error: None,
+            },
+            Err(e) => {
+                tracing::warn!("Auto-join of {} failed: {}", code, e);
+                AutoJoinEvent {
+                    code,
+                    info: None,
+                    error: Some(
+                        i18n::error_description(e.code())
+                            .map(str::to_string)
+                            .unwrap_or_else(|| e.to_string()),
+                    ),
+                }
+            }
+        };
+        if let Err(e) = events::emit(&app, &event) {
+            tracing::warn!("Failed to emit auto-join: {}", e);
+        }
+    });
+}
+
+/// Listen for invite links opened while running and handle the launch ones
+pub fn init(app: &AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installers register the scheme; dev builds and AppImages have to do it here
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("Failed to register the {} scheme: {}", SCHEME, e);
+    }
+
+    // Links opened while running (macOS; elsewhere they start a second instance)
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        handle_args(&handle, event.urls().iter().map(Url::as_str));
+    });
+
+    // The launch link comes through the plugin on macOS, through argv elsewhere
+    #[cfg(target_os = "macos")]
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        handle_args(app, urls.iter().map(Url::as_str));
+        return;
+    }
+    handle_args(app, std::env::args().skip(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_code_from_args_and_links() {
+        assert_eq!(
+            parse_join_code(["--join", "ab12cd"]),
+            Some("AB12CD".to_string())
+        );
+        assert_eq!(
+            parse_join_code(["--guest", "--join=XY34ZW"]),
+            Some("XY34ZW".to_string())
+        );
+        assert_eq!(
+            parse_join_code(["hydrowland://join/QWE789/"]),
+            Some("QWE789".to_string())
+        );
+        assert_eq!(parse_join_code(["https://join/QWE789"]), None);
+        assert_eq!(parse_join_code(["--join", "../etc"]), None);
+        assert_eq!(parse_join_code(["--guest"]), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Crate-wide error type
+//! Every command returns `AppResult`; errors reach the frontend as
+//! `{ "code": "...", "message": "...", "localized": "..." }` so it can react to
+//! the code. `message` is the detailed error, `localized` its description in
+//! the user's language (see `i18n`).
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+use crate::bans::BanError;
+use crate::i18n;
+use crate::plugins::PluginError;
+use crate::room::RoomError;
+#[cfg(feature = "screen")]
+use crate::screen::ScreenCaptureError;
+use crate::server::ServerError;
+use crate::session::SessionError;
+use crate::webrtc::SdpError;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Device not found: {0}")]
+    DeviceNotFound(String),
+    #[error("Device unavailable: {0}")]
+    DeviceUnavailable(String),
+    #[error("Audio error: {0}")]
+    Audio(String),
+    #[error("Codec error: {0}")]
+    Codec(String),
+    #[error("Video error: {0}")]
+    Video(String),
+    #[error("WebRTC error: {0}")]
+    WebRtc(String),
+    #[error("Peer not found: {0}")]
+    PeerNotFound(String),
+    #[error("Not connected: {0}")]
+    NotConnected(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("{0}")]
+    AlreadyActive(String),
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Update error: {0}")]
+    Update(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[cfg(feature = "screen")]
+    #[error(transparent)]
+    Screen(#[from] ScreenCaptureError),
+    #[error(transparent)]
+    Server(#[from] ServerError),
+    #[error(transparent)]
+    Room(#[from] RoomError),
+    #[error(transparent)]
+    Ban(#[from] BanError),
+    #[error(transparent)]
+    Session(#[from] SessionError),
+    #[error(transparent)]
+    Sdp(#[from] SdpError),
+    #[error(transparent)]
+    Plugin(#[from] PluginError),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable error code for the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied(_) => "permission_denied",
+            Self::DeviceNotFound(_) => "device_not_found",
+            Self::DeviceUnavailable(_) => "device_unavailable",
+            Self::Audio(_) => "audio_error",
+            Self::Codec(_) => "codec_error",
+            Self::Video(_) => "video_error",
+            Self::WebRtc(_) => "webrtc_error",
+            Self::PeerNotFound(_) => "peer_not_found",
+            Self::NotConnected(_) => "not_connected",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::AlreadyActive(_) => "already_active",
+            Self::Storage(_) => "storage_error",
+            Self::Update(_) => "update_error",
+            Self::Timeout(_) => "timeout",
+            #[cfg(feature = "screen")]
+            Self::Screen(e) => match e {
+                ScreenCaptureError::PermissionDenied => "permission_denied",
+                ScreenCaptureError::NoSourceSelected => "no_source_selected",
+                ScreenCaptureError::SourceNotFound(_) => "source_not_found",
+                _ => "screen_capture_error",
+            },
+            Self::Server(e) => match e {
+                ServerError::NotRunning => "server_not_running",
+                ServerError::AlreadyHosting => "already_hosting",
+                ServerError::AlreadyConnected => "already_connected",
+                ServerError::ConfigError(_) => "storage_error",
+                ServerError::InvalidCode => "invalid_code",
+                ServerError::CodeExpired => "code_expired",
+                ServerError::InviteNotFound => "invite_not_found",
+            },
+            Self::Room(e) => match e {
+                RoomError::NotFound => "room_not_found",
+                RoomError::Full(_) => "room_full",
+                RoomError::InvalidCode => "invalid_code",
+                RoomError::AlreadyInRoom => "already_in_room",
+                RoomError::NotInRoom => "not_in_room",
+            },
+            Self::Ban(e) => match e {
+                BanError::NotFound => "not_banned",
+                BanError::AlreadyBanned => "already_banned",
+                BanError::UnknownIdentity => "unknown_identity",
+                BanError::ConfigError(_) => "storage_error",
+            },
+            Self::Session(SessionError::IllegalTransition { .. }) => "illegal_session_transition",
+            Self::Sdp(e) => match e {
+                SdpError::Empty => "sdp_empty",
+                SdpError::NotBase64(_) => "sdp_not_base64",
+                SdpError::Truncated => "sdp_truncated",
+                SdpError::Malformed(_) => "sdp_malformed",
+                SdpError::WrongType { .. } => "sdp_wrong_type",
+                SdpError::Incompatible(_) => "sdp_incompatible",
+            },
+            Self::Plugin(e) => match e {
+                PluginError::InvalidNamespace(_) => "plugin_invalid_namespace",
+                PluginError::NamespaceTaken(_) => "plugin_namespace_taken",
+                PluginError::NotClaimed(_) => "plugin_not_claimed",
+            },
+            Self::Internal(_) => "internal",
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let code = self.code();
+        let message = self.to_string();
+        let localized = i18n::error_description(code).unwrap_or(&message);
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", code)?;
+        state.serialize_field("message", &message)?;
+        state.serialize_field("localized", localized)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::DevicesError> for AppError {
+    fn from(e: cpal::DevicesError) -> Self {
+        Self::Audio(format!("Failed to enumerate devices: {}", e))
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::DefaultStreamConfigError> for AppError {
+    fn from(e: cpal::DefaultStreamConfigError) -> Self {
+        match e {
+            cpal::DefaultStreamConfigError::DeviceNotAvailable => {
+                Self::DeviceUnavailable(e.to_string())
+            }
+            _ => Self::Audio(format!("Failed to get device config: {}", e)),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::BuildStreamError> for AppError {
+    fn from(e: cpal::BuildStreamError) -> Self {
+        match e {
+            cpal::BuildStreamError::DeviceNotAvailable => Self::DeviceUnavailable(e.to_string()),
+            cpal::BuildStreamError::BackendSpecific { ref err }
+                if is_permission_error(&err.description) =>
+            {
+                Self::PermissionDenied(err.description.clone())
+            }
+            _ => Self::Audio(format!("Failed to build stream: {}", e)),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::PlayStreamError> for AppError {
+    fn from(e: cpal::PlayStreamError) -> Self {
+        match e {
+            cpal::PlayStreamError::DeviceNotAvailable => Self::DeviceUnavailable(e.to_string()),
+            _ => Self::Audio(format!("Failed to start stream: {}", e)),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<opus::Error> for AppError {
+    fn from(e: opus::Error) -> Self {
+        Self::Codec(e.to_string())
+    }
+}
+
+impl From<webrtc::Error> for AppError {
+    fn from(e: webrtc::Error) -> Self {
+        Self::WebRtc(e.to_string())
+    }
+}
+
+/// Backends only report OS permission failures (e.g. macOS microphone access) as text
+#[cfg(feature = "audio")]
+fn is_permission_error(description: &str) -> bool {
+    let description = description.to_lowercase();
+    description.contains("permission") || description.contains("not authorized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_with_code() {
+        let json = serde_json::to_value(AppError::Server(ServerError::CodeExpired)).unwrap();
+        assert_eq!(json["code"], "code_expired");
+        assert_eq!(json["message"], "Invite code expired");
+        assert_eq!(json["localized"], "Invitation expirée");
+    }
+}
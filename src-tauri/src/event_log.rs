@@ -0,0 +1,96 @@
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::storage;
+
+const EVENT_LOG_FILE: &str = "room_events.json";
+
+/// Nombre max d'événements conservés (les plus anciens sont supprimés)
+const MAX_EVENTS: usize = 1000;
+
+/// Type d'événement de room
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomEventKind {
+    Join,
+    Leave,
+    Kick,
+    Ban,
+    HostMigration,
+}
+
+/// Événement enregistré par l'hôte
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomEvent {
+    pub kind: RoomEventKind,
+    pub room_code: String,
+    pub peer_id: String,
+    pub username: String,
+    pub detail: Option<String>,
+    pub timestamp: u64,
+}
+
+/// Journal des événements de room, persisté dans le dossier de config
+#[derive(Clone, Default)]
+pub struct EventLog {
+    events: Arc<RwLock<Vec<RoomEvent>>>,
+}
+
+impl EventLog {
+    /// Charger le journal depuis le fichier
+    pub fn load() -> Self {
+        let events: Vec<RoomEvent> = storage::load_json(EVENT_LOG_FILE).unwrap_or_default();
+        Self {
+            events: Arc::new(RwLock::new(events)),
+        }
+    }
+
+    /// Enregistrer un événement
+    pub fn record(
+        &self,
+        kind: RoomEventKind,
+        room_code: &str,
+        peer_id: &str,
+        username: &str,
+        detail: Option<String>,
+    ) {
+        let event = RoomEvent {
+            kind,
+            room_code: room_code.to_string(),
+            peer_id: peer_id.to_string(),
+            username: username.to_string(),
+            detail,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        tracing::info!("Room event: {:?} {} ({})", event.kind, event.username, event.peer_id);
+
+        let mut events = self.events.write();
+        events.push(event);
+        if events.len() > MAX_EVENTS {
+            let excess = events.len() - MAX_EVENTS;
+            events.drain(..excess);
+        }
+
+        if let Err(e) = storage::save_json(EVENT_LOG_FILE, &*events) {
+            tracing::warn!("Failed to save event log: {}", e);
+        }
+    }
+
+    /// Obtenir les derniers événements (du plus ancien au plus récent)
+    pub fn list(&self, limit: Option<usize>) -> Vec<RoomEvent> {
+        let events = self.events.read();
+        let start = limit.map(|l| events.len().saturating_sub(l)).unwrap_or(0);
+        events[start..].to_vec()
+    }
+
+    /// Vider le journal
+    pub fn clear(&self) -> Result<(), String> {
+        let mut events = self.events.write();
+        events.clear();
+        storage::save_json(EVENT_LOG_FILE, &*events)
+    }
+}
@@ -0,0 +1,645 @@
+//! Event catalog
+//! Every event sent to the frontend is declared here with its name and payload.
+//! Payloads are emitted flattened into `{ "version": N, ...fields }` so the
+//! frontend can detect (and ignore) payload shapes it does not understand.
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Runtime};
+
+#[cfg(feature = "audio")]
+use crate::audio::{MicPermission, StreamKind};
+use crate::audio::{AudioPolicy, AutoMuteReason};
+use crate::chat_commands::SystemMessage;
+use crate::commands::call_timer::LimitAction;
+use crate::commands::port_mapping::PortMappingStatus;
+#[cfg(feature = "audio")]
+use crate::recording::RecordingSummary;
+#[cfg(feature = "screen")]
+use crate::screen::PresenterHints;
+use crate::server::{Peer, ServerInfo};
+use crate::session::SessionPhase;
+use crate::webrtc::gathering::GatheringProgress;
+use crate::webrtc::QualityReport;
+
+/// An event the backend sends to the frontend
+pub trait AppEvent: Serialize + Clone {
+    /// Event name used with `listen()` on the frontend
+    const NAME: &'static str;
+    /// Payload version, bumped on breaking changes to the payload fields
+    const VERSION: u32 = 1;
+}
+
+#[derive(Serialize, Clone)]
+struct Envelope<'a, E> {
+    version: u32,
+    #[serde(flatten)]
+    payload: &'a E,
+}
+
+/// Emit an event to every webview, tagged with its payload version
+pub fn emit<E: AppEvent, R: Runtime>(emitter: &impl Emitter<R>, event: &E) -> tauri::Result<()> {
+    emitter.emit(
+        E::NAME,
+        Envelope {
+            version: E::VERSION,
+            payload: event,
+        },
+    )
+}
+
+/// Emit an event whose name is only known at runtime (data-channel plugins)
+/// A payload that isn't a map (a number, a list...) can't be flattened next to
+/// the version: it is emitted as `{ "version": 1, "value": ... }`.
+pub fn emit_named<S: Serialize + Clone, R: Runtime>(
+    emitter: &impl Emitter<R>,
+    name: &str,
+    payload: &S,
+) -> tauri::Result<()> {
+    let payload = match serde_json::to_value(payload)? {
+        map @ serde_json::Value::Object(_) => map,
+        value => serde_json::json!({ "value": value }),
+    };
+    emitter.emit(
+        name,
+        Envelope {
+            version: 1,
+            payload: &payload,
+        },
+    )
+}
+
+/// Emit an event to one webview only (e.g. a stats stream nobody else needs)
+pub fn emit_to<E: AppEvent, R: Runtime>(
+    emitter: &impl Emitter<R>,
+    label: &str,
+    event: &E,
+) -> tauri::Result<()> {
+    emitter.emit_to(
+        label,
+        E::NAME,
+        Envelope {
+            version: E::VERSION,
+            payload: event,
+        },
+    )
+}
+
+// ============ AUDIO ============
+
+/// Microphone level update
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioLevelEvent {
+    /// Audio level from 0.0 to 1.0
+    pub level: f32,
+    /// Whether the user is speaking (level > threshold)
+    pub is_speaking: bool,
+    /// Raw RMS value
+    pub rms: f32,
+}
+
+impl AppEvent for AudioLevelEvent {
+    const NAME: &'static str = "audio-level";
+}
+
+/// Mute, deafen or push-to-talk changed (from the UI, a hotkey or AFK)
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceStateEvent {
+    pub muted: bool,
+    pub deafened: bool,
+    pub push_to_talk_pressed: bool,
+}
+
+impl AppEvent for VoiceStateEvent {
+    const NAME: &'static str = "voice-state-changed";
+}
+
+/// The capture pipeline muted the mic on its own (followed by a voice state change)
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoMutedEvent {
+    pub reason: AutoMuteReason,
+}
+
+impl AppEvent for AutoMutedEvent {
+    const NAME: &'static str = "auto-muted";
+}
+
+/// The microphone authorization changed (granted or revoked in the OS settings)
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct MicPermissionEvent {
+    pub status: MicPermission,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for MicPermissionEvent {
+    const NAME: &'static str = "mic-permission-changed";
+}
+
+/// An audio stream stopped calling back and the watchdog restarted it
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioStreamStalledEvent {
+    pub stream: StreamKind,
+    pub stalled_ms: u64,
+    /// False when the stream could not be rebuilt (it is left stopped)
+    pub restarted: bool,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for AudioStreamStalledEvent {
+    const NAME: &'static str = "audio-stream-stalled";
+}
+
+/// Opening an audio device failed and will be retried
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceRetryEvent {
+    pub stream: StreamKind,
+    /// Attempt that just failed (from 1)
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub retry_in_ms: u64,
+    pub error: String,
+    /// What to tell the user, in their language
+    pub message: String,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for AudioDeviceRetryEvent {
+    const NAME: &'static str = "audio-device-retry";
+}
+
+/// A stream's device went away and the stream moved to the system default
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceFallbackEvent {
+    pub stream: StreamKind,
+    /// Device that went away (None: it was already the default)
+    pub lost_device: Option<String>,
+    /// Default device now in use (None: nothing could be opened, the stream is stopped)
+    pub fallback_device: Option<String>,
+    pub error: String,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for AudioDeviceFallbackEvent {
+    const NAME: &'static str = "audio-device-fallback";
+}
+
+/// A peer sent no audio for a while and its decoder was dropped
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerAudioTimeoutEvent {
+    pub peer_id: String,
+    pub idle_ms: u64,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for PeerAudioTimeoutEvent {
+    const NAME: &'static str = "peer-audio-timeout";
+}
+
+/// Another participant took the floor (with hysteresis, for highlighting or
+/// enlarging the speaker)
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveSpeakerChangedEvent {
+    /// Peer id, "local" for us, None when nobody speaks
+    pub peer_id: Option<String>,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for ActiveSpeakerChangedEvent {
+    const NAME: &'static str = "active-speaker-changed";
+}
+
+/// Transcribed segment of the call
+#[cfg(feature = "transcription")]
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionEvent {
+    /// Speaking peer, None when the mixed call audio is transcribed
+    pub peer_id: Option<String>,
+    pub text: String,
+    /// From the start of the transcription
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+#[cfg(feature = "transcription")]
+impl AppEvent for CaptionEvent {
+    const NAME: &'static str = "caption";
+}
+
+/// A voice message was received (play it with `voice_message_play`)
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceMessageEvent {
+    pub room_code: String,
+    pub peer_id: String,
+    pub id: String,
+    pub sender: String,
+    pub duration_ms: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for VoiceMessageEvent {
+    const NAME: &'static str = "voice-message";
+}
+
+// ============ PEERS ============
+
+/// A media track of a connected peer stopped (recovery is being attempted)
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerMediaLostEvent {
+    pub peer_id: String,
+    /// "audio" or "video"
+    pub kind: String,
+    pub reason: String,
+}
+
+impl AppEvent for PeerMediaLostEvent {
+    const NAME: &'static str = "peer-media-lost";
+}
+
+/// ICE candidates are being gathered for an offer or answer (or just finished)
+impl AppEvent for GatheringProgress {
+    const NAME: &'static str = "ice-gathering";
+}
+
+// ============ SCREEN ============
+
+/// Encoded screen frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedFrameData {
+    /// Base64 encoded JPEG data
+    pub data: String,
+    /// Frame width
+    pub width: u32,
+    /// Frame height
+    pub height: u32,
+    /// Whether this is a keyframe
+    pub is_keyframe: bool,
+    /// Frame number
+    pub frame_number: u64,
+    /// Timestamp in milliseconds
+    pub timestamp: u64,
+    /// Local stream the frame belongs to, returned with its ack (0 for frames
+    /// of other peers)
+    #[serde(default)]
+    pub stream_id: u64,
+}
+
+impl AppEvent for EncodedFrameData {
+    const NAME: &'static str = "screen-frame";
+}
+
+/// Presenter state of our screen share, relayed to viewers next to the frames
+#[cfg(feature = "screen")]
+impl AppEvent for PresenterHints {
+    const NAME: &'static str = "screen-presenter-hints";
+}
+
+/// Game mode switched: a fullscreen app took or left the foreground
+#[cfg(feature = "screen")]
+#[derive(Debug, Clone, Serialize)]
+pub struct GameModeChangedEvent {
+    pub active: bool,
+    /// Fullscreen app the reductions are applied for
+    pub app_name: Option<String>,
+}
+
+#[cfg(feature = "screen")]
+impl AppEvent for GameModeChangedEvent {
+    const NAME: &'static str = "game-mode-changed";
+}
+
+/// The call recording was finished
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFinishedEvent {
+    #[serde(flatten)]
+    pub summary: RecordingSummary,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for RecordingFinishedEvent {
+    const NAME: &'static str = "recording-finished";
+}
+
+/// The call recording stopped on an error (the file has what was written)
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFailedEvent {
+    pub error: String,
+}
+
+#[cfg(feature = "audio")]
+impl AppEvent for RecordingFailedEvent {
+    const NAME: &'static str = "recording-failed";
+}
+
+/// A presenter's frame, sent to their pop-out window only
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenterFrameEvent {
+    pub presenter_id: String,
+    #[serde(flatten)]
+    pub frame: EncodedFrameData,
+}
+
+impl AppEvent for PresenterFrameEvent {
+    const NAME: &'static str = "presenter-frame";
+}
+
+/// The active speaker's frame, sent to the picture-in-picture window only
+#[derive(Debug, Clone, Serialize)]
+pub struct PipFrameEvent {
+    pub peer_id: String,
+    #[serde(flatten)]
+    pub frame: EncodedFrameData,
+}
+
+impl AppEvent for PipFrameEvent {
+    const NAME: &'static str = "pip-frame";
+}
+
+/// The picture-in-picture window opened or closed (the viewer only pushes
+/// frames while it is open)
+#[derive(Debug, Clone, Serialize)]
+pub struct PipStateEvent {
+    pub open: bool,
+}
+
+impl AppEvent for PipStateEvent {
+    const NAME: &'static str = "pip-state-changed";
+}
+
+// ============ ROOM ============
+
+/// A peer moved to another voice channel
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerChannelEvent {
+    pub room_code: String,
+    pub peer_id: String,
+    pub channel: String,
+}
+
+impl AppEvent for PeerChannelEvent {
+    const NAME: &'static str = "peer-channel-changed";
+}
+
+/// The host changed the audio policy of a room
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomPolicyEvent {
+    pub room_code: String,
+    pub policy: AudioPolicy,
+}
+
+impl AppEvent for RoomPolicyEvent {
+    const NAME: &'static str = "room-policy-changed";
+}
+
+/// The host-synced roster changed
+#[derive(Debug, Clone, Serialize)]
+pub struct RosterUpdatedEvent {
+    pub peers: Vec<Peer>,
+}
+
+impl AppEvent for RosterUpdatedEvent {
+    const NAME: &'static str = "roster-updated";
+}
+
+/// A chat command ran, here or on a peer (`line` is the line to show)
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatSystemMessageEvent {
+    pub room_code: String,
+    #[serde(flatten)]
+    pub message: SystemMessage,
+    pub line: String,
+}
+
+impl AppEvent for ChatSystemMessageEvent {
+    const NAME: &'static str = "chat-system-message";
+}
+
+/// An invite code reached its expiry time
+#[derive(Debug, Clone, Serialize)]
+pub struct InviteExpiredEvent {
+    pub code: String,
+}
+
+impl AppEvent for InviteExpiredEvent {
+    const NAME: &'static str = "invite-expired";
+}
+
+/// A room code from the command line or an invite link was joined (or failed to)
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoJoinEvent {
+    pub code: String,
+    /// Set when the join succeeded
+    pub info: Option<ServerInfo>,
+    /// Localized reason when it failed
+    pub error: Option<String>,
+}
+
+impl AppEvent for AutoJoinEvent {
+    const NAME: &'static str = "auto-join";
+}
+
+/// The router forwarded the hosting port, or stopped doing so
+#[derive(Debug, Clone, Serialize)]
+pub struct PortMappingChangedEvent {
+    #[serde(flatten)]
+    pub status: PortMappingStatus,
+}
+
+impl AppEvent for PortMappingChangedEvent {
+    const NAME: &'static str = "port-mapping-changed";
+}
+
+/// The session moved to another phase
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStateEvent {
+    pub from: SessionPhase,
+    pub to: SessionPhase,
+}
+
+impl AppEvent for SessionStateEvent {
+    const NAME: &'static str = "session-state-changed";
+}
+
+// ============ PRESENCE ============
+
+/// The local user went away or came back
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceEvent {
+    /// None for the local user
+    pub peer_id: Option<String>,
+    pub away: bool,
+}
+
+impl AppEvent for PresenceEvent {
+    const NAME: &'static str = "presence-changed";
+}
+
+/// A peer went away or came back (same payload as `PresenceEvent`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct PeerPresenceEvent(pub PresenceEvent);
+
+impl AppEvent for PeerPresenceEvent {
+    const NAME: &'static str = "peer-presence-changed";
+}
+
+// ============ CALL STATS ============
+
+/// Live stats of the connection with one peer
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerCallStats {
+    pub peer_id: String,
+    pub rtt_ms: Option<f64>,
+    pub send_kbps: f64,
+    pub recv_kbps: f64,
+}
+
+/// Live call stats, sent several times a second to the stats overlay only
+#[derive(Debug, Clone, Serialize)]
+pub struct CallStatsEvent {
+    /// Totals over every peer connection
+    pub send_kbps: f64,
+    pub recv_kbps: f64,
+    /// Worst round-trip time among the peers
+    pub max_rtt_ms: Option<f64>,
+    /// Frames actually sent while sharing the screen, None when not sharing
+    pub screen_fps: Option<f64>,
+    pub screen_kbps: Option<f64>,
+    /// Participants speaking right now ("local" for us)
+    pub speaking: Vec<String>,
+    pub peers: Vec<PeerCallStats>,
+}
+
+impl AppEvent for CallStatsEvent {
+    const NAME: &'static str = "call-stats";
+}
+
+/// The quality level of the link with a peer changed
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerQualityChangedEvent {
+    #[serde(flatten)]
+    pub report: QualityReport,
+}
+
+impl AppEvent for PeerQualityChangedEvent {
+    const NAME: &'static str = "peer-quality-changed";
+}
+
+// ============ APP ============
+
+/// A backend thread or task panicked
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendCrashEvent {
+    /// Name of the thread that panicked (e.g. an audio callback or a tokio worker)
+    pub thread: String,
+    pub message: String,
+    /// Source location of the panic
+    pub location: Option<String>,
+    /// Crash file with the full backtrace (None in guest mode or if writing failed)
+    pub crash_file: Option<String>,
+}
+
+impl AppEvent for BackendCrashEvent {
+    const NAME: &'static str = "backend-crash";
+}
+
+/// "Check for updates" was chosen in the menu
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckForUpdatesEvent {}
+
+impl AppEvent for CheckForUpdatesEvent {
+    const NAME: &'static str = "check-for-updates";
+}
+
+/// Update download progress
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateProgressEvent {
+    /// Bytes downloaded so far
+    pub downloaded: u64,
+    /// Total size, when the server sent it
+    pub total: Option<u64>,
+}
+
+impl AppEvent for UpdateProgressEvent {
+    const NAME: &'static str = "update-download-progress";
+}
+
+/// Tray actions that need the frontend (it owns the peer connections and viewer window)
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    StopScreenShare,
+    Disconnect,
+}
+
+/// A tray menu item was chosen
+#[derive(Debug, Clone, Serialize)]
+pub struct TrayActionEvent {
+    pub action: TrayAction,
+}
+
+impl AppEvent for TrayActionEvent {
+    const NAME: &'static str = "tray-action";
+}
+
+/// Call duration, every second while the call timer runs
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTimerTickEvent {
+    pub elapsed_secs: u64,
+    /// Time left before the limit, when one is set
+    pub remaining_secs: Option<u64>,
+}
+
+impl AppEvent for CallTimerTickEvent {
+    const NAME: &'static str = "call-timer-tick";
+}
+
+/// The call reached a warning threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTimerWarningEvent {
+    pub minutes: u32,
+    pub remaining_secs: Option<u64>,
+}
+
+impl AppEvent for CallTimerWarningEvent {
+    const NAME: &'static str = "call-timer-warning";
+}
+
+/// The call time limit was reached: the frontend applies `action`
+#[derive(Debug, Clone, Serialize)]
+pub struct CallTimerExpiredEvent {
+    pub action: LimitAction,
+}
+
+impl AppEvent for CallTimerExpiredEvent {
+    const NAME: &'static str = "call-timer-expired";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_flattens_payload() {
+        let event = InviteExpiredEvent {
+            code: "ABC123".to_string(),
+        };
+        let json = serde_json::to_value(Envelope {
+            version: InviteExpiredEvent::VERSION,
+            payload: &event,
+        })
+        .unwrap();
+
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["code"], "ABC123");
+    }
+}
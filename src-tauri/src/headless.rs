@@ -0,0 +1,261 @@
+//! Headless soak test
+//! Runs the native stack without a webview: hosts a room, connects synthetic peers
+//! in-process (offers are auto-accepted), loops synthetic audio between them and
+//! logs stats, for overnight stability runs and CI.
+//!
+//! Usage: `hydrowland --headless [--peers N] [--duration SECS] [--stats-interval SECS]`
+//! Runs in guest mode (nothing persisted, logs on stderr only) and exits with 1
+//! when audio stops flowing.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::audio::{OpusDecoder, OpusEncoder, SAMPLES_PER_FRAME, SAMPLE_RATE};
+use crate::error::{AppError, AppResult};
+use crate::server::{Peer, ServerState};
+use crate::webrtc::{loopback, AudioMeshManager};
+
+const HOST_ID: &str = "soak-host";
+
+/// Soak test parameters, read from the command line
+#[derive(Debug, Clone)]
+pub struct HeadlessConfig {
+    /// Synthetic peers connected to the host
+    pub peers: usize,
+    /// Stop after this long (None = run until killed)
+    pub duration: Option<Duration>,
+    pub stats_interval: Duration,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            peers: 2,
+            duration: None,
+            stats_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl HeadlessConfig {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> AppResult<Self> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--peers" => config.peers = parse_value(&arg, args.next())?,
+                "--duration" => {
+                    config.duration = Some(Duration::from_secs(parse_value(&arg, args.next())?))
+                }
+                "--stats-interval" => {
+                    config.stats_interval = Duration::from_secs(parse_value(&arg, args.next())?)
+                }
+                _ => {}
+            }
+        }
+        if config.peers == 0 {
+            return Err(AppError::InvalidArgument("--peers must be at least 1".to_string()));
+        }
+        // tokio::time::interval panics on a zero period
+        if config.stats_interval.is_zero() {
+            return Err(AppError::InvalidArgument(
+                "--stats-interval must be at least 1".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> AppResult<T> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| AppError::InvalidArgument(format!("{} expects a number", flag)))
+}
+
+#[derive(Default)]
+struct SoakStats {
+    packets_sent: AtomicU64,
+    send_errors: AtomicU64,
+    packets_received: AtomicU64,
+    decode_errors: AtomicU64,
+}
+
+/// Run the soak test (blocks until the duration elapses)
+pub fn run(config: HeadlessConfig) -> AppResult<()> {
+    tauri::async_runtime::block_on(soak(config))
+}
+
+async fn soak(config: HeadlessConfig) -> AppResult<()> {
+    tracing::info!("Headless soak test: {:?}", config);
+
+    let server = ServerState::new();
+    let info = server.start_hosting(HOST_ID.to_string())?;
+    tracing::info!("Hosting room {}", info.code);
+
+    let stats = Arc::new(SoakStats::default());
+    let host = mesh(HOST_ID, &stats);
+
+    // Connect every synthetic peer to the host, auto-accepting the offers
+    let mut peers = Vec::with_capacity(config.peers);
+    for i in 0..config.peers {
+        let peer_id = format!("soak-peer-{}", i + 1);
+        let peer = mesh(&peer_id, &stats);
+
+        loopback::connect(&peer, &peer_id, &host, HOST_ID).await?;
+
+        server.add_peer(Peer {
+            id: peer_id.clone(),
+            username: peer_id.clone(),
+            is_host: false,
+        });
+        tracing::info!("{} connected", peer_id);
+        peers.push(peer);
+    }
+
+    // Everyone talks: one tone per participant
+    let mut speakers = vec![spawn_speaker(host.clone(), 220.0, stats.clone())];
+    for (i, peer) in peers.iter().enumerate() {
+        speakers.push(spawn_speaker(peer.clone(), 330.0 + 110.0 * i as f32, stats.clone()));
+    }
+
+    let start = Instant::now();
+    let mut last_received = 0;
+    let mut ticker = tokio::time::interval(config.stats_interval);
+    ticker.tick().await;
+    let result = loop {
+        ticker.tick().await;
+
+        let received = stats.packets_received.load(Ordering::Relaxed);
+        tracing::info!(
+            "Soak {:?}: peers {}/{}, sent {} ({} errors), received {} ({} decode errors)",
+            start.elapsed(),
+            host.peer_count(),
+            config.peers,
+            stats.packets_sent.load(Ordering::Relaxed),
+            stats.send_errors.load(Ordering::Relaxed),
+            received,
+            stats.decode_errors.load(Ordering::Relaxed),
+        );
+
+        if received == last_received {
+            break Err(AppError::Internal(format!(
+                "No audio received during the last {:?}",
+                config.stats_interval
+            )));
+        }
+        last_received = received;
+
+        if config.duration.is_some_and(|d| start.elapsed() >= d) {
+            break Ok(());
+        }
+    };
+
+    for speaker in speakers {
+        speaker.abort();
+    }
+    host.close_all();
+    for peer in &peers {
+        peer.close_all();
+    }
+    server.disconnect()?;
+
+    match &result {
+        Ok(()) => tracing::info!("Soak test passed after {:?}", start.elapsed()),
+        Err(e) => tracing::error!("Soak test failed after {:?}: {}", start.elapsed(), e),
+    }
+    result
+}
+
+/// Audio-enabled mesh whose received packets are decoded and counted
+fn mesh(username: &str, stats: &Arc<SoakStats>) -> Arc<AudioMeshManager> {
+    let manager = Arc::new(AudioMeshManager::new());
+    manager.set_username(username.to_string());
+    manager.enable_local_audio(true);
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<(String, Vec<u8>)>();
+    manager.set_audio_receiver(tx);
+
+    let stats = stats.clone();
+    tokio::spawn(async move {
+        let mut decoders = HashMap::new();
+        while let Some((peer_id, data)) = rx.recv().await {
+            stats.packets_received.fetch_add(1, Ordering::Relaxed);
+            let decoder = match decoders.entry(peer_id) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => match OpusDecoder::new() {
+                    Ok(decoder) => e.insert(decoder),
+                    Err(_) => {
+                        stats.decode_errors.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                },
+            };
+            if decoder.decode(&data).is_err() {
+                stats.decode_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    });
+
+    manager
+}
+
+/// Broadcast a sine tone in 20ms Opus frames, at real-time pace
+fn spawn_speaker(
+    manager: Arc<AudioMeshManager>,
+    frequency: f32,
+    stats: Arc<SoakStats>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut encoder = match OpusEncoder::new() {
+            Ok(encoder) => encoder,
+            Err(e) => {
+                tracing::error!("Failed to create encoder: {}", e);
+                return;
+            }
+        };
+
+        let step = 2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE as f32;
+        let mut phase = 0.0f32;
+        let mut frame = vec![0.0f32; SAMPLES_PER_FRAME];
+        let mut ticker = tokio::time::interval(Duration::from_millis(20));
+
+        loop {
+            ticker.tick().await;
+            for sample in frame.iter_mut() {
+                *sample = 0.3 * phase.sin();
+                phase = (phase + step) % (2.0 * std::f32::consts::PI);
+            }
+
+            let sent = match encoder.encode(&frame) {
+                Ok(packet) => manager.broadcast_audio(&packet).await.is_ok(),
+                Err(_) => false,
+            };
+            if sent {
+                stats.packets_sent.fetch_add(1, Ordering::Relaxed);
+            } else {
+                stats.send_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn zero_stats_interval_is_rejected() {
+        let config = HeadlessConfig::from_args(args(&["--headless", "--stats-interval", "5"]));
+        assert_eq!(config.unwrap().stats_interval, Duration::from_secs(5));
+        assert!(HeadlessConfig::from_args(args(&["--stats-interval", "0"])).is_err());
+        assert!(HeadlessConfig::from_args(args(&["--peers", "0"])).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! Headset buttons
+//! USB and Bluetooth headsets expose their call buttons on the HID telephony
+//! usage page. The report descriptor says where the Phone Mute and Hook Switch
+//! bits sit in the input reports; a thread per headset reads the reports and
+//! calls back on every press. hidapi is blocking, so this runs on plain threads.
+
+use hidapi::{HidApi, HidDevice};
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+/// HID usage page of telephony devices
+const TELEPHONY_PAGE: u32 = 0x0B;
+
+/// Telephony usages of the buttons we follow
+const HOOK_SWITCH: u32 = 0x20;
+const PHONE_MUTE: u32 = 0x2F;
+
+/// Time between two looks for newly plugged headsets
+const SCAN_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Longest wait for a report, so a reader notices it should stop
+const READ_TIMEOUT_MS: i32 = 500;
+
+/// Longest report descriptor (HID_MAX_DESCRIPTOR_SIZE)
+const MAX_DESCRIPTOR_SIZE: usize = 4096;
+
+/// A headset button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    /// Dedicated mute button, reported while held
+    Mute,
+    /// Call button: the headset flips the hook state on each press
+    Hook,
+}
+
+/// Where a button's bit sits in the input reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonField {
+    pub report_id: u8,
+    /// Bit offset in the report, after the report ID byte
+    pub bit: usize,
+}
+
+/// Telephony buttons found in a report descriptor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TelephonyButtons {
+    pub mute: Option<ButtonField>,
+    pub hook: Option<ButtonField>,
+    /// Reports start with a report ID byte
+    pub report_ids: bool,
+}
+
+impl TelephonyButtons {
+    fn is_empty(&self) -> bool {
+        self.mute.is_none() && self.hook.is_none()
+    }
+
+    fn bit(&self, report: &[u8], field: ButtonField) -> Option<bool> {
+        let data = if self.report_ids {
+            let (&id, data) = report.split_first()?;
+            if id != field.report_id {
+                return None;
+            }
+            data
+        } else {
+            report
+        };
+        let byte = data.get(field.bit / 8)?;
+        Some(byte & (1 << (field.bit % 8)) != 0)
+    }
+}
+
+/// Find the mute and hook switch bits in a HID report descriptor
+/// Only short items matter here: global state (usage page, report size, count
+/// and ID), local usages, and the Input main items that consume bits.
+pub fn parse_descriptor(descriptor: &[u8]) -> TelephonyButtons {
+    let mut buttons = TelephonyButtons::default();
+    let mut usage_page = 0u32;
+    let mut report_size = 0usize;
+    let mut report_count = 0usize;
+    let mut report_id = 0u8;
+    // Input bits used so far, per report ID
+    let mut offsets = [0usize; 256];
+    // Local usages: (value, carries its own page)
+    let mut usages: Vec<(u32, bool)> = Vec::new();
+    let mut usage_range: (Option<u32>, Option<u32>) = (None, None);
+
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        // Long item: skipped whole
+        if prefix == 0xFE {
+            let size = descriptor.get(i + 1).copied().unwrap_or(0) as usize;
+            i += 3 + size;
+            continue;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let end = (i + 1 + size).min(descriptor.len());
+        let value = descriptor[i + 1..end]
+            .iter()
+            .rev()
+            .fold(0u32, |value, &byte| (value << 8) | byte as u32);
+        let kind = (prefix >> 2) & 0x03;
+        let tag = prefix >> 4;
+
+        match (kind, tag) {
+            // Global items
+            (1, 0x0) => usage_page = value,
+            (1, 0x7) => report_size = value as usize,
+            (1, 0x8) => {
+                report_id = value as u8;
+                buttons.report_ids = true;
+            }
+            (1, 0x9) => report_count = value as usize,
+            // Local items
+            (2, 0x0) => usages.push((value, size == 4)),
+            (2, 0x1) => usage_range.0 = Some(value),
+            (2, 0x2) => usage_range.1 = Some(value),
+            // Input
+            (0, 0x8) => {
+                let constant = value & 0x01 != 0;
+                let variable = value & 0x02 != 0;
+                let offset = offsets[report_id as usize];
+                if !constant && variable {
+                    let full = |(usage, extended): (u32, bool)| {
+                        if extended {
+                            usage
+                        } else {
+                            (usage_page << 16) | usage
+                        }
+                    };
+                    let mut resolved: Vec<u32> = usages.iter().copied().map(full).collect();
+                    if let (Some(min), Some(max)) = usage_range {
+                        resolved.extend((min..=max.min(min + 0xFF)).map(|u| full((u, false))));
+                    }
+                    for n in 0..report_count.min(256) {
+                        // The last usage repeats for the remaining fields
+                        let Some(&usage) = resolved.get(n).or(resolved.last()) else {
+                            break;
+                        };
+                        let field = ButtonField {
+                            report_id,
+                            bit: offset + n * report_size,
+                        };
+                        if usage == (TELEPHONY_PAGE << 16) | PHONE_MUTE {
+                            buttons.mute.get_or_insert(field);
+                        } else if usage == (TELEPHONY_PAGE << 16) | HOOK_SWITCH {
+                            buttons.hook.get_or_insert(field);
+                        }
+                    }
+                }
+                offsets[report_id as usize] = offset + report_size * report_count;
+                usages.clear();
+                usage_range = (None, None);
+            }
+            // Other main items (output, feature, collections) end the locals
+            (0, _) => {
+                usages.clear();
+                usage_range = (None, None);
+            }
+            _ => {}
+        }
+        i += 1 + size;
+    }
+    buttons
+}
+
+/// Watches every telephony headset, calling `on_press` on each button press
+/// Stops once `running` is cleared.
+pub fn spawn_watcher(
+    running: Arc<AtomicBool>,
+    headsets: Arc<Mutex<Vec<String>>>,
+    on_press: impl Fn(Button) + Send + Sync + 'static,
+) {
+    let on_press = Arc::new(on_press);
+    let spawned = std::thread::Builder::new()
+        .name("headset-hid".to_string())
+        .spawn(move || {
+            let mut api = match HidApi::new() {
+                Ok(api) => api,
+                Err(e) => {
+                    tracing::warn!("Headset buttons unavailable: {}", e);
+                    return;
+                }
+            };
+            // Paths of the headsets being read
+            let open: Arc<Mutex<HashSet<CString>>> = Arc::default();
+            while running.load(Ordering::SeqCst) {
+                if let Err(e) = api.refresh_devices() {
+                    tracing::debug!("Failed to list HID devices: {}", e);
+                }
+                for info in api.device_list() {
+                    if info.usage_page() as u32 != TELEPHONY_PAGE
+                        || open.lock().contains(info.path())
+                    {
+                        continue;
+                    }
+                    let name = info
+                        .product_string()
+                        .unwrap_or("Unknown headset")
+                        .to_string();
+                    let device = match info.open_device(&api) {
+                        Ok(device) => device,
+                        Err(e) => {
+                            tracing::debug!("Failed to open headset {}: {}", name, e);
+                            continue;
+                        }
+                    };
+                    let buttons = match read_buttons(&device) {
+                        Some(buttons) if !buttons.is_empty() => buttons,
+                        _ => continue,
+                    };
+                    tracing::info!("Following the buttons of {} ({:?})", name, buttons);
+
+                    let path = info.path().to_owned();
+                    open.lock().insert(path.clone());
+                    headsets.lock().push(name.clone());
+                    let (running, open, headsets, on_press) = (
+                        running.clone(),
+                        open.clone(),
+                        headsets.clone(),
+                        on_press.clone(),
+                    );
+                    std::thread::spawn(move || {
+                        read_reports(&device, buttons, &running, &*on_press);
+                        tracing::info!("Stopped following {}", name);
+                        open.lock().remove(&path);
+                        let mut headsets = headsets.lock();
+                        if let Some(i) = headsets.iter().position(|h| *h == name) {
+                            headsets.remove(i);
+                        }
+                    });
+                }
+                std::thread::sleep(SCAN_INTERVAL);
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!("Failed to start the headset watcher: {}", e);
+    }
+}
+
+fn read_buttons(device: &HidDevice) -> Option<TelephonyButtons> {
+    let mut descriptor = vec![0u8; MAX_DESCRIPTOR_SIZE];
+    match device.get_report_descriptor(&mut descriptor) {
+        Ok(len) => Some(parse_descriptor(&descriptor[..len])),
+        Err(e) => {
+            tracing::debug!("Failed to read a headset descriptor: {}", e);
+            None
+        }
+    }
+}
+
+/// Read reports until the headset goes away or `running` is cleared
+fn read_reports(
+    device: &HidDevice,
+    buttons: TelephonyButtons,
+    running: &AtomicBool,
+    on_press: impl Fn(Button),
+) {
+    let mut report = [0u8; 64];
+    let (mut mute, mut hook) = (None, None);
+    while running.load(Ordering::SeqCst) {
+        let len = match device.read_timeout(&mut report, READ_TIMEOUT_MS) {
+            Ok(0) => continue,
+            Ok(len) => len,
+            // Unplugged
+            Err(_) => return,
+        };
+        let report = &report[..len];
+
+        if let Some(pressed) = buttons.mute.and_then(|field| buttons.bit(report, field)) {
+            if pressed && mute != Some(true) {
+                on_press(Button::Mute);
+            }
+            mute = Some(pressed);
+        }
+        if let Some(off_hook) = buttons.hook.and_then(|field| buttons.bit(report, field)) {
+            // The first report only tells where the hook stands: a headset
+            // already off hook when plugged in is not a press
+            if hook.is_some_and(|was_off_hook| was_off_hook != off_hook) {
+                on_press(Button::Hook);
+            }
+            hook = Some(off_hook);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_telephony_buttons() {
+        #[rustfmt::skip]
+        let descriptor = [
+            0x05, 0x0B,       // Usage Page (Telephony)
+            0x09, 0x05,       // Usage (Headset)
+            0xA1, 0x01,       // Collection (Application)
+            0x85, 0x02,       //   Report ID (2)
+            0x15, 0x00,       //   Logical Minimum (0)
+            0x25, 0x01,       //   Logical Maximum (1)
+            0x09, 0x20,       //   Usage (Hook Switch)
+            0x09, 0x2F,       //   Usage (Phone Mute)
+            0x75, 0x01,       //   Report Size (1)
+            0x95, 0x02,       //   Report Count (2)
+            0x81, 0x06,       //   Input (Data, Variable, Relative)
+            0x95, 0x06,       //   Report Count (6)
+            0x81, 0x03,       //   Input (Constant): padding
+            0xC0,             // End Collection
+        ];
+        let buttons = parse_descriptor(&descriptor);
+        assert!(buttons.report_ids);
+        assert_eq!(
+            buttons.hook,
+            Some(ButtonField {
+                report_id: 2,
+                bit: 0
+            })
+        );
+        let mute = buttons.mute.unwrap();
+        assert_eq!(mute.bit, 1);
+
+        assert_eq!(buttons.bit(&[2, 0b10], mute), Some(true));
+        assert_eq!(buttons.bit(&[2, 0b01], mute), Some(false));
+        // Another report
+        assert_eq!(buttons.bit(&[3, 0b10], mute), None);
+    }
+}
@@ -0,0 +1,318 @@
+//! Backend strings
+//! Error descriptions, menu labels and notification texts in the locale chosen
+//! by the frontend (French until it says otherwise, like the rest of the UI).
+//! The choice is persisted so the menus are right from the next launch.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const LOCALE_FILE: &str = "locale.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Locale {
+    /// Locale for a BCP 47 tag ("fr", "fr-CA", "en-US"...), English when unsupported
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("fr") {
+            Locale::Fr
+        } else {
+            Locale::En
+        }
+    }
+}
+
+static LOCALE: RwLock<Locale> = parking_lot::const_rwlock(Locale::Fr);
+
+/// Restore the persisted locale (call once at startup)
+pub fn init() {
+    if let Some(locale) = storage::load_json(LOCALE_FILE) {
+        *LOCALE.write() = locale;
+    }
+}
+
+pub fn locale() -> Locale {
+    *LOCALE.read()
+}
+
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write() = locale;
+    if let Err(e) = storage::save_json(LOCALE_FILE, &locale) {
+        tracing::warn!("Failed to save locale: {}", e);
+    }
+}
+
+/// A backend string shown to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    // Menus
+    MenuFile,
+    MenuEdit,
+    MenuWindow,
+    CheckForUpdates,
+    About,
+    Hide,
+    HideOthers,
+    ShowAll,
+    Quit,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+    Minimize,
+    Maximize,
+    CloseWindow,
+    // Tray
+    TrayNotConnected,
+    /// `{code}`
+    TrayHosting,
+    /// `{code}`
+    TrayConnected,
+    TrayMute,
+    TrayDeafen,
+    TrayStopShare,
+    TrayDisconnect,
+    TrayShow,
+    TooltipDeafened,
+    TooltipMuted,
+    // Notifications
+    PeerJoinedTitle,
+    /// `{username}`
+    PeerJoinedBody,
+    // Power management
+    WakeLockReason,
+    // Chat read aloud
+    /// `{username}`, `{message}`
+    ChatMessageSpoken,
+    // Chat system messages
+    /// `{actor}`, `{target}`
+    ChatMuted,
+    /// `{actor}`, `{target}`
+    ChatKicked,
+    /// `{actor}`, `{topic}`
+    ChatTopic,
+    /// `{actor}`
+    ChatTopicCleared,
+    // Windows
+    StatsOverlayTitle,
+    /// `{username}`
+    ScreenPopoutTitle,
+    PipTitle,
+    // Audio devices
+    /// `{attempt}`, `{max}`
+    MicRetry,
+    /// `{attempt}`, `{max}`
+    OutputRetry,
+}
+
+/// The string for `text` in the current locale
+pub fn t(text: Text) -> &'static str {
+    translate(text, locale())
+}
+
+/// Same as `t`, with `{name}` placeholders replaced
+pub fn tf(text: Text, args: &[(&str, &str)]) -> String {
+    args.iter().fold(t(text).to_string(), |s, (name, value)| {
+        s.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+fn translate(text: Text, locale: Locale) -> &'static str {
+    use Text::*;
+    match locale {
+        Locale::Fr => match text {
+            MenuFile => "Fichier",
+            MenuEdit => "Édition",
+            MenuWindow => "Fenêtre",
+            CheckForUpdates => "Rechercher les mises à jour...",
+            About => "À propos de HydrowLand",
+            Hide => "Masquer HydrowLand",
+            HideOthers => "Masquer les autres",
+            ShowAll => "Tout afficher",
+            Quit => "Quitter",
+            Undo => "Annuler",
+            Redo => "Rétablir",
+            Cut => "Couper",
+            Copy => "Copier",
+            Paste => "Coller",
+            SelectAll => "Tout sélectionner",
+            Minimize => "Réduire",
+            Maximize => "Agrandir",
+            CloseWindow => "Fermer",
+            TrayNotConnected => "Non connecté",
+            TrayHosting => "Hébergement : {code}",
+            TrayConnected => "Connecté : {code}",
+            TrayMute => "Couper le micro",
+            TrayDeafen => "Couper le son",
+            TrayStopShare => "Arrêter le partage d'écran",
+            TrayDisconnect => "Se déconnecter",
+            TrayShow => "Afficher HydrowLand",
+            TooltipDeafened => " (son coupé)",
+            TooltipMuted => " (micro coupé)",
+            PeerJoinedTitle => "Nouvelle connexion",
+            PeerJoinedBody => "{username} a rejoint le serveur",
+            WakeLockReason => "Appel ou partage d'écran en cours",
+            ChatMessageSpoken => "{username} dit : {message}",
+            ChatMuted => "{actor} a coupé le micro de {target}",
+            ChatKicked => "{actor} a expulsé {target}",
+            ChatTopic => "{actor} a changé le sujet : {topic}",
+            ChatTopicCleared => "{actor} a retiré le sujet",
+            StatsOverlayTitle => "Statistiques de l'appel",
+            ScreenPopoutTitle => "Écran de {username}",
+            PipTitle => "Intervenant actif",
+            MicRetry => "Micro indisponible, nouvelle tentative ({attempt}/{max})…",
+            OutputRetry => "Sortie audio indisponible, nouvelle tentative ({attempt}/{max})…",
+        },
+        Locale::En => match text {
+            MenuFile => "File",
+            MenuEdit => "Edit",
+            MenuWindow => "Window",
+            CheckForUpdates => "Check for Updates...",
+            About => "About HydrowLand",
+            Hide => "Hide HydrowLand",
+            HideOthers => "Hide Others",
+            ShowAll => "Show All",
+            Quit => "Quit",
+            Undo => "Undo",
+            Redo => "Redo",
+            Cut => "Cut",
+            Copy => "Copy",
+            Paste => "Paste",
+            SelectAll => "Select All",
+            Minimize => "Minimize",
+            Maximize => "Zoom",
+            CloseWindow => "Close",
+            TrayNotConnected => "Not connected",
+            TrayHosting => "Hosting: {code}",
+            TrayConnected => "Connected: {code}",
+            TrayMute => "Mute microphone",
+            TrayDeafen => "Deafen",
+            TrayStopShare => "Stop screen sharing",
+            TrayDisconnect => "Disconnect",
+            TrayShow => "Show HydrowLand",
+            TooltipDeafened => " (deafened)",
+            TooltipMuted => " (muted)",
+            PeerJoinedTitle => "New connection",
+            PeerJoinedBody => "{username} joined the server",
+            WakeLockReason => "Call or screen share in progress",
+            ChatMessageSpoken => "{username} says: {message}",
+            ChatMuted => "{actor} muted {target}",
+            ChatKicked => "{actor} kicked {target}",
+            ChatTopic => "{actor} changed the topic: {topic}",
+            ChatTopicCleared => "{actor} cleared the topic",
+            StatsOverlayTitle => "Call stats",
+            ScreenPopoutTitle => "{username}'s screen",
+            PipTitle => "Active speaker",
+            MicRetry => "Microphone unavailable, retrying ({attempt}/{max})…",
+            OutputRetry => "Audio output unavailable, retrying ({attempt}/{max})…",
+        },
+    }
+}
+
+/// Description of an `AppError` code in the current locale
+pub fn error_description(code: &str) -> Option<&'static str> {
+    let (fr, en) = match code {
+        "permission_denied" => ("Autorisation refusée", "Permission denied"),
+        "device_not_found" => ("Périphérique introuvable", "Device not found"),
+        "device_unavailable" => ("Périphérique indisponible", "Device unavailable"),
+        "audio_error" => ("Erreur audio", "Audio error"),
+        "codec_error" => ("Erreur du codec", "Codec error"),
+        "video_error" => ("Erreur vidéo", "Video error"),
+        "webrtc_error" => ("Erreur de connexion", "Connection error"),
+        "peer_not_found" => ("Pair introuvable", "Peer not found"),
+        "not_connected" => ("Non connecté", "Not connected"),
+        "invalid_argument" => ("Paramètre invalide", "Invalid argument"),
+        "already_active" => ("Déjà en cours", "Already running"),
+        "storage_error" => ("Erreur d'enregistrement", "Storage error"),
+        "update_error" => ("Erreur de mise à jour", "Update error"),
+        "timeout" => ("Délai d'attente dépassé", "Timed out"),
+        "no_source_selected" => ("Aucune source sélectionnée", "No source selected"),
+        "source_not_found" => ("Source introuvable", "Source not found"),
+        "screen_capture_error" => ("Erreur de capture d'écran", "Screen capture error"),
+        "server_not_running" => ("Aucun serveur lancé", "Server not running"),
+        "already_hosting" => ("Vous hébergez déjà un serveur", "Already hosting"),
+        "already_connected" => (
+            "Déjà connecté à un serveur",
+            "Already connected to a server",
+        ),
+        "invalid_code" => ("Code invalide", "Invalid code"),
+        "code_expired" => ("Invitation expirée", "Invite code expired"),
+        "invite_not_found" => ("Invitation introuvable", "Invite not found"),
+        "room_not_found" => ("Salon introuvable", "Room not found"),
+        "room_full" => ("Salon complet", "Room is full"),
+        "already_in_room" => ("Déjà dans un salon", "Already in a room"),
+        "not_in_room" => ("Pas dans un salon", "Not in a room"),
+        "not_banned" => ("Ce pair n'est pas banni", "Peer is not banned"),
+        "already_banned" => ("Ce pair est déjà banni", "Peer is already banned"),
+        "unknown_identity" => ("Identité du pair inconnue", "Unknown peer identity"),
+        "illegal_session_transition" => (
+            "Action impossible dans l'état actuel de la session",
+            "Not possible in the current session state",
+        ),
+        "sdp_empty" => ("Aucun code de connexion collé", "No connection code pasted"),
+        "sdp_not_base64" => (
+            "Ce texte n'est pas un code de connexion",
+            "This text is not a connection code",
+        ),
+        "sdp_truncated" => (
+            "Code de connexion incomplet : copiez-le en entier",
+            "Connection code is incomplete: copy all of it",
+        ),
+        "sdp_malformed" => (
+            "Code de connexion corrompu : demandez-en un nouveau",
+            "Connection code is corrupted: ask for a new one",
+        ),
+        "sdp_wrong_type" => (
+            "Mauvais code : une offre est attendue ici et une réponse là-bas, collez l'autre",
+            "Wrong code: this step expects the other one (offer vs answer)",
+        ),
+        "sdp_incompatible" => (
+            "Code de connexion incompatible : mettez HydrowLand à jour des deux côtés",
+            "Incompatible connection code: update HydrowLand on both sides",
+        ),
+        "plugin_invalid_namespace" => (
+            "Nom d'extension invalide (a-z, 0-9, - et _, 32 caractères au plus)",
+            "Invalid plugin namespace (a-z, 0-9, - and _, up to 32 characters)",
+        ),
+        "plugin_namespace_taken" => (
+            "Ce nom d'extension est déjà utilisé",
+            "Plugin namespace already claimed",
+        ),
+        "plugin_not_claimed" => (
+            "Ce nom d'extension n'a pas été réservé par l'interface",
+            "Plugin namespace not claimed by the interface",
+        ),
+        "internal" => ("Erreur interne", "Internal error"),
+        _ => return None,
+    };
+    Some(match locale() {
+        Locale::Fr => fr,
+        Locale::En => en,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_tags_and_placeholders() {
+        assert_eq!(Locale::from_tag("fr-CA"), Locale::Fr);
+        assert_eq!(Locale::from_tag("en_US"), Locale::En);
+        assert_eq!(Locale::from_tag("de"), Locale::En);
+        assert_eq!(
+            translate(Text::PeerJoinedBody, Locale::En).replace("{username}", "Alice"),
+            "Alice joined the server"
+        );
+    }
+}
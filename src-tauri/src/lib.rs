@@ -1,150 +1,366 @@
+// Un verrou std/parking_lot gardé pendant un `.await` bloque un thread du runtime
+#![deny(clippy::await_holding_lock)]
+
 use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
-use tauri::{Emitter, Manager};
+use tauri::{AppHandle, Manager, RunEvent, Wry};
+
+use crate::i18n::{t, Text};
 
 mod audio;
+mod bandwidth;
+mod bans;
+mod chat_commands;
+mod chat_history;
+mod chat_transcript;
 mod commands;
+mod crash;
+mod deep_link;
+mod error;
+mod event_log;
+mod events;
+#[cfg(feature = "audio")]
+mod headless;
+#[cfg(feature = "audio")]
+mod headset;
+mod i18n;
+#[cfg(feature = "lock-debug")]
+mod lock_debug;
+mod logging;
+#[cfg(debug_assertions)]
+mod netsim;
+mod perf;
+mod plugins;
+mod port_mapping;
+mod power;
+#[cfg(feature = "audio")]
+mod recording;
 mod room;
+#[cfg(feature = "screen")]
 mod screen;
 mod server;
+mod session;
+mod shutdown;
+mod storage;
+mod tray;
+#[cfg(feature = "video")]
 mod video;
 mod webrtc;
 
+pub use bans::BanList;
+pub use chat_transcript::ChatTranscript;
+pub use deep_link::DeepLinkState;
+pub use event_log::EventLog;
+pub use plugins::{ChannelPlugin, PluginContext, PluginError, PluginRegistry};
+pub use power::PowerState;
+#[cfg(feature = "audio")]
 pub use commands::audio::AudioState;
 pub use commands::audio_mesh::AudioMeshState;
+pub use commands::bandwidth::BandwidthState;
+pub use commands::call_timer::CallTimerState;
+#[cfg(feature = "screen")]
+pub use commands::game_mode::GameModeState;
+pub use commands::guard::CommandGuards;
+#[cfg(feature = "audio")]
+pub use commands::headset::HeadsetState;
+#[cfg(feature = "audio")]
+pub use commands::hotkeys::HotkeyState;
+pub use commands::notifications::NotificationState;
+#[cfg(feature = "audio")]
+pub use commands::pip::PipState;
+pub use commands::port_mapping::PortMappingState;
+pub use commands::presence::PresenceState;
+pub use commands::proxy::ProxyState;
+pub use commands::quality::QualityState;
+#[cfg(feature = "screen")]
 pub use commands::screen::ScreenState;
+#[cfg(feature = "screen")]
 pub use commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+pub use commands::sound_scheme::SoundSchemeState;
+#[cfg(feature = "audio")]
 pub use commands::streaming::StreamingState;
+pub use commands::updater::UpdaterState;
+#[cfg(feature = "audio")]
+pub use commands::tts::TtsState;
+#[cfg(feature = "audio")]
+pub use commands::voice_message::VoiceMessageState;
 pub use room::RoomState;
+#[cfg(feature = "screen")]
 pub use screen::ScreenCapture;
 pub use server::ServerState;
+pub use session::SessionState;
 pub use webrtc::{AudioMeshManager, MeshManager, WebRTCManager};
 
+/// Hot paths exposed to the criterion benches in `benches/`
+#[cfg(feature = "bench")]
+pub mod bench {
+    pub use crate::audio::{AudioDenoiser, AudioMixer, OpusDecoder, OpusEncoder, Resampler};
+    pub use crate::audio::{SAMPLES_PER_FRAME, SAMPLE_RATE};
+    pub use crate::video::{EncoderConfig, VideoEncoder, VideoFrame};
+
+    /// RMS used for the level meter and speaking detection
+    pub fn rms(samples: &[f32]) -> f32 {
+        crate::audio::rms(samples)
+    }
+
+    /// Downmix of interleaved device samples to mono
+    pub fn downmix_into(data: &[f32], channels: usize, out: &mut Vec<f32>) {
+        crate::audio::downmix_into(data, channels, out)
+    }
+}
+
 /// Commande de test pour vérifier l'IPC
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Salut {} ! Bienvenue sur HydrowLand", name)
 }
 
+/// Barre de menus dans la langue courante (reconstruite quand elle change)
+pub(crate) fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let check_update = MenuItem::with_id(app, "check_update", t(Text::CheckForUpdates), true, None::<&str>)?;
+    let quit = PredefinedMenuItem::quit(app, Some(t(Text::Quit)))?;
+    let separator = PredefinedMenuItem::separator(app)?;
+
+    let edit_menu = Submenu::with_items(
+        app,
+        t(Text::MenuEdit),
+        true,
+        &[
+            &PredefinedMenuItem::undo(app, Some(t(Text::Undo)))?,
+            &PredefinedMenuItem::redo(app, Some(t(Text::Redo)))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::cut(app, Some(t(Text::Cut)))?,
+            &PredefinedMenuItem::copy(app, Some(t(Text::Copy)))?,
+            &PredefinedMenuItem::paste(app, Some(t(Text::Paste)))?,
+            &PredefinedMenuItem::select_all(app, Some(t(Text::SelectAll)))?,
+        ],
+    )?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let app_menu = Submenu::with_items(
+            app,
+            "HydrowLand",
+            true,
+            &[
+                &PredefinedMenuItem::about(app, Some(t(Text::About)), None)?,
+                &separator,
+                &check_update,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::services(app, None)?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::hide(app, Some(t(Text::Hide)))?,
+                &PredefinedMenuItem::hide_others(app, Some(t(Text::HideOthers)))?,
+                &PredefinedMenuItem::show_all(app, Some(t(Text::ShowAll)))?,
+                &PredefinedMenuItem::separator(app)?,
+                &quit,
+            ],
+        )?;
+
+        let window_menu = Submenu::with_items(
+            app,
+            t(Text::MenuWindow),
+            true,
+            &[
+                &PredefinedMenuItem::minimize(app, Some(t(Text::Minimize)))?,
+                &PredefinedMenuItem::maximize(app, Some(t(Text::Maximize)))?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::close_window(app, Some(t(Text::CloseWindow)))?,
+            ],
+        )?;
+
+        Menu::with_items(app, &[&app_menu, &edit_menu, &window_menu])
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let file_menu = Submenu::with_items(
+            app,
+            t(Text::MenuFile),
+            true,
+            &[
+                &check_update,
+                &separator,
+                &quit,
+            ],
+        )?;
+
+        Menu::with_items(app, &[&file_menu, &edit_menu])
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::fmt::init();
+    run_with_plugins(PluginRegistry::default())
+}
+
+/// Comme `run`, avec des plugins du canal de données fournis par un crate
+/// qui embarque l'application (tableau blanc partagé, synchro de jeu...)
+pub fn run_with_plugins(plugins: PluginRegistry) {
+    // Soak test sans webview : ne touche pas à la config de l'utilisateur
+    let headless = std::env::args().any(|arg| arg == "--headless");
+
+    // Mode invité (machines partagées) : --guest ou HYDROWLAND_GUEST=1
+    if headless
+        || std::env::args().any(|arg| arg == "--guest")
+        || std::env::var("HYDROWLAND_GUEST").is_ok_and(|v| v == "1")
+    {
+        storage::enable_guest_mode();
+    }
+
+    // Après le mode invité : pas de fichier de log en invité
+    logging::init();
+    crash::install();
+    #[cfg(feature = "lock-debug")]
+    lock_debug::install();
+    if storage::is_guest_mode() {
+        tracing::info!("Guest mode enabled: nothing will be persisted");
+    }
+
+    if headless {
+        #[cfg(feature = "audio")]
+        let result = headless::HeadlessConfig::from_args(std::env::args()).and_then(headless::run);
+        // Le soak test fait circuler de l'audio Opus
+        #[cfg(not(feature = "audio"))]
+        let result: error::AppResult<()> = Err(error::AppError::InvalidArgument(
+            "--headless requires the audio feature".to_string(),
+        ));
+        if let Err(e) = result {
+            tracing::error!("Headless run failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    i18n::init();
+    webrtc::timeouts::load();
+    webrtc::network::load();
+    webrtc::ice_servers::load();
+    let ban_list = BanList::load();
 
-    tauri::Builder::default()
+    let builder = tauri::Builder::default()
+        // En premier : un second lancement transmet ses arguments puis quitte
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            tray::show_main_window(app);
+            deep_link::handle_args(app, args);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
-        .setup(|app| {
-            // Create menu
-            let check_update = MenuItem::with_id(app, "check_update", "Rechercher les mises à jour...", true, None::<&str>)?;
-            let quit = PredefinedMenuItem::quit(app, Some("Quitter"))?;
-            let separator = PredefinedMenuItem::separator(app)?;
-
-            #[cfg(target_os = "macos")]
-            {
-                let app_menu = Submenu::with_items(
-                    app,
-                    "HydrowLand",
-                    true,
-                    &[
-                        &PredefinedMenuItem::about(app, Some("À propos de HydrowLand"), None)?,
-                        &separator,
-                        &check_update,
-                        &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::services(app, None)?,
-                        &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::hide(app, Some("Masquer HydrowLand"))?,
-                        &PredefinedMenuItem::hide_others(app, Some("Masquer les autres"))?,
-                        &PredefinedMenuItem::show_all(app, Some("Tout afficher"))?,
-                        &PredefinedMenuItem::separator(app)?,
-                        &quit,
-                    ],
-                )?;
+        .plugin(tauri_plugin_notification::init());
 
-                let edit_menu = Submenu::with_items(
-                    app,
-                    "Édition",
-                    true,
-                    &[
-                        &PredefinedMenuItem::undo(app, Some("Annuler"))?,
-                        &PredefinedMenuItem::redo(app, Some("Rétablir"))?,
-                        &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::cut(app, Some("Couper"))?,
-                        &PredefinedMenuItem::copy(app, Some("Copier"))?,
-                        &PredefinedMenuItem::paste(app, Some("Coller"))?,
-                        &PredefinedMenuItem::select_all(app, Some("Tout sélectionner"))?,
-                    ],
-                )?;
+    // Sous-systèmes optionnels (features cargo `audio`, `screen`, `video` et `whip`)
+    // Les raccourcis globaux ne pilotent que la voix
+    #[cfg(feature = "audio")]
+    let builder = builder
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .manage(AudioState::default())
+        .manage(StreamingState::default())
+        .manage(HotkeyState::default())
+        .manage(HeadsetState::default())
+        .manage(VoiceMessageState::default())
+        .manage(TtsState::default())
+        .manage(SoundSchemeState::default())
+        .manage(PipState::default())
+        .manage(recording::CallRecorder::default());
+    #[cfg(feature = "screen")]
+    let builder = builder
+        .manage(ScreenState::default())
+        .manage(ScreenStreamState::default())
+        .manage(GameModeState::default())
+        .manage(video::PreviewServer::default());
+    #[cfg(feature = "video")]
+    let builder = builder.manage(video::VirtualCamera::default());
+    #[cfg(feature = "whip")]
+    let builder = builder.manage(webrtc::WhipPublisher::default());
+    #[cfg(feature = "transcription")]
+    let builder = builder.manage(audio::Transcriber::default());
 
-                let window_menu = Submenu::with_items(
-                    app,
-                    "Fenêtre",
-                    true,
-                    &[
-                        &PredefinedMenuItem::minimize(app, Some("Réduire"))?,
-                        &PredefinedMenuItem::maximize(app, Some("Agrandir"))?,
-                        &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::close_window(app, Some("Fermer"))?,
-                    ],
-                )?;
+    builder
+        .setup(|app| {
+            // Créée ici et non par la config : le proxy se fixe à la création
+            commands::proxy::create_main_window(app.handle())?;
+            app.set_menu(build_menu(app.handle())?)?;
 
-                let menu = Menu::with_items(app, &[&app_menu, &edit_menu, &window_menu])?;
-                app.set_menu(menu)?;
-            }
+            crash::set_app_handle(app.handle().clone());
 
-            #[cfg(not(target_os = "macos"))]
+            commands::server::spawn_invite_expiry_task(app.handle().clone());
+            commands::audio_mesh::spawn_signaling_task(app.handle().clone());
+            commands::webrtc::spawn_gathering_task(app.handle().clone());
+            commands::presence::spawn_afk_task(app.handle().clone());
+            power::spawn_wake_lock_task(app.handle().clone());
+            commands::bandwidth::spawn_bandwidth_task(app.handle().clone());
+            commands::call_timer::spawn_call_timer_task(app.handle().clone());
+            commands::quality::spawn_quality_task(app.handle().clone());
+            #[cfg(feature = "screen")]
+            commands::game_mode::spawn_game_mode_task(app.handle().clone());
+            #[cfg(feature = "audio")]
             {
-                let file_menu = Submenu::with_items(
-                    app,
-                    "Fichier",
-                    true,
-                    &[
-                        &check_update,
-                        &separator,
-                        &quit,
-                    ],
-                )?;
-
-                let edit_menu = Submenu::with_items(
-                    app,
-                    "Édition",
-                    true,
-                    &[
-                        &PredefinedMenuItem::undo(app, Some("Annuler"))?,
-                        &PredefinedMenuItem::redo(app, Some("Rétablir"))?,
-                        &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::cut(app, Some("Couper"))?,
-                        &PredefinedMenuItem::copy(app, Some("Copier"))?,
-                        &PredefinedMenuItem::paste(app, Some("Coller"))?,
-                        &PredefinedMenuItem::select_all(app, Some("Tout sélectionner"))?,
-                    ],
-                )?;
-
-                let menu = Menu::with_items(app, &[&file_menu, &edit_menu])?;
-                app.set_menu(menu)?;
+                commands::streaming::spawn_audio_pipeline(app.handle().clone());
+                if let Err(e) = commands::hotkeys::register_all(app.handle()) {
+                    tracing::warn!("Global hotkeys unavailable: {}", e);
+                }
+                commands::headset::apply(app.handle());
+            }
+            if let Err(e) = tray::init(app.handle()) {
+                tracing::warn!("System tray unavailable: {}", e);
             }
+            // Après le reste : le join automatique a besoin de l'état et du tray
+            deep_link::init(app.handle());
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Le PiP suit la réduction de la fenêtre principale
+            #[cfg(feature = "audio")]
+            if window.label() == "main" {
+                commands::pip::on_main_window_event(window, event);
+            }
+            #[cfg(not(feature = "audio"))]
+            let _ = (window, event);
+        })
         .on_menu_event(|app, event| {
             if event.id() == "check_update" {
                 // Emit event to frontend to trigger update check
                 if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.emit("check-for-updates", ());
+                    let _ = events::emit(&window, &events::CheckForUpdatesEvent {});
                 }
             }
         })
         .manage(RoomState::default())
         .manage(ServerState::new())
+        .manage(SessionState::default())
+        .manage(DeepLinkState::default())
+        .manage(PowerState::default())
+        .manage(CommandGuards::default())
         .manage(WebRTCManager::new())
-        .manage(MeshManager::new())
-        .manage(AudioState::default())
-        .manage(AudioMeshState::default())
-        .manage(ScreenState::default())
-        .manage(ScreenStreamState::default())
-        .manage(StreamingState::default())
+        .manage(MeshManager::with_ban_list(ban_list.clone()))
+        .manage(AudioMeshState::with_ban_list(ban_list.clone()))
+        .manage(ban_list)
+        .manage(EventLog::load())
+        .manage(ChatTranscript::load())
+        .manage(PresenceState::default())
+        .manage(CallTimerState::default())
+        .manage(QualityState::default())
+        .manage(PortMappingState::default())
+        .manage(ProxyState::default())
+        .manage(NotificationState::default())
+        .manage(UpdaterState::default())
+        .manage(BandwidthState::default())
+        .manage(plugins)
         .invoke_handler(tauri::generate_handler![
             greet,
+            // App commands
+            commands::app::app_get_capabilities,
+            commands::app::app_set_locale,
+            commands::app::app_get_locale,
+            // Updater commands
+            commands::updater::updater_get_channel,
+            commands::updater::updater_set_channel,
+            commands::updater::updater_check_now,
+            commands::updater::updater_install,
             // Server commands
             commands::server::get_server_config,
             commands::server::set_username,
@@ -153,12 +369,34 @@ pub fn run() {
             commands::server::disconnect,
             commands::server::get_server_info,
             commands::server::is_connected,
+            commands::server::is_guest_mode,
+            commands::server::session_get_state,
+            commands::server::take_pending_join_code,
+            commands::server::server_create_invite,
+            commands::server::server_list_invites,
+            commands::server::server_revoke_invite,
+            // Port mapping commands
+            commands::port_mapping::port_mapping_get_settings,
+            commands::port_mapping::port_mapping_set_settings,
+            commands::port_mapping::port_mapping_get_status,
+            // Proxy commands
+            commands::proxy::proxy_get_settings,
+            commands::proxy::proxy_set_settings,
+            commands::proxy::proxy_get_active,
             // Room commands (legacy)
             commands::room::create_room,
             commands::room::join_room,
             commands::room::leave_room,
             commands::room::get_room_info,
             // Single peer WebRTC commands (backward compatible)
+            commands::webrtc::webrtc_get_connection_timeouts,
+            commands::webrtc::webrtc_set_connection_timeouts,
+            commands::webrtc::webrtc_get_network_settings,
+            commands::webrtc::webrtc_set_network_settings,
+            commands::webrtc::webrtc_list_network_interfaces,
+            commands::webrtc::webrtc_get_ice_servers,
+            commands::webrtc::webrtc_set_ice_servers,
+            commands::webrtc::webrtc_get_ice_configuration,
             commands::webrtc::create_webrtc_offer,
             commands::webrtc::accept_webrtc_offer,
             commands::webrtc::accept_webrtc_answer,
@@ -178,27 +416,76 @@ pub fn run() {
             commands::webrtc::mesh_close_all,
             commands::webrtc::mesh_announce_peer,
             // Audio commands (local processing)
+            #[cfg(feature = "audio")]
             commands::audio::audio_init,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_check_permission,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_request_permission,
+            #[cfg(feature = "audio")]
             commands::audio::audio_start_voice,
+            #[cfg(feature = "audio")]
             commands::audio::audio_stop_voice,
+            #[cfg(feature = "audio")]
             commands::audio::audio_set_mute,
+            #[cfg(feature = "audio")]
             commands::audio::audio_is_muted,
+            #[cfg(feature = "audio")]
             commands::audio::audio_is_voice_active,
+            #[cfg(feature = "audio")]
             commands::audio::audio_get_level,
+            #[cfg(feature = "audio")]
             commands::audio::audio_list_input_devices,
+            #[cfg(feature = "audio")]
             commands::audio::audio_list_output_devices,
+            #[cfg(feature = "audio")]
             commands::audio::audio_encode,
+            #[cfg(feature = "audio")]
             commands::audio::audio_decode,
+            #[cfg(feature = "audio")]
             commands::audio::audio_add_peer_samples,
+            #[cfg(feature = "audio")]
             commands::audio::audio_set_peer_volume,
+            #[cfg(feature = "audio")]
             commands::audio::audio_remove_peer,
+            #[cfg(feature = "audio")]
             commands::audio::audio_set_master_volume,
+            #[cfg(feature = "audio")]
             commands::audio::audio_get_master_volume,
+            #[cfg(feature = "audio")]
             commands::audio::audio_cleanup,
+            #[cfg(feature = "audio")]
             commands::audio::audio_set_input_device,
+            #[cfg(feature = "audio")]
             commands::audio::audio_get_input_device,
+            #[cfg(feature = "audio")]
             commands::audio::audio_set_noise_suppression,
+            #[cfg(feature = "audio")]
             commands::audio::audio_is_noise_suppression_enabled,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_denoiser,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_get_denoiser,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_denoise_model,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_get_denoise_model,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_equalizer,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_equalizer_preset,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_get_equalizer,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_dynamics,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_get_dynamics,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_list_denoisers,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_set_encoder_options,
+            #[cfg(feature = "audio")]
+            commands::audio::audio_get_encoder_options,
             // Audio mesh commands (WebRTC audio streaming)
             commands::audio_mesh::audio_mesh_init,
             commands::audio_mesh::audio_mesh_enable_audio,
@@ -214,55 +501,310 @@ pub fn run() {
             commands::audio_mesh::audio_mesh_is_connected,
             commands::audio_mesh::audio_mesh_remove_peer,
             commands::audio_mesh::audio_mesh_close_all,
+            commands::audio_mesh::audio_mesh_join_room,
+            commands::audio_mesh::audio_mesh_leave_room,
+            commands::audio_mesh::audio_mesh_list_rooms,
+            commands::audio_mesh::audio_mesh_set_talk_room,
+            commands::audio_mesh::audio_mesh_set_listen_room,
+            commands::audio_mesh::audio_mesh_join_channel,
+            commands::audio_mesh::audio_mesh_get_channels,
+            commands::audio_mesh::audio_mesh_set_room_policy,
+            commands::audio_mesh::audio_mesh_get_room_policy,
             commands::audio_mesh::audio_mesh_calculate_level,
             commands::audio_mesh::audio_mesh_is_speaking,
+            // Ban list commands
+            commands::bans::ban_peer,
+            commands::bans::ban_list,
+            commands::bans::ban_remove,
+            commands::bans::kick_peer,
+            // Room event log commands
+            commands::event_log::room_get_event_log,
+            commands::event_log::room_clear_event_log,
+            commands::event_log::room_record_host_migration,
+            // Chat commands
+            commands::chat::chat_record_message,
+            commands::chat::chat_export,
+            commands::chat::chat_run_command,
+            commands::chat::chat_get_topic,
+            commands::chat::chat_search,
+            commands::chat::chat_clear_history,
+            // Data-channel plugin commands
+            commands::plugins::plugin_claim_namespace,
+            commands::plugins::plugin_release_namespace,
+            commands::plugins::plugin_list_namespaces,
+            commands::plugins::plugin_send,
+            // Voice message commands
+            #[cfg(feature = "audio")]
+            commands::voice_message::voice_message_start,
+            #[cfg(feature = "audio")]
+            commands::voice_message::voice_message_cancel,
+            #[cfg(feature = "audio")]
+            commands::voice_message::voice_message_send,
+            #[cfg(feature = "audio")]
+            commands::voice_message::voice_message_play,
+            // Chat read aloud commands
+            #[cfg(feature = "audio")]
+            commands::tts::tts_get_settings,
+            #[cfg(feature = "audio")]
+            commands::tts::tts_set_settings,
+            #[cfg(feature = "audio")]
+            commands::tts::tts_set_sender_enabled,
+            // Sound scheme commands
+            #[cfg(feature = "audio")]
+            commands::sound_scheme::sound_scheme_get,
+            #[cfg(feature = "audio")]
+            commands::sound_scheme::sound_scheme_set,
+            #[cfg(feature = "audio")]
+            commands::sound_scheme::sound_scheme_set_sound,
+            #[cfg(feature = "audio")]
+            commands::sound_scheme::sound_scheme_preview,
+            // Transcription commands
+            #[cfg(feature = "transcription")]
+            commands::transcription::transcription_start,
+            #[cfg(feature = "transcription")]
+            commands::transcription::transcription_stop,
+            #[cfg(feature = "transcription")]
+            commands::transcription::transcription_status,
+            // Call recording commands
+            #[cfg(feature = "audio")]
+            commands::recording::recording_start,
+            #[cfg(feature = "audio")]
+            commands::recording::recording_pause,
+            #[cfg(feature = "audio")]
+            commands::recording::recording_resume,
+            #[cfg(feature = "audio")]
+            commands::recording::recording_stop,
+            #[cfg(feature = "audio")]
+            commands::recording::recording_status,
+            #[cfg(feature = "audio")]
+            commands::recording::recording_push_frame,
+            // Picture-in-picture commands
+            #[cfg(feature = "audio")]
+            commands::pip::pip_open,
+            #[cfg(feature = "audio")]
+            commands::pip::pip_close,
+            #[cfg(feature = "audio")]
+            commands::pip::pip_is_open,
+            #[cfg(feature = "audio")]
+            commands::pip::pip_set_auto,
+            #[cfg(feature = "audio")]
+            commands::pip::pip_get_auto,
+            #[cfg(feature = "audio")]
+            commands::pip::pip_push_frame,
+            // Stats overlay commands
+            commands::stats_overlay::stats_overlay_open,
+            commands::stats_overlay::stats_overlay_close,
+            commands::stats_overlay::stats_overlay_is_open,
+            // Connection quality commands
+            commands::quality::quality_get_peers,
+            // Bandwidth commands
+            commands::bandwidth::bandwidth_get_limits,
+            commands::bandwidth::bandwidth_set_limits,
+            commands::bandwidth::bandwidth_get_allocation,
+            // Diagnostics commands
+            commands::diagnostics::diagnostics_get_log_level,
+            commands::diagnostics::diagnostics_set_log_level,
+            commands::diagnostics::diagnostics_export_logs,
+            commands::diagnostics::diagnostics_run,
+            commands::diagnostics::self_test,
+            commands::diagnostics::perf_get_usage,
+            #[cfg(debug_assertions)]
+            commands::diagnostics::netsim_get_config,
+            #[cfg(debug_assertions)]
+            commands::diagnostics::netsim_set_config,
+            // Hotkey commands
+            #[cfg(feature = "audio")]
+            commands::hotkeys::hotkeys_get_settings,
+            #[cfg(feature = "audio")]
+            commands::hotkeys::hotkeys_set_settings,
+            // Headset commands
+            #[cfg(feature = "audio")]
+            commands::headset::headset_get_settings,
+            #[cfg(feature = "audio")]
+            commands::headset::headset_set_settings,
+            #[cfg(feature = "audio")]
+            commands::headset::headset_list_devices,
+            // Notification commands
+            commands::notifications::notifications_get_settings,
+            commands::notifications::notifications_set_settings,
+            commands::notifications::notifications_notify,
+            // Presence commands
+            commands::presence::presence_get_afk_settings,
+            commands::presence::presence_set_afk_settings,
+            commands::presence::presence_is_away,
+            // Call timer commands
+            commands::call_timer::call_timer_get_settings,
+            commands::call_timer::call_timer_set_settings,
+            commands::call_timer::call_timer_get_status,
             // Screen capture commands
+            #[cfg(feature = "screen")]
             commands::screen::screen_list_monitors,
+            #[cfg(feature = "screen")]
             commands::screen::screen_list_windows,
+            #[cfg(feature = "screen")]
             commands::screen::screen_list_sources,
+            #[cfg(feature = "screen")]
             commands::screen::screen_select_monitor,
+            #[cfg(feature = "screen")]
             commands::screen::screen_select_window,
+            #[cfg(feature = "screen")]
             commands::screen::screen_clear_selection,
+            #[cfg(feature = "screen")]
             commands::screen::screen_get_selection,
+            #[cfg(feature = "screen")]
             commands::screen::screen_check_permission,
+            #[cfg(feature = "screen")]
             commands::screen::screen_request_permission,
+            #[cfg(feature = "screen")]
             commands::screen::screen_capture_preview,
+            #[cfg(feature = "screen")]
             commands::screen::screen_start_sharing,
+            #[cfg(feature = "screen")]
             commands::screen::screen_stop_sharing,
+            #[cfg(feature = "screen")]
             commands::screen::screen_is_sharing,
+            #[cfg(feature = "screen")]
             commands::screen::screen_capture_frame,
             // Screen streaming commands
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_start,
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_stop,
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_is_active,
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_get_stats,
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_get_current_frame,
+            #[cfg(feature = "screen")]
+            commands::screen_stream::screen_stream_set_paused,
+            #[cfg(feature = "screen")]
+            commands::screen_stream::screen_stream_ack_frame,
+            #[cfg(feature = "screen")]
             commands::screen_stream::screen_stream_set_fps,
+            // Game mode
+            #[cfg(feature = "screen")]
+            commands::game_mode::game_mode_get_status,
+            #[cfg(feature = "screen")]
+            commands::game_mode::game_mode_set_enabled,
+            // Screen share pop-out
+            #[cfg(feature = "screen")]
+            commands::screen_popout::screen_popout_open,
+            #[cfg(feature = "screen")]
+            commands::screen_popout::screen_popout_push_frame,
+            #[cfg(feature = "screen")]
+            commands::screen_popout::screen_popout_close,
+            // LAN preview server
+            #[cfg(feature = "screen")]
+            commands::preview_server::preview_server_start,
+            #[cfg(feature = "screen")]
+            commands::preview_server::preview_server_stop,
+            #[cfg(feature = "screen")]
+            commands::preview_server::preview_server_status,
+            // Virtual camera output
+            #[cfg(feature = "video")]
+            commands::virtual_camera::virtual_camera_start,
+            #[cfg(feature = "video")]
+            commands::virtual_camera::virtual_camera_stop,
+            #[cfg(feature = "video")]
+            commands::virtual_camera::virtual_camera_status,
+            #[cfg(feature = "video")]
+            commands::virtual_camera::virtual_camera_push_frame,
+            // Broadcast to a WHIP server
+            #[cfg(feature = "whip")]
+            commands::whip::whip_start,
+            #[cfg(feature = "whip")]
+            commands::whip::whip_stop,
+            #[cfg(feature = "whip")]
+            commands::whip::whip_status,
             // Audio streaming commands (complete pipeline)
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_init,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_start_capture,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_stop_capture,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_start_playback,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_stop_playback,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_set_muted,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_is_muted,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_deafened,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_is_deafened,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_mic_check,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_active_speaker,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_auto_mute_settings,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_auto_mute_settings,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_sidetone,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_sidetone,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_is_capturing,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_is_playing,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_get_level,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_level_event_rate,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_level_event_rate,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_set_input_device,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_get_input_device,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_set_output_device,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_list_input_devices,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_list_output_devices,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_supported_configs,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_device_configs,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_device_config,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_set_noise_suppression,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_is_noise_suppression_enabled,
-            commands::streaming::streaming_get_outgoing_packet,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_set_push_to_talk,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_policy,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_subscribe_outgoing,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_unsubscribe_outgoing,
+            #[cfg(feature = "audio")]
+            commands::streaming::streaming_get_send_stats,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_receive_audio,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_remove_peer,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_clear_peers,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_start_voice,
+            #[cfg(feature = "audio")]
             commands::streaming::streaming_stop_voice,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Quitter proprement : prévenir les pairs, couper capture/lecture, vider les logs
+            if let RunEvent::ExitRequested { .. } | RunEvent::Exit = event {
+                shutdown::run(app);
+            }
+        });
 }
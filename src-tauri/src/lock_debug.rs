@@ -0,0 +1,42 @@
+//! Lock debugging (`lock-debug` feature, meant for debug builds)
+//! A watchdog thread polls parking_lot's deadlock detector and logs every cycle
+//! of threads waiting on each other, with their backtraces, so a hang shows up
+//! in the log instead of a frozen UI. Guards held across an `.await` are caught
+//! at compile time instead (`clippy::await_holding_lock` is denied in lib.rs).
+
+use std::time::Duration;
+
+/// How often the lock graph is checked (the check walks every parked thread)
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start the deadlock watchdog (call once, after logging is initialized)
+pub fn install() {
+    let spawned = std::thread::Builder::new()
+        .name("deadlock-detector".to_string())
+        .spawn(|| loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            report(&parking_lot::deadlock::check_deadlock());
+        });
+    match spawned {
+        Ok(_) => tracing::info!("Lock debugging enabled: deadlock detector running"),
+        Err(e) => tracing::warn!("Failed to start the deadlock detector: {}", e),
+    }
+}
+
+fn report(cycles: &[Vec<parking_lot::deadlock::DeadlockedThread>]) {
+    for (i, threads) in cycles.iter().enumerate() {
+        tracing::error!(
+            "Deadlock {} of {}: {} threads waiting on each other",
+            i + 1,
+            cycles.len(),
+            threads.len()
+        );
+        for thread in threads {
+            tracing::error!(
+                "Deadlocked thread {}:\n{:#?}",
+                thread.thread_id(),
+                thread.backtrace()
+            );
+        }
+    }
+}
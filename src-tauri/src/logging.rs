@@ -0,0 +1,151 @@
+//! Logging setup
+//! Logs go to stderr and to a daily-rotated file in the app data dir,
+//! with a level that can be changed at runtime
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const LOG_FILE_PREFIX: &str = "hydrowland";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Number of daily log files kept on disk
+const MAX_LOG_FILES: usize = 7;
+
+const DEFAULT_LEVEL: &str = "info";
+
+struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    level: parking_lot::RwLock<String>,
+    /// Flushes the file writer on drop, kept until `flush` on exit
+    guard: parking_lot::Mutex<Option<WorkerGuard>>,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+/// Directory holding the log files
+pub fn log_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("hydrowland")
+        .join("logs")
+}
+
+/// Install the global subscriber (call once, before anything logs)
+/// `RUST_LOG` overrides the default level. In guest mode nothing is written to disk.
+pub fn init() {
+    let level = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LEVEL.to_string());
+    let filter = EnvFilter::try_new(&level).unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let (file_layer, guard) = if storage::is_guest_mode() {
+        (None, None)
+    } else {
+        match file_appender() {
+            Ok(appender) => {
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                let layer = fmt::layer().with_ansi(false).with_writer(writer);
+                (Some(layer), Some(guard))
+            }
+            Err(e) => {
+                eprintln!("File logging disabled: {}", e);
+                (None, None)
+            }
+        }
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .init();
+
+    let _ = LOGGING.set(Logging {
+        filter: handle,
+        level: parking_lot::RwLock::new(level),
+        guard: parking_lot::Mutex::new(guard),
+    });
+}
+
+/// Flush pending lines to the log file (on exit; later lines only reach stderr)
+pub fn flush() {
+    if let Some(logging) = LOGGING.get() {
+        drop(logging.guard.lock().take());
+    }
+}
+
+fn file_appender() -> Result<RollingFileAppender, String> {
+    fs::create_dir_all(log_dir()).map_err(|e| e.to_string())?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+        .map_err(|e| e.to_string())
+}
+
+/// Current log filter (e.g. "info" or "hydrowland_lib=debug,webrtc=warn")
+pub fn level() -> String {
+    LOGGING
+        .get()
+        .map(|l| l.level.read().clone())
+        .unwrap_or_else(|| DEFAULT_LEVEL.to_string())
+}
+
+/// Change the log filter at runtime
+pub fn set_level(level: &str) -> AppResult<()> {
+    let filter = EnvFilter::try_new(level)
+        .map_err(|e| AppError::InvalidArgument(format!("Invalid log level '{}': {}", level, e)))?;
+    let logging = LOGGING
+        .get()
+        .ok_or_else(|| AppError::Internal("Logging is not initialized".to_string()))?;
+
+    logging
+        .filter
+        .reload(filter)
+        .map_err(|e| AppError::Internal(format!("Failed to change log level: {}", e)))?;
+    *logging.level.write() = level.to_string();
+
+    tracing::info!("Log level set to {}", level);
+    Ok(())
+}
+
+/// Zip the log files into `destination`
+/// Returns the number of files archived
+pub fn export_logs(destination: &Path) -> AppResult<usize> {
+    let storage_err = |e: std::io::Error| AppError::Storage(e.to_string());
+    let zip_err = |e: zip::result::ZipError| AppError::Storage(e.to_string());
+
+    let mut files: Vec<PathBuf> = fs::read_dir(log_dir())
+        .map_err(storage_err)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(destination).map_err(storage_err)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for path in &files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        zip.start_file(name, options).map_err(zip_err)?;
+        zip.write_all(&fs::read(path).map_err(storage_err)?)
+            .map_err(storage_err)?;
+    }
+
+    zip.finish().map_err(zip_err)?;
+    tracing::info!("Exported {} log files to {}", files.len(), destination.display());
+    Ok(files.len())
+}
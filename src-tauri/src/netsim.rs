@@ -0,0 +1,182 @@
+//! Network condition simulator (debug builds only)
+//! Drops and delays outgoing audio and video RTP packets right before they are
+//! written to their track, so concealment, jitter buffers and reordering can be
+//! exercised without a bad network at hand. Decisions come from a seeded
+//! generator: the same seed and settings drop and delay the same packets.
+
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use webrtc::rtp::packet::Packet as RtpPacket;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::TrackLocalWriter;
+
+use crate::error::{AppError, AppResult};
+
+/// Highest latency or jitter accepted
+const MAX_DELAY_MS: u32 = 5000;
+
+/// Simulated conditions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetSimConfig {
+    pub enabled: bool,
+    /// Share of packets dropped, 0-100
+    pub loss_percent: f32,
+    /// Delay added to every packet
+    pub latency_ms: u32,
+    /// Random extra delay, from 0 up to this (packets may arrive out of order)
+    pub jitter_ms: u32,
+    /// Seed of the generator, applied again each time the config is set
+    pub seed: u64,
+}
+
+impl NetSimConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(0.0..=100.0).contains(&self.loss_percent) {
+            return Err(AppError::InvalidArgument(
+                "Packet loss must be between 0 and 100%".to_string(),
+            ));
+        }
+        if self.latency_ms > MAX_DELAY_MS || self.jitter_ms > MAX_DELAY_MS {
+            return Err(AppError::InvalidArgument(format!(
+                "Latency and jitter must be at most {} ms",
+                MAX_DELAY_MS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What happens to a packet
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+    Send,
+    Delay(Duration),
+    Drop,
+}
+
+struct Simulator {
+    config: NetSimConfig,
+    rng: StdRng,
+}
+
+impl Simulator {
+    fn new(config: NetSimConfig) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    fn decide(&mut self) -> Verdict {
+        if self.rng.gen::<f32>() * 100.0 < self.config.loss_percent {
+            return Verdict::Drop;
+        }
+        let jitter = match self.config.jitter_ms {
+            0 => 0,
+            max => self.rng.gen_range(0..=max),
+        };
+        match self.config.latency_ms + jitter {
+            0 => Verdict::Send,
+            ms => Verdict::Delay(Duration::from_millis(ms as u64)),
+        }
+    }
+}
+
+/// Checked on every packet before taking the lock
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static SIMULATOR: Mutex<Option<Simulator>> = Mutex::new(None);
+
+pub fn config() -> NetSimConfig {
+    SIMULATOR
+        .lock()
+        .as_ref()
+        .map(|s| s.config)
+        .unwrap_or_default()
+}
+
+/// Apply `config`, restarting the generator from its seed
+pub fn configure(config: NetSimConfig) -> AppResult<()> {
+    config.validate()?;
+    *SIMULATOR.lock() = Some(Simulator::new(config));
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+    if config.enabled {
+        tracing::warn!(
+            "Network simulator on: {}% loss, {} ms latency, {} ms jitter",
+            config.loss_percent,
+            config.latency_ms,
+            config.jitter_ms
+        );
+    } else {
+        tracing::info!("Network simulator off");
+    }
+    Ok(())
+}
+
+/// Take `packet` over if the simulator drops or delays it (the caller must
+/// then not write it); false when it should be sent now as usual
+pub fn intercept(track: &Arc<TrackLocalStaticRTP>, packet: &RtpPacket) -> bool {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    let verdict = match SIMULATOR.lock().as_mut() {
+        Some(simulator) => simulator.decide(),
+        None => Verdict::Send,
+    };
+    match verdict {
+        Verdict::Send => false,
+        Verdict::Drop => true,
+        Verdict::Delay(delay) => {
+            let track = track.clone();
+            let packet = packet.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let _ = track.write_rtp(&packet).await;
+            });
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_conditions() {
+        let config = NetSimConfig {
+            enabled: true,
+            loss_percent: 20.0,
+            latency_ms: 50,
+            jitter_ms: 30,
+            seed: 42,
+        };
+        let run = || {
+            let mut simulator = Simulator::new(config);
+            (0..1000).map(|_| simulator.decide()).collect::<Vec<_>>()
+        };
+        let verdicts = run();
+        assert_eq!(verdicts, run());
+
+        let dropped = verdicts.iter().filter(|v| **v == Verdict::Drop).count();
+        assert!((150..250).contains(&dropped), "{} dropped", dropped);
+        assert!(verdicts.iter().all(|v| match v {
+            Verdict::Delay(d) => (50..=80).contains(&(d.as_millis() as u32)),
+            Verdict::Send => false,
+            Verdict::Drop => true,
+        }));
+
+        assert!(NetSimConfig {
+            loss_percent: 101.0,
+            ..config
+        }
+        .validate()
+        .is_err());
+    }
+}
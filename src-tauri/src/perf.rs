@@ -0,0 +1,237 @@
+//! Self-profiling
+//! The hot paths time themselves into per-component counters, so
+//! `perf_get_usage` can tell what a call is costing (capture, denoise, encode,
+//! screen encoding, WebRTC). Sections are short and never block, so their wall
+//! time is a good stand-in for CPU time. Nested sections only count their own
+//! time: the denoise span inside the capture span isn't counted twice.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Profiled parts of the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// Capture worker: resampling, metering, queueing (denoise/encode excluded)
+    Capture,
+    Denoise,
+    /// Opus encoding
+    Encode,
+    ScreenEncode,
+    /// RTP packetization and sending, callback event handling
+    WebRtc,
+}
+
+impl Component {
+    pub const ALL: [Component; 5] = [
+        Component::Capture,
+        Component::Denoise,
+        Component::Encode,
+        Component::ScreenEncode,
+        Component::WebRtc,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Component::Capture => "capture",
+            Component::Denoise => "denoise",
+            Component::Encode => "encode",
+            Component::ScreenEncode => "screen_encode",
+            Component::WebRtc => "webrtc",
+        }
+    }
+}
+
+struct Counter {
+    busy_ns: AtomicU64,
+    calls: AtomicU64,
+}
+
+/// Busy time of every component
+struct Counters {
+    components: [Counter; Component::ALL.len()],
+    /// First recorded section, the start of the first window
+    first_record: OnceLock<Instant>,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        const ZERO: Counter = Counter {
+            busy_ns: AtomicU64::new(0),
+            calls: AtomicU64::new(0),
+        };
+        Self {
+            components: [ZERO; Component::ALL.len()],
+            first_record: OnceLock::new(),
+        }
+    }
+
+    fn record(&self, component: Component, busy: Duration) {
+        self.first_record.get_or_init(Instant::now);
+        let counter = &self.components[component as usize];
+        counter
+            .busy_ns
+            .fetch_add(busy.as_nanos() as u64, Ordering::Relaxed);
+        counter.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn busy_ns(&self, component: Component) -> u64 {
+        self.components[component as usize]
+            .busy_ns
+            .load(Ordering::Relaxed)
+    }
+
+    /// Time the rest of the scope as `component`
+    fn span(&'static self, component: Component) -> Span {
+        Span {
+            counters: self,
+            component,
+            started: Instant::now(),
+            outer_nested_ns: NESTED_NS.with(|n| n.replace(0)),
+        }
+    }
+}
+
+/// What the app records into
+static COUNTERS: Counters = Counters::new();
+
+thread_local! {
+    /// Time spent in nested spans of the innermost open span on this thread
+    static NESTED_NS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Add `busy` to `component` (for sections a span can't wrap, like an await)
+pub fn record(component: Component, busy: Duration) {
+    COUNTERS.record(component, busy);
+}
+
+/// Time the rest of the scope as `component`
+/// Not for async code: the span must close on the thread that opened it.
+pub fn span(component: Component) -> Span {
+    COUNTERS.span(component)
+}
+
+/// Open section, recorded when dropped
+pub struct Span {
+    counters: &'static Counters,
+    component: Component,
+    started: Instant,
+    outer_nested_ns: u64,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        let nested =
+            NESTED_NS.with(|n| n.replace(self.outer_nested_ns + elapsed.as_nanos() as u64));
+        self.counters.record(
+            self.component,
+            elapsed.saturating_sub(Duration::from_nanos(nested)),
+        );
+    }
+}
+
+/// Usage of one component
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentUsage {
+    pub name: &'static str,
+    /// Share of one core since the previous report, in percent
+    pub cpu_percent: f32,
+    /// Total busy time since launch
+    pub busy_ms: u64,
+    pub calls: u64,
+}
+
+/// Report returned by `perf_get_usage`
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfUsage {
+    /// Length of the window `cpu_percent` is measured over
+    pub window_ms: u64,
+    pub components: Vec<ComponentUsage>,
+    /// Resident memory of the process, when the platform reports it
+    pub resident_memory_bytes: Option<u64>,
+}
+
+/// Busy times at the previous report, the start of the next window
+static LAST_REPORT: Mutex<Option<(Instant, [u64; Component::ALL.len()])>> = Mutex::new(None);
+
+/// Usage since the previous call (since the first recorded section for the first one)
+pub fn usage() -> PerfUsage {
+    let now = Instant::now();
+    let busy_ns = Component::ALL.map(|c| COUNTERS.busy_ns(c));
+
+    let mut last = LAST_REPORT.lock();
+    let (since, previous) = last.unwrap_or_else(|| {
+        let first = COUNTERS.first_record.get().copied().unwrap_or(now);
+        (first, [0; Component::ALL.len()])
+    });
+    *last = Some((now, busy_ns));
+    drop(last);
+
+    let window_ns = now.duration_since(since).as_nanos().max(1) as f64;
+    let components = Component::ALL
+        .iter()
+        .map(|&c| {
+            let i = c as usize;
+            ComponentUsage {
+                name: c.name(),
+                cpu_percent: ((busy_ns[i] - previous[i]) as f64 * 100.0 / window_ns) as f32,
+                busy_ms: busy_ns[i] / 1_000_000,
+                calls: COUNTERS.components[i].calls.load(Ordering::Relaxed),
+            }
+        })
+        .collect();
+
+    PerfUsage {
+        window_ms: (window_ns / 1e6) as u64,
+        components,
+        resident_memory_bytes: resident_memory(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_spans_only_count_their_own_time() {
+        // Counters of this test alone: other tests record into the app's
+        static COUNTERS: Counters = Counters::new();
+        let started = Instant::now();
+        {
+            let _capture = COUNTERS.span(Component::Capture);
+            std::thread::sleep(Duration::from_millis(10));
+            let _denoise = COUNTERS.span(Component::Denoise);
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        let total = started.elapsed().as_nanos() as u64;
+        let capture = COUNTERS.busy_ns(Component::Capture);
+        let denoise = COUNTERS.busy_ns(Component::Denoise);
+        assert!(denoise >= 30_000_000);
+        assert!(capture >= 10_000_000);
+        // Counted once: the outer span excludes the nested one
+        assert!(capture + denoise <= total);
+    }
+}
@@ -0,0 +1,267 @@
+//! Data-channel plugins
+//! A plugin claims a namespace and receives the `plugin` messages peers send
+//! under it, without the mesh managers knowing about it (a shared whiteboard,
+//! game state sync...). Plugins are registered by a crate embedding the app
+//! (`run_with_plugins`) or claimed at runtime by the webview, which then gets
+//! the messages as `plugin:<namespace>:message` events.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+use thiserror::Error;
+
+use crate::error::AppResult;
+use crate::events;
+use crate::webrtc::{AudioMeshManager, SignalingMessage};
+
+/// Longest namespace (it ends up in event names)
+const MAX_NAMESPACE_LEN: usize = 32;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PluginError {
+    #[error("Invalid plugin namespace '{0}' (a-z, 0-9, '-' and '_', up to 32 characters)")]
+    InvalidNamespace(String),
+    #[error("Plugin namespace '{0}' is already claimed")]
+    NamespaceTaken(String),
+    #[error("Plugin namespace '{0}' is not claimed by the webview")]
+    NotClaimed(String),
+}
+
+/// Handler of the messages sent under one namespace
+pub trait ChannelPlugin: Send + Sync + 'static {
+    /// A peer sent `payload` under the namespace of the plugin
+    fn on_message(&self, ctx: &PluginContext<'_>, payload: Value);
+}
+
+/// Where a plugin message comes from, and the means to answer it
+pub struct PluginContext<'a> {
+    pub app: &'a AppHandle,
+    pub namespace: &'a str,
+    pub room_code: &'a str,
+    pub peer_id: &'a str,
+    /// Username of the sender, when the mesh knows it
+    pub username: Option<&'a str>,
+    manager: &'a Arc<AudioMeshManager>,
+}
+
+impl PluginContext<'_> {
+    /// Emit `plugin:<namespace>:<event>` to the webviews (versioned like every
+    /// event, a payload that isn't a map goes under `value`)
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, payload: &S) {
+        let name = format!("plugin:{}:{}", self.namespace, event);
+        if let Err(e) = events::emit_named(self.app, &name, payload) {
+            tracing::warn!("Failed to emit {}: {}", name, e);
+        }
+    }
+
+    /// Send `payload` under the same namespace to the sender of the message
+    pub fn reply(&self, payload: Value) {
+        self.spawn_send(payload, Some(self.peer_id.to_string()));
+    }
+
+    /// Send `payload` under the same namespace to every peer of the room
+    pub fn broadcast(&self, payload: Value) {
+        self.spawn_send(payload, None);
+    }
+
+    fn spawn_send(&self, payload: Value, peer_id: Option<String>) {
+        let manager = self.manager.clone();
+        let namespace = self.namespace.to_string();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = send(&manager, &namespace, payload, peer_id.as_deref()).await {
+                tracing::warn!("Failed to send plugin message ({}): {}", namespace, e);
+            }
+        });
+    }
+}
+
+/// Forwards the messages of a namespace claimed by the webview
+struct WebviewPlugin;
+
+/// Payload of `plugin:<namespace>:message`
+#[derive(Debug, Clone, Serialize)]
+struct WebviewMessage<'a> {
+    room_code: &'a str,
+    peer_id: &'a str,
+    username: Option<&'a str>,
+    payload: Value,
+}
+
+impl ChannelPlugin for WebviewPlugin {
+    fn on_message(&self, ctx: &PluginContext<'_>, payload: Value) {
+        ctx.emit(
+            "message",
+            &WebviewMessage {
+                room_code: ctx.room_code,
+                peer_id: ctx.peer_id,
+                username: ctx.username,
+                payload,
+            },
+        );
+    }
+}
+
+struct Claim {
+    plugin: Arc<dyn ChannelPlugin>,
+    /// Claimed by the webview (which may only release its own claims)
+    webview: bool,
+}
+
+/// Namespaces claimed by plugins
+#[derive(Default)]
+pub struct PluginRegistry {
+    claims: RwLock<HashMap<String, Claim>>,
+}
+
+impl PluginRegistry {
+    /// Route the messages of `namespace` to `plugin`
+    pub fn register(&self, namespace: &str, plugin: impl ChannelPlugin) -> Result<(), PluginError> {
+        self.claim(namespace, Arc::new(plugin), false)
+    }
+
+    /// Forward the messages of `namespace` to the webview
+    /// Claiming a namespace the webview already holds succeeds: a reloaded
+    /// webview claims its namespaces again.
+    pub fn claim_for_webview(&self, namespace: &str) -> Result<(), PluginError> {
+        self.claim(namespace, Arc::new(WebviewPlugin), true)
+    }
+
+    /// Release a namespace claimed by the webview
+    pub fn release_for_webview(&self, namespace: &str) -> Result<(), PluginError> {
+        let mut claims = self.claims.write();
+        match claims.get(namespace) {
+            Some(claim) if claim.webview => {
+                claims.remove(namespace);
+                Ok(())
+            }
+            _ => Err(PluginError::NotClaimed(namespace.to_string())),
+        }
+    }
+
+    /// Claimed namespaces, sorted
+    pub fn namespaces(&self) -> Vec<String> {
+        let mut namespaces: Vec<String> = self.claims.read().keys().cloned().collect();
+        namespaces.sort();
+        namespaces
+    }
+
+    fn claim(
+        &self,
+        namespace: &str,
+        plugin: Arc<dyn ChannelPlugin>,
+        webview: bool,
+    ) -> Result<(), PluginError> {
+        validate_namespace(namespace)?;
+        let mut claims = self.claims.write();
+        if let Some(claim) = claims.get(namespace) {
+            // Already forwarded to the webview, which claims it again
+            if webview && claim.webview {
+                return Ok(());
+            }
+            return Err(PluginError::NamespaceTaken(namespace.to_string()));
+        }
+        claims.insert(namespace.to_string(), Claim { plugin, webview });
+        Ok(())
+    }
+
+    fn plugin(&self, namespace: &str) -> Option<Arc<dyn ChannelPlugin>> {
+        self.claims.read().get(namespace).map(|c| c.plugin.clone())
+    }
+}
+
+/// Namespaces end up in event names: lowercase ASCII letters, digits, `-` and `_`
+pub fn validate_namespace(namespace: &str) -> Result<(), PluginError> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_LEN
+        && namespace
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(PluginError::InvalidNamespace(namespace.to_string()))
+    }
+}
+
+/// Hand a message received from `peer_id` to the plugin of its namespace
+/// Messages of unclaimed namespaces are dropped.
+pub fn dispatch(
+    app: &AppHandle,
+    registry: &PluginRegistry,
+    manager: &Arc<AudioMeshManager>,
+    room_code: &str,
+    peer_id: &str,
+    namespace: &str,
+    payload: Value,
+) {
+    // Called outside the lock: a plugin may claim or release namespaces
+    let Some(plugin) = registry.plugin(namespace) else {
+        tracing::debug!("No plugin for namespace '{}' (from {})", namespace, peer_id);
+        return;
+    };
+    let username = manager.peer_username(peer_id);
+    let ctx = PluginContext {
+        app,
+        namespace,
+        room_code,
+        peer_id,
+        username: username.as_deref(),
+        manager,
+    };
+    plugin.on_message(&ctx, payload);
+}
+
+/// Send `payload` under `namespace` to one peer of the room, or to all of them
+pub async fn send(
+    manager: &AudioMeshManager,
+    namespace: &str,
+    payload: Value,
+    peer_id: Option<&str>,
+) -> AppResult<()> {
+    validate_namespace(namespace)?;
+    let msg = SignalingMessage::Plugin {
+        namespace: namespace.to_string(),
+        payload,
+    };
+    match peer_id {
+        Some(peer_id) => manager.send_signaling(peer_id, &msg).await,
+        None => manager.broadcast_signaling(&msg).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Noop;
+
+    impl ChannelPlugin for Noop {
+        fn on_message(&self, _ctx: &PluginContext<'_>, _payload: Value) {}
+    }
+
+    #[test]
+    fn namespaces_are_claimed_once() {
+        let registry = PluginRegistry::default();
+        registry.register("whiteboard", Noop).unwrap();
+        assert_eq!(
+            registry.claim_for_webview("whiteboard"),
+            Err(PluginError::NamespaceTaken("whiteboard".to_string()))
+        );
+        assert!(matches!(
+            registry.register("Game State", Noop),
+            Err(PluginError::InvalidNamespace(_))
+        ));
+
+        // The webview only releases its own claims, and may claim them again
+        registry.claim_for_webview("game-sync").unwrap();
+        registry.claim_for_webview("game-sync").unwrap();
+        assert_eq!(registry.namespaces(), ["game-sync", "whiteboard"]);
+        assert!(registry.release_for_webview("whiteboard").is_err());
+        registry.release_for_webview("game-sync").unwrap();
+        assert_eq!(registry.namespaces(), ["whiteboard"]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Port mapping
+//! Asks the router to forward a UDP port to us, so peers behind their own NAT
+//! reach the host directly instead of relying on hole punching alone. UPnP IGD
+//! is tried first, then NAT-PMP. Mappings are leased: the caller renews them
+//! while hosting and removes them on disconnect.
+
+use igd_next::aio::tokio::search_gateway;
+use igd_next::{PortMappingProtocol, SearchOptions};
+use natpmp::{Natpmp, Protocol, Response};
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Lease asked for, renewed well before it runs out
+pub const LEASE: Duration = Duration::from_secs(3600);
+
+/// Longest wait for the router to answer a discovery or a request
+const ROUTER_TIMEOUT: Duration = Duration::from_secs(3);
+
+const DESCRIPTION: &str = "HydrowLand";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    Upnp,
+    NatPmp,
+}
+
+/// A port forwarded by the router
+#[derive(Debug, Clone, Serialize)]
+pub struct Mapping {
+    pub method: Method,
+    pub local_port: u16,
+    /// Public address and port peers can reach us on
+    pub external: SocketAddr,
+}
+
+/// Forward `local_port` (UDP) to us, on the same external port when the router allows it
+/// When renewing, `held` is the current mapping: its external port is asked
+/// for again rather than a new one.
+pub async fn map_udp(local_port: u16, held: Option<&Mapping>) -> Result<Mapping, String> {
+    let external_port = held.map_or(local_port, |mapping| mapping.external.port());
+    let upnp = match map_upnp(local_port, external_port).await {
+        Ok(mapping) => return Ok(mapping),
+        Err(e) => e,
+    };
+    tracing::debug!("UPnP port mapping failed: {}", upnp);
+    map_natpmp(local_port, external_port)
+        .await
+        .map_err(|natpmp| format!("UPnP: {}; NAT-PMP: {}", upnp, natpmp))
+}
+
+/// Remove a mapping (best effort: it expires with its lease anyway)
+pub async fn unmap(mapping: &Mapping) {
+    let result = match mapping.method {
+        Method::Upnp => unmap_upnp(mapping.external.port()).await,
+        Method::NatPmp => natpmp_request(mapping.local_port, 0, Duration::ZERO)
+            .await
+            .map(|_| ()),
+    };
+    match result {
+        Ok(()) => tracing::info!("Removed the port mapping of {}", mapping.external),
+        Err(e) => tracing::debug!("Failed to remove the port mapping: {}", e),
+    }
+}
+
+async fn map_upnp(local_port: u16, external_port: u16) -> Result<Mapping, String> {
+    let gateway = search_gateway(SearchOptions {
+        timeout: Some(ROUTER_TIMEOUT),
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let local_ip = local_ip_towards(gateway.addr).map_err(|e| e.to_string())?;
+    let external_ip = gateway.get_external_ip().await.map_err(|e| e.to_string())?;
+
+    let local = SocketAddr::new(local_ip, local_port);
+    let lease = LEASE.as_secs() as u32;
+    let external_port = match gateway
+        .add_port(
+            PortMappingProtocol::UDP,
+            external_port,
+            local,
+            lease,
+            DESCRIPTION,
+        )
+        .await
+    {
+        Ok(()) => external_port,
+        // Taken by another host: let the router pick one
+        Err(e) => {
+            tracing::debug!(
+                "UPnP port {} unavailable ({}), asking for any",
+                external_port,
+                e
+            );
+            gateway
+                .add_any_port(PortMappingProtocol::UDP, local, lease, DESCRIPTION)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+    };
+    Ok(Mapping {
+        method: Method::Upnp,
+        local_port,
+        external: SocketAddr::new(external_ip, external_port),
+    })
+}
+
+async fn unmap_upnp(external_port: u16) -> Result<(), String> {
+    let gateway = search_gateway(SearchOptions {
+        timeout: Some(ROUTER_TIMEOUT),
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    gateway
+        .remove_port(PortMappingProtocol::UDP, external_port)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn map_natpmp(local_port: u16, external_port: u16) -> Result<Mapping, String> {
+    let external_port = natpmp_request(local_port, external_port, LEASE).await?;
+    let external_ip = tokio::task::spawn_blocking(|| {
+        let mut client = Natpmp::new().map_err(|e| e.to_string())?;
+        client
+            .send_public_address_request()
+            .map_err(|e| e.to_string())?;
+        match natpmp_response(&mut client)? {
+            Response::Gateway(gateway) => Ok(IpAddr::V4(*gateway.public_address())),
+            _ => Err("unexpected NAT-PMP response".to_string()),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(Mapping {
+        method: Method::NatPmp,
+        local_port,
+        external: SocketAddr::new(external_ip, external_port),
+    })
+}
+
+/// Map (or with a zero lifetime, remove) `local_port`, returning the public port
+async fn natpmp_request(
+    local_port: u16,
+    public_port: u16,
+    lifetime: Duration,
+) -> Result<u16, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut client = Natpmp::new().map_err(|e| e.to_string())?;
+        client
+            .send_port_mapping_request(
+                Protocol::UDP,
+                local_port,
+                public_port,
+                lifetime.as_secs() as u32,
+            )
+            .map_err(|e| e.to_string())?;
+        match natpmp_response(&mut client)? {
+            Response::UDP(mapping) => Ok(mapping.public_port()),
+            _ => Err("unexpected NAT-PMP response".to_string()),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Wait for the router's answer (the client retries on its own schedule)
+fn natpmp_response(client: &mut Natpmp) -> Result<Response, String> {
+    let deadline = Instant::now() + ROUTER_TIMEOUT;
+    loop {
+        match client.read_response_or_retry() {
+            Ok(response) => return Ok(response),
+            Err(natpmp::Error::NATPMP_TRYAGAIN) if Instant::now() < deadline => {
+                let wait = client
+                    .get_natpmp_request_timeout()
+                    .map_err(|e| e.to_string())?;
+                std::thread::sleep(wait.min(deadline.saturating_duration_since(Instant::now())));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Our address on the interface that reaches `gateway` (no packet is sent)
+fn local_ip_towards(gateway: SocketAddr) -> std::io::Result<IpAddr> {
+    let socket = UdpSocket::bind(SocketAddr::new(
+        match gateway {
+            SocketAddr::V4(_) => IpAddr::from([0, 0, 0, 0]),
+            SocketAddr::V6(_) => IpAddr::from([0u16; 8]),
+        },
+        0,
+    ))?;
+    socket.connect(gateway)?;
+    Ok(socket.local_addr()?.ip())
+}
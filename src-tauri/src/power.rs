@@ -0,0 +1,116 @@
+//! Sleep prevention
+//! Holds a wake lock while the mic is captured, the screen is streamed or a call
+//! is up, so the OS doesn't sleep in the middle of a long presentation. Sharing
+//! the screen also keeps the display on; voice alone only blocks idle sleep.
+
+use parking_lot::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+#[cfg(feature = "audio")]
+use crate::commands::audio::AudioState;
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::i18n::{t, Text};
+use crate::session::{SessionPhase, SessionState};
+
+/// How often the activity is re-checked (sleep timeouts are minutes long)
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the wake lock has to keep awake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeLevel {
+    None,
+    /// No idle sleep, the display may still turn off
+    System,
+    /// No idle sleep and no display sleep
+    Display,
+}
+
+/// The wake lock currently held
+#[derive(Default)]
+pub struct PowerState {
+    lock: Mutex<Option<(WakeLevel, keepawake::KeepAwake)>>,
+}
+
+impl PowerState {
+    /// Hold a lock for `level`, replacing the current one when it differs
+    fn apply(&self, level: WakeLevel) {
+        let mut lock = self.lock.lock();
+        let current = lock.as_ref().map_or(WakeLevel::None, |(level, _)| *level);
+        if current == level {
+            return;
+        }
+        // Release first: some platforms refuse a second assertion from the same app
+        *lock = None;
+        if level == WakeLevel::None {
+            tracing::info!("Wake lock released");
+            return;
+        }
+        match keepawake::Builder::default()
+            .idle(true)
+            .display(level == WakeLevel::Display)
+            .reason(t(Text::WakeLockReason))
+            .app_name("HydrowLand")
+            .app_reverse_domain("com.hydrowland.app")
+            .create()
+        {
+            Ok(awake) => {
+                tracing::info!("Wake lock acquired ({:?})", level);
+                *lock = Some((level, awake));
+            }
+            Err(e) => tracing::warn!("Failed to acquire a wake lock: {}", e),
+        }
+    }
+}
+
+/// Level needed for the current activity
+fn wanted_level(app: &AppHandle) -> WakeLevel {
+    #[cfg(feature = "screen")]
+    if app.state::<ScreenStreamState>().is_streaming() {
+        return WakeLevel::Display;
+    }
+
+    let in_call = matches!(
+        app.state::<SessionState>().phase(),
+        SessionPhase::Connecting | SessionPhase::InCall | SessionPhase::Reconnecting
+    );
+    #[cfg(feature = "audio")]
+    let capturing = app.state::<StreamingState>().service.is_capturing()
+        || app.state::<AudioState>().is_voice_active();
+    #[cfg(not(feature = "audio"))]
+    let capturing = false;
+
+    if in_call || capturing {
+        WakeLevel::System
+    } else {
+        WakeLevel::None
+    }
+}
+
+/// Acquire or release the wake lock to match the current activity
+pub fn refresh(app: &AppHandle) {
+    if let Some(power) = app.try_state::<PowerState>() {
+        power.apply(wanted_level(app));
+    }
+}
+
+/// Follow the activity for the lifetime of the app
+pub fn spawn_wake_lock_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app);
+        }
+    });
+}
+
+/// Let the OS sleep again (on shutdown)
+pub fn release(app: &AppHandle) {
+    if let Some(power) = app.try_state::<PowerState>() {
+        power.apply(WakeLevel::None);
+    }
+}
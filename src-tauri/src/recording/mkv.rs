@@ -0,0 +1,297 @@
+//! Minimal Matroska writer
+//! Just what a call recording needs: Opus audio tracks and an MJPEG video
+//! track. Clusters are written as the recording goes, with an unknown size, so
+//! the file stays readable if the app dies mid-recording; the duration is
+//! patched in when the file is finished.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+// Element ids (https://www.matroska.org/technical/elements.html)
+const EBML: u32 = 0x1A45_DFA3;
+const EBML_VERSION: u32 = 0x4286;
+const EBML_READ_VERSION: u32 = 0x42F7;
+const EBML_MAX_ID_LENGTH: u32 = 0x42F2;
+const EBML_MAX_SIZE_LENGTH: u32 = 0x42F3;
+const DOC_TYPE: u32 = 0x4282;
+const DOC_TYPE_VERSION: u32 = 0x4287;
+const DOC_TYPE_READ_VERSION: u32 = 0x4285;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const FLAG_DEFAULT: u32 = 0x88;
+const FLAG_LACING: u32 = 0x9C;
+const NAME: u32 = 0x536E;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const CODEC_DELAY: u32 = 0x56AA;
+const SEEK_PRE_ROLL: u32 = 0x56BB;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+const TIMESTAMP: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+
+/// Size of an element whose end isn't known while writing it
+const UNKNOWN_SIZE: [u8; 8] = [0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+/// Timestamps are in milliseconds
+const TIMESTAMP_SCALE_NS: u64 = 1_000_000;
+
+/// A new cluster is started every few seconds (block timestamps are 16-bit
+/// offsets from their cluster's)
+const CLUSTER_MS: i64 = 5000;
+
+/// Opus decoders discard this much at the start (encoder lookahead at 48kHz)
+const OPUS_PRE_SKIP: u16 = 312;
+/// Audio to decode before a seek point for the output to converge
+const OPUS_SEEK_PRE_ROLL_NS: u64 = 80_000_000;
+
+pub enum Codec {
+    /// Opus at 48kHz
+    Opus { channels: u8 },
+    /// JPEG frames (each one carries its own size, this is the nominal one)
+    Mjpeg { width: u32, height: u32 },
+}
+
+pub struct Track {
+    /// Track number used by `write_frame`, from 1
+    pub number: u8,
+    pub name: String,
+    pub codec: Codec,
+    /// Picked by players when nothing else is asked
+    pub default: bool,
+}
+
+pub struct MkvWriter<W: Write + Seek> {
+    out: W,
+    /// Where the duration float is, patched by `finish`
+    duration_offset: u64,
+    /// Timestamp of the open cluster
+    cluster: Option<u64>,
+}
+
+impl<W: Write + Seek> MkvWriter<W> {
+    /// Write the header and the track list
+    pub fn new(mut out: W, tracks: &[Track]) -> io::Result<Self> {
+        let start = out.stream_position()?;
+        let mut header = Vec::new();
+
+        let mut ebml = Vec::new();
+        uint(&mut ebml, EBML_VERSION, 1);
+        uint(&mut ebml, EBML_READ_VERSION, 1);
+        uint(&mut ebml, EBML_MAX_ID_LENGTH, 4);
+        uint(&mut ebml, EBML_MAX_SIZE_LENGTH, 8);
+        string(&mut ebml, DOC_TYPE, "matroska");
+        uint(&mut ebml, DOC_TYPE_VERSION, 4);
+        uint(&mut ebml, DOC_TYPE_READ_VERSION, 2);
+        element(&mut header, EBML, &ebml);
+
+        id(&mut header, SEGMENT);
+        header.extend_from_slice(&UNKNOWN_SIZE);
+
+        let mut info = Vec::new();
+        uint(&mut info, TIMESTAMP_SCALE, TIMESTAMP_SCALE_NS);
+        string(&mut info, MUXING_APP, "HydrowLand");
+        string(&mut info, WRITING_APP, "HydrowLand");
+        let duration_in_info = info.len();
+        float(&mut info, DURATION, 0.0);
+        id(&mut header, INFO);
+        size(&mut header, info.len() as u64);
+        // Past the duration's id and size
+        let duration_offset = (header.len() + duration_in_info + 3) as u64;
+        header.extend_from_slice(&info);
+
+        let mut entries = Vec::new();
+        for track in tracks {
+            element(&mut entries, TRACK_ENTRY, &track_entry(track));
+        }
+        element(&mut header, TRACKS, &entries);
+
+        out.write_all(&header)?;
+        Ok(Self {
+            out,
+            duration_offset: start + duration_offset,
+            cluster: None,
+        })
+    }
+
+    /// Write a frame of `track` at `timestamp_ms`
+    pub fn write_frame(
+        &mut self,
+        track: u8,
+        timestamp_ms: u64,
+        keyframe: bool,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let offset = match self.cluster {
+            Some(cluster) => timestamp_ms as i64 - cluster as i64,
+            None => CLUSTER_MS,
+        };
+        // Slightly late frames stay in the open cluster (negative offset)
+        let offset = if offset >= CLUSTER_MS || offset < i16::MIN as i64 {
+            let mut cluster = Vec::new();
+            id(&mut cluster, CLUSTER);
+            cluster.extend_from_slice(&UNKNOWN_SIZE);
+            uint(&mut cluster, TIMESTAMP, timestamp_ms);
+            self.out.write_all(&cluster)?;
+            self.cluster = Some(timestamp_ms);
+            0
+        } else {
+            offset as i16
+        };
+
+        let mut block = Vec::with_capacity(16);
+        id(&mut block, SIMPLE_BLOCK);
+        size(&mut block, 4 + data.len() as u64);
+        block.push(0x80 | track);
+        block.extend_from_slice(&offset.to_be_bytes());
+        block.push(if keyframe { 0x80 } else { 0 });
+        self.out.write_all(&block)?;
+        self.out.write_all(data)
+    }
+
+    /// Patch the duration in and flush, giving the output back
+    pub fn finish(mut self, duration_ms: u64) -> io::Result<W> {
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(self.duration_offset))?;
+        self.out.write_all(&(duration_ms as f64).to_be_bytes())?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn track_entry(track: &Track) -> Vec<u8> {
+    let mut entry = Vec::new();
+    uint(&mut entry, TRACK_NUMBER, track.number as u64);
+    uint(&mut entry, TRACK_UID, track.number as u64);
+    uint(&mut entry, FLAG_DEFAULT, track.default as u64);
+    uint(&mut entry, FLAG_LACING, 0);
+    string(&mut entry, NAME, &track.name);
+    match track.codec {
+        Codec::Opus { channels } => {
+            uint(&mut entry, TRACK_TYPE, 2);
+            string(&mut entry, CODEC_ID, "A_OPUS");
+            element(&mut entry, CODEC_PRIVATE, &opus_head(channels));
+            uint(
+                &mut entry,
+                CODEC_DELAY,
+                OPUS_PRE_SKIP as u64 * 1_000_000_000 / 48_000,
+            );
+            uint(&mut entry, SEEK_PRE_ROLL, OPUS_SEEK_PRE_ROLL_NS);
+            let mut audio = Vec::new();
+            float(&mut audio, SAMPLING_FREQUENCY, 48_000.0);
+            uint(&mut audio, CHANNELS, channels as u64);
+            element(&mut entry, AUDIO, &audio);
+        }
+        Codec::Mjpeg { width, height } => {
+            uint(&mut entry, TRACK_TYPE, 1);
+            string(&mut entry, CODEC_ID, "V_MJPEG");
+            let mut video = Vec::new();
+            uint(&mut video, PIXEL_WIDTH, width as u64);
+            uint(&mut video, PIXEL_HEIGHT, height as u64);
+            element(&mut entry, VIDEO, &video);
+        }
+    }
+    entry
+}
+
+/// Identification header of an Opus stream (RFC 7845)
+fn opus_head(channels: u8) -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(channels);
+    head.extend_from_slice(&OPUS_PRE_SKIP.to_le_bytes());
+    head.extend_from_slice(&48_000u32.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    head
+}
+
+/// Ids carry their own length marker: written as is, without leading zeros
+fn id(buf: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = (id.leading_zeros() / 8) as usize;
+    buf.extend_from_slice(&bytes[skip..]);
+}
+
+/// Variable-length size, as short as possible (all ones is reserved)
+fn size(buf: &mut Vec<u8>, size: u64) {
+    let len = (1..8usize)
+        .find(|&n| size < (1u64 << (7 * n)) - 1)
+        .unwrap_or(8);
+    let marked = size | (1 << (7 * len));
+    buf.extend_from_slice(&marked.to_be_bytes()[8 - len..]);
+}
+
+fn element(buf: &mut Vec<u8>, element_id: u32, body: &[u8]) {
+    id(buf, element_id);
+    size(buf, body.len() as u64);
+    buf.extend_from_slice(body);
+}
+
+fn uint(buf: &mut Vec<u8>, element_id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = ((value.leading_zeros() / 8) as usize).min(7);
+    element(buf, element_id, &bytes[skip..]);
+}
+
+fn float(buf: &mut Vec<u8>, element_id: u32, value: f64) {
+    element(buf, element_id, &value.to_be_bytes());
+}
+
+fn string(buf: &mut Vec<u8>, element_id: u32, value: &str) {
+    element(buf, element_id, value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn writes_blocks_and_patches_the_duration() {
+        let mut encoded = Vec::new();
+        size(&mut encoded, 5);
+        size(&mut encoded, 127);
+        assert_eq!(encoded, [0x85, 0x40, 0x7F]);
+
+        let tracks = [Track {
+            number: 1,
+            name: "Appel".to_string(),
+            codec: Codec::Opus { channels: 1 },
+            default: true,
+        }];
+        let mut writer = MkvWriter::new(Cursor::new(Vec::new()), &tracks).unwrap();
+        for i in 0..300u64 {
+            writer
+                .write_frame(1, i * 20, true, &[0xFC, i as u8])
+                .unwrap();
+        }
+        let file = writer.finish(6000).unwrap().into_inner();
+
+        assert!(file.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]));
+        let duration = [&[0x44, 0x89, 0x88][..], &6000f64.to_be_bytes()].concat();
+        assert!(file.windows(duration.len()).any(|w| w == duration));
+        // 6 s of frames: a second cluster at 5 s
+        let clusters = file
+            .windows(4)
+            .filter(|w| *w == [0x1F, 0x43, 0xB6, 0x75])
+            .count();
+        assert_eq!(clusters, 2);
+        // Last block: track 1, 980 ms into its cluster, keyframe
+        assert!(file.ends_with(&[0xA3, 0x86, 0x81, 0x03, 0xD4, 0x80, 0xFC, 43]));
+    }
+}
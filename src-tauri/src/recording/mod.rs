@@ -0,0 +1,409 @@
+//! Call recording
+//! Writes the call to a single Matroska file: what we hear (the playback mix
+//! and our own voice) and our voice alone as two Opus tracks, plus the screen
+//! share on screen as MJPEG, all on one clock. A writer thread takes a frame of
+//! each audio tap every 20ms, padding with silence when one runs dry, so the
+//! tracks keep to the wall clock; pausing stops that clock, and the paused
+//! time is left out of the file.
+
+mod mkv;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::audio::{CallTap, OpusEncoder, FRAME_DURATION_MS, SAMPLES_PER_FRAME};
+use crate::error::{AppError, AppResult};
+use mkv::{Codec, MkvWriter, Track};
+
+const CALL_TRACK: u8 = 1;
+const MIC_TRACK: u8 = 2;
+const SCREEN_TRACK: u8 = 3;
+
+/// Nominal size of the screen track (the encoder's largest frames)
+const SCREEN_WIDTH: u32 = 1920;
+const SCREEN_HEIGHT: u32 = 1080;
+
+/// Screen frames waiting for the writer, newer ones are dropped beyond that
+const SCREEN_QUEUE_FRAMES: usize = 4;
+
+/// A presenter silent for this long gives the screen track to the next one
+const SCREEN_SOURCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where the recording stands
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingStatus {
+    pub active: bool,
+    pub paused: bool,
+    pub path: Option<String>,
+    /// Recorded time, pauses excluded
+    pub duration_ms: u64,
+    /// Why the writer stopped on its own (the file has what was written)
+    pub error: Option<String>,
+}
+
+/// What was recorded, once the file is finished
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingSummary {
+    pub path: String,
+    /// Recorded time, pauses excluded
+    pub duration_ms: u64,
+    pub paused_ms: u64,
+    pub size_bytes: u64,
+    pub screen_frames: u64,
+}
+
+/// Elapsed recording time, with the pauses taken out
+struct Clock {
+    started: Instant,
+    paused_at: Option<Instant>,
+    paused: Duration,
+}
+
+impl Clock {
+    fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            paused_at: None,
+            paused: Duration::ZERO,
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool, now: Instant) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(now),
+            (false, Some(at)) => {
+                self.paused += now - at;
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn paused_total(&self, now: Instant) -> Duration {
+        self.paused + self.paused_at.map_or(Duration::ZERO, |at| now - at)
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        (now - self.started).saturating_sub(self.paused_total(now))
+    }
+}
+
+/// Shared with the writer thread
+#[derive(Default)]
+struct Shared {
+    stop: AtomicBool,
+    paused: AtomicBool,
+    duration_ms: AtomicU64,
+    screen_frames: AtomicU64,
+    /// Error the writer stopped on
+    failed: Mutex<Option<String>>,
+}
+
+struct Session {
+    path: PathBuf,
+    shared: Arc<Shared>,
+    tap: Arc<CallTap>,
+    /// JPEG frames of the screen track (None when it isn't recorded)
+    screen_tx: Option<SyncSender<Vec<u8>>>,
+    /// Presenter whose frames are recorded, and when they last sent one
+    screen_source: Mutex<Option<(String, Instant)>>,
+    writer: JoinHandle<AppResult<RecordingSummary>>,
+}
+
+impl Session {
+    /// The writer stopped without being asked to (an error, or a panic)
+    fn is_dead(&self) -> bool {
+        self.writer.is_finished()
+    }
+
+    fn error(&self) -> Option<String> {
+        self.is_dead().then(|| {
+            self.shared
+                .failed
+                .lock()
+                .clone()
+                .unwrap_or_else(|| "The recorder panicked".to_string())
+        })
+    }
+}
+
+/// Call recorder, shared between the commands, the screen stream and the
+/// frames pushed by the frontend
+#[derive(Clone, Default)]
+pub struct CallRecorder {
+    session: Arc<Mutex<Option<Session>>>,
+}
+
+impl CallRecorder {
+    /// Start recording to `path` (with the screen share if `screen`)
+    /// `on_failure` is called from the writer thread if it stops on an error.
+    pub fn start(
+        &self,
+        path: PathBuf,
+        tap: Arc<CallTap>,
+        screen: bool,
+        on_failure: impl FnOnce(String) + Send + 'static,
+    ) -> AppResult<()> {
+        let mut session = self.session.lock();
+        // A dead writer leaves its session behind until the next start or stop
+        if session.as_ref().is_some_and(Session::is_dead) {
+            if let Some(dead) = session.take() {
+                let _ = dead.writer.join();
+            }
+        }
+        if session.is_some() {
+            return Err(AppError::AlreadyActive(
+                "The call is already being recorded".to_string(),
+            ));
+        }
+
+        let file = File::create(&path).map_err(|e| {
+            AppError::Storage(format!("Failed to create {}: {}", path.display(), e))
+        })?;
+        let mut tracks = vec![
+            Track {
+                number: CALL_TRACK,
+                name: "Call".to_string(),
+                codec: Codec::Opus { channels: 1 },
+                default: true,
+            },
+            Track {
+                number: MIC_TRACK,
+                name: "Microphone".to_string(),
+                codec: Codec::Opus { channels: 1 },
+                default: false,
+            },
+        ];
+        if screen {
+            tracks.push(Track {
+                number: SCREEN_TRACK,
+                name: "Screen".to_string(),
+                codec: Codec::Mjpeg {
+                    width: SCREEN_WIDTH,
+                    height: SCREEN_HEIGHT,
+                },
+                default: true,
+            });
+        }
+        let writer = MkvWriter::new(BufWriter::new(file), &tracks)
+            .map_err(|e| AppError::Storage(format!("Failed to write the recording: {}", e)))?;
+
+        let (screen_tx, screen_rx) = if screen {
+            let (tx, rx) = mpsc::sync_channel(SCREEN_QUEUE_FRAMES);
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        let shared = Arc::new(Shared::default());
+        tap.set_enabled(true);
+
+        let thread_shared = shared.clone();
+        let thread_tap = tap.clone();
+        let thread_path = path.clone();
+        let spawned = std::thread::Builder::new()
+            .name("call-recorder".to_string())
+            .spawn(move || {
+                let result = write(writer, &thread_path, &thread_shared, &thread_tap, screen_rx);
+                if let Err(e) = &result {
+                    tracing::error!("Recording stopped: {}", e);
+                    // Nothing collects the tap anymore
+                    thread_tap.set_enabled(false);
+                    *thread_shared.failed.lock() = Some(e.to_string());
+                    on_failure(e.to_string());
+                }
+                result
+            });
+        let writer = match spawned {
+            Ok(writer) => writer,
+            Err(e) => {
+                tap.set_enabled(false);
+                return Err(AppError::Internal(format!(
+                    "Failed to spawn the recorder: {}",
+                    e
+                )));
+            }
+        };
+
+        tracing::info!("Recording the call to {}", path.display());
+        *session = Some(Session {
+            path,
+            shared,
+            tap,
+            screen_tx,
+            screen_source: Mutex::new(None),
+            writer,
+        });
+        Ok(())
+    }
+
+    /// Pause or resume (audio from the pause is dropped, not kept for later)
+    pub fn set_paused(&self, paused: bool) -> AppResult<()> {
+        let session = self.session.lock();
+        let session = session.as_ref().filter(|s| !s.is_dead()).ok_or_else(|| {
+            AppError::InvalidArgument("The call isn't being recorded".to_string())
+        })?;
+        session.shared.paused.store(paused, Ordering::SeqCst);
+        session.tap.set_enabled(!paused);
+        tracing::info!("Recording {}", if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    /// Finish the file (None if nothing was being recorded)
+    pub fn stop(&self) -> AppResult<Option<RecordingSummary>> {
+        let Some(session) = self.session.lock().take() else {
+            return Ok(None);
+        };
+        session.shared.stop.store(true, Ordering::SeqCst);
+        session.tap.set_enabled(false);
+        let summary = session
+            .writer
+            .join()
+            .map_err(|_| AppError::Internal("The recorder panicked".to_string()))??;
+        tracing::info!(
+            "Recording finished: {} ({} ms)",
+            summary.path,
+            summary.duration_ms
+        );
+        Ok(Some(summary))
+    }
+
+    /// Queue a JPEG frame of `presenter_id`'s screen share
+    /// One presenter is recorded at a time: the first to send a frame keeps
+    /// the screen track until they stop sending.
+    pub fn push_screen_frame(&self, presenter_id: &str, jpeg: &[u8]) {
+        let session = self.session.lock();
+        let Some(session) = session.as_ref() else {
+            return;
+        };
+        let Some(tx) = session.screen_tx.as_ref() else {
+            return;
+        };
+        if session.shared.paused.load(Ordering::Relaxed) {
+            return;
+        }
+
+        {
+            let now = Instant::now();
+            let mut source = session.screen_source.lock();
+            match source.as_mut() {
+                Some((id, last)) if id == presenter_id => *last = now,
+                Some((_, last)) if now - *last < SCREEN_SOURCE_TIMEOUT => return,
+                _ => *source = Some((presenter_id.to_string(), now)),
+            }
+        }
+        // Writer busy: drop the frame, the next one will do
+        let _ = tx.try_send(jpeg.to_vec());
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        let session = self.session.lock();
+        let running = session.as_ref().filter(|s| !s.is_dead());
+        RecordingStatus {
+            active: running.is_some(),
+            paused: running.is_some_and(|s| s.shared.paused.load(Ordering::Relaxed)),
+            path: running.map(|s| s.path.to_string_lossy().into_owned()),
+            duration_ms: running.map_or(0, |s| s.shared.duration_ms.load(Ordering::Relaxed)),
+            error: session.as_ref().and_then(Session::error),
+        }
+    }
+}
+
+/// Writer thread: audio every 20ms, screen frames as they come
+fn write(
+    mut writer: MkvWriter<BufWriter<File>>,
+    path: &Path,
+    shared: &Shared,
+    tap: &CallTap,
+    screen_rx: Option<Receiver<Vec<u8>>>,
+) -> AppResult<RecordingSummary> {
+    let io_error =
+        |e: std::io::Error| AppError::Storage(format!("Failed to write the recording: {}", e));
+    let mut call_encoder = OpusEncoder::new()?;
+    let mut mic_encoder = OpusEncoder::new()?;
+    let mut mix = vec![0.0f32; SAMPLES_PER_FRAME];
+    let mut mic = vec![0.0f32; SAMPLES_PER_FRAME];
+    let frame_ms = FRAME_DURATION_MS as u64;
+
+    let mut clock = Clock::new(Instant::now());
+    let mut audio_frames = 0u64;
+    while !shared.stop.load(Ordering::SeqCst) {
+        clock.set_paused(shared.paused.load(Ordering::SeqCst), Instant::now());
+        let now_ms = clock.elapsed(Instant::now()).as_millis() as u64;
+
+        // Audio frames due: the call is the mix with our voice on top
+        while (audio_frames + 1) * frame_ms <= now_ms {
+            tap.take_frame(&mut mix, &mut mic);
+            for (call, voice) in mix.iter_mut().zip(&mic) {
+                *call = (*call + voice).clamp(-1.0, 1.0);
+            }
+            let timestamp = audio_frames * frame_ms;
+            writer
+                .write_frame(CALL_TRACK, timestamp, true, &call_encoder.encode(&mix)?)
+                .map_err(io_error)?;
+            writer
+                .write_frame(MIC_TRACK, timestamp, true, &mic_encoder.encode(&mic)?)
+                .map_err(io_error)?;
+            audio_frames += 1;
+        }
+        shared.duration_ms.store(now_ms, Ordering::Relaxed);
+
+        // Screen frames until the next audio frame is due
+        let Some(rx) = screen_rx.as_ref() else {
+            std::thread::sleep(Duration::from_millis(frame_ms / 2));
+            continue;
+        };
+        if let Ok(jpeg) = rx.recv_timeout(Duration::from_millis(frame_ms / 2)) {
+            let timestamp = clock.elapsed(Instant::now()).as_millis() as u64;
+            writer
+                .write_frame(SCREEN_TRACK, timestamp, true, &jpeg)
+                .map_err(io_error)?;
+            shared.screen_frames.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let now = Instant::now();
+    let duration_ms = audio_frames * frame_ms;
+    let file = writer.finish(duration_ms).map_err(io_error)?;
+    let size_bytes = file
+        .get_ref()
+        .metadata()
+        .map(|m| m.len())
+        .unwrap_or_default();
+    Ok(RecordingSummary {
+        path: path.to_string_lossy().into_owned(),
+        duration_ms,
+        paused_ms: clock.paused_total(now).as_millis() as u64,
+        size_bytes,
+        screen_frames: shared.screen_frames.load(Ordering::Relaxed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_leaves_pauses_out() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut clock = Clock::new(start);
+        assert_eq!(clock.elapsed(at(100)), Duration::from_millis(100));
+
+        clock.set_paused(true, at(100));
+        // Already paused: the pause still counts from its start
+        clock.set_paused(true, at(150));
+        assert_eq!(clock.elapsed(at(300)), Duration::from_millis(100));
+        clock.set_paused(false, at(300));
+        clock.set_paused(false, at(350));
+        assert_eq!(clock.elapsed(at(400)), Duration::from_millis(200));
+        assert_eq!(clock.paused_total(at(400)), Duration::from_millis(200));
+    }
+}
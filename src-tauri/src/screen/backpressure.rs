@@ -0,0 +1,99 @@
+//! Screen frame backpressure
+//! Every "screen-frame" event is queued in the webview until its script gets to
+//! it. When the webview is busy, frames pile up there and memory and latency
+//! grow. The frontend acknowledges each frame it handled; past a few frames in
+//! flight the next ones are not emitted. Each window listening acknowledges for
+//! itself, so the slowest one sets the pace. A window that stops acknowledging
+//! (closed, reloaded) is forgotten once its frames time out, and acks of a
+//! previous stream are ignored: its frame numbers mean nothing to this one.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Frames emitted and not acknowledged yet before new ones are dropped
+pub const MAX_UNACKED_FRAMES: usize = 3;
+
+/// A frame not acknowledged within this time is considered lost
+const ACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Frames emitted to the webviews and not acknowledged yet, per window
+#[derive(Default)]
+pub struct FrameAcks {
+    /// Stream the frames belong to
+    generation: u64,
+    /// By window label, for the windows that acknowledged frames of the stream
+    in_flight: HashMap<String, VecDeque<(u64, Instant)>>,
+}
+
+impl FrameAcks {
+    /// Whether a frame may be emitted now (false: a window is behind)
+    pub fn can_emit(&mut self, now: Instant) -> bool {
+        self.in_flight.retain(|_, frames| {
+            !frames
+                .front()
+                .is_some_and(|(_, sent)| now.duration_since(*sent) >= ACK_TIMEOUT)
+        });
+        self.in_flight
+            .values()
+            .all(|frames| frames.len() < MAX_UNACKED_FRAMES)
+    }
+
+    pub fn emitted(&mut self, frame_number: u64, now: Instant) {
+        for frames in self.in_flight.values_mut() {
+            frames.push_back((frame_number, now));
+        }
+    }
+
+    /// Window `label` handled `frame_number` of stream `generation` (and so
+    /// every frame before it)
+    pub fn ack(&mut self, label: &str, generation: u64, frame_number: u64) {
+        if generation != self.generation {
+            return;
+        }
+        self.in_flight
+            .entry(label.to_string())
+            .or_default()
+            .retain(|(number, _)| *number > frame_number);
+    }
+
+    /// Track the frames of stream `generation` from now on
+    pub fn reset(&mut self, generation: u64) {
+        self.generation = generation;
+        self.in_flight.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_held_until_acked_or_timed_out() {
+        let start = Instant::now();
+        let mut acks = FrameAcks::default();
+        acks.reset(1);
+        acks.ack("main", 1, 0);
+        for frame in 1..=MAX_UNACKED_FRAMES as u64 {
+            assert!(acks.can_emit(start));
+            acks.emitted(frame, start);
+        }
+        assert!(!acks.can_emit(start));
+
+        // An ack covers the frames before it
+        acks.ack("main", 1, 2);
+        assert!(acks.can_emit(start));
+        acks.emitted(4, start);
+        assert!(acks.can_emit(start));
+        acks.emitted(5, start);
+        assert!(!acks.can_emit(start));
+
+        // Another window or another stream doesn't ack for this one
+        acks.ack("viewer", 1, 5);
+        acks.ack("main", 0, 5);
+        assert!(!acks.can_emit(start));
+
+        // Gone: the window and its frames in flight are forgotten
+        assert!(acks.can_emit(start + ACK_TIMEOUT));
+        assert_eq!(acks.in_flight.len(), 1);
+    }
+}
@@ -4,6 +4,8 @@ use thiserror::Error;
 use tokio::sync::RwLock;
 use xcap::{Monitor, Window};
 
+use super::hints::SourceGeometry;
+
 #[derive(Error, Debug)]
 pub enum ScreenCaptureError {
     #[error("Failed to enumerate monitors: {0}")]
@@ -20,6 +22,15 @@ pub enum ScreenCaptureError {
     PermissionDenied,
 }
 
+/// Apps drawing the desktop, never taken for a fullscreen app
+const DESKTOP_SHELLS: &[&str] = &[
+    "explorer",
+    "finder",
+    "program manager",
+    "gnome-shell",
+    "plasmashell",
+];
+
 /// Information about a monitor/display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorInfo {
@@ -162,6 +173,32 @@ impl ScreenCapture {
         Ok(result)
     }
 
+    /// App name of the focused window when it covers its whole monitor (a
+    /// game, a fullscreen video), None otherwise or when the window is ours
+    pub fn fullscreen_app() -> Option<String> {
+        let windows = Window::all().ok()?;
+        let window = windows.iter().find(|w| w.is_focused().unwrap_or(false))?;
+        if window.pid().ok()? == std::process::id() || window.is_minimized().unwrap_or(false) {
+            return None;
+        }
+
+        // The desktop itself is a focused window covering the screen
+        let app_name = window.app_name().unwrap_or_default();
+        let lower = app_name.to_lowercase();
+        if DESKTOP_SHELLS.iter().any(|shell| lower.contains(shell)) {
+            return None;
+        }
+
+        let monitor = window.current_monitor().ok()?;
+        let (x, y) = (window.x().ok()? as i64, window.y().ok()? as i64);
+        let (mx, my) = (monitor.x().ok()? as i64, monitor.y().ok()? as i64);
+        let covers = x <= mx
+            && y <= my
+            && x + window.width().ok()? as i64 >= mx + monitor.width().ok()? as i64
+            && y + window.height().ok()? as i64 >= my + monitor.height().ok()? as i64;
+        covers.then_some(app_name)
+    }
+
     /// Get all available capture sources (monitors + windows)
     pub fn list_sources(include_minimized: bool) -> Result<Vec<CaptureSourceInfo>, ScreenCaptureError> {
         let mut sources = Vec::new();
@@ -200,6 +237,35 @@ impl ScreenCapture {
         self.selected_source.read().await.clone()
     }
 
+    /// Name and desktop position of the selected source (for presenter hints)
+    pub async fn selected_geometry(&self) -> Option<SourceGeometry> {
+        let source = self.selected_source.read().await.clone()?;
+        match source {
+            CaptureSource::Monitor { id } => {
+                let monitors = Monitor::all().ok()?;
+                let monitor = monitors.get(id as usize)?;
+                Some(SourceGeometry {
+                    name: monitor.name().unwrap_or_default(),
+                    x: monitor.x().unwrap_or(0),
+                    y: monitor.y().unwrap_or(0),
+                    width: monitor.width().unwrap_or(0),
+                    height: monitor.height().unwrap_or(0),
+                })
+            }
+            CaptureSource::Window { id } => {
+                let windows = Window::all().ok()?;
+                let window = windows.iter().find(|w| w.id().unwrap_or(0) == id)?;
+                Some(SourceGeometry {
+                    name: window.title().unwrap_or_default(),
+                    x: window.x().unwrap_or(0),
+                    y: window.y().unwrap_or(0),
+                    width: window.width().unwrap_or(0),
+                    height: window.height().unwrap_or(0),
+                })
+            }
+        }
+    }
+
     /// Capture a single frame from the selected source
     pub async fn capture_frame(&self) -> Result<CapturedFrame, ScreenCaptureError> {
         let source = self.selected_source.read().await;
@@ -0,0 +1,178 @@
+//! Presenter hints
+//! Context sent next to the screen share frames: what is shared, at which
+//! resolution, whether the presenter paused, and where their pointer is. The
+//! pointer is a fraction of the shared source, so viewers can place it over
+//! the frames whatever size they show them at.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Hints are sent again this often when nothing changed, for viewers who
+/// joined meanwhile
+const HINTS_KEEPALIVE: Duration = Duration::from_secs(2);
+
+/// How often the source geometry is looked up again (a window may move)
+const GEOMETRY_REFRESH: Duration = Duration::from_secs(1);
+
+/// Pointer steps, as a fraction of the source (hides sub-pixel jitter)
+const POINTER_STEP: f64 = 1000.0;
+
+/// Name and place of the shared source on the desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceGeometry {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SourceGeometry {
+    /// Where a desktop position falls in the source (None outside)
+    pub fn pointer(&self, x: f64, y: f64) -> Option<PointerPosition> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let fx = (x - self.x as f64) / self.width as f64;
+        let fy = (y - self.y as f64) / self.height as f64;
+        let inside = (0.0..1.0).contains(&fx) && (0.0..1.0).contains(&fy);
+        inside.then(|| PointerPosition {
+            x: ((fx * POINTER_STEP).round() / POINTER_STEP) as f32,
+            y: ((fy * POINTER_STEP).round() / POINTER_STEP) as f32,
+        })
+    }
+}
+
+/// Pointer position from the top-left corner, 0.0 to 1.0 on both axes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointerPosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenterHints {
+    pub source_name: String,
+    /// Resolution of the source (frames may be scaled down)
+    pub width: u32,
+    pub height: u32,
+    /// Frames are held: viewers keep showing the last one
+    pub paused: bool,
+    /// None when the pointer is outside the shared source
+    pub pointer: Option<PointerPosition>,
+}
+
+/// Builds the hints of a stream and decides when to send them
+#[derive(Default)]
+pub struct HintsTracker {
+    geometry: Option<SourceGeometry>,
+    geometry_at: Option<Instant>,
+    last: Option<PresenterHints>,
+    sent_at: Option<Instant>,
+}
+
+impl HintsTracker {
+    /// Whether the source geometry should be looked up again
+    pub fn needs_geometry(&self, now: Instant) -> bool {
+        !self
+            .geometry_at
+            .is_some_and(|at| now.duration_since(at) < GEOMETRY_REFRESH)
+    }
+
+    pub fn set_geometry(&mut self, geometry: Option<SourceGeometry>, now: Instant) {
+        self.geometry = geometry;
+        self.geometry_at = Some(now);
+    }
+
+    /// Hints to send, when they changed or are due again
+    /// `resolution` is the size of the last captured frame, `cursor` the
+    /// pointer on the desktop.
+    pub fn update(
+        &mut self,
+        resolution: Option<(u32, u32)>,
+        paused: bool,
+        cursor: Option<(f64, f64)>,
+        now: Instant,
+    ) -> Option<PresenterHints> {
+        let geometry = self.geometry.as_ref()?;
+        let (width, height) = resolution.unwrap_or((geometry.width, geometry.height));
+        let hints = PresenterHints {
+            source_name: geometry.name.clone(),
+            width,
+            height,
+            paused,
+            pointer: cursor.and_then(|(x, y)| geometry.pointer(x, y)),
+        };
+
+        let due = !self
+            .sent_at
+            .is_some_and(|at| now.duration_since(at) < HINTS_KEEPALIVE);
+        if !due && self.last.as_ref() == Some(&hints) {
+            return None;
+        }
+        self.last = Some(hints.clone());
+        self.sent_at = Some(now);
+        Some(hints)
+    }
+}
+
+/// Pointer position on the desktop, in the units of the xcap geometry
+pub fn cursor_position(app: &AppHandle) -> Option<(f64, f64)> {
+    let position = app.cursor_position().ok()?;
+    // xcap measures macOS sources in points, tauri the pointer in pixels
+    #[cfg(target_os = "macos")]
+    if let Ok(Some(monitor)) = app.primary_monitor() {
+        let logical = position.to_logical::<f64>(monitor.scale_factor());
+        return Some((logical.x, logical.y));
+    }
+    Some((position.x, position.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointer_follows_the_source_and_hints_only_go_out_on_change() {
+        let geometry = SourceGeometry {
+            name: "Second écran".to_string(),
+            x: 1920,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        };
+        assert_eq!(
+            geometry.pointer(3200.0, 720.0),
+            Some(PointerPosition { x: 0.5, y: 0.5 })
+        );
+        assert_eq!(geometry.pointer(100.0, 720.0), None);
+
+        let start = Instant::now();
+        let mut tracker = HintsTracker::default();
+        assert!(tracker.needs_geometry(start));
+        tracker.set_geometry(Some(geometry), start);
+        assert!(!tracker.needs_geometry(start));
+
+        let hints = tracker
+            .update(Some((1280, 720)), false, Some((3200.0, 720.0)), start)
+            .unwrap();
+        assert_eq!((hints.width, hints.height), (1280, 720));
+        let later = start + Duration::from_millis(100);
+        assert_eq!(
+            tracker.update(Some((1280, 720)), false, Some((3200.0, 720.0)), later),
+            None
+        );
+        assert!(
+            tracker
+                .update(Some((1280, 720)), true, Some((3200.0, 720.0)), later)
+                .unwrap()
+                .paused
+        );
+        // Unchanged, but sent again for late joiners
+        let keepalive = later + HINTS_KEEPALIVE;
+        assert!(tracker
+            .update(Some((1280, 720)), true, Some((3200.0, 720.0)), keepalive)
+            .is_some());
+    }
+}
@@ -1,5 +1,9 @@
+mod backpressure;
 mod capture;
+mod hints;
 
 pub use capture::{
-    CaptureSource, CaptureSourceInfo, MonitorInfo, ScreenCapture, WindowInfo,
+    CaptureSource, CaptureSourceInfo, MonitorInfo, ScreenCapture, ScreenCaptureError, WindowInfo,
 };
+pub use backpressure::FrameAcks;
+pub use hints::{cursor_position, HintsTracker, PresenterHints};
@@ -1,10 +1,10 @@
 use parking_lot::RwLock;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::storage;
+
 #[derive(Error, Debug)]
 pub enum ServerError {
     #[error("Server not running")]
@@ -15,6 +15,12 @@ pub enum ServerError {
     AlreadyConnected,
     #[error("Config error: {0}")]
     ConfigError(String),
+    #[error("Invalid server code")]
+    InvalidCode,
+    #[error("Invite code expired")]
+    CodeExpired,
+    #[error("Invite not found")]
+    InviteNotFound,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +42,52 @@ pub struct ServerInfo {
     pub is_hosting: bool,
     pub username: String,
     pub peers: Vec<Peer>,
+    /// Adresse publique ouverte sur le routeur (hôte, UPnP / NAT-PMP)
+    #[serde(default)]
+    pub external_address: Option<String>,
+}
+
+/// Instantané du roster diffusé par l'hôte
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RosterSnapshot {
+    pub version: u64,
+    pub peers: Vec<Peer>,
+}
+
+/// Mise à jour incrémentale du roster
+#[derive(Debug, Clone)]
+pub enum RosterUpdate {
+    Joined(Peer),
+    Left(String),
+}
+
+/// Id du peer local dans le roster
+pub const LOCAL_PEER_ID: &str = "local";
+
+/// Code d'invitation temporaire ou à usage unique
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteCode {
+    pub code: String,
+    pub created_at: u64,
+    /// Expiration (timestamp en secondes), None = pas de limite de temps
+    pub expires_at: Option<u64>,
+    /// Nombre d'utilisations max, None = illimité
+    pub max_uses: Option<u32>,
+    pub uses: u32,
+}
+
+impl InviteCode {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.map(|t| now >= t).unwrap_or(false)
+            || self.max_uses.map(|max| self.uses >= max).unwrap_or(false)
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
 }
 
 /// Génère un code serveur de 6 caractères
@@ -50,34 +102,16 @@ fn generate_server_code() -> String {
         .collect()
 }
 
-/// Chemin vers le fichier de config
-fn config_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("hydrowland");
-    fs::create_dir_all(&config_dir).ok();
-    config_dir.join("server.json")
-}
+const CONFIG_FILE: &str = "server.json";
 
 /// Charger la config depuis le fichier
 fn load_config() -> Option<ServerConfig> {
-    let path = config_path();
-    if path.exists() {
-        let content = fs::read_to_string(&path).ok()?;
-        serde_json::from_str(&content).ok()
-    } else {
-        None
-    }
+    storage::load_json(CONFIG_FILE)
 }
 
 /// Sauvegarder la config dans le fichier
 fn save_config(config: &ServerConfig) -> Result<(), ServerError> {
-    let path = config_path();
-    let content = serde_json::to_string_pretty(config)
-        .map_err(|e| ServerError::ConfigError(e.to_string()))?;
-    fs::write(&path, content)
-        .map_err(|e| ServerError::ConfigError(e.to_string()))?;
-    Ok(())
+    storage::save_json(CONFIG_FILE, config).map_err(ServerError::ConfigError)
 }
 
 /// État global du serveur
@@ -86,7 +120,17 @@ pub struct ServerState {
     config: RwLock<Option<ServerConfig>>,
     is_hosting: RwLock<bool>,
     connected_to: RwLock<Option<String>>, // Code du serveur rejoint
-    peers: RwLock<Vec<Peer>>,
+    /// Id de l'hôte du serveur rejoint, fixé par le handshake fait avec le code
+    /// (seul ce peer fait autorité sur le roster)
+    host_peer: RwLock<Option<String>>,
+    /// Peers et version du roster (incrémentée par l'hôte à chaque changement),
+    /// sous un même verrou pour rester cohérents
+    roster: RwLock<RosterSnapshot>,
+    invites: RwLock<Vec<InviteCode>>,
+    /// Adresse publique du port redirigé par le routeur
+    external_address: RwLock<Option<String>>,
+    /// Sujet de la room (commande `/topic` de l'hôte)
+    topic: RwLock<Option<String>>,
 }
 
 impl ServerState {
@@ -96,7 +140,11 @@ impl ServerState {
             config: RwLock::new(config),
             is_hosting: RwLock::new(false),
             connected_to: RwLock::new(None),
-            peers: RwLock::new(Vec::new()),
+            host_peer: RwLock::new(None),
+            roster: RwLock::new(RosterSnapshot::default()),
+            invites: RwLock::new(Vec::new()),
+            external_address: RwLock::new(None),
+            topic: RwLock::new(None),
         }
     }
 
@@ -151,10 +199,10 @@ impl ServerState {
         *self.is_hosting.write() = true;
 
         // Ajouter l'hôte comme premier peer
-        let mut peers = self.peers.write();
-        peers.clear();
-        peers.push(Peer {
-            id: "local".to_string(),
+        let mut roster = self.roster.write();
+        roster.peers.clear();
+        roster.peers.push(Peer {
+            id: LOCAL_PEER_ID.to_string(),
             username: username.clone(),
             is_host: true,
         });
@@ -165,7 +213,8 @@ impl ServerState {
             code: config.code,
             is_hosting: true,
             username,
-            peers: peers.clone(),
+            peers: roster.peers.clone(),
+            external_address: None,
         })
     }
 
@@ -185,10 +234,10 @@ impl ServerState {
         self.get_or_create_config(username.clone());
 
         // Ajouter l'utilisateur local comme peer
-        let mut peers = self.peers.write();
-        peers.clear();
-        peers.push(Peer {
-            id: "local".to_string(),
+        let mut roster = self.roster.write();
+        roster.peers.clear();
+        roster.peers.push(Peer {
+            id: LOCAL_PEER_ID.to_string(),
             username: username.clone(),
             is_host: false,
         });
@@ -199,7 +248,8 @@ impl ServerState {
             code,
             is_hosting: false,
             username,
-            peers: peers.clone(),
+            peers: roster.peers.clone(),
+            external_address: None,
         })
     }
 
@@ -207,18 +257,27 @@ impl ServerState {
     pub fn disconnect(&self) -> Result<(), ServerError> {
         *self.is_hosting.write() = false;
         *self.connected_to.write() = None;
-        self.peers.write().clear();
+        *self.host_peer.write() = None;
+        *self.roster.write() = RosterSnapshot::default();
+        self.invites.write().clear();
+        *self.external_address.write() = None;
+        *self.topic.write() = None;
 
         tracing::info!("Disconnected from server");
         Ok(())
     }
 
+    /// Renseigner l'adresse publique du port redirigé (None : plus de redirection)
+    pub fn set_external_address(&self, address: Option<String>) {
+        *self.external_address.write() = address;
+    }
+
     /// Obtenir les infos du serveur actuel
     pub fn get_server_info(&self) -> Option<ServerInfo> {
         let config = self.config.read();
         let is_hosting = *self.is_hosting.read();
         let connected_to = self.connected_to.read().clone();
-        let peers = self.peers.read().clone();
+        let peers = self.roster.read().peers.clone();
 
         if is_hosting {
             config.as_ref().map(|cfg| ServerInfo {
@@ -226,6 +285,7 @@ impl ServerState {
                 is_hosting: true,
                 username: cfg.username.clone(),
                 peers,
+                external_address: self.external_address.read().clone(),
             })
         } else if let Some(code) = connected_to {
             config.as_ref().map(|cfg| ServerInfo {
@@ -233,27 +293,371 @@ impl ServerState {
                 is_hosting: false,
                 username: cfg.username.clone(),
                 peers,
+                external_address: None,
             })
         } else {
             None
         }
     }
 
-    /// Ajouter un peer
-    pub fn add_peer(&self, peer: Peer) {
-        let mut peers = self.peers.write();
-        if !peers.iter().any(|p| p.id == peer.id) {
-            peers.push(peer);
+    /// Ajouter un peer, retourne la nouvelle version du roster si ajouté
+    pub fn add_peer(&self, peer: Peer) -> Option<u64> {
+        let mut roster = self.roster.write();
+        if roster.peers.iter().any(|p| p.id == peer.id) {
+            return None;
         }
+        roster.peers.push(peer);
+        roster.version += 1;
+        Some(roster.version)
     }
 
-    /// Retirer un peer
-    pub fn remove_peer(&self, peer_id: &str) {
-        self.peers.write().retain(|p| p.id != peer_id);
+    /// Retirer un peer, retourne la nouvelle version du roster si retiré
+    pub fn remove_peer(&self, peer_id: &str) -> Option<u64> {
+        let mut roster = self.roster.write();
+        let before = roster.peers.len();
+        roster.peers.retain(|p| p.id != peer_id);
+        if roster.peers.len() == before {
+            return None;
+        }
+        roster.version += 1;
+        Some(roster.version)
+    }
+
+    /// Liste des peers (roster)
+    pub fn roster(&self) -> Vec<Peer> {
+        self.roster.read().peers.clone()
+    }
+
+    /// Instantané du roster (hôte)
+    pub fn roster_snapshot(&self) -> RosterSnapshot {
+        self.roster.read().clone()
+    }
+
+    /// Fixer l'hôte du serveur rejoint : le peer à qui l'offre part avec le
+    /// code `join_code` (celui du serveur rejoint)
+    pub fn pin_host(&self, peer_id: &str, join_code: &str) -> Result<(), ServerError> {
+        if self.is_hosting() {
+            return Err(ServerError::AlreadyHosting);
+        }
+        let joined = self
+            .connected_to
+            .read()
+            .as_ref()
+            .is_some_and(|code| code.eq_ignore_ascii_case(join_code));
+        if !joined {
+            return Err(ServerError::InvalidCode);
+        }
+        *self.host_peer.write() = Some(peer_id.to_string());
+        Ok(())
+    }
+
+    /// Appliquer l'instantané reçu de `host_peer_id`
+    /// `local_peer_id` est l'id que l'hôte nous donne dans le roster. Ignoré
+    /// (false) s'il ne vient pas de l'hôte fixé au handshake ou ne désigne pas
+    /// exactement un hôte.
+    pub fn apply_roster_snapshot(
+        &self,
+        host_peer_id: &str,
+        local_peer_id: &str,
+        snapshot: RosterSnapshot,
+    ) -> bool {
+        if !self.is_host_peer(host_peer_id) {
+            return false;
+        }
+        if snapshot.peers.iter().filter(|p| p.is_host).count() != 1 {
+            return false;
+        }
+        let peers = snapshot
+            .peers
+            .into_iter()
+            .map(|mut peer| {
+                if peer.is_host {
+                    peer.id = host_peer_id.to_string();
+                } else if peer.id == local_peer_id {
+                    peer.id = LOCAL_PEER_ID.to_string();
+                }
+                peer
+            })
+            .collect();
+
+        *self.roster.write() = RosterSnapshot {
+            version: snapshot.version,
+            peers,
+        };
+        true
+    }
+
+    /// Appliquer une mise à jour incrémentale reçue de l'hôte
+    /// Retourne false si une mise à jour a été manquée (il faut redemander un instantané)
+    pub fn apply_roster_update(&self, version: u64, update: RosterUpdate) -> bool {
+        let mut roster = self.roster.write();
+        if version <= roster.version {
+            return true;
+        }
+        if version != roster.version + 1 {
+            return false;
+        }
+
+        match update {
+            RosterUpdate::Joined(peer) => {
+                if !roster.peers.iter().any(|p| p.id == peer.id) {
+                    roster.peers.push(peer);
+                }
+            }
+            RosterUpdate::Left(peer_id) => roster.peers.retain(|p| p.id != peer_id),
+        }
+        roster.version = version;
+        true
+    }
+
+    /// Vérifier si `peer_id` est l'hôte du serveur rejoint
+    pub fn is_host_peer(&self, peer_id: &str) -> bool {
+        self.host_peer.read().as_deref() == Some(peer_id)
+    }
+
+    /// Sujet de la room (None si aucun)
+    pub fn topic(&self) -> Option<String> {
+        self.topic.read().clone()
+    }
+
+    /// Changer le sujet de la room (None pour le retirer)
+    pub fn set_topic(&self, topic: Option<String>) {
+        *self.topic.write() = topic;
     }
 
     /// Vérifier si connecté
     pub fn is_connected(&self) -> bool {
         *self.is_hosting.read() || self.connected_to.read().is_some()
     }
+
+    /// Vérifier si on héberge le serveur
+    pub fn is_hosting(&self) -> bool {
+        *self.is_hosting.read()
+    }
+
+    /// Code du serveur hébergé (None si on n'héberge pas)
+    pub fn hosted_code(&self) -> Option<String> {
+        if !self.is_hosting() {
+            return None;
+        }
+        self.config.read().as_ref().map(|cfg| cfg.code.clone())
+    }
+
+    /// Créer un code d'invitation (hôte uniquement)
+    pub fn create_invite(
+        &self,
+        ttl_secs: Option<u64>,
+        max_uses: Option<u32>,
+    ) -> Result<InviteCode, ServerError> {
+        if !self.is_hosting() {
+            return Err(ServerError::NotRunning);
+        }
+
+        let now = now_secs();
+        let invite = InviteCode {
+            code: generate_server_code(),
+            created_at: now,
+            expires_at: ttl_secs.map(|ttl| now + ttl),
+            max_uses,
+            uses: 0,
+        };
+        self.invites.write().push(invite.clone());
+
+        tracing::info!("Invite code created: {}", invite.code);
+        Ok(invite)
+    }
+
+    /// Lister les codes d'invitation actifs
+    pub fn list_invites(&self) -> Vec<InviteCode> {
+        let now = now_secs();
+        self.invites
+            .read()
+            .iter()
+            .filter(|i| !i.is_expired(now))
+            .cloned()
+            .collect()
+    }
+
+    /// Révoquer un code d'invitation
+    pub fn revoke_invite(&self, code: &str) -> Result<(), ServerError> {
+        let code = code.to_uppercase();
+        let mut invites = self.invites.write();
+        let before = invites.len();
+        invites.retain(|i| i.code != code);
+        if invites.len() == before {
+            return Err(ServerError::InviteNotFound);
+        }
+        Ok(())
+    }
+
+    /// Valider le code présenté par un peer avant le handshake, sans le consommer.
+    /// Quand on héberge, un peer sans code est refusé.
+    pub fn validate_join_code(&self, code: Option<&str>) -> Result<(), ServerError> {
+        match code {
+            Some(code) => self.redeem_join_code(code, false),
+            None if self.is_hosting() => Err(ServerError::InvalidCode),
+            None => Ok(()),
+        }
+    }
+
+    /// Consommer le code une fois le handshake réussi (revalidé : une autre
+    /// connexion a pu épuiser l'invitation entre-temps)
+    pub fn consume_join_code(&self, code: Option<&str>) -> Result<(), ServerError> {
+        match code {
+            Some(code) => self.redeem_join_code(code, true),
+            None => self.validate_join_code(None),
+        }
+    }
+
+    fn redeem_join_code(&self, code: &str, consume: bool) -> Result<(), ServerError> {
+        if !*self.is_hosting.read() {
+            return Err(ServerError::NotRunning);
+        }
+
+        let code = code.to_uppercase();
+        let is_server_code = self
+            .config
+            .read()
+            .as_ref()
+            .map(|cfg| cfg.code == code)
+            .unwrap_or(false);
+        if is_server_code {
+            return Ok(());
+        }
+
+        let now = now_secs();
+        let mut invites = self.invites.write();
+        let invite = invites
+            .iter_mut()
+            .find(|i| i.code == code)
+            .ok_or(ServerError::InvalidCode)?;
+
+        if invite.is_expired(now) {
+            return Err(ServerError::CodeExpired);
+        }
+        if consume {
+            invite.uses += 1;
+        }
+        Ok(())
+    }
+
+    /// Supprimer les invitations expirées, retourne les codes retirés
+    pub fn purge_expired_invites(&self) -> Vec<String> {
+        let now = now_secs();
+        let mut invites = self.invites.write();
+        let expired: Vec<String> = invites
+            .iter()
+            .filter(|i| i.is_expired(now))
+            .map(|i| i.code.clone())
+            .collect();
+        invites.retain(|i| !i.is_expired(now));
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: &str) -> Peer {
+        Peer {
+            id: id.to_string(),
+            username: id.to_string(),
+            is_host: false,
+        }
+    }
+
+    #[test]
+    fn test_roster_update_in_order() {
+        let state = ServerState::new();
+        assert!(state.apply_roster_update(1, RosterUpdate::Joined(peer("a"))));
+        assert!(state.apply_roster_update(2, RosterUpdate::Joined(peer("b"))));
+        assert!(state.apply_roster_update(3, RosterUpdate::Left("a".to_string())));
+        // Mise à jour déjà appliquée : ignorée
+        assert!(state.apply_roster_update(2, RosterUpdate::Joined(peer("b"))));
+
+        let roster = state.roster();
+        assert_eq!(roster.len(), 1);
+        assert_eq!(roster[0].id, "b");
+    }
+
+    /// Rejoint avec le code ABC123, l'hôte joint sous l'id `host`
+    fn joined() -> ServerState {
+        let state = ServerState::default();
+        *state.connected_to.write() = Some("ABC123".to_string());
+        state.pin_host("host", "abc123").unwrap();
+        state
+    }
+
+    fn named(id: &str, username: &str, is_host: bool) -> Peer {
+        Peer {
+            id: id.to_string(),
+            username: username.to_string(),
+            is_host,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_only_from_pinned_host() {
+        let state = ServerState::default();
+        *state.connected_to.write() = Some("ABC123".to_string());
+        assert!(state.pin_host("host", "XYZ789").is_err());
+        state.pin_host("host", "abc123").unwrap();
+
+        // Un autre peer envoie son instantané en premier et se dit hôte : rejeté
+        let forged = RosterSnapshot {
+            version: 1,
+            peers: vec![
+                named("mallory", "mallory", true),
+                named("me", "alice", false),
+            ],
+        };
+        assert!(!state.apply_roster_snapshot("mallory", "me", forged));
+        assert!(state.roster().is_empty());
+        assert!(!state.is_host_peer("mallory"));
+        assert!(state.is_host_peer("host"));
+    }
+
+    #[test]
+    fn test_snapshot_needs_exactly_one_host() {
+        let state = joined();
+        let no_host = RosterSnapshot {
+            version: 1,
+            peers: vec![named("me", "alice", false)],
+        };
+        assert!(!state.apply_roster_snapshot("host", "me", no_host));
+        let two_hosts = RosterSnapshot {
+            version: 1,
+            peers: vec![named("a", "a", true), named("b", "b", true)],
+        };
+        assert!(!state.apply_roster_snapshot("host", "me", two_hosts));
+        assert!(state.roster().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_remaps_host_and_local_peer() {
+        let state = joined();
+        // L'hôte se liste sous son propre id local ; un autre peer porte notre pseudo
+        let snapshot = RosterSnapshot {
+            version: 4,
+            peers: vec![
+                named(LOCAL_PEER_ID, "bob", true),
+                named("me", "alice", false),
+                named("other", "alice", false),
+            ],
+        };
+        assert!(state.apply_roster_snapshot("host", "me", snapshot));
+
+        let ids: Vec<_> = state.roster().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, ["host", LOCAL_PEER_ID, "other"]);
+        assert!(state.apply_roster_update(5, RosterUpdate::Left("other".to_string())));
+        assert_eq!(state.roster().len(), 2);
+    }
+
+    #[test]
+    fn test_roster_update_gap() {
+        let state = ServerState::new();
+        assert!(!state.apply_roster_update(2, RosterUpdate::Joined(peer("a"))));
+        assert!(state.roster().is_empty());
+    }
 }
@@ -0,0 +1,151 @@
+//! Session lifecycle
+//! One state machine for the whole call instead of booleans scattered across
+//! the server, mesh and peer counts. Every transition is emitted to the
+//! frontend; illegal ones are rejected with `SessionError`.
+//!
+//! Idle -> Signaling (host/join) -> Connecting (offer/answer exchanged)
+//! -> InCall (a peer connection is up) -> Reconnecting (every connection
+//! dropped) -> InCall ... -> Ended (disconnect) -> Signaling (next session)
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+
+use crate::error::AppResult;
+use crate::events::{self, SessionStateEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionPhase {
+    /// No session yet
+    Idle,
+    /// Hosting or joined, waiting for peers
+    Signaling,
+    /// Offer/answer exchanged, peer connections being established
+    Connecting,
+    /// At least one peer connection is up
+    InCall,
+    /// Every peer connection dropped, waiting for one to recover
+    Reconnecting,
+    /// Disconnected
+    Ended,
+}
+
+impl SessionPhase {
+    pub fn can_transition_to(self, to: SessionPhase) -> bool {
+        use SessionPhase::*;
+        matches!(
+            (self, to),
+            (Idle, Signaling)
+                | (Signaling, Connecting | InCall | Ended)
+                | (Connecting, InCall | Reconnecting | Signaling | Ended)
+                | (InCall, Reconnecting | Signaling | Ended)
+                | (Reconnecting, InCall | Signaling | Ended)
+                | (Ended, Signaling | Idle)
+        )
+    }
+
+    /// Hosting or joined (until disconnect)
+    pub fn is_active(self) -> bool {
+        !matches!(self, SessionPhase::Idle | SessionPhase::Ended)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("Illegal session transition from {from:?} to {to:?}")]
+    IllegalTransition { from: SessionPhase, to: SessionPhase },
+}
+
+/// Current phase of the session
+pub struct SessionState {
+    phase: RwLock<SessionPhase>,
+}
+
+impl SessionState {
+    pub fn new() -> Self {
+        Self {
+            phase: RwLock::new(SessionPhase::Idle),
+        }
+    }
+
+    pub fn phase(&self) -> SessionPhase {
+        *self.phase.read()
+    }
+
+    /// Fail if `to` is not reachable from the current phase (staying put is allowed)
+    pub fn check(&self, to: SessionPhase) -> Result<(), SessionError> {
+        let from = self.phase();
+        if from == to || from.can_transition_to(to) {
+            Ok(())
+        } else {
+            Err(SessionError::IllegalTransition { from, to })
+        }
+    }
+
+    /// Move to `to`, returning the previous phase (None when already there)
+    pub fn transition(&self, to: SessionPhase) -> Result<Option<SessionPhase>, SessionError> {
+        let mut phase = self.phase.write();
+        let from = *phase;
+        if from == to {
+            return Ok(None);
+        }
+        if !from.can_transition_to(to) {
+            return Err(SessionError::IllegalTransition { from, to });
+        }
+        *phase = to;
+        Ok(Some(from))
+    }
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Move the session to `to` and notify the frontend
+pub fn transition(app: &AppHandle, to: SessionPhase) -> AppResult<()> {
+    if let Some(from) = app.state::<SessionState>().transition(to)? {
+        tracing::info!("Session: {:?} -> {:?}", from, to);
+        let _ = events::emit(app, &SessionStateEvent { from, to });
+    }
+    Ok(())
+}
+
+/// Apply a transition driven by the network (peer connection changes), logging
+/// instead of failing when the session has moved on (e.g. already ended)
+pub fn follow(app: &AppHandle, to: SessionPhase) {
+    if let Err(e) = transition(app, to) {
+        tracing::debug!("Session transition ignored: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_lifecycle_and_illegal_transitions() {
+        let session = SessionState::new();
+        assert!(matches!(
+            session.transition(SessionPhase::InCall),
+            Err(SessionError::IllegalTransition { from: SessionPhase::Idle, .. })
+        ));
+
+        for phase in [
+            SessionPhase::Signaling,
+            SessionPhase::Connecting,
+            SessionPhase::InCall,
+            SessionPhase::Reconnecting,
+            SessionPhase::InCall,
+            SessionPhase::Ended,
+        ] {
+            assert!(session.transition(phase).unwrap().is_some());
+        }
+        assert_eq!(session.transition(SessionPhase::Ended).unwrap(), None);
+        assert!(session.check(SessionPhase::Connecting).is_err());
+        assert!(session.check(SessionPhase::Signaling).is_ok());
+    }
+}
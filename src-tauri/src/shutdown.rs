@@ -0,0 +1,109 @@
+//! Graceful shutdown
+//! On exit, peers are told we left and connections are closed instead of being
+//! dropped, so they don't sit waiting for ICE timeouts. Settings, bans and the
+//! event log are written on every change; only the log file is buffered.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Manager};
+
+#[cfg(feature = "transcription")]
+use crate::audio::Transcriber;
+#[cfg(feature = "audio")]
+use crate::commands::audio::AudioState;
+use crate::commands::audio_mesh::AudioMeshState;
+use crate::commands::port_mapping;
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::StreamingState;
+use crate::logging;
+use crate::power;
+#[cfg(feature = "audio")]
+use crate::recording::CallRecorder;
+use crate::server::ServerState;
+use crate::session::{self, SessionPhase, SessionState};
+#[cfg(feature = "screen")]
+use crate::video::PreviewServer;
+#[cfg(feature = "video")]
+use crate::video::VirtualCamera;
+#[cfg(feature = "whip")]
+use crate::webrtc::WhipPublisher;
+use crate::webrtc::{MeshManager, WebRTCManager};
+
+/// Longest we wait for peers to be notified and connections to close
+const LEAVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Tear everything down (runs once, from the exit handlers in `run()`)
+pub fn run(app: &AppHandle) {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::info!("Shutting down");
+
+    #[cfg(feature = "screen")]
+    if let Some(stream) = app.try_state::<ScreenStreamState>() {
+        stream.stop();
+    }
+    #[cfg(feature = "screen")]
+    if let Some(preview) = app.try_state::<PreviewServer>() {
+        preview.stop();
+    }
+    #[cfg(feature = "video")]
+    if let Some(camera) = app.try_state::<VirtualCamera>() {
+        camera.stop();
+    }
+
+    #[cfg(feature = "audio")]
+    {
+        // Finish the file while the taps still have the last frames
+        if let Some(recorder) = app.try_state::<CallRecorder>() {
+            if let Err(e) = recorder.stop() {
+                tracing::warn!("Failed to finish the call recording: {}", e);
+            }
+        }
+        if let Some(streaming) = app.try_state::<StreamingState>() {
+            streaming.stop_voice();
+        }
+        if let Some(audio) = app.try_state::<AudioState>() {
+            audio.stop_voice();
+        }
+    }
+    #[cfg(feature = "transcription")]
+    if let Some(transcriber) = app.try_state::<Transcriber>() {
+        transcriber.stop();
+    }
+
+    let audio_meshes = app.state::<AudioMeshState>().managers();
+    let mesh = app.state::<MeshManager>();
+    #[cfg(feature = "whip")]
+    let whip = app.state::<WhipPublisher>();
+    let leave = async {
+        // Ends the broadcast on the server rather than letting it time out
+        #[cfg(feature = "whip")]
+        whip.stop().await;
+        for manager in audio_meshes {
+            manager.leave().await;
+        }
+        mesh.leave().await;
+        // Connections are closed: the router can drop the forwarded port
+        port_mapping::stop(app).await;
+    };
+    if tauri::async_runtime::block_on(tokio::time::timeout(LEAVE_TIMEOUT, leave)).is_err() {
+        tracing::warn!("Peers did not close within {:?}", LEAVE_TIMEOUT);
+    }
+    app.state::<WebRTCManager>().close();
+
+    let _ = app.state::<ServerState>().disconnect();
+    if app.state::<SessionState>().phase().is_active() {
+        session::follow(app, SessionPhase::Ended);
+    }
+
+    power::release(app);
+
+    tracing::info!("Shutdown complete");
+    logging::flush();
+}
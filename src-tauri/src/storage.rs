@@ -0,0 +1,78 @@
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Mode invité : rien n'est lu ni écrit sur le disque
+static GUEST_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Fichiers du mode invité, gardés en mémoire uniquement
+static GUEST_FILES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// Activer le mode invité (à appeler avant tout accès au stockage)
+pub fn enable_guest_mode() {
+    GUEST_MODE.store(true, Ordering::SeqCst);
+}
+
+/// Vérifier si le mode invité est actif
+pub fn is_guest_mode() -> bool {
+    GUEST_MODE.load(Ordering::SeqCst)
+}
+
+fn guest_files() -> &'static Mutex<HashMap<String, String>> {
+    GUEST_FILES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Dossier de config de l'application
+pub fn config_dir() -> PathBuf {
+    let config_dir = dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("hydrowland");
+    fs::create_dir_all(&config_dir).ok();
+    config_dir
+}
+
+/// Chemin vers un fichier du dossier de config
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+/// Lire un fichier texte du dossier de config
+pub fn read_file(name: &str) -> Option<String> {
+    if is_guest_mode() {
+        return guest_files().lock().get(name).cloned();
+    }
+
+    let path = config_file(name);
+    if path.exists() {
+        fs::read_to_string(&path).ok()
+    } else {
+        None
+    }
+}
+
+/// Écrire un fichier texte dans le dossier de config
+pub fn write_file(name: &str, content: &str) -> Result<(), String> {
+    if is_guest_mode() {
+        guest_files().lock().insert(name.to_string(), content.to_string());
+        return Ok(());
+    }
+
+    fs::write(config_file(name), content).map_err(|e| e.to_string())
+}
+
+/// Charger un fichier JSON du dossier de config
+pub fn load_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let content = read_file(name)?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Sauvegarder une valeur en JSON dans le dossier de config
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    write_file(name, &content)
+}
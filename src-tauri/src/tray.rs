@@ -0,0 +1,183 @@
+//! System tray
+//! Tray icon with the call state and quick controls, usable while the window is hidden
+
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+#[cfg(feature = "screen")]
+use crate::commands::screen_stream::ScreenStreamState;
+#[cfg(feature = "audio")]
+use crate::commands::streaming::{self, StreamingState};
+use crate::events::{self, TrayAction, TrayActionEvent};
+use crate::i18n::{t, tf, Text};
+use crate::server::ServerState;
+
+const TRAY_ID: &str = "main";
+
+/// Menu items updated by `refresh`
+struct TrayMenu {
+    status: MenuItem<Wry>,
+    mute: CheckMenuItem<Wry>,
+    deafen: CheckMenuItem<Wry>,
+    stop_share: MenuItem<Wry>,
+    disconnect: MenuItem<Wry>,
+    show: MenuItem<Wry>,
+    quit: PredefinedMenuItem<Wry>,
+}
+
+/// Build the tray icon (call once from setup)
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let items = TrayMenu {
+        status: MenuItem::with_id(app, "tray_status", t(Text::TrayNotConnected), false, None::<&str>)?,
+        mute: CheckMenuItem::with_id(
+            app,
+            "tray_mute",
+            t(Text::TrayMute),
+            cfg!(feature = "audio"),
+            false,
+            None::<&str>,
+        )?,
+        deafen: CheckMenuItem::with_id(
+            app,
+            "tray_deafen",
+            t(Text::TrayDeafen),
+            cfg!(feature = "audio"),
+            false,
+            None::<&str>,
+        )?,
+        stop_share: MenuItem::with_id(
+            app,
+            "tray_stop_share",
+            t(Text::TrayStopShare),
+            false,
+            None::<&str>,
+        )?,
+        disconnect: MenuItem::with_id(
+            app,
+            "tray_disconnect",
+            t(Text::TrayDisconnect),
+            false,
+            None::<&str>,
+        )?,
+        show: MenuItem::with_id(app, "tray_show", t(Text::TrayShow), true, None::<&str>)?,
+        quit: PredefinedMenuItem::quit(app, Some(t(Text::Quit)))?,
+    };
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &items.status,
+            &PredefinedMenuItem::separator(app)?,
+            &items.mute,
+            &items.deafen,
+            &items.stop_share,
+            &items.disconnect,
+            &PredefinedMenuItem::separator(app)?,
+            &items.show,
+            &items.quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("HydrowLand")
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    app.manage(items);
+    refresh(app);
+    Ok(())
+}
+
+/// Sync the tray with the current connection, voice and screen share state
+pub fn refresh(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+
+    let server = app.state::<ServerState>().get_server_info();
+    #[cfg(feature = "audio")]
+    let (muted, deafened) = {
+        let streaming = app.state::<StreamingState>();
+        (streaming.service.is_muted(), streaming.service.is_deafened())
+    };
+    #[cfg(not(feature = "audio"))]
+    let (muted, deafened) = (false, false);
+    #[cfg(feature = "screen")]
+    let sharing = app.state::<ScreenStreamState>().is_streaming();
+    #[cfg(not(feature = "screen"))]
+    let sharing = false;
+
+    let status = match &server {
+        Some(info) if info.is_hosting => tf(Text::TrayHosting, &[("code", &info.code)]),
+        Some(info) => tf(Text::TrayConnected, &[("code", &info.code)]),
+        None => t(Text::TrayNotConnected).to_string(),
+    };
+
+    let _ = items.status.set_text(&status);
+    let _ = items.mute.set_checked(muted);
+    let _ = items.deafen.set_checked(deafened);
+    let _ = items.stop_share.set_enabled(sharing);
+    let _ = items.disconnect.set_enabled(server.is_some());
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let mut tooltip = format!("HydrowLand - {}", status);
+        if deafened {
+            tooltip.push_str(t(Text::TooltipDeafened));
+        } else if muted {
+            tooltip.push_str(t(Text::TooltipMuted));
+        }
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+/// Apply the current locale to the fixed labels (the status follows in `refresh`)
+pub fn relabel(app: &AppHandle) {
+    if let Some(items) = app.try_state::<TrayMenu>() {
+        let _ = items.mute.set_text(t(Text::TrayMute));
+        let _ = items.deafen.set_text(t(Text::TrayDeafen));
+        let _ = items.stop_share.set_text(t(Text::TrayStopShare));
+        let _ = items.disconnect.set_text(t(Text::TrayDisconnect));
+        let _ = items.show.set_text(t(Text::TrayShow));
+        let _ = items.quit.set_text(t(Text::Quit));
+    }
+    refresh(app);
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        #[cfg(feature = "audio")]
+        "tray_mute" => {
+            streaming::toggle_mute(app);
+        }
+        #[cfg(feature = "audio")]
+        "tray_deafen" => {
+            streaming::toggle_deafen(app);
+        }
+        "tray_stop_share" => request(app, TrayAction::StopScreenShare),
+        "tray_disconnect" => request(app, TrayAction::Disconnect),
+        "tray_show" => show_main_window(app),
+        _ => {}
+    }
+    // Check items toggle themselves on click; put them back in line with the real state
+    refresh(app);
+}
+
+/// Bring the main window to the front (also used when a second launch hands over)
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn request(app: &AppHandle, action: TrayAction) {
+    if let Err(e) = events::emit(app, &TrayActionEvent { action }) {
+        tracing::warn!("Failed to emit tray action: {}", e);
+    }
+}
@@ -9,6 +9,9 @@
 use image::{ImageBuffer, Rgba, ImageEncoder};
 use std::io::Cursor;
 
+use crate::error::{AppError, AppResult};
+use crate::perf::{self, Component};
+
 /// Video frame to be encoded
 #[derive(Debug, Clone)]
 pub struct VideoFrame {
@@ -99,14 +102,15 @@ impl VideoEncoder {
 
     /// Encode a video frame
     /// Returns the encoded data and whether it's a keyframe
-    pub fn encode(&mut self, frame: &VideoFrame) -> Result<EncodedFrame, String> {
+    pub fn encode(&mut self, frame: &VideoFrame) -> AppResult<EncodedFrame> {
+        let _span = perf::span(Component::ScreenEncode);
         let is_keyframe = self.should_be_keyframe();
         self.frame_count += 1;
 
         // Create image buffer from RGBA data
         let img: ImageBuffer<Rgba<u8>, Vec<u8>> =
             ImageBuffer::from_raw(frame.width, frame.height, frame.data.clone())
-                .ok_or_else(|| "Failed to create image buffer from frame data".to_string())?;
+                .ok_or_else(|| AppError::Video("Failed to create image buffer from frame data".to_string()))?;
 
         // Resize if needed
         let (target_width, target_height) = self.calculate_target_size(frame.width, frame.height);
@@ -130,7 +134,7 @@ impl VideoEncoder {
             rgb_img.width(),
             rgb_img.height(),
             image::ExtendedColorType::Rgb8,
-        ).map_err(|e| format!("JPEG encoding failed: {}", e))?;
+        ).map_err(|e| AppError::Video(format!("JPEG encoding failed: {}", e)))?;
 
         Ok(EncodedFrame {
             data: jpeg_data,
@@ -174,6 +178,13 @@ impl VideoEncoder {
         }
     }
 
+    /// Change the frame rate and bitrate of a running stream
+    pub fn set_rate(&mut self, fps: u32, bitrate_kbps: u32) {
+        self.config.fps = fps.max(1);
+        self.config.bitrate_kbps = bitrate_kbps;
+        self.keyframe_interval = (self.config.fps * 2) as u64;
+    }
+
     /// Reset frame counter (call when starting a new stream)
     pub fn reset(&mut self) {
         self.frame_count = 0;
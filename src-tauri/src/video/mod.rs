@@ -1,10 +1,19 @@
 //! Video module for screen sharing
-//! Handles VP8 encoding and WebRTC video tracks
+//! Handles VP8 encoding, WebRTC video tracks, the virtual camera output and
+//! the LAN preview server
 
 mod track;
 mod encoder;
+mod virtual_camera;
+#[cfg(feature = "screen")]
+mod preview_server;
 
 pub use encoder::{VideoEncoder, VideoFrame, EncoderConfig};
+#[cfg(feature = "screen")]
+pub use preview_server::{PreviewServer, PreviewServerConfig, PreviewServerStatus};
+pub use virtual_camera::{
+    VirtualCamera, VirtualCameraConfig, VirtualCameraSource, VirtualCameraStatus,
+};
 
 #[allow(dead_code, unused_imports)]
 pub use track::{LocalVideoTrack, VP8_PAYLOAD_TYPE, VP8_CLOCK_RATE};
@@ -0,0 +1,408 @@
+//! LAN preview server
+//! Serves our screen stream over plain HTTP so someone on the same network can
+//! watch the presentation in any browser (a smart TV, a phone) without
+//! HydrowLand: `/` is a viewer page, `/stream.mjpg` an MJPEG stream of the
+//! frames we already encode for peers and `/snapshot.jpg` the latest frame.
+//! Off by default and bound to localhost unless LAN viewers are allowed.
+//! Viewers always get the newest frame: a slow one skips frames, it never
+//! holds the others back.
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+use crate::error::{AppError, AppResult};
+
+pub const DEFAULT_PREVIEW_PORT: u16 = 8787;
+
+/// Simultaneous MJPEG viewers
+const MAX_VIEWERS: usize = 8;
+
+/// Longest request head we read (we only need the request line)
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// A client this slow to send its request or take a frame is dropped
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+const BOUNDARY: &str = "hydrowland-frame";
+
+const VIEWER_PAGE: &str = "<!DOCTYPE html>\n\
+<html lang=\"fr\"><head><meta charset=\"utf-8\">\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+<title>HydrowLand - Partage d'écran</title></head>\
+<body style=\"margin:0;background:#000\">\
+<img src=\"/stream.mjpg\" alt=\"Partage d'écran\" \
+style=\"display:block;width:100vw;height:100vh;object-fit:contain\">\
+</body></html>\n";
+
+/// Where the server listens
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewServerConfig {
+    pub port: u16,
+    /// Listen on every interface instead of localhost only
+    pub allow_lan: bool,
+}
+
+impl Default for PreviewServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PREVIEW_PORT,
+            allow_lan: false,
+        }
+    }
+}
+
+impl PreviewServerConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.port < 1024 {
+            return Err(AppError::InvalidArgument(
+                "Preview port must be between 1024 and 65535".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewServerStatus {
+    pub running: bool,
+    pub allow_lan: bool,
+    /// Addresses to open in a browser (the LAN one first when allowed)
+    pub urls: Vec<String>,
+    pub viewers: usize,
+    /// Frames handed to the server since it started
+    pub frames_published: u64,
+}
+
+/// Running server, its tasks stop when dropped
+struct Running {
+    config: PreviewServerConfig,
+    port: u16,
+    /// Dropping it closes the listener and every connection
+    _stop_tx: watch::Sender<()>,
+}
+
+/// Preview server, shared between the commands and the screen stream
+#[derive(Clone)]
+pub struct PreviewServer {
+    running: Arc<Mutex<Option<Running>>>,
+    frames: Arc<watch::Sender<Option<Bytes>>>,
+    viewers: Arc<AtomicUsize>,
+    frames_published: Arc<AtomicU64>,
+}
+
+impl Default for PreviewServer {
+    fn default() -> Self {
+        Self {
+            running: Arc::new(Mutex::new(None)),
+            frames: Arc::new(watch::channel(None).0),
+            viewers: Arc::new(AtomicUsize::new(0)),
+            frames_published: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl PreviewServer {
+    /// Listen with `config`, replacing the running server if any
+    pub async fn start(&self, config: PreviewServerConfig) -> AppResult<PreviewServerStatus> {
+        config.validate()?;
+        self.stop();
+
+        let ip = if config.allow_lan {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        let listener = TcpListener::bind((ip, config.port)).await.map_err(|e| {
+            AppError::Internal(format!("Failed to listen on port {}: {}", config.port, e))
+        })?;
+        let port = listener
+            .local_addr()
+            .map(|a| a.port())
+            .unwrap_or(config.port);
+
+        let (stop_tx, stop_rx) = watch::channel(());
+        self.frames_published.store(0, Ordering::Relaxed);
+        tokio::spawn(accept_loop(listener, self.clone(), stop_rx));
+
+        tracing::info!(
+            "Screen preview server listening on {}:{}",
+            IpAddr::V4(ip),
+            port
+        );
+        *self.running.lock() = Some(Running {
+            config,
+            port,
+            _stop_tx: stop_tx,
+        });
+        Ok(self.status())
+    }
+
+    /// Stop listening and disconnect the viewers
+    pub fn stop(&self) {
+        if let Some(running) = self.running.lock().take() {
+            tracing::info!("Screen preview server on port {} stopped", running.port);
+        }
+        self.frames.send_replace(None);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.lock().is_some()
+    }
+
+    /// Hand over an encoded JPEG frame of our screen stream
+    pub fn publish(&self, jpeg: &[u8]) {
+        if !self.is_running() {
+            return;
+        }
+        self.frames.send_replace(Some(Bytes::copy_from_slice(jpeg)));
+        self.frames_published.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The screen stream ended: viewers keep showing its last frame, snapshots
+    /// report no stream until the next one
+    pub fn clear(&self) {
+        self.frames.send_replace(None);
+    }
+
+    pub fn status(&self) -> PreviewServerStatus {
+        let running = self.running.lock();
+        let (allow_lan, urls) = match running.as_ref() {
+            Some(r) => {
+                let mut urls = Vec::new();
+                if r.config.allow_lan {
+                    if let Some(ip) = lan_address() {
+                        urls.push(format!("http://{}:{}/", ip, r.port));
+                    }
+                }
+                urls.push(format!("http://localhost:{}/", r.port));
+                (r.config.allow_lan, urls)
+            }
+            None => (false, Vec::new()),
+        };
+        PreviewServerStatus {
+            running: running.is_some(),
+            allow_lan,
+            urls,
+            viewers: self.viewers.load(Ordering::Relaxed),
+            frames_published: self.frames_published.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Address of the interface holding the default route
+/// Connecting a UDP socket only picks the route, nothing is sent.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    server: PreviewServer,
+    mut stop_rx: watch::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((socket, addr)) => {
+                    let server = server.clone();
+                    let stop_rx = stop_rx.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve(socket, addr, &server, stop_rx).await {
+                            tracing::debug!("Preview viewer {} disconnected: {}", addr, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Preview server failed to accept a viewer: {}", e),
+            },
+            // Stopped: the sender was dropped
+            _ = stop_rx.changed() => break,
+        }
+    }
+}
+
+async fn serve(
+    mut socket: TcpStream,
+    addr: SocketAddr,
+    server: &PreviewServer,
+    stop_rx: watch::Receiver<()>,
+) -> std::io::Result<()> {
+    let head = match timeout(IO_TIMEOUT, read_request_head(&mut socket)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+    let Some(path) = parse_request_line(&head) else {
+        return respond(
+            &mut socket,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"GET only\n",
+        )
+        .await;
+    };
+    tracing::debug!("Preview request from {}: {}", addr, path);
+
+    match path {
+        "/" | "/index.html" => {
+            respond(
+                &mut socket,
+                "200 OK",
+                "text/html; charset=utf-8",
+                VIEWER_PAGE.as_bytes(),
+            )
+            .await
+        }
+        "/snapshot.jpg" => {
+            let frame = server.frames.borrow().clone();
+            match frame {
+                Some(jpeg) => respond(&mut socket, "200 OK", "image/jpeg", &jpeg).await,
+                None => {
+                    respond(
+                        &mut socket,
+                        "503 Service Unavailable",
+                        "text/plain",
+                        b"No screen stream\n",
+                    )
+                    .await
+                }
+            }
+        }
+        "/stream.mjpg" => stream(socket, server, stop_rx).await,
+        _ => respond(&mut socket, "404 Not Found", "text/plain", b"Not found\n").await,
+    }
+}
+
+/// Counts a viewer for as long as it is held
+struct Viewer(Arc<AtomicUsize>);
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+async fn stream(
+    mut socket: TcpStream,
+    server: &PreviewServer,
+    mut stop_rx: watch::Receiver<()>,
+) -> std::io::Result<()> {
+    let viewers = server.viewers.fetch_add(1, Ordering::Relaxed);
+    let _viewer = Viewer(server.viewers.clone());
+    if viewers >= MAX_VIEWERS {
+        return respond(
+            &mut socket,
+            "503 Service Unavailable",
+            "text/plain",
+            b"Too many viewers\n",
+        )
+        .await;
+    }
+
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\n\
+         Cache-Control: no-cache, no-store\r\nConnection: close\r\n\r\n",
+        BOUNDARY
+    );
+    write(&mut socket, head.as_bytes()).await?;
+
+    let mut frames = server.frames.subscribe();
+    loop {
+        let frame = frames.borrow_and_update().clone();
+        if let Some(jpeg) = frame {
+            let part = format!(
+                "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                BOUNDARY,
+                jpeg.len()
+            );
+            write(&mut socket, part.as_bytes()).await?;
+            write(&mut socket, &jpeg).await?;
+            write(&mut socket, b"\r\n").await?;
+        }
+        tokio::select! {
+            changed = frames.changed() => if changed.is_err() { break },
+            _ = stop_rx.changed() => break,
+        }
+    }
+    Ok(())
+}
+
+async fn respond(
+    socket: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-cache, no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    write(socket, head.as_bytes()).await?;
+    write(socket, body).await?;
+    socket.shutdown().await
+}
+
+/// Write with a deadline, so a stalled viewer cannot hold a task forever
+async fn write(socket: &mut TcpStream, data: &[u8]) -> std::io::Result<()> {
+    timeout(IO_TIMEOUT, socket.write_all(data))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "viewer stalled"))?
+}
+
+/// Read up to the end of the request head
+async fn read_request_head(socket: &mut TcpStream) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() >= MAX_REQUEST_BYTES {
+            break;
+        }
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Path of a GET request, without its query string
+fn parse_request_line(head: &str) -> Option<&str> {
+    let mut parts = head.lines().next()?.split_whitespace();
+    let (method, target) = (parts.next()?, parts.next()?);
+    if method != "GET" || !parts.next()?.starts_with("HTTP/1.") {
+        return None;
+    }
+    target.split('?').next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_get_request_paths() {
+        assert_eq!(
+            parse_request_line("GET /stream.mjpg?t=1 HTTP/1.1\r\nHost: tv\r\n\r\n"),
+            Some("/stream.mjpg")
+        );
+        assert_eq!(parse_request_line("GET / HTTP/1.0\r\n\r\n"), Some("/"));
+        assert_eq!(parse_request_line("POST / HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(parse_request_line("GET /\r\n"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+}
@@ -3,19 +3,33 @@
 //! WebRTC video track for screen sharing
 
 use std::sync::Arc;
+use std::time::Instant;
 use parking_lot::Mutex;
 use webrtc::api::media_engine::MediaEngine;
 use webrtc::rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType};
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocalWriter;
+use webrtc::rtp::header::Header as RtpHeader;
 use webrtc::rtp::packet::Packet as RtpPacket;
 
+use crate::error::{AppError, AppResult};
+#[cfg(debug_assertions)]
+use crate::netsim;
+use crate::perf::{self, Component};
+use crate::webrtc::PayloadPool;
+
 /// VP8 payload type (dynamic, typically 96)
 pub const VP8_PAYLOAD_TYPE: u8 = 96;
 
 /// RTP clock rate for VP8 is 90000 Hz
 pub const VP8_CLOCK_RATE: u32 = 90000;
 
+/// VP8 RTP payload max size (leave room for VP8 payload descriptor)
+const MAX_PAYLOAD_SIZE: usize = 1200;
+
+/// Payload bytes kept for packets in flight (a few large keyframes)
+const PAYLOAD_POOL_BYTES: usize = 512 * 1024;
+
 /// Video track for sending screen share via WebRTC
 pub struct LocalVideoTrack {
     track: Arc<TrackLocalStaticRTP>,
@@ -23,12 +37,15 @@ pub struct LocalVideoTrack {
     timestamp: Mutex<u32>,
     ssrc: u32,
     frame_duration: u32, // in clock ticks (90000 Hz)
+    /// Header fields that never change, copied into every packet
+    header: RtpHeader,
+    payloads: PayloadPool,
 }
 
 impl LocalVideoTrack {
     /// Create a new local video track
     /// fps: target frames per second (e.g., 15)
-    pub fn new(track_id: &str, stream_id: &str, fps: u32) -> Result<Self, String> {
+    pub fn new(track_id: &str, stream_id: &str, fps: u32) -> AppResult<Self> {
         let track = Arc::new(TrackLocalStaticRTP::new(
             RTCRtpCodecCapability {
                 mime_type: "video/VP8".to_owned(),
@@ -54,6 +71,15 @@ impl LocalVideoTrack {
             timestamp: Mutex::new(rand::random::<u32>()),
             ssrc,
             frame_duration,
+            header: RtpHeader {
+                version: 2,
+                padding: false,
+                extension: false,
+                payload_type: VP8_PAYLOAD_TYPE,
+                ssrc,
+                ..Default::default()
+            },
+            payloads: PayloadPool::with_capacity(PAYLOAD_POOL_BYTES),
         })
     }
 
@@ -64,51 +90,45 @@ impl LocalVideoTrack {
 
     /// Send encoded VP8 video frame
     /// For large frames, this handles fragmentation into multiple RTP packets
-    pub async fn send_frame(&self, vp8_data: &[u8], is_keyframe: bool) -> Result<(), String> {
+    pub async fn send_frame(&self, vp8_data: &[u8], is_keyframe: bool) -> AppResult<()> {
         if vp8_data.is_empty() {
             return Ok(());
         }
 
-        // VP8 RTP payload max size (leave room for VP8 payload descriptor)
-        const MAX_PAYLOAD_SIZE: usize = 1200;
-
-        let chunks: Vec<&[u8]> = vp8_data.chunks(MAX_PAYLOAD_SIZE).collect();
-        let num_chunks = chunks.len();
+        let started = Instant::now();
+        let num_chunks = vp8_data.len().div_ceil(MAX_PAYLOAD_SIZE);
 
-        for (i, chunk) in chunks.iter().enumerate() {
+        for (i, chunk) in vp8_data.chunks(MAX_PAYLOAD_SIZE).enumerate() {
             let is_first = i == 0;
             let is_last = i == num_chunks - 1;
 
             // Build VP8 payload with descriptor
-            let payload = Self::build_vp8_payload(chunk, is_first, is_keyframe);
+            let descriptor = Self::vp8_descriptor(is_first, is_keyframe);
+            let payload = self.payloads.payload(&[descriptor], chunk);
 
             let packet = {
                 let mut seq = self.sequence_number.lock();
                 let ts = self.timestamp.lock();
 
-                let packet = RtpPacket {
-                    header: webrtc::rtp::header::Header {
-                        version: 2,
-                        padding: false,
-                        extension: false,
-                        marker: is_last, // Marker bit indicates end of frame
-                        payload_type: VP8_PAYLOAD_TYPE,
-                        sequence_number: *seq,
-                        timestamp: *ts,
-                        ssrc: self.ssrc,
-                        ..Default::default()
-                    },
-                    payload: bytes::Bytes::from(payload),
-                };
+                let mut header = self.header.clone();
+                header.marker = is_last; // Marker bit indicates end of frame
+                header.sequence_number = *seq;
+                header.timestamp = *ts;
+                let packet = RtpPacket { header, payload };
 
                 *seq = seq.wrapping_add(1);
                 packet
             };
 
+            #[cfg(debug_assertions)]
+            if netsim::intercept(&self.track, &packet) {
+                continue;
+            }
+
             self.track
                 .write_rtp(&packet)
                 .await
-                .map_err(|e| format!("Failed to write RTP video packet: {}", e))?;
+                .map_err(|e| AppError::Video(format!("Failed to write RTP video packet: {}", e)))?;
         }
 
         // Increment timestamp for next frame
@@ -117,12 +137,14 @@ impl LocalVideoTrack {
             *ts = ts.wrapping_add(self.frame_duration);
         }
 
+        // write_rtp only awaits short internal locks: close enough to CPU time
+        perf::record(Component::WebRtc, started.elapsed());
         Ok(())
     }
 
-    /// Build VP8 RTP payload descriptor + data
+    /// VP8 RTP payload descriptor, sent before the data of each packet
     /// See RFC 7741 for VP8 RTP payload format
-    fn build_vp8_payload(data: &[u8], is_start: bool, _is_keyframe: bool) -> Vec<u8> {
+    fn vp8_descriptor(is_start: bool, _is_keyframe: bool) -> u8 {
         // Simple VP8 payload descriptor (1 byte)
         // X: 0 (no extensions)
         // R: 0 (reserved)
@@ -137,10 +159,7 @@ impl LocalVideoTrack {
         // For keyframes, we don't set any special bits in the simple descriptor
         // The keyframe indication is in the VP8 bitstream itself
 
-        let mut payload = Vec::with_capacity(1 + data.len());
-        payload.push(descriptor);
-        payload.extend_from_slice(data);
-        payload
+        descriptor
     }
 
     /// Get current timestamp (for synchronization)
@@ -150,7 +169,7 @@ impl LocalVideoTrack {
 }
 
 /// Configure MediaEngine with VP8 codec for video
-pub fn register_video_codec(m: &mut MediaEngine) -> Result<(), String> {
+pub fn register_video_codec(m: &mut MediaEngine) -> AppResult<()> {
     m.register_codec(
         RTCRtpCodecParameters {
             capability: RTCRtpCodecCapability {
@@ -165,7 +184,7 @@ pub fn register_video_codec(m: &mut MediaEngine) -> Result<(), String> {
         },
         RTPCodecType::Video,
     )
-    .map_err(|e| format!("Failed to register VP8 codec: {}", e))?;
+    .map_err(|e| AppError::Video(format!("Failed to register VP8 codec: {}", e)))?;
 
     Ok(())
 }
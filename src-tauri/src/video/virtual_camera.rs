@@ -0,0 +1,463 @@
+//! Virtual camera output
+//! Pushes a screen share (a received one or our own) into a virtual camera
+//! device, so other apps (OBS, browsers, meeting apps) can pick it up as a
+//! webcam. Frames are letterboxed to the device size and converted to YUYV on
+//! a writer thread; while it is busy, new frames are dropped rather than queued.
+//! Linux writes to a v4l2loopback device (what OBS's virtual camera uses there);
+//! other platforms have no output yet.
+
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use super::VideoFrame;
+use crate::error::{AppError, AppResult};
+
+/// Frames waiting for the writer thread
+const QUEUE_FRAMES: usize = 2;
+
+/// Screen share feeding the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VirtualCameraSource {
+    /// Our own screen stream
+    Local,
+    /// A peer's screen share, pushed by the viewer
+    Remote,
+}
+
+/// Output device and picture size
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VirtualCameraConfig {
+    /// Device path (e.g. `/dev/video10`); None picks the first loopback device
+    pub device: Option<String>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for VirtualCameraConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+impl VirtualCameraConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        if !(160..=3840).contains(&self.width) || !(120..=2160).contains(&self.height) {
+            return Err(AppError::InvalidArgument(
+                "Virtual camera size must be between 160x120 and 3840x2160".to_string(),
+            ));
+        }
+        // YUYV stores two pixels per chroma sample
+        if self.width % 2 != 0 {
+            return Err(AppError::InvalidArgument(
+                "Virtual camera width must be even".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VirtualCameraStatus {
+    pub active: bool,
+    pub source: Option<VirtualCameraSource>,
+    pub device: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub frames_written: u64,
+    /// Frames dropped because the writer was still busy with the previous one
+    pub frames_dropped: u64,
+}
+
+enum Frame {
+    Rgba(VideoFrame),
+    Jpeg(Vec<u8>),
+}
+
+/// Device accepting packed YUYV frames of the configured size
+trait FrameSink: Send {
+    fn write_frame(&mut self, yuyv: &[u8]) -> std::io::Result<()>;
+}
+
+/// Running output, its writer thread stops when dropped
+struct Output {
+    source: VirtualCameraSource,
+    device: String,
+    config: VirtualCameraConfig,
+    tx: Option<SyncSender<Frame>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        // The writer exits once the queue is closed
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Virtual camera output, shared between the commands and the screen stream
+#[derive(Clone, Default)]
+pub struct VirtualCamera {
+    output: Arc<Mutex<Option<Output>>>,
+    frames_written: Arc<AtomicU64>,
+    frames_dropped: Arc<AtomicU64>,
+}
+
+impl VirtualCamera {
+    /// Open the device and start writing frames of `source` to it, replacing
+    /// the running output if any
+    pub fn start(&self, source: VirtualCameraSource, config: VirtualCameraConfig) -> AppResult<()> {
+        config.validate()?;
+        self.stop();
+
+        let (device, sink) = open_sink(config.device.as_deref(), config.width, config.height)?;
+        self.frames_written.store(0, Ordering::Relaxed);
+        self.frames_dropped.store(0, Ordering::Relaxed);
+
+        let (tx, rx) = mpsc::sync_channel(QUEUE_FRAMES);
+        let thread = {
+            let (width, height) = (config.width, config.height);
+            let frames_written = self.frames_written.clone();
+            std::thread::Builder::new()
+                .name("virtual-camera".to_string())
+                .spawn(move || run(rx, sink, width, height, &frames_written))
+                .map_err(|e| {
+                    AppError::Video(format!("Failed to spawn virtual camera writer: {}", e))
+                })?
+        };
+
+        tracing::info!(
+            "Virtual camera started on {} ({}x{}, {:?} screen)",
+            device,
+            config.width,
+            config.height,
+            source
+        );
+        *self.output.lock() = Some(Output {
+            source,
+            device,
+            config,
+            tx: Some(tx),
+            thread: Some(thread),
+        });
+        Ok(())
+    }
+
+    /// Stop the output and close the device
+    pub fn stop(&self) {
+        // Joined outside the lock: the writer may be finishing a frame
+        let output = self.output.lock().take();
+        if let Some(output) = output {
+            tracing::info!("Virtual camera on {} stopped", output.device);
+        }
+    }
+
+    /// Whether frames of `source` are wanted (saves copying them otherwise)
+    pub fn wants(&self, source: VirtualCameraSource) -> bool {
+        self.output
+            .lock()
+            .as_ref()
+            .is_some_and(|o| o.source == source)
+    }
+
+    /// Queue a raw frame of our own screen stream
+    pub fn push_local(&self, frame: &VideoFrame) {
+        if self.wants(VirtualCameraSource::Local) {
+            self.send(VirtualCameraSource::Local, Frame::Rgba(frame.clone()));
+        }
+    }
+
+    /// Queue a received JPEG frame
+    pub fn push_remote(&self, jpeg: Vec<u8>) {
+        self.send(VirtualCameraSource::Remote, Frame::Jpeg(jpeg));
+    }
+
+    fn send(&self, source: VirtualCameraSource, frame: Frame) {
+        let output = self.output.lock();
+        let Some(tx) = output
+            .as_ref()
+            .filter(|o| o.source == source)
+            .and_then(|o| o.tx.as_ref())
+        else {
+            return;
+        };
+        // Busy, or the writer gave up on the device
+        if tx.try_send(frame).is_err() {
+            self.frames_dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn status(&self) -> VirtualCameraStatus {
+        let output = self.output.lock();
+        let config = output
+            .as_ref()
+            .map(|o| o.config.clone())
+            .unwrap_or_default();
+        VirtualCameraStatus {
+            active: output
+                .as_ref()
+                .and_then(|o| o.thread.as_ref())
+                .is_some_and(|t| !t.is_finished()),
+            source: output.as_ref().map(|o| o.source),
+            device: output.as_ref().map(|o| o.device.clone()),
+            width: config.width,
+            height: config.height,
+            frames_written: self.frames_written.load(Ordering::Relaxed),
+            frames_dropped: self.frames_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Writer thread: decode, letterbox, convert and write each frame
+fn run(
+    rx: Receiver<Frame>,
+    mut sink: Box<dyn FrameSink>,
+    width: u32,
+    height: u32,
+    frames_written: &AtomicU64,
+) {
+    let mut yuyv = vec![0u8; width as usize * height as usize * 2];
+    for frame in rx {
+        let image = match frame {
+            Frame::Rgba(frame) => RgbaImage::from_raw(frame.width, frame.height, frame.data),
+            Frame::Jpeg(data) => image::load_from_memory_with_format(&data, ImageFormat::Jpeg)
+                .map(|image| image.to_rgba8())
+                .map_err(|e| tracing::debug!("Dropping undecodable frame: {}", e))
+                .ok(),
+        };
+        let Some(image) = image else {
+            continue;
+        };
+
+        let fitted = letterbox(&image, width, height);
+        rgba_to_yuyv(fitted.as_raw(), &mut yuyv);
+        if let Err(e) = sink.write_frame(&yuyv) {
+            tracing::warn!("Virtual camera device failed, stopping output: {}", e);
+            break;
+        }
+        frames_written.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Scale `image` to fit `width`x`height`, centered on black
+fn letterbox(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    if (w, h) == (width, height) {
+        return image.clone();
+    }
+    let scale = (width as f32 / w as f32).min(height as f32 / h as f32);
+    let fit_w = ((w as f32 * scale) as u32).clamp(1, width);
+    let fit_h = ((h as f32 * scale) as u32).clamp(1, height);
+    let scaled = imageops::resize(image, fit_w, fit_h, imageops::FilterType::Triangle);
+
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    imageops::overlay(
+        &mut canvas,
+        &scaled,
+        ((width - fit_w) / 2) as i64,
+        ((height - fit_h) / 2) as i64,
+    );
+    canvas
+}
+
+/// Convert RGBA pixels to packed YUYV (BT.601, limited range), averaging the
+/// chroma of each pixel pair
+fn rgba_to_yuyv(rgba: &[u8], yuyv: &mut [u8]) {
+    let luma = |p: &[u8]| {
+        let (r, g, b) = (p[0] as i32, p[1] as i32, p[2] as i32);
+        (16 + ((66 * r + 129 * g + 25 * b + 128) >> 8)) as u8
+    };
+    for (pair, out) in rgba.chunks_exact(8).zip(yuyv.chunks_exact_mut(4)) {
+        let r = (pair[0] as i32 + pair[4] as i32) / 2;
+        let g = (pair[1] as i32 + pair[5] as i32) / 2;
+        let b = (pair[2] as i32 + pair[6] as i32) / 2;
+        out[0] = luma(&pair[..4]);
+        out[1] = (128 + ((-38 * r - 74 * g + 112 * b + 128) >> 8)) as u8;
+        out[2] = luma(&pair[4..]);
+        out[3] = (128 + ((112 * r - 94 * g - 18 * b + 128) >> 8)) as u8;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_sink(
+    device: Option<&str>,
+    width: u32,
+    height: u32,
+) -> AppResult<(String, Box<dyn FrameSink>)> {
+    let (device, sink) = v4l2::Loopback::open(device, width, height)?;
+    Ok((device, Box::new(sink)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_sink(
+    _device: Option<&str>,
+    _width: u32,
+    _height: u32,
+) -> AppResult<(String, Box<dyn FrameSink>)> {
+    Err(AppError::Video(
+        "Virtual camera output is only available on Linux (v4l2loopback)".to_string(),
+    ))
+}
+
+/// v4l2loopback output: set the format once, then `write()` whole frames
+#[cfg(target_os = "linux")]
+mod v4l2 {
+    use std::fs::{File, OpenOptions};
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+
+    use super::FrameSink;
+    use crate::error::{AppError, AppResult};
+
+    /// Loopback devices are the only virtual video4linux devices
+    const SYSFS_VIRTUAL_DEVICES: &str = "/sys/devices/virtual/video4linux";
+
+    const V4L2_BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
+    const V4L2_FIELD_NONE: u32 = 1;
+    const V4L2_COLORSPACE_SMPTE170M: u32 = 1;
+    const V4L2_PIX_FMT_YUYV: u32 = u32::from_le_bytes(*b"YUYV");
+
+    /// `struct v4l2_pix_format`
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    #[allow(dead_code)] // Read by the kernel
+    struct PixFormat {
+        width: u32,
+        height: u32,
+        pixelformat: u32,
+        field: u32,
+        bytesperline: u32,
+        sizeimage: u32,
+        colorspace: u32,
+        priv_: u32,
+        flags: u32,
+        ycbcr_enc: u32,
+        quantization: u32,
+        xfer_func: u32,
+    }
+
+    /// `struct v4l2_format`: the union is 200 bytes, pointer-aligned
+    #[repr(C)]
+    #[allow(dead_code)] // Read by the kernel
+    union FormatData {
+        pix: PixFormat,
+        raw: [libc::c_ulong; 200 / std::mem::size_of::<libc::c_ulong>()],
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)] // Read by the kernel
+    struct Format {
+        buf_type: u32,
+        fmt: FormatData,
+    }
+
+    /// `_IOWR('V', 5, struct v4l2_format)`
+    const VIDIOC_S_FMT: libc::c_ulong = (3 << 30)
+        | ((std::mem::size_of::<Format>() as libc::c_ulong) << 16)
+        | ((b'V' as libc::c_ulong) << 8)
+        | 5;
+
+    pub struct Loopback {
+        file: File,
+    }
+
+    impl Loopback {
+        /// Open `device` (or the first loopback device) for `width`x`height` YUYV
+        pub fn open(device: Option<&str>, width: u32, height: u32) -> AppResult<(String, Self)> {
+            let path = match device {
+                Some(device) => device.to_string(),
+                None => find_device()?,
+            };
+            let file = OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .map_err(|e| AppError::DeviceUnavailable(format!("{}: {}", path, e)))?;
+
+            let mut format = Format {
+                buf_type: V4L2_BUF_TYPE_VIDEO_OUTPUT,
+                fmt: FormatData {
+                    pix: PixFormat {
+                        width,
+                        height,
+                        pixelformat: V4L2_PIX_FMT_YUYV,
+                        field: V4L2_FIELD_NONE,
+                        bytesperline: width * 2,
+                        sizeimage: width * height * 2,
+                        colorspace: V4L2_COLORSPACE_SMPTE170M,
+                        priv_: 0,
+                        flags: 0,
+                        ycbcr_enc: 0,
+                        quantization: 0,
+                        xfer_func: 0,
+                    },
+                },
+            };
+            // SAFETY: `format` matches the kernel's `struct v4l2_format` layout and
+            // outlives the call; the fd is open for the duration of the call
+            let result = unsafe { libc::ioctl(file.as_raw_fd(), VIDIOC_S_FMT as _, &mut format) };
+            if result < 0 {
+                return Err(AppError::Video(format!(
+                    "{} rejected the {}x{} YUYV format: {}",
+                    path,
+                    width,
+                    height,
+                    std::io::Error::last_os_error()
+                )));
+            }
+            Ok((path, Self { file }))
+        }
+    }
+
+    impl FrameSink for Loopback {
+        fn write_frame(&mut self, yuyv: &[u8]) -> std::io::Result<()> {
+            self.file.write_all(yuyv)
+        }
+    }
+
+    fn find_device() -> AppResult<String> {
+        let mut numbers: Vec<u32> = std::fs::read_dir(SYSFS_VIRTUAL_DEVICES)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| e.file_name().to_str()?.strip_prefix("video")?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        numbers.sort_unstable();
+        numbers
+            .first()
+            .map(|n| format!("/dev/video{}", n))
+            .ok_or_else(|| {
+                AppError::DeviceNotFound(
+                    "No v4l2loopback device (is the v4l2loopback module loaded?)".to_string(),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_rgba_to_limited_range_yuyv() {
+        // White then black: full luma swing, chroma of the pair stays neutral
+        let rgba = [255, 255, 255, 255, 0, 0, 0, 255];
+        let mut yuyv = [0u8; 4];
+        rgba_to_yuyv(&rgba, &mut yuyv);
+        assert_eq!(yuyv, [235, 128, 16, 128]);
+    }
+}
@@ -3,33 +3,90 @@
 //! Audio-enabled mesh manager extension
 //! Adds WebRTC audio track support to the existing mesh network
 
-use parking_lot::RwLock;
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_OPUS};
 use webrtc::api::APIBuilder;
-use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
-use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::sdp_type::RTCSdpType;
+use webrtc::peer_connection::signaling_state::RTCSignalingState;
 use webrtc::peer_connection::RTCPeerConnection;
 use webrtc::rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType};
 use webrtc::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
+use webrtc::stats::StatsReportType;
 #[allow(unused_imports)]
 use webrtc::track::track_local::TrackLocal;
-#[allow(unused_imports)]
 use webrtc::track::track_remote::TrackRemote;
+use webrtc::data_channel::data_channel_state::RTCDataChannelState;
 use webrtc::data_channel::RTCDataChannel;
 use webrtc::data_channel::data_channel_message::DataChannelMessage;
 
 use super::audio_track::{LocalAudioTrack, OPUS_CLOCK_RATE, OPUS_PAYLOAD_TYPE};
-use super::signaling::{ConnectionOffer, SignalingMessage};
-use crate::audio::CHANNELS;
-
-pub type MessageSender = mpsc::UnboundedSender<String>;
+use super::callback_queue::{CallbackQueue, EventSink};
+use super::gathering::{self, GatheringSender, GatheringSource};
+use super::ice_servers;
+use super::identity;
+use super::quality::ReceiveStats;
+use super::reorder::{ReorderBuffer, REORDER_WINDOW};
+use super::setup::{self, SetupGuard};
+use super::signaling::{self, ConnectionOffer, SignalingMessage};
+use super::timeouts;
+use crate::bans::BanList;
+use crate::audio::{AudioPolicy, CHANNELS};
+use crate::error::{AppError, AppResult};
+
+/// Channel for chat messages received from peers, tagged with the sender peer id
+pub type MessageSender = mpsc::UnboundedSender<(String, String)>;
 pub type AudioPacketSender = mpsc::UnboundedSender<(String, Vec<u8>)>;
+/// Channel for binary data channel messages (chat attachments), tagged with the sender peer id
+pub type BinarySender = mpsc::UnboundedSender<(String, Bytes)>;
+/// Channel for control messages received from peers, tagged with the sender peer id
+pub type SignalingSender = mpsc::UnboundedSender<(String, SignalingMessage)>;
+/// Channel for peer connection state changes, tagged with the peer id
+pub type PeerStateSender = mpsc::UnboundedSender<(String, RTCPeerConnectionState)>;
+/// Channel for remote media tracks that stopped on a live connection
+pub type MediaLostSender = mpsc::UnboundedSender<MediaLoss>;
+
+/// Recoveries tried in a row for a peer's tracks before giving up (reset
+/// once a recovered track delivers packets again)
+const MAX_TRACK_RECOVERIES: u32 = 3;
+
+/// A remote media track failed while its connection is still up
+#[derive(Debug, Clone)]
+pub struct MediaLoss {
+    pub peer_id: String,
+    /// "audio" or "video"
+    pub kind: String,
+    pub reason: String,
+}
+
+/// Transport counters of a peer connection, from its nominated ICE candidate pair
+#[derive(Debug, Clone)]
+pub struct PeerTransportStats {
+    pub peer_id: String,
+    /// Latest round-trip time, None before the first STUN response
+    pub rtt_ms: Option<f64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Incoming audio packets the sequence numbers account for, and those received
+    pub packets_expected: u64,
+    pub packets_received: u64,
+    /// Interarrival jitter of the incoming audio
+    pub jitter_ms: Option<f64>,
+    /// Share of our packets the peer reports lost, None before its first report
+    pub remote_loss_percent: Option<f64>,
+}
+
+/// Voice channel every peer starts in
+pub const DEFAULT_CHANNEL: &str = "general";
 
 /// Peer entry with audio track support
 struct AudioPeerEntry {
@@ -37,6 +94,43 @@ struct AudioPeerEntry {
     data_channel: Option<Arc<RTCDataChannel>>,
     local_audio_track: Option<Arc<LocalAudioTrack>>,
     username: String,
+    /// DTLS fingerprint of the remote peer (known once its SDP is received)
+    fingerprint: Option<String>,
+    /// Voice channel the peer is in
+    channel: String,
+    /// Loss and jitter of the audio received from the peer
+    receive_stats: Arc<Mutex<ReceiveStats>>,
+}
+
+/// Raised by WebRTC callbacks, applied in order by the manager's event task
+enum MeshEvent {
+    /// The answering side received the offerer's data channel
+    DataChannel {
+        peer_id: String,
+        dc: Arc<RTCDataChannel>,
+    },
+    ChannelOpen {
+        peer_id: String,
+    },
+    Message {
+        peer_id: String,
+        text: String,
+    },
+    Binary {
+        peer_id: String,
+        data: Bytes,
+    },
+    ChannelClosed {
+        peer_id: String,
+    },
+    StateChange {
+        peer_id: String,
+        state: RTCPeerConnectionState,
+    },
+    Track {
+        peer_id: String,
+        track: Arc<TrackRemote>,
+    },
 }
 
 /// Audio-enabled mesh manager
@@ -47,10 +141,227 @@ pub struct AudioMeshManager {
     local_username: Arc<RwLock<Option<String>>>,
     /// Channel for chat messages to frontend
     message_tx: Arc<RwLock<Option<MessageSender>>>,
+    /// Channel for binary messages (voice messages)
+    binary_tx: Arc<RwLock<Option<BinarySender>>>,
     /// Channel for incoming audio packets
     audio_rx_tx: Arc<RwLock<Option<AudioPacketSender>>>,
     /// Local audio track template (shared SSRC concept)
     local_audio_enabled: Arc<RwLock<bool>>,
+    /// Channel for control messages (room policy, ...) received from peers
+    signaling_tx: Arc<RwLock<Option<SignalingSender>>>,
+    /// Channel for peer connection state changes
+    peer_state_tx: Arc<RwLock<Option<PeerStateSender>>>,
+    /// Channel for remote tracks that stopped
+    media_lost_tx: Arc<RwLock<Option<MediaLostSender>>>,
+    /// Channel for the progress of ICE gathering
+    gathering_tx: Arc<RwLock<Option<GatheringSender>>>,
+    /// Audio policy of the room we host, advertised to every peer
+    room_policy: Arc<RwLock<Option<AudioPolicy>>>,
+    /// Audio policy received from the host of this room
+    remote_policy: Arc<RwLock<Option<AudioPolicy>>>,
+    /// Our download cap in kbps, advertised to every peer
+    download_cap: Arc<RwLock<Option<u32>>>,
+    /// Whether incoming audio of this mesh is forwarded for playback
+    listening: Arc<AtomicBool>,
+    /// Local voice channel (audio is only exchanged within a channel)
+    channel: Arc<RwLock<String>>,
+    /// Banned identities, consulted before accepting offers/answers
+    ban_list: BanList,
+    /// Applies the events raised by WebRTC callbacks
+    events: CallbackQueue<MeshEvent>,
+}
+
+/// Manager state the event task works on
+#[derive(Clone)]
+struct EventContext {
+    peers: Arc<RwLock<HashMap<String, AudioPeerEntry>>>,
+    message_tx: Arc<RwLock<Option<MessageSender>>>,
+    binary_tx: Arc<RwLock<Option<BinarySender>>>,
+    audio_rx_tx: Arc<RwLock<Option<AudioPacketSender>>>,
+    signaling_tx: Arc<RwLock<Option<SignalingSender>>>,
+    peer_state_tx: Arc<RwLock<Option<PeerStateSender>>>,
+    media_lost_tx: Arc<RwLock<Option<MediaLostSender>>>,
+    gathering_tx: Arc<RwLock<Option<GatheringSender>>>,
+    download_cap: Arc<RwLock<Option<u32>>>,
+    listening: Arc<AtomicBool>,
+    channel: Arc<RwLock<String>>,
+}
+
+impl EventContext {
+    fn apply(&self, event: MeshEvent) {
+        match event {
+            MeshEvent::DataChannel { peer_id, dc } => {
+                tracing::info!("Data channel '{}' opened from peer {}", dc.label(), peer_id);
+                if let Some(entry) = self.peers.write().get_mut(&peer_id) {
+                    entry.data_channel = Some(dc);
+                }
+            }
+            MeshEvent::ChannelOpen { peer_id } => {
+                tracing::info!("Data channel opened for peer {}", peer_id);
+                let dc = self
+                    .peers
+                    .read()
+                    .get(&peer_id)
+                    .and_then(|e| e.data_channel.clone());
+                let Some(dc) = dc else {
+                    return;
+                };
+                // Greet the new peer (download cap, voice channel, roster request)
+                let greetings = greeting_messages(&self.download_cap, &self.channel);
+                tokio::spawn(async move {
+                    for json in greetings {
+                        if let Err(e) = dc.send_text(json).await {
+                            tracing::warn!("Failed to send greeting: {}", e);
+                        }
+                    }
+                });
+            }
+            MeshEvent::Message { peer_id, text } => {
+                dispatch_message(
+                    &peer_id,
+                    text,
+                    self.message_tx.read().as_ref(),
+                    self.signaling_tx.read().as_ref(),
+                );
+            }
+            MeshEvent::Binary { peer_id, data } => {
+                if let Some(tx) = self.binary_tx.read().as_ref() {
+                    let _ = tx.send((peer_id, data));
+                }
+            }
+            MeshEvent::ChannelClosed { peer_id } => {
+                tracing::info!("Data channel closed for peer {}", peer_id);
+            }
+            MeshEvent::StateChange { peer_id, state } => {
+                tracing::debug!("Peer {} connection state: {}", peer_id, state);
+                if let Some(tx) = self.peer_state_tx.read().as_ref() {
+                    let _ = tx.send((peer_id, state));
+                }
+            }
+            MeshEvent::Track { peer_id, track } => {
+                if track.kind() == RTPCodecType::Audio {
+                    tracing::info!("Received audio track from peer {}", peer_id);
+                    self.spawn_track_reader(peer_id, track, 0);
+                }
+            }
+        }
+    }
+
+    /// Forward the Opus payloads of a remote track while the peer shares our
+    /// voice channel and we are listening
+    /// `recoveries` counts the recoveries that led to this track without any
+    /// packet received since.
+    fn spawn_track_reader(&self, peer_id: String, track: Arc<TrackRemote>, recoveries: u32) {
+        let ctx = self.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 1500];
+            // The decoder needs the frames in sequence order, not arrival order
+            let mut reorder = ReorderBuffer::new(REORDER_WINDOW);
+            let mut recoveries = recoveries;
+            let receive_stats = ctx
+                .peers
+                .read()
+                .get(&peer_id)
+                .map(|e| e.receive_stats.clone());
+            let reason = loop {
+                match track.read(&mut buf).await {
+                    Ok((rtp_packet, _attributes)) => {
+                        recoveries = 0;
+                        if let Some(stats) = &receive_stats {
+                            stats.lock().on_packet(
+                                rtp_packet.header.sequence_number,
+                                rtp_packet.header.timestamp,
+                                Instant::now(),
+                                OPUS_CLOCK_RATE,
+                            );
+                        }
+                        // Extract Opus payload from RTP packet
+                        let payload = rtp_packet.payload.to_vec();
+                        if payload.is_empty() {
+                            continue;
+                        }
+                        let same_channel = ctx
+                            .peers
+                            .read()
+                            .get(&peer_id)
+                            .is_some_and(|e| e.channel == *ctx.channel.read());
+                        let listening = ctx.listening.load(Ordering::SeqCst);
+                        reorder.push(rtp_packet.header.sequence_number, payload, |payload| {
+                            if same_channel && listening {
+                                if let Some(tx) = ctx.audio_rx_tx.read().as_ref() {
+                                    let _ = tx.send((peer_id.clone(), payload));
+                                }
+                            }
+                        });
+                    }
+                    Err(e) => break e.to_string(),
+                }
+            };
+            ctx.track_lost(peer_id, track, reason, recoveries).await;
+        });
+    }
+
+    /// A remote track stopped: report it and try to get the media back
+    /// Reads also end when the connection closes, which is not a loss.
+    async fn track_lost(
+        &self,
+        peer_id: String,
+        lost: Arc<TrackRemote>,
+        reason: String,
+        recoveries: u32,
+    ) {
+        let entry = self
+            .peers
+            .read()
+            .get(&peer_id)
+            .map(|e| (e.peer_connection.clone(), e.data_channel.clone()));
+        let Some((pc, dc)) = entry else {
+            tracing::debug!("Track of removed peer {} ended: {}", peer_id, reason);
+            return;
+        };
+        if matches!(
+            pc.connection_state(),
+            RTCPeerConnectionState::Closed | RTCPeerConnectionState::Failed
+        ) {
+            tracing::debug!("Track of peer {} ended with its connection: {}", peer_id, reason);
+            return;
+        }
+
+        let kind = lost.kind();
+        tracing::warn!("Lost {} track of peer {}: {}", kind, peer_id, reason);
+        if let Some(tx) = self.media_lost_tx.read().as_ref() {
+            let _ = tx.send(MediaLoss {
+                peer_id: peer_id.clone(),
+                kind: kind.to_string(),
+                reason,
+            });
+        }
+
+        if recoveries >= MAX_TRACK_RECOVERIES {
+            tracing::warn!("Giving up on the {} track of peer {}", kind, peer_id);
+            return;
+        }
+
+        // The receiver may already carry a replacement track
+        for transceiver in pc.get_transceivers().await {
+            for track in transceiver.receiver().await.tracks().await {
+                if track.kind() == kind && !Arc::ptr_eq(&track, &lost) {
+                    tracing::info!("Re-subscribing to the {} track of peer {}", kind, peer_id);
+                    self.spawn_track_reader(peer_id, track, recoveries + 1);
+                    return;
+                }
+            }
+        }
+
+        // Otherwise renegotiate: the remote track comes back through on_track
+        let Some(dc) = dc else {
+            return;
+        };
+        let progress = self.gathering_tx.read().clone();
+        if let Err(e) = send_renegotiation_offer(&pc, &dc, &peer_id, progress.as_ref()).await {
+            tracing::warn!("Failed to renegotiate with peer {}: {}", peer_id, e);
+        }
+    }
 }
 
 impl Default for AudioMeshManager {
@@ -61,12 +372,46 @@ impl Default for AudioMeshManager {
 
 impl AudioMeshManager {
     pub fn new() -> Self {
-        Self {
+        Self::with_ban_list(BanList::default())
+    }
+
+    pub fn with_ban_list(ban_list: BanList) -> Self {
+        let ctx = EventContext {
             peers: Arc::new(RwLock::new(HashMap::new())),
-            local_username: Arc::new(RwLock::new(None)),
             message_tx: Arc::new(RwLock::new(None)),
+            binary_tx: Arc::new(RwLock::new(None)),
             audio_rx_tx: Arc::new(RwLock::new(None)),
+            signaling_tx: Arc::new(RwLock::new(None)),
+            peer_state_tx: Arc::new(RwLock::new(None)),
+            media_lost_tx: Arc::new(RwLock::new(None)),
+            gathering_tx: Arc::new(RwLock::new(None)),
+            download_cap: Arc::new(RwLock::new(None)),
+            listening: Arc::new(AtomicBool::new(true)),
+            channel: Arc::new(RwLock::new(DEFAULT_CHANNEL.to_string())),
+        };
+        let events = {
+            let ctx = ctx.clone();
+            CallbackQueue::spawn("Audio mesh", move |event| ctx.apply(event))
+        };
+
+        Self {
+            peers: ctx.peers,
+            local_username: Arc::new(RwLock::new(None)),
+            message_tx: ctx.message_tx,
+            binary_tx: ctx.binary_tx,
+            audio_rx_tx: ctx.audio_rx_tx,
             local_audio_enabled: Arc::new(RwLock::new(false)),
+            signaling_tx: ctx.signaling_tx,
+            peer_state_tx: ctx.peer_state_tx,
+            media_lost_tx: ctx.media_lost_tx,
+            gathering_tx: ctx.gathering_tx,
+            room_policy: Arc::new(RwLock::new(None)),
+            remote_policy: Arc::new(RwLock::new(None)),
+            download_cap: ctx.download_cap,
+            listening: ctx.listening,
+            channel: ctx.channel,
+            ban_list,
+            events,
         }
     }
 
@@ -78,10 +423,91 @@ impl AudioMeshManager {
         *self.message_tx.write() = Some(tx);
     }
 
+    pub fn set_binary_sender(&self, tx: BinarySender) {
+        *self.binary_tx.write() = Some(tx);
+    }
+
     pub fn set_audio_receiver(&self, tx: AudioPacketSender) {
         *self.audio_rx_tx.write() = Some(tx);
     }
 
+    pub fn set_signaling_sender(&self, tx: SignalingSender) {
+        *self.signaling_tx.write() = Some(tx);
+    }
+
+    pub fn set_peer_state_sender(&self, tx: PeerStateSender) {
+        *self.peer_state_tx.write() = Some(tx);
+    }
+
+    pub fn set_media_lost_sender(&self, tx: MediaLostSender) {
+        *self.media_lost_tx.write() = Some(tx);
+    }
+
+    pub fn set_gathering_sender(&self, tx: GatheringSender) {
+        *self.gathering_tx.write() = Some(tx);
+    }
+
+    /// Set (or clear) the audio policy advertised to peers
+    pub fn set_room_policy(&self, policy: Option<AudioPolicy>) {
+        *self.room_policy.write() = policy;
+    }
+
+    pub fn room_policy(&self) -> Option<AudioPolicy> {
+        self.room_policy.read().clone()
+    }
+
+    /// Store the audio policy received from the room host
+    pub fn set_remote_policy(&self, policy: Option<AudioPolicy>) {
+        *self.remote_policy.write() = policy;
+    }
+
+    /// Policy to enforce when talking in this room (ours if hosting, else the host's)
+    pub fn effective_policy(&self) -> AudioPolicy {
+        self.room_policy()
+            .or_else(|| self.remote_policy.read().clone())
+            .unwrap_or_default()
+    }
+
+    /// Set (or clear) our download cap and advertise it to every peer
+    pub async fn set_download_cap(&self, download_kbps: Option<u32>) -> AppResult<()> {
+        if std::mem::replace(&mut *self.download_cap.write(), download_kbps) == download_kbps {
+            return Ok(());
+        }
+        self.broadcast_signaling(&SignalingMessage::BandwidthLimit { download_kbps })
+            .await
+    }
+
+    /// Enable/disable forwarding of incoming audio from this mesh
+    pub fn set_listening(&self, listening: bool) {
+        self.listening.store(listening, Ordering::SeqCst);
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening.load(Ordering::SeqCst)
+    }
+
+    /// Send the room policy to every connected peer
+    pub async fn broadcast_room_policy(&self) -> AppResult<()> {
+        match self.room_policy() {
+            Some(policy) => self.broadcast_signaling(&SignalingMessage::RoomPolicy { policy }).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Send a control message to every connected peer
+    pub async fn broadcast_signaling(&self, msg: &SignalingMessage) -> AppResult<()> {
+        let json = serde_json::to_string(msg)
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
+        self.broadcast_message(&json).await
+    }
+
+    /// Send a control message to a specific peer
+    pub async fn send_signaling(&self, peer_id: &str, msg: &SignalingMessage) -> AppResult<()> {
+        let json = serde_json::to_string(msg)
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
+        self.send_to_peer(peer_id, &json).await
+    }
+
     pub fn enable_local_audio(&self, enabled: bool) {
         *self.local_audio_enabled.write() = enabled;
     }
@@ -106,8 +532,51 @@ impl AudioMeshManager {
         self.peers.read().len()
     }
 
+    /// Peers whose connection is currently up
+    pub fn connected_peer_count(&self) -> usize {
+        self.peers
+            .read()
+            .values()
+            .filter(|e| e.peer_connection.connection_state() == RTCPeerConnectionState::Connected)
+            .count()
+    }
+
+    pub fn has_peer(&self, peer_id: &str) -> bool {
+        self.peers.read().contains_key(peer_id)
+    }
+
+    /// Local voice channel
+    pub fn channel(&self) -> String {
+        self.channel.read().clone()
+    }
+
+    /// Move to another voice channel and tell every peer
+    pub async fn join_channel(&self, channel: &str) -> AppResult<()> {
+        *self.channel.write() = channel.to_string();
+        self.broadcast_signaling(&SignalingMessage::ChannelMove {
+            channel: channel.to_string(),
+        })
+        .await
+    }
+
+    /// Record the voice channel a peer moved to
+    pub fn set_peer_channel(&self, peer_id: &str, channel: &str) {
+        if let Some(entry) = self.peers.write().get_mut(peer_id) {
+            entry.channel = channel.to_string();
+        }
+    }
+
+    /// Voice channel of every peer, as (peer_id, username, channel)
+    pub fn peer_channels(&self) -> Vec<(String, String, String)> {
+        self.peers
+            .read()
+            .iter()
+            .map(|(id, e)| (id.clone(), e.username.clone(), e.channel.clone()))
+            .collect()
+    }
+
     /// Create media engine with Opus codec
-    fn create_media_engine() -> Result<MediaEngine, String> {
+    fn create_media_engine() -> AppResult<MediaEngine> {
         let mut m = MediaEngine::default();
 
         // Register Opus codec for audio
@@ -125,97 +594,116 @@ impl AudioMeshManager {
             },
             RTPCodecType::Audio,
         )
-        .map_err(|e| format!("Failed to register Opus codec: {}", e))?;
+        .map_err(|e| AppError::WebRtc(format!("Failed to register Opus codec: {}", e)))?;
 
         // Also register default codecs for compatibility
         m.register_default_codecs()
-            .map_err(|e| format!("Failed to register default codecs: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register default codecs: {}", e)))?;
 
         Ok(m)
     }
 
-    async fn create_peer_connection(&self) -> Result<Arc<RTCPeerConnection>, String> {
+    async fn create_peer_connection(&self) -> AppResult<Arc<RTCPeerConnection>> {
         let mut m = Self::create_media_engine()?;
 
         let mut registry = Registry::new();
         registry = register_default_interceptors(registry, &mut m)
-            .map_err(|e| format!("Failed to register interceptors: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register interceptors: {}", e)))?;
 
         let api = APIBuilder::new()
             .with_media_engine(m)
             .with_interceptor_registry(registry)
+            .with_setting_engine(timeouts::current().setting_engine())
             .build();
 
         let config = RTCConfiguration {
-            ice_servers: vec![
-                RTCIceServer {
-                    urls: vec!["stun:stun.l.google.com:19302".to_owned()],
-                    ..Default::default()
-                },
-                RTCIceServer {
-                    urls: vec!["stun:stun.cloudflare.com:3478".to_owned()],
-                    ..Default::default()
-                },
-            ],
-            ..Default::default()
+            certificates: vec![identity::local_certificate()],
+            ..ice_servers::rtc_configuration()
         };
 
-        let peer_connection = api
-            .new_peer_connection(config)
-            .await
-            .map_err(|e| format!("Failed to create peer connection: {}", e))?;
+        let peer_connection =
+            setup::step("create peer connection", api.new_peer_connection(config)).await?;
 
         Ok(Arc::new(peer_connection))
     }
 
+    /// Closes the peer being set up unless the setup completes (failure,
+    /// timeout or a cancelled command)
+    fn setup_guard(
+        &self,
+        peer_id: &str,
+        pc: &Arc<RTCPeerConnection>,
+    ) -> SetupGuard<impl FnOnce()> {
+        let peers = self.peers.clone();
+        let peer_id = peer_id.to_string();
+        let pc = pc.clone();
+        SetupGuard::new(move || {
+            tracing::warn!("Setup of peer {} did not complete, closing its connection", peer_id);
+            discard_peer(&peers, &peer_id, pc);
+        })
+    }
+
+    /// Drop the peer if its data channel is not open in time: a stalled
+    /// DTLS/SCTP handshake would otherwise leave the entry behind for good
+    fn spawn_open_deadline(&self, peer_id: &str, pc: &Arc<RTCPeerConnection>) {
+        let peers = self.peers.clone();
+        let peer_state_tx = self.peer_state_tx.clone();
+        let peer_id = peer_id.to_string();
+        let pc = pc.clone();
+        let timeout = timeouts::current().connection();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let open = peers
+                .read()
+                .get(&peer_id)
+                .filter(|e| Arc::ptr_eq(&e.peer_connection, &pc))
+                .map(|e| {
+                    e.data_channel
+                        .as_ref()
+                        .is_some_and(|dc| dc.ready_state() == RTCDataChannelState::Open)
+                });
+            if open == Some(false) {
+                tracing::warn!(
+                    "Data channel of peer {} not open after {:?}, dropping the peer",
+                    peer_id,
+                    timeout
+                );
+                discard_peer(&peers, &peer_id, pc);
+                if let Some(tx) = peer_state_tx.read().as_ref() {
+                    let _ = tx.send((peer_id, RTCPeerConnectionState::Failed));
+                }
+            }
+        });
+    }
+
     /// Create a local audio track for a peer
-    fn create_local_audio_track(&self, peer_id: &str) -> Result<LocalAudioTrack, String> {
+    fn create_local_audio_track(&self, peer_id: &str) -> AppResult<LocalAudioTrack> {
         let username = self.local_username.read().clone().unwrap_or_else(|| "user".to_string());
         let track_id = format!("audio-{}", peer_id);
         let stream_id = format!("stream-{}", username);
         LocalAudioTrack::new(&track_id, &stream_id)
     }
 
-    /// Setup remote audio track handler
-    fn setup_remote_track_handler(&self, pc: &Arc<RTCPeerConnection>, peer_id: String) {
-        let audio_tx = self.audio_rx_tx.clone();
-        let peer_id_clone = peer_id.clone();
+    /// Queue connection state changes and remote tracks of a peer
+    fn watch_peer_connection(&self, pc: &Arc<RTCPeerConnection>, peer_id: &str) {
+        let events = self.events.sink();
+        let state_peer_id = peer_id.to_string();
+        pc.on_peer_connection_state_change(Box::new(move |state| {
+            events.push(MeshEvent::StateChange {
+                peer_id: state_peer_id.clone(),
+                state,
+            });
+            Box::pin(async {})
+        }));
 
+        let events = self.events.sink();
+        let track_peer_id = peer_id.to_string();
         pc.on_track(Box::new(move |track, _receiver, _transceiver| {
-            let audio_tx = audio_tx.clone();
-            let peer_id = peer_id_clone.clone();
-
-            Box::pin(async move {
-                if track.kind() == webrtc::rtp_transceiver::rtp_codec::RTPCodecType::Audio {
-                    tracing::info!("Received audio track from peer {}", peer_id);
-
-                    // Read RTP packets from the track
-                    let track = track.clone();
-                    let audio_tx = audio_tx.clone();
-                    let peer_id = peer_id.clone();
-
-                    tokio::spawn(async move {
-                        let mut buf = vec![0u8; 1500];
-                        loop {
-                            match track.read(&mut buf).await {
-                                Ok((rtp_packet, _attributes)) => {
-                                    // Extract Opus payload from RTP packet
-                                    let payload = rtp_packet.payload.to_vec();
-                                    if !payload.is_empty() {
-                                        if let Some(tx) = audio_tx.read().as_ref() {
-                                            let _ = tx.send((peer_id.clone(), payload));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::warn!("Error reading audio track: {}", e);
-                                    break;
-                                }
-                            }
-                        }
-                    });
-                }
-            })
+            events.push(MeshEvent::Track {
+                peer_id: track_peer_id.clone(),
+                track,
+            });
+            Box::pin(async {})
         }));
     }
 
@@ -224,43 +712,41 @@ impl AudioMeshManager {
         &self,
         peer_id: &str,
         peer_username: &str,
-    ) -> Result<ConnectionOffer, String> {
+    ) -> AppResult<ConnectionOffer> {
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(peer_id, &pc);
 
-        // Setup remote track handler
-        self.setup_remote_track_handler(&pc, peer_id.to_string());
+        // Remote track and connection state handlers
+        self.watch_peer_connection(&pc, peer_id);
 
         // Create and add local audio track if audio is enabled
         let local_audio_track = if *self.local_audio_enabled.read() {
             let audio_track = self.create_local_audio_track(peer_id)?;
 
             // Add track to peer connection
-            pc.add_track(audio_track.track())
-                .await
-                .map_err(|e| format!("Failed to add audio track: {}", e))?;
+            setup::step("add audio track", pc.add_track(audio_track.track())).await?;
 
             Some(Arc::new(audio_track))
         } else {
             // Add transceiver for receiving audio even if not sending
-            pc.add_transceiver_from_kind(
-                webrtc::rtp_transceiver::rtp_codec::RTPCodecType::Audio,
-                Some(webrtc::rtp_transceiver::RTCRtpTransceiverInit {
-                    direction: RTCRtpTransceiverDirection::Recvonly,
-                    send_encodings: vec![],
-                }),
+            setup::step(
+                "add audio transceiver",
+                pc.add_transceiver_from_kind(
+                    webrtc::rtp_transceiver::rtp_codec::RTPCodecType::Audio,
+                    Some(webrtc::rtp_transceiver::RTCRtpTransceiverInit {
+                        direction: RTCRtpTransceiverDirection::Recvonly,
+                        send_encodings: vec![],
+                    }),
+                ),
             )
-            .await
-            .map_err(|e| format!("Failed to add audio transceiver: {}", e))?;
+            .await?;
             None
         };
 
         // Create data channel for chat
-        let dc = pc
-            .create_data_channel("chat", None)
-            .await
-            .map_err(|e| format!("Failed to create data channel: {}", e))?;
+        let dc = setup::step("create data channel", pc.create_data_channel("chat", None)).await?;
 
-        self.setup_data_channel(peer_id.to_string(), dc.clone()).await;
+        watch_data_channel(&self.events.sink(), peer_id, &dc);
 
         // Store peer entry
         {
@@ -272,34 +758,33 @@ impl AudioMeshManager {
                     data_channel: Some(dc),
                     local_audio_track,
                     username: peer_username.to_string(),
+                    fingerprint: None,
+                    channel: DEFAULT_CHANNEL.to_string(),
+                    receive_stats: Arc::default(),
                 },
             );
         }
 
         // Create offer
-        let offer = pc
-            .create_offer(None)
-            .await
-            .map_err(|e| format!("Failed to create offer: {}", e))?;
+        let offer = setup::step("create offer", pc.create_offer(None)).await?;
 
-        pc.set_local_description(offer.clone())
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(offer.clone())).await?;
 
         // Wait for ICE gathering
-        self.wait_for_ice_gathering(&pc).await;
+        self.wait_for_ice_gathering(&pc, peer_id).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize SDP: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize SDP: {}", e)))?;
 
         use base64::Engine;
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: true,
@@ -312,67 +797,51 @@ impl AudioMeshManager {
         peer_id: &str,
         peer_username: &str,
         offer_base64: &str,
-    ) -> Result<ConnectionOffer, String> {
+    ) -> AppResult<ConnectionOffer> {
+        // Decode the offer first so banned identities are rejected before any setup
+        let offer = signaling::decode_description(offer_base64, RTCSdpType::Offer)?;
+
+        let fingerprint = identity::sdp_fingerprint(&offer.sdp);
+        self.ban_list.check_not_banned(peer_id, fingerprint.as_deref())?;
+
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(peer_id, &pc);
 
-        // Setup remote track handler
-        self.setup_remote_track_handler(&pc, peer_id.to_string());
+        // Remote track and connection state handlers
+        self.watch_peer_connection(&pc, peer_id);
 
         // Setup handler for incoming data channel
-        let peers = self.peers.clone();
-        let message_tx = self.message_tx.clone();
-        let peer_id_clone = peer_id.to_string();
-
+        let events = self.events.sink();
+        let dc_peer_id = peer_id.to_string();
         pc.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
-            let peers = peers.clone();
-            let message_tx = message_tx.clone();
-            let peer_id = peer_id_clone.clone();
-
-            Box::pin(async move {
-                tracing::info!("Data channel '{}' opened from peer {}", dc.label(), peer_id);
-
-                // Store data channel in peer entry
-                {
-                    let mut peers_lock = peers.write();
-                    if let Some(entry) = peers_lock.get_mut(&peer_id) {
-                        entry.data_channel = Some(dc.clone());
-                    }
-                }
-
-                // Setup message handler
-                let tx = message_tx.read().clone();
-                dc.on_message(Box::new(move |msg: DataChannelMessage| {
-                    let tx = tx.clone();
-                    Box::pin(async move {
-                        if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                            if let Some(ref sender) = tx {
-                                let _ = sender.send(text);
-                            }
-                        }
-                    })
-                }));
-            })
+            // Before returning: the channel opens (and can receive) right after
+            watch_data_channel(&events, &dc_peer_id, &dc);
+            events.push(MeshEvent::DataChannel {
+                peer_id: dc_peer_id.clone(),
+                dc,
+            });
+            Box::pin(async {})
         }));
 
         // Create and add local audio track if audio is enabled
         let local_audio_track = if *self.local_audio_enabled.read() {
             let audio_track = self.create_local_audio_track(peer_id)?;
 
-            pc.add_track(audio_track.track())
-                .await
-                .map_err(|e| format!("Failed to add audio track: {}", e))?;
+            setup::step("add audio track", pc.add_track(audio_track.track())).await?;
 
             Some(Arc::new(audio_track))
         } else {
-            pc.add_transceiver_from_kind(
-                webrtc::rtp_transceiver::rtp_codec::RTPCodecType::Audio,
-                Some(webrtc::rtp_transceiver::RTCRtpTransceiverInit {
-                    direction: RTCRtpTransceiverDirection::Recvonly,
-                    send_encodings: vec![],
-                }),
+            setup::step(
+                "add audio transceiver",
+                pc.add_transceiver_from_kind(
+                    webrtc::rtp_transceiver::rtp_codec::RTPCodecType::Audio,
+                    Some(webrtc::rtp_transceiver::RTCRtpTransceiverInit {
+                        direction: RTCRtpTransceiverDirection::Recvonly,
+                        send_encodings: vec![],
+                    }),
+                ),
             )
-            .await
-            .map_err(|e| format!("Failed to add audio transceiver: {}", e))?;
+            .await?;
             None
         };
 
@@ -386,49 +855,35 @@ impl AudioMeshManager {
                     data_channel: None,
                     local_audio_track,
                     username: peer_username.to_string(),
+                    fingerprint,
+                    channel: DEFAULT_CHANNEL.to_string(),
+                    receive_stats: Arc::default(),
                 },
             );
         }
 
-        // Decode and set remote description
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(offer_base64)
-            .map_err(|e| format!("Failed to decode offer: {}", e))?;
-
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in offer: {}", e))?;
-
-        let offer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse offer: {}", e))?;
-
-        pc.set_remote_description(offer)
-            .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+        setup::step("set remote description", pc.set_remote_description(offer)).await?;
 
         // Create answer
-        let answer = pc
-            .create_answer(None)
-            .await
-            .map_err(|e| format!("Failed to create answer: {}", e))?;
+        let answer = setup::step("create answer", pc.create_answer(None)).await?;
 
-        pc.set_local_description(answer)
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(answer)).await?;
 
         // Wait for ICE gathering
-        self.wait_for_ice_gathering(&pc).await;
+        self.wait_for_ice_gathering(&pc, peer_id).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize answer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize answer: {}", e)))?;
 
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
+        self.spawn_open_deadline(peer_id, &pc);
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: false,
@@ -436,41 +891,78 @@ impl AudioMeshManager {
     }
 
     /// Accept answer from peer
-    pub async fn accept_answer_from_peer(&self, peer_id: &str, answer_base64: &str) -> Result<(), String> {
+    pub async fn accept_answer_from_peer(&self, peer_id: &str, answer_base64: &str) -> AppResult<()> {
         let pc = {
             let peers = self.peers.read();
             peers
                 .get(peer_id)
                 .map(|e| e.peer_connection.clone())
-                .ok_or_else(|| format!("No peer connection for {}", peer_id))?
+                .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))?
         };
 
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(answer_base64)
-            .map_err(|e| format!("Failed to decode answer: {}", e))?;
+        let answer = signaling::decode_description(answer_base64, RTCSdpType::Answer)?;
+
+        let fingerprint = identity::sdp_fingerprint(&answer.sdp);
+        if let Err(e) = self.ban_list.check_not_banned(peer_id, fingerprint.as_deref()) {
+            self.remove_peer(peer_id);
+            return Err(e);
+        }
+        if let Some(entry) = self.peers.write().get_mut(peer_id) {
+            entry.fingerprint = fingerprint;
+        }
+
+        let guard = self.setup_guard(peer_id, &pc);
+        setup::step("set remote description", pc.set_remote_description(answer)).await?;
+        guard.complete();
+        self.spawn_open_deadline(peer_id, &pc);
+
+        tracing::info!("Answer from peer {} accepted", peer_id);
+        Ok(())
+    }
 
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in answer: {}", e))?;
+    /// Answer a peer's renegotiation offer on the existing connection
+    pub async fn accept_renegotiation(&self, peer_id: &str, offer_base64: &str) -> AppResult<()> {
+        let pc = self.peer_connection(peer_id)?;
+        let offer = signaling::decode_description(offer_base64, RTCSdpType::Offer)?;
 
-        let answer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse answer: {}", e))?;
+        setup::step("set remote description", pc.set_remote_description(offer)).await?;
+        let answer = setup::step("create answer", pc.create_answer(None)).await?;
+        setup::step("set local description", pc.set_local_description(answer)).await?;
+        self.wait_for_ice_gathering(&pc, peer_id).await;
 
-        pc.set_remote_description(answer)
+        let sdp_base64 = local_description_base64(&pc).await?;
+        self.send_signaling(peer_id, &SignalingMessage::RenegotiateAnswer { sdp_base64 })
             .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+    }
 
-        tracing::info!("Answer from peer {} accepted", peer_id);
+    /// Apply the answer to a renegotiation we started
+    pub async fn complete_renegotiation(&self, peer_id: &str, answer_base64: &str) -> AppResult<()> {
+        let pc = self.peer_connection(peer_id)?;
+        let answer = signaling::decode_description(answer_base64, RTCSdpType::Answer)?;
+
+        setup::step("set remote description", pc.set_remote_description(answer)).await?;
+        tracing::info!("Renegotiated connection with peer {}", peer_id);
         Ok(())
     }
 
+    fn peer_connection(&self, peer_id: &str) -> AppResult<Arc<RTCPeerConnection>> {
+        self.peers
+            .read()
+            .get(peer_id)
+            .map(|e| e.peer_connection.clone())
+            .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))
+    }
+
     /// Send audio to all peers
-    pub async fn broadcast_audio(&self, opus_data: &[u8]) -> Result<(), String> {
+    pub async fn broadcast_audio(&self, opus_data: &[u8]) -> AppResult<()> {
         // Collect tracks first to avoid holding lock across await
+        // Only peers in our voice channel hear us
+        let channel = self.channel.read().clone();
         let tracks: Vec<(String, Arc<LocalAudioTrack>)> = {
             let peers = self.peers.read();
             peers
                 .iter()
+                .filter(|(_, entry)| entry.channel == channel)
                 .filter_map(|(id, entry)| {
                     entry.local_audio_track.as_ref().map(|t| (id.clone(), t.clone()))
                 })
@@ -487,7 +979,7 @@ impl AudioMeshManager {
     }
 
     /// Send audio to specific peer
-    pub async fn send_audio_to_peer(&self, peer_id: &str, opus_data: &[u8]) -> Result<(), String> {
+    pub async fn send_audio_to_peer(&self, peer_id: &str, opus_data: &[u8]) -> AppResult<()> {
         // Get track without holding lock across await
         let track = {
             let peers = self.peers.read();
@@ -501,39 +993,55 @@ impl AudioMeshManager {
         Ok(())
     }
 
-    async fn setup_data_channel(&self, peer_id: String, dc: Arc<RTCDataChannel>) {
-        let message_tx = self.message_tx.clone();
-
-        dc.on_open(Box::new(move || {
-            tracing::info!("Data channel opened for peer!");
-            Box::pin(async {})
-        }));
+    /// Get the username of a peer
+    pub fn peer_username(&self, peer_id: &str) -> Option<String> {
+        self.peers.read().get(peer_id).map(|e| e.username.clone())
+    }
 
-        let tx = message_tx.read().clone();
-        dc.on_message(Box::new(move |msg: DataChannelMessage| {
-            let tx = tx.clone();
-            Box::pin(async move {
-                if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                    if let Some(ref sender) = tx {
-                        let _ = sender.send(text);
+    /// Find a peer by username (case-insensitive): its id and exact username
+    /// When several peers match, only an exact match is accepted.
+    pub fn find_peer(&self, username: &str) -> AppResult<(String, String)> {
+        let peers = self.peers.read();
+        let matches: Vec<(&String, &AudioPeerEntry)> = peers
+            .iter()
+            .filter(|(_, e)| e.username.eq_ignore_ascii_case(username))
+            .collect();
+        let found = match matches.as_slice() {
+            [] => return Err(AppError::PeerNotFound(username.to_string())),
+            [only] => *only,
+            _ => {
+                let mut exact = matches.iter().filter(|(_, e)| e.username == username);
+                match (exact.next(), exact.next()) {
+                    (Some(found), None) => *found,
+                    _ => {
+                        return Err(AppError::InvalidArgument(format!(
+                            "Several peers are named {}",
+                            username
+                        )))
                     }
                 }
-            })
-        }));
+            }
+        };
+        Ok((found.0.clone(), found.1.username.clone()))
+    }
 
-        let peer_id_clone = peer_id.clone();
-        dc.on_close(Box::new(move || {
-            tracing::info!("Data channel closed for peer {}", peer_id_clone);
-            Box::pin(async {})
-        }));
+    /// Get the identity fingerprint and username of a peer
+    pub fn peer_fingerprint(&self, peer_id: &str) -> Option<(String, String)> {
+        let peers = self.peers.read();
+        let entry = peers.get(peer_id)?;
+        entry
+            .fingerprint
+            .clone()
+            .map(|fp| (fp, entry.username.clone()))
     }
 
-    async fn wait_for_ice_gathering(&self, _pc: &Arc<RTCPeerConnection>) {
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    async fn wait_for_ice_gathering(&self, pc: &Arc<RTCPeerConnection>, peer_id: &str) {
+        let progress = self.gathering_tx.read().clone();
+        wait_for_gathering(pc, peer_id, progress.as_ref()).await;
     }
 
     /// Send chat message to all peers
-    pub async fn send_chat_message(&self, content: &str) -> Result<(), String> {
+    pub async fn send_chat_message(&self, content: &str) -> AppResult<()> {
         let username = self
             .local_username
             .read()
@@ -550,13 +1058,13 @@ impl AudioMeshManager {
         };
 
         let json = serde_json::to_string(&msg)
-            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
 
         self.broadcast_message(&json).await
     }
 
     /// Broadcast message to all peers
-    pub async fn broadcast_message(&self, message: &str) -> Result<(), String> {
+    pub async fn broadcast_message(&self, message: &str) -> AppResult<()> {
         let peer_ids: Vec<String> = self.peers.read().keys().cloned().collect();
 
         for peer_id in peer_ids {
@@ -569,22 +1077,87 @@ impl AudioMeshManager {
     }
 
     /// Send message to specific peer
-    pub async fn send_to_peer(&self, peer_id: &str, message: &str) -> Result<(), String> {
+    pub async fn send_to_peer(&self, peer_id: &str, message: &str) -> AppResult<()> {
         let dc = {
             let peers = self.peers.read();
             peers
                 .get(peer_id)
                 .and_then(|e| e.data_channel.clone())
-                .ok_or_else(|| format!("No data channel for peer {}", peer_id))?
+                .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))?
         };
 
         dc.send_text(message.to_string())
             .await
-            .map_err(|e| format!("Failed to send to peer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to send to peer: {}", e)))?;
+
+        Ok(())
+    }
 
+    /// Send binary messages to every peer, in order (chunks of an attachment)
+    pub async fn broadcast_binary(&self, messages: &[Bytes]) -> AppResult<()> {
+        let channels: Vec<(String, Arc<RTCDataChannel>)> = self
+            .peers
+            .read()
+            .iter()
+            .filter_map(|(id, e)| e.data_channel.clone().map(|dc| (id.clone(), dc)))
+            .collect();
+
+        for (peer_id, dc) in channels {
+            for data in messages {
+                if let Err(e) = dc.send(data).await {
+                    tracing::warn!("Failed to send binary message to peer {}: {}", peer_id, e);
+                    break;
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Transport counters of every peer
+    pub async fn transport_stats(&self) -> Vec<PeerTransportStats> {
+        // Collect connections first to avoid holding lock across await
+        let connections: Vec<(String, Arc<RTCPeerConnection>, Arc<Mutex<ReceiveStats>>)> = self
+            .peers
+            .read()
+            .iter()
+            .map(|(id, entry)| {
+                (id.clone(), entry.peer_connection.clone(), entry.receive_stats.clone())
+            })
+            .collect();
+
+        let mut stats = Vec::with_capacity(connections.len());
+        for (peer_id, pc, receive_stats) in connections {
+            let report = pc.get_stats().await;
+            let pair = report.reports.values().find_map(|r| match r {
+                StatsReportType::CandidatePair(pair) if pair.nominated => Some(pair),
+                _ => None,
+            });
+            // Receiver reports about our audio (the worst stream if several)
+            let remote_loss = report
+                .reports
+                .values()
+                .filter_map(|r| match r {
+                    StatsReportType::RemoteInboundRTP(s) => Some(s.fraction_lost * 100.0),
+                    _ => None,
+                })
+                .reduce(f64::max);
+            let receive = receive_stats.lock();
+            stats.push(PeerTransportStats {
+                peer_id,
+                rtt_ms: pair
+                    .map(|p| p.current_round_trip_time * 1000.0)
+                    .filter(|rtt| *rtt > 0.0),
+                bytes_sent: pair.map_or(0, |p| p.bytes_sent),
+                bytes_received: pair.map_or(0, |p| p.bytes_received),
+                packets_expected: receive.expected(),
+                packets_received: receive.received(),
+                jitter_ms: receive.jitter_ms(),
+                remote_loss_percent: remote_loss,
+            });
+        }
+        stats
+    }
+
     /// Remove peer
     pub fn remove_peer(&self, peer_id: &str) {
         let entry = self.peers.write().remove(peer_id);
@@ -603,6 +1176,8 @@ impl AudioMeshManager {
 
     /// Close all connections
     pub fn close_all(&self) {
+        *self.room_policy.write() = None;
+        *self.remote_policy.write() = None;
         let entries: Vec<AudioPeerEntry> = self.peers.write().drain().map(|(_, v)| v).collect();
         for entry in entries {
             tokio::spawn(async move {
@@ -610,4 +1185,172 @@ impl AudioMeshManager {
             });
         }
     }
+
+    /// Tell every peer we are leaving, then close all connections and wait for
+    /// them to shut down (used on app exit, where spawned closes would not run)
+    pub async fn leave(&self) {
+        if let Some(username) = self.get_local_username() {
+            if let Err(e) = self.broadcast_signaling(&SignalingMessage::user_left(username)).await {
+                tracing::warn!("Failed to announce leave: {}", e);
+            }
+        }
+        *self.room_policy.write() = None;
+        *self.remote_policy.write() = None;
+        let entries: Vec<AudioPeerEntry> = self.peers.write().drain().map(|(_, v)| v).collect();
+        for entry in entries {
+            let _ = entry.peer_connection.close().await;
+        }
+    }
+}
+
+/// Queue the open, message and close events of a peer's data channel
+fn watch_data_channel(events: &EventSink<MeshEvent>, peer_id: &str, dc: &Arc<RTCDataChannel>) {
+    let (sink, open_peer_id) = (events.clone(), peer_id.to_string());
+    dc.on_open(Box::new(move || {
+        sink.push(MeshEvent::ChannelOpen {
+            peer_id: open_peer_id,
+        });
+        Box::pin(async {})
+    }));
+
+    let (sink, msg_peer_id) = (events.clone(), peer_id.to_string());
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        if !msg.is_string {
+            sink.push(MeshEvent::Binary {
+                peer_id: msg_peer_id.clone(),
+                data: msg.data,
+            });
+        } else if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
+            sink.push(MeshEvent::Message {
+                peer_id: msg_peer_id.clone(),
+                text,
+            });
+        }
+        Box::pin(async {})
+    }));
+
+    let (sink, close_peer_id) = (events.clone(), peer_id.to_string());
+    dc.on_close(Box::new(move || {
+        sink.push(MeshEvent::ChannelClosed {
+            peer_id: close_peer_id.clone(),
+        });
+        Box::pin(async {})
+    }));
+}
+
+/// Wait for the candidates of the local description for `peer_id`
+async fn wait_for_gathering(
+    pc: &RTCPeerConnection,
+    peer_id: &str,
+    progress: Option<&GatheringSender>,
+) {
+    gathering::wait_for_gathering(
+        pc,
+        progress,
+        GatheringSource::Mesh,
+        Some(peer_id),
+        timeouts::current().gathering(),
+    )
+    .await;
+}
+
+/// Start a new offer/answer round on an established connection, sending the
+/// offer over its data channel
+async fn send_renegotiation_offer(
+    pc: &Arc<RTCPeerConnection>,
+    dc: &RTCDataChannel,
+    peer_id: &str,
+    progress: Option<&GatheringSender>,
+) -> AppResult<()> {
+    // Both sides may notice the loss: only one offer can be in flight
+    if pc.signaling_state() != RTCSignalingState::Stable {
+        return Err(AppError::WebRtc("Negotiation already in progress".to_string()));
+    }
+
+    let offer = setup::step("create offer", pc.create_offer(None)).await?;
+    setup::step("set local description", pc.set_local_description(offer)).await?;
+    wait_for_gathering(pc, peer_id, progress).await;
+
+    let sdp_base64 = local_description_base64(pc).await?;
+    let json = serde_json::to_string(&SignalingMessage::RenegotiateOffer { sdp_base64 })
+        .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
+    dc.send_text(json)
+        .await
+        .map_err(|e| AppError::WebRtc(format!("Failed to send offer: {}", e)))?;
+    Ok(())
+}
+
+/// Local description of `pc`, encoded for signaling
+async fn local_description_base64(pc: &RTCPeerConnection) -> AppResult<String> {
+    use base64::Engine;
+    let local_desc = pc
+        .local_description()
+        .await
+        .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
+    let sdp_json = serde_json::to_string(&local_desc)
+        .map_err(|e| AppError::WebRtc(format!("Failed to serialize SDP: {}", e)))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes()))
+}
+
+/// Remove `peer_id` if it is still backed by `pc`, and close `pc`
+fn discard_peer(
+    peers: &RwLock<HashMap<String, AudioPeerEntry>>,
+    peer_id: &str,
+    pc: Arc<RTCPeerConnection>,
+) {
+    {
+        let mut peers = peers.write();
+        if peers
+            .get(peer_id)
+            .is_some_and(|e| Arc::ptr_eq(&e.peer_connection, &pc))
+        {
+            peers.remove(peer_id);
+        }
+    }
+    tokio::spawn(async move {
+        let _ = pc.close().await;
+    });
+}
+
+/// Messages sent to a peer as soon as its data channel opens: our download cap
+/// (if any), our voice channel and a roster request
+/// The room policy follows the roster snapshot
+fn greeting_messages(download_cap: &RwLock<Option<u32>>, channel: &RwLock<String>) -> Vec<String> {
+    let mut messages = Vec::new();
+    if let Some(download_kbps) = *download_cap.read() {
+        messages.push(SignalingMessage::BandwidthLimit {
+            download_kbps: Some(download_kbps),
+        });
+    }
+    messages.push(SignalingMessage::ChannelMove {
+        channel: channel.read().clone(),
+    });
+    messages.push(SignalingMessage::RosterRequest);
+
+    messages
+        .iter()
+        .filter_map(|msg| serde_json::to_string(msg).ok())
+        .collect()
+}
+
+/// Route an incoming data channel message: control messages go to the signaling
+/// channel, everything else (chat) to the frontend message channel
+fn dispatch_message(
+    peer_id: &str,
+    text: String,
+    message_tx: Option<&MessageSender>,
+    signaling_tx: Option<&SignalingSender>,
+) {
+    if let Ok(msg) = serde_json::from_str::<SignalingMessage>(&text) {
+        if msg.is_control() {
+            if let Some(sender) = signaling_tx {
+                let _ = sender.send((peer_id.to_string(), msg));
+            }
+            return;
+        }
+    }
+
+    if let Some(sender) = message_tx {
+        let _ = sender.send((peer_id.to_string(), text));
+    }
 }
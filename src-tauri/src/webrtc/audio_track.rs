@@ -1,15 +1,22 @@
 #![allow(dead_code)]
 
 use std::sync::Arc;
+use std::time::Instant;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
 use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_OPUS};
 use webrtc::rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType};
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocalWriter;
+use webrtc::rtp::header::Header as RtpHeader;
 use webrtc::rtp::packet::Packet as RtpPacket;
 
+use super::PayloadPool;
 use crate::audio::CHANNELS;
+use crate::error::{AppError, AppResult};
+#[cfg(debug_assertions)]
+use crate::netsim;
+use crate::perf::{self, Component};
 
 /// Opus payload type (dynamic, typically 111)
 pub const OPUS_PAYLOAD_TYPE: u8 = 111;
@@ -20,17 +27,23 @@ pub const OPUS_CLOCK_RATE: u32 = 48000;
 /// Samples per RTP packet (20ms at 48kHz = 960 samples)
 pub const SAMPLES_PER_RTP_PACKET: u32 = 960;
 
+/// Payload bytes kept for packets in flight (Opus packets are at most 1275 bytes)
+const PAYLOAD_POOL_BYTES: usize = 8 * 1024;
+
 /// Audio track for sending local audio via WebRTC
 pub struct LocalAudioTrack {
     track: Arc<TrackLocalStaticRTP>,
     sequence_number: Mutex<u16>,
     timestamp: Mutex<u32>,
     ssrc: u32,
+    /// Header fields that never change, copied into every packet
+    header: RtpHeader,
+    payloads: PayloadPool,
 }
 
 impl LocalAudioTrack {
     /// Create a new local audio track
-    pub fn new(track_id: &str, stream_id: &str) -> Result<Self, String> {
+    pub fn new(track_id: &str, stream_id: &str) -> AppResult<Self> {
         let track = Arc::new(TrackLocalStaticRTP::new(
             RTCRtpCodecCapability {
                 mime_type: MIME_TYPE_OPUS.to_owned(),
@@ -51,6 +64,16 @@ impl LocalAudioTrack {
             sequence_number: Mutex::new(0),
             timestamp: Mutex::new(rand::random::<u32>()),
             ssrc,
+            header: RtpHeader {
+                version: 2,
+                padding: false,
+                extension: false,
+                marker: false, // Opus doesn't use marker bit typically
+                payload_type: OPUS_PAYLOAD_TYPE,
+                ssrc,
+                ..Default::default()
+            },
+            payloads: PayloadPool::with_capacity(PAYLOAD_POOL_BYTES),
         })
     }
 
@@ -61,26 +84,20 @@ impl LocalAudioTrack {
 
     /// Send encoded Opus audio data
     /// `opus_data` should be the output from OpusEncoder::encode()
-    pub async fn send_audio(&self, opus_data: &[u8]) -> Result<(), String> {
+    pub async fn send_audio(&self, opus_data: &[u8]) -> AppResult<()> {
+        let started = Instant::now();
+        let payload = self.payloads.payload(&[], opus_data);
+
         // Build RTP packet without holding locks across await
         let packet = {
             let mut seq = self.sequence_number.lock();
             let mut ts = self.timestamp.lock();
 
-            let packet = RtpPacket {
-                header: webrtc::rtp::header::Header {
-                    version: 2,
-                    padding: false,
-                    extension: false,
-                    marker: false, // Opus doesn't use marker bit typically
-                    payload_type: OPUS_PAYLOAD_TYPE,
-                    sequence_number: *seq,
-                    timestamp: *ts,
-                    ssrc: self.ssrc,
-                    ..Default::default()
-                },
-                payload: bytes::Bytes::copy_from_slice(opus_data),
-            };
+            // The template has no extensions or CSRCs: cloning it doesn't allocate
+            let mut header = self.header.clone();
+            header.sequence_number = *seq;
+            header.timestamp = *ts;
+            let packet = RtpPacket { header, payload };
 
             // Increment sequence number and timestamp
             *seq = seq.wrapping_add(1);
@@ -89,12 +106,19 @@ impl LocalAudioTrack {
             packet
         }; // locks released here
 
+        #[cfg(debug_assertions)]
+        if netsim::intercept(&self.track, &packet) {
+            return Ok(());
+        }
+
         // Send via track (without holding any locks)
         self.track
             .write_rtp(&packet)
             .await
-            .map_err(|e| format!("Failed to write RTP packet: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to write RTP packet: {}", e)))?;
 
+        // write_rtp only awaits short internal locks: close enough to CPU time
+        perf::record(Component::WebRtc, started.elapsed());
         Ok(())
     }
 
@@ -127,7 +151,7 @@ impl RemoteAudioTrack {
 }
 
 /// Configure MediaEngine with Opus codec for audio
-pub fn register_audio_codec(m: &mut MediaEngine) -> Result<(), String> {
+pub fn register_audio_codec(m: &mut MediaEngine) -> AppResult<()> {
     // Register Opus codec
     m.register_codec(
         RTCRtpCodecParameters {
@@ -143,7 +167,7 @@ pub fn register_audio_codec(m: &mut MediaEngine) -> Result<(), String> {
         },
         RTPCodecType::Audio,
     )
-    .map_err(|e| format!("Failed to register Opus codec: {}", e))?;
+    .map_err(|e| AppError::WebRtc(format!("Failed to register Opus codec: {}", e)))?;
 
     Ok(())
 }
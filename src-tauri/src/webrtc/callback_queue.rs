@@ -0,0 +1,87 @@
+//! Callback event queue
+//! webrtc-rs runs callbacks (`on_data_channel`, `on_message`, `on_track`, ...)
+//! on its own tasks, concurrently with the commands. Taking the manager's locks
+//! there is what needed the "collect first" workarounds. Callbacks only push an
+//! event instead; one task per manager applies the events in order, and is the
+//! only place where callbacks change the manager's state.
+
+use tokio::sync::mpsc;
+
+use crate::perf::{self, Component};
+
+/// Sending side owned by the manager: the event task stops once it is dropped
+pub struct CallbackQueue<E> {
+    tx: mpsc::UnboundedSender<E>,
+}
+
+impl<E: Send + 'static> CallbackQueue<E> {
+    /// Start the event task of `manager`, calling `apply` on each event in turn
+    /// (`apply` must not block: slow work such as sending goes to its own task)
+    pub fn spawn(manager: &'static str, mut apply: impl FnMut(E) + Send + 'static) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let _span = perf::span(Component::WebRtc);
+                apply(event);
+            }
+            tracing::debug!("{} event task stopped", manager);
+        });
+        Self { tx }
+    }
+
+    /// Handle for callbacks to push events through
+    pub fn sink(&self) -> EventSink<E> {
+        EventSink {
+            tx: self.tx.downgrade(),
+        }
+    }
+}
+
+/// Pushing side given to callbacks
+/// Weak, so callbacks stored in a peer connection don't keep the task (and
+/// through it the peers) alive after the manager is gone.
+pub struct EventSink<E> {
+    tx: mpsc::WeakUnboundedSender<E>,
+}
+
+impl<E> EventSink<E> {
+    /// Queue `event` (dropped if the manager is gone)
+    pub fn push(&self, event: E) {
+        if let Some(tx) = self.tx.upgrade() {
+            let _ = tx.send(event);
+        }
+    }
+}
+
+impl<E> Clone for EventSink<E> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn applies_events_in_order_until_dropped() {
+        let (seen_tx, mut seen) = mpsc::unbounded_channel();
+        let queue = CallbackQueue::spawn("test", move |n: u32| {
+            let _ = seen_tx.send(n);
+        });
+        let sink = queue.sink();
+        for n in 0..3 {
+            sink.push(n);
+        }
+        for n in 0..3 {
+            assert_eq!(seen.recv().await, Some(n));
+        }
+
+        // The task (and the `apply` closure holding `seen_tx`) ends with the queue
+        drop(queue);
+        sink.push(3);
+        assert_eq!(seen.recv().await, None);
+    }
+}
@@ -0,0 +1,239 @@
+//! ICE gathering
+//! Offers and answers are exchanged without trickle ICE, so the SDP has to carry
+//! every candidate: wait until the agent reports gathering complete, capped by a
+//! timeout so an unreachable STUN server can't hold up the connection.
+//! Each step (candidate found, gathering complete or timed out) is reported to
+//! the progress sender of the manager running it, so the UI can show that
+//! candidates are being gathered.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidate;
+use webrtc::ice_transport::ice_gatherer_state::RTCIceGathererState;
+use webrtc::ice_transport::ice_gathering_state::RTCIceGatheringState;
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// Default cap on candidate gathering (see `timeouts`)
+pub const DEFAULT_GATHERING_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type GatheringSender = mpsc::UnboundedSender<GatheringProgress>;
+
+/// Connection the gathering is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatheringSource {
+    /// A peer of a mesh
+    Mesh,
+    /// The single manual connection
+    Connection,
+    /// The WHIP publisher
+    Whip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatheringPhase {
+    /// Candidates are being gathered
+    Gathering,
+    /// Every candidate is known
+    Complete,
+    /// The timeout elapsed first: the SDP has the candidates found so far
+    TimedOut,
+}
+
+/// Where the gathering for a local description stands
+#[derive(Debug, Clone, Serialize)]
+pub struct GatheringProgress {
+    pub source: GatheringSource,
+    /// Peer the description is for (None for the single connection and WHIP)
+    pub peer_id: Option<String>,
+    pub phase: GatheringPhase,
+    /// Candidates found so far
+    pub candidates: usize,
+    pub elapsed_ms: u64,
+    pub timeout_ms: u64,
+}
+
+/// What the ICE agent reports while gathering
+enum Signal {
+    Candidate(String),
+    /// The gatherer reached the complete state (or sent the end-of-candidates marker)
+    Complete,
+}
+
+/// Candidates and phase of one gathering
+struct Gathering {
+    phase: GatheringPhase,
+    /// Candidate lines, so one seen in the SDP and in a callback counts once
+    candidates: HashSet<String>,
+}
+
+impl Gathering {
+    fn new() -> Self {
+        Self {
+            phase: GatheringPhase::Gathering,
+            candidates: HashSet::new(),
+        }
+    }
+
+    /// Apply a signal; true when the progress changed
+    fn apply(&mut self, signal: Signal) -> bool {
+        if self.phase != GatheringPhase::Gathering {
+            return false;
+        }
+        match signal {
+            Signal::Candidate(candidate) => self.candidates.insert(candidate),
+            Signal::Complete => {
+                self.phase = GatheringPhase::Complete;
+                true
+            }
+        }
+    }
+
+    /// The timeout elapsed; true when gathering was still running
+    fn time_out(&mut self) -> bool {
+        let running = self.phase == GatheringPhase::Gathering;
+        if running {
+            self.phase = GatheringPhase::TimedOut;
+        }
+        running
+    }
+}
+
+/// Wait for gathering to complete after `set_local_description`; returns false
+/// if `timeout` elapsed first (the SDP then has the candidates found so far)
+/// Progress is reported to `progress`, when set.
+pub async fn wait_for_gathering(
+    pc: &RTCPeerConnection,
+    progress: Option<&GatheringSender>,
+    source: GatheringSource,
+    peer_id: Option<&str>,
+    timeout: Duration,
+) -> bool {
+    let started = Instant::now();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // Registered first: what is found meanwhile is also in the SDP read below
+    let candidate_tx = tx.clone();
+    pc.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+        let signal = match candidate {
+            Some(candidate) => candidate
+                .to_json()
+                .ok()
+                .map(|c| Signal::Candidate(c.candidate)),
+            None => Some(Signal::Complete),
+        };
+        if let Some(signal) = signal {
+            let _ = candidate_tx.send(signal);
+        }
+        Box::pin(async {})
+    }));
+    let state_tx = tx.clone();
+    pc.on_ice_gathering_state_change(Box::new(move |state: RTCIceGathererState| {
+        if state == RTCIceGathererState::Complete {
+            let _ = state_tx.send(Signal::Complete);
+        }
+        Box::pin(async {})
+    }));
+    // Resolves right away when gathering already finished
+    let mut done = pc.gathering_complete_promise().await;
+    tauri::async_runtime::spawn(async move {
+        if done.recv().await.is_some() {
+            let _ = tx.send(Signal::Complete);
+        }
+    });
+
+    let mut gathering = Gathering::new();
+    if let Some(local) = pc.local_description().await {
+        for line in local.sdp.lines() {
+            if let Some(candidate) = line.strip_prefix("a=") {
+                if candidate.starts_with("candidate:") {
+                    gathering.apply(Signal::Candidate(candidate.to_string()));
+                }
+            }
+        }
+    }
+    if pc.ice_gathering_state() == RTCIceGatheringState::Complete {
+        gathering.apply(Signal::Complete);
+    }
+
+    let report = |gathering: &Gathering| {
+        if let Some(tx) = progress {
+            let _ = tx.send(GatheringProgress {
+                source,
+                peer_id: peer_id.map(str::to_string),
+                phase: gathering.phase,
+                candidates: gathering.candidates.len(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                timeout_ms: timeout.as_millis() as u64,
+            });
+        }
+    };
+    report(&gathering);
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    while gathering.phase == GatheringPhase::Gathering {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(signal)) => {
+                if gathering.apply(signal) {
+                    report(&gathering);
+                }
+            }
+            // Every sender is gone: nothing more can be learned
+            Ok(None) => break,
+            Err(_) => {
+                if gathering.time_out() {
+                    report(&gathering);
+                }
+            }
+        }
+    }
+
+    // Later candidates would be missing from the SDP anyway
+    pc.on_ice_candidate(Box::new(|_: Option<RTCIceCandidate>| Box::pin(async {})));
+    pc.on_ice_gathering_state_change(Box::new(|_: RTCIceGathererState| Box::pin(async {})));
+
+    if gathering.phase == GatheringPhase::Complete {
+        tracing::debug!(
+            "ICE gathering complete in {:?} ({} candidates)",
+            started.elapsed(),
+            gathering.candidates.len()
+        );
+        true
+    } else {
+        tracing::warn!(
+            "ICE gathering still running after {:?}, sending the {} candidates gathered so far",
+            started.elapsed(),
+            gathering.candidates.len()
+        );
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gathering_counts_each_candidate_once_until_it_ends() {
+        let mut gathering = Gathering::new();
+        let host =
+            || Signal::Candidate("candidate:1 1 udp 2130706431 10.0.0.2 5000 typ host".into());
+        assert!(gathering.apply(host()));
+        assert!(!gathering.apply(host()));
+        assert!(gathering.apply(Signal::Complete));
+        assert_eq!(gathering.phase, GatheringPhase::Complete);
+
+        // Nothing changes once complete, not even the timeout
+        assert!(!gathering.apply(Signal::Candidate("candidate:2".into())));
+        assert!(!gathering.time_out());
+        assert_eq!(gathering.candidates.len(), 1);
+
+        let mut slow = Gathering::new();
+        assert!(slow.time_out());
+        assert_eq!(slow.phase, GatheringPhase::TimedOut);
+        assert!(!slow.apply(Signal::Complete));
+    }
+}
@@ -0,0 +1,131 @@
+//! ICE connectivity check
+//! Gathers candidates against each STUN server to tell whether NAT traversal works,
+//! and builds a throwaway local peer connection to validate the WebRTC stack
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+
+use crate::error::{AppError, AppResult};
+
+/// STUN servers used by the peer connections
+pub const STUN_SERVERS: &[&str] = &["stun:stun.l.google.com:19302", "stun:stun.cloudflare.com:3478"];
+
+/// Result of gathering against one STUN server
+#[derive(Debug, Clone, Serialize)]
+pub struct IceServerCheck {
+    pub url: String,
+    /// A server-reflexive candidate was obtained (the server is reachable)
+    pub reachable: bool,
+    pub host_candidates: usize,
+    pub srflx_candidates: usize,
+    pub relay_candidates: usize,
+    pub gathering_ms: u64,
+    pub timed_out: bool,
+    pub error: Option<String>,
+}
+
+/// Check every STUN server, one peer connection each
+pub async fn check_stun_servers(timeout: Duration) -> Vec<IceServerCheck> {
+    let mut results = Vec::with_capacity(STUN_SERVERS.len());
+    for url in STUN_SERVERS {
+        let result = match check_server(url, timeout).await {
+            Ok(result) => result,
+            Err(e) => IceServerCheck {
+                url: url.to_string(),
+                reachable: false,
+                host_candidates: 0,
+                srflx_candidates: 0,
+                relay_candidates: 0,
+                gathering_ms: 0,
+                timed_out: false,
+                error: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+    results
+}
+
+async fn check_server(url: &str, timeout: Duration) -> AppResult<IceServerCheck> {
+    let api = APIBuilder::new()
+        .with_media_engine(MediaEngine::default())
+        .build();
+    let config = RTCConfiguration {
+        ice_servers: vec![RTCIceServer {
+            urls: vec![url.to_owned()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let pc = api.new_peer_connection(config).await?;
+
+    // A data channel is needed for the offer to trigger gathering
+    pc.create_data_channel("ice-check", None).await?;
+    let offer = pc.create_offer(None).await?;
+    let mut gathering_done = pc.gathering_complete_promise().await;
+
+    let started = Instant::now();
+    pc.set_local_description(offer).await?;
+    let timed_out = tokio::time::timeout(timeout, gathering_done.recv())
+        .await
+        .is_err();
+    let gathering_ms = started.elapsed().as_millis() as u64;
+
+    let sdp = pc
+        .local_description()
+        .await
+        .map(|desc| desc.sdp)
+        .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
+    let _ = pc.close().await;
+
+    let count = |typ: &str| {
+        sdp.lines()
+            .filter(|line| line.starts_with("a=candidate:"))
+            .filter(|line| {
+                let mut fields = line.split_whitespace().skip_while(|f| *f != "typ").skip(1);
+                fields.next() == Some(typ)
+            })
+            .count()
+    };
+    let srflx_candidates = count("srflx");
+
+    Ok(IceServerCheck {
+        url: url.to_string(),
+        reachable: srflx_candidates > 0,
+        host_candidates: count("host"),
+        srflx_candidates,
+        relay_candidates: count("relay"),
+        gathering_ms,
+        timed_out,
+        error: None,
+    })
+}
+
+/// Build a peer connection the way the meshes do (codecs, interceptors, data
+/// channel), produce a local offer and close it. No network access needed.
+pub async fn check_local_stack() -> AppResult<()> {
+    let mut media_engine = MediaEngine::default();
+    media_engine.register_default_codecs()?;
+    let registry = register_default_interceptors(Registry::new(), &mut media_engine)?;
+    let api = APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let result = async {
+        pc.create_data_channel("self-test", None).await?;
+        let offer = pc.create_offer(None).await?;
+        pc.set_local_description(offer).await?;
+        Ok::<(), AppError>(())
+    }
+    .await;
+    let _ = pc.close().await;
+    result
+}
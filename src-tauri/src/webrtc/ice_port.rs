@@ -0,0 +1,127 @@
+//! Fixed ICE port
+//! While hosting, every new peer connection gathers its UDP candidates on one
+//! shared socket (webrtc-rs UDP mux) instead of a random port per connection,
+//! so that single port can be forwarded by the router (see `port_mapping`).
+//! Once forwarded, the public address is advertised in the candidates.
+
+use parking_lot::{Mutex, RwLock};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use webrtc::api::setting_engine::SettingEngine;
+use webrtc::ice::udp_mux::{UDPMux, UDPMuxDefault, UDPMuxParams};
+use webrtc::ice::udp_network::UDPNetwork;
+use webrtc::ice_transport::ice_candidate_type::RTCIceCandidateType;
+
+use super::network;
+use crate::error::{AppError, AppResult};
+
+/// How often a closed port checks whether its last connection is gone
+const DRAIN_POLL: Duration = Duration::from_secs(1);
+
+/// Shared socket and its port
+type Mux = (u16, Arc<UDPMuxDefault>);
+
+/// Shared socket, while open
+static MUX: RwLock<Option<Mux>> = RwLock::new(None);
+
+/// Closed socket still carrying connections made through it
+static DRAINING: Mutex<Option<Mux>> = Mutex::new(None);
+
+/// Public IP the router forwards the shared port from, on the same port number
+static EXTERNAL_IP: RwLock<Option<IpAddr>> = RwLock::new(None);
+
+/// Bind the shared socket on `port` (0 for any free port), returning its port
+/// The address follows the network settings (IPv6, chosen interface).
+pub async fn open(port: u16) -> AppResult<u16> {
+    if let Some((port, _)) = MUX.read().as_ref() {
+        return Ok(*port);
+    }
+    // Still draining from the last time we hosted: take it back rather than
+    // fail to bind the same port
+    let draining = DRAINING.lock().take();
+    if let Some((draining_port, mux)) = draining {
+        if port == 0 || port == draining_port {
+            *MUX.write() = Some((draining_port, mux));
+            tracing::info!("ICE traffic shares UDP port {} again", draining_port);
+            return Ok(draining_port);
+        }
+        *DRAINING.lock() = Some((draining_port, mux));
+    }
+
+    let socket = tokio::net::UdpSocket::bind((network::current().bind_address(), port))
+        .await
+        .map_err(|e| AppError::WebRtc(format!("Failed to bind ICE port {}: {}", port, e)))?;
+    let port = socket
+        .local_addr()
+        .map_err(|e| AppError::WebRtc(format!("Failed to read the ICE port: {}", e)))?
+        .port();
+    *MUX.write() = Some((port, UDPMuxDefault::new(UDPMuxParams::new(socket))));
+    tracing::info!("ICE traffic shares UDP port {}", port);
+    Ok(port)
+}
+
+/// Go back to a random port per connection
+/// New connections stop using the shared socket right away; it is closed once
+/// the connections made through it are gone.
+pub async fn close() {
+    *EXTERNAL_IP.write() = None;
+    let Some((port, mux)) = MUX.write().take() else {
+        return;
+    };
+    // Every connection gathered on the socket holds a reference to it
+    if Arc::strong_count(&mux) == 1 {
+        close_mux(port, &mux).await;
+        return;
+    }
+    tracing::info!("ICE port {} closes once its connections are gone", port);
+    if let Some((previous, mux)) = DRAINING.lock().replace((port, mux)) {
+        tauri::async_runtime::spawn(async move { close_mux(previous, &mux).await });
+    }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(DRAIN_POLL).await;
+            let drained = {
+                let mut draining = DRAINING.lock();
+                let refs = match draining.as_ref() {
+                    Some((p, mux)) if *p == port => Arc::strong_count(mux),
+                    // Reopened, or replaced by a later close
+                    _ => return,
+                };
+                if refs > 1 {
+                    continue;
+                }
+                draining.take()
+            };
+            if let Some((port, mux)) = drained {
+                close_mux(port, &mux).await;
+            }
+            return;
+        }
+    });
+}
+
+async fn close_mux(port: u16, mux: &UDPMuxDefault) {
+    if let Err(e) = mux.close().await {
+        tracing::debug!("Failed to close ICE port {}: {}", port, e);
+    }
+}
+
+/// Advertise `ip` as our address on the shared port (the router forwards the
+/// same port number), None when nothing is forwarded
+pub fn set_external_ip(ip: Option<IpAddr>) {
+    *EXTERNAL_IP.write() = ip;
+}
+
+/// Gather the candidates of a new connection on the shared socket, when open
+pub(super) fn apply(engine: &mut SettingEngine) {
+    let Some((_, mux)) = MUX.read().clone() else {
+        return;
+    };
+    engine.set_udp_network(UDPNetwork::Muxed(mux));
+    // Mapped as host candidates: webrtc-rs gathers mapped server-reflexive
+    // candidates on a socket of their own, outside the forwarded port
+    if let Some(ip) = *EXTERNAL_IP.read() {
+        engine.set_nat_1to1_ips(vec![ip.to_string()], RTCIceCandidateType::Host);
+    }
+}
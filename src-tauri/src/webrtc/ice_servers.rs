@@ -0,0 +1,225 @@
+//! ICE servers
+//! STUN and TURN servers used by every peer connection, native or in the
+//! webview. Only public STUN servers are set up by default: a TURN relay (for
+//! symmetric NATs, or calls behind a proxy) is added by the user with its own
+//! credentials. Like the timeouts, these are user settings read by every
+//! manager when it creates a peer connection.
+//! When the active proxy profile is relay only, connections use the TURN
+//! servers reachable over TCP/TLS and nothing else.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const ICE_SERVERS_FILE: &str = "ice_servers.json";
+
+/// Used when the user configured nothing
+const DEFAULT_STUN_SERVERS: &[&str] = &[
+    "stun:stun.l.google.com:19302",
+    "stun:stun.cloudflare.com:3478",
+];
+
+/// A STUN or TURN server (same shape as the webview's `RTCIceServer`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IceServer {
+    pub urls: Vec<String>,
+    /// TURN only
+    #[serde(default)]
+    pub username: Option<String>,
+    /// TURN only
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+impl IceServer {
+    fn is_turn(&self) -> bool {
+        self.urls
+            .iter()
+            .any(|url| url.starts_with("turn:") || url.starts_with("turns:"))
+    }
+
+    /// Reachable through an HTTP or SOCKS5 proxy: TURN over TCP or TLS only
+    fn is_tcp_relay(&self) -> bool {
+        self.urls
+            .iter()
+            .all(|url| url.starts_with("turns:") || url.ends_with("transport=tcp"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IceServerSettings {
+    pub servers: Vec<IceServer>,
+}
+
+impl IceServerSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        for server in &self.servers {
+            if server.urls.is_empty() {
+                return Err(AppError::InvalidArgument(
+                    "An ICE server needs at least one URL".to_string(),
+                ));
+            }
+            if let Some(url) = server.urls.iter().find(|url| {
+                !["stun:", "turn:", "turns:"]
+                    .iter()
+                    .any(|scheme| url.starts_with(scheme))
+            }) {
+                return Err(AppError::InvalidArgument(format!(
+                    "\"{}\" is not a stun:, turn: or turns: URL",
+                    url
+                )));
+            }
+            if server.is_turn() && (server.username.is_none() || server.credential.is_none()) {
+                return Err(AppError::InvalidArgument(format!(
+                    "The TURN server {} needs a username and a credential",
+                    server.urls[0]
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Servers a new connection uses, given the relay policy
+    fn effective(&self, relay_only: bool) -> Vec<IceServer> {
+        self.servers
+            .iter()
+            .filter(|server| !relay_only || server.is_tcp_relay())
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for IceServerSettings {
+    fn default() -> Self {
+        Self {
+            servers: DEFAULT_STUN_SERVERS
+                .iter()
+                .map(|url| IceServer {
+                    urls: vec![url.to_string()],
+                    username: None,
+                    credential: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What a new connection uses, for the webview's `RTCPeerConnection`
+#[derive(Debug, Clone, Serialize)]
+pub struct IceConfiguration {
+    pub servers: Vec<IceServer>,
+    /// Only relayed candidates (the active proxy can't carry UDP)
+    pub relay_only: bool,
+}
+
+/// None until settings are saved or loaded: the defaults apply
+static CURRENT: RwLock<Option<IceServerSettings>> = RwLock::new(None);
+
+/// Set from the proxy profile the main window was created with
+static RELAY_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// ICE servers configured by the user
+pub fn current() -> IceServerSettings {
+    CURRENT.read().clone().unwrap_or_default()
+}
+
+/// Apply the saved servers (defaults when unset or invalid)
+pub fn load() {
+    if let Some(settings) = storage::load_json::<IceServerSettings>(ICE_SERVERS_FILE) {
+        match settings.validate() {
+            Ok(()) => *CURRENT.write() = Some(settings),
+            Err(e) => tracing::warn!("Ignoring saved ICE servers: {}", e),
+        }
+    }
+}
+
+/// Validate, save and apply new servers
+pub fn save(settings: IceServerSettings) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(ICE_SERVERS_FILE, &settings).map_err(AppError::Storage)?;
+    *CURRENT.write() = Some(settings);
+    Ok(())
+}
+
+/// Only use TURN over TCP/TLS from now on (the proxy in use can't carry UDP)
+pub fn set_relay_only(relay_only: bool) {
+    RELAY_ONLY.store(relay_only, Ordering::Relaxed);
+}
+
+/// Servers and relay policy of connections created from now on
+pub fn configuration() -> IceConfiguration {
+    let relay_only = RELAY_ONLY.load(Ordering::Relaxed);
+    let servers = current().effective(relay_only);
+    if relay_only && servers.is_empty() {
+        tracing::warn!("Relay only, but no TURN server over TCP/TLS is configured");
+    }
+    IceConfiguration {
+        servers,
+        relay_only,
+    }
+}
+
+/// Configuration of a new native peer connection (certificates left to the caller)
+pub fn rtc_configuration() -> RTCConfiguration {
+    let IceConfiguration {
+        servers,
+        relay_only,
+    } = configuration();
+    RTCConfiguration {
+        ice_servers: servers
+            .into_iter()
+            .map(|server| RTCIceServer {
+                urls: server.urls,
+                username: server.username.unwrap_or_default(),
+                credential: server.credential.unwrap_or_default(),
+                ..Default::default()
+            })
+            .collect(),
+        ice_transport_policy: if relay_only {
+            RTCIceTransportPolicy::Relay
+        } else {
+            RTCIceTransportPolicy::All
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(urls: &[&str]) -> IceServer {
+        IceServer {
+            urls: urls.iter().map(|url| url.to_string()).collect(),
+            username: Some("user".to_string()),
+            credential: Some("secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn relay_only_keeps_tcp_relays() {
+        let mut settings = IceServerSettings::default();
+        settings.servers.push(turn(&["turn:relay.example:3478"]));
+        settings
+            .servers
+            .push(turn(&["turns:relay.example:443?transport=tcp"]));
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.effective(false).len(), 4);
+        assert_eq!(
+            settings.effective(true),
+            [turn(&["turns:relay.example:443?transport=tcp"])]
+        );
+
+        settings.servers[2].credential = None;
+        assert!(settings.validate().is_err());
+        settings.servers[2] = turn(&["http://relay.example"]);
+        assert!(settings.validate().is_err());
+    }
+}
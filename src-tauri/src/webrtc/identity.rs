@@ -0,0 +1,60 @@
+//! Persistent DTLS identity
+//! The local certificate is stored in the config dir so our fingerprint stays the
+//! same across sessions, letting remote peers recognize us by identity.
+
+use std::sync::OnceLock;
+use webrtc::peer_connection::certificate::RTCCertificate;
+
+use crate::storage;
+
+/// File holding the PEM-encoded certificate and private key
+const IDENTITY_FILE: &str = "identity.pem";
+
+static LOCAL_CERTIFICATE: OnceLock<RTCCertificate> = OnceLock::new();
+
+/// Get the local DTLS certificate (loaded or generated on first use)
+pub fn local_certificate() -> RTCCertificate {
+    LOCAL_CERTIFICATE.get_or_init(load_or_generate).clone()
+}
+
+fn load_or_generate() -> RTCCertificate {
+    if let Some(pem) = storage::read_file(IDENTITY_FILE) {
+        match RTCCertificate::from_pem(&pem) {
+            Ok(cert) => return cert,
+            Err(e) => tracing::warn!("Invalid identity file, generating a new one: {}", e),
+        }
+    }
+
+    let key_pair = rcgen::KeyPair::generate().expect("Failed to generate identity key pair");
+    let cert = RTCCertificate::from_key_pair(key_pair).expect("Failed to generate identity certificate");
+
+    if let Err(e) = storage::write_file(IDENTITY_FILE, &cert.serialize_pem()) {
+        tracing::warn!("Failed to persist identity: {}", e);
+    }
+
+    tracing::info!("Generated new peer identity");
+    cert
+}
+
+/// Extract the DTLS fingerprint (e.g. "sha-256 ab:cd:...") from an SDP blob
+pub fn sdp_fingerprint(sdp: &str) -> Option<String> {
+    sdp.lines()
+        .find_map(|line| line.trim().strip_prefix("a=fingerprint:"))
+        .map(|value| value.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sdp_fingerprint() {
+        let sdp = "v=0\r\no=- 1 2 IN IP4 0.0.0.0\r\na=fingerprint:sha-256 AB:CD:EF\r\na=setup:actpass\r\n";
+        assert_eq!(sdp_fingerprint(sdp).as_deref(), Some("sha-256 ab:cd:ef"));
+    }
+
+    #[test]
+    fn test_sdp_fingerprint_missing() {
+        assert!(sdp_fingerprint("v=0\r\n").is_none());
+    }
+}
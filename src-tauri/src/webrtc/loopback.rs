@@ -0,0 +1,126 @@
+//! In-process loopback
+//! Connects two meshes living in the same process with the full offer/answer
+//! exchange and no signaling server. Used by the headless soak test and the
+//! two-peer integration tests.
+
+use super::AudioMeshManager;
+use crate::error::AppResult;
+
+/// Connect `offerer` to `answerer` (each id doubles as the peer's username)
+pub async fn connect(
+    offerer: &AudioMeshManager,
+    offerer_id: &str,
+    answerer: &AudioMeshManager,
+    answerer_id: &str,
+) -> AppResult<()> {
+    let offer = offerer.create_offer_for_peer(answerer_id, answerer_id).await?;
+    let answer = answerer
+        .accept_offer_from_peer(offerer_id, offerer_id, &offer.sdp_base64)
+        .await?;
+    offerer.accept_answer_from_peer(answerer_id, &answer.sdp_base64).await
+}
+
+/// Two connected peers with their incoming chat and audio, for tests
+#[cfg(test)]
+pub(crate) struct MeshPair {
+    pub alice: AudioMeshManager,
+    pub bob: AudioMeshManager,
+    pub alice_chat: tokio::sync::mpsc::UnboundedReceiver<String>,
+    pub bob_chat: tokio::sync::mpsc::UnboundedReceiver<String>,
+    pub alice_audio: tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+    pub bob_audio: tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+}
+
+#[cfg(test)]
+impl MeshPair {
+    /// Alice offers, Bob answers
+    pub async fn connect() -> Self {
+        let (alice, alice_chat, alice_audio) = Self::mesh("alice");
+        let (bob, bob_chat, bob_audio) = Self::mesh("bob");
+        connect(&alice, "alice", &bob, "bob").await.expect("offer/answer exchange");
+
+        Self {
+            alice,
+            bob,
+            alice_chat,
+            bob_chat,
+            alice_audio,
+            bob_audio,
+        }
+    }
+
+    fn mesh(
+        username: &str,
+    ) -> (
+        AudioMeshManager,
+        tokio::sync::mpsc::UnboundedReceiver<String>,
+        tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+    ) {
+        // Senders are captured when connections are set up, so wire them first
+        let manager = AudioMeshManager::new();
+        manager.set_username(username.to_string());
+        manager.enable_local_audio(true);
+        let (chat_tx, chat_rx) = tokio::sync::mpsc::unbounded_channel();
+        manager.set_message_sender(chat_tx);
+        let (audio_tx, audio_rx) = tokio::sync::mpsc::unbounded_channel();
+        manager.set_audio_receiver(audio_tx);
+        (manager, chat_rx, audio_rx)
+    }
+}
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    use super::*;
+    use crate::audio::{OpusEncoder, SAMPLES_PER_FRAME};
+    use crate::webrtc::SignalingMessage;
+    use std::time::Duration;
+    use tokio::time::{timeout, Instant};
+
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+    const RETRY: Duration = Duration::from_millis(200);
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_and_audio_flow_between_two_peers() {
+        let mut pair = MeshPair::connect().await;
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+
+        // Chat: resend until the data channel is open
+        let chat = loop {
+            assert!(Instant::now() < deadline, "chat never arrived");
+            let _ = pair.alice.send_chat_message("hello bob").await;
+            if let Ok(Some(text)) = timeout(RETRY, pair.bob_chat.recv()).await {
+                break text;
+            }
+        };
+        match serde_json::from_str::<SignalingMessage>(&chat).unwrap() {
+            SignalingMessage::Chat { sender, content, .. } => {
+                assert_eq!(sender, "alice");
+                assert_eq!(content, "hello bob");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        // Audio: resend until the media path is up, in both directions
+        let packet = OpusEncoder::new()
+            .unwrap()
+            .encode(&vec![0.1; SAMPLES_PER_FRAME])
+            .unwrap();
+        for (sender, receiver, from) in [
+            (&pair.alice, &mut pair.bob_audio, "alice"),
+            (&pair.bob, &mut pair.alice_audio, "bob"),
+        ] {
+            let (peer_id, data) = loop {
+                assert!(Instant::now() < deadline, "audio from {} never arrived", from);
+                sender.broadcast_audio(&packet).await.unwrap();
+                if let Ok(Some(received)) = timeout(RETRY, receiver.recv()).await {
+                    break received;
+                }
+            };
+            assert_eq!(peer_id, from);
+            assert_eq!(data, packet);
+        }
+
+        pair.alice.close_all();
+        pair.bob.close_all();
+    }
+}
@@ -8,14 +8,22 @@ use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::MediaEngine;
 use webrtc::api::APIBuilder;
 use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::data_channel_state::RTCDataChannelState;
 use webrtc::data_channel::RTCDataChannel;
-use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
-use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::sdp::sdp_type::RTCSdpType;
 use webrtc::peer_connection::RTCPeerConnection;
 
-use super::signaling::{ConnectionOffer, SignalingMessage};
+use super::callback_queue::{CallbackQueue, EventSink};
+use super::gathering::{self, GatheringSender, GatheringSource};
+use super::ice_servers;
+use super::identity;
+use super::setup::{self, SetupGuard};
+use super::signaling::{self, ConnectionOffer, SignalingMessage};
+use super::timeouts;
+use crate::bans::BanList;
+use crate::error::{AppError, AppResult};
 
 pub type MessageSender = mpsc::UnboundedSender<String>;
 
@@ -24,6 +32,20 @@ struct PeerEntry {
     peer_connection: Arc<RTCPeerConnection>,
     data_channel: Option<Arc<RTCDataChannel>>,
     username: String,
+    /// DTLS fingerprint of the remote peer (known once its SDP is received)
+    fingerprint: Option<String>,
+}
+
+/// Raised by WebRTC callbacks, applied in order by the manager's event task
+enum MeshEvent {
+    /// The answering side received the offerer's data channel
+    DataChannel {
+        peer_id: String,
+        dc: Arc<RTCDataChannel>,
+    },
+    Message {
+        text: String,
+    },
 }
 
 /// Manages a mesh network of WebRTC peer connections
@@ -34,8 +56,38 @@ pub struct MeshManager {
     local_username: Arc<RwLock<Option<String>>>,
     /// Channel to send received messages to frontend
     message_tx: Arc<RwLock<Option<MessageSender>>>,
+    /// Channel for the progress of ICE gathering
+    gathering_tx: Arc<RwLock<Option<GatheringSender>>>,
     /// List of known peer usernames for mesh coordination
     known_peers: Arc<RwLock<Vec<String>>>,
+    /// Banned identities, consulted before accepting offers/answers
+    ban_list: BanList,
+    /// Applies the events raised by WebRTC callbacks
+    events: CallbackQueue<MeshEvent>,
+}
+
+/// Manager state the event task works on
+struct EventContext {
+    peers: Arc<RwLock<HashMap<String, PeerEntry>>>,
+    message_tx: Arc<RwLock<Option<MessageSender>>>,
+}
+
+impl EventContext {
+    fn apply(&self, event: MeshEvent) {
+        match event {
+            MeshEvent::DataChannel { peer_id, dc } => {
+                tracing::info!("Data channel '{}' opened from peer {}", dc.label(), peer_id);
+                if let Some(entry) = self.peers.write().get_mut(&peer_id) {
+                    entry.data_channel = Some(dc);
+                }
+            }
+            MeshEvent::Message { text } => {
+                if let Some(sender) = self.message_tx.read().as_ref() {
+                    let _ = sender.send(text);
+                }
+            }
+        }
+    }
 }
 
 impl Default for MeshManager {
@@ -46,11 +98,25 @@ impl Default for MeshManager {
 
 impl MeshManager {
     pub fn new() -> Self {
+        Self::with_ban_list(BanList::default())
+    }
+
+    pub fn with_ban_list(ban_list: BanList) -> Self {
+        let peers = Arc::new(RwLock::new(HashMap::new()));
+        let message_tx = Arc::new(RwLock::new(None));
+        let ctx = EventContext {
+            peers: peers.clone(),
+            message_tx: message_tx.clone(),
+        };
+
         Self {
-            peers: Arc::new(RwLock::new(HashMap::new())),
+            peers,
             local_username: Arc::new(RwLock::new(None)),
-            message_tx: Arc::new(RwLock::new(None)),
+            message_tx,
+            gathering_tx: Arc::new(RwLock::new(None)),
             known_peers: Arc::new(RwLock::new(Vec::new())),
+            ban_list,
+            events: CallbackQueue::spawn("Mesh", move |event| ctx.apply(event)),
         }
     }
 
@@ -62,6 +128,10 @@ impl MeshManager {
         *self.message_tx.write() = Some(tx);
     }
 
+    pub fn set_gathering_sender(&self, tx: GatheringSender) {
+        *self.gathering_tx.write() = Some(tx);
+    }
+
     pub fn get_local_username(&self) -> Option<String> {
         self.local_username.read().clone()
     }
@@ -78,53 +148,81 @@ impl MeshManager {
         self.peers.read().len()
     }
 
-    async fn create_peer_connection(&self) -> Result<Arc<RTCPeerConnection>, String> {
+    async fn create_peer_connection(&self) -> AppResult<Arc<RTCPeerConnection>> {
         let mut m = MediaEngine::default();
         m.register_default_codecs()
-            .map_err(|e| format!("Failed to register codecs: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register codecs: {}", e)))?;
 
         let mut registry = Registry::new();
         registry = register_default_interceptors(registry, &mut m)
-            .map_err(|e| format!("Failed to register interceptors: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register interceptors: {}", e)))?;
 
         let api = APIBuilder::new()
             .with_media_engine(m)
             .with_interceptor_registry(registry)
+            .with_setting_engine(timeouts::current().setting_engine())
             .build();
 
         let config = RTCConfiguration {
-            ice_servers: vec![
-                RTCIceServer {
-                    urls: vec!["stun:stun.l.google.com:19302".to_owned()],
-                    ..Default::default()
-                },
-                RTCIceServer {
-                    urls: vec!["stun:stun.cloudflare.com:3478".to_owned()],
-                    ..Default::default()
-                },
-            ],
-            ..Default::default()
+            certificates: vec![identity::local_certificate()],
+            ..ice_servers::rtc_configuration()
         };
 
-        let peer_connection = api
-            .new_peer_connection(config)
-            .await
-            .map_err(|e| format!("Failed to create peer connection: {}", e))?;
+        let peer_connection =
+            setup::step("create peer connection", api.new_peer_connection(config)).await?;
 
         Ok(Arc::new(peer_connection))
     }
 
+    /// Closes the peer being set up unless the setup completes (failure,
+    /// timeout or a cancelled command)
+    fn setup_guard(
+        &self,
+        peer_id: &str,
+        pc: &Arc<RTCPeerConnection>,
+    ) -> SetupGuard<impl FnOnce()> {
+        let peers = self.peers.clone();
+        let peer_id = peer_id.to_string();
+        let pc = pc.clone();
+        SetupGuard::new(move || {
+            tracing::warn!("Setup of peer {} did not complete, closing its connection", peer_id);
+            discard_peer(&peers, &peer_id, pc);
+        })
+    }
+
+    /// Drop the peer if its data channel is not open in time
+    fn spawn_open_deadline(&self, peer_id: &str, pc: &Arc<RTCPeerConnection>) {
+        let peers = self.peers.clone();
+        let peer_id = peer_id.to_string();
+        let pc = pc.clone();
+        let timeout = timeouts::current().connection();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let open = peers
+                .read()
+                .get(&peer_id)
+                .filter(|e| Arc::ptr_eq(&e.peer_connection, &pc))
+                .map(|e| {
+                    e.data_channel
+                        .as_ref()
+                        .is_some_and(|dc| dc.ready_state() == RTCDataChannelState::Open)
+                });
+            if open == Some(false) {
+                tracing::warn!("Data channel of peer {} never opened, dropping the peer", peer_id);
+                discard_peer(&peers, &peer_id, pc);
+            }
+        });
+    }
+
     /// Create an offer for a new peer (used by initiator)
-    pub async fn create_offer_for_peer(&self, peer_id: &str, peer_username: &str) -> Result<ConnectionOffer, String> {
+    pub async fn create_offer_for_peer(&self, peer_id: &str, peer_username: &str) -> AppResult<ConnectionOffer> {
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(peer_id, &pc);
 
         // Create data channel
-        let dc = pc
-            .create_data_channel("chat", None)
-            .await
-            .map_err(|e| format!("Failed to create data channel: {}", e))?;
+        let dc = setup::step("create data channel", pc.create_data_channel("chat", None)).await?;
 
-        self.setup_data_channel(peer_id.to_string(), dc.clone()).await;
+        watch_data_channel(&self.events.sink(), peer_id, &dc);
 
         // Store peer entry
         {
@@ -135,34 +233,31 @@ impl MeshManager {
                     peer_connection: pc.clone(),
                     data_channel: Some(dc),
                     username: peer_username.to_string(),
+                    fingerprint: None,
                 },
             );
         }
 
         // Create offer
-        let offer = pc
-            .create_offer(None)
-            .await
-            .map_err(|e| format!("Failed to create offer: {}", e))?;
+        let offer = setup::step("create offer", pc.create_offer(None)).await?;
 
-        pc.set_local_description(offer.clone())
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(offer.clone())).await?;
 
         // Wait for ICE gathering
-        self.wait_for_ice_gathering(&pc).await;
+        self.wait_for_ice_gathering(&pc, peer_id).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize SDP: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize SDP: {}", e)))?;
 
         use base64::Engine;
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: true,
@@ -175,48 +270,27 @@ impl MeshManager {
         peer_id: &str,
         peer_username: &str,
         offer_base64: &str,
-    ) -> Result<ConnectionOffer, String> {
+    ) -> AppResult<ConnectionOffer> {
+        // Decode the offer first so banned identities are rejected before any setup
+        let offer = signaling::decode_description(offer_base64, RTCSdpType::Offer)?;
+
+        let fingerprint = identity::sdp_fingerprint(&offer.sdp);
+        self.ban_list.check_not_banned(peer_id, fingerprint.as_deref())?;
+
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(peer_id, &pc);
 
         // Setup handler for incoming data channel
-        let peers = self.peers.clone();
-        let message_tx = self.message_tx.clone();
-        let peer_id_clone = peer_id.to_string();
-
+        let events = self.events.sink();
+        let dc_peer_id = peer_id.to_string();
         pc.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
-            let peers = peers.clone();
-            let message_tx = message_tx.clone();
-            let peer_id = peer_id_clone.clone();
-
-            Box::pin(async move {
-                tracing::info!("Data channel '{}' opened from peer {}", dc.label(), peer_id);
-
-                // Store data channel in peer entry
-                {
-                    let mut peers_lock = peers.write();
-                    if let Some(entry) = peers_lock.get_mut(&peer_id) {
-                        entry.data_channel = Some(dc.clone());
-                    }
-                }
-
-                // Setup message handler
-                let tx = message_tx.read().clone();
-                dc.on_message(Box::new(move |msg: DataChannelMessage| {
-                    let tx = tx.clone();
-                    Box::pin(async move {
-                        if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                            if let Some(ref sender) = tx {
-                                let _ = sender.send(text);
-                            }
-                        }
-                    })
-                }));
-
-                dc.on_open(Box::new(|| {
-                    tracing::info!("Data channel opened!");
-                    Box::pin(async {})
-                }));
-            })
+            // Before returning: the channel opens (and can receive) right after
+            watch_data_channel(&events, &dc_peer_id, &dc);
+            events.push(MeshEvent::DataChannel {
+                peer_id: dc_peer_id.clone(),
+                dc,
+            });
+            Box::pin(async {})
         }));
 
         // Store peer entry (without data channel yet, will be set in on_data_channel)
@@ -228,49 +302,33 @@ impl MeshManager {
                     peer_connection: pc.clone(),
                     data_channel: None,
                     username: peer_username.to_string(),
+                    fingerprint,
                 },
             );
         }
 
-        // Decode and set remote description
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(offer_base64)
-            .map_err(|e| format!("Failed to decode offer: {}", e))?;
-
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in offer: {}", e))?;
-
-        let offer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse offer: {}", e))?;
-
-        pc.set_remote_description(offer)
-            .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+        setup::step("set remote description", pc.set_remote_description(offer)).await?;
 
         // Create answer
-        let answer = pc
-            .create_answer(None)
-            .await
-            .map_err(|e| format!("Failed to create answer: {}", e))?;
+        let answer = setup::step("create answer", pc.create_answer(None)).await?;
 
-        pc.set_local_description(answer)
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(answer)).await?;
 
         // Wait for ICE gathering
-        self.wait_for_ice_gathering(&pc).await;
+        self.wait_for_ice_gathering(&pc, peer_id).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize answer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize answer: {}", e)))?;
 
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
+        self.spawn_open_deadline(peer_id, &pc);
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: false,
@@ -278,86 +336,81 @@ impl MeshManager {
     }
 
     /// Accept an answer from a peer
-    pub async fn accept_answer_from_peer(&self, peer_id: &str, answer_base64: &str) -> Result<(), String> {
+    pub async fn accept_answer_from_peer(&self, peer_id: &str, answer_base64: &str) -> AppResult<()> {
         let pc = {
             let peers = self.peers.read();
             peers
                 .get(peer_id)
                 .map(|e| e.peer_connection.clone())
-                .ok_or_else(|| format!("No peer connection for {}", peer_id))?
+                .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))?
         };
 
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(answer_base64)
-            .map_err(|e| format!("Failed to decode answer: {}", e))?;
-
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in answer: {}", e))?;
+        let answer = signaling::decode_description(answer_base64, RTCSdpType::Answer)?;
 
-        let answer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse answer: {}", e))?;
+        let fingerprint = identity::sdp_fingerprint(&answer.sdp);
+        if let Err(e) = self.ban_list.check_not_banned(peer_id, fingerprint.as_deref()) {
+            self.remove_peer(peer_id);
+            return Err(e);
+        }
+        if let Some(entry) = self.peers.write().get_mut(peer_id) {
+            entry.fingerprint = fingerprint;
+        }
 
-        pc.set_remote_description(answer)
-            .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+        let guard = self.setup_guard(peer_id, &pc);
+        setup::step("set remote description", pc.set_remote_description(answer)).await?;
+        guard.complete();
+        self.spawn_open_deadline(peer_id, &pc);
 
         tracing::info!("Answer from peer {} accepted", peer_id);
         Ok(())
     }
 
-    async fn setup_data_channel(&self, peer_id: String, dc: Arc<RTCDataChannel>) {
-        let message_tx = self.message_tx.clone();
-
-        dc.on_open(Box::new(move || {
-            tracing::info!("Data channel opened for peer!");
-            Box::pin(async {})
-        }));
-
-        let tx = message_tx.read().clone();
-        dc.on_message(Box::new(move |msg: DataChannelMessage| {
-            let tx = tx.clone();
-            Box::pin(async move {
-                if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                    tracing::info!("Received message: {}", text);
-                    if let Some(ref sender) = tx {
-                        let _ = sender.send(text);
-                    }
-                }
-            })
-        }));
+    /// Get the username of a peer
+    pub fn peer_username(&self, peer_id: &str) -> Option<String> {
+        self.peers.read().get(peer_id).map(|e| e.username.clone())
+    }
 
-        let peer_id_clone = peer_id.clone();
-        dc.on_close(Box::new(move || {
-            tracing::info!("Data channel closed for peer {}", peer_id_clone);
-            Box::pin(async {})
-        }));
+    /// Get the identity fingerprint and username of a peer
+    pub fn peer_fingerprint(&self, peer_id: &str) -> Option<(String, String)> {
+        let peers = self.peers.read();
+        let entry = peers.get(peer_id)?;
+        entry
+            .fingerprint
+            .clone()
+            .map(|fp| (fp, entry.username.clone()))
     }
 
-    async fn wait_for_ice_gathering(&self, _pc: &Arc<RTCPeerConnection>) {
-        // Simple wait for ICE candidates
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    async fn wait_for_ice_gathering(&self, pc: &Arc<RTCPeerConnection>, peer_id: &str) {
+        let progress = self.gathering_tx.read().clone();
+        gathering::wait_for_gathering(
+            pc,
+            progress.as_ref(),
+            GatheringSource::Mesh,
+            Some(peer_id),
+            timeouts::current().gathering(),
+        )
+        .await;
     }
 
     /// Send a message to a specific peer
-    pub async fn send_to_peer(&self, peer_id: &str, message: &str) -> Result<(), String> {
+    pub async fn send_to_peer(&self, peer_id: &str, message: &str) -> AppResult<()> {
         let dc = {
             let peers = self.peers.read();
             peers
                 .get(peer_id)
                 .and_then(|e| e.data_channel.clone())
-                .ok_or_else(|| format!("No data channel for peer {}", peer_id))?
+                .ok_or_else(|| AppError::PeerNotFound(peer_id.to_string()))?
         };
 
         dc.send_text(message.to_string())
             .await
-            .map_err(|e| format!("Failed to send to peer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to send to peer: {}", e)))?;
 
         Ok(())
     }
 
     /// Broadcast a message to all connected peers
-    pub async fn broadcast(&self, message: &str) -> Result<(), String> {
+    pub async fn broadcast(&self, message: &str) -> AppResult<()> {
         let peer_ids: Vec<String> = self.peers.read().keys().cloned().collect();
 
         for peer_id in peer_ids {
@@ -370,7 +423,7 @@ impl MeshManager {
     }
 
     /// Send a chat message to all peers
-    pub async fn send_chat_message(&self, content: &str) -> Result<(), String> {
+    pub async fn send_chat_message(&self, content: &str) -> AppResult<()> {
         let username = self
             .local_username
             .read()
@@ -387,7 +440,7 @@ impl MeshManager {
         };
 
         let json = serde_json::to_string(&msg)
-            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
 
         self.broadcast(&json).await
     }
@@ -418,20 +471,34 @@ impl MeshManager {
         }
     }
 
+    /// Tell every peer we are leaving, then close all connections and wait for
+    /// them to shut down
+    pub async fn leave(&self) {
+        if let Some(username) = self.get_local_username() {
+            if let Ok(json) = serde_json::to_string(&SignalingMessage::user_left(username)) {
+                let _ = self.broadcast(&json).await;
+            }
+        }
+        let entries: Vec<PeerEntry> = self.peers.write().drain().map(|(_, v)| v).collect();
+        for entry in entries {
+            let _ = entry.peer_connection.close().await;
+        }
+    }
+
     /// Relay a peer offer to another peer (for in-band signaling)
     pub async fn relay_peer_offer(
         &self,
         from_peer_id: &str,
         to_peer_id: &str,
         offer_base64: &str,
-    ) -> Result<(), String> {
+    ) -> AppResult<()> {
         let msg = SignalingMessage::PeerOffer {
             from_peer: from_peer_id.to_string(),
             sdp_base64: offer_base64.to_string(),
         };
 
         let json = serde_json::to_string(&msg)
-            .map_err(|e| format!("Failed to serialize peer offer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize peer offer: {}", e)))?;
 
         self.send_to_peer(to_peer_id, &json).await
     }
@@ -442,27 +509,70 @@ impl MeshManager {
         from_peer_id: &str,
         to_peer_id: &str,
         answer_base64: &str,
-    ) -> Result<(), String> {
+    ) -> AppResult<()> {
         let msg = SignalingMessage::PeerAnswer {
             from_peer: from_peer_id.to_string(),
             sdp_base64: answer_base64.to_string(),
         };
 
         let json = serde_json::to_string(&msg)
-            .map_err(|e| format!("Failed to serialize peer answer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize peer answer: {}", e)))?;
 
         self.send_to_peer(to_peer_id, &json).await
     }
 
     /// Announce a new peer to all existing peers
-    pub async fn announce_new_peer(&self, new_peer_username: &str) -> Result<(), String> {
+    pub async fn announce_new_peer(&self, new_peer_username: &str) -> AppResult<()> {
         let msg = SignalingMessage::NewPeerAnnounce {
             username: new_peer_username.to_string(),
         };
 
         let json = serde_json::to_string(&msg)
-            .map_err(|e| format!("Failed to serialize announcement: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize announcement: {}", e)))?;
 
         self.broadcast(&json).await
     }
 }
+
+/// Queue the messages of a peer's data channel (open and close are only logged)
+fn watch_data_channel(events: &EventSink<MeshEvent>, peer_id: &str, dc: &Arc<RTCDataChannel>) {
+    let open_peer_id = peer_id.to_string();
+    dc.on_open(Box::new(move || {
+        tracing::info!("Data channel opened for peer {}", open_peer_id);
+        Box::pin(async {})
+    }));
+
+    let sink = events.clone();
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
+            sink.push(MeshEvent::Message { text });
+        }
+        Box::pin(async {})
+    }));
+
+    let close_peer_id = peer_id.to_string();
+    dc.on_close(Box::new(move || {
+        tracing::info!("Data channel closed for peer {}", close_peer_id);
+        Box::pin(async {})
+    }));
+}
+
+/// Remove `peer_id` if it is still backed by `pc`, and close `pc`
+fn discard_peer(
+    peers: &RwLock<HashMap<String, PeerEntry>>,
+    peer_id: &str,
+    pc: Arc<RTCPeerConnection>,
+) {
+    {
+        let mut peers = peers.write();
+        if peers
+            .get(peer_id)
+            .is_some_and(|e| Arc::ptr_eq(&e.peer_connection, &pc))
+        {
+            peers.remove(peer_id);
+        }
+    }
+    tokio::spawn(async move {
+        let _ = pc.close().await;
+    });
+}
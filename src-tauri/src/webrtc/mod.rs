@@ -1,14 +1,36 @@
 mod audio_mesh;
 mod audio_track;
+mod callback_queue;
+pub mod gathering;
+pub mod ice_check;
+pub mod ice_port;
+pub mod ice_servers;
+mod identity;
+pub mod loopback;
 mod mesh_manager;
+pub mod network;
+mod payload_pool;
 mod peer_connection;
+mod quality;
+mod reorder;
+mod setup;
 mod signaling;
+pub mod timeouts;
+#[cfg(feature = "whip")]
+mod whip;
 
-pub use audio_mesh::AudioMeshManager;
+pub use audio_mesh::{
+    AudioMeshManager, AudioPacketSender, BinarySender, MediaLoss, MediaLostSender, MessageSender,
+    PeerStateSender, PeerTransportStats, SignalingSender, DEFAULT_CHANNEL,
+};
 pub use audio_track::calculate_audio_level;
 pub use mesh_manager::MeshManager;
+pub use payload_pool::PayloadPool;
 pub use peer_connection::WebRTCManager;
-pub use signaling::ConnectionOffer;
+pub use quality::{loss_percent, score, QualityReport, QualityTracker};
+pub use signaling::{ConnectionOffer, SdpError};
+#[cfg(feature = "whip")]
+pub use whip::{WhipConfig, WhipPublisher, WhipStatus};
 
 #[allow(dead_code, unused_imports)]
 pub use audio_track::{
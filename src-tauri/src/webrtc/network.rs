@@ -0,0 +1,272 @@
+//! Network selection
+//! Which addresses ICE gathers candidates on: IPv6 can be preferred (IPv4
+//! stays available, IPv6 pairs are just tried first) or required, and candidates can be limited to one network interface (to force
+//! a VPN or the LAN adapter). Like the timeouts, these are user settings read
+//! by every manager when it creates a peer connection, and by the fixed ICE
+//! port when it is bound.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use webrtc::api::setting_engine::SettingEngine;
+use webrtc::ice::network_type::NetworkType;
+use webrtc::util::ifaces::{self, Kind};
+
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const NETWORK_FILE: &str = "network.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpFamily {
+    /// IPv4 and IPv6 candidates, ICE picks the pair that works
+    #[default]
+    Any,
+    /// Both, IPv6 pairs first when this machine has a routable IPv6 address
+    PreferIpv6,
+    /// Only IPv6 (peers without IPv6 can't connect)
+    RequireIpv6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub ip_family: IpFamily,
+    /// Only gather candidates on this interface (None: every interface)
+    pub interface: Option<String>,
+}
+
+/// A network interface candidates can be limited to
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+}
+
+impl NetworkSettings {
+    pub const DEFAULT: Self = Self {
+        ip_family: IpFamily::Any,
+        interface: None,
+    };
+
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(name) = &self.interface {
+            if name.trim().is_empty() {
+                return Err(AppError::InvalidArgument(
+                    "The interface name can't be empty".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn ipv6_only(&self) -> bool {
+        self.ip_family == IpFamily::RequireIpv6
+    }
+
+    /// Whether IPv6 pairs go first, given the addresses of the allowed interfaces
+    fn prefers_ipv6(&self, addresses: &[IpAddr]) -> bool {
+        self.ip_family == IpFamily::PreferIpv6 && addresses.iter().any(is_routable_ipv6)
+    }
+
+    /// Addresses of the interfaces candidates may be gathered on
+    fn allowed_addresses(&self) -> Vec<IpAddr> {
+        list_interfaces()
+            .into_iter()
+            .filter(|iface| match &self.interface {
+                Some(name) => *name == iface.name,
+                None => true,
+            })
+            .flat_map(|iface| iface.addresses)
+            .collect()
+    }
+
+    /// Restrict the candidates of a new peer connection
+    pub(super) fn apply(&self, engine: &mut SettingEngine) {
+        if self.ipv6_only() {
+            engine.set_network_types(vec![NetworkType::Udp6]);
+        }
+        if let Some(name) = self.interface.clone() {
+            engine.set_interface_filter(Box::new(move |iface: &str| iface == name));
+        }
+    }
+
+    /// Address the fixed ICE port is bound on
+    pub fn bind_address(&self) -> IpAddr {
+        let addresses = self.allowed_addresses();
+        let ipv6_only = self.ipv6_only();
+        let family_matches = |ip: &&IpAddr| ip.is_ipv6() == ipv6_only;
+        match &self.interface {
+            // On that interface only, so traffic can't leave through another one
+            Some(_) => match addresses.iter().find(family_matches) {
+                Some(ip) => *ip,
+                None => {
+                    tracing::warn!(
+                        "Interface {:?} has no usable address, binding the ICE port on all",
+                        self.interface
+                    );
+                    unspecified(ipv6_only)
+                }
+            },
+            None => unspecified(ipv6_only),
+        }
+    }
+
+    /// Remote description with its IPv4 candidates ranked below its IPv6 ones
+    /// when IPv6 is preferred: our agent then checks, and when controlling
+    /// nominates, IPv6 pairs first (webrtc-rs has no setting for this)
+    pub fn prioritize_remote(&self, sdp: &str) -> Option<String> {
+        // Checked first: listing the interfaces isn't free
+        if self.ip_family != IpFamily::PreferIpv6 {
+            return None;
+        }
+        if !self.prefers_ipv6(&self.allowed_addresses()) {
+            return None;
+        }
+        let lines: Vec<String> = sdp
+            .split("\r\n")
+            .map(|line| lower_ipv4_candidate(line).unwrap_or_else(|| line.to_string()))
+            .collect();
+        Some(lines.join("\r\n"))
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+fn unspecified(ipv6: bool) -> IpAddr {
+    if ipv6 {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    }
+}
+
+/// An IPv6 address peers outside the link can reach (global or unique local)
+fn is_routable_ipv6(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(ip) => {
+            let link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+            !ip.is_loopback() && !ip.is_unspecified() && !link_local
+        }
+        IpAddr::V4(_) => false,
+    }
+}
+
+/// `a=candidate:` line of an IPv4 candidate with half its local preference
+/// (it stays above every candidate of a lower type)
+fn lower_ipv4_candidate(line: &str) -> Option<String> {
+    let candidate = line.strip_prefix("a=candidate:")?;
+    // foundation component transport priority address port typ ...
+    let mut fields: Vec<&str> = candidate.split(' ').collect();
+    let address: IpAddr = fields.get(4)?.parse().ok()?;
+    if !address.is_ipv4() {
+        return None;
+    }
+    let priority: u32 = fields.get(3)?.parse().ok()?;
+    let local_preference = (priority >> 8) & 0xffff;
+    let lowered = (priority & !0x00ff_ff00) | ((local_preference / 2) << 8);
+    let lowered = lowered.to_string();
+    fields[3] = &lowered;
+    Some(format!("a=candidate:{}", fields.join(" ")))
+}
+
+/// Interfaces with an IP address, loopback excluded, by name
+pub fn list_interfaces() -> Vec<NetworkInterface> {
+    let found = match ifaces::ifaces() {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!("Failed to list the network interfaces: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut by_name: BTreeMap<String, Vec<IpAddr>> = BTreeMap::new();
+    for iface in found {
+        if !matches!(iface.kind, Kind::Ipv4 | Kind::Ipv6) {
+            continue;
+        }
+        let Some(addr) = iface.addr else {
+            continue;
+        };
+        if addr.ip().is_loopback() {
+            continue;
+        }
+        by_name.entry(iface.name).or_default().push(addr.ip());
+    }
+    by_name
+        .into_iter()
+        .map(|(name, addresses)| NetworkInterface { name, addresses })
+        .collect()
+}
+
+static CURRENT: RwLock<NetworkSettings> = RwLock::new(NetworkSettings::DEFAULT);
+
+/// Network settings for connections created from now on
+pub fn current() -> NetworkSettings {
+    CURRENT.read().clone()
+}
+
+/// Apply the saved settings (defaults when unset or invalid)
+pub fn load() {
+    if let Some(settings) = storage::load_json::<NetworkSettings>(NETWORK_FILE) {
+        match settings.validate() {
+            Ok(()) => *CURRENT.write() = settings,
+            Err(e) => tracing::warn!("Ignoring saved network settings: {}", e),
+        }
+    }
+}
+
+/// Validate, save and apply new settings
+pub fn save(settings: NetworkSettings) -> AppResult<()> {
+    settings.validate()?;
+    storage::save_json(NETWORK_FILE, &settings).map_err(AppError::Storage)?;
+    *CURRENT.write() = settings;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefer_ipv6_needs_a_routable_address() {
+        let settings = NetworkSettings {
+            ip_family: IpFamily::PreferIpv6,
+            interface: None,
+        };
+        let link_local: IpAddr = "fe80::1".parse().unwrap();
+        let global: IpAddr = "2001:db8::1".parse().unwrap();
+        let v4: IpAddr = "192.168.1.2".parse().unwrap();
+
+        assert!(!settings.prefers_ipv6(&[v4, link_local]));
+        assert!(settings.prefers_ipv6(&[v4, global]));
+        // Preferring only ranks: IPv4 candidates are still gathered
+        assert!(!settings.ipv6_only());
+        let required = NetworkSettings {
+            ip_family: IpFamily::RequireIpv6,
+            interface: None,
+        };
+        assert!(required.ipv6_only());
+        assert!(!NetworkSettings::default().prefers_ipv6(&[global]));
+    }
+
+    #[test]
+    fn ipv4_candidates_rank_below_ipv6_of_the_same_type() {
+        let v4 = "a=candidate:1 1 udp 2130706431 192.168.1.2 5000 typ host";
+        let v6 = "a=candidate:2 1 udp 2130706431 2001:db8::1 5000 typ host";
+        let srflx = "a=candidate:3 1 udp 1694498815 203.0.113.5 6000 typ srflx";
+        let priority = |line: &str| -> u32 { line.split(' ').nth(3).unwrap().parse().unwrap() };
+
+        let lowered = lower_ipv4_candidate(v4).unwrap();
+        assert!(priority(&lowered) < priority(v6));
+        assert_eq!(priority(&lowered) >> 24, 126);
+        assert!(lower_ipv4_candidate(v6).is_none());
+        assert!(lower_ipv4_candidate("a=fingerprint:sha-256 AB").is_none());
+        assert!(priority(&lower_ipv4_candidate(srflx).unwrap()) < priority(srflx));
+    }
+}
@@ -0,0 +1,48 @@
+//! RTP payload pool
+//! Payloads are split off one growing `BytesMut` instead of allocated one by
+//! one. Once the packets written from a region are dropped, `reserve` reclaims
+//! it, so a track keeps reusing the same allocation at any frame rate.
+
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+
+/// Reusable storage for the payloads of one track
+pub struct PayloadPool {
+    buf: Mutex<BytesMut>,
+}
+
+impl PayloadPool {
+    /// Pool starting with room for `capacity` bytes of payloads in flight
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Mutex::new(BytesMut::with_capacity(capacity)),
+        }
+    }
+
+    /// Payload made of `header` followed by `data`
+    pub fn payload(&self, header: &[u8], data: &[u8]) -> Bytes {
+        let mut buf = self.buf.lock();
+        buf.reserve(header.len() + data.len());
+        buf.extend_from_slice(header);
+        buf.extend_from_slice(data);
+        buf.split().freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_the_buffer_once_payloads_are_dropped() {
+        let pool = PayloadPool::with_capacity(16);
+        let first = pool.payload(&[0x10], &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(&first[..], [0x10, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let ptr = first.as_ptr();
+        drop(first);
+
+        // Doesn't fit in what is left after the first payload: reclaimed, not grown
+        let second = pool.payload(&[0x00], &[9; 9]);
+        assert_eq!(second.as_ptr(), ptr);
+    }
+}
@@ -8,13 +8,19 @@ use webrtc::api::media_engine::MediaEngine;
 use webrtc::api::APIBuilder;
 use webrtc::data_channel::data_channel_message::DataChannelMessage;
 use webrtc::data_channel::RTCDataChannel;
-use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
-use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::sdp::sdp_type::RTCSdpType;
 use webrtc::peer_connection::RTCPeerConnection;
 
-use super::signaling::{ConnectionOffer, SignalingMessage};
+use super::callback_queue::{CallbackQueue, EventSink};
+use super::gathering::{self, GatheringSender, GatheringSource};
+use super::ice_servers;
+use super::identity;
+use super::setup::{self, SetupGuard};
+use super::signaling::{self, ConnectionOffer, SignalingMessage};
+use super::timeouts;
+use crate::error::{AppError, AppResult};
 
 pub type MessageSender = mpsc::UnboundedSender<String>;
 
@@ -25,11 +31,43 @@ pub struct ChatMessage {
     pub timestamp: u64,
 }
 
+/// Raised by WebRTC callbacks, applied in order by the manager's event task
+enum ConnectionEvent {
+    /// The joiner received the host's data channel
+    DataChannel(Arc<RTCDataChannel>),
+    Message(String),
+}
+
 pub struct WebRTCManager {
     peer_connection: Arc<RwLock<Option<Arc<RTCPeerConnection>>>>,
     data_channel: Arc<RwLock<Option<Arc<RTCDataChannel>>>>,
     message_tx: Arc<RwLock<Option<MessageSender>>>,
+    gathering_tx: Arc<RwLock<Option<GatheringSender>>>,
     local_username: Arc<RwLock<Option<String>>>,
+    /// Applies the events raised by WebRTC callbacks
+    events: CallbackQueue<ConnectionEvent>,
+}
+
+/// Manager state the event task works on
+struct EventContext {
+    data_channel: Arc<RwLock<Option<Arc<RTCDataChannel>>>>,
+    message_tx: Arc<RwLock<Option<MessageSender>>>,
+}
+
+impl EventContext {
+    fn apply(&self, event: ConnectionEvent) {
+        match event {
+            ConnectionEvent::DataChannel(dc) => {
+                tracing::info!("Data channel '{}' opened", dc.label());
+                *self.data_channel.write() = Some(dc);
+            }
+            ConnectionEvent::Message(text) => {
+                if let Some(sender) = self.message_tx.read().as_ref() {
+                    let _ = sender.send(text);
+                }
+            }
+        }
+    }
 }
 
 impl Default for WebRTCManager {
@@ -40,11 +78,20 @@ impl Default for WebRTCManager {
 
 impl WebRTCManager {
     pub fn new() -> Self {
+        let data_channel = Arc::new(RwLock::new(None));
+        let message_tx = Arc::new(RwLock::new(None));
+        let ctx = EventContext {
+            data_channel: data_channel.clone(),
+            message_tx: message_tx.clone(),
+        };
+
         Self {
             peer_connection: Arc::new(RwLock::new(None)),
-            data_channel: Arc::new(RwLock::new(None)),
-            message_tx: Arc::new(RwLock::new(None)),
+            data_channel,
+            message_tx,
+            gathering_tx: Arc::new(RwLock::new(None)),
             local_username: Arc::new(RwLock::new(None)),
+            events: CallbackQueue::spawn("WebRTC", move |event| ctx.apply(event)),
         }
     }
 
@@ -56,38 +103,32 @@ impl WebRTCManager {
         *self.message_tx.write() = Some(tx);
     }
 
-    async fn create_peer_connection(&self) -> Result<Arc<RTCPeerConnection>, String> {
+    pub fn set_gathering_sender(&self, tx: GatheringSender) {
+        *self.gathering_tx.write() = Some(tx);
+    }
+
+    async fn create_peer_connection(&self) -> AppResult<Arc<RTCPeerConnection>> {
         let mut m = MediaEngine::default();
         m.register_default_codecs()
-            .map_err(|e| format!("Failed to register codecs: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register codecs: {}", e)))?;
 
         let mut registry = Registry::new();
         registry = register_default_interceptors(registry, &mut m)
-            .map_err(|e| format!("Failed to register interceptors: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to register interceptors: {}", e)))?;
 
         let api = APIBuilder::new()
             .with_media_engine(m)
             .with_interceptor_registry(registry)
+            .with_setting_engine(timeouts::current().setting_engine())
             .build();
 
         let config = RTCConfiguration {
-            ice_servers: vec![
-                RTCIceServer {
-                    urls: vec!["stun:stun.l.google.com:19302".to_owned()],
-                    ..Default::default()
-                },
-                RTCIceServer {
-                    urls: vec!["stun:stun.cloudflare.com:3478".to_owned()],
-                    ..Default::default()
-                },
-            ],
-            ..Default::default()
+            certificates: vec![identity::local_certificate()],
+            ..ice_servers::rtc_configuration()
         };
 
-        let peer_connection = api
-            .new_peer_connection(config)
-            .await
-            .map_err(|e| format!("Failed to create peer connection: {}", e))?;
+        let peer_connection =
+            setup::step("create peer connection", api.new_peer_connection(config)).await?;
 
         let pc = Arc::new(peer_connection);
         *self.peer_connection.write() = Some(pc.clone());
@@ -95,44 +136,59 @@ impl WebRTCManager {
         Ok(pc)
     }
 
+    /// Closes the connection being set up unless the setup completes (failure,
+    /// timeout or a cancelled command)
+    fn setup_guard(&self, pc: &Arc<RTCPeerConnection>) -> SetupGuard<impl FnOnce()> {
+        let current = self.peer_connection.clone();
+        let data_channel = self.data_channel.clone();
+        let pc = pc.clone();
+        SetupGuard::new(move || {
+            tracing::warn!("Connection setup did not complete, closing it");
+            {
+                let mut current = current.write();
+                if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &pc)) {
+                    *current = None;
+                    *data_channel.write() = None;
+                }
+            }
+            tokio::spawn(async move {
+                let _ = pc.close().await;
+            });
+        })
+    }
+
     /// Create an offer (for the host)
-    pub async fn create_offer(&self) -> Result<ConnectionOffer, String> {
+    pub async fn create_offer(&self) -> AppResult<ConnectionOffer> {
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(&pc);
 
         // Create data channel
-        let dc = pc
-            .create_data_channel("chat", None)
-            .await
-            .map_err(|e| format!("Failed to create data channel: {}", e))?;
+        let dc = setup::step("create data channel", pc.create_data_channel("chat", None)).await?;
 
-        self.setup_data_channel(dc.clone()).await;
+        watch_data_channel(&self.events.sink(), &dc);
         *self.data_channel.write() = Some(dc);
 
         // Create offer
-        let offer = pc
-            .create_offer(None)
-            .await
-            .map_err(|e| format!("Failed to create offer: {}", e))?;
+        let offer = setup::step("create offer", pc.create_offer(None)).await?;
 
         // Set local description
-        pc.set_local_description(offer.clone())
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(offer.clone())).await?;
 
         // Wait for ICE gathering to complete
-        self.wait_for_ice_gathering().await;
+        self.wait_for_ice_gathering(&pc).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize SDP: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize SDP: {}", e)))?;
 
         use base64::Engine;
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: true,
@@ -140,82 +196,43 @@ impl WebRTCManager {
     }
 
     /// Accept an offer and create an answer (for the joiner)
-    pub async fn accept_offer(&self, offer_base64: &str) -> Result<ConnectionOffer, String> {
+    pub async fn accept_offer(&self, offer_base64: &str) -> AppResult<ConnectionOffer> {
         let pc = self.create_peer_connection().await?;
+        let guard = self.setup_guard(&pc);
 
         // Setup handler for when we receive the data channel
-        let dc_lock = self.data_channel.clone();
-        let message_tx = self.message_tx.read().clone();
-        let username = self.local_username.read().clone();
-
+        let events = self.events.sink();
         pc.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
-            let dc_lock = dc_lock.clone();
-            let message_tx = message_tx.clone();
-            let username = username.clone();
-
-            Box::pin(async move {
-                tracing::info!("Data channel '{}' opened", dc.label());
-                *dc_lock.write() = Some(dc.clone());
-
-                // Setup message handlers
-                let tx = message_tx.clone();
-                dc.on_message(Box::new(move |msg: DataChannelMessage| {
-                    let tx = tx.clone();
-                    Box::pin(async move {
-                        if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                            if let Some(ref sender) = tx {
-                                let _ = sender.send(text);
-                            }
-                        }
-                    })
-                }));
-
-                dc.on_open(Box::new(move || {
-                    tracing::info!("Data channel opened for {}", username.clone().unwrap_or_default());
-                    Box::pin(async {})
-                }));
-            })
+            // Before returning: the channel opens (and can receive) right after
+            watch_data_channel(&events, &dc);
+            events.push(ConnectionEvent::DataChannel(dc));
+            Box::pin(async {})
         }));
 
         // Decode and set remote description (the offer)
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(offer_base64)
-            .map_err(|e| format!("Failed to decode offer: {}", e))?;
+        let offer = signaling::decode_description(offer_base64, RTCSdpType::Offer)?;
 
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in offer: {}", e))?;
-
-        let offer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse offer: {}", e))?;
-
-        pc.set_remote_description(offer)
-            .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+        setup::step("set remote description", pc.set_remote_description(offer)).await?;
 
         // Create answer
-        let answer = pc
-            .create_answer(None)
-            .await
-            .map_err(|e| format!("Failed to create answer: {}", e))?;
+        let answer = setup::step("create answer", pc.create_answer(None)).await?;
 
-        pc.set_local_description(answer)
-            .await
-            .map_err(|e| format!("Failed to set local description: {}", e))?;
+        setup::step("set local description", pc.set_local_description(answer)).await?;
 
         // Wait for ICE gathering
-        self.wait_for_ice_gathering().await;
+        self.wait_for_ice_gathering(&pc).await;
 
         let local_desc = pc
             .local_description()
             .await
-            .ok_or("No local description")?;
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
 
         let sdp_json = serde_json::to_string(&local_desc)
-            .map_err(|e| format!("Failed to serialize answer: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize answer: {}", e)))?;
 
         let encoded = base64::engine::general_purpose::STANDARD.encode(sdp_json.as_bytes());
 
+        guard.complete();
         Ok(ConnectionOffer {
             sdp_base64: encoded,
             is_offer: false,
@@ -223,71 +240,42 @@ impl WebRTCManager {
     }
 
     /// Accept the answer (for the host, after receiving joiner's answer)
-    pub async fn accept_answer(&self, answer_base64: &str) -> Result<(), String> {
+    pub async fn accept_answer(&self, answer_base64: &str) -> AppResult<()> {
         let pc = self
             .peer_connection
             .read()
             .clone()
-            .ok_or("No peer connection")?;
-
-        use base64::Engine;
-        let sdp_json = base64::engine::general_purpose::STANDARD
-            .decode(answer_base64)
-            .map_err(|e| format!("Failed to decode answer: {}", e))?;
+            .ok_or_else(|| AppError::WebRtc("No peer connection".to_string()))?;
 
-        let sdp_str =
-            String::from_utf8(sdp_json).map_err(|e| format!("Invalid UTF-8 in answer: {}", e))?;
+        let answer = signaling::decode_description(answer_base64, RTCSdpType::Answer)?;
 
-        let answer: RTCSessionDescription =
-            serde_json::from_str(&sdp_str).map_err(|e| format!("Failed to parse answer: {}", e))?;
-
-        pc.set_remote_description(answer)
-            .await
-            .map_err(|e| format!("Failed to set remote description: {}", e))?;
+        let guard = self.setup_guard(&pc);
+        setup::step("set remote description", pc.set_remote_description(answer)).await?;
+        guard.complete();
 
         tracing::info!("Answer accepted, connection establishing...");
         Ok(())
     }
 
-    async fn setup_data_channel(&self, dc: Arc<RTCDataChannel>) {
-        let message_tx = self.message_tx.read().clone();
-
-        dc.on_open(Box::new(move || {
-            tracing::info!("Data channel opened!");
-            Box::pin(async {})
-        }));
-
-        let tx = message_tx.clone();
-        dc.on_message(Box::new(move |msg: DataChannelMessage| {
-            let tx = tx.clone();
-            Box::pin(async move {
-                if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                    tracing::info!("Received message: {}", text);
-                    if let Some(ref sender) = tx {
-                        let _ = sender.send(text);
-                    }
-                }
-            })
-        }));
-
-        dc.on_close(Box::new(|| {
-            tracing::info!("Data channel closed");
-            Box::pin(async {})
-        }));
-    }
-
-    async fn wait_for_ice_gathering(&self) {
-        // Simple wait for ICE candidates (in production, you'd want a proper state machine)
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    async fn wait_for_ice_gathering(&self, pc: &Arc<RTCPeerConnection>) {
+        let progress = self.gathering_tx.read().clone();
+        gathering::wait_for_gathering(
+            pc,
+            progress.as_ref(),
+            GatheringSource::Connection,
+            None,
+            timeouts::current().gathering(),
+        )
+        .await;
     }
 
     /// Send a chat message
-    pub async fn send_message(&self, message: &str) -> Result<(), String> {
+    pub async fn send_message(&self, message: &str) -> AppResult<()> {
         let dc = self
             .data_channel
             .read()
             .clone()
-            .ok_or("No data channel available")?;
+            .ok_or_else(|| AppError::WebRtc("No data channel available".to_string()))?;
 
         let username = self
             .local_username
@@ -305,11 +293,11 @@ impl WebRTCManager {
         };
 
         let json = serde_json::to_string(&chat_msg)
-            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to serialize message: {}", e)))?;
 
         dc.send_text(json)
             .await
-            .map_err(|e| format!("Failed to send message: {}", e))?;
+            .map_err(|e| AppError::WebRtc(format!("Failed to send message: {}", e)))?;
 
         Ok(())
     }
@@ -332,3 +320,24 @@ impl WebRTCManager {
         }
     }
 }
+
+/// Queue the messages of the data channel (open and close are only logged)
+fn watch_data_channel(events: &EventSink<ConnectionEvent>, dc: &Arc<RTCDataChannel>) {
+    dc.on_open(Box::new(|| {
+        tracing::info!("Data channel opened!");
+        Box::pin(async {})
+    }));
+
+    let sink = events.clone();
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
+            sink.push(ConnectionEvent::Message(text));
+        }
+        Box::pin(async {})
+    }));
+
+    dc.on_close(Box::new(|| {
+        tracing::info!("Data channel closed");
+        Box::pin(async {})
+    }));
+}
@@ -0,0 +1,212 @@
+//! Link quality
+//! Scores the connection with a peer from its round-trip time, packet loss and
+//! jitter, for the signal bars of the UI. webrtc-rs doesn't report the loss and
+//! jitter of what we receive, so they are measured here on the incoming audio
+//! RTP stream (RFC 3550 interarrival jitter); the loss of what we send comes
+//! from the peer's receiver reports.
+
+use serde::Serialize;
+use std::time::Instant;
+
+/// Good below every one of these
+const GOOD_RTT_MS: f64 = 150.0;
+const GOOD_LOSS_PERCENT: f64 = 2.0;
+const GOOD_JITTER_MS: f64 = 30.0;
+
+/// Poor beyond any one of these
+const POOR_RTT_MS: f64 = 400.0;
+const POOR_LOSS_PERCENT: f64 = 8.0;
+const POOR_JITTER_MS: f64 = 80.0;
+
+/// Scores in a row needed to change level, so one bad sample doesn't flicker the bars
+const CONFIRM_SCORES: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    Poor,
+    Fair,
+    Good,
+}
+
+/// Score a link from what is known of it (None when nothing is)
+pub fn score(
+    rtt_ms: Option<f64>,
+    loss_percent: Option<f64>,
+    jitter_ms: Option<f64>,
+) -> Option<Quality> {
+    let metrics = [
+        (rtt_ms, GOOD_RTT_MS, POOR_RTT_MS),
+        (loss_percent, GOOD_LOSS_PERCENT, POOR_LOSS_PERCENT),
+        (jitter_ms, GOOD_JITTER_MS, POOR_JITTER_MS),
+    ];
+    // The worst metric decides
+    metrics
+        .iter()
+        .filter_map(|&(value, good, poor)| {
+            value.map(|v| {
+                if v > poor {
+                    Quality::Poor
+                } else if v >= good {
+                    Quality::Fair
+                } else {
+                    Quality::Good
+                }
+            })
+        })
+        .min()
+}
+
+/// Quality level of a peer, changed only once confirmed
+#[derive(Debug, Default)]
+pub struct QualityTracker {
+    current: Option<Quality>,
+    /// Differing level seen in the last scores, and how many times in a row
+    candidate: Option<(Quality, u8)>,
+}
+
+impl QualityTracker {
+    pub fn current(&self) -> Option<Quality> {
+        self.current
+    }
+
+    /// Feed a score, returning the new level when it changes (the first one included)
+    pub fn update(&mut self, quality: Quality) -> Option<Quality> {
+        if self.current.is_none() || self.current == Some(quality) {
+            self.candidate = None;
+            let changed = self.current.is_none();
+            self.current = Some(quality);
+            return changed.then_some(quality);
+        }
+        let seen = match self.candidate {
+            Some((candidate, seen)) if candidate == quality => seen + 1,
+            _ => 1,
+        };
+        if seen >= CONFIRM_SCORES {
+            self.candidate = None;
+            self.current = Some(quality);
+            Some(quality)
+        } else {
+            self.candidate = Some((quality, seen));
+            None
+        }
+    }
+}
+
+/// Counters of an incoming RTP stream
+#[derive(Debug, Default)]
+pub struct ReceiveStats {
+    /// Highest sequence number seen, extended past the 16-bit wrap
+    highest: Option<i64>,
+    first: i64,
+    received: u64,
+    /// Arrival times are measured from here
+    reference: Option<Instant>,
+    last_transit: Option<i64>,
+    /// Interarrival jitter in RTP timestamp units
+    jitter: f64,
+    clock_rate: u32,
+}
+
+impl ReceiveStats {
+    pub fn on_packet(&mut self, seq: u16, rtp_timestamp: u32, arrival: Instant, clock_rate: u32) {
+        self.received += 1;
+        match self.highest {
+            None => {
+                self.highest = Some(seq as i64);
+                self.first = seq as i64;
+            }
+            Some(highest) => {
+                let ext = highest + seq.wrapping_sub(highest as u16) as i16 as i64;
+                self.highest = Some(highest.max(ext));
+            }
+        }
+
+        self.clock_rate = clock_rate;
+        let reference = *self.reference.get_or_insert(arrival);
+        let arrival_units =
+            (arrival.duration_since(reference).as_secs_f64() * clock_rate as f64) as i64;
+        let transit = arrival_units - rtp_timestamp as i64;
+        if let Some(last) = self.last_transit {
+            // The timestamp wraps too: a huge step is a wrap, not jitter
+            let d = ((transit - last) as i32).unsigned_abs() as f64;
+            self.jitter += (d - self.jitter) / 16.0;
+        }
+        self.last_transit = Some(transit);
+    }
+
+    /// Packets the sequence numbers say were sent so far
+    pub fn expected(&self) -> u64 {
+        self.highest.map_or(0, |h| (h - self.first + 1) as u64)
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Current jitter, None before two packets
+    pub fn jitter_ms(&self) -> Option<f64> {
+        self.last_transit
+            .filter(|_| self.received > 1 && self.clock_rate > 0)
+            .map(|_| self.jitter * 1000.0 / self.clock_rate as f64)
+    }
+}
+
+/// Loss between two samples of (expected, received) counters, None without packets
+pub fn loss_percent(previous: (u64, u64), current: (u64, u64)) -> Option<f64> {
+    let expected = current.0.saturating_sub(previous.0);
+    let received = current.1.saturating_sub(previous.1);
+    (expected > 0).then(|| expected.saturating_sub(received) as f64 * 100.0 / expected as f64)
+}
+
+/// Quality of the link with a peer, as reported to the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityReport {
+    pub peer_id: String,
+    pub quality: Quality,
+    pub rtt_ms: Option<f64>,
+    /// Worst of the incoming and outgoing loss
+    pub loss_percent: Option<f64>,
+    pub jitter_ms: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn scores_and_confirms_levels() {
+        assert_eq!(score(None, None, None), None);
+        assert_eq!(score(Some(40.0), Some(0.0), Some(5.0)), Some(Quality::Good));
+        assert_eq!(score(Some(40.0), Some(3.0), None), Some(Quality::Fair));
+        assert_eq!(
+            score(Some(500.0), Some(0.0), Some(5.0)),
+            Some(Quality::Poor)
+        );
+
+        let mut tracker = QualityTracker::default();
+        assert_eq!(tracker.update(Quality::Good), Some(Quality::Good));
+        assert_eq!(tracker.update(Quality::Poor), None);
+        assert_eq!(tracker.update(Quality::Good), None);
+        assert_eq!(tracker.update(Quality::Fair), None);
+        assert_eq!(tracker.update(Quality::Fair), Some(Quality::Fair));
+
+        // 20ms Opus frames across the sequence wrap, one lost, arriving on time
+        let start = Instant::now();
+        let mut stats = ReceiveStats::default();
+        for (i, seq) in (65530u16..=65535).chain(0..5).enumerate() {
+            if seq == 2 {
+                continue;
+            }
+            let arrival = start + Duration::from_millis(20 * i as u64);
+            stats.on_packet(seq, 960 * i as u32, arrival, 48_000);
+        }
+        assert_eq!(stats.expected(), 11);
+        assert_eq!(
+            loss_percent((0, 0), (stats.expected(), stats.received())),
+            Some(100.0 / 11.0)
+        );
+        assert!(stats.jitter_ms().unwrap() < 1.0);
+    }
+}
@@ -0,0 +1,102 @@
+//! RTP reordering
+//! Packets can overtake each other on the network, and Opus decodes each frame
+//! against the previous one: fed in arrival order, a swap is heard as a glitch.
+//! Packets are put back in sequence order before decoding. In-order packets go
+//! through right away; a gap holds the following packets until the missing one
+//! shows up or the window is full, then it is given up as lost.
+
+use std::collections::VecDeque;
+
+/// Packets held while waiting for a missing one (60ms of 20ms Opus frames)
+pub const REORDER_WINDOW: usize = 3;
+
+/// A jump this far ahead is a restarted stream rather than reordering
+const MAX_SEQUENCE_JUMP: u16 = 1000;
+
+/// Releases packets in RTP sequence order, wrapping at 2^16
+pub struct ReorderBuffer<T> {
+    window: usize,
+    /// Sequence number of `pending[0]`
+    next: Option<u16>,
+    pending: VecDeque<Option<T>>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            next: None,
+            pending: VecDeque::with_capacity(window + 1),
+        }
+    }
+
+    /// Add packet `seq`, calling `release` with every packet now in order
+    /// Late packets (already given up on) and duplicates are dropped.
+    pub fn push(&mut self, seq: u16, packet: T, mut release: impl FnMut(T)) {
+        let next = *self.next.get_or_insert(seq);
+        let distance = seq.wrapping_sub(next);
+        if distance >= MAX_SEQUENCE_JUMP {
+            // Behind `next` (wrapped) is late; far ahead is a new stream
+            if distance <= u16::MAX - MAX_SEQUENCE_JUMP {
+                self.flush(&mut release);
+                self.next = Some(seq.wrapping_add(1));
+                release(packet);
+            }
+            return;
+        }
+
+        let index = distance as usize;
+        if self.pending.len() <= index {
+            self.pending.resize_with(index + 1, || None);
+        }
+        if self.pending[index].is_none() {
+            self.pending[index] = Some(packet);
+        }
+
+        // Release what is in order, giving up on a missing packet once the
+        // window is full
+        loop {
+            match self.pending.front() {
+                Some(Some(_)) => {
+                    if let Some(packet) = self.advance() {
+                        release(packet);
+                    }
+                }
+                Some(None) if self.pending.len() > self.window => {
+                    self.advance();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Release everything held, skipping the gaps
+    pub fn flush(&mut self, mut release: impl FnMut(T)) {
+        while !self.pending.is_empty() {
+            if let Some(packet) = self.advance() {
+                release(packet);
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Option<T> {
+        self.next = self.next.map(|n| n.wrapping_add(1));
+        self.pending.pop_front().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_order_and_skips_lost_packets() {
+        let mut buffer = ReorderBuffer::new(3);
+        let mut out = Vec::new();
+        // 65534 and 65535 swapped across the wrap, 2 lost, 1 duplicated
+        for seq in [65533u16, 65535, 65534, 0, 1, 1, 3, 4, 5, 2] {
+            buffer.push(seq, seq, |p| out.push(p));
+        }
+        assert_eq!(out, [65533, 65534, 65535, 0, 1, 3, 4, 5]);
+    }
+}
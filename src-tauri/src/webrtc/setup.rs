@@ -0,0 +1,82 @@
+//! Peer setup deadlines
+//! Every await of an offer/answer exchange is bounded, so a step that never
+//! completes (a stuck remote description, a data channel that never opens)
+//! fails the command with `AppError::Timeout` instead of hanging it. A
+//! `SetupGuard` closes the half-built peer when the setup fails, times out or
+//! is cancelled.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+
+/// Longest a single setup step may take
+pub const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest a data channel may take to open once the descriptions are exchanged
+/// (default of the user's connection timeout, see `timeouts`)
+pub const DATA_CHANNEL_OPEN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Run the setup step `what` ("create offer", ...), bounded by `STEP_TIMEOUT`
+pub async fn step<T, E: Display>(
+    what: &str,
+    future: impl Future<Output = Result<T, E>>,
+) -> AppResult<T> {
+    match tokio::time::timeout(STEP_TIMEOUT, future).await {
+        Ok(result) => result.map_err(|e| AppError::WebRtc(format!("Failed to {}: {}", what, e))),
+        Err(_) => Err(AppError::Timeout(format!(
+            "{} did not complete within {:?}",
+            what, STEP_TIMEOUT
+        ))),
+    }
+}
+
+/// Runs its cleanup when dropped, unless the setup completed
+pub struct SetupGuard<F: FnOnce()> {
+    cleanup: Option<F>,
+}
+
+impl<F: FnOnce()> SetupGuard<F> {
+    pub fn new(cleanup: F) -> Self {
+        Self {
+            cleanup: Some(cleanup),
+        }
+    }
+
+    /// The peer is fully set up: keep it
+    pub fn complete(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl<F: FnOnce()> Drop for SetupGuard<F> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn failed_step_runs_the_cleanup() {
+        let cleaned = AtomicBool::new(false);
+        let guard = SetupGuard::new(|| cleaned.store(true, Ordering::SeqCst));
+        let result: AppResult<()> = step("create offer", async { Err("no codecs") }).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "WebRTC error: Failed to create offer: no codecs"
+        );
+        drop(guard);
+        assert!(cleaned.load(Ordering::SeqCst));
+
+        let kept = AtomicBool::new(false);
+        SetupGuard::new(|| kept.store(true, Ordering::SeqCst)).complete();
+        assert!(!kept.load(Ordering::SeqCst));
+    }
+}
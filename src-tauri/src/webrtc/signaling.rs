@@ -1,6 +1,15 @@
 #![allow(dead_code)]
 
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use webrtc::peer_connection::sdp::sdp_type::RTCSdpType;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+
+use super::network;
+use crate::audio::AudioPolicy;
+use crate::chat_commands::SystemMessage;
+use crate::server::{Peer, RosterSnapshot};
 
 /// Represents a connection offer or answer encoded in base64
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +18,83 @@ pub struct ConnectionOffer {
     pub is_offer: bool,
 }
 
+/// Why a pasted connection code was rejected
+/// Each case maps to its own error code, so the UI can tell the user what to do
+/// (copy the whole code, paste the other one, update the app...).
+#[derive(Error, Debug)]
+pub enum SdpError {
+    #[error("Empty connection code")]
+    Empty,
+    #[error("Connection code is not valid base64: {0}")]
+    NotBase64(String),
+    #[error("Connection code is truncated")]
+    Truncated,
+    #[error("Connection code is malformed: {0}")]
+    Malformed(String),
+    #[error("Expected an {expected} but got an {found}")]
+    WrongType { expected: String, found: String },
+    #[error("Incompatible connection code: {0}")]
+    Incompatible(String),
+}
+
+/// Decode a pasted or relayed `sdp_base64`, checking it is an `expected` description
+/// Whitespace is ignored: codes copied from chat or mail often get wrapped.
+pub fn decode_description(
+    encoded: &str,
+    expected: RTCSdpType,
+) -> Result<RTCSessionDescription, SdpError> {
+    let compact: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.is_empty() {
+        return Err(SdpError::Empty);
+    }
+
+    let json = base64::engine::general_purpose::STANDARD
+        .decode(&compact)
+        .map_err(|e| match e {
+            base64::DecodeError::InvalidLength(_) | base64::DecodeError::InvalidPadding => {
+                SdpError::Truncated
+            }
+            other => SdpError::NotBase64(other.to_string()),
+        })?;
+    let json = String::from_utf8(json).map_err(|_| SdpError::Malformed("not text".to_string()))?;
+
+    let description: RTCSessionDescription =
+        serde_json::from_str(&json).map_err(|e| match e.classify() {
+            serde_json::error::Category::Eof => SdpError::Truncated,
+            _ => SdpError::Malformed(e.to_string()),
+        })?;
+
+    if description.sdp_type != expected {
+        return Err(SdpError::WrongType {
+            expected: expected.to_string(),
+            found: description.sdp_type.to_string(),
+        });
+    }
+
+    // SDP version 0 is the only one defined; WebRTC peers always send DTLS and ICE credentials
+    if !description.sdp.starts_with("v=0") {
+        return Err(SdpError::Incompatible(
+            "unsupported SDP version".to_string(),
+        ));
+    }
+    for (attribute, what) in [
+        ("a=fingerprint:", "DTLS fingerprint"),
+        ("a=ice-ufrag:", "ICE credentials"),
+    ] {
+        if !description.sdp.contains(attribute) {
+            return Err(SdpError::Incompatible(format!("no {}", what)));
+        }
+    }
+
+    // Every remote description goes through here: rank its candidates by the
+    // preferred IP family
+    let mut description = description;
+    if let Some(sdp) = network::current().prioritize_remote(&description.sdp) {
+        description.sdp = sdp;
+    }
+    Ok(description)
+}
+
 /// Messages sent over the data channel
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -61,6 +147,62 @@ pub enum SignalingMessage {
         peer_id: String,
         peer_username: String,
     },
+
+    /// Audio policy of the room, advertised by the host
+    #[serde(rename = "room_policy")]
+    RoomPolicy { policy: AudioPolicy },
+
+    /// Download cap of the sender in kbps (None = no cap): peers keep what
+    /// they send under it
+    #[serde(rename = "bandwidth_limit")]
+    BandwidthLimit { download_kbps: Option<u32> },
+
+    /// The sender moved to another voice channel
+    #[serde(rename = "channel_move")]
+    ChannelMove { channel: String },
+
+    /// The sender went away (idle) or came back
+    #[serde(rename = "presence")]
+    Presence { away: bool },
+
+    /// Ask the host for a full roster snapshot
+    #[serde(rename = "roster_request")]
+    RosterRequest,
+
+    /// Full roster, sent by the host; `recipient_id` is the id the host lists
+    /// the recipient under
+    #[serde(rename = "roster_snapshot")]
+    RosterSnapshot {
+        snapshot: RosterSnapshot,
+        recipient_id: String,
+    },
+
+    /// A peer joined the room (incremental roster update from the host)
+    #[serde(rename = "roster_joined")]
+    RosterJoined { version: u64, peer: Peer },
+
+    /// A peer left the room (incremental roster update from the host)
+    #[serde(rename = "roster_left")]
+    RosterLeft { version: u64, peer_id: String },
+
+    /// New offer on an established connection (a media track was lost)
+    #[serde(rename = "renegotiate_offer")]
+    RenegotiateOffer { sdp_base64: String },
+
+    /// Answer to a renegotiation offer
+    #[serde(rename = "renegotiate_answer")]
+    RenegotiateAnswer { sdp_base64: String },
+
+    /// Outcome of a chat command (`/mute`, `/kick`, `/topic`, `/me`)
+    #[serde(rename = "system_message")]
+    SystemMessage { message: SystemMessage },
+
+    /// Message of a data-channel plugin, handed to whoever claimed `namespace`
+    #[serde(rename = "plugin")]
+    Plugin {
+        namespace: String,
+        payload: serde_json::Value,
+    },
 }
 
 impl SignalingMessage {
@@ -82,4 +224,65 @@ impl SignalingMessage {
     pub fn user_left(username: String) -> Self {
         Self::UserLeft { username }
     }
+
+    /// Control messages are handled by the backend rather than shown as chat
+    pub fn is_control(&self) -> bool {
+        matches!(
+            self,
+            Self::RoomPolicy { .. }
+                | Self::BandwidthLimit { .. }
+                | Self::ChannelMove { .. }
+                | Self::Presence { .. }
+                | Self::RosterRequest
+                | Self::RosterSnapshot { .. }
+                | Self::RosterJoined { .. }
+                | Self::RosterLeft { .. }
+                | Self::RenegotiateOffer { .. }
+                | Self::RenegotiateAnswer { .. }
+                | Self::SystemMessage { .. }
+                | Self::Plugin { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_why_a_code_is_rejected() {
+        let encode = |json: &str| base64::engine::general_purpose::STANDARD.encode(json);
+        let answer = encode(
+            r#"{"type":"answer","sdp":"v=0\r\na=fingerprint:sha-256 AA\r\na=ice-ufrag:x\r\n"}"#,
+        );
+
+        assert!(decode_description(&answer, RTCSdpType::Answer).is_ok());
+        // Wrapped by a mail client
+        let wrapped = format!("{}\n  {}", &answer[..20], &answer[20..]);
+        assert!(decode_description(&wrapped, RTCSdpType::Answer).is_ok());
+
+        assert!(matches!(
+            decode_description(" ", RTCSdpType::Offer),
+            Err(SdpError::Empty)
+        ));
+        assert!(matches!(
+            decode_description(&answer, RTCSdpType::Offer),
+            Err(SdpError::WrongType { .. })
+        ));
+        assert!(matches!(
+            decode_description(&answer[..answer.len() - 4], RTCSdpType::Answer),
+            Err(SdpError::Truncated)
+        ));
+        assert!(matches!(
+            decode_description("not*base64", RTCSdpType::Offer),
+            Err(SdpError::NotBase64(_))
+        ));
+        assert!(matches!(
+            decode_description(
+                &encode(r#"{"type":"offer","sdp":"v=0\r\n"}"#),
+                RTCSdpType::Offer
+            ),
+            Err(SdpError::Incompatible(_))
+        ));
+    }
 }
@@ -0,0 +1,131 @@
+//! Connection timeouts
+//! How patient peer setup is: a slow STUN server or a congested link needs more
+//! time than a LAN. The values are user settings, read by every manager when it
+//! creates a peer connection (changes apply to the next connection).
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use webrtc::api::setting_engine::SettingEngine;
+
+use super::gathering::DEFAULT_GATHERING_TIMEOUT;
+use super::ice_port;
+use super::network;
+use super::setup::DATA_CHANNEL_OPEN_TIMEOUT;
+use crate::error::{AppError, AppResult};
+use crate::storage;
+
+const TIMEOUTS_FILE: &str = "connection_timeouts.json";
+
+/// webrtc-rs default interval between ICE keepalives
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A pair missing keepalives this long is reported disconnected (webrtc-rs default)
+const MIN_DISCONNECTED_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An unreachable pair fails after this long at the least (webrtc-rs default)
+const MIN_FAILED_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Timeouts of peer setup and ICE, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionTimeouts {
+    /// Longest wait for ICE candidates before sending an offer or answer
+    pub gathering_ms: u64,
+    /// Longest a new peer may take to open its data channel; a connected peer
+    /// is also given up after being unreachable this long (25s at the least)
+    pub connection_ms: u64,
+    /// Interval of ICE keepalives on a connected pair
+    pub keepalive_ms: u64,
+}
+
+impl ConnectionTimeouts {
+    pub const DEFAULT: Self = Self {
+        gathering_ms: DEFAULT_GATHERING_TIMEOUT.as_millis() as u64,
+        connection_ms: DATA_CHANNEL_OPEN_TIMEOUT.as_millis() as u64,
+        keepalive_ms: DEFAULT_KEEPALIVE_INTERVAL.as_millis() as u64,
+    };
+
+    pub fn gathering(&self) -> Duration {
+        Duration::from_millis(self.gathering_ms)
+    }
+
+    pub fn connection(&self) -> Duration {
+        Duration::from_millis(self.connection_ms)
+    }
+
+    pub fn keepalive(&self) -> Duration {
+        Duration::from_millis(self.keepalive_ms)
+    }
+
+    /// Reject values that would break connections rather than slow them down
+    pub fn validate(&self) -> AppResult<()> {
+        let check = |name: &str, value: u64, min: u64, max: u64| {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(AppError::InvalidArgument(format!(
+                    "{} must be between {} and {} ms",
+                    name, min, max
+                )))
+            }
+        };
+        check("gathering_ms", self.gathering_ms, 500, 30_000)?;
+        check("connection_ms", self.connection_ms, 5_000, 120_000)?;
+        check("keepalive_ms", self.keepalive_ms, 500, 30_000)?;
+        if self.keepalive_ms * 2 > self.connection_ms {
+            return Err(AppError::InvalidArgument(
+                "keepalive_ms must be at most half of connection_ms".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// ICE settings of a new peer connection
+    pub fn setting_engine(&self) -> SettingEngine {
+        let mut engine = SettingEngine::default();
+        // Disconnected after missing two keepalives, failed once unreachable
+        // for the connection timeout (never sooner than webrtc-rs would)
+        engine.set_ice_timeouts(
+            Some((self.keepalive() * 2).max(MIN_DISCONNECTED_TIMEOUT)),
+            Some(self.connection().max(MIN_FAILED_TIMEOUT)),
+            Some(self.keepalive()),
+        );
+        // IP family and interface chosen by the user
+        network::current().apply(&mut engine);
+        // One forwarded port for every connection while hosting
+        ice_port::apply(&mut engine);
+        engine
+    }
+}
+
+impl Default for ConnectionTimeouts {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static CURRENT: RwLock<ConnectionTimeouts> = RwLock::new(ConnectionTimeouts::DEFAULT);
+
+/// Timeouts for connections created from now on
+pub fn current() -> ConnectionTimeouts {
+    *CURRENT.read()
+}
+
+/// Apply the saved timeouts (defaults when unset or invalid)
+pub fn load() {
+    if let Some(timeouts) = storage::load_json::<ConnectionTimeouts>(TIMEOUTS_FILE) {
+        match timeouts.validate() {
+            Ok(()) => *CURRENT.write() = timeouts,
+            Err(e) => tracing::warn!("Ignoring saved connection timeouts: {}", e),
+        }
+    }
+}
+
+/// Validate, save and apply new timeouts
+pub fn save(timeouts: ConnectionTimeouts) -> AppResult<()> {
+    timeouts.validate()?;
+    storage::save_json(TIMEOUTS_FILE, &timeouts).map_err(AppError::Storage)?;
+    *CURRENT.write() = timeouts;
+    Ok(())
+}
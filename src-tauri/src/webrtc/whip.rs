@@ -0,0 +1,585 @@
+//! WHIP egress
+//! Republishes our voice and screen share to a WHIP endpoint (RFC 9725:
+//! LiveKit, Janus, Cloudflare Stream...) so a call can be broadcast to an
+//! audience far larger than the mesh: a single offer is POSTed to the
+//! endpoint and the SFU fans the stream out. Voice is the whole call, what
+//! we hear with our own mic on top, encoded to Opus on a thread of its own
+//! (read from a call tap, like the recording). Screen frames are encoded to H.264 on a
+//! dedicated thread (the JPEG frames peers get are not a WebRTC codec); while
+//! it is busy new frames are dropped, and keyframes are sent when the server
+//! asks for one. Stopping deletes the session resource on the server.
+
+use bytes::Bytes;
+use image::{imageops, ImageBuffer, Rgba};
+use openh264::encoder::Encoder;
+use openh264::formats::{RgbaSliceU8, YUVBuffer};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self as std_mpsc, Receiver, SyncSender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::{MediaEngine, MIME_TYPE_H264, MIME_TYPE_OPUS};
+use webrtc::api::APIBuilder;
+use webrtc::interceptor::registry::Registry;
+use webrtc::media::Sample;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::rtp_transceiver::rtp_sender::RTCRtpSender;
+use webrtc::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
+use webrtc::rtp_transceiver::RTCRtpTransceiverInit;
+use webrtc::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use webrtc::track::track_local::TrackLocal;
+
+use super::gathering::{self, GatheringSender, GatheringSource};
+use super::ice_servers;
+use super::setup;
+use super::timeouts;
+use crate::audio::{CallTap, OpusEncoder, FRAME_DURATION_MS, SAMPLES_PER_FRAME};
+use crate::error::{AppError, AppResult};
+use crate::video::VideoFrame;
+
+/// Largest picture sent, bigger screens are downscaled
+const MAX_WIDTH: u32 = 1920;
+const MAX_HEIGHT: u32 = 1080;
+
+/// Opus packets waiting for the writer (~200ms)
+const AUDIO_QUEUE_PACKETS: usize = 10;
+
+/// Encoded frames waiting for the writer
+const VIDEO_QUEUE_FRAMES: usize = 4;
+
+/// Stream id of our tracks in the SDP
+const STREAM_ID: &str = "hydrowland";
+
+/// Baseline profile, what every WHIP server and browser decodes
+const H264_FMTP: &str = "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f";
+
+/// Endpoint and what to publish
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhipConfig {
+    /// WHIP endpoint URL given by the server
+    pub endpoint: String,
+    /// Bearer token, when the endpoint requires one
+    pub token: Option<String>,
+    pub audio: bool,
+    pub video: bool,
+}
+
+impl Default for WhipConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            token: None,
+            audio: true,
+            video: true,
+        }
+    }
+}
+
+impl WhipConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        let url = reqwest::Url::parse(&self.endpoint)
+            .map_err(|e| AppError::InvalidArgument(format!("Invalid WHIP endpoint: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::InvalidArgument(
+                "WHIP endpoint must be an http(s) URL".to_string(),
+            ));
+        }
+        if !self.audio && !self.video {
+            return Err(AppError::InvalidArgument(
+                "Nothing to publish: enable audio or video".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WhipStatus {
+    pub active: bool,
+    pub endpoint: Option<String>,
+    /// Peer connection state with the server ("connected", "failed", ...)
+    pub connection_state: Option<String>,
+    pub audio_packets: u64,
+    pub video_frames: u64,
+    /// Screen frames dropped because the H.264 encoder was still busy
+    pub video_frames_dropped: u64,
+    /// Keyframes requested by the server (new viewers, losses)
+    pub keyframe_requests: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    audio_packets: AtomicU64,
+    video_frames: AtomicU64,
+    video_frames_dropped: AtomicU64,
+    keyframe_requests: AtomicU64,
+}
+
+/// Published session, its tasks stop once it is dropped and the connection closed
+struct Session {
+    endpoint: String,
+    token: Option<String>,
+    /// Session resource on the server (`Location` of the answer)
+    resource: Option<reqwest::Url>,
+    pc: Arc<RTCPeerConnection>,
+    /// Call tap read by the audio thread, which exits once this is set
+    audio_stop: Option<(Arc<CallTap>, Arc<AtomicBool>)>,
+    /// Raw frames for the encoder thread, which exits once this is dropped
+    video_tx: Option<SyncSender<VideoFrame>>,
+}
+
+/// WHIP publisher, shared between the commands and the screen stream
+#[derive(Clone, Default)]
+pub struct WhipPublisher {
+    session: Arc<Mutex<Option<Session>>>,
+    counters: Arc<Counters>,
+    /// Progress of the ICE gathering of the offer
+    gathering_tx: Arc<RwLock<Option<GatheringSender>>>,
+}
+
+impl WhipPublisher {
+    pub fn set_gathering_sender(&self, tx: GatheringSender) {
+        *self.gathering_tx.write() = Some(tx);
+    }
+
+    /// Publish to `config.endpoint`, replacing the running session if any
+    /// The call audio is read from `tap`.
+    pub async fn start(&self, config: WhipConfig, tap: Arc<CallTap>) -> AppResult<WhipStatus> {
+        config.validate()?;
+        self.stop().await;
+        for counter in [
+            &self.counters.audio_packets,
+            &self.counters.video_frames,
+            &self.counters.video_frames_dropped,
+            &self.counters.keyframe_requests,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+
+        let pc = create_peer_connection().await?;
+        let session = match self.negotiate(&pc, &config, tap).await {
+            Ok(session) => session,
+            Err(e) => {
+                let _ = pc.close().await;
+                return Err(e);
+            }
+        };
+
+        tracing::info!("Publishing to WHIP endpoint {}", config.endpoint);
+        *self.session.lock() = Some(session);
+        Ok(self.status())
+    }
+
+    async fn negotiate(
+        &self,
+        pc: &Arc<RTCPeerConnection>,
+        config: &WhipConfig,
+        tap: Arc<CallTap>,
+    ) -> AppResult<Session> {
+        let audio_stop = if config.audio {
+            let track = Arc::new(TrackLocalStaticSample::new(
+                RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_OPUS.to_owned(),
+                    clock_rate: 48000,
+                    channels: 2,
+                    ..Default::default()
+                },
+                "audio".to_owned(),
+                STREAM_ID.to_owned(),
+            ));
+            let sender = add_send_track(pc, track.clone()).await?;
+            tokio::spawn(read_rtcp(sender, None));
+
+            let (tx, rx) = mpsc::channel(AUDIO_QUEUE_PACKETS);
+            tokio::spawn(write_audio(track, rx));
+
+            let stop = Arc::new(AtomicBool::new(false));
+            let mut encoder = OpusEncoder::new()?;
+            let (thread_tap, thread_stop) = (tap.clone(), stop.clone());
+            let counters = self.counters.clone();
+            tap.set_enabled(true);
+            let spawned = std::thread::Builder::new()
+                .name("whip-audio".to_string())
+                .spawn(move || {
+                    encode_audio(&thread_tap, &mut encoder, tx, &thread_stop, &counters)
+                });
+            if let Err(e) = spawned {
+                tap.set_enabled(false);
+                return Err(AppError::Audio(format!(
+                    "Failed to spawn WHIP audio encoder: {}",
+                    e
+                )));
+            }
+            Some((tap, stop))
+        } else {
+            None
+        };
+
+        let video_tx = if config.video {
+            let track = Arc::new(TrackLocalStaticSample::new(
+                RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_H264.to_owned(),
+                    clock_rate: 90000,
+                    sdp_fmtp_line: H264_FMTP.to_owned(),
+                    ..Default::default()
+                },
+                "screen".to_owned(),
+                STREAM_ID.to_owned(),
+            ));
+            let sender = add_send_track(pc, track.clone()).await?;
+            let keyframe = Arc::new(AtomicBool::new(false));
+            tokio::spawn(read_rtcp(
+                sender,
+                Some((keyframe.clone(), self.counters.clone())),
+            ));
+
+            let (encoded_tx, encoded_rx) = mpsc::channel(VIDEO_QUEUE_FRAMES);
+            tokio::spawn(write_video(track, encoded_rx));
+
+            let (tx, rx) = std_mpsc::sync_channel(1);
+            let counters = self.counters.clone();
+            std::thread::Builder::new()
+                .name("whip-video".to_string())
+                .spawn(move || encode_video(rx, encoded_tx, &keyframe, &counters))
+                .map_err(|e| AppError::Video(format!("Failed to spawn WHIP encoder: {}", e)))?;
+            Some(tx)
+        } else {
+            None
+        };
+
+        let offer = setup::step("create offer", pc.create_offer(None)).await?;
+        setup::step("set local description", pc.set_local_description(offer)).await?;
+        // WHIP has no trickle by default: the offer carries every candidate
+        let progress = self.gathering_tx.read().clone();
+        gathering::wait_for_gathering(
+            pc,
+            progress.as_ref(),
+            GatheringSource::Whip,
+            None,
+            timeouts::current().gathering(),
+        )
+        .await;
+        let offer = pc
+            .local_description()
+            .await
+            .ok_or_else(|| AppError::WebRtc("No local description".to_string()))?;
+
+        let (answer, resource) = post_offer(config, offer.sdp).await?;
+        let answer = RTCSessionDescription::answer(answer)
+            .map_err(|e| AppError::WebRtc(format!("Invalid WHIP answer: {}", e)))?;
+        setup::step("set remote description", pc.set_remote_description(answer)).await?;
+
+        Ok(Session {
+            endpoint: config.endpoint.clone(),
+            token: config.token.clone(),
+            resource,
+            pc: pc.clone(),
+            audio_stop,
+            video_tx,
+        })
+    }
+
+    /// Stop publishing and delete the session on the server (best effort)
+    pub async fn stop(&self) {
+        let Some(session) = self.session.lock().take() else {
+            return;
+        };
+        tracing::info!("Stopped publishing to {}", session.endpoint);
+        if let Some((tap, stop)) = &session.audio_stop {
+            stop.store(true, Ordering::SeqCst);
+            tap.set_enabled(false);
+        }
+        let _ = session.pc.close().await;
+        if let Some(resource) = &session.resource {
+            let mut request = reqwest::Client::new().delete(resource.clone());
+            if let Some(token) = &session.token {
+                request = request.bearer_auth(token);
+            }
+            if let Err(e) = setup::step("delete the WHIP session", request.send()).await {
+                tracing::debug!("{}", e);
+            }
+        }
+    }
+
+    /// Queue a raw frame of our screen stream
+    pub fn push_video(&self, frame: &VideoFrame) {
+        let session = self.session.lock();
+        let Some(tx) = session.as_ref().and_then(|s| s.video_tx.as_ref()) else {
+            return;
+        };
+        if tx.try_send(frame.clone()).is_err() {
+            self.counters
+                .video_frames_dropped
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn status(&self) -> WhipStatus {
+        let session = self.session.lock();
+        WhipStatus {
+            active: session.is_some(),
+            endpoint: session.as_ref().map(|s| s.endpoint.clone()),
+            connection_state: session
+                .as_ref()
+                .map(|s| s.pc.connection_state().to_string()),
+            audio_packets: self.counters.audio_packets.load(Ordering::Relaxed),
+            video_frames: self.counters.video_frames.load(Ordering::Relaxed),
+            video_frames_dropped: self.counters.video_frames_dropped.load(Ordering::Relaxed),
+            keyframe_requests: self.counters.keyframe_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+async fn create_peer_connection() -> AppResult<Arc<RTCPeerConnection>> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()
+        .map_err(|e| AppError::WebRtc(format!("Failed to register codecs: {}", e)))?;
+
+    let mut registry = Registry::new();
+    registry = register_default_interceptors(registry, &mut m)
+        .map_err(|e| AppError::WebRtc(format!("Failed to register interceptors: {}", e)))?;
+
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .with_setting_engine(timeouts::current().setting_engine())
+        .build();
+
+    let config = ice_servers::rtc_configuration();
+    let pc = setup::step("create peer connection", api.new_peer_connection(config)).await?;
+    Ok(Arc::new(pc))
+}
+
+/// Add `track` on a send-only transceiver
+async fn add_send_track(
+    pc: &RTCPeerConnection,
+    track: Arc<TrackLocalStaticSample>,
+) -> AppResult<Arc<RTCRtpSender>> {
+    let kind = track.kind();
+    let transceiver = setup::step(
+        &format!("add {} track", kind),
+        pc.add_transceiver_from_track(
+            track,
+            Some(RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings: vec![],
+            }),
+        ),
+    )
+    .await?;
+    Ok(transceiver.sender().await)
+}
+
+/// POST the offer, returning the answer and the session resource
+async fn post_offer(
+    config: &WhipConfig,
+    offer: String,
+) -> AppResult<(String, Option<reqwest::Url>)> {
+    let mut request = reqwest::Client::new()
+        .post(&config.endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+        .body(offer);
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    let response = setup::step("reach the WHIP endpoint", request.send()).await?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(AppError::PermissionDenied(format!(
+            "WHIP endpoint refused the token ({})",
+            status
+        )));
+    }
+    if !status.is_success() {
+        return Err(AppError::WebRtc(format!(
+            "WHIP endpoint answered {}",
+            status
+        )));
+    }
+
+    // Usually relative to the endpoint
+    let resource = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| response.url().join(location).ok());
+    let answer = setup::step("read the WHIP answer", response.text()).await?;
+    Ok((answer, resource))
+}
+
+/// Drain RTCP so the interceptors keep working, flagging keyframe requests
+async fn read_rtcp(sender: Arc<RTCRtpSender>, keyframe: Option<(Arc<AtomicBool>, Arc<Counters>)>) {
+    // Fails once the connection is closed
+    while let Ok((packets, _)) = sender.read_rtcp().await {
+        let Some((keyframe, counters)) = &keyframe else {
+            continue;
+        };
+        let requested = packets.iter().any(|p| {
+            p.as_any().is::<PictureLossIndication>() || p.as_any().is::<FullIntraRequest>()
+        });
+        if requested {
+            keyframe.store(true, Ordering::Relaxed);
+            counters.keyframe_requests.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+async fn write_audio(track: Arc<TrackLocalStaticSample>, mut rx: mpsc::Receiver<Bytes>) {
+    let duration = Duration::from_millis(FRAME_DURATION_MS as u64);
+    while let Some(data) = rx.recv().await {
+        let sample = Sample {
+            data,
+            duration,
+            ..Default::default()
+        };
+        if let Err(e) = track.write_sample(&sample).await {
+            tracing::debug!("Failed to send WHIP audio: {}", e);
+        }
+    }
+}
+
+/// Audio thread: one Opus frame of the call every 20ms until the session stops
+fn encode_audio(
+    tap: &CallTap,
+    encoder: &mut OpusEncoder,
+    tx: mpsc::Sender<Bytes>,
+    stop: &AtomicBool,
+    counters: &Counters,
+) {
+    let mut mix = vec![0.0f32; SAMPLES_PER_FRAME];
+    let mut mic = vec![0.0f32; SAMPLES_PER_FRAME];
+    let frame_ms = FRAME_DURATION_MS as u64;
+    let started = Instant::now();
+    let mut frames = 0u64;
+    while !stop.load(Ordering::SeqCst) {
+        let now_ms = started.elapsed().as_millis() as u64;
+        while (frames + 1) * frame_ms <= now_ms {
+            // The call is the mix with our voice on top, as in the recording
+            tap.take_frame(&mut mix, &mut mic);
+            for (call, voice) in mix.iter_mut().zip(&mic) {
+                *call = (*call + voice).clamp(-1.0, 1.0);
+            }
+            match encoder.encode(&mix) {
+                Ok(packet) => {
+                    if tx.try_send(Bytes::from(packet)).is_ok() {
+                        counters.audio_packets.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Err(e) => tracing::debug!("Failed to encode WHIP audio: {}", e),
+            }
+            frames += 1;
+        }
+        std::thread::sleep(Duration::from_millis(frame_ms / 2));
+    }
+}
+
+async fn write_video(
+    track: Arc<TrackLocalStaticSample>,
+    mut rx: mpsc::Receiver<(Bytes, Duration)>,
+) {
+    while let Some((data, duration)) = rx.recv().await {
+        let sample = Sample {
+            data,
+            duration,
+            ..Default::default()
+        };
+        if let Err(e) = track.write_sample(&sample).await {
+            tracing::debug!("Failed to send WHIP video: {}", e);
+        }
+    }
+}
+
+/// Encoder thread: H.264 frames until the session drops its sender
+fn encode_video(
+    rx: Receiver<VideoFrame>,
+    tx: mpsc::Sender<(Bytes, Duration)>,
+    keyframe: &AtomicBool,
+    counters: &Counters,
+) {
+    // Recreated when the picture size changes
+    let mut encoder: Option<(Encoder, u32, u32)> = None;
+    let mut last_frame: Option<Instant> = None;
+
+    for frame in rx {
+        let (width, height) = output_size(frame.width, frame.height);
+        if width == 0 || height == 0 {
+            continue;
+        }
+        let rgba = if (width, height) == (frame.width, frame.height) {
+            frame.data
+        } else {
+            let Some(image) =
+                ImageBuffer::<Rgba<u8>, _>::from_raw(frame.width, frame.height, frame.data)
+            else {
+                continue;
+            };
+            imageops::resize(&image, width, height, imageops::FilterType::Triangle).into_raw()
+        };
+
+        if !matches!(&encoder, Some((_, w, h)) if (*w, *h) == (width, height)) {
+            match Encoder::new() {
+                Ok(new) => encoder = Some((new, width, height)),
+                Err(e) => {
+                    tracing::error!("Failed to create the H.264 encoder: {}", e);
+                    return;
+                }
+            }
+        }
+        let Some((enc, _, _)) = encoder.as_mut() else {
+            return;
+        };
+        if keyframe.swap(false, Ordering::Relaxed) {
+            enc.force_intra_frame();
+        }
+
+        let yuv =
+            YUVBuffer::from_rgb_source(RgbaSliceU8::new(&rgba, (width as usize, height as usize)));
+        let data = match enc.encode(&yuv) {
+            Ok(bitstream) => bitstream.to_vec(),
+            Err(e) => {
+                tracing::warn!("Failed to encode WHIP frame: {}", e);
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        let duration = last_frame.map_or(Duration::from_millis(33), |last| now - last);
+        last_frame = Some(now);
+        if tx.blocking_send((Bytes::from(data), duration)).is_err() {
+            return;
+        }
+        counters.video_frames.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Largest even size (4:2:0 chroma) fitting `MAX_WIDTH`x`MAX_HEIGHT`, keeping
+/// the aspect ratio
+fn output_size(width: u32, height: u32) -> (u32, u32) {
+    let scale = (MAX_WIDTH as f64 / width.max(1) as f64)
+        .min(MAX_HEIGHT as f64 / height.max(1) as f64)
+        .min(1.0);
+    let even = |v: f64| (v as u32) & !1;
+    (even(width as f64 * scale), even(height as f64 * scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_size_fits_and_is_even() {
+        assert_eq!(output_size(1920, 1080), (1920, 1080));
+        assert_eq!(output_size(2560, 1440), (1920, 1080));
+        assert_eq!(output_size(1279, 721), (1278, 720));
+        assert_eq!(output_size(3840, 1080), (1920, 540));
+    }
+}
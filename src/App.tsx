@@ -2,19 +2,38 @@ import { useServerStore } from "./stores/serverStore";
 import { ServerLobby } from "./components/server/ServerLobby";
 import { ServerView } from "./components/server/ServerView";
 import { ScreenViewerPage } from "./pages/ScreenViewerPage";
+import { ScreenPopoutPage } from "./pages/ScreenPopoutPage";
+import { PipPage } from "./pages/PipPage";
+import { StatsOverlayPage } from "./pages/StatsOverlayPage";
 import { ThemeProvider } from "./contexts/ThemeContext";
 import { UpdateChecker } from "./components/ui/UpdateChecker";
+import "./services/crashService";
 
 function AppContent() {
   const { serverInfo } = useServerStore();
 
   // Check if we're in the screen viewer window
   const isScreenViewerWindow = window.location.hash === "#screen-viewer";
+  const isStatsOverlayWindow = window.location.hash === "#stats-overlay";
+  const isScreenPopoutWindow = window.location.hash === "#screen-popout";
+  const isPipWindow = window.location.hash === "#pip";
 
   if (isScreenViewerWindow) {
     return <ScreenViewerPage />;
   }
 
+  if (isStatsOverlayWindow) {
+    return <StatsOverlayPage />;
+  }
+
+  if (isScreenPopoutWindow) {
+    return <ScreenPopoutPage />;
+  }
+
+  if (isPipWindow) {
+    return <PipPage />;
+  }
+
   // Si pas connecté à un serveur, afficher le lobby
   if (!serverInfo) {
     return (
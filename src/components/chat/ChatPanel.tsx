@@ -1,7 +1,19 @@
 import { useState, useRef, useEffect, useCallback, memo } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { useServerStore } from "../../stores/serverStore";
 import { peerService } from "../../services/peerService";
+import {
+  chatGetTopic,
+  chatRecordMessage,
+  chatRunCommand,
+  errorMessage,
+} from "../../services/tauriApi";
+import { EVENTS, type ChatSystemMessageEvent } from "../../types/events";
 import type { Message } from "../../types/room";
+import { ChatSearch } from "./ChatSearch";
+
+/** Sender id of the lines produced by chat commands */
+const SYSTEM_SENDER = "system";
 
 interface ChatPanelProps {
   isConnected: boolean;
@@ -22,6 +34,16 @@ const ChatMessage = memo(function ChatMessage({
     });
   };
 
+  // Outcome of a chat command: one centered line
+  if (msg.senderId === SYSTEM_SENDER) {
+    return (
+      <div className="px-4 py-1 text-center">
+        <span className="text-[12px] italic text-dark-400">{msg.content}</span>
+        <span className="ml-2 text-[11px] text-dark-600">{formatTime(msg.timestamp)}</span>
+      </div>
+    );
+  }
+
   return (
     <div className={`group flex gap-3 px-4 py-2 hover:bg-dark-800/30 transition ${isLocal ? "flex-row-reverse" : ""}`}>
       {/* Avatar */}
@@ -58,9 +80,12 @@ const ChatMessage = memo(function ChatMessage({
 });
 
 export function ChatPanel({ isConnected }: ChatPanelProps) {
-  const { username, messages, addMessage } = useServerStore();
+  const { username, messages, addMessage, serverInfo } = useServerStore();
   const [input, setInput] = useState("");
   const [sending, setSending] = useState(false);
+  const [topic, setTopic] = useState<string | null>(null);
+  const [commandError, setCommandError] = useState<string | null>(null);
+  const [searchOpen, setSearchOpen] = useState(false);
   const messagesEndRef = useRef<HTMLDivElement>(null);
   const messagesContainerRef = useRef<HTMLDivElement>(null);
   const inputRef = useRef<HTMLTextAreaElement>(null);
@@ -94,11 +119,59 @@ export function ChatPanel({ isConnected }: ChatPanelProps) {
     }
   }, [input]);
 
+  // Lines produced by chat commands (ours and the peers'), and the room topic
+  useEffect(() => {
+    if (!isConnected) return;
+
+    chatGetTopic().then(setTopic).catch(console.error);
+    let unlisten: (() => void) | undefined;
+    const setupListener = async () => {
+      unlisten = await listen<ChatSystemMessageEvent>(EVENTS.chatSystemMessage, (event) => {
+        const message = event.payload;
+        if (message.kind === "topic") {
+          // null when `/topic` was sent without a topic
+          setTopic(message.text);
+        }
+        addMessage({
+          id: crypto.randomUUID(),
+          senderId: SYSTEM_SENDER,
+          senderName: message.actor,
+          content: message.line,
+          timestamp: message.timestamp * 1000,
+        });
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [isConnected, addMessage]);
+
   const handleSend = useCallback(() => {
     if (!input.trim() || !isConnected || sending) return;
 
-    const content = input.trim();
+    let content = input.trim();
     setInput("");
+    setCommandError(null);
+
+    // "/..." is a command run by the backend, "//..." a message starting with "/"
+    if (content.startsWith("/") && !content.startsWith("//")) {
+      setSending(true);
+      chatRunCommand(content)
+        .catch((e) => {
+          setCommandError(errorMessage(e));
+          setInput(content);
+        })
+        .finally(() => setSending(false));
+      return;
+    }
+    if (content.startsWith("//")) {
+      content = content.slice(1);
+    }
     setSending(true);
 
     try {
@@ -106,20 +179,25 @@ export function ChatPanel({ isConnected }: ChatPanelProps) {
       peerService.sendChat(content);
 
       // Ajouter notre propre message à la liste
+      const timestamp = Date.now();
       addMessage({
         id: crypto.randomUUID(),
         senderId: "local",
         senderName: username,
         content,
-        timestamp: Date.now(),
+        timestamp,
       });
+      // Et à la transcription exportable
+      if (serverInfo) {
+        chatRecordMessage(serverInfo.code, username, content, timestamp, true).catch(console.error);
+      }
     } catch (e) {
       console.error("Failed to send message:", e);
       setInput(content);
     } finally {
       setSending(false);
     }
-  }, [input, isConnected, sending, addMessage, username]);
+  }, [input, isConnected, sending, addMessage, username, serverInfo]);
 
   const handleKeyDown = useCallback(
     (e: React.KeyboardEvent) => {
@@ -133,6 +211,18 @@ export function ChatPanel({ isConnected }: ChatPanelProps) {
 
   return (
     <div className="h-full flex flex-col">
+      {/* History search */}
+      {searchOpen && (
+        <ChatSearch roomCode={serverInfo?.code ?? null} onClose={() => setSearchOpen(false)} />
+      )}
+
+      {/* Room topic */}
+      {topic && (
+        <div className="flex-shrink-0 px-4 py-2 border-b border-dark-700/50 text-[12px] text-dark-300 truncate" title={topic}>
+          Sujet : {topic}
+        </div>
+      )}
+
       {/* Messages area */}
       <div
         ref={messagesContainerRef}
@@ -186,6 +276,18 @@ export function ChatPanel({ isConnected }: ChatPanelProps) {
             style={{ minHeight: "46px", maxHeight: "120px" }}
           />
 
+          <button
+            onClick={() => setSearchOpen((open) => !open)}
+            className={`flex-shrink-0 w-[46px] h-[46px] flex items-center justify-center rounded-xl transition ${
+              searchOpen ? "bg-accent-600/30 text-white" : "bg-dark-800 text-dark-400 hover:text-white"
+            }`}
+            title="Rechercher dans l'historique"
+          >
+            <svg className="w-5 h-5" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+              <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M21 21l-6-6m2-5a7 7 0 11-14 0 7 7 0 0114 0z" />
+            </svg>
+          </button>
+
           <button
             onClick={handleSend}
             disabled={!isConnected || !input.trim() || sending}
@@ -197,6 +299,10 @@ export function ChatPanel({ isConnected }: ChatPanelProps) {
           </button>
         </div>
 
+        {commandError && (
+          <p className="text-[12px] text-red-400 mt-2 px-1">{commandError}</p>
+        )}
+
         {/* Keyboard hint */}
         <p className="text-[11px] text-dark-500 mt-2 px-1">
           Appuie sur <kbd className="px-1.5 py-0.5 bg-dark-800 rounded text-dark-400">Entrée</kbd> pour envoyer
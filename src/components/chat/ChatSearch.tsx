@@ -0,0 +1,178 @@
+import { useState, useEffect } from "react";
+import { ask } from "@tauri-apps/plugin-dialog";
+import {
+  chatClearHistory,
+  chatSearch,
+  errorMessage,
+  type ChatEntry,
+  type ChatSearchHit,
+} from "../../services/tauriApi";
+
+interface ChatSearchProps {
+  /** Room searched by default (all rooms when unchecked) */
+  roomCode: string | null;
+  onClose: () => void;
+}
+
+/** Wait this long after the last keystroke before searching */
+const SEARCH_DEBOUNCE_MS = 250;
+
+const formatDate = (timestamp: number) =>
+  new Date(timestamp * 1000).toLocaleString("fr-FR", {
+    day: "2-digit",
+    month: "2-digit",
+    hour: "2-digit",
+    minute: "2-digit",
+  });
+
+/** Snippet with the matched terms (between \u0002 and \u0003) highlighted */
+function Snippet({ snippet }: { snippet: string }) {
+  const parts = snippet.split(/(\u0002[^\u0003]*\u0003)/);
+  return (
+    <>
+      {parts.map((part, i) =>
+        part.startsWith("\u0002") ? (
+          <mark key={i} className="bg-accent-500/40 text-white rounded px-0.5">
+            {part.slice(1, -1)}
+          </mark>
+        ) : (
+          <span key={i}>{part}</span>
+        )
+      )}
+    </>
+  );
+}
+
+function ContextLine({ entry }: { entry: ChatEntry }) {
+  return (
+    <p className="text-[12px] text-dark-500 truncate">
+      <span className="font-medium">{entry.sender}</span> : {entry.content}
+    </p>
+  );
+}
+
+export function ChatSearch({ roomCode, onClose }: ChatSearchProps) {
+  const [query, setQuery] = useState("");
+  const [thisRoomOnly, setThisRoomOnly] = useState(true);
+  const [hits, setHits] = useState<ChatSearchHit[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setHits([]);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(() => {
+      chatSearch(query, thisRoomOnly ? roomCode ?? undefined : undefined)
+        .then((results) => {
+          if (!cancelled) {
+            setHits(results);
+            setError(null);
+          }
+        })
+        .catch((e) => {
+          if (!cancelled) setError(errorMessage(e));
+        });
+    }, SEARCH_DEBOUNCE_MS);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [query, thisRoomOnly, roomCode]);
+
+  const handleClear = async () => {
+    const room = thisRoomOnly ? roomCode ?? undefined : undefined;
+    const confirmed = await ask(
+      room
+        ? `Effacer l'historique du chat de la room ${room} ?`
+        : "Effacer l'historique du chat de toutes les rooms ?",
+      { title: "Historique du chat", kind: "warning" }
+    );
+    if (!confirmed) return;
+    try {
+      await chatClearHistory(room);
+      setHits([]);
+      setError(null);
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  };
+
+  return (
+    <div className="flex-shrink-0 max-h-[50%] flex flex-col border-b border-dark-700/50 bg-dark-900">
+      <div className="flex items-center gap-2 p-3">
+        <input
+          autoFocus
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          onKeyDown={(e) => e.key === "Escape" && onClose()}
+          placeholder="Rechercher dans l'historique..."
+          className="flex-1 min-w-0 bg-dark-800 text-white text-[13px] rounded-lg px-3 py-2 placeholder-dark-500 border border-dark-700/50 focus:border-accent-500/50 outline-none"
+        />
+        {roomCode && (
+          <label className="flex items-center gap-1 text-[11px] text-dark-400 whitespace-nowrap">
+            <input
+              type="checkbox"
+              checked={thisRoomOnly}
+              onChange={(e) => setThisRoomOnly(e.target.checked)}
+            />
+            Cette room
+          </label>
+        )}
+        <button
+          onClick={handleClear}
+          className="p-1.5 rounded text-dark-400 hover:text-red-400 transition"
+          title="Effacer l'historique"
+        >
+          <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth={2}
+              d="M19 7l-.867 12.142A2 2 0 0116.138 21H7.862a2 2 0 01-1.995-1.858L5 7m5 4v6m4-6v6m1-10V4a1 1 0 00-1-1h-4a1 1 0 00-1 1v3M4 7h16"
+            />
+          </svg>
+        </button>
+        <button
+          onClick={onClose}
+          className="p-1.5 rounded text-dark-400 hover:text-white transition"
+          title="Fermer la recherche"
+        >
+          <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+            <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M6 18L18 6M6 6l12 12" />
+          </svg>
+        </button>
+      </div>
+
+      <div className="overflow-y-auto px-3 pb-3 space-y-2">
+        {error && <p className="text-[12px] text-red-400">{error}</p>}
+        {query.trim() && !error && hits.length === 0 && (
+          <p className="text-[12px] text-dark-500">Aucun résultat</p>
+        )}
+        {hits.map((hit) => (
+          <div key={hit.id} className="rounded-lg bg-dark-800/60 border border-dark-700/50 px-3 py-2">
+            {hit.before.map((entry, i) => (
+              <ContextLine key={`b${i}`} entry={entry} />
+            ))}
+            <div className="my-1">
+              <div className="flex items-baseline gap-2">
+                <span className="text-[12px] font-semibold text-emerald-400">{hit.sender}</span>
+                <span className="text-[11px] text-dark-500">
+                  {formatDate(hit.timestamp)}
+                  {!thisRoomOnly && ` · ${hit.room_code}`}
+                </span>
+              </div>
+              <p className="text-[13px] text-white whitespace-pre-wrap break-words">
+                <Snippet snippet={hit.snippet} />
+              </p>
+            </div>
+            {hit.after.map((entry, i) => (
+              <ContextLine key={`a${i}`} entry={entry} />
+            ))}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -1,6 +1,8 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import * as api from "../../services/tauriApi";
 import { useServerStore } from "../../stores/serverStore";
+import { EVENTS, type IceGatheringEvent } from "../../types/events";
 
 interface SignalingModalProps {
   isHost: boolean;
@@ -17,16 +19,36 @@ export function SignalingModal({ isHost, onConnected, onClose }: SignalingModalP
   const [remoteData, setRemoteData] = useState("");
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState("");
+  // Candidates gathered while the code is being generated
+  const [gathering, setGathering] = useState<IceGatheringEvent | null>(null);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    const setup = async () => {
+      unlisten = await listen<IceGatheringEvent>(EVENTS.iceGathering, (event) => {
+        // Mesh peers and WHIP have their own gathering, this modal drives the single connection
+        if (event.payload.source === "connection") setGathering(event.payload);
+      });
+    };
+    setup();
+    return () => unlisten?.();
+  }, []);
+
+  const generatingLabel =
+    gathering?.phase === "gathering"
+      ? `Recherche des candidats... (${gathering.candidates})`
+      : "Generation...";
 
   const generateOffer = async () => {
     setLoading(true);
     setError("");
+    setGathering(null);
     try {
       const result = await api.createWebRTCOffer(username || "Anonymous");
       setOffer(result.sdp_base64);
       setStep("waiting");
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -35,12 +57,13 @@ export function SignalingModal({ isHost, onConnected, onClose }: SignalingModalP
   const acceptRemoteOffer = async () => {
     setLoading(true);
     setError("");
+    setGathering(null);
     try {
       const result = await api.acceptWebRTCOffer(remoteData.trim(), username || "Anonymous");
       setOffer(result.sdp_base64);
       setStep("waiting");
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -53,7 +76,7 @@ export function SignalingModal({ isHost, onConnected, onClose }: SignalingModalP
       await api.acceptWebRTCAnswer(remoteData.trim());
       onConnected();
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -99,7 +122,7 @@ export function SignalingModal({ isHost, onConnected, onClose }: SignalingModalP
                   disabled={loading}
                   className="w-full py-3 bg-primary-600 hover:bg-primary-700 text-white font-medium rounded-lg transition disabled:opacity-50"
                 >
-                  {loading ? "Generation..." : "Generer le code"}
+                  {loading ? generatingLabel : "Generer le code"}
                 </button>
               </div>
             )}
@@ -164,7 +187,7 @@ export function SignalingModal({ isHost, onConnected, onClose }: SignalingModalP
                   disabled={loading || !remoteData.trim()}
                   className="w-full py-3 bg-primary-600 hover:bg-primary-700 text-white font-medium rounded-lg transition disabled:opacity-50"
                 >
-                  {loading ? "Generation..." : "Generer ma reponse"}
+                  {loading ? generatingLabel : "Generer ma reponse"}
                 </button>
               </div>
             )}
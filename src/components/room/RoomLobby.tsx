@@ -20,7 +20,7 @@ export function RoomLobby() {
       const localP = room.participants.find((p) => p.is_host);
       if (localP) setLocalParticipant(localP);
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setIsLoading(false);
     }
@@ -38,7 +38,7 @@ export function RoomLobby() {
       const localP = room.participants.find((p) => !p.is_host);
       if (localP) setLocalParticipant(localP);
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setIsLoading(false);
     }
@@ -1,21 +1,92 @@
 import { useState, useRef, useEffect } from "react";
-import type { EncodedFrameData } from "../../services/tauriApi";
+import type { EncodedFrameData, PresenterHints } from "../../services/tauriApi";
+import * as api from "../../services/tauriApi";
 
 interface RemoteScreenViewerProps {
+  peerId: string;
   peerUsername: string;
   frame: EncodedFrameData | null;
+  /** What the presenter shares and where their pointer is */
+  hints: PresenterHints | null;
   onClose: () => void;
 }
 
 export function RemoteScreenViewer({
+  peerId,
   peerUsername,
   frame,
+  hints,
   onClose,
 }: RemoteScreenViewerProps) {
   const [isFullscreen, setIsFullscreen] = useState(false);
   const [isMinimized, setIsMinimized] = useState(false);
+  const [isVirtualCamera, setIsVirtualCamera] = useState(false);
+  const [cameraError, setCameraError] = useState<string | null>(null);
+  const [isPoppedOut, setIsPoppedOut] = useState(false);
   const containerRef = useRef<HTMLDivElement>(null);
 
+  // Send the shared screen to a virtual camera usable by other apps
+  const toggleVirtualCamera = async () => {
+    setCameraError(null);
+    try {
+      if (isVirtualCamera) {
+        await api.virtualCameraStop();
+        setIsVirtualCamera(false);
+      } else {
+        await api.virtualCameraStart("remote");
+        setIsVirtualCamera(true);
+      }
+    } catch (e) {
+      setCameraError(api.errorMessage(e));
+    }
+  };
+
+  // Forward every received frame while the virtual camera is on
+  useEffect(() => {
+    if (isVirtualCamera && frame) {
+      api.virtualCameraPushFrame(frame.data).catch(console.error);
+    }
+  }, [frame, isVirtualCamera]);
+
+  // Closing the viewer stops the virtual camera
+  useEffect(() => {
+    if (!isVirtualCamera) return;
+    return () => {
+      api.virtualCameraStop().catch(console.error);
+    };
+  }, [isVirtualCamera]);
+
+  // Show the share in its own window (e.g. on a second monitor)
+  const popOut = async () => {
+    try {
+      await api.screenPopoutOpen(peerId, peerUsername);
+      setIsPoppedOut(true);
+      setIsMinimized(true);
+    } catch (e) {
+      setCameraError(api.errorMessage(e));
+    }
+  };
+
+  // Forward every received frame to the pop-out until it is closed
+  useEffect(() => {
+    if (isPoppedOut && frame) {
+      api
+        .screenPopoutPushFrame(peerId, frame)
+        .then((open) => {
+          if (!open) setIsPoppedOut(false);
+        })
+        .catch(console.error);
+    }
+  }, [frame, isPoppedOut, peerId]);
+
+  // Closing the viewer closes the pop-out
+  useEffect(() => {
+    if (!isPoppedOut) return;
+    return () => {
+      api.screenPopoutClose(peerId).catch(console.error);
+    };
+  }, [isPoppedOut, peerId]);
+
   // Handle fullscreen
   const toggleFullscreen = async () => {
     if (!containerRef.current) return;
@@ -77,14 +148,62 @@ export function RemoteScreenViewer({
           <span className="text-white text-sm font-medium">
             {peerUsername} partage son écran
           </span>
-          {frame && (
+          {hints && (
+            <span className="text-dark-300 text-xs max-w-xs truncate" title={hints.source_name}>
+              {hints.source_name}
+            </span>
+          )}
+          {hints ? (
             <span className="text-dark-400 text-xs">
-              {frame.width} x {frame.height}
+              {hints.width} x {hints.height}
+            </span>
+          ) : (
+            frame && (
+              <span className="text-dark-400 text-xs">
+                {frame.width} x {frame.height}
+              </span>
+            )
+          )}
+          {hints?.paused && (
+            <span className="px-1.5 py-0.5 rounded bg-yellow-600/80 text-white text-xs">
+              En pause
             </span>
           )}
         </div>
 
         <div className="flex items-center gap-2">
+          {cameraError && (
+            <span className="text-red-400 text-xs max-w-xs truncate" title={cameraError}>
+              {cameraError}
+            </span>
+          )}
+
+          {/* Virtual camera */}
+          <button
+            onClick={toggleVirtualCamera}
+            className={`p-1.5 rounded transition ${
+              isVirtualCamera
+                ? "bg-primary-600 text-white hover:bg-primary-700"
+                : "bg-dark-700/80 text-dark-300 hover:text-white"
+            }`}
+            title={isVirtualCamera ? "Arrêter la caméra virtuelle" : "Envoyer vers la caméra virtuelle"}
+          >
+            <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+              <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M15 10l4.553-2.276A1 1 0 0121 8.618v6.764a1 1 0 01-1.447.894L15 14M5 18h8a2 2 0 002-2V8a2 2 0 00-2-2H5a2 2 0 00-2 2v8a2 2 0 002 2z" />
+            </svg>
+          </button>
+
+          {/* Pop-out window */}
+          <button
+            onClick={popOut}
+            className="p-1.5 rounded bg-dark-700/80 text-dark-300 hover:text-white transition"
+            title="Ouvrir dans une fenêtre"
+          >
+            <svg className="w-4 h-4" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+              <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M10 6H6a2 2 0 00-2 2v10a2 2 0 002 2h10a2 2 0 002-2v-4M14 4h6m0 0v6m0-6L10 14" />
+            </svg>
+          </button>
+
           {/* Minimize */}
           <button
             onClick={() => setIsMinimized(true)}
@@ -129,12 +248,24 @@ export function RemoteScreenViewer({
       {/* Video frame */}
       <div className="flex-1 flex items-center justify-center bg-black overflow-hidden">
         {frame ? (
-          <img
-            src={`data:image/jpeg;base64,${frame.data}`}
-            alt={`Écran de ${peerUsername}`}
-            className="max-w-full max-h-full object-contain"
-            style={{ imageRendering: "auto" }}
-          />
+          <div className="relative max-w-full max-h-full">
+            <img
+              src={`data:image/jpeg;base64,${frame.data}`}
+              alt={`Écran de ${peerUsername}`}
+              className="block max-w-full max-h-full object-contain"
+              style={{ imageRendering: "auto" }}
+            />
+            {/* Presenter's pointer, placed as a fraction of the shared source */}
+            {hints?.pointer && (
+              <div
+                className="absolute w-3 h-3 -ml-1.5 -mt-1.5 rounded-full bg-red-500/80 ring-2 ring-white/80 pointer-events-none transition-all duration-100"
+                style={{
+                  left: `${hints.pointer.x * 100}%`,
+                  top: `${hints.pointer.y * 100}%`,
+                }}
+              />
+            )}
+          </div>
         ) : (
           <div className="text-center">
             <div className="w-12 h-12 border-4 border-primary-500 border-t-transparent rounded-full animate-spin mx-auto mb-4" />
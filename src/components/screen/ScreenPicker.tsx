@@ -6,6 +6,7 @@ import {
   screenCapturePreview,
   screenCheckPermission,
   screenRequestPermission,
+  errorMessage,
   type CaptureSourceInfo,
 } from "../../services/tauriApi";
 
@@ -32,7 +33,7 @@ export function ScreenPicker({ isOpen, onClose, onSelect }: ScreenPickerProps) {
       const allSources = await screenListSources(false);
       setSources(allSources);
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -46,7 +47,7 @@ export function ScreenPicker({ isOpen, onClose, onSelect }: ScreenPickerProps) {
         loadSources();
       }
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
     }
   }, [loadSources]);
 
@@ -58,7 +59,7 @@ export function ScreenPicker({ isOpen, onClose, onSelect }: ScreenPickerProps) {
         loadSources();
       }
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
     }
   }, [loadSources]);
 
@@ -85,7 +86,7 @@ export function ScreenPicker({ isOpen, onClose, onSelect }: ScreenPickerProps) {
       const previewData = await screenCapturePreview(400);
       setPreview(previewData);
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
     } finally {
       setPreviewLoading(false);
     }
@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { ScreenPicker } from "./ScreenPicker";
 import {
   screenStartSharing,
@@ -6,6 +6,7 @@ import {
   screenClearSelection,
   screenStreamStart,
   screenStreamStop,
+  screenStreamSetPaused,
   type CaptureSourceInfo,
 } from "../../services/tauriApi";
 import {
@@ -20,17 +21,37 @@ interface ScreenShareButtonProps {
   fps?: number;
 }
 
+/** Stop sharing and streaming, and close the viewer window */
+export async function stopScreenSharing() {
+  await closeScreenViewerWindow();
+  await screenStreamStop();
+  await screenStopSharing();
+  await screenClearSelection();
+}
+
 export function ScreenShareButton({ isSharing, onSharingChange, fps = 30 }: ScreenShareButtonProps) {
   const [pickerOpen, setPickerOpen] = useState(false);
+  const [isPaused, setIsPaused] = useState(false);
+
+  // The share may also be stopped from outside the button
+  useEffect(() => {
+    if (!isSharing) setIsPaused(false);
+  }, [isSharing]);
+
+  // Hold the frames: viewers keep the last one and see the share is paused
+  const togglePause = async () => {
+    try {
+      await screenStreamSetPaused(!isPaused);
+      setIsPaused(!isPaused);
+    } catch (err) {
+      console.error("Failed to pause sharing:", err);
+    }
+  };
 
   const handleClick = async () => {
     if (isSharing) {
-      // Stop sharing and streaming
       try {
-        await closeScreenViewerWindow();
-        await screenStreamStop();
-        await screenStopSharing();
-        await screenClearSelection();
+        await stopScreenSharing();
         onSharingChange(false);
       } catch (err) {
         console.error("Failed to stop sharing:", err);
@@ -101,6 +122,43 @@ export function ScreenShareButton({ isSharing, onSharingChange, fps = 30 }: Scre
         </svg>
       </button>
 
+      {isSharing && (
+        <button
+          onClick={togglePause}
+          className={`p-3 rounded-full transition-colors ${
+            isPaused
+              ? "bg-yellow-600 hover:bg-yellow-700 text-white"
+              : "bg-zinc-700 hover:bg-zinc-600 text-zinc-300"
+          }`}
+          title={isPaused ? "Resume sharing" : "Pause sharing"}
+        >
+          <svg
+            className="w-5 h-5"
+            fill="none"
+            stroke="currentColor"
+            viewBox="0 0 24 24"
+          >
+            {isPaused ? (
+              // Play icon when paused
+              <path
+                strokeLinecap="round"
+                strokeLinejoin="round"
+                strokeWidth={2}
+                d="M14.752 11.168l-3.197-2.132A1 1 0 0010 9.87v4.263a1 1 0 001.555.832l3.197-2.132a1 1 0 000-1.664z M21 12a9 9 0 11-18 0 9 9 0 0118 0z"
+              />
+            ) : (
+              // Pause icon
+              <path
+                strokeLinecap="round"
+                strokeLinejoin="round"
+                strokeWidth={2}
+                d="M10 9v6m4-6v6m7-3a9 9 0 11-18 0 9 9 0 0118 0z"
+              />
+            )}
+          </svg>
+        </button>
+      )}
+
       <ScreenPicker
         isOpen={pickerOpen}
         onClose={() => setPickerOpen(false)}
@@ -2,6 +2,7 @@ import { useState, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
 import type { EncodedFrameData, StreamStats } from "../../services/tauriApi";
 import * as api from "../../services/tauriApi";
+import { EVENTS, type ScreenFrameEvent } from "../../types/events";
 
 interface ScreenShareViewerProps {
   /** Whether the local user is the one sharing */
@@ -29,8 +30,9 @@ export function ScreenShareViewer({
     let unlistenFn: (() => void) | undefined;
 
     const setupListener = async () => {
-      unlistenFn = await listen<EncodedFrameData>("screen-frame", (event) => {
+      unlistenFn = await listen<ScreenFrameEvent>(EVENTS.screenFrame, (event) => {
         setCurrentFrame(event.payload);
+        api.screenStreamAckFrame(event.payload).catch(console.error);
       });
     };
 
@@ -204,6 +206,14 @@ export function ScreenShareViewer({
               <span className="text-dark-400">Frames:</span>
               <span>{stats.frames_sent.toLocaleString()}</span>
             </div>
+            <div className="flex justify-between gap-4">
+              <span className="text-dark-400">Sautées:</span>
+              <span>{stats.frames_skipped.toLocaleString()}</span>
+            </div>
+            <div className="flex justify-between gap-4">
+              <span className="text-dark-400">Perdues:</span>
+              <span>{stats.frames_dropped.toLocaleString()}</span>
+            </div>
             <div className="flex justify-between gap-4">
               <span className="text-dark-400">Total:</span>
               <span>{formatBytes(stats.total_bytes)}</span>
@@ -1,6 +1,8 @@
 import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { useServerStore } from "../../stores/serverStore";
 import * as api from "../../services/tauriApi";
+import { EVENTS, type AutoJoinEvent } from "../../types/events";
 import { ThemeToggle } from "../ui/ThemeToggle";
 
 export function ServerLobby() {
@@ -15,39 +17,80 @@ export function ServerLobby() {
 
   // Charger le code serveur au démarrage
   useEffect(() => {
-    api.getServerConfig(username).then((config) => {
+    api.getServerConfig(username).then(async (config) => {
       setMyServerCode(config.code);
+      // Lien d'invitation reçu avant que le pseudo soit connu
+      const code = await api.takePendingJoinCode();
+      if (code) {
+        await joinWithCode(code);
+      }
     });
+  // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [username]);
 
-  const handleHost = async () => {
+  // Lien d'invitation ou --join : le backend rejoint lui-même
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<AutoJoinEvent>(EVENTS.autoJoin, (event) => {
+        if (event.payload.info) {
+          setServerInfo(event.payload.info);
+        } else {
+          setJoinCode(event.payload.code);
+          setError(event.payload.error);
+        }
+      });
+      // Rejoint avant que le lobby écoute (lancement depuis un lien)
+      const info = await api.getServerInfo();
+      if (info) {
+        setServerInfo(info);
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  const joinWithCode = async (code: string) => {
     setIsLoading(true);
     setError(null);
     try {
-      const info = await api.startHosting(username);
+      const info = await api.joinServer(code.trim().toUpperCase(), username);
       setServerInfo(info);
     } catch (e) {
-      setError(String(e));
+      setJoinCode(code);
+      setError(api.errorMessage(e));
     } finally {
       setIsLoading(false);
     }
   };
 
-  const handleJoin = async (e: React.FormEvent) => {
-    e.preventDefault();
-    if (!joinCode.trim()) return;
+  const handleHost = async () => {
     setIsLoading(true);
     setError(null);
     try {
-      const info = await api.joinServer(joinCode.trim().toUpperCase(), username);
+      const info = await api.startHosting(username);
       setServerInfo(info);
     } catch (e) {
-      setError(String(e));
+      setError(api.errorMessage(e));
     } finally {
       setIsLoading(false);
     }
   };
 
+  const handleJoin = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!joinCode.trim()) return;
+    await joinWithCode(joinCode);
+  };
+
   const startEditingName = () => {
     setTempName(username);
     setIsEditingName(true);
@@ -1,13 +1,34 @@
 import { useState, useEffect, useRef, useCallback } from "react";
+import { Channel } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useServerStore } from "../../stores/serverStore";
 import * as api from "../../services/tauriApi";
-import type { EncodedFrameData } from "../../services/tauriApi";
+import type { EncodedFrameData, PresenterHints } from "../../services/tauriApi";
+import {
+  EVENTS,
+  type AudioDeviceFallbackEvent,
+  type AudioDeviceRetryEvent,
+  type AudioLevelEvent,
+  type AutoMutedEvent,
+  type CallTimerExpiredEvent,
+  type CallTimerTickEvent,
+  type CallTimerWarningEvent,
+  type GameModeChangedEvent,
+  type PeerQualityChangedEvent,
+  type PipStateEvent,
+  type PortMappingChangedEvent,
+  type PresenterHintsEvent,
+  type RecordingFailedEvent,
+  type RecordingFinishedEvent,
+  type ScreenFrameEvent,
+  type TrayActionEvent,
+} from "../../types/events";
 import { peerService } from "../../services/peerService";
 import { ChatPanel } from "../chat/ChatPanel";
 import { VoiceControls, type VoiceControlsRef } from "../voice/VoiceControls";
 import { ToastContainer } from "../ui/Toast";
 import { ConnectionIndicator } from "../ui/ConnectionIndicator";
+import { SignalBars } from "../ui/SignalBars";
 import { ThemeToggle } from "../ui/ThemeToggle";
 import { useKeyboardShortcuts } from "../../hooks/useKeyboardShortcuts";
 import { useToast } from "../../hooks/useToast";
@@ -18,12 +39,6 @@ interface ConnectedPeer {
   username: string;
 }
 
-interface AudioLevelEvent {
-  level: number;
-  is_speaking: boolean;
-  rms: number;
-}
-
 interface SpeakingState {
   [odId: string]: boolean;
 }
@@ -32,6 +47,15 @@ interface RemoteScreenShare {
   peerId: string;
   peerUsername: string;
   frame: EncodedFrameData | null;
+  hints: PresenterHints | null;
+}
+
+/** "m:ss", or "h:mm:ss" past an hour */
+function formatDuration(totalSecs: number): string {
+  const hours = Math.floor(totalSecs / 3600);
+  const minutes = Math.floor((totalSecs % 3600) / 60);
+  const secs = String(totalSecs % 60).padStart(2, "0");
+  return hours > 0 ? `${hours}:${String(minutes).padStart(2, "0")}:${secs}` : `${minutes}:${secs}`;
 }
 
 export function ServerView() {
@@ -46,20 +70,40 @@ export function ServerView() {
   const [remoteScreenShare, setRemoteScreenShare] = useState<RemoteScreenShare | null>(null);
   const [isLocalScreenSharing, setIsLocalScreenSharing] = useState(false);
   const voiceControlsRef = useRef<VoiceControlsRef>(null);
-  const audioLoopRef = useRef<ReturnType<typeof setTimeout> | null>(null);
   const audioLoopRunningRef = useRef(false);
+  const pipOpenRef = useRef(false);
+  const [recording, setRecording] = useState<api.RecordingStatus | null>(null);
+  const recordingRef = useRef(false);
+  const [callTimer, setCallTimer] = useState<CallTimerTickEvent | null>(null);
+  const [peerQuality, setPeerQuality] = useState<Record<string, api.PeerQuality>>({});
+  const [externalAddress, setExternalAddress] = useState<string | null>(null);
   const { toasts, toast, removeToast } = useToast();
 
   const isHost = serverInfo?.is_hosting ?? false;
 
   // Handle receiving screen frames from peers
   const handlePeerScreenFrame = useCallback((peerId: string, frameData: EncodedFrameData) => {
+    // The backend only shows the active speaker's frames in the PiP window
+    if (pipOpenRef.current) {
+      api.pipPushFrame(peerId, frameData).catch(console.error);
+    }
+    if (recordingRef.current) {
+      api.recordingPushFrame(peerId, frameData).catch(console.error);
+    }
     setRemoteScreenShare((prev) => {
       if (!prev || prev.peerId !== peerId) return prev;
       return { ...prev, frame: frameData };
     });
   }, []);
 
+  // Handle presenter hints (source, pause, pointer) from the sharing peer
+  const handlePeerScreenHints = useCallback((peerId: string, hints: PresenterHints) => {
+    setRemoteScreenShare((prev) => {
+      if (!prev || prev.peerId !== peerId) return prev;
+      return { ...prev, hints };
+    });
+  }, []);
+
   // Handle peer screen sharing state change
   const handlePeerScreenState = useCallback((peerId: string, isSharing: boolean, peerUsername: string) => {
     if (isSharing) {
@@ -67,6 +111,7 @@ export function ServerView() {
         peerId,
         peerUsername,
         frame: null,
+        hints: null,
       });
       toast.info(`${peerUsername} partage son écran`);
     } else {
@@ -89,7 +134,9 @@ export function ServerView() {
     const minFrameInterval = 33; // ~30fps max for network
 
     const setupListener = async () => {
-      unlisten = await listen<EncodedFrameData>("screen-frame", (event) => {
+      unlisten = await listen<ScreenFrameEvent>(EVENTS.screenFrame, (event) => {
+        // Acknowledge even the frames throttled below, the backend only waits for the webview
+        api.screenStreamAckFrame(event.payload).catch(console.error);
         const now = Date.now();
         // Throttle to avoid overwhelming the network
         if (now - lastFrameTime < minFrameInterval) return;
@@ -122,6 +169,26 @@ export function ServerView() {
     };
   }, [isConnected, isLocalScreenSharing, username]);
 
+  // Relay our presenter hints to peers next to the frames
+  useEffect(() => {
+    if (!isConnected) return;
+
+    let unlisten: (() => void) | undefined;
+    const setupListener = async () => {
+      unlisten = await listen<PresenterHintsEvent>(EVENTS.screenPresenterHints, (event) => {
+        peerService.broadcast({ type: "screen-hints", payload: event.payload });
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [isConnected]);
+
   // Notify peers when we stop sharing
   useEffect(() => {
     const checkSharingStatus = async () => {
@@ -152,48 +219,30 @@ export function ServerView() {
     }
   }, []);
 
-  // Audio send loop - polls for encoded packets and sends them
+  // Audio send loop - encoded packets are pushed by the backend as they're produced
   const startAudioLoop = useCallback(() => {
     if (audioLoopRunningRef.current) return;
     audioLoopRunningRef.current = true;
 
-    const loop = async () => {
-      if (!audioLoopRunningRef.current) return;
-
-      try {
-        const packet = await api.streamingGetOutgoingPacket();
-        if (packet && packet.data.length > 0) {
-          console.log("[Audio] Sending packet size:", packet.data.length);
-          peerService.broadcast({
-            type: "audio",
-            payload: {
-              data: packet.data,
-              timestamp: packet.timestamp,
-            },
-          });
-        }
-      } catch (e) {
-        // Ignore errors
-      }
-
-      // Schedule next iteration (~20ms for 50fps, matching Opus frame rate)
-      audioLoopRef.current = setTimeout(() => {
-        if (audioLoopRunningRef.current) {
-          loop();
-        }
-      }, 20);
+    const channel = new Channel<api.AudioPacket>();
+    channel.onmessage = (packet) => {
+      if (!audioLoopRunningRef.current || packet.data.length === 0) return;
+      peerService.broadcast({
+        type: "audio",
+        payload: {
+          data: packet.data,
+          timestamp: packet.timestamp,
+        },
+      });
     };
 
-    loop();
+    api.streamingSubscribeOutgoing(channel).catch(console.error);
     console.log("[Audio] Streaming loop started");
   }, []);
 
   const stopAudioLoop = useCallback(() => {
     audioLoopRunningRef.current = false;
-    if (audioLoopRef.current) {
-      clearTimeout(audioLoopRef.current);
-      audioLoopRef.current = null;
-    }
+    api.streamingUnsubscribeOutgoing().catch(console.error);
     console.log("[Audio] Streaming loop stopped");
   }, []);
 
@@ -234,7 +283,7 @@ export function ServerView() {
     let lastSpeakingState = false;
 
     const setupListener = async () => {
-      unlisten = await listen<AudioLevelEvent>("audio-level", (event) => {
+      unlisten = await listen<AudioLevelEvent>(EVENTS.audioLevel, (event) => {
         const isSpeaking = event.payload.is_speaking;
         setLocalSpeaking(isSpeaking);
 
@@ -315,6 +364,12 @@ export function ServerView() {
               content: payload.content,
               timestamp: payload.timestamp,
             });
+            api
+              .chatRecordMessage(serverInfo.code, payload.sender, payload.content, payload.timestamp, false)
+              .catch(console.error);
+            api
+              .notificationsNotify("chat_message", payload.sender, payload.content)
+              .catch(console.error);
           } else if (msg.type === "speaking") {
             const payload = msg.payload as { is_speaking: boolean };
             setSpeakingStates((prev) => ({
@@ -330,6 +385,9 @@ export function ServerView() {
             // Handle incoming screen frame from peer
             const payload = msg.payload as EncodedFrameData;
             handlePeerScreenFrame(peerId, payload);
+          } else if (msg.type === "screen-hints") {
+            // Handle presenter hints from the sharing peer
+            handlePeerScreenHints(peerId, msg.payload as PresenterHints);
           } else if (msg.type === "screen-state") {
             // Handle peer screen sharing state change
             const payload = msg.payload as { isSharing: boolean; username: string };
@@ -382,6 +440,265 @@ export function ServerView() {
     }
     storeDisconnect();
   };
+  // Listeners registered once call the handler of the latest render
+  const handleLeaveRef = useRef(handleLeave);
+  handleLeaveRef.current = handleLeave;
+
+  // Remote frames are only pushed to the backend while the PiP window is open
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<PipStateEvent>(EVENTS.pipStateChanged, (event) => {
+        pipOpenRef.current = event.payload.open;
+      });
+      pipOpenRef.current = await api.pipIsOpen();
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // Call recording: the summary arrives once the file is written, or the
+  // error if the writer stopped on its own
+  useEffect(() => {
+    let unlistenFinished: (() => void) | undefined;
+    let unlistenFailed: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlistenFinished = await listen<RecordingFinishedEvent>(EVENTS.recordingFinished, (event) => {
+        recordingRef.current = false;
+        setRecording(null);
+        const minutes = Math.round(event.payload.duration_ms / 60000);
+        toast.success(`Enregistrement terminé (${minutes} min) : ${event.payload.path}`);
+      });
+      unlistenFailed = await listen<RecordingFailedEvent>(EVENTS.recordingFailed, (event) => {
+        recordingRef.current = false;
+        setRecording(null);
+        toast.error(`Enregistrement interrompu : ${event.payload.error}`);
+      });
+      const status = await api.recordingStatus();
+      recordingRef.current = status.active;
+      setRecording(status.active ? status : null);
+    };
+
+    setupListener();
+
+    return () => {
+      unlistenFinished?.();
+      unlistenFailed?.();
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // A fullscreen game took the foreground: the call lowered its quality
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<GameModeChangedEvent>(EVENTS.gameModeChanged, (event) => {
+        toast.info(
+          event.payload.active
+            ? `Mode jeu activé${event.payload.app_name ? ` (${event.payload.app_name})` : ""} : qualité du partage réduite`
+            : "Mode jeu désactivé : qualité rétablie"
+        );
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // The capture pipeline muted the mic on its own
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<AutoMutedEvent>(EVENTS.autoMuted, (event) => {
+        toast.info(
+          event.payload.reason === "silence"
+            ? "Micro coupé après une longue période de silence"
+            : "Micro coupé : le périphérique est utilisé par une autre application"
+        );
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // A device failed to open and the backend is retrying
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<AudioDeviceRetryEvent>(EVENTS.audioDeviceRetry, (event) => {
+        toast.info(event.payload.message);
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // The selected device went away mid-call and the backend moved to the default one
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<AudioDeviceFallbackEvent>(EVENTS.audioDeviceFallback, (event) => {
+        const { stream, lost_device, fallback_device } = event.payload;
+        const label = stream === "capture" ? "Micro" : "Sortie audio";
+        const lost = lost_device ? ` « ${lost_device} »` : "";
+        if (fallback_device) {
+          toast.warning(`${label}${lost} déconnecté, bascule sur « ${fallback_device} »`);
+        } else {
+          toast.error(`${label}${lost} déconnecté et aucun périphérique par défaut disponible`);
+        }
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // "Se déconnecter" from the system tray
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<TrayActionEvent>(EVENTS.trayAction, (event) => {
+        if (event.payload.action === "disconnect") {
+          handleLeaveRef.current();
+        }
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // Public address forwarded by the router while hosting
+  useEffect(() => {
+    if (!isHost) return;
+
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<PortMappingChangedEvent>(EVENTS.portMappingChanged, (event) => {
+        setExternalAddress(event.payload.mapping?.external ?? null);
+      });
+      const status = await api.portMappingGetStatus();
+      setExternalAddress(status.mapping?.external ?? null);
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [isHost]);
+
+  // Link quality of each peer, scored by the backend
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<PeerQualityChangedEvent>(EVENTS.peerQualityChanged, (event) => {
+        setPeerQuality((prev) => ({ ...prev, [event.payload.peer_id]: event.payload }));
+      });
+      const current = await api.qualityGetPeers();
+      setPeerQuality((prev) => {
+        const next = { ...prev };
+        for (const quality of current) {
+          next[quality.peer_id] = next[quality.peer_id] ?? quality;
+        }
+        return next;
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // Call timer: elapsed time, warnings and the time limit
+  useEffect(() => {
+    const unlisteners: (() => void)[] = [];
+
+    const setupListeners = async () => {
+      unlisteners.push(
+        await listen<CallTimerTickEvent>(EVENTS.callTimerTick, (event) => {
+          setCallTimer(event.payload);
+        }),
+        await listen<CallTimerWarningEvent>(EVENTS.callTimerWarning, (event) => {
+          const { minutes, remaining_secs } = event.payload;
+          toast.warning(
+            remaining_secs !== null
+              ? `Appel en cours depuis ${minutes} min, fin dans ${formatDuration(remaining_secs)}`
+              : `Appel en cours depuis ${minutes} min`
+          );
+        }),
+        await listen<CallTimerExpiredEvent>(EVENTS.callTimerExpired, (event) => {
+          if (event.payload.action === "disconnect") {
+            toast.warning("Durée maximale atteinte : déconnexion");
+            handleLeaveRef.current();
+          } else {
+            // VoiceControls stops the share
+            toast.warning("Durée maximale atteinte : partage d'écran arrêté");
+          }
+        })
+      );
+    };
+
+    setupListeners();
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
 
   const copyCode = () => {
     if (serverInfo?.code) {
@@ -390,6 +707,47 @@ export function ServerView() {
     }
   };
 
+  const exportChat = async (format: api.ChatExportFormat) => {
+    if (!serverInfo) return;
+    try {
+      const path = await api.chatExport(serverInfo.code, format);
+      if (path) toast.success("Chat exporté");
+    } catch (err) {
+      console.error("Failed to export chat:", err);
+      toast.error("Impossible d'exporter le chat");
+    }
+  };
+
+  const startRecording = async () => {
+    try {
+      const status = await api.recordingStart(null, true);
+      recordingRef.current = true;
+      setRecording(status);
+      toast.info("Enregistrement de l'appel démarré");
+    } catch (err) {
+      console.error("Failed to start recording:", err);
+      toast.error("Impossible de démarrer l'enregistrement");
+    }
+  };
+
+  const toggleRecordingPause = async () => {
+    try {
+      setRecording(await (recording?.paused ? api.recordingResume() : api.recordingPause()));
+    } catch (err) {
+      console.error("Failed to pause recording:", err);
+    }
+  };
+
+  const stopRecording = async () => {
+    try {
+      // The summary toast comes with the recording-finished event
+      await api.recordingStop();
+    } catch (err) {
+      console.error("Failed to stop recording:", err);
+      toast.error("L'enregistrement n'a pas pu être finalisé");
+    }
+  };
+
   if (!serverInfo) return null;
 
   return (
@@ -419,6 +777,71 @@ export function ServerView() {
               </svg>
             </div>
           </button>
+
+          {/* Port forwarded by the router */}
+          {isHost && externalAddress && (
+            <div
+              className="mt-2 flex items-center justify-between px-1 text-[11px] text-dark-500"
+              title="Port ouvert sur le routeur (UPnP / NAT-PMP) : les invités te joignent directement"
+            >
+              <span>Adresse publique</span>
+              <code className="font-mono">{externalAddress}</code>
+            </div>
+          )}
+
+          {/* Call timer */}
+          {callTimer && (
+            <div className="mt-2 flex items-center justify-between px-1 text-[11px] text-dark-500">
+              <span>Durée de l'appel</span>
+              <span className="font-mono">
+                {formatDuration(callTimer.elapsed_secs)}
+                {callTimer.remaining_secs !== null && (
+                  <span className={callTimer.remaining_secs < 60 ? "text-red-400" : ""}>
+                    {" "}(reste {formatDuration(callTimer.remaining_secs)})
+                  </span>
+                )}
+              </span>
+            </div>
+          )}
+
+          {/* Chat export */}
+          <div className="mt-2 flex items-center justify-between px-1 text-[11px] text-dark-500">
+            <span>Exporter le chat</span>
+            <div className="flex gap-2">
+              <button onClick={() => exportChat("html")} className="hover:text-dark-300 transition">
+                HTML
+              </button>
+              <button onClick={() => exportChat("json")} className="hover:text-dark-300 transition">
+                JSON
+              </button>
+            </div>
+          </div>
+
+          {/* Call recording */}
+          <div className="mt-1 flex items-center justify-between px-1 text-[11px] text-dark-500">
+            <span className="flex items-center gap-1.5">
+              {recording && (
+                <span className={`w-1.5 h-1.5 rounded-full bg-red-500 ${recording.paused ? "" : "animate-pulse"}`} />
+              )}
+              {recording ? (recording.paused ? "Enregistrement en pause" : "Enregistrement") : "Enregistrer l'appel"}
+            </span>
+            <div className="flex gap-2">
+              {recording ? (
+                <>
+                  <button onClick={toggleRecordingPause} className="hover:text-dark-300 transition">
+                    {recording.paused ? "Reprendre" : "Pause"}
+                  </button>
+                  <button onClick={stopRecording} className="hover:text-dark-300 transition">
+                    Arrêter
+                  </button>
+                </>
+              ) : (
+                <button onClick={startRecording} className="hover:text-dark-300 transition">
+                  Démarrer
+                </button>
+              )}
+            </div>
+          </div>
         </div>
 
         {/* Status indicator */}
@@ -491,6 +914,7 @@ export function ServerView() {
                   <p className="text-[13px] text-white font-medium truncate flex-1">
                     {peer.username}
                   </p>
+                  <SignalBars quality={peerQuality[peer.id]} />
                 </li>
               );
             })}
@@ -543,8 +967,10 @@ export function ServerView() {
       {/* Remote Screen Share Viewer */}
       {remoteScreenShare && (
         <RemoteScreenViewer
+          peerId={remoteScreenShare.peerId}
           peerUsername={remoteScreenShare.peerUsername}
           frame={remoteScreenShare.frame}
+          hints={remoteScreenShare.hints}
           onClose={() => setRemoteScreenShare(null)}
         />
       )}
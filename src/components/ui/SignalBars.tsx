@@ -0,0 +1,41 @@
+import type { PeerQuality } from "../../services/tauriApi";
+
+interface SignalBarsProps {
+  quality: PeerQuality | undefined;
+}
+
+const BARS = { good: 3, fair: 2, poor: 1 } as const;
+
+const COLORS = {
+  good: "bg-green-400",
+  fair: "bg-yellow-400",
+  poor: "bg-red-400",
+} as const;
+
+function describe(quality: PeerQuality): string {
+  const parts = [];
+  if (quality.rtt_ms !== null) parts.push(`${Math.round(quality.rtt_ms)} ms`);
+  if (quality.loss_percent !== null) parts.push(`perte ${quality.loss_percent.toFixed(1)} %`);
+  if (quality.jitter_ms !== null) parts.push(`gigue ${Math.round(quality.jitter_ms)} ms`);
+  return parts.join(", ");
+}
+
+/** Quality of the link with a peer, as scored by the backend */
+export function SignalBars({ quality }: SignalBarsProps) {
+  if (!quality) return null;
+
+  const barCount = BARS[quality.quality];
+  return (
+    <div className="flex items-end gap-0.5 h-3" title={describe(quality)}>
+      {[1, 2, 3].map((bar) => (
+        <div
+          key={bar}
+          className={`w-0.5 rounded-sm transition-colors ${
+            bar <= barCount ? COLORS[quality.quality] : "bg-dark-600"
+          }`}
+          style={{ height: `${bar * 33}%` }}
+        />
+      ))}
+    </div>
+  );
+}
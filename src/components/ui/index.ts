@@ -1,3 +1,4 @@
 export { ToastContainer, type ToastData, type ToastType } from "./Toast";
 export { ConnectionIndicator } from "./ConnectionIndicator";
+export { SignalBars } from "./SignalBars";
 export { ThemeToggle } from "./ThemeToggle";
@@ -1,15 +1,16 @@
-import { useState, useEffect, forwardRef, useImperativeHandle } from "react";
+import { useState, useEffect, useRef, forwardRef, useImperativeHandle } from "react";
 import { listen } from "@tauri-apps/api/event";
 import * as api from "../../services/tauriApi";
 import { AudioLevelMeter } from "./AudioLevelMeter";
-import { ScreenShareButton } from "../screen/ScreenShareButton";
+import { ScreenShareButton, stopScreenSharing } from "../screen/ScreenShareButton";
 import type { CaptureSourceInfo } from "../../services/tauriApi";
-
-interface AudioLevelEvent {
-  level: number;
-  is_speaking: boolean;
-  rms: number;
-}
+import {
+  EVENTS,
+  type AudioLevelEvent,
+  type CallTimerExpiredEvent,
+  type TrayActionEvent,
+  type VoiceStateEvent,
+} from "../../types/events";
 
 interface VoiceControlsProps {
   isConnected: boolean;
@@ -38,6 +39,21 @@ export const VoiceControls = forwardRef<VoiceControlsRef, VoiceControlsProps>(
   const [noiseSuppressionEnabled, setNoiseSuppressionEnabled] = useState(true);
   const [isScreenSharing, setIsScreenSharing] = useState(false);
   const [_screenShareSource, setScreenShareSource] = useState<CaptureSourceInfo | null>(null);
+  // Read by the event listeners, registered once
+  const isScreenSharingRef = useRef(false);
+  isScreenSharingRef.current = isScreenSharing;
+
+  // Stop the share from outside its button
+  const stopScreenShare = async () => {
+    if (!isScreenSharingRef.current) return;
+    try {
+      await stopScreenSharing();
+      setIsScreenSharing(false);
+      setScreenShareSource(null);
+    } catch (e) {
+      console.error("Failed to stop sharing:", e);
+    }
+  };
 
   // Expose methods via ref for keyboard shortcuts
   useImperativeHandle(ref, () => ({
@@ -82,7 +98,7 @@ export const VoiceControls = forwardRef<VoiceControlsRef, VoiceControlsProps>(
     let unlisten: (() => void) | undefined;
 
     const setupListener = async () => {
-      unlisten = await listen<AudioLevelEvent>("audio-level", (event) => {
+      unlisten = await listen<AudioLevelEvent>(EVENTS.audioLevel, (event) => {
         setAudioLevel(event.payload.level);
         setIsSpeaking(event.payload.is_speaking);
       });
@@ -97,6 +113,69 @@ export const VoiceControls = forwardRef<VoiceControlsRef, VoiceControlsProps>(
     };
   }, []);
 
+  // Keep the mute button in sync with global hotkeys
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<VoiceStateEvent>(EVENTS.voiceStateChanged, (event) => {
+        setIsMuted(event.payload.muted);
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // "Arrêter le partage d'écran" from the system tray
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<TrayActionEvent>(EVENTS.trayAction, (event) => {
+        if (event.payload.action === "stop_screen_share") {
+          stopScreenShare();
+        }
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // Call time limit reached with "stop streaming" as its action
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<CallTimerExpiredEvent>(EVENTS.callTimerExpired, (event) => {
+        if (event.payload.action === "stop_streaming") {
+          stopScreenShare();
+        }
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   // Load audio devices and get currently selected device
   useEffect(() => {
     const loadDevices = async () => {
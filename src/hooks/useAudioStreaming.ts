@@ -1,13 +1,9 @@
 import { useEffect, useRef, useCallback, useState } from "react";
+import { Channel } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import * as api from "../services/tauriApi";
 import { peerService } from "../services/peerService";
-
-interface AudioLevelEvent {
-  level: number;
-  is_speaking: boolean;
-  rms: number;
-}
+import { EVENTS, type AudioLevelEvent } from "../types/events";
 
 interface UseAudioStreamingOptions {
   enabled: boolean;
@@ -21,7 +17,6 @@ export function useAudioStreaming({ enabled, onSpeakingChange }: UseAudioStreami
   const [peerLevels, setPeerLevels] = useState<Map<string, number>>(new Map());
   const [peerSpeaking, setPeerSpeaking] = useState<Map<string, boolean>>(new Map());
 
-  const audioLoopRef = useRef<number | null>(null);
   const isRunningRef = useRef(false);
 
   // Initialize the streaming service
@@ -40,10 +35,7 @@ export function useAudioStreaming({ enabled, onSpeakingChange }: UseAudioStreami
   // Cleanup
   const cleanup = useCallback(async () => {
     isRunningRef.current = false;
-    if (audioLoopRef.current) {
-      cancelAnimationFrame(audioLoopRef.current);
-      audioLoopRef.current = null;
-    }
+    api.streamingUnsubscribeOutgoing().catch(() => {});
 
     try {
       await api.streamingStopVoice();
@@ -54,50 +46,33 @@ export function useAudioStreaming({ enabled, onSpeakingChange }: UseAudioStreami
     }
   }, []);
 
-  // Audio send loop - polls for encoded packets and sends them
+  // Audio send loop - encoded packets are pushed by the backend as they're produced
   const startAudioLoop = useCallback(() => {
     if (isRunningRef.current) return;
     isRunningRef.current = true;
 
-    const loop = async () => {
-      if (!isRunningRef.current) return;
-
-      try {
-        // Get encoded audio packet from backend
-        const packet = await api.streamingGetOutgoingPacket();
-
-        if (packet && packet.data.length > 0) {
-          // Send to all peers via PeerJS data channel
-          peerService.broadcast({
-            type: "audio",
-            payload: {
-              data: packet.data,
-              timestamp: packet.timestamp,
-            },
-          });
-        }
-      } catch (e) {
-        // Ignore errors during normal operation
-      }
-
-      // Schedule next iteration (~20ms for 50fps, matching Opus frame rate)
-      audioLoopRef.current = window.setTimeout(() => {
-        if (isRunningRef.current) {
-          loop();
-        }
-      }, 20) as unknown as number;
+    const channel = new Channel<api.AudioPacket>();
+    channel.onmessage = (packet) => {
+      if (!isRunningRef.current || packet.data.length === 0) return;
+      // Send to all peers via PeerJS data channel
+      peerService.broadcast({
+        type: "audio",
+        payload: {
+          data: packet.data,
+          timestamp: packet.timestamp,
+        },
+      });
     };
 
-    loop();
+    api.streamingSubscribeOutgoing(channel).catch((e) => {
+      console.error("[AudioStreaming] Failed to subscribe to packets:", e);
+    });
   }, []);
 
   // Stop the audio loop
   const stopAudioLoop = useCallback(() => {
     isRunningRef.current = false;
-    if (audioLoopRef.current) {
-      clearTimeout(audioLoopRef.current);
-      audioLoopRef.current = null;
-    }
+    api.streamingUnsubscribeOutgoing().catch(() => {});
   }, []);
 
   // Handle incoming audio from peers
@@ -159,7 +134,7 @@ export function useAudioStreaming({ enabled, onSpeakingChange }: UseAudioStreami
     let unlisten: (() => void) | undefined;
 
     const setup = async () => {
-      unlisten = await listen<AudioLevelEvent>("audio-level", (event) => {
+      unlisten = await listen<AudioLevelEvent>(EVENTS.audioLevel, (event) => {
         setLocalLevel(event.payload.level);
         const speaking = event.payload.is_speaking;
         setLocalSpeaking(speaking);
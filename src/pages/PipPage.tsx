@@ -0,0 +1,91 @@
+import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import * as api from "../services/tauriApi";
+import {
+  EVENTS,
+  type ActiveSpeakerChangedEvent,
+  type PipFrameEvent,
+} from "../types/events";
+
+/**
+ * Standalone page for the picture-in-picture window.
+ * Shows the active speaker's screen share; the backend only sends their frames.
+ */
+export function PipPage() {
+  const [speaker, setSpeaker] = useState<string | null>(null);
+  const [speakerName, setSpeakerName] = useState<string | null>(null);
+  const [currentFrame, setCurrentFrame] = useState<PipFrameEvent | null>(null);
+
+  useEffect(() => {
+    const unlistenFns: Array<() => void> = [];
+
+    listen<PipFrameEvent>(EVENTS.pipFrame, (event) => {
+      setCurrentFrame(event.payload);
+    }).then((fn) => unlistenFns.push(fn));
+
+    listen<ActiveSpeakerChangedEvent>(EVENTS.activeSpeakerChanged, (event) => {
+      setSpeaker(event.payload.peer_id);
+    }).then((fn) => unlistenFns.push(fn));
+
+    api.streamingGetActiveSpeaker().then(setSpeaker).catch(console.error);
+
+    return () => {
+      unlistenFns.forEach((fn) => fn());
+    };
+  }, []);
+
+  // Name of the speaker, from the roster
+  useEffect(() => {
+    if (!speaker || speaker === "local") {
+      setSpeakerName(speaker ? "Vous" : null);
+      return;
+    }
+    api
+      .getServerInfo()
+      .then((info) => {
+        const peer = info?.peers.find((p) => p.id === speaker);
+        setSpeakerName(peer?.username ?? speaker.slice(0, 8));
+      })
+      .catch(() => setSpeakerName(speaker.slice(0, 8)));
+  }, [speaker]);
+
+  // Frames of the previous speaker are not shown
+  const frame = currentFrame && currentFrame.peer_id === speaker ? currentFrame : null;
+
+  return (
+    <div
+      data-tauri-drag-region
+      className="group h-screen w-screen relative bg-black flex items-center justify-center overflow-hidden select-none"
+    >
+      {frame ? (
+        <img
+          src={`data:image/jpeg;base64,${frame.data}`}
+          alt={speakerName ?? "Intervenant actif"}
+          className="max-w-full max-h-full object-contain pointer-events-none"
+          draggable={false}
+        />
+      ) : (
+        <p data-tauri-drag-region className="text-dark-400 text-sm">
+          {speakerName ? `${speakerName} parle` : "Personne ne parle"}
+        </p>
+      )}
+
+      {frame && speakerName && (
+        <span className="absolute bottom-1 left-1 px-1.5 py-0.5 rounded bg-black/60 text-white text-xs pointer-events-none">
+          {speakerName}
+        </span>
+      )}
+
+      <button
+        onClick={() => getCurrentWindow().close()}
+        className="absolute top-1 right-1 p-1 rounded bg-black/60 text-white opacity-0 group-hover:opacity-100 transition"
+        title="Fermer"
+      >
+        <svg className="w-3 h-3" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+          <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M6 18L18 6M6 6l12 12" />
+        </svg>
+      </button>
+    </div>
+  );
+}
@@ -0,0 +1,49 @@
+import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { EVENTS, type PresenterFrameEvent } from "../types/events";
+
+/**
+ * Standalone page for a presenter's pop-out window.
+ * Only this presenter's frames are sent to this window.
+ */
+export function ScreenPopoutPage() {
+  const [currentFrame, setCurrentFrame] = useState<PresenterFrameEvent | null>(null);
+
+  useEffect(() => {
+    let unlistenFn: (() => void) | undefined;
+
+    listen<PresenterFrameEvent>(EVENTS.presenterFrame, (event) => {
+      setCurrentFrame(event.payload);
+    }).then((fn) => {
+      unlistenFn = fn;
+    });
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
+  if (!currentFrame) {
+    return (
+      <div className="h-screen w-screen flex items-center justify-center bg-dark-900">
+        <div className="text-center">
+          <div className="w-16 h-16 border-4 border-primary-500 border-t-transparent rounded-full animate-spin mx-auto mb-4" />
+          <p className="text-dark-400">En attente du flux vidéo...</p>
+        </div>
+      </div>
+    );
+  }
+
+  return (
+    <div className="h-screen w-screen bg-black flex items-center justify-center">
+      <img
+        src={`data:image/jpeg;base64,${currentFrame.data}`}
+        alt="Partage d'écran"
+        className="max-w-full max-h-full object-contain"
+        draggable={false}
+      />
+    </div>
+  );
+}
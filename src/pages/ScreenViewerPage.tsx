@@ -3,6 +3,7 @@ import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import type { EncodedFrameData, StreamStats } from "../services/tauriApi";
 import * as api from "../services/tauriApi";
+import { EVENTS, type ScreenFrameEvent } from "../types/events";
 
 /**
  * Standalone page for the screen share viewer window.
@@ -21,9 +22,10 @@ export function ScreenViewerPage() {
     let checkInterval: ReturnType<typeof setInterval>;
 
     const setupListener = async () => {
-      unlistenFn = await listen<EncodedFrameData>("screen-frame", (event) => {
+      unlistenFn = await listen<ScreenFrameEvent>(EVENTS.screenFrame, (event) => {
         setCurrentFrame(event.payload);
         setIsStreaming(true);
+        api.screenStreamAckFrame(event.payload).catch(console.error);
       });
     };
 
@@ -170,6 +172,14 @@ export function ScreenViewerPage() {
               <span className="text-dark-400">Frames:</span>
               <span>{stats.frames_sent.toLocaleString()}</span>
             </div>
+            <div className="flex justify-between gap-6">
+              <span className="text-dark-400">Sautées:</span>
+              <span>{stats.frames_skipped.toLocaleString()}</span>
+            </div>
+            <div className="flex justify-between gap-6">
+              <span className="text-dark-400">Perdues:</span>
+              <span>{stats.frames_dropped.toLocaleString()}</span>
+            </div>
             <div className="flex justify-between gap-6">
               <span className="text-dark-400">Total:</span>
               <span>{formatBytes(stats.total_bytes)}</span>
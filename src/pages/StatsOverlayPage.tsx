@@ -0,0 +1,100 @@
+import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { EVENTS, type CallStatsEvent } from "../types/events";
+
+function formatKbps(kbps: number): string {
+  return kbps >= 1000 ? `${(kbps / 1000).toFixed(1)} Mb/s` : `${Math.round(kbps)} kb/s`;
+}
+
+/**
+ * Standalone page for the always-on-top stats overlay window.
+ * Fed by `call-stats` events sent to this window only.
+ */
+export function StatsOverlayPage() {
+  const [stats, setStats] = useState<CallStatsEvent | null>(null);
+
+  useEffect(() => {
+    let unlistenFn: (() => void) | undefined;
+
+    listen<CallStatsEvent>(EVENTS.callStats, (event) => {
+      setStats(event.payload);
+    }).then((fn) => {
+      unlistenFn = fn;
+    });
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
+  return (
+    <div className="h-screen w-screen bg-dark-900/90 text-white font-mono text-xs select-none overflow-hidden rounded-lg border border-dark-700">
+      <div
+        data-tauri-drag-region
+        className="flex items-center justify-between px-2 py-1 bg-dark-800 cursor-move"
+      >
+        <span data-tauri-drag-region className="text-dark-400">
+          Statistiques
+        </span>
+        <button
+          onClick={() => getCurrentWindow().close()}
+          className="text-dark-400 hover:text-white"
+          title="Fermer"
+        >
+          ✕
+        </button>
+      </div>
+
+      {!stats ? (
+        <div className="p-2 text-dark-400">En attente…</div>
+      ) : (
+        <div className="p-2 space-y-1">
+          <div className="flex justify-between">
+            <span className="text-dark-400">Envoi</span>
+            <span>{formatKbps(stats.send_kbps)}</span>
+          </div>
+          <div className="flex justify-between">
+            <span className="text-dark-400">Réception</span>
+            <span>{formatKbps(stats.recv_kbps)}</span>
+          </div>
+          <div className="flex justify-between">
+            <span className="text-dark-400">Latence</span>
+            <span>{stats.max_rtt_ms !== null ? `${Math.round(stats.max_rtt_ms)} ms` : "—"}</span>
+          </div>
+          {stats.screen_fps !== null && (
+            <div className="flex justify-between">
+              <span className="text-dark-400">Écran</span>
+              <span>
+                {stats.screen_fps.toFixed(0)} fps · {formatKbps(stats.screen_kbps ?? 0)}
+              </span>
+            </div>
+          )}
+          <div className="flex justify-between">
+            <span className="text-dark-400">Parle</span>
+            <span className="truncate ml-2">
+              {stats.speaking.length > 0
+                ? stats.speaking.map((id) => (id === "local" ? "Vous" : id.slice(0, 8))).join(", ")
+                : "—"}
+            </span>
+          </div>
+          {stats.peers.length > 0 && (
+            <div className="pt-1 border-t border-dark-700 space-y-0.5">
+              {stats.peers.map((peer) => (
+                <div key={peer.peer_id} className="flex justify-between text-dark-400">
+                  <span className="truncate">{peer.peer_id.slice(0, 8)}</span>
+                  <span>
+                    {peer.rtt_ms !== null ? `${Math.round(peer.rtt_ms)} ms` : "—"} ·{" "}
+                    {formatKbps(peer.send_kbps + peer.recv_kbps)}
+                  </span>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      )}
+    </div>
+  );
+}
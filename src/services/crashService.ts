@@ -0,0 +1,30 @@
+import { ask } from "@tauri-apps/plugin-dialog";
+import { relaunch } from "@tauri-apps/plugin-process";
+import { listen } from "@tauri-apps/api/event";
+import { EVENTS, type BackendCrashEvent } from "../types/events";
+
+let prompting = false;
+
+// Offer a restart when a backend task dies
+listen<BackendCrashEvent>(EVENTS.backendCrash, async (event) => {
+  const { thread, message, crash_file } = event.payload;
+  console.error(`Backend crash in ${thread}: ${message}`, crash_file);
+
+  // Several tasks can panic in a row, only ask once
+  if (prompting) return;
+  prompting = true;
+
+  const details = crash_file ? `\n\nRapport : ${crash_file}` : "";
+  const shouldRelaunch = await ask(
+    `Une erreur interne est survenue (${message}). Certaines fonctions (audio, partage d'écran) peuvent ne plus marcher. Redémarrer l'application ?${details}`,
+    {
+      title: "Erreur interne",
+      kind: "error",
+    }
+  );
+  prompting = false;
+
+  if (shouldRelaunch) {
+    await relaunch();
+  }
+});
@@ -1,37 +1,40 @@
+import { webrtcGetIceConfiguration } from "./tauriApi";
+
 export type MessageHandler = (peerId: string, data: unknown) => void;
 export type ConnectionHandler = (peerId: string, username: string) => void;
 export type DisconnectionHandler = (peerId: string) => void;
 
 interface PeerMessage {
-  type: "chat" | "audio" | "announce" | "ping" | "pong" | "speaking" | "screen" | "screen-state";
+  type:
+    | "chat"
+    | "audio"
+    | "announce"
+    | "ping"
+    | "pong"
+    | "speaking"
+    | "screen"
+    | "screen-state"
+    | "screen-hints";
   payload: unknown;
 }
 
 // Configuration du serveur WebSocket de signaling
 const SIGNALING_SERVER = "wss://cabochards.duckdns.org";
 
-// Configuration ICE avec serveurs STUN/TURN publics fiables
-const ICE_SERVERS: RTCIceServer[] = [
-  { urls: "stun:stun.l.google.com:19302" },
-  { urls: "stun:stun1.l.google.com:19302" },
-  { urls: "stun:stun2.l.google.com:19302" },
-  { urls: "stun:stun.cloudflare.com:3478" },
-  {
-    urls: "turn:openrelay.metered.ca:80",
-    username: "openrelayproject",
-    credential: "openrelayproject",
-  },
-  {
-    urls: "turn:openrelay.metered.ca:443",
-    username: "openrelayproject",
-    credential: "openrelayproject",
-  },
-  {
-    urls: "turn:openrelay.metered.ca:443?transport=tcp",
-    username: "openrelayproject",
-    credential: "openrelayproject",
-  },
-];
+// Serveurs STUN/TURN des réglages, les mêmes que pour les connexions natives.
+// Derrière un proxy en relais seul, ce sont les relais TURN en TCP/TLS uniquement.
+async function rtcConfiguration(): Promise<RTCConfiguration> {
+  const config = await webrtcGetIceConfiguration().catch(() => null);
+  if (!config) {
+    return { iceServers: [{ urls: "stun:stun.l.google.com:19302" }] };
+  }
+  const iceServers: RTCIceServer[] = config.servers.map((server) => ({
+    urls: server.urls,
+    username: server.username ?? undefined,
+    credential: server.credential ?? undefined,
+  }));
+  return { iceServers, iceTransportPolicy: config.relay_only ? "relay" : "all" };
+}
 
 export interface ConnectionQuality {
   latency: number;
@@ -256,7 +259,7 @@ class PeerService {
   private async createPeerConnection(peerId: string, username: string, initiator: boolean): Promise<void> {
     console.log(`[WebRTC] Création connexion avec ${peerId} (initiator: ${initiator})`);
 
-    const pc = new RTCPeerConnection({ iceServers: ICE_SERVERS });
+    const pc = new RTCPeerConnection(await rtcConfiguration());
     const peerConn: PeerConnection = { pc, dc: null, username };
     this.peerConnections.set(peerId, peerConn);
     this.pendingCandidates.set(peerId, []);
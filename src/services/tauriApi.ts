@@ -1,5 +1,95 @@
-import { invoke } from "@tauri-apps/api/core";
-import type { Room, ConnectionOffer, ServerConfig, ServerInfo } from "../types/room";
+import { invoke, Channel } from "@tauri-apps/api/core";
+import type {
+  Room,
+  ConnectionOffer,
+  ServerConfig,
+  ServerInfo,
+  InviteCode,
+} from "../types/room";
+
+// ============ ERRORS ============
+
+/** Error returned by every backend command */
+export interface AppError {
+  code: string;
+  /** Detailed backend error, for logs and bug reports */
+  message: string;
+  /** Description of the code in the locale set with `appSetLocale` */
+  localized: string;
+}
+
+export const isAppError = (e: unknown): e is AppError =>
+  typeof e === "object" && e !== null && "code" in e && "message" in e;
+
+/** Human-readable message for an error thrown by `invoke` */
+export const errorMessage = (e: unknown): string => {
+  if (isAppError(e)) return e.localized;
+  if (e instanceof Error) return e.message;
+  return String(e);
+};
+
+// ============ APP API ============
+
+export type AppFeature = "audio" | "screen" | "video";
+
+export interface PermissionRequirement {
+  name: "microphone" | "screen_recording";
+  /** The OS asks the user before granting access */
+  required: boolean;
+}
+
+export interface AppCapabilities {
+  version: string;
+  os: string;
+  arch: string;
+  features: AppFeature[];
+  audio_codecs: string[];
+  video_codecs: string[];
+  audio_backends: string[];
+  screen_capture_backends: string[];
+  permissions: PermissionRequirement[];
+}
+
+/** What this build can do on this platform (hide what it cannot) */
+export const appGetCapabilities = (): Promise<AppCapabilities> =>
+  invoke("app_get_capabilities");
+
+export type Locale = "fr" | "en";
+
+/** Language of backend strings (errors, menus, tray, notifications), e.g. "fr-FR" */
+export const appSetLocale = (locale: string): Promise<Locale> =>
+  invoke("app_set_locale", { locale });
+
+export const appGetLocale = (): Promise<Locale> => invoke("app_get_locale");
+
+// ============ UPDATER API ============
+
+export type UpdateChannel = "stable" | "beta";
+
+export interface UpdateAvailability {
+  /** Installable with `updaterInstall` */
+  available: boolean;
+  current_version: string;
+  version: string | null;
+  date: string | null;
+  notes: string | null;
+  channel: UpdateChannel;
+  /** A newer version exists but this install is not in its staged rollout yet */
+  deferred_by_rollout: boolean;
+}
+
+export const updaterGetChannel = (): Promise<UpdateChannel> =>
+  invoke("updater_get_channel");
+
+export const updaterSetChannel = (channel: UpdateChannel): Promise<void> =>
+  invoke("updater_set_channel", { channel });
+
+/** Check the manifest of the current channel */
+export const updaterCheckNow = (): Promise<UpdateAvailability> =>
+  invoke("updater_check_now");
+
+/** Download and install the update found by the last check (progress: `updateDownloadProgress`) */
+export const updaterInstall = (): Promise<void> => invoke("updater_install");
 
 // ============ SERVER API ============
 
@@ -20,8 +110,96 @@ export const disconnect = (): Promise<void> => invoke("disconnect");
 export const getServerInfo = (): Promise<ServerInfo | null> =>
   invoke("get_server_info");
 
+/** Code from an invite link opened before a username was known (taken once) */
+export const takePendingJoinCode = (): Promise<string | null> =>
+  invoke("take_pending_join_code");
+
 export const isConnected = (): Promise<boolean> => invoke("is_connected");
 
+export const isGuestMode = (): Promise<boolean> => invoke("is_guest_mode");
+
+export type SessionPhase =
+  | "idle"
+  | "signaling"
+  | "connecting"
+  | "in_call"
+  | "reconnecting"
+  | "ended";
+
+export const sessionGetState = (): Promise<SessionPhase> =>
+  invoke("session_get_state");
+
+export const serverCreateInvite = (
+  ttlSecs?: number,
+  maxUses?: number
+): Promise<InviteCode> => invoke("server_create_invite", { ttlSecs, maxUses });
+
+export const serverListInvites = (): Promise<InviteCode[]> =>
+  invoke("server_list_invites");
+
+export const serverRevokeInvite = (code: string): Promise<void> =>
+  invoke("server_revoke_invite", { code });
+
+// ============ PORT MAPPING API ============
+
+export interface PortMappingSettings {
+  enabled: boolean;
+  /** UDP port of the ICE traffic while hosting (0: any free port) */
+  port: number;
+}
+
+export interface PortMapping {
+  method: "upnp" | "nat_pmp";
+  local_port: number;
+  /** "ip:port" peers can reach the host on */
+  external: string;
+}
+
+export interface PortMappingStatus {
+  local_port: number | null;
+  mapping: PortMapping | null;
+  /** Why the router didn't forward the port */
+  error: string | null;
+}
+
+export const portMappingGetSettings = (): Promise<PortMappingSettings> =>
+  invoke("port_mapping_get_settings");
+
+/** Applied the next time we host */
+export const portMappingSetSettings = (settings: PortMappingSettings): Promise<void> =>
+  invoke("port_mapping_set_settings", { settings });
+
+export const portMappingGetStatus = (): Promise<PortMappingStatus> =>
+  invoke("port_mapping_get_status");
+
+// ============ PROXY API ============
+
+export interface ProxyProfile {
+  name: string;
+  kind: "http" | "socks5";
+  host: string;
+  port: number;
+  /** Only reach peers through TURN over TCP/TLS (UDP doesn't cross the proxy) */
+  relay_only: boolean;
+}
+
+export interface ProxySettings {
+  /** Name of the profile in use (null: direct connection) */
+  active: string | null;
+  profiles: ProxyProfile[];
+}
+
+export const proxyGetSettings = (): Promise<ProxySettings> =>
+  invoke("proxy_get_settings");
+
+/** Applied at the next launch */
+export const proxySetSettings = (settings: ProxySettings): Promise<void> =>
+  invoke("proxy_set_settings", { settings });
+
+/** Proxy this launch goes through (null: direct connection) */
+export const proxyGetActive = (): Promise<ProxyProfile | null> =>
+  invoke("proxy_get_active");
+
 // ============ ROOM API (legacy) ============
 
 // Room Management
@@ -35,6 +213,74 @@ export const leaveRoom = (): Promise<void> => invoke("leave_room");
 
 export const getRoomInfo = (): Promise<Room | null> => invoke("get_room_info");
 
+/** Patience of peer setup, in milliseconds (applies to new connections) */
+export interface ConnectionTimeouts {
+  /** Longest wait for ICE candidates before sending an offer or answer (500-30000) */
+  gathering_ms: number;
+  /** Longest a peer may take to connect (5000-120000), or stay unreachable (25000 at the least) */
+  connection_ms: number;
+  /** Interval of ICE keepalives, at most half of connection_ms (500-30000) */
+  keepalive_ms: number;
+}
+
+export const webrtcGetConnectionTimeouts = (): Promise<ConnectionTimeouts> =>
+  invoke("webrtc_get_connection_timeouts");
+
+export const webrtcSetConnectionTimeouts = (timeouts: ConnectionTimeouts): Promise<void> =>
+  invoke("webrtc_set_connection_timeouts", { timeouts });
+
+/** Which IP family ICE candidates use */
+export type IpFamily = "any" | "prefer_ipv6" | "require_ipv6";
+
+/** Addresses ICE gathers candidates on (applies to new connections) */
+export interface NetworkSettings {
+  /** prefer_ipv6 keeps IPv4 but tries IPv6 pairs first (given a routable IPv6 address) */
+  ip_family: IpFamily;
+  /** Only use this interface, e.g. a VPN or the LAN adapter (null: every interface) */
+  interface: string | null;
+}
+
+export interface NetworkInterface {
+  name: string;
+  addresses: string[];
+}
+
+export const webrtcGetNetworkSettings = (): Promise<NetworkSettings> =>
+  invoke("webrtc_get_network_settings");
+
+export const webrtcSetNetworkSettings = (settings: NetworkSettings): Promise<void> =>
+  invoke("webrtc_set_network_settings", { settings });
+
+export const webrtcListNetworkInterfaces = (): Promise<NetworkInterface[]> =>
+  invoke("webrtc_list_network_interfaces");
+
+/** A STUN or TURN server (TURN needs a username and a credential) */
+export interface IceServer {
+  urls: string[];
+  username: string | null;
+  credential: string | null;
+}
+
+export interface IceServerSettings {
+  servers: IceServer[];
+}
+
+/** Servers and relay policy new connections use */
+export interface IceConfiguration {
+  servers: IceServer[];
+  /** The active proxy only lets TURN over TCP/TLS through */
+  relay_only: boolean;
+}
+
+export const webrtcGetIceServers = (): Promise<IceServerSettings> =>
+  invoke("webrtc_get_ice_servers");
+
+export const webrtcSetIceServers = (settings: IceServerSettings): Promise<void> =>
+  invoke("webrtc_set_ice_servers", { settings });
+
+export const webrtcGetIceConfiguration = (): Promise<IceConfiguration> =>
+  invoke("webrtc_get_ice_configuration");
+
 // WebRTC (Single Peer - backward compatible)
 export const createWebRTCOffer = (username: string): Promise<ConnectionOffer> =>
   invoke("create_webrtc_offer", { username });
@@ -70,9 +316,10 @@ export const meshCreateOffer = (
 export const meshAcceptOffer = (
   peerId: string,
   peerUsername: string,
-  offerBase64: string
+  offerBase64: string,
+  joinCode?: string
 ): Promise<ConnectionOffer> =>
-  invoke("mesh_accept_offer", { peerId, peerUsername, offerBase64 });
+  invoke("mesh_accept_offer", { peerId, peerUsername, offerBase64, joinCode });
 
 export const meshAcceptAnswer = (
   peerId: string,
@@ -101,6 +348,14 @@ export const meshAnnouncePeer = (peerUsername: string): Promise<void> =>
 
 export const audioInit = (): Promise<void> => invoke("audio_init");
 
+export type MicPermission = "granted" | "denied" | "restricted" | "not_determined";
+
+export const audioCheckPermission = (): Promise<MicPermission> =>
+  invoke("audio_check_permission");
+
+export const audioRequestPermission = (): Promise<MicPermission> =>
+  invoke("audio_request_permission");
+
 export const audioStartVoice = (): Promise<void> => invoke("audio_start_voice");
 
 export const audioStopVoice = (): Promise<void> => invoke("audio_stop_voice");
@@ -160,6 +415,80 @@ export const audioSetNoiseSuppression = (enabled: boolean): Promise<void> =>
 export const audioIsNoiseSuppressionEnabled = (): Promise<boolean> =>
   invoke("audio_is_noise_suppression_enabled");
 
+/** Noise suppression backend ("speexdsp" only in builds with that feature) */
+export type DenoiseBackend = "nnnoiseless" | "speexdsp" | "passthrough";
+
+export const audioSetDenoiser = (backend: DenoiseBackend): Promise<void> =>
+  invoke("audio_set_denoiser", { backend });
+
+export const audioGetDenoiser = (): Promise<DenoiseBackend> => invoke("audio_get_denoiser");
+
+/**
+ * Use a custom trained RNNoise model for the nnnoiseless backend, or the
+ * built-in one with null. Rejects files that are not RNNoise models.
+ */
+export const audioSetDenoiseModel = (path: string | null): Promise<void> =>
+  invoke("audio_set_denoise_model", { path });
+
+export const audioGetDenoiseModel = (): Promise<string | null> =>
+  invoke("audio_get_denoise_model");
+
+/** Backends compiled into this build */
+export const audioListDenoisers = (): Promise<DenoiseBackend[]> =>
+  invoke("audio_list_denoisers");
+
+export type EqPreset = "flat" | "voice_clarity" | "bass_boost";
+
+export interface EqSettings {
+  enabled: boolean;
+  /** Gain of each band in dB (-12 to 12): bass, low mids, mids, presence, treble */
+  gains_db: [number, number, number, number, number];
+}
+
+/** Set the output equalizer (saved in the settings) */
+export const audioSetEqualizer = (settings: EqSettings): Promise<void> =>
+  invoke("audio_set_equalizer", { settings });
+
+/** Apply an equalizer preset, resolves to its settings */
+export const audioSetEqualizerPreset = (preset: EqPreset): Promise<EqSettings> =>
+  invoke("audio_set_equalizer_preset", { preset });
+
+export const audioGetEqualizer = (): Promise<EqSettings> => invoke("audio_get_equalizer");
+
+export interface DynamicsSettings {
+  enabled: boolean;
+  /** dBFS, -60 to 0 */
+  threshold_db: number;
+  /** 1 to 20 */
+  ratio: number;
+  /** dB, 0 to 24 */
+  makeup_db: number;
+  attack_ms: number;
+  release_ms: number;
+}
+
+/** Set the output compressor; the -1 dBFS limiter after it is always on */
+export const audioSetDynamics = (settings: DynamicsSettings): Promise<void> =>
+  invoke("audio_set_dynamics", { settings });
+
+export const audioGetDynamics = (): Promise<DynamicsSettings> => invoke("audio_get_dynamics");
+
+export interface EncoderOptions {
+  /** 0 (cheapest) to 10 (best quality) */
+  complexity: number;
+  /** Expected packet loss in percent (tunes FEC redundancy) */
+  packet_loss_perc: number;
+  inband_fec: boolean;
+  /** Follow the loss peers measure; the two fields above only apply until then */
+  adaptive_fec: boolean;
+}
+
+export const audioSetEncoderOptions = (options: EncoderOptions): Promise<void> =>
+  invoke("audio_set_encoder_options", { options });
+
+export const audioGetEncoderOptions = (): Promise<EncoderOptions> =>
+  invoke("audio_get_encoder_options");
+
 // ============ AUDIO MESH API (WebRTC Audio Streaming) ============
 
 export const audioMeshInit = (username: string): Promise<void> =>
@@ -173,21 +502,26 @@ export const audioMeshIsAudioEnabled = (): Promise<boolean> =>
 
 export const audioMeshCreateOffer = (
   peerId: string,
-  peerUsername: string
+  peerUsername: string,
+  joinCode?: string,
+  roomCode?: string
 ): Promise<ConnectionOffer> =>
-  invoke("audio_mesh_create_offer", { peerId, peerUsername });
+  invoke("audio_mesh_create_offer", { peerId, peerUsername, joinCode, roomCode });
 
 export const audioMeshAcceptOffer = (
   peerId: string,
   peerUsername: string,
-  offerBase64: string
+  offerBase64: string,
+  joinCode?: string,
+  roomCode?: string
 ): Promise<ConnectionOffer> =>
-  invoke("audio_mesh_accept_offer", { peerId, peerUsername, offerBase64 });
+  invoke("audio_mesh_accept_offer", { peerId, peerUsername, offerBase64, joinCode, roomCode });
 
 export const audioMeshAcceptAnswer = (
   peerId: string,
-  answerBase64: string
-): Promise<void> => invoke("audio_mesh_accept_answer", { peerId, answerBase64 });
+  answerBase64: string,
+  roomCode?: string
+): Promise<void> => invoke("audio_mesh_accept_answer", { peerId, answerBase64, roomCode });
 
 export const audioMeshBroadcastAudio = (opusData: number[]): Promise<void> =>
   invoke("audio_mesh_broadcast_audio", { opusData });
@@ -215,12 +549,376 @@ export const audioMeshRemovePeer = (peerId: string): Promise<void> =>
 export const audioMeshCloseAll = (): Promise<void> =>
   invoke("audio_mesh_close_all");
 
+export interface RoomStatus {
+  code: string;
+  peer_count: number;
+  is_talk_room: boolean;
+  is_listen_room: boolean;
+}
+
+export const audioMeshJoinRoom = (roomCode: string, username: string): Promise<void> =>
+  invoke("audio_mesh_join_room", { roomCode, username });
+
+export const audioMeshLeaveRoom = (roomCode: string): Promise<void> =>
+  invoke("audio_mesh_leave_room", { roomCode });
+
+export const audioMeshListRooms = (): Promise<RoomStatus[]> =>
+  invoke("audio_mesh_list_rooms");
+
+export const audioMeshSetTalkRoom = (roomCode: string): Promise<void> =>
+  invoke("audio_mesh_set_talk_room", { roomCode });
+
+export const audioMeshSetListenRoom = (roomCode: string): Promise<void> =>
+  invoke("audio_mesh_set_listen_room", { roomCode });
+
+export interface VoiceChannelInfo {
+  name: string;
+  members: string[];
+}
+
+export const audioMeshJoinChannel = (channel: string, roomCode?: string): Promise<void> =>
+  invoke("audio_mesh_join_channel", { channel, roomCode });
+
+export const audioMeshGetChannels = (roomCode?: string): Promise<VoiceChannelInfo[]> =>
+  invoke("audio_mesh_get_channels", { roomCode });
+
+export interface AudioPolicy {
+  force_push_to_talk: boolean;
+  max_bitrate: number | null;
+  require_noise_suppression: boolean;
+}
+
+export const audioMeshSetRoomPolicy = (policy: AudioPolicy): Promise<void> =>
+  invoke("audio_mesh_set_room_policy", { policy });
+
+export const audioMeshGetRoomPolicy = (): Promise<AudioPolicy | null> =>
+  invoke("audio_mesh_get_room_policy");
+
 export const audioMeshCalculateLevel = (samples: number[]): Promise<number> =>
   invoke("audio_mesh_calculate_level", { samples });
 
 export const audioMeshIsSpeaking = (samples: number[]): Promise<boolean> =>
   invoke("audio_mesh_is_speaking", { samples });
 
+// ============ BAN LIST API ============
+
+export interface BanEntry {
+  fingerprint: string;
+  username: string;
+  reason: string | null;
+  banned_at: number;
+}
+
+export const banPeer = (peerId: string, reason?: string): Promise<BanEntry> =>
+  invoke("ban_peer", { peerId, reason });
+
+export const banList = (): Promise<BanEntry[]> => invoke("ban_list");
+
+export const banRemove = (fingerprint: string): Promise<void> =>
+  invoke("ban_remove", { fingerprint });
+
+export const kickPeer = (peerId: string, reason?: string): Promise<void> =>
+  invoke("kick_peer", { peerId, reason });
+
+// ============ ROOM EVENT LOG API ============
+
+export type RoomEventKind = "join" | "leave" | "kick" | "ban" | "host_migration";
+
+export interface RoomEvent {
+  kind: RoomEventKind;
+  room_code: string;
+  peer_id: string;
+  username: string;
+  detail: string | null;
+  timestamp: number;
+}
+
+export const roomGetEventLog = (limit?: number): Promise<RoomEvent[]> =>
+  invoke("room_get_event_log", { limit });
+
+export const roomClearEventLog = (): Promise<void> => invoke("room_clear_event_log");
+
+export const roomRecordHostMigration = (
+  previousHostId: string,
+  previousHostUsername: string
+): Promise<void> =>
+  invoke("room_record_host_migration", { previousHostId, previousHostUsername });
+
+// ============ PRESENCE API ============
+
+export interface AfkSettings {
+  enabled: boolean;
+  idle_minutes: number;
+  auto_mute: boolean;
+}
+
+export const presenceGetAfkSettings = (): Promise<AfkSettings> =>
+  invoke("presence_get_afk_settings");
+
+export const presenceSetAfkSettings = (settings: AfkSettings): Promise<void> =>
+  invoke("presence_set_afk_settings", { settings });
+
+export const presenceIsAway = (): Promise<boolean> => invoke("presence_is_away");
+
+// ============ CALL TIMER API ============
+
+export type CallTimerLimitAction = "stop_streaming" | "disconnect";
+
+export interface CallTimerSettings {
+  enabled: boolean;
+  /** Warn when the call reaches these durations, in minutes */
+  warn_at_minutes: number[];
+  /** null: no time limit */
+  limit_minutes: number | null;
+  limit_action: CallTimerLimitAction;
+}
+
+export interface CallTimerStatus {
+  running: boolean;
+  elapsed_secs: number;
+  remaining_secs: number | null;
+}
+
+export const callTimerGetSettings = (): Promise<CallTimerSettings> =>
+  invoke("call_timer_get_settings");
+
+export const callTimerSetSettings = (settings: CallTimerSettings): Promise<CallTimerStatus> =>
+  invoke("call_timer_set_settings", { settings });
+
+export const callTimerGetStatus = (): Promise<CallTimerStatus> =>
+  invoke("call_timer_get_status");
+
+// ============ HOTKEYS API ============
+
+// Accelerator syntax, e.g. "CmdOrCtrl+Shift+M" (null = disabled)
+export interface HotkeySettings {
+  toggle_mute: string | null;
+  toggle_deafen: string | null;
+  push_to_talk: string | null;
+  push_to_talk_enabled: boolean;
+}
+
+export const hotkeysGetSettings = (): Promise<HotkeySettings> =>
+  invoke("hotkeys_get_settings");
+
+export const hotkeysSetSettings = (settings: HotkeySettings): Promise<void> =>
+  invoke("hotkeys_set_settings", { settings });
+
+// ============ HEADSET API ============
+
+export interface HeadsetSettings {
+  /** Toggle mute from the headset mute/call buttons */
+  enabled: boolean;
+}
+
+export const headsetGetSettings = (): Promise<HeadsetSettings> =>
+  invoke("headset_get_settings");
+
+export const headsetSetSettings = (settings: HeadsetSettings): Promise<void> =>
+  invoke("headset_set_settings", { settings });
+
+/** Names of the headsets whose buttons are followed */
+export const headsetListDevices = (): Promise<string[]> =>
+  invoke("headset_list_devices");
+
+// ============ NOTIFICATIONS API ============
+
+export type NotificationKind = "chat_message" | "join_request" | "file_offer";
+
+export interface NotificationSettings {
+  enabled: boolean;
+  do_not_disturb: boolean;
+  chat_messages: boolean;
+  join_requests: boolean;
+  file_offers: boolean;
+}
+
+export const notificationsGetSettings = (): Promise<NotificationSettings> =>
+  invoke("notifications_get_settings");
+
+export const notificationsSetSettings = (settings: NotificationSettings): Promise<void> =>
+  invoke("notifications_set_settings", { settings });
+
+// Shown only if the window is unfocused and the settings allow it
+export const notificationsNotify = (
+  kind: NotificationKind,
+  title: string,
+  body: string
+): Promise<void> => invoke("notifications_notify", { kind, title, body });
+
+// ============ CHAT READ ALOUD API ============
+
+export interface TtsSettings {
+  enabled: boolean;
+  /** Speech rate, 0.5 to 2 (1 is the normal pace) */
+  rate: number;
+  /** 0 to 1 */
+  volume: number;
+  /** Per-sender choice, senders not listed are read */
+  senders: Record<string, boolean>;
+}
+
+export const ttsGetSettings = (): Promise<TtsSettings> =>
+  invoke("tts_get_settings");
+
+export const ttsSetSettings = (settings: TtsSettings): Promise<void> =>
+  invoke("tts_set_settings", { settings });
+
+export const ttsSetSenderEnabled = (sender: string, enabled: boolean): Promise<void> =>
+  invoke("tts_set_sender_enabled", { sender, enabled });
+
+// ============ SOUND SCHEME API ============
+
+export type SoundEvent = "peer_joined" | "peer_left" | "muted" | "unmuted" | "chat_message";
+
+export type Sound =
+  | { type: "silent" }
+  /** Sine notes played one after the other (1 to 4 notes, 100-4000 Hz, 30-500 ms each) */
+  | { type: "tone"; notes: number[]; note_ms: number }
+  /** 16-bit PCM WAV file, cut after 5 s */
+  | { type: "file"; path: string };
+
+export interface SoundScheme {
+  enabled: boolean;
+  /** 0 to 1 */
+  volume: number;
+  /** Events not listed play their built-in cue */
+  sounds: Partial<Record<SoundEvent, Sound>>;
+}
+
+export const soundSchemeGet = (): Promise<SoundScheme> =>
+  invoke("sound_scheme_get");
+
+export const soundSchemeSet = (scheme: SoundScheme): Promise<void> =>
+  invoke("sound_scheme_set", { scheme });
+
+export const soundSchemeSetSound = (event: SoundEvent, sound: Sound): Promise<void> =>
+  invoke("sound_scheme_set_sound", { event, sound });
+
+/** Play the cue of an event now, even with the scheme off */
+export const soundSchemePreview = (event: SoundEvent): Promise<void> =>
+  invoke("sound_scheme_preview", { event });
+
+// ============ DIAGNOSTICS API ============
+
+export const diagnosticsGetLogLevel = (): Promise<string> =>
+  invoke("diagnostics_get_log_level");
+
+export const diagnosticsSetLogLevel = (level: string): Promise<void> =>
+  invoke("diagnostics_set_log_level", { level });
+
+/** Zip recent logs; returns the archive path (downloads folder by default) */
+export const diagnosticsExportLogs = (destination?: string): Promise<string> =>
+  invoke("diagnostics_export_logs", { destination });
+
+export interface IceServerCheck {
+  url: string;
+  reachable: boolean;
+  host_candidates: number;
+  srflx_candidates: number;
+  relay_candidates: number;
+  gathering_ms: number;
+  timed_out: boolean;
+  error: string | null;
+}
+
+export interface AudioDeviceReport {
+  name: string;
+  is_default: boolean;
+  sample_rate: number | null;
+  channels: number | null;
+  sample_format: string | null;
+  error: string | null;
+}
+
+export interface DiagnosticsReport {
+  generated_at: number;
+  system: {
+    os: string;
+    arch: string;
+    app_version: string;
+    tauri_version: string;
+    guest_mode: boolean;
+    log_level: string;
+  };
+  audio: {
+    host: string;
+    inputs: AudioDeviceReport[];
+    outputs: AudioDeviceReport[];
+    error: string | null;
+  };
+  screen_permission: boolean;
+  ice: IceServerCheck[];
+  session: {
+    server: ServerInfo | null;
+    rooms: RoomStatus[];
+    is_capturing: boolean;
+    is_playing: boolean;
+    is_muted: boolean;
+    input_device: string | null;
+    audio_send: AudioSendStats;
+    screen_stream: StreamStats;
+  };
+}
+
+/** Gather a support report (takes a few seconds: runs an ICE check) */
+export const diagnosticsRun = (): Promise<DiagnosticsReport> =>
+  invoke("diagnostics_run");
+
+export interface SelfTestCheck {
+  name: "audio_input" | "audio_output" | "webrtc" | "config_dir";
+  status: "passed" | "failed" | "skipped";
+  detail: string | null;
+  duration_ms: number;
+}
+
+export interface SelfTestReport {
+  /** No check failed (skipped ones don't count) */
+  passed: boolean;
+  checks: SelfTestCheck[];
+}
+
+/** Health checks for the first-run screen (opens the default audio devices briefly) */
+export const selfTest = (): Promise<SelfTestReport> => invoke("self_test");
+
+export interface ComponentUsage {
+  name: "capture" | "denoise" | "encode" | "screen_encode" | "webrtc";
+  /** Share of one core since the previous call, in percent */
+  cpu_percent: number;
+  /** Total busy time since launch */
+  busy_ms: number;
+  calls: number;
+}
+
+export interface PerfUsage {
+  /** Window cpu_percent is measured over */
+  window_ms: number;
+  components: ComponentUsage[];
+  /** Process resident memory, null when the platform doesn't report it */
+  resident_memory_bytes: number | null;
+}
+
+/** CPU used by each subsystem since the previous call (poll it at a fixed rate) */
+export const perfGetUsage = (): Promise<PerfUsage> => invoke("perf_get_usage");
+
+/** Simulated network conditions, applied to outgoing audio/video packets */
+export interface NetSimConfig {
+  enabled: boolean;
+  /** 0-100 */
+  loss_percent: number;
+  latency_ms: number;
+  /** Random extra delay up to this (max 5000 ms, like the latency) */
+  jitter_ms: number;
+  /** Same seed and settings drop and delay the same packets */
+  seed: number;
+}
+
+/** Debug builds only: these commands don't exist in release builds */
+export const netsimGetConfig = (): Promise<NetSimConfig> => invoke("netsim_get_config");
+
+export const netsimSetConfig = (config: NetSimConfig): Promise<void> =>
+  invoke("netsim_set_config", { config });
+
 // ============ SCREEN CAPTURE API ============
 
 export interface MonitorInfo {
@@ -308,12 +1006,18 @@ export interface EncodedFrameData {
   is_keyframe: boolean;
   frame_number: number;
   timestamp: number;
+  /** Local stream the frame belongs to (absent for frames of other peers) */
+  stream_id?: number;
 }
 
 export interface StreamStats {
   is_streaming: boolean;
   fps: number;
   frames_sent: number;
+  /** Frames skipped to hold the frame rate when capture/encoding runs behind */
+  frames_skipped: number;
+  /** Frames not emitted because the webview had not handled the previous ones */
+  frames_dropped: number;
   total_bytes: number;
   avg_frame_size: number;
 }
@@ -336,6 +1040,451 @@ export const screenStreamGetCurrentFrame = (): Promise<EncodedFrameData | null>
 export const screenStreamSetFps = (fps: number): Promise<void> =>
   invoke("screen_stream_set_fps", { fps });
 
+/**
+ * Tell the backend this window handled a "screen-frame" event
+ * Past a few frames unacknowledged, the next ones are dropped instead of piling up.
+ */
+export const screenStreamAckFrame = (frame: EncodedFrameData): Promise<void> =>
+  invoke("screen_stream_ack_frame", {
+    streamId: frame.stream_id ?? 0,
+    frameNumber: frame.frame_number,
+  });
+
+/** Hold the frames (viewers keep the last one) or resume */
+export const screenStreamSetPaused = (paused: boolean): Promise<void> =>
+  invoke("screen_stream_set_paused", { paused });
+
+/** Pointer position over the shared source, 0 to 1 from the top-left corner */
+export interface PointerPosition {
+  x: number;
+  y: number;
+}
+
+/** Context of a screen share, sent to viewers next to the frames */
+export interface PresenterHints {
+  source_name: string;
+  width: number;
+  height: number;
+  paused: boolean;
+  /** null when the pointer is outside the shared source */
+  pointer: PointerPosition | null;
+}
+
+// ============ GAME MODE API ============
+
+export interface GameModeStatus {
+  enabled: boolean;
+  /** A fullscreen app has the focus: lower screen share rate, deeper audio buffer */
+  active: boolean;
+  app_name: string | null;
+}
+
+export const gameModeGetStatus = (): Promise<GameModeStatus> =>
+  invoke("game_mode_get_status");
+
+export const gameModeSetEnabled = (enabled: boolean): Promise<GameModeStatus> =>
+  invoke("game_mode_set_enabled", { enabled });
+
+// ============ SCREEN POP-OUT API ============
+
+/** Open a presenter's screen share in its own window (fed by `screenPopoutPushFrame`) */
+export const screenPopoutOpen = (presenterId: string, presenterName: string): Promise<void> =>
+  invoke("screen_popout_open", { presenterId, presenterName });
+
+/** Send a received frame to the presenter's pop-out; false once that window is closed */
+export const screenPopoutPushFrame = (
+  presenterId: string,
+  frame: EncodedFrameData
+): Promise<boolean> =>
+  invoke("screen_popout_push_frame", { presenterId, frame });
+
+export const screenPopoutClose = (presenterId: string): Promise<void> =>
+  invoke("screen_popout_close", { presenterId });
+
+// ============ CALL RECORDING API ============
+
+export interface RecordingStatus {
+  active: boolean;
+  paused: boolean;
+  path: string | null;
+  /** Recorded time, pauses excluded */
+  duration_ms: number;
+  /** Why the recording stopped on its own */
+  error: string | null;
+}
+
+export interface RecordingSummary {
+  path: string;
+  duration_ms: number;
+  paused_ms: number;
+  size_bytes: number;
+  screen_frames: number;
+}
+
+/** Record the call to an MKV file (videos folder when no destination), with the screen share if `screen` */
+export const recordingStart = (destination: string | null, screen: boolean): Promise<RecordingStatus> =>
+  invoke("recording_start", { destination, screen });
+
+export const recordingPause = (): Promise<RecordingStatus> =>
+  invoke("recording_pause");
+
+export const recordingResume = (): Promise<RecordingStatus> =>
+  invoke("recording_resume");
+
+/** Finish the file (null if nothing was being recorded); a `recording-finished` event follows */
+export const recordingStop = (): Promise<RecordingSummary | null> =>
+  invoke("recording_stop");
+
+export const recordingStatus = (): Promise<RecordingStatus> =>
+  invoke("recording_status");
+
+/** Push a received frame; recorded while its presenter holds the screen track */
+export const recordingPushFrame = (presenterId: string, frame: EncodedFrameData): Promise<void> =>
+  invoke("recording_push_frame", { presenterId, frame });
+
+// ============ PICTURE-IN-PICTURE API ============
+
+/** Open the small always-on-top window showing the active speaker's share */
+export const pipOpen = (): Promise<void> =>
+  invoke("pip_open");
+
+export const pipClose = (): Promise<void> =>
+  invoke("pip_close");
+
+export const pipIsOpen = (): Promise<boolean> =>
+  invoke("pip_is_open");
+
+/** Open the PiP window when the main window is minimized, close it on restore */
+export const pipSetAuto = (enabled: boolean): Promise<void> =>
+  invoke("pip_set_auto", { enabled });
+
+export const pipGetAuto = (): Promise<boolean> =>
+  invoke("pip_get_auto");
+
+/** Push a received frame; only shown if its presenter holds the floor */
+export const pipPushFrame = (presenterId: string, frame: EncodedFrameData): Promise<void> =>
+  invoke("pip_push_frame", { presenterId, frame });
+
+// ============ STATS OVERLAY API ============
+
+/** Open the always-on-top stats window (fed by `call-stats` events) */
+export const statsOverlayOpen = (): Promise<void> =>
+  invoke("stats_overlay_open");
+
+export const statsOverlayClose = (): Promise<void> =>
+  invoke("stats_overlay_close");
+
+export const statsOverlayIsOpen = (): Promise<boolean> =>
+  invoke("stats_overlay_is_open");
+
+// ============ CONNECTION QUALITY API ============
+
+export type LinkQuality = "good" | "fair" | "poor";
+
+export interface PeerQuality {
+  peer_id: string;
+  quality: LinkQuality;
+  rtt_ms: number | null;
+  /** Worst of the incoming and outgoing loss */
+  loss_percent: number | null;
+  jitter_ms: number | null;
+}
+
+/** Latest quality of every peer, then follow `peer-quality-changed` */
+export const qualityGetPeers = (): Promise<PeerQuality[]> =>
+  invoke("quality_get_peers");
+
+// ============ BANDWIDTH API ============
+
+/** Caps in kbps, null = no cap (minimum 32) */
+export interface BandwidthLimits {
+  upload_kbps: number | null;
+  /** Advertised to the peers, who keep what they send under it */
+  download_kbps: number | null;
+}
+
+/** How the upload budget is shared out, in kbps (null = not limited) */
+export interface BandwidthAllocation {
+  upload_kbps: number | null;
+  /** Voice bitrate, sent to each peer */
+  audio_kbps: number | null;
+  /** Screen share bitrate, sent to each peer */
+  screen_kbps: number | null;
+  /** Voice message transfers, all peers together */
+  transfer_kbps: number | null;
+}
+
+export const bandwidthGetLimits = (): Promise<BandwidthLimits> =>
+  invoke("bandwidth_get_limits");
+
+export const bandwidthSetLimits = (limits: BandwidthLimits): Promise<BandwidthAllocation> =>
+  invoke("bandwidth_set_limits", { limits });
+
+export const bandwidthGetAllocation = (): Promise<BandwidthAllocation> =>
+  invoke("bandwidth_get_allocation");
+
+// ============ LAN PREVIEW SERVER API ============
+
+export interface PreviewServerConfig {
+  port: number;
+  /** Listen on the network, not only on localhost */
+  allow_lan: boolean;
+}
+
+export interface PreviewServerStatus {
+  running: boolean;
+  allow_lan: boolean;
+  /** Addresses to open in a browser, the LAN one first */
+  urls: string[];
+  viewers: number;
+  frames_published: number;
+}
+
+export const previewServerStart = (
+  config?: PreviewServerConfig
+): Promise<PreviewServerStatus> =>
+  invoke("preview_server_start", { config });
+
+export const previewServerStop = (): Promise<void> =>
+  invoke("preview_server_stop");
+
+export const previewServerStatus = (): Promise<PreviewServerStatus> =>
+  invoke("preview_server_status");
+
+// ============ VIRTUAL CAMERA API ============
+
+/** `local`: our screen stream, `remote`: frames pushed by the viewer */
+export type VirtualCameraSource = "local" | "remote";
+
+export interface VirtualCameraConfig {
+  /** Device path (e.g. /dev/video10), first v4l2loopback device when null */
+  device: string | null;
+  width: number;
+  height: number;
+}
+
+export interface VirtualCameraStatus {
+  active: boolean;
+  source: VirtualCameraSource | null;
+  device: string | null;
+  width: number;
+  height: number;
+  frames_written: number;
+  frames_dropped: number;
+}
+
+export const virtualCameraStart = (
+  source: VirtualCameraSource,
+  config?: VirtualCameraConfig
+): Promise<VirtualCameraStatus> =>
+  invoke("virtual_camera_start", { source, config });
+
+export const virtualCameraStop = (): Promise<void> =>
+  invoke("virtual_camera_stop");
+
+export const virtualCameraStatus = (): Promise<VirtualCameraStatus> =>
+  invoke("virtual_camera_status");
+
+/** Forward a received screen frame (base64 JPEG) to the virtual camera */
+export const virtualCameraPushFrame = (data: string): Promise<void> =>
+  invoke("virtual_camera_push_frame", { data });
+
+// ============ CHAT EXPORT API ============
+
+export type ChatExportFormat = "json" | "html";
+
+/** Keep a chat message in the session transcript used by chatExport */
+export const chatRecordMessage = (
+  roomCode: string,
+  sender: string,
+  content: string,
+  timestampMs: number,
+  outgoing: boolean
+): Promise<void> =>
+  invoke("chat_record_message", { roomCode, sender, content, timestampMs, outgoing });
+
+/**
+ * Write the room's chat to a JSON or standalone HTML file
+ * Without a path, a save dialog asks for one. Resolves to the written path,
+ * null when the dialog was cancelled.
+ */
+export const chatExport = (
+  roomCode: string,
+  format: ChatExportFormat,
+  path?: string
+): Promise<string | null> =>
+  invoke("chat_export", { roomCode, format, path });
+
+// ============ CHAT SEARCH API ============
+
+/** A message of the persisted chat history */
+export interface ChatEntry {
+  sender: string;
+  content: string;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+  /** Sent by us */
+  outgoing: boolean;
+}
+
+/** A message matching a chat search, with the messages around it in its room */
+export interface ChatSearchHit extends ChatEntry {
+  id: number;
+  room_code: string;
+  /** Excerpt of the message, matched terms between "\u0002" and "\u0003" */
+  snippet: string;
+  before: ChatEntry[];
+  after: ChatEntry[];
+}
+
+/**
+ * Full-text search over the persisted chat history, best matches first
+ * Each word is matched as a prefix; accents are ignored. Without a room, every
+ * room is searched. `limit` defaults to 20 (100 at most).
+ */
+export const chatSearch = (
+  query: string,
+  roomCode?: string,
+  limit?: number
+): Promise<ChatSearchHit[]> =>
+  invoke("chat_search", { query, roomCode, limit });
+
+/** Erase the chat history of a room (every room without one), saved and in this session */
+export const chatClearHistory = (roomCode?: string): Promise<void> =>
+  invoke("chat_clear_history", { roomCode });
+
+// ============ CHAT COMMANDS API ============
+
+export type SystemMessageKind = "muted" | "kicked" | "topic" | "me";
+
+/** Outcome of a chat command (`/mute`, `/kick`, `/topic`, `/me`) */
+export interface SystemMessage {
+  kind: SystemMessageKind;
+  actor: string;
+  /** Peer muted or kicked */
+  target: string | null;
+  /** Topic (null when cleared) or action */
+  text: string | null;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+}
+
+/**
+ * Run a message starting with `/` as a chat command
+ * Moderation commands are reserved to the host. The result reaches everyone,
+ * us included, as a chatSystemMessage event.
+ */
+export const chatRunCommand = (message: string): Promise<SystemMessage> =>
+  invoke("chat_run_command", { message });
+
+/** Topic of the room, null when none */
+export const chatGetTopic = (): Promise<string | null> => invoke("chat_get_topic");
+
+// ============ DATA-CHANNEL PLUGINS API ============
+
+/**
+ * Receive the messages peers send under `namespace` as pluginMessageEvent(namespace) events
+ * Namespaces are made of a-z, 0-9, `-` and `_` (32 characters at most). Claiming one the
+ * webview already holds succeeds, so claims can simply be made again after a reload.
+ */
+export const pluginClaimNamespace = (namespace: string): Promise<void> =>
+  invoke("plugin_claim_namespace", { namespace });
+
+/** Stop receiving the messages of a namespace claimed with pluginClaimNamespace */
+export const pluginReleaseNamespace = (namespace: string): Promise<void> =>
+  invoke("plugin_release_namespace", { namespace });
+
+/** Namespaces claimed by the interface or by plugins built into the app */
+export const pluginListNamespaces = (): Promise<string[]> => invoke("plugin_list_namespaces");
+
+/** Send `payload` under `namespace` to a peer, or to every peer of the talk room */
+export const pluginSend = (namespace: string, payload: unknown, peerId?: string): Promise<void> =>
+  invoke("plugin_send", { namespace, payload, peerId });
+
+// ============ VOICE MESSAGE API ============
+
+export interface VoiceMessageInfo {
+  id: string;
+  sender: string;
+  duration_ms: number;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+}
+
+/** Start recording a voice message (at most 60 s are kept) */
+export const voiceMessageStart = (): Promise<void> => invoke("voice_message_start");
+
+/** Discard the recording */
+export const voiceMessageCancel = (): Promise<void> => invoke("voice_message_cancel");
+
+/** Stop recording and send the clip to the talk room */
+export const voiceMessageSend = (): Promise<VoiceMessageInfo> =>
+  invoke("voice_message_send");
+
+/** Play a sent or received voice message, resolves once it is over */
+export const voiceMessagePlay = (id: string): Promise<void> =>
+  invoke("voice_message_play", { id });
+
+// ============ TRANSCRIPTION API (feature `transcription`) ============
+
+export interface TranscriptionConfig {
+  /** ggml whisper model file (e.g. ggml-base.bin) */
+  model_path: string;
+  /** ISO 639-1 code ("fr", "en"...), null to detect the language */
+  language?: string | null;
+  /** One caption stream per peer instead of the mixed call audio */
+  per_peer?: boolean;
+}
+
+export interface TranscriptionStatus {
+  active: boolean;
+  language: string | null;
+  per_peer: boolean;
+  /** Captions of the last stopped session */
+  captions: number;
+}
+
+/** Load the model and start captioning; captions arrive as `caption` events */
+export const transcriptionStart = (config: TranscriptionConfig): Promise<TranscriptionStatus> =>
+  invoke("transcription_start", { config });
+
+export const transcriptionStop = (): Promise<void> =>
+  invoke("transcription_stop");
+
+export const transcriptionStatus = (): Promise<TranscriptionStatus> =>
+  invoke("transcription_status");
+
+// ============ WHIP BROADCAST API ============
+
+export interface WhipConfig {
+  /** WHIP endpoint URL given by the server (LiveKit, Janus, Cloudflare...) */
+  endpoint: string;
+  /** Bearer token, when the endpoint requires one */
+  token?: string | null;
+  audio?: boolean;
+  video?: boolean;
+}
+
+export interface WhipStatus {
+  active: boolean;
+  endpoint: string | null;
+  /** Connection state with the server ("connected", "failed"...) */
+  connection_state: string | null;
+  audio_packets: number;
+  video_frames: number;
+  video_frames_dropped: number;
+  keyframe_requests: number;
+}
+
+export const whipStart = (config: WhipConfig): Promise<WhipStatus> =>
+  invoke("whip_start", { config });
+
+export const whipStop = (): Promise<void> =>
+  invoke("whip_stop");
+
+export const whipStatus = (): Promise<WhipStatus> =>
+  invoke("whip_status");
+
 // ============ AUDIO STREAMING API (Complete Pipeline) ============
 
 export interface AudioPacket {
@@ -364,6 +1513,55 @@ export const streamingSetMuted = (muted: boolean): Promise<void> =>
 export const streamingIsMuted = (): Promise<boolean> =>
   invoke("streaming_is_muted");
 
+export const streamingSetDeafened = (deafened: boolean): Promise<void> =>
+  invoke("streaming_set_deafened", { deafened });
+
+export const streamingIsDeafened = (): Promise<boolean> =>
+  invoke("streaming_is_deafened");
+
+export interface MicCheckResult {
+  duration_ms: number;
+  peak: number;
+  rms: number;
+  peak_dbfs: number;
+  rms_dbfs: number;
+  clipped: boolean;
+  silent: boolean;
+}
+
+export interface AutoMuteSettings {
+  join_muted: boolean;
+  mute_on_device_conflict: boolean;
+  silence_timeout_minutes: number | null;
+}
+
+export const streamingGetAutoMuteSettings = (): Promise<AutoMuteSettings> =>
+  invoke("streaming_get_auto_mute_settings");
+
+export const streamingSetAutoMuteSettings = (settings: AutoMuteSettings): Promise<void> =>
+  invoke("streaming_set_auto_mute_settings", { settings });
+
+/** Local playback of the processed mic, for headset users */
+export interface SidetoneSettings {
+  enabled: boolean;
+  /** Gain applied to the mic, 0 to 1 */
+  volume: number;
+}
+
+export const streamingGetSidetone = (): Promise<SidetoneSettings> =>
+  invoke("streaming_get_sidetone");
+
+export const streamingSetSidetone = (settings: SidetoneSettings): Promise<void> =>
+  invoke("streaming_set_sidetone", { settings });
+
+// Records ~5s, plays it back, then resolves with the levels
+export const streamingMicCheck = (): Promise<MicCheckResult> =>
+  invoke("streaming_mic_check");
+
+/** Participant holding the floor ("local" for us, null when nobody speaks) */
+export const streamingGetActiveSpeaker = (): Promise<string | null> =>
+  invoke("streaming_get_active_speaker");
+
 export const streamingIsCapturing = (): Promise<boolean> =>
   invoke("streaming_is_capturing");
 
@@ -388,14 +1586,80 @@ export const streamingListInputDevices = (): Promise<string[]> =>
 export const streamingListOutputDevices = (): Promise<string[]> =>
   invoke("streaming_list_output_devices");
 
+/** A range of configurations a device supports */
+export interface SupportedConfig {
+  channels: number;
+  min_sample_rate: number;
+  max_sample_rate: number;
+  /** Frames per callback (null: the driver doesn't tell) */
+  min_buffer_size: number | null;
+  max_buffer_size: number | null;
+  sample_format: string;
+}
+
+/** What to force when opening a device (null: the device default) */
+export interface DeviceConfig {
+  sample_rate: number | null;
+  /** Frames per callback */
+  buffer_size: number | null;
+}
+
+/** Pinned configurations, by device name */
+export interface DeviceConfigs {
+  capture: Record<string, DeviceConfig>;
+  playback: Record<string, DeviceConfig>;
+}
+
+export const streamingGetSupportedConfigs = (
+  kind: "capture" | "playback",
+  deviceName: string | null
+): Promise<SupportedConfig[]> => invoke("streaming_get_supported_configs", { kind, deviceName });
+
+export const streamingGetDeviceConfigs = (): Promise<DeviceConfigs> =>
+  invoke("streaming_get_device_configs");
+
+/** Reopens the running stream of that kind; both fields null unpins the device */
+export const streamingSetDeviceConfig = (
+  kind: "capture" | "playback",
+  deviceName: string,
+  config: DeviceConfig
+): Promise<void> => invoke("streaming_set_device_config", { kind, deviceName, config });
+
+export const streamingSetLevelEventRate = (rate: number): Promise<void> =>
+  invoke("streaming_set_level_event_rate", { rate });
+
+export const streamingGetLevelEventRate = (): Promise<number> =>
+  invoke("streaming_get_level_event_rate");
+
 export const streamingSetNoiseSuppression = (enabled: boolean): Promise<void> =>
   invoke("streaming_set_noise_suppression", { enabled });
 
 export const streamingIsNoiseSuppressionEnabled = (): Promise<boolean> =>
   invoke("streaming_is_noise_suppression_enabled");
 
-export const streamingGetOutgoingPacket = (): Promise<AudioPacket | null> =>
-  invoke("streaming_get_outgoing_packet");
+export const streamingSetPushToTalk = (pressed: boolean): Promise<void> =>
+  invoke("streaming_set_push_to_talk", { pressed });
+
+export const streamingGetPolicy = (): Promise<AudioPolicy> =>
+  invoke("streaming_get_policy");
+
+export const streamingSubscribeOutgoing = (onPacket: Channel<AudioPacket>): Promise<void> =>
+  invoke("streaming_subscribe_outgoing", { onPacket });
+
+export const streamingUnsubscribeOutgoing = (): Promise<void> =>
+  invoke("streaming_unsubscribe_outgoing");
+
+export interface AudioSendStats {
+  packets_queued: number;
+  /** Dropped because the sender fell more than ~200ms behind */
+  packets_dropped: number;
+  queue_len: number;
+  /** Captured samples lost because the encoder thread fell behind */
+  capture_overruns: number;
+}
+
+export const streamingGetSendStats = (): Promise<AudioSendStats> =>
+  invoke("streaming_get_send_stats");
 
 export const streamingReceiveAudio = (peerId: string, opusData: number[]): Promise<void> =>
   invoke("streaming_receive_audio", { peerId, opusData });
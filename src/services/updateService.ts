@@ -1,7 +1,13 @@
-import { check, Update } from "@tauri-apps/plugin-updater";
 import { ask, message } from "@tauri-apps/plugin-dialog";
 import { relaunch } from "@tauri-apps/plugin-process";
 import { listen } from "@tauri-apps/api/event";
+import { EVENTS, type UpdateProgressEvent } from "../types/events";
+import {
+  errorMessage,
+  updaterCheckNow,
+  updaterInstall,
+  type UpdateAvailability,
+} from "./tauriApi";
 
 type UpdateListener = (state: UpdateState) => void;
 
@@ -9,7 +15,7 @@ export interface UpdateState {
   status: "idle" | "checking" | "available" | "downloading" | "ready" | "error";
   progress: number;
   error: string | null;
-  update: Update | null;
+  update: UpdateAvailability | null;
   showBanner: boolean;
 }
 
@@ -51,16 +57,16 @@ class UpdateService {
     try {
       this.setState({ status: "checking", error: null });
 
-      const updateInfo = await check();
+      const updateInfo = await updaterCheckNow();
 
-      if (updateInfo) {
+      if (updateInfo.available) {
         this.setState({
           update: updateInfo,
           status: "available",
           showBanner: true,
         });
       } else {
-        this.setState({ status: "idle" });
+        this.setState({ status: "idle", update: null });
         if (manual) {
           await message("Tu utilises déjà la dernière version.", {
             title: "Aucune mise à jour",
@@ -71,11 +77,11 @@ class UpdateService {
     } catch (err) {
       console.error("Update check failed:", err);
       this.setState({
-        error: String(err),
+        error: errorMessage(err),
         status: "error",
       });
       if (manual) {
-        await message(`Erreur lors de la vérification : ${err}`, {
+        await message(`Erreur lors de la vérification : ${errorMessage(err)}`, {
           title: "Erreur",
           kind: "error",
         });
@@ -90,25 +96,21 @@ class UpdateService {
     try {
       this.setState({ status: "downloading", progress: 0 });
 
-      let totalSize = 0;
-      let downloadedSize = 0;
-
-      await update.downloadAndInstall((event) => {
-        switch (event.event) {
-          case "Started":
-            totalSize = event.data.contentLength || 0;
-            console.log("Download started, total:", totalSize);
-            break;
-          case "Progress":
-            downloadedSize += event.data.chunkLength || 0;
-            const percent = totalSize > 0 ? Math.round((downloadedSize / totalSize) * 100) : 0;
-            this.setState({ progress: Math.min(percent, 100) });
-            break;
-          case "Finished":
-            console.log("Download finished");
-            break;
+      const unlisten = await listen<UpdateProgressEvent>(
+        EVENTS.updateDownloadProgress,
+        ({ payload }) => {
+          const percent = payload.total
+            ? Math.round((payload.downloaded / payload.total) * 100)
+            : 0;
+          this.setState({ progress: Math.min(percent, 100) });
         }
-      });
+      );
+
+      try {
+        await updaterInstall();
+      } finally {
+        unlisten();
+      }
 
       this.setState({ status: "ready" });
 
@@ -126,7 +128,7 @@ class UpdateService {
     } catch (err) {
       console.error("Update download failed:", err);
       this.setState({
-        error: String(err),
+        error: errorMessage(err),
         status: "error",
       });
     }
@@ -138,7 +140,7 @@ class UpdateService {
 
   // Listen for menu event from Rust
   setupMenuListener(): void {
-    listen("check-for-updates", () => {
+    listen(EVENTS.checkForUpdates, () => {
       this.checkForUpdates(true);
     });
   }
@@ -0,0 +1,288 @@
+// Backend event catalog (mirrors src-tauri/src/events.rs)
+// Every payload carries the `version` of its shape
+
+import type { Peer, ServerInfo } from "./room";
+import type {
+  AudioPolicy,
+  CallTimerLimitAction,
+  EncodedFrameData,
+  MicPermission,
+  PortMappingStatus,
+  PeerQuality,
+  PresenterHints,
+  RecordingSummary,
+  SessionPhase,
+  SystemMessage,
+} from "../services/tauriApi";
+
+export const EVENTS = {
+  audioLevel: "audio-level",
+  screenFrame: "screen-frame",
+  screenPresenterHints: "screen-presenter-hints",
+  presenterFrame: "presenter-frame",
+  pipFrame: "pip-frame",
+  pipStateChanged: "pip-state-changed",
+  gameModeChanged: "game-mode-changed",
+  recordingFinished: "recording-finished",
+  recordingFailed: "recording-failed",
+  peerChannelChanged: "peer-channel-changed",
+  roomPolicyChanged: "room-policy-changed",
+  rosterUpdated: "roster-updated",
+  chatSystemMessage: "chat-system-message",
+  inviteExpired: "invite-expired",
+  autoJoin: "auto-join",
+  portMappingChanged: "port-mapping-changed",
+  sessionStateChanged: "session-state-changed",
+  presenceChanged: "presence-changed",
+  peerPresenceChanged: "peer-presence-changed",
+  checkForUpdates: "check-for-updates",
+  updateDownloadProgress: "update-download-progress",
+  backendCrash: "backend-crash",
+  voiceStateChanged: "voice-state-changed",
+  autoMuted: "auto-muted",
+  micPermissionChanged: "mic-permission-changed",
+  audioStreamStalled: "audio-stream-stalled",
+  audioDeviceRetry: "audio-device-retry",
+  audioDeviceFallback: "audio-device-fallback",
+  peerAudioTimeout: "peer-audio-timeout",
+  peerMediaLost: "peer-media-lost",
+  iceGathering: "ice-gathering",
+  voiceMessage: "voice-message",
+  activeSpeakerChanged: "active-speaker-changed",
+  caption: "caption",
+  callStats: "call-stats",
+  peerQualityChanged: "peer-quality-changed",
+  trayAction: "tray-action",
+  callTimerTick: "call-timer-tick",
+  callTimerWarning: "call-timer-warning",
+  callTimerExpired: "call-timer-expired",
+} as const;
+
+/** Messages of a data-channel plugin namespace claimed by the interface */
+export const pluginMessageEvent = (namespace: string) => `plugin:${namespace}:message`;
+
+export type Versioned<T> = T & { version: number };
+
+export type AudioLevelEvent = Versioned<{
+  level: number;
+  is_speaking: boolean;
+  rms: number;
+}>;
+
+export type ScreenFrameEvent = Versioned<EncodedFrameData>;
+
+/** What our screen share shows, relayed to viewers */
+export type PresenterHintsEvent = Versioned<PresenterHints>;
+
+/** A presenter's frame, sent to their pop-out window only */
+export type PresenterFrameEvent = Versioned<EncodedFrameData & { presenter_id: string }>;
+
+/** The active speaker's frame, sent to the PiP window only ("local" for us) */
+export type PipFrameEvent = Versioned<EncodedFrameData & { peer_id: string }>;
+
+export type PipStateEvent = Versioned<{
+  open: boolean;
+}>;
+
+/** A fullscreen app took (or left) the foreground: the call lowered its load */
+export type GameModeChangedEvent = Versioned<{
+  active: boolean;
+  app_name: string | null;
+}>;
+
+/** The call recording was finished and its file written */
+export type RecordingFinishedEvent = Versioned<RecordingSummary>;
+
+/** The call recording stopped on an error (the file keeps what was written) */
+export type RecordingFailedEvent = Versioned<{
+  error: string;
+}>;
+
+export type PeerChannelEvent = Versioned<{
+  room_code: string;
+  peer_id: string;
+  channel: string;
+}>;
+
+export type RoomPolicyEvent = Versioned<{
+  room_code: string;
+  policy: AudioPolicy;
+}>;
+
+export type RosterUpdatedEvent = Versioned<{ peers: Peer[] }>;
+
+/** A chat command ran, here or on a peer (`line` is the line to show) */
+export type ChatSystemMessageEvent = Versioned<
+  SystemMessage & {
+    room_code: string;
+    line: string;
+  }
+>;
+
+export type InviteExpiredEvent = Versioned<{ code: string }>;
+
+/** A code from the command line or an invite link was joined (`info`) or not (`error`) */
+export type AutoJoinEvent = Versioned<{
+  code: string;
+  info: ServerInfo | null;
+  error: string | null;
+}>;
+
+/** The router forwarded the hosting port, or stopped doing so */
+export type PortMappingChangedEvent = Versioned<PortMappingStatus>;
+
+export type SessionStateEvent = Versioned<{
+  from: SessionPhase;
+  to: SessionPhase;
+}>;
+
+export type PresenceEvent = Versioned<{
+  peer_id: string | null;
+  away: boolean;
+}>;
+
+export type VoiceStateEvent = Versioned<{
+  muted: boolean;
+  deafened: boolean;
+  push_to_talk_pressed: boolean;
+}>;
+
+export type AutoMutedEvent = Versioned<{
+  reason: "device_conflict" | "silence";
+}>;
+
+export type MicPermissionEvent = Versioned<{ status: MicPermission }>;
+
+export type AudioStreamStalledEvent = Versioned<{
+  stream: "capture" | "playback";
+  stalled_ms: number;
+  restarted: boolean;
+}>;
+
+/** Opening a device failed (`attempt` of `max_attempts`), retrying in `retry_in_ms` */
+export type AudioDeviceRetryEvent = Versioned<{
+  stream: "capture" | "playback";
+  attempt: number;
+  max_attempts: number;
+  retry_in_ms: number;
+  error: string;
+  /** Localized text for the user */
+  message: string;
+}>;
+
+/** The device went away and the stream moved to the default one (null: stopped) */
+export type AudioDeviceFallbackEvent = Versioned<{
+  stream: "capture" | "playback";
+  lost_device: string | null;
+  fallback_device: string | null;
+  error: string;
+}>;
+
+/** A peer sent no audio for `idle_ms` and its decoder was dropped */
+export type PeerAudioTimeoutEvent = Versioned<{
+  peer_id: string;
+  idle_ms: number;
+}>;
+
+/** A media track of a connected peer stopped; the backend tries to recover it */
+export type PeerMediaLostEvent = Versioned<{
+  peer_id: string;
+  kind: "audio" | "video";
+  reason: string;
+}>;
+
+/** Another participant took the floor; "local" is us, null when nobody speaks */
+export type ActiveSpeakerChangedEvent = Versioned<{
+  peer_id: string | null;
+}>;
+
+/** Transcribed segment, timed from the start of the transcription */
+export type CaptionEvent = Versioned<{
+  peer_id: string | null;
+  text: string;
+  start_ms: number;
+  end_ms: number;
+}>;
+
+/** A voice message was received; play it with `voiceMessagePlay(id)` */
+export type VoiceMessageEvent = Versioned<{
+  room_code: string;
+  peer_id: string;
+  id: string;
+  sender: string;
+  duration_ms: number;
+  timestamp: number;
+}>;
+
+export interface PeerCallStats {
+  peer_id: string;
+  rtt_ms: number | null;
+  send_kbps: number;
+  recv_kbps: number;
+}
+
+/** Live call stats, sent several times a second to the stats overlay only */
+export type CallStatsEvent = Versioned<{
+  send_kbps: number;
+  recv_kbps: number;
+  max_rtt_ms: number | null;
+  /** null when not sharing the screen */
+  screen_fps: number | null;
+  screen_kbps: number | null;
+  /** Peer ids speaking right now, "local" for us */
+  speaking: string[];
+  peers: PeerCallStats[];
+}>;
+
+/** The quality level of the link with a peer changed */
+export type PeerQualityChangedEvent = Versioned<PeerQuality>;
+
+export type TrayActionEvent = Versioned<{
+  action: "stop_screen_share" | "disconnect";
+}>;
+
+export type CallTimerTickEvent = Versioned<{
+  elapsed_secs: number;
+  /** null without a time limit */
+  remaining_secs: number | null;
+}>;
+
+export type CallTimerWarningEvent = Versioned<{
+  minutes: number;
+  remaining_secs: number | null;
+}>;
+
+/** The call time limit was reached */
+export type CallTimerExpiredEvent = Versioned<{
+  action: CallTimerLimitAction;
+}>;
+
+export type UpdateProgressEvent = Versioned<{
+  downloaded: number;
+  total: number | null;
+}>;
+
+export type PluginMessageEvent<T = unknown> = Versioned<{
+  room_code: string;
+  peer_id: string;
+  username: string | null;
+  payload: T;
+}>;
+
+/** ICE candidates gathered for an offer or answer (peer_id null: single connection, WHIP) */
+export type IceGatheringEvent = Versioned<{
+  source: "mesh" | "connection" | "whip";
+  peer_id: string | null;
+  phase: "gathering" | "complete" | "timed_out";
+  /** Candidates found so far */
+  candidates: number;
+  elapsed_ms: number;
+  timeout_ms: number;
+}>;
+
+export type BackendCrashEvent = Versioned<{
+  thread: string;
+  message: string;
+  location: string | null;
+  crash_file: string | null;
+}>;
@@ -30,6 +30,16 @@ export interface ServerInfo {
   is_hosting: boolean;
   username: string;
   peers: Peer[];
+  /** Public address forwarded by the router while hosting (UPnP / NAT-PMP) */
+  external_address?: string | null;
+}
+
+export interface InviteCode {
+  code: string;
+  created_at: number;
+  expires_at: number | null;
+  max_uses: number | null;
+  uses: number;
 }
 
 export interface Message {